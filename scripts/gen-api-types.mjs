@@ -0,0 +1,128 @@
+// Genera src/api/types.generated.ts a partir del esquema de comandos y eventos del backend
+// (src-tauri/src/schema.rs). Uso: npm run typegen [-- esquema.json]
+// Sin argumento, exporta el esquema con `cargo test export_api_schema`.
+import { execFileSync } from "node:child_process";
+import { mkdirSync, mkdtempSync, readFileSync, writeFileSync } from "node:fs";
+import { tmpdir } from "node:os";
+import { dirname, join } from "node:path";
+import { fileURLToPath } from "node:url";
+
+const root = join(dirname(fileURLToPath(import.meta.url)), "..");
+const output = join(root, "src", "api", "types.generated.ts");
+
+function exportSchema() {
+  const file = join(mkdtempSync(join(tmpdir(), "drk-api-")), "api-schema.json");
+  execFileSync(
+    "cargo",
+    ["test", "--manifest-path", join(root, "src-tauri", "Cargo.toml"), "--lib", "schema::tests::export_api_schema", "--", "--exact"],
+    { stdio: "inherit", env: { ...process.env, DRK_API_SCHEMA_OUT: file } },
+  );
+  return file;
+}
+
+const schema = JSON.parse(readFileSync(process.argv[2] ?? exportSchema(), "utf8"));
+
+const identifier = (name) => name.replace(/[^A-Za-z0-9_]/g, "_");
+const key = (name) => (/^[A-Za-z_$][A-Za-z0-9_$]*$/.test(name) ? name : JSON.stringify(name));
+
+function union(types) {
+  const unique = [...new Set(types)];
+  return unique.length === 1 ? unique[0] : unique.map((t) => (t.includes("=>") ? `(${t})` : t)).join(" | ");
+}
+
+function object(s, indent) {
+  const required = new Set(s.required ?? []);
+  const pad = "  ".repeat(indent + 1);
+  const fields = Object.entries(s.properties ?? {}).map(([name, prop]) => {
+    const doc = prop.description ? `${pad}/** ${prop.description.replace(/\s*\n\s*/g, " ")} */\n` : "";
+    return `${doc}${pad}${key(name)}${required.has(name) ? "" : "?"}: ${toTs(prop, indent + 1)};`;
+  });
+  const extra = s.additionalProperties;
+  if (extra && extra !== false) {
+    fields.push(`${pad}[key: string]: ${extra === true ? "unknown" : toTs(extra, indent + 1)};`);
+  }
+  if (!fields.length) {
+    return extra === false ? "Record<string, never>" : "Record<string, unknown>";
+  }
+  return `{\n${fields.join("\n")}\n${"  ".repeat(indent)}}`;
+}
+
+function toTs(s, indent = 0) {
+  if (s === true || s === undefined) return "unknown";
+  if (s === false) return "never";
+  if (s.$ref) return identifier(s.$ref.split("/").pop());
+  if (s.const !== undefined) return JSON.stringify(s.const);
+  if (s.enum) return union(s.enum.map((v) => JSON.stringify(v)));
+  if (s.allOf) return s.allOf.map((part) => toTs(part, indent)).join(" & ");
+  if (s.anyOf || s.oneOf) return union((s.anyOf ?? s.oneOf).map((part) => toTs(part, indent)));
+  const types = Array.isArray(s.type) ? s.type : s.type ? [s.type] : [];
+  if (!types.length) return s.properties ? object(s, indent) : "unknown";
+  return union(types.map((type) => {
+    switch (type) {
+      case "null": return "null";
+      case "string": return "string";
+      case "boolean": return "boolean";
+      case "integer":
+      case "number": return "number";
+      case "array":
+        if (Array.isArray(s.items)) return `[${s.items.map((item) => toTs(item, indent)).join(", ")}]`;
+        return `Array<${toTs(s.items, indent)}>`;
+      case "object": return object(s, indent);
+      default: return "unknown";
+    }
+  }));
+}
+
+const lines = [
+  "// Generado por scripts/gen-api-types.mjs a partir de src-tauri/src/schema.rs. No editar a mano.",
+  'import { invoke } from "@tauri-apps/api/core";',
+  'import { listen, type EventCallback, type UnlistenFn } from "@tauri-apps/api/event";',
+  "",
+  `export const API_SCHEMA_VERSION = ${schema.version};`,
+  "",
+];
+
+for (const [name, definition] of Object.entries(schema.definitions)) {
+  if (definition.description) lines.push(`/** ${definition.description.replace(/\s*\n\s*/g, " ")} */`);
+  lines.push(`export type ${identifier(name)} = ${toTs(definition)};`, "");
+}
+
+lines.push("export interface Commands {");
+for (const [name, command] of Object.entries(schema.commands)) {
+  const args = command.args.map((arg) => {
+    // Option<T> en Rust: Tauri acepta que falte
+    const optional = (Array.isArray(arg.schema.type) && arg.schema.type.includes("null"))
+      || (arg.schema.anyOf ?? []).some((part) => part.type === "null");
+    return `${key(arg.name)}${optional ? "?" : ""}: ${toTs(arg.schema, 2)}`;
+  });
+  const argsType = args.length ? `{ ${args.join("; ")} }` : "{}";
+  lines.push(`  ${name}: { args: ${argsType}; output: ${toTs(command.output, 1)} };`);
+}
+lines.push("}", "");
+
+lines.push("export interface Events {");
+for (const [name, payload] of Object.entries(schema.events)) {
+  lines.push(`  ${name}: ${toTs(payload, 1)};`);
+}
+lines.push("}", "");
+
+lines.push(
+  "type ArgsOf<C extends keyof Commands> = Commands[C][\"args\"];",
+  "",
+  "// invoke con los argumentos y el resultado de cada comando comprobados por TypeScript",
+  "export function invokeCommand<C extends keyof Commands>(",
+  "  command: C,",
+  "  ...args: {} extends ArgsOf<C> ? [args?: ArgsOf<C>] : [args: ArgsOf<C>]",
+  "): Promise<Commands[C][\"output\"]> {",
+  "  return invoke(command, args[0]);",
+  "}",
+  "",
+  "export function listenEvent<E extends keyof Events>(event: E, handler: EventCallback<Events[E]>): Promise<UnlistenFn> {",
+  "  return listen<Events[E]>(event, handler);",
+  "}",
+  "",
+);
+
+mkdirSync(dirname(output), { recursive: true });
+writeFileSync(output, lines.join("\n"));
+console.log(`${Object.keys(schema.commands).length} comandos y ${Object.keys(schema.events).length} eventos escritos en ${output}`);
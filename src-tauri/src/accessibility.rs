@@ -0,0 +1,144 @@
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::process::Command;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+use crate::minecraft::launch_logic::parse_mc_version;
+
+// OS settings have no change notification we can reach from here; this is quick enough to feel live
+const POLL_INTERVAL: Duration = Duration::from_secs(30);
+// `HCF_HIGHCONTRASTON` in the `Flags` of `HKCU\Control Panel\Accessibility\HighContrast`
+const HCF_HIGHCONTRASTON: u32 = 0x1;
+
+/// Accessibility settings the player turned on in the OS. Sent as `accessibility_changed` when
+/// they change while the launcher is open.
+#[derive(Serialize, Clone, Copy, Default, PartialEq, Debug, JsonSchema)]
+pub struct AccessibilityPrefs {
+    /// Windows "Show animations" off, macOS "Reduce motion", GNOME animations off
+    #[serde(rename = "reducedMotion")]
+    pub reduced_motion: bool,
+    /// Windows high contrast theme, macOS "Increase contrast", GNOME high contrast
+    #[serde(rename = "highContrast")]
+    pub high_contrast: bool,
+}
+
+/// Last detected prefs, once the poller ran.
+static LAST: Mutex<Option<AccessibilityPrefs>> = Mutex::new(None);
+
+fn output(program: &str, args: &[&str]) -> Option<String> {
+    let mut cmd = Command::new(program);
+    cmd.args(args);
+    #[cfg(target_os = "windows")]
+    {
+        use std::os::windows::process::CommandExt;
+        cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
+    }
+    let out = cmd.output().ok().filter(|out| out.status.success())?;
+    Some(String::from_utf8_lossy(&out.stdout).trim().to_string())
+}
+
+/// Data of `name` in `reg query` output: `    Flags    REG_SZ    126`.
+pub fn reg_value(output: &str, name: &str) -> Option<String> {
+    output.lines()
+        .map(|line| line.split_whitespace().collect::<Vec<_>>())
+        .find(|fields| fields.len() >= 3 && fields[0].eq_ignore_ascii_case(name))
+        .map(|fields| fields[2..].join(" "))
+}
+
+/// High contrast from the `Flags` value, which Windows stores as a decimal string.
+pub fn high_contrast_flags(flags: &str) -> bool {
+    flags.trim().parse::<u32>().is_ok_and(|flags| flags & HCF_HIGHCONTRASTON != 0)
+}
+
+fn reg_query(key: &str, name: &str) -> Option<String> {
+    reg_value(&output("reg", &["query", key, "/v", name])?, name)
+}
+
+fn detect() -> AccessibilityPrefs {
+    if cfg!(target_os = "windows") {
+        AccessibilityPrefs {
+            // "Show animations in Windows" turns this off along with the rest of the animations
+            reduced_motion: reg_query(r"HKCU\Control Panel\Desktop\WindowMetrics", "MinAnimate").is_some_and(|v| v == "0"),
+            high_contrast: reg_query(r"HKCU\Control Panel\Accessibility\HighContrast", "Flags").is_some_and(|v| high_contrast_flags(&v)),
+        }
+    } else if cfg!(target_os = "macos") {
+        let enabled = |key: &str| output("defaults", &["read", "com.apple.universalaccess", key]).is_some_and(|v| v == "1");
+        AccessibilityPrefs { reduced_motion: enabled("reduceMotion"), high_contrast: enabled("increaseContrast") }
+    } else {
+        let setting = |schema: &str, key: &str| output("gsettings", &["get", schema, key]).unwrap_or_default();
+        AccessibilityPrefs {
+            reduced_motion: setting("org.gnome.desktop.interface", "enable-animations") == "false",
+            high_contrast: setting("org.gnome.desktop.a11y.interface", "high-contrast") == "true"
+                || setting("org.gnome.desktop.interface", "gtk-theme").contains("HighContrast"),
+        }
+    }
+}
+
+/// `options.txt` defaults matching `prefs`: no flashing splash for high contrast, no screen
+/// distortion, FOV changes or lightning flashes for reduced motion. Options older versions don't
+/// have are left out.
+pub fn option_defaults(prefs: AccessibilityPrefs, mc_version: &str) -> Vec<(&'static str, String)> {
+    let minor = parse_mc_version(mc_version).map(|(minor, _)| minor).unwrap_or(u32::MAX);
+    let mut options = Vec::new();
+    if prefs.high_contrast && minor >= 17 {
+        options.push(("darkMojangStudiosBackground", "true".to_string()));
+    }
+    if prefs.reduced_motion && minor >= 17 {
+        options.push(("screenEffectScale", "0.0".to_string()));
+        options.push(("fovEffectScale", "0.0".to_string()));
+    }
+    if prefs.reduced_motion && minor >= 19 {
+        options.push(("hideLightningFlashes", "true".to_string()));
+    }
+    options
+}
+
+/// The OS prefs as last seen by the poller, detected now if it hasn't run yet.
+pub fn current() -> AccessibilityPrefs {
+    let cached = LAST.lock().ok().and_then(|last| *last);
+    cached.unwrap_or_else(detect)
+}
+
+/// Checks the OS settings on startup and then every `POLL_INTERVAL`, telling the UI on change.
+pub fn start(app: AppHandle) {
+    std::thread::spawn(move || loop {
+        let prefs = detect();
+        let changed = LAST.lock()
+            .map(|mut last| last.replace(prefs).is_some_and(|old| old != prefs))
+            .unwrap_or(false);
+        if changed {
+            let _ = app.emit("accessibility_changed", prefs);
+        }
+        std::thread::sleep(POLL_INTERVAL);
+    });
+}
+
+#[tauri::command]
+pub fn get_accessibility_prefs() -> AccessibilityPrefs {
+    current()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn windows_values_are_read_from_reg_output() {
+        let out = "\r\nHKEY_CURRENT_USER\\Control Panel\\Accessibility\\HighContrast\r\n    Flags    REG_SZ    127\r\n";
+        let flags = reg_value(out, "Flags").unwrap();
+        assert!(high_contrast_flags(&flags));
+        assert!(!high_contrast_flags("126"));
+        assert_eq!(reg_value(out, "MinAnimate"), None);
+    }
+
+    #[test]
+    fn defaults_only_use_options_the_version_has() {
+        let both = AccessibilityPrefs { reduced_motion: true, high_contrast: true };
+        let keys = |version: &str| option_defaults(both, version).into_iter().map(|(k, _)| k).collect::<Vec<_>>();
+        assert_eq!(keys("1.20.1"), ["darkMojangStudiosBackground", "screenEffectScale", "fovEffectScale", "hideLightningFlashes"]);
+        assert_eq!(keys("1.18.2"), ["darkMojangStudiosBackground", "screenEffectScale", "fovEffectScale"]);
+        assert!(keys("1.12.2").is_empty());
+        assert!(option_defaults(AccessibilityPrefs::default(), "1.20.1").is_empty());
+    }
+}
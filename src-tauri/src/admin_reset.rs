@@ -0,0 +1,166 @@
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use serde::Deserialize;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+
+/// Every reset attempt, one JSON line each; successful ids can't be used again.
+const AUDIT_FILE: &str = "admin-resets.log";
+/// Event PCs drift a few minutes from the backend's clock
+const MAX_CLOCK_SKEW_SECS: u64 = 5 * 60;
+/// Longest a token may be valid for, whatever the backend put in it
+const MAX_VALIDITY_SECS: u64 = 24 * 60 * 60;
+
+/// What the admin backend signs to let one machine set a new admin password once.
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct ResetToken {
+    pub id: String,
+    /// As shown by `get_machine_id`
+    #[serde(rename = "machineId", alias = "machine_id")]
+    pub machine_id: String,
+    #[serde(rename = "issuedAt", alias = "issued_at")]
+    pub issued_at: u64,
+    #[serde(rename = "expiresAt", alias = "expires_at")]
+    pub expires_at: u64,
+    /// Staff member it was issued to, for the audit log
+    #[serde(rename = "issuedTo", alias = "issued_to", default)]
+    pub issued_to: Option<String>,
+}
+
+fn same_machine(a: &str, b: &str) -> bool {
+    let normalize = |id: &str| id.chars().filter(|c| c.is_ascii_alphanumeric()).map(|c| c.to_ascii_uppercase()).collect::<String>();
+    normalize(a) == normalize(b)
+}
+
+/// Checks `<base64url payload>.<hex HMAC-SHA256 of the payload>`, signed with the same secret
+/// as remote commands, against this machine and the ids already used.
+pub fn verify(token: &str, secret: Option<&str>, machine_id: &str, used: &[String], now: u64) -> Result<ResetToken, String> {
+    let secret = secret.filter(|s| !s.is_empty()).ok_or("No hay secreto de comandos configurado en este equipo")?;
+    let (payload, signature) = token.trim().rsplit_once('.').ok_or("Token no válido")?;
+    let payload = URL_SAFE_NO_PAD.decode(payload.trim_end_matches('='))
+        .ok()
+        .and_then(|bytes| String::from_utf8(bytes).ok())
+        .ok_or("Token no válido")?;
+    if !crate::remote_commands::signature_valid(secret, &payload, signature) {
+        return Err("Firma no válida".to_string());
+    }
+    let token: ResetToken = serde_json::from_str(&payload).map_err(|e| format!("Token no válido: {}", e))?;
+    if !same_machine(&token.machine_id, machine_id) {
+        return Err(format!("El token es para otro equipo ({}); este es {}", token.machine_id, machine_id));
+    }
+    if token.issued_at > now + MAX_CLOCK_SKEW_SECS {
+        return Err("El token aún no es válido; revisa la hora del equipo".to_string());
+    }
+    if token.expires_at < now || token.expires_at.saturating_sub(token.issued_at) > MAX_VALIDITY_SECS {
+        return Err("Token caducado".to_string());
+    }
+    if used.contains(&token.id) {
+        return Err("Este token ya se usó".to_string());
+    }
+    Ok(token)
+}
+
+fn audit_path() -> PathBuf {
+    crate::data_dir::root().join(AUDIT_FILE)
+}
+
+fn audit_entries() -> Vec<serde_json::Value> {
+    let content = fs::read_to_string(audit_path()).unwrap_or_default();
+    content.lines().filter_map(|l| serde_json::from_str(l).ok()).collect()
+}
+
+/// Ids of the tokens that already reset the password here.
+fn used_ids() -> Vec<String> {
+    audit_entries().into_iter()
+        .filter(|e| e["ok"] == true)
+        .filter_map(|e| e["id"].as_str().map(str::to_string))
+        .collect()
+}
+
+fn audit(token: Option<&ResetToken>, result: &Result<(), String>) {
+    let line = serde_json::json!({
+        "at": crate::unix_now(),
+        "id": token.map(|t| &t.id),
+        "issuedTo": token.and_then(|t| t.issued_to.as_ref()),
+        "ok": result.is_ok(),
+        "detail": match result { Ok(()) => "Contraseña restablecida", Err(e) => e },
+    });
+    if let Ok(mut file) = OpenOptions::new().create(true).append(true).open(audit_path()) {
+        let _ = writeln!(file, "{}", line);
+    }
+}
+
+/// Sets a new admin password with a token from the admin backend, for when nobody on site
+/// remembers the old one.
+#[tauri::command]
+pub fn reset_admin_password(token: String, password: String) -> Result<(), String> {
+    let secret = crate::settings::load_settings().announcements.command_secret;
+    let token = verify(&token, secret.as_deref(), &crate::platform::machine_id(), &used_ids(), crate::unix_now());
+    let result = token.as_ref().map_err(Clone::clone).and_then(|_| {
+        crate::admin_setup::validate_password(&password)?;
+        crate::store_admin_password(&password)
+    });
+    audit(token.as_ref().ok(), &result);
+    if result.is_ok() {
+        // A pending first-run code is moot now
+        crate::admin_setup::ensure_code();
+    }
+    result
+}
+
+#[tauri::command]
+pub fn get_admin_reset_log(password: String) -> Result<Vec<serde_json::Value>, String> {
+    crate::ensure_admin(password)?;
+    let entries = audit_entries();
+    Ok(entries[entries.len().saturating_sub(200)..].to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::remote_commands::sign;
+
+    const SECRET: &str = "secreto-del-evento";
+    const MACHINE: &str = "A1B2-C3D4-E5F6-0718";
+
+    fn token(payload: &serde_json::Value, secret: &str) -> String {
+        let payload = payload.to_string();
+        format!("{}.{}", URL_SAFE_NO_PAD.encode(&payload), sign(secret, &payload))
+    }
+
+    fn payload(id: &str, machine: &str, issued_at: u64, expires_at: u64) -> serde_json::Value {
+        serde_json::json!({ "id": id, "machineId": machine, "issuedAt": issued_at, "expiresAt": expires_at, "issuedTo": "staff" })
+    }
+
+    #[test]
+    fn tokens_are_bound_to_the_machine_and_used_once() {
+        let now = 1_000_000;
+        let ok = token(&payload("r1", MACHINE, now - 60, now + 3600), SECRET);
+        let parsed = verify(&ok, Some(SECRET), "a1b2c3d4e5f60718", &[], now).unwrap();
+        assert_eq!(parsed.issued_to.as_deref(), Some("staff"));
+
+        assert!(verify(&ok, Some(SECRET), "FFFF-C3D4-E5F6-0718", &[], now).is_err());
+        assert!(verify(&ok, Some(SECRET), MACHINE, &["r1".to_string()], now).is_err());
+        assert!(verify(&ok, Some("otro"), MACHINE, &[], now).is_err());
+        assert!(verify(&ok, None, MACHINE, &[], now).is_err());
+        assert!(verify(&ok.replace('.', ""), Some(SECRET), MACHINE, &[], now).is_err());
+    }
+
+    #[test]
+    fn stale_or_overlong_tokens_are_refused() {
+        let now = 1_000_000;
+        let expired = token(&payload("r2", MACHINE, now - 7200, now - 1), SECRET);
+        assert!(verify(&expired, Some(SECRET), MACHINE, &[], now).is_err());
+        let future = token(&payload("r3", MACHINE, now + 3600, now + 7200), SECRET);
+        assert!(verify(&future, Some(SECRET), MACHINE, &[], now).is_err());
+        let forever = token(&payload("r4", MACHINE, now, now + 365 * 24 * 3600), SECRET);
+        assert!(verify(&forever, Some(SECRET), MACHINE, &[], now).is_err());
+
+        // A signature over other bytes than the payload doesn't count
+        let tampered = token(&payload("r5", MACHINE, now, now + 60), SECRET);
+        let (_, signature) = tampered.rsplit_once('.').unwrap();
+        let other = URL_SAFE_NO_PAD.encode(payload("r5", "OTRO", now, now + 60).to_string());
+        assert!(verify(&format!("{}.{}", other, signature), Some(SECRET), "OTRO", &[], now).is_err());
+    }
+}
@@ -0,0 +1,152 @@
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// One-time code that authorizes setting the first admin password; lives in the data root
+/// until the setup is done.
+const SETUP_CODE_FILE: &str = "admin-setup-code.txt";
+const MIN_PASSWORD_CHARS: usize = 8;
+
+#[derive(Serialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AdminSetupStatus {
+    pub configured: bool,
+    /// Where to read the setup code while no password is set
+    #[serde(rename = "codePath")]
+    pub code_path: Option<String>,
+}
+
+fn code_path() -> PathBuf {
+    crate::data_dir::root().join(SETUP_CODE_FILE)
+}
+
+/// `ABCD-EF12-3456` from the OS random source.
+fn new_code() -> String {
+    let hex = uuid::Uuid::new_v4().simple().to_string().to_uppercase();
+    format!("{}-{}-{}", &hex[..4], &hex[4..8], &hex[8..12])
+}
+
+fn normalize(code: &str) -> Vec<u8> {
+    code.bytes().filter(|b| b.is_ascii_alphanumeric()).map(|b| b.to_ascii_uppercase()).collect()
+}
+
+/// Dashes, spaces and case don't matter. Compares every byte so timing doesn't leak a prefix.
+fn code_matches(expected: &str, given: &str) -> bool {
+    let (expected, given) = (normalize(expected), normalize(given));
+    !expected.is_empty() && expected.len() == given.len() && expected.iter().zip(&given).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+pub fn validate_password(password: &str) -> Result<(), String> {
+    if password.chars().count() < MIN_PASSWORD_CHARS {
+        return Err(format!("La contraseña debe tener al menos {} caracteres", MIN_PASSWORD_CHARS));
+    }
+    Ok(())
+}
+
+/// Creates `path` readable by the current OS user only, then writes `text`.
+#[cfg(unix)]
+fn write_private(path: &Path, text: &str) -> io::Result<()> {
+    use std::os::unix::fs::OpenOptionsExt;
+    let mut file = fs::OpenOptions::new().write(true).create_new(true).mode(0o600).open(path)?;
+    file.write_all(text.as_bytes())
+}
+
+#[cfg(windows)]
+fn write_private(path: &Path, text: &str) -> io::Result<()> {
+    use std::os::windows::process::CommandExt;
+    let mut file = fs::OpenOptions::new().write(true).create_new(true).open(path)?;
+    // Inherited entries dropped before anything is written, so only this user can read it
+    let user = std::env::var("USERNAME").map_err(|e| io::Error::other(e.to_string()))?;
+    let status = std::process::Command::new("icacls")
+        .arg(path)
+        .args(["/inheritance:r", "/grant:r", &format!("{}:F", user)])
+        .creation_flags(0x08000000) // CREATE_NO_WINDOW
+        .status()?;
+    if !status.success() {
+        drop(file);
+        let _ = fs::remove_file(path);
+        return Err(io::Error::other("icacls no pudo restringir el archivo"));
+    }
+    file.write_all(text.as_bytes())
+}
+
+#[cfg(not(any(unix, windows)))]
+fn write_private(path: &Path, text: &str) -> io::Result<()> {
+    fs::OpenOptions::new().write(true).create_new(true).open(path)?.write_all(text.as_bytes())
+}
+
+/// Makes sure a setup code exists while no admin password is set, and is gone once one is.
+/// Runs on startup so the file is there before anyone opens the admin screen.
+pub fn ensure_code() -> Option<PathBuf> {
+    let path = code_path();
+    if crate::is_admin_configured() {
+        let _ = fs::remove_file(&path);
+        return None;
+    }
+    if path.is_file() {
+        return Some(path);
+    }
+    match write_private(&path, &format!("{}\n", new_code())) {
+        Ok(()) => {
+            eprintln!("[admin_setup] Admin setup code written to {}", path.display());
+            Some(path)
+        }
+        Err(e) => {
+            eprintln!("[admin_setup] Failed to write the admin setup code: {}", e);
+            None
+        }
+    }
+}
+
+#[tauri::command]
+pub fn get_admin_setup_status() -> AdminSetupStatus {
+    let code_path = ensure_code().map(|p| p.to_string_lossy().to_string());
+    AdminSetupStatus { configured: crate::is_admin_configured(), code_path }
+}
+
+/// Sets the first admin password with the code from the setup file.
+#[tauri::command]
+pub fn complete_admin_setup(code: String, password: String) -> Result<(), String> {
+    if crate::is_admin_configured() {
+        return Err("La contraseña de administrador ya está configurada".to_string());
+    }
+    let expected = fs::read_to_string(code_path())
+        .map_err(|_| "No hay código de configuración; reinicia el launcher para generarlo".to_string())?;
+    if !code_matches(&expected, &code) {
+        return Err("Código de configuración incorrecto".to_string());
+    }
+    validate_password(&password)?;
+    crate::store_admin_password(&password)?;
+    let _ = fs::remove_file(code_path());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn codes_compare_loosely_but_completely() {
+        let code = new_code();
+        assert_eq!(code.len(), 14);
+        assert!(code_matches(&format!("{}\n", code), &code.to_lowercase().replace('-', " ")));
+        assert!(!code_matches(&code, &code[..13]));
+        assert!(!code_matches(&code, &new_code()));
+        assert!(!code_matches("", ""));
+        assert!(validate_password("corta").is_err());
+        assert!(validate_password("evento-drk-2026").is_ok());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn setup_code_is_private_to_the_user() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(SETUP_CODE_FILE);
+        write_private(&path, "ABCD-EF12-3456\n").unwrap();
+        assert_eq!(fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+        // Never overwrites a code someone may already have read
+        assert!(write_private(&path, "otro").is_err());
+    }
+}
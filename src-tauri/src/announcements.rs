@@ -0,0 +1,257 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::net::TcpStream;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+use tungstenite::client::IntoClientRequest;
+use tungstenite::stream::MaybeTlsStream;
+use tungstenite::{Message, WebSocket};
+use crate::settings::{load_settings, save_settings};
+
+// How often a connected socket looks at the settings again, so disabling it takes effect
+const POLL_INTERVAL: Duration = Duration::from_secs(30);
+// While disabled or unconfigured the thread only checks the settings this often
+const IDLE_INTERVAL: Duration = Duration::from_secs(30);
+const MIN_BACKOFF: Duration = Duration::from_secs(2);
+const MAX_BACKOFF: Duration = Duration::from_secs(120);
+// A rejected token won't start working by itself; retry rarely until staff fix it
+const AUTH_RETRY: Duration = Duration::from_secs(600);
+
+/// Staff broadcast channel (pack pushed, server restarting). Admin only, the token is a secret.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, JsonSchema)]
+pub struct AnnouncementSettings {
+    #[serde(default)]
+    pub enabled: bool,
+    /// `ws://` or `wss://` URL
+    #[serde(default)]
+    pub endpoint: Option<String>,
+    /// Sent as `Authorization: Bearer <token>` on the handshake
+    #[serde(default)]
+    pub token: Option<String>,
+    /// Shared HMAC key for remote commands (see `crate::remote_commands`); none means commands are ignored
+    #[serde(rename = "commandSecret", alias = "command_secret", default)]
+    pub command_secret: Option<String>,
+    #[serde(rename = "allowedCommands", alias = "allowed_commands", default = "default_allowed_commands")]
+    pub allowed_commands: Vec<String>,
+}
+
+impl Default for AnnouncementSettings {
+    fn default() -> Self {
+        AnnouncementSettings {
+            enabled: false,
+            endpoint: None,
+            token: None,
+            command_secret: None,
+            allowed_commands: default_allowed_commands(),
+        }
+    }
+}
+
+fn default_allowed_commands() -> Vec<String> {
+    crate::remote_commands::KNOWN_ACTIONS.iter().map(|a| a.to_string()).collect()
+}
+
+impl AnnouncementSettings {
+    fn active_endpoint(&self) -> Option<&str> {
+        self.endpoint.as_deref().map(str::trim).filter(|e| self.enabled && !e.is_empty())
+    }
+}
+
+/// A broadcast forwarded to the UI as the `announcement` event.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Announcement {
+    /// `pack_update`, `server_restart`, `message` or whatever the server sends
+    #[serde(rename = "type", alias = "kind", default = "default_kind")]
+    pub kind: String,
+    #[serde(default)]
+    pub title: Option<String>,
+    #[serde(default)]
+    pub message: String,
+    /// Instance the broadcast is about, if any
+    #[serde(rename = "instanceId", alias = "instance_id", default)]
+    pub instance_id: Option<String>,
+    #[serde(rename = "receivedAt", default)]
+    pub received_at: u64,
+}
+
+fn default_kind() -> String {
+    "message".to_string()
+}
+
+/// JSON broadcasts are taken as they are; any other text is shown as a plain message.
+pub fn parse_announcement(text: &str, received_at: u64) -> Option<Announcement> {
+    let text = text.trim();
+    if text.is_empty() {
+        return None;
+    }
+    let mut announcement = serde_json::from_str::<Announcement>(text).unwrap_or_else(|_| Announcement {
+        kind: default_kind(),
+        title: None,
+        message: text.to_string(),
+        instance_id: None,
+        received_at: 0,
+    });
+    if announcement.message.trim().is_empty() && announcement.title.is_none() {
+        return None;
+    }
+    announcement.received_at = received_at;
+    Some(announcement)
+}
+
+/// Doubles from `MIN_BACKOFF` per failed attempt, capped at `MAX_BACKOFF`.
+pub fn backoff(failures: u32) -> Duration {
+    MIN_BACKOFF.saturating_mul(1u32 << failures.min(16)).min(MAX_BACKOFF)
+}
+
+/// `announcement_status` payload: whether the socket is up, and why not.
+#[derive(Serialize, Clone, JsonSchema)]
+pub struct AnnouncementStatus {
+    pub connected: bool,
+    pub error: Option<String>,
+}
+
+fn emit_status(app: &AppHandle, connected: bool, error: Option<&str>) {
+    let _ = app.emit("announcement_status", AnnouncementStatus { connected, error: error.map(str::to_string) });
+}
+
+enum ConnectError {
+    Auth(String),
+    Other(String),
+}
+
+fn connect(settings: &AnnouncementSettings, endpoint: &str) -> Result<WebSocket<MaybeTlsStream<TcpStream>>, ConnectError> {
+    let mut request = endpoint.into_client_request().map_err(|e| ConnectError::Other(format!("URL de anuncios no válida: {}", e)))?;
+    let headers = request.headers_mut();
+    if let Ok(agent) = crate::http::user_agent().parse() {
+        headers.insert("User-Agent", agent);
+    }
+    if let Some(token) = settings.token.as_deref().filter(|t| !t.is_empty()) {
+        let value = format!("Bearer {}", token).parse().map_err(|_| ConnectError::Auth("Token de anuncios no válido".to_string()))?;
+        headers.insert("Authorization", value);
+    }
+    let (socket, _) = tungstenite::connect(request).map_err(|e| match e {
+        tungstenite::Error::Http(resp) if matches!(resp.status().as_u16(), 401 | 403) => {
+            ConnectError::Auth(format!("El servidor de anuncios rechazó el token ({})", resp.status()))
+        }
+        e => ConnectError::Other(e.to_string()),
+    })?;
+    // Reads time out so the loop can notice settings changes between broadcasts
+    let timeout = Some(POLL_INTERVAL);
+    let _ = match socket.get_ref() {
+        MaybeTlsStream::Plain(stream) => stream.set_read_timeout(timeout),
+        MaybeTlsStream::NativeTls(stream) => stream.get_ref().set_read_timeout(timeout),
+        _ => Ok(()),
+    };
+    Ok(socket)
+}
+
+/// Forwards broadcasts until the socket drops or the settings change.
+fn listen(app: &AppHandle, socket: &mut WebSocket<MaybeTlsStream<TcpStream>>, settings: &AnnouncementSettings) -> Result<(), String> {
+    loop {
+        match socket.read() {
+            Ok(Message::Text(text)) => {
+                if crate::remote_commands::handle(app, &text) {
+                    continue;
+                }
+                if let Some(announcement) = parse_announcement(&text, crate::unix_now()) {
+                    let _ = app.emit("announcement", announcement);
+                }
+            }
+            Ok(Message::Close(_)) => return Err("El servidor de anuncios cerró la conexión".to_string()),
+            // Pings are answered by tungstenite on the next read
+            Ok(_) => {}
+            Err(tungstenite::Error::Io(e)) if matches!(e.kind(), std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut) => {
+                if load_settings().announcements != *settings {
+                    let _ = socket.close(None);
+                    return Ok(());
+                }
+            }
+            Err(e) => return Err(e.to_string()),
+        }
+    }
+}
+
+/// Keeps the announcement socket connected for the lifetime of the app while it's enabled.
+pub fn start(app: AppHandle) {
+    std::thread::spawn(move || {
+        let mut failures = 0;
+        loop {
+            let settings = load_settings().announcements;
+            let Some(endpoint) = settings.active_endpoint().map(str::to_string) else {
+                failures = 0;
+                std::thread::sleep(IDLE_INTERVAL);
+                continue;
+            };
+            let wait = match connect(&settings, &endpoint) {
+                Ok(mut socket) => {
+                    failures = 0;
+                    emit_status(&app, true, None);
+                    let result = listen(&app, &mut socket, &settings);
+                    emit_status(&app, false, result.as_ref().err().map(String::as_str));
+                    if result.is_ok() { Duration::ZERO } else { backoff(0) }
+                }
+                Err(ConnectError::Auth(e)) => {
+                    emit_status(&app, false, Some(&e));
+                    AUTH_RETRY
+                }
+                Err(ConnectError::Other(e)) => {
+                    eprintln!("[announcements] {}", e);
+                    emit_status(&app, false, Some(&e));
+                    failures += 1;
+                    backoff(failures)
+                }
+            };
+            std::thread::sleep(wait);
+        }
+    });
+}
+
+#[tauri::command]
+pub fn get_announcement_settings(password: String) -> Result<AnnouncementSettings, String> {
+    crate::ensure_admin(password)?;
+    Ok(load_settings().announcements)
+}
+
+#[tauri::command]
+pub fn set_announcement_settings(password: String, announcements: AnnouncementSettings) -> Result<(), String> {
+    crate::ensure_admin(password)?;
+    let mut settings = load_settings();
+    settings.announcements = announcements;
+    save_settings(&settings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn broadcasts_parse_as_json_or_plain_text() {
+        let pack = parse_announcement(r#"{"type":"pack_update","message":"Nueva versión del pack","instance_id":"evento"}"#, 42).unwrap();
+        assert_eq!(pack.kind, "pack_update");
+        assert_eq!(pack.instance_id.as_deref(), Some("evento"));
+        assert_eq!(pack.received_at, 42);
+
+        let plain = parse_announcement("El servidor reinicia en 5 minutos", 1).unwrap();
+        assert_eq!(plain.kind, "message");
+        assert_eq!(plain.message, "El servidor reinicia en 5 minutos");
+        assert!(parse_announcement("  ", 1).is_none());
+        assert!(parse_announcement(r#"{"type":"pack_update"}"#, 1).is_none());
+    }
+
+    #[test]
+    fn reconnects_back_off_up_to_a_cap() {
+        assert_eq!(backoff(0), Duration::from_secs(2));
+        assert_eq!(backoff(3), Duration::from_secs(16));
+        assert_eq!(backoff(40), MAX_BACKOFF);
+    }
+
+    #[test]
+    fn only_enabled_settings_with_an_endpoint_connect() {
+        let mut settings = AnnouncementSettings { endpoint: Some("wss://drk.example/ws".into()), ..Default::default() };
+        assert_eq!(settings.active_endpoint(), None);
+        settings.enabled = true;
+        assert_eq!(settings.active_endpoint(), Some("wss://drk.example/ws"));
+        settings.endpoint = Some(" ".into());
+        assert_eq!(settings.active_endpoint(), None);
+    }
+}
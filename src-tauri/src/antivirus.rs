@@ -0,0 +1,218 @@
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Prefix of errors caused by antivirus interference; the UI swaps the generic error for guidance.
+pub const ERROR_MARKER: &str = "[antivirus]";
+// A game that dies this fast without writing a log line never got to run Java code
+const JAVA_KILLED_WINDOW: Duration = Duration::from_secs(5);
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum Symptom {
+    AccessDenied,
+    /// The file was gone right after being written (quarantined)
+    Vanished,
+    HashMismatch,
+    /// Java exited immediately without any output
+    JavaKilled,
+    Other,
+}
+
+impl Symptom {
+    pub fn from_io(e: &std::io::Error) -> Self {
+        if e.kind() == std::io::ErrorKind::PermissionDenied { Symptom::AccessDenied } else { Symptom::Other }
+    }
+
+    fn description(self) -> &'static str {
+        match self {
+            Symptom::AccessDenied => "acceso denegado",
+            Symptom::Vanished => "el archivo desapareció justo después de descargarse",
+            Symptom::HashMismatch => "el archivo cambió después de descargarse",
+            Symptom::JavaKilled => "Java se cerró al instante",
+            Symptom::Other => "error desconocido",
+        }
+    }
+}
+
+/// The interference pattern behind a failed download, if any. Access denied or a vanished file
+/// once is enough; hash mismatches only count when every attempt had one.
+pub fn diagnose(symptoms: &[Symptom]) -> Option<Symptom> {
+    if symptoms.contains(&Symptom::AccessDenied) {
+        return Some(Symptom::AccessDenied);
+    }
+    if symptoms.contains(&Symptom::Vanished) {
+        return Some(Symptom::Vanished);
+    }
+    if symptoms.len() > 1 && symptoms.iter().all(|s| *s == Symptom::HashMismatch) {
+        return Some(Symptom::HashMismatch);
+    }
+    None
+}
+
+pub fn java_killed(elapsed: Duration, log_bytes: u64) -> bool {
+    elapsed < JAVA_KILLED_WINDOW && log_bytes == 0
+}
+
+#[derive(Serialize, Clone, Debug, JsonSchema)]
+pub struct BlockedFile {
+    pub path: String,
+    /// Where to download it again; `None` for files the launcher doesn't fetch itself (Java)
+    pub url: Option<String>,
+    #[serde(skip)]
+    sha1: Option<String>,
+    pub symptom: Symptom,
+}
+
+static BLOCKED: Mutex<Vec<BlockedFile>> = Mutex::new(Vec::new());
+
+/// Remembers a blocked file for `reverify_blocked_files` and returns the error to report.
+pub fn record_blocked(url: Option<&str>, path: &Path, sha1: Option<&str>, symptom: Symptom) -> String {
+    let path_str = path.to_string_lossy().to_string();
+    if let Ok(mut blocked) = BLOCKED.lock() {
+        blocked.retain(|b| b.path != path_str);
+        blocked.push(BlockedFile {
+            path: path_str.clone(),
+            url: url.map(str::to_string),
+            sha1: sha1.map(str::to_string),
+            symptom,
+        });
+    }
+    format!(
+        "{} Posible bloqueo del antivirus en {} ({}). Añade una exclusión para {} y vuelve a verificar los archivos.",
+        ERROR_MARKER,
+        path_str,
+        symptom.description(),
+        crate::data_dir::root().display()
+    )
+}
+
+/// Exclusion steps for an antivirus product, matched on its display name.
+pub fn guidance_for(product: &str) -> &'static str {
+    let name = product.to_lowercase();
+    if name.contains("defender") {
+        "Seguridad de Windows > Protección contra virus y amenazas > Administrar la configuración > Exclusiones > Agregar una exclusión > Carpeta."
+    } else if name.contains("avast") || name.contains("avg") {
+        "Menú > Configuración > General > Excepciones > Añadir excepción, y elige la carpeta del launcher."
+    } else if name.contains("kaspersky") {
+        "Configuración > Amenazas y exclusiones > Administrar exclusiones > Añadir, e incluye la carpeta del launcher."
+    } else if name.contains("eset") {
+        "Configuración avanzada (F5) > Detección > Exclusiones de rendimiento > Editar > Añadir la carpeta del launcher."
+    } else if name.contains("norton") {
+        "Configuración > Antivirus > Análisis y riesgos > Elementos que se excluirán de los análisis > Configurar > Añadir carpetas."
+    } else if name.contains("mcafee") {
+        "Mi protección > Análisis en tiempo real > Archivos excluidos > Agregar archivo/carpeta."
+    } else if name.contains("bitdefender") {
+        "Protección > Antivirus > Configuración > Gestionar excepciones > Añadir una excepción para la carpeta del launcher."
+    } else {
+        "Abre la configuración de tu antivirus, busca \"Exclusiones\" o \"Excepciones\" y añade la carpeta del launcher."
+    }
+}
+
+/// Antivirus products registered with the Windows Security Center.
+fn installed_products() -> Vec<String> {
+    if !cfg!(target_os = "windows") {
+        return Vec::new();
+    }
+    let mut cmd = std::process::Command::new("powershell");
+    cmd.args([
+        "-NoProfile",
+        "-Command",
+        "Get-CimInstance -Namespace root/SecurityCenter2 -ClassName AntivirusProduct | ForEach-Object { $_.displayName }",
+    ]);
+    #[cfg(target_os = "windows")]
+    {
+        use std::os::windows::process::CommandExt;
+        cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
+    }
+    cmd.output()
+        .map(|o| String::from_utf8_lossy(&o.stdout).lines().map(str::trim).filter(|l| !l.is_empty()).map(str::to_string).collect())
+        .unwrap_or_default()
+}
+
+#[derive(Serialize, Clone, Debug, JsonSchema)]
+pub struct ProductGuidance {
+    pub name: String,
+    pub steps: String,
+}
+
+#[derive(Serialize, Clone, Debug, JsonSchema)]
+pub struct AntivirusGuidance {
+    pub products: Vec<ProductGuidance>,
+    #[serde(rename = "exclusionPath")]
+    pub exclusion_path: String,
+    #[serde(rename = "blockedFiles")]
+    pub blocked_files: Vec<BlockedFile>,
+}
+
+/// Asks the OS which antivirus is installed, which spawns PowerShell on Windows.
+#[tauri::command]
+pub async fn get_antivirus_guidance() -> Result<AntivirusGuidance, String> {
+    crate::run_blocking(|| {
+        let mut products = installed_products();
+        if products.is_empty() {
+            products.push("Antivirus".to_string());
+        }
+        Ok(AntivirusGuidance {
+            products: products.into_iter().map(|name| ProductGuidance { steps: guidance_for(&name).to_string(), name }).collect(),
+            exclusion_path: crate::data_dir::root().to_string_lossy().to_string(),
+            blocked_files: BLOCKED.lock().map(|b| b.clone()).unwrap_or_default(),
+        })
+    }).await
+}
+
+/// Downloads the blocked files again (after the user added an exclusion). Returns the paths that
+/// are still blocked.
+#[tauri::command]
+pub async fn reverify_blocked_files() -> Result<Vec<String>, String> {
+    let blocked = std::mem::take(&mut *BLOCKED.lock().map_err(|_| "Failed to lock blocked files")?);
+    tauri::async_runtime::spawn_blocking(move || {
+        let mut still_blocked = Vec::new();
+        for file in blocked {
+            let path = PathBuf::from(&file.path);
+            let ok = match file.url.as_deref() {
+                Some(url) => crate::minecraft::downloader::download_file(url, &path, file.sha1.as_deref()).is_ok(),
+                // A quarantined Java is downloaded again on the next launch
+                None => !matches!(File::open(&path), Err(e) if e.kind() == std::io::ErrorKind::PermissionDenied),
+            };
+            if !ok {
+                still_blocked.push(file.path);
+            }
+        }
+        still_blocked
+    })
+    .await
+    .map_err(|e| format!("Task panicked: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn diagnosis_needs_a_clear_pattern() {
+        assert_eq!(diagnose(&[Symptom::Other, Symptom::AccessDenied]), Some(Symptom::AccessDenied));
+        assert_eq!(diagnose(&[Symptom::HashMismatch, Symptom::Vanished]), Some(Symptom::Vanished));
+        assert_eq!(diagnose(&[Symptom::HashMismatch; 3]), Some(Symptom::HashMismatch));
+        assert_eq!(diagnose(&[Symptom::HashMismatch, Symptom::Other]), None);
+        assert_eq!(diagnose(&[Symptom::HashMismatch]), None);
+        assert_eq!(diagnose(&[]), None);
+    }
+
+    #[test]
+    fn java_killed_means_fast_and_silent() {
+        assert!(java_killed(Duration::from_millis(300), 0));
+        assert!(!java_killed(Duration::from_millis(300), 120));
+        assert!(!java_killed(Duration::from_secs(30), 0));
+    }
+
+    #[test]
+    fn guidance_matches_product_names() {
+        assert!(guidance_for("Windows Defender").starts_with("Seguridad de Windows"));
+        assert!(guidance_for("AVG AntiVirus FREE").contains("Excepciones"));
+        assert!(guidance_for("Desconocido").contains("Exclusiones"));
+    }
+}
@@ -0,0 +1,175 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::http::{header, Request, Response, StatusCode};
+use tauri::{Runtime, UriSchemeContext, UriSchemeResponder};
+
+/// `drk-asset://localhost/<encoded path>` (`http://drk-asset.localhost/...` on Windows) serves
+/// the launcher's own files to the webview. Only these paths are answered:
+/// - `instance/<id>/images/<file>` and `instance/<id>/thumbs/<file>`: imported instance images
+/// - `instance/<id>/screenshots/<file>`: the game's screenshots
+/// - `image-cache/<hash>`: an image kept by `image_cache`
+/// - `remote/<url>`: a remote image through `image_cache`, only those `image_cache::allowed` lets through
+pub const SCHEME: &str = "drk-asset";
+const IMAGE_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "gif", "webp", "bmp", "ico"];
+
+/// A file the protocol is allowed to serve.
+#[derive(Debug, PartialEq)]
+pub enum Asset {
+    InstanceImage { instance_id: String, name: String },
+    Thumbnail { instance_id: String, name: String },
+    Screenshot { instance_id: String, name: String },
+    CachedImage { stem: String },
+    RemoteImage { url: String },
+}
+
+fn plain_name(name: &str) -> bool {
+    !name.is_empty() && !name.starts_with('.') && !name.contains(['/', '\\', ':'])
+}
+
+fn image_name(name: &str) -> Option<String> {
+    let extension = name.rsplit_once('.')?.1.to_ascii_lowercase();
+    (plain_name(name) && IMAGE_EXTENSIONS.contains(&extension.as_str())).then(|| name.to_string())
+}
+
+/// What a request path asks for, or `None` when it's outside the whitelist.
+pub fn parse(path: &str) -> Option<Asset> {
+    let path = crate::image_cache::percent_decode(path.trim_start_matches('/'));
+    if let Some(url) = path.strip_prefix("remote/") {
+        return (url.starts_with("http://") || url.starts_with("https://")).then(|| Asset::RemoteImage { url: url.to_string() });
+    }
+    let parts: Vec<&str> = path.split('/').collect();
+    match parts.as_slice() {
+        ["instance", id, area, name] if plain_name(id) => {
+            let instance_id = id.to_string();
+            let name = image_name(name)?;
+            match *area {
+                "images" => Some(Asset::InstanceImage { instance_id, name }),
+                "thumbs" => Some(Asset::Thumbnail { instance_id, name }),
+                "screenshots" => Some(Asset::Screenshot { instance_id, name }),
+                _ => None,
+            }
+        }
+        ["image-cache", stem] if stem.len() == 40 && stem.bytes().all(|b| b.is_ascii_hexdigit()) => {
+            Some(Asset::CachedImage { stem: stem.to_ascii_lowercase() })
+        }
+        _ => None,
+    }
+}
+
+/// File behind `asset` under `data_root`; `instance_path` looks an instance's folder up by id.
+/// The result still has to pass `path_policy` so symlinks can't lead out of the data root.
+pub fn locate(asset: &Asset, data_root: &Path, instance_path: impl Fn(&str) -> Option<PathBuf>) -> Option<PathBuf> {
+    let path = match asset {
+        Asset::InstanceImage { instance_id, name } => instance_path(instance_id)?.join(crate::instance_images::IMAGES_DIR).join(name),
+        Asset::Thumbnail { instance_id, name } => instance_path(instance_id)?.join(crate::instance_images::IMAGES_DIR).join("thumbs").join(name),
+        Asset::Screenshot { instance_id, name } => instance_path(instance_id)?.join("minecraft").join("screenshots").join(name),
+        Asset::CachedImage { stem } => crate::image_cache::cache_dir_in(data_root).join(stem),
+        Asset::RemoteImage { .. } => return None,
+    };
+    crate::path_policy::inside(&path, data_root).ok()
+}
+
+fn content_type(asset: &Asset, path: &Path) -> String {
+    if let Asset::CachedImage { stem } = asset {
+        return crate::image_cache::stored_content_type(path.parent().unwrap_or(path), stem).unwrap_or_else(|| "application/octet-stream".to_string());
+    }
+    let extension = path.extension().and_then(|e| e.to_str()).unwrap_or_default().to_ascii_lowercase();
+    match extension.as_str() {
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "bmp" => "image/bmp",
+        "ico" => "image/x-icon",
+        _ => "application/octet-stream",
+    }
+    .to_string()
+}
+
+fn respond(responder: UriSchemeResponder, status: StatusCode, content_type: &str, body: Vec<u8>) {
+    let response = Response::builder()
+        .status(status)
+        .header(header::CONTENT_TYPE, content_type)
+        .body(body);
+    if let Ok(response) = response {
+        responder.respond(response);
+    }
+}
+
+/// Handler of the `drk-asset` protocol.
+pub fn handle_request<R: Runtime>(_ctx: UriSchemeContext<'_, R>, request: Request<Vec<u8>>, responder: UriSchemeResponder) {
+    let path = request.uri().path().to_string();
+    std::thread::spawn(move || {
+        let Some(asset) = parse(&path) else {
+            return respond(responder, StatusCode::FORBIDDEN, "text/plain", b"Ruta no permitida".to_vec());
+        };
+        if let Asset::RemoteImage { url } = &asset {
+            if !crate::image_cache::allowed(url) {
+                return respond(responder, StatusCode::FORBIDDEN, "text/plain", b"Imagen no permitida".to_vec());
+            }
+            return match crate::image_cache::get(url) {
+                Ok(cached) => respond(responder, StatusCode::OK, &cached.content_type, cached.bytes.to_vec()),
+                Err(e) => respond(responder, StatusCode::BAD_GATEWAY, "text/plain", e.into_bytes()),
+            };
+        }
+        let data_root = PathBuf::from(crate::get_instances_dir());
+        let file = locate(&asset, &data_root, |id| {
+            crate::load_instances().into_iter().find(|i| i.id == id).map(|i| PathBuf::from(i.path))
+        });
+        match file.and_then(|file| fs::read(&file).ok().map(|bytes| (file, bytes))) {
+            Some((file, bytes)) => respond(responder, StatusCode::OK, &content_type(&asset, &file), bytes),
+            None => respond(responder, StatusCode::NOT_FOUND, "text/plain", b"No encontrado".to_vec()),
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_whitelisted_paths_parse() {
+        assert_eq!(
+            parse("/instance%2Fevento-1%2Fimages%2Fbanner.PNG"),
+            Some(Asset::InstanceImage { instance_id: "evento-1".into(), name: "banner.PNG".into() })
+        );
+        assert_eq!(
+            parse("/instance/evento-1/screenshots/2024-05-01_20.11.03.png"),
+            Some(Asset::Screenshot { instance_id: "evento-1".into(), name: "2024-05-01_20.11.03.png".into() })
+        );
+        let stem = "a".repeat(40);
+        assert_eq!(parse(&format!("/image-cache/{}", stem)), Some(Asset::CachedImage { stem }));
+        assert_eq!(
+            parse("/remote%2Fhttps%3A%2F%2Fmc-heads.net%2Fbody%2Fabc%3Fsize%3D64"),
+            Some(Asset::RemoteImage { url: "https://mc-heads.net/body/abc?size=64".into() })
+        );
+        assert_eq!(parse("/remote%2Ffile%3A%2F%2F%2Fetc%2Fpasswd"), None);
+
+        assert_eq!(parse("/instance/evento-1/images/..%2F..%2Fsettings.json"), None);
+        assert_eq!(parse("/instance/..%2Fevento-1/images/a.png"), None);
+        assert_eq!(parse("/instance/evento-1/mods/a.png"), None);
+        assert_eq!(parse("/instance/evento-1/images/options.txt"), None);
+        assert_eq!(parse("/instance/evento-1/images/.hidden.png"), None);
+        assert_eq!(parse("/image-cache/..%2Fsettings.json"), None);
+        assert_eq!(parse("/C:%5CWindows%5Cwin.ini"), None);
+    }
+
+    #[test]
+    fn assets_stay_inside_the_data_root() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("data");
+        let instance = root.join("instancias").join("Evento");
+        fs::create_dir_all(instance.join("images")).unwrap();
+        fs::write(instance.join("images").join("banner.png"), "png").unwrap();
+        let outside = dir.path().join("Otro");
+        fs::create_dir_all(outside.join("images")).unwrap();
+        fs::write(outside.join("images").join("banner.png"), "png").unwrap();
+
+        let asset = parse("/instance/evento/images/banner.png").unwrap();
+        let found = locate(&asset, &root, |_| Some(instance.clone())).unwrap();
+        assert!(found.ends_with(Path::new("images").join("banner.png")));
+        assert_eq!(locate(&asset, &root, |_| None), None);
+        // An instance whose path points elsewhere doesn't widen what's served
+        assert_eq!(locate(&asset, &root, |_| Some(outside.clone())), None);
+    }
+}
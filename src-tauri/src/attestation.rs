@@ -0,0 +1,228 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+use crate::settings::{load_settings, save_settings};
+
+/// Bumped when the attestation payload changes shape; sent as `v`.
+pub const ATTESTATION_VERSION: u32 = 1;
+/// Folders of `minecraft/` whose files are hashed; what an event pack actually controls
+const ATTESTED_FOLDERS: [&str; 2] = ["mods", "config"];
+// Written next to the instance's `minecraft/` folder, not inside what the game can read
+const ATTESTATION_FILE: &str = "attestation.json";
+
+/// Signed list of the pack's file hashes made at launch, for event servers that check players
+/// run the unmodified pack. Admin only, the secret is shared with the server.
+#[derive(Serialize, Deserialize, Clone, Default, PartialEq, Debug, JsonSchema)]
+pub struct AttestationSettings {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Verification endpoint the signed attestation is POSTed to; none keeps it local
+    #[serde(default)]
+    pub endpoint: Option<String>,
+    /// HMAC key; falls back to the announcement command secret
+    #[serde(default)]
+    pub secret: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Attestation {
+    pub v: u32,
+    #[serde(rename = "instanceId")]
+    pub instance_id: String,
+    #[serde(rename = "instanceName")]
+    pub instance_name: String,
+    pub player: String,
+    #[serde(rename = "playerUuid")]
+    pub player_uuid: String,
+    /// Unix seconds
+    #[serde(rename = "issuedAt")]
+    pub issued_at: u64,
+    #[serde(rename = "launcherVersion")]
+    pub launcher_version: String,
+    /// SHA-1 of every attested file, by path relative to `minecraft/`
+    pub files: BTreeMap<String, String>,
+    /// SHA-256 over the sorted `path:sha1` lines, so servers can compare one value
+    pub digest: String,
+}
+
+/// Same envelope as remote commands: `payload` is the attestation JSON exactly as signed,
+/// `signature` its HMAC-SHA256 in hex.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SignedAttestation {
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub payload: String,
+    pub signature: String,
+}
+
+/// Hashes of the attested folders in `minecraft_dir`.
+pub fn file_hashes(minecraft_dir: &Path) -> Result<BTreeMap<String, String>, String> {
+    let mut files = BTreeMap::new();
+    for folder in ATTESTED_FOLDERS {
+        for (name, path) in crate::minecraft::archive::walk_files(&minecraft_dir.join(folder)) {
+            files.insert(format!("{}/{}", folder, name), crate::minecraft::downloader::file_sha1(&path)?);
+        }
+    }
+    Ok(files)
+}
+
+pub fn digest(files: &BTreeMap<String, String>) -> String {
+    let mut hasher = Sha256::new();
+    for (path, sha1) in files {
+        hasher.update(format!("{}:{}\n", path, sha1).as_bytes());
+    }
+    hex::encode(hasher.finalize())
+}
+
+/// Fingerprint of the pack's mods, passed to the game as `-Ddrk.pack.fingerprint` (see
+/// `launch_logic::pack_fingerprint_flags`). Only `mods/` counts: the game rewrites `config/`,
+/// and the value must stay the same from one launch to the next. `None` without mods.
+pub fn pack_fingerprint(minecraft_dir: &Path) -> Result<Option<String>, String> {
+    let mut files = BTreeMap::new();
+    for (name, path) in crate::minecraft::archive::walk_files(&minecraft_dir.join("mods")) {
+        if name.to_lowercase().ends_with(".jar") {
+            files.insert(format!("mods/{}", name), crate::minecraft::downloader::file_sha1(&path)?);
+        }
+    }
+    Ok((!files.is_empty()).then(|| digest(&files)))
+}
+
+pub fn sign(attestation: &Attestation, secret: &str) -> Result<SignedAttestation, String> {
+    let payload = serde_json::to_string(attestation).map_err(|e| e.to_string())?;
+    let signature = crate::remote_commands::sign(secret, &payload);
+    Ok(SignedAttestation { kind: "attestation".to_string(), payload, signature })
+}
+
+fn submit(endpoint: &str, signed: &SignedAttestation) -> Result<(), String> {
+    let client = reqwest::blocking::Client::builder()
+        .user_agent(crate::http::user_agent())
+        .timeout(Duration::from_secs(15))
+        .build()
+        .map_err(|e| e.to_string())?;
+    let resp = crate::http::send(&client, client.post(endpoint).json(signed))?;
+    if !resp.status().is_success() {
+        return Err(format!("El servidor de verificación respondió {}", resp.status()));
+    }
+    Ok(())
+}
+
+/// Builds, signs and saves the attestation for a launch, then submits it in the background
+/// when an endpoint is set. Never blocks the launch: problems are only logged.
+pub fn at_launch(instance_id: &str, instance_name: &str, instance_path: &Path, profile: &crate::auth::MinecraftProfile) {
+    let settings = load_settings();
+    if !settings.attestation.enabled {
+        return;
+    }
+    let Some(secret) = settings.attestation.secret.clone().or(settings.announcements.command_secret.clone()).filter(|s| !s.is_empty()) else {
+        eprintln!("[attestation] Enabled but no secret is configured");
+        return;
+    };
+    let files = match file_hashes(&instance_path.join("minecraft")) {
+        Ok(files) => files,
+        Err(e) => {
+            eprintln!("[attestation] Failed to hash files: {}", e);
+            return;
+        }
+    };
+    let attestation = Attestation {
+        v: ATTESTATION_VERSION,
+        instance_id: instance_id.to_string(),
+        instance_name: instance_name.to_string(),
+        player: profile.name.clone(),
+        player_uuid: profile.id.clone(),
+        issued_at: crate::unix_now(),
+        launcher_version: env!("CARGO_PKG_VERSION").to_string(),
+        digest: digest(&files),
+        files,
+    };
+    let signed = match sign(&attestation, &secret) {
+        Ok(signed) => signed,
+        Err(e) => {
+            eprintln!("[attestation] Failed to sign: {}", e);
+            return;
+        }
+    };
+    if let Ok(json) = serde_json::to_string_pretty(&signed) {
+        let _ = fs::write(instance_path.join(ATTESTATION_FILE), json);
+    }
+    if let Some(endpoint) = settings.attestation.endpoint.filter(|u| !u.trim().is_empty()) {
+        std::thread::spawn(move || {
+            if let Err(e) = submit(&endpoint, &signed) {
+                eprintln!("[attestation] Failed to submit: {}", e);
+            }
+        });
+    }
+}
+
+#[tauri::command]
+pub fn get_attestation_settings(password: String) -> Result<AttestationSettings, String> {
+    crate::ensure_admin(password)?;
+    Ok(load_settings().attestation)
+}
+
+#[tauri::command]
+pub fn set_attestation_settings(password: String, attestation: AttestationSettings) -> Result<(), String> {
+    crate::ensure_admin(password)?;
+    let mut settings = load_settings();
+    settings.attestation = attestation;
+    save_settings(&settings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn attested_files_are_hashed_and_signed() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("mods")).unwrap();
+        fs::create_dir_all(dir.path().join("config").join("create")).unwrap();
+        fs::create_dir_all(dir.path().join("saves")).unwrap();
+        fs::write(dir.path().join("mods").join("create.jar"), b"jar").unwrap();
+        fs::write(dir.path().join("config").join("create").join("server.toml"), b"toml").unwrap();
+        fs::write(dir.path().join("saves").join("level.dat"), b"world").unwrap();
+
+        let files = file_hashes(dir.path()).unwrap();
+        assert_eq!(files.keys().collect::<Vec<_>>(), vec!["config/create/server.toml", "mods/create.jar"]);
+
+        let mut changed = files.clone();
+        changed.insert("mods/xray.jar".to_string(), "abc".to_string());
+        assert_ne!(digest(&files), digest(&changed));
+
+        let attestation = Attestation {
+            v: ATTESTATION_VERSION,
+            instance_id: "evento".to_string(),
+            instance_name: "Evento".to_string(),
+            player: "Steve".to_string(),
+            player_uuid: "uuid".to_string(),
+            issued_at: 1,
+            launcher_version: "1.0.0".to_string(),
+            digest: digest(&files),
+            files,
+        };
+        let signed = sign(&attestation, "secreto").unwrap();
+        assert_eq!(signed.signature, crate::remote_commands::sign("secreto", &signed.payload));
+        assert_eq!(serde_json::from_str::<Attestation>(&signed.payload).unwrap(), attestation);
+    }
+
+    #[test]
+    fn fingerprint_covers_mod_jars_only() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(pack_fingerprint(dir.path()).unwrap(), None);
+        fs::create_dir_all(dir.path().join("mods")).unwrap();
+        fs::create_dir_all(dir.path().join("config")).unwrap();
+        fs::write(dir.path().join("mods").join("create.jar"), b"jar").unwrap();
+        let fingerprint = pack_fingerprint(dir.path()).unwrap().unwrap();
+
+        fs::write(dir.path().join("config").join("create.toml"), b"rewritten by the game").unwrap();
+        fs::write(dir.path().join("mods").join("notes.txt"), b"not a mod").unwrap();
+        assert_eq!(pack_fingerprint(dir.path()).unwrap(), Some(fingerprint.clone()));
+
+        fs::write(dir.path().join("mods").join("xray.jar"), b"jar").unwrap();
+        assert_ne!(pack_fingerprint(dir.path()).unwrap(), Some(fingerprint));
+    }
+}
@@ -1,16 +1,30 @@
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::sync::Mutex;
-use tauri::State;
+use tauri::{AppHandle, Emitter, Manager, State};
 use oauth2::{
     basic::BasicClient, AuthUrl, ClientId, CsrfToken, PkceCodeChallenge, RedirectUrl, Scope,
-    TokenUrl, TokenResponse, PkceCodeVerifier, AuthorizationCode
+    TokenUrl, TokenResponse, PkceCodeVerifier, AuthorizationCode, RefreshToken
 };
 use oauth2::reqwest::async_http_client;
 use tokio::net::TcpListener;
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use reqwest::Client;
-
-#[derive(Default, Serialize, Clone, Debug)]
+use base64::Engine;
+use md5::{Md5, Digest};
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+
+const ACCOUNTS_FILE: &str = "accounts.json";
+const AVATARS_DIR: &str = "avatars";
+const AVATAR_MAX_AGE_SECS: u64 = 24 * 60 * 60;
+// Credential store entries are `DrkLauncher / <account id>`
+const KEYRING_SERVICE: &str = "DrkLauncher";
+// A token refresh that hangs this long leaves the player on the login screen
+const RESTORE_TIMEOUT: Duration = Duration::from_secs(15);
+
+#[derive(Default, Serialize, Clone, Debug, JsonSchema)]
 pub struct MinecraftProfile {
     pub id: String,
     pub name: String,
@@ -23,12 +37,34 @@ pub struct AuthState {
     pub pkce_verifier: Option<PkceCodeVerifier>,
     pub csrf_token: Option<CsrfToken>,
     pub profile: Option<MinecraftProfile>,
+    /// "msa" or "offline", set once a session is established or restored at startup
+    pub auth_type: Option<String>,
 }
 
 pub struct AppState {
     pub auth: Mutex<AuthState>,
 }
 
+#[derive(Serialize, Deserialize, Clone)]
+pub struct SavedAccount {
+    pub id: String,
+    pub name: String,
+    #[serde(rename = "authType", alias = "auth_type")]
+    pub auth_type: String,
+    /// Only read from files written by older versions; the token now lives in the OS credential
+    /// store (see `store_refresh_token`) and is dropped from the file on the next save
+    #[serde(rename = "refreshToken", alias = "refresh_token", default, skip_serializing)]
+    pub refresh_token: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct AccountsFile {
+    #[serde(rename = "selectedAccount", alias = "selected_account", default)]
+    selected_account: Option<String>,
+    #[serde(default)]
+    accounts: Vec<SavedAccount>,
+}
+
 #[derive(Deserialize)]
 struct XboxLiveResponse {
     #[serde(rename = "Token")]
@@ -58,8 +94,7 @@ struct EntitlementItem {
     name: String,
 }
 
-#[tauri::command]
-pub async fn start_microsoft_login(state: State<'_, AppState>) -> Result<String, String> {
+fn microsoft_oauth_client() -> Result<BasicClient, String> {
     let client_id = ClientId::new("00000000402b5328".to_string()); // Public Minecraft Client ID
     let auth_url = AuthUrl::new("https://login.live.com/oauth20_authorize.srf".to_string())
         .map_err(|e| e.to_string())?;
@@ -69,74 +104,17 @@ pub async fn start_microsoft_login(state: State<'_, AppState>) -> Result<String,
     let redirect_url = RedirectUrl::new("http://localhost:3434/auth/callback".to_string())
         .map_err(|e| e.to_string())?;
 
-    let client = BasicClient::new(
+    Ok(BasicClient::new(
         client_id,
         None,
         auth_url,
         Some(token_url)
     )
-    .set_redirect_uri(redirect_url);
-
-    let (pkce_challenge, pkce_verifier) = PkceCodeChallenge::new_random_sha256();
-
-    let (auth_url, csrf_token) = client
-        .authorize_url(CsrfToken::new_random)
-        .add_scope(Scope::new("XboxLive.signin".to_string()))
-        .add_scope(Scope::new("offline_access".to_string()))
-        .set_pkce_challenge(pkce_challenge)
-        .url();
-
-    // Store verifier for later use in the callback
-    {
-        let mut auth_state = state.auth.lock().map_err(|_| "Failed to lock auth state".to_string())?;
-        auth_state.pkce_verifier = Some(pkce_verifier);
-        auth_state.csrf_token = Some(csrf_token);
-    } // unlock
-
-    // Spawn the local server to listen for the callback
-
-    // Open browser
-    if let Err(e) = open::that(auth_url.to_string()) {
-        return Err(format!("Failed to open browser: {}", e));
-    }
-
-    // Start listener
-    let listener = TcpListener::bind("127.0.0.1:3434").await.map_err(|e| e.to_string())?;
-
-    // Accept one connection
-    let (mut stream, _) = listener.accept().await.map_err(|e| e.to_string())?;
-
-    let mut buffer = [0; 1024];
-    let n = stream.read(&mut buffer).await.map_err(|e| e.to_string())?;
-    let request = String::from_utf8_lossy(&buffer[..n]);
-
-    // Parse code from request
-    // GET /auth/callback?code=M... HTTP/1.1
-    let code = if let Some(start) = request.find("code=") {
-        let rest = &request[start + 5..];
-        let end = rest.find('&').or_else(|| rest.find(' ')).unwrap_or(rest.len());
-        &rest[..end]
-    } else {
-        return Err("No code found in callback".to_string());
-    };
-
-    let code = AuthorizationCode::new(code.to_string());
-
-    // Exchange code for token
-    let pkce_verifier = {
-        let mut auth_state = state.auth.lock().map_err(|_| "Failed to lock auth state".to_string())?;
-        auth_state.pkce_verifier.take().ok_or("No PKCE verifier found")?
-    };
-
-    let token_result = client
-        .exchange_code(code)
-        .set_pkce_verifier(pkce_verifier)
-        .request_async(async_http_client)
-        .await
-        .map_err(|e| format!("Token exchange failed: {}", e))?;
-
-    let access_token = token_result.access_token().secret();
+    .set_redirect_uri(redirect_url))
+}
 
+/// Xbox Live -> XSTS -> Minecraft services chain, shared by interactive login and session restore.
+async fn authenticate_minecraft(access_token: &str) -> Result<MinecraftProfile, String> {
     // Authenticate with Xbox Live
     let client_http = Client::new();
     let xbl_body = serde_json::json!({
@@ -223,22 +201,94 @@ pub async fn start_microsoft_login(state: State<'_, AppState>) -> Result<String,
         })
     };
 
+    Ok(MinecraftProfile {
+        id: profile_res.id,
+        name: profile_res.name,
+        access_token: mc_token,
+        has_entitlement: entitlements_ok,
+    })
+}
+
+#[tauri::command]
+pub async fn start_microsoft_login(state: State<'_, AppState>) -> Result<String, String> {
+    let client = microsoft_oauth_client()?;
+
+    let (pkce_challenge, pkce_verifier) = PkceCodeChallenge::new_random_sha256();
+
+    let (auth_url, csrf_token) = client
+        .authorize_url(CsrfToken::new_random)
+        .add_scope(Scope::new("XboxLive.signin".to_string()))
+        .add_scope(Scope::new("offline_access".to_string()))
+        .set_pkce_challenge(pkce_challenge)
+        .url();
+
+    // Store verifier for later use in the callback
+    {
+        let mut auth_state = state.auth.lock().map_err(|_| "Failed to lock auth state".to_string())?;
+        auth_state.pkce_verifier = Some(pkce_verifier);
+        auth_state.csrf_token = Some(csrf_token);
+    } // unlock
+
+    // Spawn the local server to listen for the callback
+
+    // Open browser
+    if let Err(e) = open::that(auth_url.to_string()) {
+        return Err(format!("Failed to open browser: {}", e));
+    }
+
+    // Start listener
+    let listener = TcpListener::bind("127.0.0.1:3434").await.map_err(|e| e.to_string())?;
+
+    // Accept one connection
+    let (mut stream, _) = listener.accept().await.map_err(|e| e.to_string())?;
+
+    let mut buffer = [0; 1024];
+    let n = stream.read(&mut buffer).await.map_err(|e| e.to_string())?;
+    let request = String::from_utf8_lossy(&buffer[..n]);
+
+    // Parse code from request
+    // GET /auth/callback?code=M... HTTP/1.1
+    let code = if let Some(start) = request.find("code=") {
+        let rest = &request[start + 5..];
+        let end = rest.find('&').or_else(|| rest.find(' ')).unwrap_or(rest.len());
+        &rest[..end]
+    } else {
+        return Err("No code found in callback".to_string());
+    };
+
+    let code = AuthorizationCode::new(code.to_string());
+
+    // Exchange code for token
+    let pkce_verifier = {
+        let mut auth_state = state.auth.lock().map_err(|_| "Failed to lock auth state".to_string())?;
+        auth_state.pkce_verifier.take().ok_or("No PKCE verifier found")?
+    };
+
+    let token_result = client
+        .exchange_code(code)
+        .set_pkce_verifier(pkce_verifier)
+        .request_async(async_http_client)
+        .await
+        .map_err(|e| format!("Token exchange failed: {}", e))?;
+
+    let access_token = token_result.access_token().secret();
+    let refresh_token = token_result.refresh_token().map(|t| t.secret().clone());
+    let profile = authenticate_minecraft(access_token).await?;
+    let profile_name = profile.name.clone();
+
     // Save profile to state
+    remember_account(&profile, "msa", refresh_token)?;
     {
         let mut auth_state = state.auth.lock().map_err(|_| "Failed to lock auth state".to_string())?;
-        auth_state.profile = Some(MinecraftProfile {
-            id: profile_res.id,
-            name: profile_res.name.clone(),
-            access_token: mc_token,
-            has_entitlement: entitlements_ok,
-        });
+        auth_state.profile = Some(profile);
+        auth_state.auth_type = Some("msa".to_string());
     }
 
     // Send success response to browser
     let response = "HTTP/1.1 200 OK\r\nContent-Type: text/html\r\n\r\n<html><body><h1>Login Successful!</h1><p>You can close this window and return to the launcher.</p><script>window.close()</script></body></html>";
     stream.write_all(response.as_bytes()).await.map_err(|e| e.to_string())?;
 
-    Ok(format!("Logged in as {}", profile_res.name))
+    Ok(format!("Logged in as {}", profile_name))
 }
 
 #[tauri::command]
@@ -247,14 +297,297 @@ pub fn get_auth_profile(state: State<'_, AppState>) -> Result<Option<MinecraftPr
     Ok(auth_state.profile.clone())
 }
 
-#[tauri::command]
-pub async fn start_offline_login(username: String, state: State<'_, AppState>) -> Result<String, String> {
-    let mut auth_state = state.auth.lock().map_err(|_| "Failed to lock auth state".to_string())?;
-    auth_state.profile = Some(MinecraftProfile {
-        id: uuid::Uuid::new_v4().to_string(),
+/// Minecraft usernames are 3-16 characters of ASCII letters, digits and underscores.
+pub fn is_valid_username(username: &str) -> bool {
+    (3..=16).contains(&username.len()) && username.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Same UUID the vanilla server assigns in offline mode (`UUID.nameUUIDFromBytes("OfflinePlayer:<name>")`),
+/// so skins, inventories and permissions keyed by UUID survive between sessions.
+pub fn offline_uuid(username: &str) -> String {
+    let mut hasher = Md5::new();
+    hasher.update(format!("OfflinePlayer:{}", username).as_bytes());
+    let hash: [u8; 16] = hasher.finalize().into();
+    uuid::Builder::from_md5_bytes(hash).into_uuid().to_string()
+}
+
+/// Signs in (and remembers) the offline account `username`.
+pub fn login_offline(state: &AppState, username: &str) -> Result<MinecraftProfile, String> {
+    let username = username.trim().to_string();
+    if !is_valid_username(&username) {
+        return Err("El nombre de usuario debe tener entre 3 y 16 caracteres (letras, números o _)".to_string());
+    }
+    let profile = MinecraftProfile {
+        id: offline_uuid(&username),
         name: username,
         access_token: "offline".to_string(),
         has_entitlement: false,
-    });
+    };
+    remember_account(&profile, "offline", None)?;
+    let mut auth_state = state.auth.lock().map_err(|_| "Failed to lock auth state".to_string())?;
+    auth_state.profile = Some(profile.clone());
+    auth_state.auth_type = Some("offline".to_string());
+    Ok(profile)
+}
+
+#[tauri::command]
+pub async fn start_offline_login(username: String, state: State<'_, AppState>) -> Result<String, String> {
+    login_offline(&state, &username)?;
     Ok("Logged in offline".to_string())
 }
+
+fn get_accounts_path() -> PathBuf {
+    PathBuf::from(crate::get_instances_dir()).join(ACCOUNTS_FILE)
+}
+
+fn load_accounts() -> AccountsFile {
+    fs::read_to_string(get_accounts_path())
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn save_accounts(accounts: &AccountsFile) -> Result<(), String> {
+    let path = get_accounts_path();
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    let json = serde_json::to_string_pretty(accounts).map_err(|e| e.to_string())?;
+    fs::write(&path, json).map_err(|e| e.to_string())
+}
+
+/// Keeps the refresh token in the OS credential store (Windows Credential Manager, macOS Keychain,
+/// Secret Service on Linux) instead of `accounts.json`.
+fn store_refresh_token(account_id: &str, token: &str) -> Result<(), String> {
+    keyring::Entry::new(KEYRING_SERVICE, account_id)
+        .and_then(|entry| entry.set_password(token))
+        .map_err(|e| format!("No se pudo guardar la sesión en el almacén de credenciales: {}", e))
+}
+
+/// The stored refresh token, or the plaintext one an older version left in `accounts.json`.
+fn stored_refresh_token(account: &SavedAccount) -> Option<String> {
+    keyring::Entry::new(KEYRING_SERVICE, &account.id)
+        .and_then(|entry| entry.get_password())
+        .ok()
+        .or_else(|| account.refresh_token.clone())
+}
+
+/// Stores (or updates) the account and marks it as the one to restore on next startup. Without a
+/// credential store the account is still listed, it just has to sign in again next time.
+fn remember_account(profile: &MinecraftProfile, auth_type: &str, refresh_token: Option<String>) -> Result<(), String> {
+    if let Some(token) = &refresh_token {
+        if let Err(e) = store_refresh_token(&profile.id, token) {
+            eprintln!("[auth] {}", e);
+        }
+    }
+    let mut accounts = load_accounts();
+    let entry = SavedAccount {
+        id: profile.id.clone(),
+        name: profile.name.clone(),
+        auth_type: auth_type.to_string(),
+        refresh_token: None,
+    };
+    if let Some(existing) = accounts.accounts.iter_mut().find(|a| a.id == entry.id) {
+        *existing = entry;
+    } else {
+        accounts.accounts.push(entry);
+    }
+    accounts.selected_account = Some(profile.id.clone());
+    save_accounts(&accounts)
+}
+
+async fn refresh_microsoft_session(account: &SavedAccount) -> Result<(MinecraftProfile, Option<String>), String> {
+    let refresh_token = stored_refresh_token(account).ok_or("No refresh token stored")?;
+    let token_result = microsoft_oauth_client()?
+        .exchange_refresh_token(&RefreshToken::new(refresh_token.clone()))
+        .request_async(async_http_client)
+        .await
+        .map_err(|e| format!("Token refresh failed: {}", e))?;
+    let new_refresh = token_result.refresh_token().map(|t| t.secret().clone()).or(Some(refresh_token));
+    let profile = authenticate_minecraft(token_result.access_token().secret()).await?;
+    Ok((profile, new_refresh))
+}
+
+/// Restores the last selected account into `AppState`. Failures are silent and just leave the user
+/// on the login screen.
+async fn restore_session(app: &AppHandle) {
+    let accounts = load_accounts();
+    let account = match accounts.selected_account.as_ref()
+        .and_then(|id| accounts.accounts.iter().find(|a| &a.id == id)) {
+        Some(a) => a.clone(),
+        None => return,
+    };
+
+    let (profile, refresh_token) = if account.auth_type == "msa" {
+        match refresh_microsoft_session(&account).await {
+            Ok(res) => res,
+            Err(e) => {
+                eprintln!("[auth] Failed to restore Microsoft session: {}", e);
+                return;
+            }
+        }
+    } else {
+        (MinecraftProfile {
+            id: offline_uuid(&account.name),
+            name: account.name.clone(),
+            access_token: "offline".to_string(),
+            has_entitlement: false,
+        }, None)
+    };
+
+    let _ = remember_account(&profile, &account.auth_type, refresh_token);
+    let state = app.state::<AppState>();
+    if let Ok(mut auth_state) = state.auth.lock() {
+        auth_state.profile = Some(profile);
+        auth_state.auth_type = Some(account.auth_type);
+    };
+}
+
+/// Restores the remembered account in the background so a slow token refresh doesn't hold the
+/// window, then sends `session_restored`. Gives up after `RESTORE_TIMEOUT`.
+pub fn start_session_restore(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        if tokio::time::timeout(RESTORE_TIMEOUT, restore_session(&app)).await.is_err() {
+            eprintln!("[auth] Session restore timed out");
+        }
+        let state = app.state::<AppState>();
+        let startup = match state.auth.lock() {
+            Ok(auth_state) => StartupAuthState::from(&*auth_state),
+            Err(_) => return,
+        };
+        let _ = app.emit("session_restored", startup);
+    });
+}
+
+/// Whether a remembered account is signed in; sent as `session_restored` once the startup restore ends.
+#[derive(Serialize, Clone, Debug, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct StartupAuthState {
+    pub logged_in: bool,
+    pub auth_type: Option<String>,
+    pub profile: Option<MinecraftProfile>,
+}
+
+impl From<&AuthState> for StartupAuthState {
+    fn from(auth_state: &AuthState) -> Self {
+        StartupAuthState {
+            logged_in: auth_state.profile.is_some(),
+            auth_type: auth_state.auth_type.clone(),
+            profile: auth_state.profile.clone(),
+        }
+    }
+}
+
+/// Lets the frontend skip the login screen when a session was restored before it asked; later
+/// restores arrive as `session_restored`.
+#[tauri::command]
+pub fn get_startup_auth_state(state: State<'_, AppState>) -> Result<StartupAuthState, String> {
+    let auth_state = state.auth.lock().map_err(|_| "Failed to lock auth state".to_string())?;
+    Ok(StartupAuthState::from(&*auth_state))
+}
+
+fn get_avatar_path(uuid: &str) -> PathBuf {
+    PathBuf::from(crate::get_instances_dir()).join(AVATARS_DIR).join(format!("{}.png", uuid))
+}
+
+fn fetch_avatar_bytes(uuid: &str) -> Result<Vec<u8>, String> {
+    let client = reqwest::blocking::Client::builder()
+        .user_agent(crate::http::user_agent())
+        .timeout(Duration::from_secs(10))
+        .build()
+        .map_err(|e| e.to_string())?;
+
+    // Both services render the 8x8 face plus the hat overlay from the player's skin texture
+    let urls = [
+        format!("https://crafatar.com/avatars/{}?size=64&overlay", uuid),
+        format!("https://mc-heads.net/avatar/{}/64", uuid),
+    ];
+    let mut last_err = String::new();
+    for url in &urls {
+        match client.get(url).send() {
+            Ok(resp) => {
+                if !resp.status().is_success() {
+                    last_err = format!("Avatar service returned status: {}", resp.status());
+                    continue;
+                }
+                match resp.bytes() {
+                    // PNG signature check, so an HTML error page is never cached as an avatar
+                    Ok(bytes) if bytes.starts_with(&[0x89, b'P', b'N', b'G']) => return Ok(bytes.to_vec()),
+                    Ok(_) => last_err = "Avatar service did not return a PNG".to_string(),
+                    Err(e) => last_err = format!("Failed to read avatar: {}", e),
+                }
+            }
+            Err(e) => last_err = format!("Failed to fetch avatar: {}", e),
+        }
+    }
+    Err(last_err)
+}
+
+/// Returns the account's head avatar as a `data:image/png;base64,...` URL.
+/// The image is cached under `avatars/` and refreshed once a day; when offline the cached copy is used.
+#[tauri::command]
+pub async fn get_account_avatar(uuid: String) -> Result<String, String> {
+    crate::run_blocking(move || {
+        let uuid = uuid.trim().to_lowercase();
+        if uuid.is_empty() || !uuid.chars().all(|c| c.is_ascii_hexdigit() || c == '-') {
+            return Err("Invalid account uuid".to_string());
+        }
+
+        let path = get_avatar_path(&uuid);
+        let is_fresh = fs::metadata(&path)
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|t| SystemTime::now().duration_since(t).ok())
+            .map(|age| age.as_secs() < AVATAR_MAX_AGE_SECS)
+            .unwrap_or(false);
+
+        let bytes = if is_fresh {
+            fs::read(&path).map_err(|e| e.to_string())?
+        } else {
+            match fetch_avatar_bytes(&uuid) {
+                Ok(bytes) => {
+                    if let Some(parent) = path.parent() {
+                        let _ = fs::create_dir_all(parent);
+                    }
+                    let _ = fs::write(&path, &bytes);
+                    bytes
+                }
+                Err(e) => fs::read(&path).map_err(|_| e)?,
+            }
+        };
+
+        Ok(format!("data:image/png;base64,{}", base64::engine::general_purpose::STANDARD.encode(bytes)))
+    }).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn offline_uuid_matches_vanilla() {
+        assert_eq!(offline_uuid("Notch"), "b50ad385-829d-3141-a216-7e7d7539ba7f");
+        // Case matters to the server, so it matters here too
+        assert_ne!(offline_uuid("notch"), offline_uuid("Notch"));
+    }
+
+    #[test]
+    fn usernames_are_3_to_16_word_characters() {
+        for name in ["Abc", "Steve", "dark_knight_2024", "___", "a1b2c3d4e5f6g7h8"] {
+            assert!(is_valid_username(name), "{} should be accepted", name);
+        }
+        for name in ["", "ab", "a_name_far_too_long", "con espacio", "guión-medio", "ñandú", "dot.name", "Stéve"] {
+            assert!(!is_valid_username(name), "{} should be rejected", name);
+        }
+    }
+
+    #[test]
+    fn plaintext_refresh_tokens_are_read_but_never_written() {
+        let legacy = r#"{"selectedAccount": "abc", "accounts": [{"id": "abc", "name": "Steve", "authType": "msa", "refreshToken": "M.R3_secret"}]}"#;
+        let accounts: AccountsFile = serde_json::from_str(legacy).unwrap();
+        assert_eq!(accounts.accounts[0].refresh_token.as_deref(), Some("M.R3_secret"));
+        let saved = serde_json::to_string(&accounts).unwrap();
+        assert!(!saved.contains("M.R3_secret"));
+        assert!(saved.contains("\"authType\":\"msa\""));
+    }
+}
@@ -0,0 +1,273 @@
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use sha1::{Digest, Sha1};
+use crate::minecraft::{curseforge, modpack, modrinth};
+
+/// Modrinth license ids that don't grant redistribution by themselves.
+const RESTRICTED_LICENSES: [&str; 2] = ["LicenseRef-All-Rights-Reserved", "ARR"];
+/// Modrinth license ids that say nothing useful; the license text has to be read.
+const UNCLEAR_LICENSES: [&str; 2] = ["LicenseRef-Unknown", "LicenseRef-Custom"];
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Origin {
+    Modrinth,
+    CurseForge,
+    Unknown,
+}
+
+/// Whether the pack may ship the file, as far as the platform metadata tells.
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Redistribution {
+    Allowed,
+    /// All rights reserved or opted out of third-party distribution: needs the author's permission
+    Restricted,
+    Unknown,
+}
+
+#[derive(Serialize, Clone, Debug, JsonSchema)]
+pub struct AuditEntry {
+    #[serde(rename = "fileName")]
+    pub file_name: String,
+    pub sha1: String,
+    pub origin: Origin,
+    #[serde(rename = "projectName", skip_serializing_if = "Option::is_none")]
+    pub project_name: Option<String>,
+    #[serde(rename = "projectUrl", skip_serializing_if = "Option::is_none")]
+    pub project_url: Option<String>,
+    #[serde(rename = "sourceUrl", skip_serializing_if = "Option::is_none")]
+    pub source_url: Option<String>,
+    /// License the platform lists for the project
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub license: Option<String>,
+    /// License written in the jar's own metadata
+    #[serde(rename = "declaredLicense", skip_serializing_if = "Option::is_none")]
+    pub declared_license: Option<String>,
+    pub redistribution: Redistribution,
+    /// What an organizer should look at, in Spanish for the UI
+    pub flags: Vec<String>,
+}
+
+#[derive(Serialize, Clone, Debug, JsonSchema)]
+pub struct ContentAudit {
+    #[serde(rename = "instanceId")]
+    pub instance_id: String,
+    #[serde(rename = "generatedAt")]
+    pub generated_at: u64,
+    pub entries: Vec<AuditEntry>,
+    /// Lookups that failed (no CurseForge key, platform down); affected files show as unknown
+    pub warnings: Vec<String>,
+}
+
+/// What the platforms said about one file.
+#[derive(Default, Clone, Debug)]
+pub struct PlatformMatch {
+    pub origin: Option<Origin>,
+    pub project_name: Option<String>,
+    pub project_url: Option<String>,
+    pub source_url: Option<String>,
+    pub license_id: Option<String>,
+    pub license_name: Option<String>,
+    /// CurseForge's `allowModDistribution`
+    pub allow_distribution: Option<bool>,
+}
+
+fn redistribution(found: &PlatformMatch) -> Redistribution {
+    match (found.origin, found.license_id.as_deref(), found.allow_distribution) {
+        (Some(Origin::Modrinth), Some(id), _) if RESTRICTED_LICENSES.contains(&id) => Redistribution::Restricted,
+        (Some(Origin::Modrinth), Some(id), _) if !UNCLEAR_LICENSES.contains(&id) => Redistribution::Allowed,
+        (Some(Origin::CurseForge), _, Some(false)) => Redistribution::Restricted,
+        (Some(Origin::CurseForge), _, Some(true)) => Redistribution::Allowed,
+        _ => Redistribution::Unknown,
+    }
+}
+
+/// Report line for a jar from what the platforms and its own metadata say.
+pub fn entry(file_name: &str, sha1: &str, found: PlatformMatch, declared_license: Option<String>) -> AuditEntry {
+    let origin = found.origin.unwrap_or(Origin::Unknown);
+    let redistribution = redistribution(&found);
+    let mut flags = Vec::new();
+    match origin {
+        Origin::Unknown => flags.push("Origen desconocido: ni Modrinth ni CurseForge reconocen este archivo".to_string()),
+        _ if found.source_url.is_none() => flags.push("Sin enlace al código fuente".to_string()),
+        _ => {}
+    }
+    match redistribution {
+        Redistribution::Restricted => flags.push("Redistribución no permitida sin permiso del autor".to_string()),
+        Redistribution::Unknown if origin != Origin::Unknown => flags.push("Revisar la licencia a mano".to_string()),
+        _ => {}
+    }
+    if origin == Origin::Unknown && declared_license.is_none() {
+        flags.push("El jar no declara licencia".to_string());
+    }
+    AuditEntry {
+        file_name: file_name.to_string(),
+        sha1: sha1.to_string(),
+        origin,
+        project_name: found.project_name,
+        project_url: found.project_url,
+        source_url: found.source_url,
+        license: found.license_name.or(found.license_id),
+        declared_license,
+        redistribution,
+        flags,
+    }
+}
+
+fn mod_jars(mods_dir: &Path) -> Vec<PathBuf> {
+    let mut jars: Vec<PathBuf> = fs::read_dir(mods_dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| p.is_file() && p.extension().map(|e| e.eq_ignore_ascii_case("jar")).unwrap_or(false))
+        .collect();
+    jars.sort();
+    jars
+}
+
+fn jar_license(path: &Path) -> Option<String> {
+    let mut archive = zip::ZipArchive::new(fs::File::open(path).ok()?).ok()?;
+    modpack::declared_license(&mut archive)
+}
+
+fn modrinth_matches(hashes: &[String]) -> Result<HashMap<String, PlatformMatch>, String> {
+    let versions = modrinth::versions_by_hash(hashes)?;
+    let mut project_ids: Vec<String> = versions.values().map(|v| v.project_id.clone()).collect();
+    project_ids.sort();
+    project_ids.dedup();
+    let projects: HashMap<String, modrinth::ModrinthProject> = modrinth::projects(&project_ids)?.into_iter().map(|p| (p.id.clone(), p)).collect();
+    Ok(versions.into_iter().map(|(sha1, version)| {
+        let project = projects.get(&version.project_id);
+        let found = PlatformMatch {
+            origin: Some(Origin::Modrinth),
+            project_name: project.map(|p| p.title.clone()),
+            project_url: project.map(|p| format!("https://modrinth.com/mod/{}", p.slug)),
+            source_url: project.and_then(|p| p.source_url.clone()),
+            license_id: project.and_then(|p| p.license.as_ref()).map(|l| l.id.clone()),
+            license_name: project.and_then(|p| p.license.as_ref()).map(|l| l.name.clone()).filter(|n| !n.is_empty()),
+            allow_distribution: None,
+        };
+        (sha1, found)
+    }).collect())
+}
+
+fn curseforge_matches(fingerprints: &[u32]) -> Result<HashMap<u32, PlatformMatch>, String> {
+    let files = curseforge::files_by_fingerprint(fingerprints)?;
+    let mut mod_ids: Vec<u64> = files.iter().map(|f| f.mod_id).collect();
+    mod_ids.sort();
+    mod_ids.dedup();
+    let mods: HashMap<u64, curseforge::CurseForgeMod> = curseforge::mods(&mod_ids)?.into_iter().map(|m| (m.id, m)).collect();
+    Ok(files.into_iter().map(|file| {
+        let project = mods.get(&file.mod_id);
+        let found = PlatformMatch {
+            origin: Some(Origin::CurseForge),
+            project_name: project.map(|m| m.name.clone()),
+            project_url: project.and_then(|m| m.links.website_url.clone()),
+            source_url: project.and_then(|m| m.links.source_url.clone()).filter(|u| !u.is_empty()),
+            license_id: None,
+            license_name: None,
+            allow_distribution: project.and_then(|m| m.allow_mod_distribution),
+        };
+        (file.file_fingerprint, found)
+    }).collect())
+}
+
+/// Looks every jar in `mods_dir` up on Modrinth (by SHA-1) and then CurseForge (by fingerprint).
+pub fn audit_mods(instance_id: &str, mods_dir: &Path) -> Result<ContentAudit, String> {
+    struct Jar {
+        name: String,
+        sha1: String,
+        fingerprint: u32,
+        declared_license: Option<String>,
+    }
+    let mut jars = Vec::new();
+    for path in mod_jars(mods_dir) {
+        let bytes = fs::read(&path).map_err(|e| format!("No se pudo leer {}: {}", path.display(), e))?;
+        jars.push(Jar {
+            name: path.file_name().unwrap_or_default().to_string_lossy().to_string(),
+            sha1: hex::encode(Sha1::digest(&bytes)),
+            fingerprint: curseforge::fingerprint(&bytes),
+            declared_license: jar_license(&path),
+        });
+    }
+
+    let mut warnings = Vec::new();
+    let hashes: Vec<String> = jars.iter().map(|j| j.sha1.clone()).collect();
+    let mut by_hash = modrinth_matches(&hashes).unwrap_or_else(|e| {
+        warnings.push(format!("Modrinth: {}", e));
+        HashMap::new()
+    });
+    // CurseForge only gets asked about what Modrinth didn't know
+    let fingerprints: Vec<u32> = jars.iter().filter(|j| !by_hash.contains_key(&j.sha1)).map(|j| j.fingerprint).collect();
+    let by_fingerprint = match (fingerprints.is_empty(), curseforge::has_api_key()) {
+        (true, _) => HashMap::new(),
+        (false, false) => {
+            warnings.push("CurseForge: falta la clave de API en las credenciales".to_string());
+            HashMap::new()
+        }
+        (false, true) => curseforge_matches(&fingerprints).unwrap_or_else(|e| {
+            warnings.push(format!("CurseForge: {}", e));
+            HashMap::new()
+        }),
+    };
+
+    let entries = jars.into_iter().map(|jar| {
+        let found = by_hash.remove(&jar.sha1).or_else(|| by_fingerprint.get(&jar.fingerprint).cloned()).unwrap_or_default();
+        entry(&jar.name, &jar.sha1, found, jar.declared_license)
+    }).collect();
+    Ok(ContentAudit {
+        instance_id: instance_id.to_string(),
+        generated_at: crate::unix_now(),
+        entries,
+        warnings,
+    })
+}
+
+/// Licenses and sources of every mod in an instance, so organizers can check redistribution
+/// rights before publishing the pack.
+#[tauri::command]
+pub async fn audit_instance_content(password: String, instance_id: String) -> Result<ContentAudit, String> {
+    crate::ensure_admin(password)?;
+    crate::run_blocking(move || {
+        let instance = crate::load_instances().into_iter().find(|i| i.id == instance_id)
+            .ok_or("Instance not found")?;
+        audit_mods(&instance_id, &Path::new(&instance.path).join("minecraft").join("mods"))
+    }).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn found(origin: Origin) -> PlatformMatch {
+        PlatformMatch { origin: Some(origin), project_name: Some("Mod".into()), source_url: Some("https://github.com/a/b".into()), ..Default::default() }
+    }
+
+    #[test]
+    fn entries_flag_unknown_origin_and_restricted_licenses() {
+        let open = entry("sodium.jar", "aa", PlatformMatch { license_id: Some("LGPL-3.0-only".into()), ..found(Origin::Modrinth) }, None);
+        assert_eq!(open.redistribution, Redistribution::Allowed);
+        assert!(open.flags.is_empty());
+
+        let arr = entry("arr.jar", "bb", PlatformMatch { license_id: Some("LicenseRef-All-Rights-Reserved".into()), ..found(Origin::Modrinth) }, None);
+        assert_eq!(arr.redistribution, Redistribution::Restricted);
+
+        let opted_out = entry("cf.jar", "cc", PlatformMatch { allow_distribution: Some(false), ..found(Origin::CurseForge) }, None);
+        assert_eq!(opted_out.redistribution, Redistribution::Restricted);
+        let unclear = entry("cf2.jar", "dd", found(Origin::CurseForge), None);
+        assert_eq!(unclear.redistribution, Redistribution::Unknown);
+        assert_eq!(unclear.flags, vec!["Revisar la licencia a mano"]);
+
+        let unknown = entry("custom.jar", "ee", PlatformMatch::default(), None);
+        assert_eq!(unknown.origin, Origin::Unknown);
+        assert_eq!(unknown.redistribution, Redistribution::Unknown);
+        assert_eq!(unknown.flags.len(), 2);
+        let declared = entry("custom.jar", "ee", PlatformMatch::default(), Some("MIT".into()));
+        assert_eq!(declared.flags.len(), 1);
+    }
+}
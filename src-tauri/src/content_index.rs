@@ -0,0 +1,342 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use crate::minecraft::downloader::file_sha1;
+
+const INDEX_VERSION: u32 = 1;
+/// This machine's own index, kept up to date by downloads and used to relink moved files.
+const LOCAL_INDEX_FILE: &str = "content-index.json";
+// Asset verification records thousands of files; the file is rewritten at most this often
+const SAVE_INTERVAL: Duration = Duration::from_secs(5);
+/// Top-level folders of the data directory that make up the installed content.
+const CONTENT_DIRS: [&str; 3] = ["assets", "libraries", crate::INSTANCES_SUBDIR];
+/// Files and folders inside instances that change with every session and would always drift.
+const VOLATILE_NAMES: [&str; 10] = [
+    "saves", "logs", "crash-reports", "screenshots", "options.txt", "optionsof.txt",
+    "servers.dat", "usercache.json", "usernamecache.json", "launcher_log.txt",
+];
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct IndexEntry {
+    pub sha1: String,
+    pub size: u64,
+}
+
+/// Hash of every content file, keyed by its path relative to the data directory (`/` separated).
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct ContentIndex {
+    pub version: u32,
+    #[serde(rename = "createdAt", alias = "created_at", default)]
+    pub created_at: u64,
+    pub files: BTreeMap<String, IndexEntry>,
+}
+
+/// Differences between this machine and a reference index.
+#[derive(Serialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct IndexDrift {
+    pub checked: usize,
+    /// In the reference, not on this machine
+    pub missing: Vec<String>,
+    /// Present on both with a different hash
+    pub changed: Vec<String>,
+    /// Only on this machine
+    pub extra: Vec<String>,
+}
+
+/// Index key of a file under the data directory: relative and `/` separated.
+fn relative_key(root: &Path, path: &Path) -> Option<String> {
+    let relative = path.strip_prefix(root).ok()?;
+    Some(relative.components().map(|c| c.as_os_str().to_string_lossy()).collect::<Vec<_>>().join("/"))
+}
+
+fn collect(root: &Path, dir: &Path, in_instances: bool, files: &mut BTreeMap<String, IndexEntry>) -> Result<(), String> {
+    let Ok(entries) = fs::read_dir(dir) else { return Ok(()); };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let name = entry.file_name().to_string_lossy().to_string();
+        if in_instances && VOLATILE_NAMES.contains(&name.as_str()) {
+            continue;
+        }
+        let Ok(file_type) = entry.file_type() else { continue; };
+        if file_type.is_dir() {
+            collect(root, &path, in_instances, files)?;
+        } else if file_type.is_file() {
+            let key = relative_key(root, &path).ok_or_else(|| format!("{} is outside {}", path.display(), root.display()))?;
+            let sha1 = file_sha1(&path).map_err(|e| format!("Failed to hash {}: {}", key, e))?;
+            let size = entry.metadata().map(|m| m.len()).unwrap_or(0);
+            files.insert(key, IndexEntry { sha1, size });
+        }
+    }
+    Ok(())
+}
+
+pub fn build_index(root: &Path) -> Result<ContentIndex, String> {
+    let mut files = BTreeMap::new();
+    for dir in CONTENT_DIRS {
+        collect(root, &root.join(dir), dir == crate::INSTANCES_SUBDIR, &mut files)?;
+    }
+    Ok(ContentIndex { version: INDEX_VERSION, created_at: crate::unix_now(), files })
+}
+
+pub fn compare(expected: &ContentIndex, actual: &ContentIndex) -> IndexDrift {
+    let mut drift = IndexDrift { checked: expected.files.len(), ..Default::default() };
+    for (path, entry) in &expected.files {
+        match actual.files.get(path) {
+            None => drift.missing.push(path.clone()),
+            Some(local) if !local.sha1.eq_ignore_ascii_case(&entry.sha1) => drift.changed.push(path.clone()),
+            Some(_) => {}
+        }
+    }
+    drift.extra = actual.files.keys().filter(|p| !expected.files.contains_key(*p)).cloned().collect();
+    drift
+}
+
+struct LocalIndex {
+    root: PathBuf,
+    index: ContentIndex,
+    /// Lowercase sha1 to the last path recorded with it
+    by_hash: HashMap<String, String>,
+    last_save: Option<Instant>,
+}
+
+impl LocalIndex {
+    fn new(root: &Path, index: ContentIndex) -> Self {
+        let by_hash = index.files.iter().map(|(path, entry)| (entry.sha1.to_lowercase(), path.clone())).collect();
+        LocalIndex { root: root.to_path_buf(), index, by_hash, last_save: None }
+    }
+
+    fn load(root: &Path) -> Self {
+        let index = fs::read_to_string(root.join(LOCAL_INDEX_FILE))
+            .ok()
+            .and_then(|json| serde_json::from_str::<ContentIndex>(&json).ok())
+            .filter(|index| index.version <= INDEX_VERSION)
+            .unwrap_or_else(|| ContentIndex { version: INDEX_VERSION, ..Default::default() });
+        LocalIndex::new(root, index)
+    }
+
+    fn insert(&mut self, key: String, sha1: &str, size: u64) {
+        let sha1 = sha1.to_lowercase();
+        self.by_hash.insert(sha1.clone(), key.clone());
+        self.index.files.insert(key, IndexEntry { sha1, size });
+    }
+
+    fn save_throttled(&mut self) {
+        if self.last_save.map(|t| t.elapsed() < SAVE_INTERVAL).unwrap_or(false) {
+            return;
+        }
+        self.last_save = Some(Instant::now());
+        if let Ok(json) = serde_json::to_string(&self.index) {
+            let _ = fs::write(self.root.join(LOCAL_INDEX_FILE), json);
+        }
+    }
+}
+
+static LOCAL: Mutex<Option<LocalIndex>> = Mutex::new(None);
+
+fn with_local<T>(root: &Path, f: impl FnOnce(&mut LocalIndex) -> T) -> T {
+    let mut guard = LOCAL.lock().unwrap_or_else(|e| e.into_inner());
+    if guard.as_ref().map(|local| local.root != root).unwrap_or(true) {
+        *guard = Some(LocalIndex::load(root));
+    }
+    f(guard.as_mut().expect("local index loaded above"))
+}
+
+/// Replaces the local index with a freshly built one, e.g. after a full scan.
+fn replace_local(root: &Path, index: &ContentIndex) {
+    with_local(root, |local| {
+        *local = LocalIndex::new(root, index.clone());
+        local.save_throttled();
+    });
+}
+
+/// Remembers a verified file so later downloads of the same content can link to it.
+pub fn record(path: &Path, sha1: &str) {
+    if crate::sandbox::contains(path) {
+        return;
+    }
+    let root = crate::data_dir::root();
+    let Some(key) = relative_key(&root, path) else { return };
+    let size = fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+    with_local(&root, |local| {
+        local.insert(key, sha1, size);
+        local.save_throttled();
+    });
+}
+
+/// Whether `path` still has the size and hash recorded when it was downloaded. Files that were
+/// never recorded (installed before the index, or in a sandbox) stand.
+pub fn unchanged_in(root: &Path, path: &Path) -> bool {
+    let Some(key) = relative_key(root, path) else { return true };
+    let Some(entry) = with_local(root, |local| local.index.files.get(&key).cloned()) else { return true };
+    fs::metadata(path).is_ok_and(|m| m.len() == entry.size) && file_sha1(path).is_ok_and(|sha1| sha1.eq_ignore_ascii_case(&entry.sha1))
+}
+
+pub fn unchanged(path: &Path) -> bool {
+    unchanged_in(&crate::data_dir::root(), path)
+}
+
+/// Hard links (or copies, across drives) a file with the same content to `target`.
+fn link_or_copy(source: &Path, target: &Path) -> bool {
+    if let Some(parent) = target.parent() {
+        if fs::create_dir_all(parent).is_err() {
+            return false;
+        }
+    }
+    let _ = fs::remove_file(target);
+    fs::hard_link(source, target).is_ok() || fs::copy(source, target).is_ok()
+}
+
+/// Puts a file with `sha1` at `target` from a copy already on disk, when the local index knows
+/// one: files survive a data directory move or a changed library layout without a download.
+/// The source is re-hashed first; an index entry that no longer matches is dropped.
+pub fn relink_in(root: &Path, target: &Path, sha1: &str) -> bool {
+    let sha1 = sha1.to_lowercase();
+    let Some(key) = with_local(root, |local| local.by_hash.get(&sha1).cloned()) else { return false };
+    let source: PathBuf = key.split('/').fold(root.to_path_buf(), |path, part| path.join(part));
+    let valid = source != target && file_sha1(&source).map(|actual| actual == sha1).unwrap_or(false);
+    if !valid || !link_or_copy(&source, target) {
+        if !valid {
+            with_local(root, |local| {
+                local.by_hash.remove(&sha1);
+                local.index.files.remove(&key);
+            });
+        }
+        return false;
+    }
+    if let Some(target_key) = relative_key(root, target) {
+        let size = fs::metadata(target).map(|m| m.len()).unwrap_or(0);
+        with_local(root, |local| {
+            local.insert(target_key, &sha1, size);
+            local.save_throttled();
+        });
+    }
+    true
+}
+
+pub fn relink(target: &Path, sha1: &str) -> bool {
+    // A hard link would let a sandbox's changes reach the files players launch with
+    if crate::sandbox::contains(target) {
+        return false;
+    }
+    relink_in(&crate::data_dir::root(), target, sha1)
+}
+
+/// Writes the hash index of this machine's assets, libraries and instances to `file`.
+#[tauri::command]
+pub async fn export_content_index(password: String, file: String) -> Result<usize, String> {
+    crate::run_blocking(move || {
+        crate::ensure_admin(password)?;
+        let root = crate::data_dir::root();
+        let index = build_index(&root)?;
+        replace_local(&root, &index);
+        let json = serde_json::to_string_pretty(&index).map_err(|e| e.to_string())?;
+        fs::write(&file, json).map_err(|e| format!("No se pudo escribir {}: {}", file, e))?;
+        Ok(index.files.len())
+    }).await
+}
+
+/// Checks this machine against an index exported from the reference PC.
+#[tauri::command]
+pub async fn verify_against_index(password: String, file: String) -> Result<IndexDrift, String> {
+    crate::run_blocking(move || {
+        crate::ensure_admin(password)?;
+        let json = fs::read_to_string(&file).map_err(|e| format!("No se pudo leer {}: {}", file, e))?;
+        let expected: ContentIndex = serde_json::from_str(&json).map_err(|e| format!("Índice no válido: {}", e))?;
+        if expected.version > INDEX_VERSION {
+            return Err(format!("Índice creado por una versión más nueva del launcher (v{})", expected.version));
+        }
+        let root = crate::data_dir::root();
+        let actual = build_index(&root)?;
+        replace_local(&root, &actual);
+        Ok(compare(&expected, &actual))
+    }).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn index_skips_volatile_instance_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let instance = dir.path().join(crate::INSTANCES_SUBDIR).join("Evento").join("minecraft");
+        fs::create_dir_all(instance.join("mods")).unwrap();
+        fs::create_dir_all(instance.join("saves/world")).unwrap();
+        fs::write(instance.join("mods/a.jar"), b"mod").unwrap();
+        fs::write(instance.join("options.txt"), b"fov:90").unwrap();
+        fs::write(instance.join("saves/world/level.dat"), b"x").unwrap();
+        fs::create_dir_all(dir.path().join("libraries/org")).unwrap();
+        fs::write(dir.path().join("libraries/org/lib.jar"), b"lib").unwrap();
+
+        let index = build_index(dir.path()).unwrap();
+        let keys = index.files.keys().cloned().collect::<Vec<_>>();
+        assert_eq!(keys, vec![
+            format!("{}/Evento/minecraft/mods/a.jar", crate::INSTANCES_SUBDIR),
+            "libraries/org/lib.jar".to_string(),
+        ]);
+        assert_eq!(index.files["libraries/org/lib.jar"].size, 3);
+    }
+
+    #[test]
+    fn moved_files_are_relinked_by_hash() {
+        let dir = tempfile::tempdir().unwrap();
+        let old = dir.path().join("old/org/lib.jar");
+        fs::create_dir_all(old.parent().unwrap()).unwrap();
+        fs::write(&old, b"library").unwrap();
+        let sha1 = file_sha1(&old).unwrap();
+        with_local(dir.path(), |local| local.insert("old/org/lib.jar".into(), &sha1, 7));
+
+        let moved = dir.path().join("libraries/org/lib.jar");
+        assert!(relink_in(dir.path(), &moved, &sha1.to_uppercase()));
+        assert_eq!(fs::read(&moved).unwrap(), b"library");
+
+        // A source whose content changed is not trusted and leaves the index
+        fs::write(&old, b"changed").unwrap();
+        fs::remove_file(&moved).unwrap();
+        with_local(dir.path(), |local| local.insert("old/org/lib.jar".into(), &sha1, 7));
+        assert!(!relink_in(dir.path(), &moved, &sha1));
+        assert!(!moved.exists());
+    }
+
+    #[test]
+    fn files_are_unchanged_while_size_and_hash_match_the_record() {
+        let dir = tempfile::tempdir().unwrap();
+        let jar = dir.path().join("instancias/Evento/minecraft/mods/a.jar");
+        fs::create_dir_all(jar.parent().unwrap()).unwrap();
+        fs::write(&jar, b"mod v1").unwrap();
+        assert!(unchanged_in(dir.path(), &jar));
+        let sha1 = file_sha1(&jar).unwrap();
+        with_local(dir.path(), |local| local.insert("instancias/Evento/minecraft/mods/a.jar".into(), &sha1, 6));
+        assert!(unchanged_in(dir.path(), &jar));
+        // Same size, other content
+        fs::write(&jar, b"mod v2").unwrap();
+        assert!(!unchanged_in(dir.path(), &jar));
+        fs::write(&jar, b"mod").unwrap();
+        assert!(!unchanged_in(dir.path(), &jar));
+    }
+
+    #[test]
+    fn drift_reports_missing_changed_and_extra() {
+        let entry = |sha1: &str| IndexEntry { sha1: sha1.to_string(), size: 1 };
+        let mut expected = ContentIndex::default();
+        expected.files.insert("a".into(), entry("11"));
+        expected.files.insert("b".into(), entry("22"));
+        expected.files.insert("c".into(), entry("AA"));
+        let mut actual = ContentIndex::default();
+        actual.files.insert("b".into(), entry("23"));
+        actual.files.insert("c".into(), entry("aa"));
+        actual.files.insert("d".into(), entry("44"));
+
+        let drift = compare(&expected, &actual);
+        assert_eq!(drift, IndexDrift {
+            checked: 3,
+            missing: vec!["a".into()],
+            changed: vec!["b".into()],
+            extra: vec!["d".into()],
+        });
+    }
+}
@@ -0,0 +1,202 @@
+use std::fs;
+use std::path::Path;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use crate::minecraft::loader_log;
+use crate::optimize::{add_modrinth_mods, performance_mods_for};
+
+// Pauses above this freeze the game visibly; a few of them point at a heap too small or too busy
+const LONG_GC_PAUSE_MS: f64 = 500.0;
+const LONG_GC_PAUSES: usize = 3;
+// "Can't keep up!" from the integrated server is the usual symptom of GC stalls without GC logs
+const CANT_KEEP_UP_LINES: usize = 5;
+const RAM_STEP_MB: u64 = 512;
+// Left to the OS and the launcher when raising an instance's heap
+const OS_HEADROOM_MB: u64 = 2048;
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum CrashCause {
+    OutOfMemory,
+    LongGcPauses,
+    /// The launch never got to the game: the loader installer failed
+    LoaderInstallFailed,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum SuggestionAction {
+    RaiseRam {
+        #[serde(rename = "ramMb")]
+        ram_mb: u64,
+    },
+    InstallMods { mods: Vec<String> },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Suggestion {
+    pub title: String,
+    pub description: String,
+    pub action: SuggestionAction,
+}
+
+#[derive(Serialize, Clone, Debug, Default, JsonSchema)]
+pub struct CrashAnalysis {
+    pub causes: Vec<CrashCause>,
+    pub suggestions: Vec<Suggestion>,
+    /// Full installer output when the cause is `LoaderInstallFailed`
+    #[serde(rename = "loaderLog", skip_serializing_if = "Option::is_none")]
+    pub loader_log: Option<String>,
+    /// After several crashes in a row, or when a safe mode launch crashed too
+    #[serde(rename = "safeMode", skip_serializing_if = "Option::is_none")]
+    pub safe_mode: Option<crate::safe_mode::SafeModeOffer>,
+    /// The last crash's exit code in words, when it's one that says something
+    #[serde(rename = "exitCode", skip_serializing_if = "Option::is_none")]
+    pub exit_code: Option<crate::exit_codes::ExitCodeInfo>,
+}
+
+/// Milliseconds of a unified JVM GC log pause line: `[12.3s][info][gc] GC(4) Pause Full ... 1234.567ms`.
+fn gc_pause_ms(line: &str) -> Option<f64> {
+    if !line.contains("Pause") {
+        return None;
+    }
+    line.trim_end().strip_suffix("ms")?.rsplit(' ').next()?.parse().ok()
+}
+
+pub fn detect_causes(log: &str) -> Vec<CrashCause> {
+    let mut causes = Vec::new();
+    if log.contains("java.lang.OutOfMemoryError") || log.contains("GC overhead limit exceeded") {
+        causes.push(CrashCause::OutOfMemory);
+    }
+    let long_pauses = log.lines().filter_map(gc_pause_ms).filter(|ms| *ms >= LONG_GC_PAUSE_MS).count();
+    let cant_keep_up = log.lines().filter(|l| l.contains("Can't keep up!")).count();
+    if long_pauses >= LONG_GC_PAUSES || cant_keep_up >= CANT_KEEP_UP_LINES {
+        causes.push(CrashCause::LongGcPauses);
+    }
+    causes
+}
+
+/// Half again the current heap in 512 MB steps, without eating into the OS headroom. `None`
+/// when the machine has no room for more.
+pub fn raised_ram_mb(current_mb: u64, max_ram_mb: u64) -> Option<u64> {
+    let ceiling = max_ram_mb.saturating_sub(OS_HEADROOM_MB).max(current_mb.min(max_ram_mb));
+    let target = (current_mb * 3 / 2).div_ceil(RAM_STEP_MB) * RAM_STEP_MB;
+    let target = target.min(ceiling / RAM_STEP_MB * RAM_STEP_MB);
+    (target > current_mb).then_some(target)
+}
+
+pub fn suggestions_for(causes: &[CrashCause], current_ram_mb: u64, max_ram_mb: u64, loader: &str) -> Vec<Suggestion> {
+    let mut suggestions = Vec::new();
+    // More memory or performance mods do nothing for a failed loader install
+    if !causes.iter().any(|c| matches!(c, CrashCause::OutOfMemory | CrashCause::LongGcPauses)) {
+        return suggestions;
+    }
+    if let Some(ram_mb) = raised_ram_mb(current_ram_mb, max_ram_mb) {
+        suggestions.push(Suggestion {
+            title: format!("Subir la RAM a {} MB", ram_mb),
+            description: if causes.contains(&CrashCause::OutOfMemory) {
+                format!("El juego se quedó sin memoria con {} MB asignados.", current_ram_mb)
+            } else {
+                format!("Con {} MB el recolector de memoria congela el juego durante largos periodos.", current_ram_mb)
+            },
+            action: SuggestionAction::RaiseRam { ram_mb },
+        });
+    }
+    let mods = performance_mods_for(loader);
+    if !mods.is_empty() {
+        suggestions.push(Suggestion {
+            title: format!("Instalar {}", mods.join(", ")),
+            description: "Mods de rendimiento que reducen el uso de memoria y los tirones.".to_string(),
+            action: SuggestionAction::InstallMods { mods: mods.iter().map(|m| m.to_string()).collect() },
+        });
+    }
+    suggestions
+}
+
+pub fn read_logs(instance_path: &Path) -> String {
+    ["latest.log", "latest_err.log"].iter()
+        .filter_map(|name| fs::read_to_string(instance_path.join("logs").join(name)).ok())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Looks for memory problems in the last session's logs and what to do about them, or for a
+/// loader install that failed before the game could start, and explains the exit code.
+#[tauri::command]
+pub fn analyze_crash(instance_id: String) -> Result<CrashAnalysis, String> {
+    let instance = crate::load_instances().into_iter().find(|i| i.id == instance_id)
+        .ok_or("Instance not found")?;
+    let instance_path = Path::new(&instance.path);
+    let install_log = loader_log::failed_install(&loader_log::log_dir(), &instance_id, &instance_path.join("logs").join("latest.log"));
+    let causes = match install_log {
+        // The game logs are from an earlier session
+        Some(_) => vec![CrashCause::LoaderInstallFailed],
+        None => detect_causes(&read_logs(instance_path)),
+    };
+    let loader = instance.modloader.as_deref().unwrap_or("vanilla");
+    let max_ram_mb = crate::platform::get_platform_info().max_ram_mb;
+    let suggestions = suggestions_for(&causes, instance.ram.unwrap_or_else(|| crate::platform::default_ram_for(Some(loader))), max_ram_mb, loader);
+    let loader_log = install_log.and_then(|path| fs::read_to_string(path).ok());
+    let safe_mode = crate::safe_mode::offer(&instance);
+    let exit_code = crate::launch_history::last_attempt(&instance_id)
+        .filter(|attempt| attempt.outcome == crate::launch_history::Outcome::Crashed)
+        .and_then(|attempt| attempt.error_code)
+        .and_then(crate::exit_codes::decode);
+    Ok(CrashAnalysis { causes, suggestions, loader_log, safe_mode, exit_code })
+}
+
+/// One-click handler for a suggestion from `analyze_crash`. Returns warnings for parts that failed.
+#[tauri::command]
+pub async fn apply_crash_suggestion(instance_id: String, suggestion: Suggestion) -> Result<Vec<String>, String> {
+    crate::settings::ensure_not_guest()?;
+    crate::run_blocking(move || {
+        let mut instances = crate::load_instances();
+        let instance = instances.iter_mut().find(|i| i.id == instance_id)
+            .ok_or("Instance not found")?;
+        let warnings = match suggestion.action {
+            SuggestionAction::RaiseRam { ram_mb } => {
+                instance.ram = Some(crate::platform::clamp_ram_mb(ram_mb));
+                Vec::new()
+            }
+            SuggestionAction::InstallMods { mods } => {
+                let slugs = mods.iter().map(String::as_str).collect::<Vec<_>>();
+                add_modrinth_mods(instance, &slugs).1
+            }
+        };
+        crate::save_instances(&instances);
+        Ok(warnings)
+    }).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn memory_problems_are_found_in_logs() {
+        assert_eq!(detect_causes("Exception in thread \"Render thread\" java.lang.OutOfMemoryError: Java heap space"), vec![CrashCause::OutOfMemory]);
+        let gc = "[10.0s][info][gc] GC(7) Pause Full (G1 Compaction Pause) 3900M->3800M(4096M) 812.402ms\n".repeat(3);
+        assert_eq!(detect_causes(&gc), vec![CrashCause::LongGcPauses]);
+        let short = "[10.0s][info][gc] GC(7) Pause Young (Normal) 900M->300M(4096M) 12.402ms\n".repeat(10);
+        assert!(detect_causes(&short).is_empty());
+        assert!(detect_causes("[Server thread/WARN]: Can't keep up! Is the server overloaded?").is_empty());
+    }
+
+    #[test]
+    fn ram_is_raised_within_the_machine() {
+        assert_eq!(raised_ram_mb(4096, 16384), Some(6144));
+        assert_eq!(raised_ram_mb(4096, 7168), Some(5120));
+        assert_eq!(raised_ram_mb(4096, 4096), None);
+        assert_eq!(raised_ram_mb(1000, 8192), Some(1536));
+    }
+
+    #[test]
+    fn suggestions_only_follow_a_detected_cause() {
+        assert!(suggestions_for(&[], 2048, 16384, "fabric").is_empty());
+        let suggestions = suggestions_for(&[CrashCause::OutOfMemory], 2048, 16384, "fabric");
+        assert_eq!(suggestions[0].action, SuggestionAction::RaiseRam { ram_mb: 3072 });
+        assert!(matches!(&suggestions[1].action, SuggestionAction::InstallMods { mods } if mods.contains(&"sodium".to_string())));
+        assert_eq!(suggestions_for(&[CrashCause::LongGcPauses], 2048, 16384, "vanilla").len(), 1);
+        assert!(suggestions_for(&[CrashCause::LoaderInstallFailed], 2048, 16384, "forge").is_empty());
+    }
+}
@@ -0,0 +1,181 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use crate::settings::{ensure_not_guest, load_settings, save_settings};
+
+// Upload servers cap request bodies; the end of the log is where the crash is
+const MAX_LOG_BYTES: usize = 1024 * 1024;
+// The `crasheado` event waits for the upload, so a slow endpoint mustn't hold it long
+const UPLOAD_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Sending logs after a crash: the player opts in, staff set where they go.
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct CrashUploadSettings {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Receives the JSON from `payload` and answers `{"url": "..."}` or the link as plain text
+    #[serde(default)]
+    pub endpoint: Option<String>,
+}
+
+/// The last `max` bytes of `text`, cut at a line start when there is one nearby.
+fn tail(text: &str, max: usize) -> &str {
+    if text.len() <= max {
+        return text;
+    }
+    let mut start = text.len() - max;
+    while !text.is_char_boundary(start) {
+        start += 1;
+    }
+    let rest = &text[start..];
+    match rest.find('\n') {
+        Some(i) if i < 1024 => &rest[i + 1..],
+        _ => rest,
+    }
+}
+
+/// Hides the session token and the user's home folder (which holds their account name).
+pub fn redact(text: &str, home: Option<&str>) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(i) = rest.find("--accessToken") {
+        let after = &rest[i + "--accessToken".len()..];
+        let spaces = after.len() - after.trim_start_matches([' ', '=']).len();
+        let token_len = after[spaces..].find(char::is_whitespace).unwrap_or(after.len() - spaces);
+        out.push_str(&rest[..i]);
+        out.push_str("--accessToken [oculto]");
+        rest = &after[spaces + token_len..];
+    }
+    out.push_str(rest);
+    match home.filter(|h| h.len() > 3) {
+        Some(home) => out.replace(home, "~"),
+        None => out,
+    }
+}
+
+fn home_dir() -> Option<String> {
+    std::env::var(if cfg!(windows) { "USERPROFILE" } else { "HOME" }).ok()
+}
+
+/// Crash report the game wrote during the session that started at `since`.
+pub fn session_crash_report(instance_path: &Path, since: SystemTime) -> Option<PathBuf> {
+    crate::diagnostics::newest_files(&crate::diagnostics::crash_reports_dir(instance_path), 1)
+        .into_iter()
+        .find(|path| fs::metadata(path).and_then(|m| m.modified()).map(|m| m >= since).unwrap_or(false))
+}
+
+/// The game's own `latest.log`, or what the launcher captured from its output.
+fn latest_log(instance_path: &Path) -> Option<String> {
+    fs::read_to_string(instance_path.join("minecraft").join("logs").join("latest.log"))
+        .or_else(|_| fs::read_to_string(instance_path.join("logs").join("latest.log")))
+        .ok()
+}
+
+pub fn payload(instance_id: &str, instance_name: &str, exit_code: i32, crash_report: Option<(String, String)>, latest_log: Option<String>, safe_mode: Option<serde_json::Value>, home: Option<&str>) -> serde_json::Value {
+    serde_json::json!({
+        "instanceId": instance_id,
+        "instanceName": instance_name,
+        "exitCode": exit_code,
+        // Name and likely culprit (driver, memory, mods...) of Windows and signal codes
+        "exitCodeInfo": crate::exit_codes::decode(exit_code),
+        "launcherVersion": env!("CARGO_PKG_VERSION"),
+        "crashReport": crash_report.map(|(name, content)| serde_json::json!({ "name": name, "content": redact(&content, home) })),
+        "latestLog": latest_log.map(|log| redact(tail(&log, MAX_LOG_BYTES), home)),
+        // Which safe mode steps still crashed and which one last ran cleanly
+        "safeMode": safe_mode,
+    })
+}
+
+fn upload(endpoint: &str, payload: &serde_json::Value) -> Result<String, String> {
+    let client = reqwest::blocking::Client::builder()
+        .user_agent(crate::http::user_agent())
+        .timeout(UPLOAD_TIMEOUT)
+        .build()
+        .map_err(|e| e.to_string())?;
+    let resp = crate::http::send(&client, client.post(endpoint).json(payload))?;
+    if !resp.status().is_success() {
+        return Err(format!("El servidor de registros respondió {}", resp.status()));
+    }
+    let body = resp.text().map_err(|e| e.to_string())?;
+    let url = serde_json::from_str::<serde_json::Value>(&body).ok()
+        .and_then(|json| json["url"].as_str().map(str::to_string))
+        .unwrap_or_else(|| body.trim().to_string());
+    if !url.starts_with("http://") && !url.starts_with("https://") {
+        return Err("El servidor de registros no devolvió un enlace".to_string());
+    }
+    Ok(url)
+}
+
+/// Uploads the crash report and log of a session that crashed, when the player opted in.
+/// Returns the link to share; failures only go to the console.
+pub fn upload_after_crash(instance_id: &str, instance_name: &str, instance_path: &Path, since: SystemTime, exit_code: i32) -> Option<String> {
+    let settings = load_settings().crash_upload;
+    let endpoint = settings.endpoint.filter(|e| settings.enabled && !e.trim().is_empty())?;
+    let crash_report = session_crash_report(instance_path, since).and_then(|path| {
+        let name = path.file_name()?.to_string_lossy().to_string();
+        Some((name, fs::read_to_string(&path).ok()?))
+    });
+    let home = home_dir();
+    let body = payload(instance_id, instance_name, exit_code, crash_report, latest_log(instance_path), crate::safe_mode::report(instance_id), home.as_deref());
+    match upload(&endpoint, &body) {
+        Ok(url) => Some(url),
+        Err(e) => {
+            eprintln!("[crash_upload] Upload failed: {}", e);
+            None
+        }
+    }
+}
+
+/// Whether the player opted in and staff configured somewhere to send the logs.
+#[tauri::command]
+pub fn get_crash_upload() -> serde_json::Value {
+    let settings = load_settings().crash_upload;
+    serde_json::json!({
+        "enabled": settings.enabled,
+        "available": settings.endpoint.map(|e| !e.trim().is_empty()).unwrap_or(false),
+    })
+}
+
+#[tauri::command]
+pub fn set_crash_upload(enabled: bool) -> Result<(), String> {
+    ensure_not_guest()?;
+    let mut settings = load_settings();
+    settings.crash_upload.enabled = enabled;
+    save_settings(&settings)
+}
+
+#[tauri::command]
+pub fn set_crash_upload_endpoint(password: String, endpoint: Option<String>) -> Result<(), String> {
+    crate::ensure_admin(password)?;
+    let mut settings = load_settings();
+    settings.crash_upload.endpoint = endpoint.filter(|e| !e.trim().is_empty());
+    save_settings(&settings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn uploads_hide_tokens_and_the_home_folder() {
+        let log = "[main] args: --username Drk --accessToken eyJhbGciOi.abc --version 1.20.1\n\
+                   Loading C:\\Users\\Harold\\AppData\\Roaming\\Eventos DRK\\libraries\n";
+        let redacted = redact(log, Some("C:\\Users\\Harold"));
+        assert!(redacted.contains("--accessToken [oculto] --version 1.20.1"));
+        assert!(redacted.contains("Loading ~\\AppData"));
+        assert!(!redacted.contains("eyJhbGciOi") && !redacted.contains("Harold"));
+        assert_eq!(redact("--accessToken", None), "--accessToken [oculto]");
+    }
+
+    #[test]
+    fn long_logs_keep_their_end() {
+        let log = format!("{}\nCaused by: java.lang.OutOfMemoryError\n", "línea\n".repeat(400_000));
+        let body = payload("evento", "Evento", 1, Some(("crash.txt".into(), "report".into())), Some(log), None, None);
+        let sent = body["latestLog"].as_str().unwrap();
+        assert!(sent.len() <= MAX_LOG_BYTES);
+        assert!(sent.starts_with("línea"));
+        assert!(sent.ends_with("Caused by: java.lang.OutOfMemoryError\n"));
+        assert_eq!(body["crashReport"]["name"], "crash.txt");
+    }
+}
@@ -0,0 +1,208 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use schemars::JsonSchema;
+use serde::Serialize;
+
+/// Presence of this file next to the executable turns on portable mode (same as `--portable`).
+pub const PORTABLE_MARKER: &str = "portable.txt";
+const PORTABLE_FLAG: &str = "--portable";
+const DATA_DIR_NAME: &str = "Eventos DRK";
+
+static DATA_ROOT: OnceLock<(PathBuf, bool)> = OnceLock::new();
+
+/// `$XDG_DATA_HOME`, or `~/.local/share` when it is unset or relative (the spec says to ignore
+/// relative values). Flatpak points XDG_DATA_HOME into the sandbox's `~/.var/app/<id>/data`.
+pub fn xdg_data_home(xdg_data_home: Option<&str>, home: Option<&str>) -> PathBuf {
+    match xdg_data_home.filter(|v| Path::new(v).is_absolute()) {
+        Some(dir) => PathBuf::from(dir),
+        None => PathBuf::from(home.unwrap_or("")).join(".local").join("share"),
+    }
+}
+
+/// Per-user data directory of the platform.
+pub fn system_data_home() -> PathBuf {
+    #[cfg(target_os = "windows")]
+    {
+        PathBuf::from(std::env::var("APPDATA").unwrap_or_default())
+    }
+    #[cfg(target_os = "linux")]
+    {
+        xdg_data_home(std::env::var("XDG_DATA_HOME").ok().as_deref(), std::env::var("HOME").ok().as_deref())
+    }
+    #[cfg(target_os = "macos")]
+    {
+        let home = std::env::var("HOME").unwrap_or_default();
+        PathBuf::from(home).join("Library").join("Application Support")
+    }
+    #[cfg(not(any(target_os = "windows", target_os = "linux", target_os = "macos")))]
+    {
+        std::env::current_dir().unwrap_or_default()
+    }
+}
+
+/// Directory holding the executable; for AppImages the directory of the image itself, not the
+/// read-only mount it runs from.
+pub fn exe_dir() -> Option<PathBuf> {
+    if let Some(appimage) = std::env::var_os("APPIMAGE") {
+        return PathBuf::from(appimage).parent().map(Path::to_path_buf);
+    }
+    std::env::current_exe().ok()?.parent().map(Path::to_path_buf)
+}
+
+pub fn portable_requested(args: &[String], exe_dir: Option<&Path>) -> bool {
+    args.iter().any(|a| a == PORTABLE_FLAG) || exe_dir.map(|d| d.join(PORTABLE_MARKER).is_file()).unwrap_or(false)
+}
+
+fn portable_root(exe_dir: &Path) -> PathBuf {
+    exe_dir.join(DATA_DIR_NAME)
+}
+
+fn system_root() -> PathBuf {
+    system_data_home().join(DATA_DIR_NAME)
+}
+
+/// Picks the data root once per process. Called at startup; later calls return the same value.
+pub fn init(args: &[String]) -> &'static (PathBuf, bool) {
+    DATA_ROOT.get_or_init(|| {
+        let exe_dir = exe_dir();
+        match exe_dir.as_deref().filter(|d| portable_requested(args, Some(d))) {
+            Some(dir) => (portable_root(dir), true),
+            None => (system_root(), false),
+        }
+    })
+}
+
+/// Root of every launcher file (instances.json, settings, assets, instances).
+pub fn root() -> PathBuf {
+    init(&std::env::args().collect::<Vec<_>>()).0.clone()
+}
+
+pub fn is_portable() -> bool {
+    init(&std::env::args().collect::<Vec<_>>()).1
+}
+
+pub fn copy_tree(from: &Path, to: &Path) -> Result<u64, String> {
+    fs::create_dir_all(to).map_err(|e| format!("Failed to create {}: {}", to.display(), e))?;
+    let mut copied = 0;
+    for entry in fs::read_dir(from).map_err(|e| e.to_string())?.flatten() {
+        let target = to.join(entry.file_name());
+        let file_type = entry.file_type().map_err(|e| e.to_string())?;
+        if file_type.is_dir() {
+            copied += copy_tree(&entry.path(), &target)?;
+        } else if file_type.is_file() {
+            copied += fs::copy(entry.path(), &target).map_err(|e| format!("Failed to copy {}: {}", entry.path().display(), e))?;
+        }
+    }
+    Ok(copied)
+}
+
+/// Rewrites absolute instance paths in `instances.json` from one root to the other.
+pub fn rebase_instance_paths(json: &str, from: &Path, to: &Path) -> Result<String, String> {
+    let mut instances: Vec<serde_json::Value> = serde_json::from_str(json).map_err(|e| e.to_string())?;
+    for instance in &mut instances {
+        let Some(path) = instance.get("path").and_then(|p| p.as_str()).map(PathBuf::from) else { continue; };
+        if let Ok(rest) = path.strip_prefix(from) {
+            instance["path"] = serde_json::Value::String(to.join(rest).to_string_lossy().to_string());
+        }
+    }
+    serde_json::to_string_pretty(&instances).map_err(|e| e.to_string())
+}
+
+/// Copies the data root to the other mode's location, fixes instance paths and flips the portable
+/// marker. The old copy is left in place; the launcher must be restarted to use the new one.
+pub fn migrate(from: &Path, to: &Path, exe_dir: &Path, to_portable: bool) -> Result<u64, String> {
+    if to.join("instances.json").exists() {
+        return Err(format!("Ya existen datos en {}", to.display()));
+    }
+    let copied = if from.exists() { copy_tree(from, to)? } else { 0 };
+    let instances_file = to.join("instances.json");
+    if let Ok(json) = fs::read_to_string(&instances_file) {
+        fs::write(&instances_file, rebase_instance_paths(&json, from, to)?).map_err(|e| e.to_string())?;
+    }
+    let marker = exe_dir.join(PORTABLE_MARKER);
+    if to_portable {
+        fs::write(&marker, "Datos del launcher junto al ejecutable\n")
+            .map_err(|e| format!("Failed to write {}: {}", marker.display(), e))?;
+    } else if marker.exists() {
+        fs::remove_file(&marker).map_err(|e| e.to_string())?;
+    }
+    Ok(copied)
+}
+
+#[derive(Serialize, Clone, Debug, JsonSchema)]
+pub struct DataDirInfo {
+    pub path: String,
+    pub portable: bool,
+    /// Running inside a Flatpak sandbox
+    pub flatpak: bool,
+}
+
+#[tauri::command]
+pub fn get_data_dir_info() -> DataDirInfo {
+    DataDirInfo {
+        path: root().to_string_lossy().to_string(),
+        portable: is_portable(),
+        flatpak: std::env::var_os("FLATPAK_ID").is_some(),
+    }
+}
+
+/// Moves launcher data between the per-user data directory and portable mode.
+#[tauri::command]
+pub async fn migrate_data_dir(password: String, to_portable: bool) -> Result<String, String> {
+    crate::run_blocking(move || {
+        crate::ensure_admin(password)?;
+        if to_portable == is_portable() {
+            return Err("El launcher ya usa ese modo".to_string());
+        }
+        let exe_dir = exe_dir().ok_or("No se pudo localizar el ejecutable")?;
+        let target = if to_portable { portable_root(&exe_dir) } else { system_root() };
+        migrate(&root(), &target, &exe_dir, to_portable)?;
+        Ok(target.to_string_lossy().to_string())
+    }).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn xdg_data_home_ignores_relative_values() {
+        assert_eq!(xdg_data_home(Some("/data/xdg"), Some("/home/u")), PathBuf::from("/data/xdg"));
+        assert_eq!(xdg_data_home(Some("relative"), Some("/home/u")), PathBuf::from("/home/u/.local/share"));
+        assert_eq!(xdg_data_home(None, Some("/home/u")), PathBuf::from("/home/u/.local/share"));
+    }
+
+    #[test]
+    fn portable_is_enabled_by_flag_or_marker() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(!portable_requested(&["drk".to_string()], Some(dir.path())));
+        assert!(portable_requested(&["drk".to_string(), "--portable".to_string()], Some(dir.path())));
+        fs::write(dir.path().join(PORTABLE_MARKER), "").unwrap();
+        assert!(portable_requested(&["drk".to_string()], Some(dir.path())));
+    }
+
+    #[test]
+    fn migration_copies_data_and_rebases_instance_paths() {
+        let dir = tempfile::tempdir().unwrap();
+        let from = dir.path().join("system").join(DATA_DIR_NAME);
+        let exe = dir.path().join("usb");
+        let to = portable_root(&exe);
+        let instance_dir = from.join("instancias").join("Evento");
+        fs::create_dir_all(&instance_dir).unwrap();
+        fs::write(instance_dir.join("options.txt"), "fov:90").unwrap();
+        let instances = serde_json::json!([{"id": "a", "path": instance_dir.to_string_lossy()}]);
+        fs::write(from.join("instances.json"), instances.to_string()).unwrap();
+
+        migrate(&from, &to, &exe, true).unwrap();
+
+        assert_eq!(fs::read_to_string(to.join("instancias/Evento/options.txt")).unwrap(), "fov:90");
+        let migrated: serde_json::Value = serde_json::from_str(&fs::read_to_string(to.join("instances.json")).unwrap()).unwrap();
+        assert_eq!(migrated[0]["path"].as_str().map(PathBuf::from), Some(to.join("instancias").join("Evento")));
+        assert!(exe.join(PORTABLE_MARKER).is_file());
+        assert!(migrate(&from, &to, &exe, true).is_err());
+
+        migrate(&to, &dir.path().join("back"), &exe, false).unwrap();
+        assert!(!exe.join(PORTABLE_MARKER).exists());
+    }
+}
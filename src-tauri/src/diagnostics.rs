@@ -0,0 +1,118 @@
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use serde::Serialize;
+use crate::minecraft::loader_log;
+
+// Older reports rarely matter and some packs crash-loop into hundreds of them
+const CRASH_REPORTS: usize = 3;
+// Enough to tell a one-off from a pattern, with the stage timings of each
+const LAUNCHES: usize = 5;
+
+#[derive(Serialize)]
+struct Summary {
+    #[serde(rename = "launcherVersion")]
+    launcher_version: String,
+    instance: serde_json::Value,
+    analysis: Option<crate::crash_analysis::CrashAnalysis>,
+    /// Newest launches last, with where their preparation time went
+    launches: Vec<crate::launch_history::LaunchAttempt>,
+    files: Vec<String>,
+}
+
+pub fn newest_files(dir: &Path, limit: usize) -> Vec<PathBuf> {
+    let mut files: Vec<(std::time::SystemTime, PathBuf)> = fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter(|e| e.path().is_file())
+        .map(|e| (e.metadata().and_then(|m| m.modified()).unwrap_or(std::time::UNIX_EPOCH), e.path()))
+        .collect();
+    files.sort_by_key(|(modified, _)| std::cmp::Reverse(*modified));
+    files.into_iter().take(limit).map(|(_, path)| path).collect()
+}
+
+/// Where the game writes crash reports: its own folder, not the launcher's `logs/`.
+pub fn crash_reports_dir(instance_path: &Path) -> PathBuf {
+    instance_path.join("minecraft").join("crash-reports")
+}
+
+/// What goes into the bundle as `(name in zip, source)`: the last session's logs, the newest
+/// crash reports and the instance's loader install logs.
+pub fn bundle_files(instance_path: &Path, install_logs: Vec<PathBuf>) -> Vec<(String, PathBuf)> {
+    let mut files = Vec::new();
+    for name in ["latest.log", "latest_err.log", "debug.log"] {
+        let path = instance_path.join("logs").join(name);
+        if path.is_file() {
+            files.push((format!("logs/{}", name), path));
+        }
+    }
+    for path in newest_files(&crash_reports_dir(instance_path), CRASH_REPORTS) {
+        files.push((format!("crash-reports/{}", path.file_name().unwrap_or_default().to_string_lossy()), path));
+    }
+    for path in install_logs {
+        files.push((format!("loader-install/{}", path.file_name().unwrap_or_default().to_string_lossy()), path));
+    }
+    files
+}
+
+/// Zips the logs staff need to look into a problem with `instance_id` to `file`, with a
+/// `summary.json` holding the instance, the crash analysis and the latest launches. Returns the
+/// files included. `file` must be inside the data root unless the admin password is given.
+#[tauri::command]
+pub async fn export_diagnostics(instance_id: String, file: String, password: Option<String>) -> Result<Vec<String>, String> {
+    crate::run_blocking(move || {
+        let target = crate::path_policy::checked(Path::new(&file), password)?;
+        let instance = crate::load_instances().into_iter().find(|i| i.id == instance_id)
+            .ok_or("Instance not found")?;
+        let files = bundle_files(Path::new(&instance.path), loader_log::logs_for(&loader_log::log_dir(), &instance_id));
+        let mut launches = crate::launch_history::get_launch_history(instance_id.clone());
+        launches.drain(..launches.len().saturating_sub(LAUNCHES));
+        let names: Vec<String> = files.iter().map(|(name, _)| name.clone()).collect();
+        let summary = Summary {
+            launcher_version: env!("CARGO_PKG_VERSION").to_string(),
+            instance: serde_json::to_value(&instance).map_err(|e| e.to_string())?,
+            analysis: crate::crash_analysis::analyze_crash(instance_id.clone()).ok(),
+            launches,
+            files: names.clone(),
+        };
+        crate::temp_files::write_atomic(&target, |out| {
+            let mut zip = zip::ZipWriter::new(out);
+            let options = zip::write::FileOptions::default();
+            for (name, source) in &files {
+                zip.start_file(name.as_str(), options).map_err(|e| e.to_string())?;
+                let mut source = File::open(source).map_err(|e| format!("No se pudo leer {}: {}", source.display(), e))?;
+                io::copy(&mut source, &mut zip).map_err(|e| e.to_string())?;
+            }
+            zip.start_file("summary.json", options).map_err(|e| e.to_string())?;
+            zip.write_all(&serde_json::to_vec_pretty(&summary).map_err(|e| e.to_string())?).map_err(|e| e.to_string())?;
+            zip.finish().map_err(|e| e.to_string())?;
+            Ok(())
+        })?;
+        Ok(names)
+    }).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bundle_takes_logs_newest_crash_reports_and_install_logs() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("logs")).unwrap();
+        fs::write(dir.path().join("logs").join("latest.log"), "game").unwrap();
+        fs::write(dir.path().join("logs").join("2024-01-01-1.log.gz"), "old").unwrap();
+        fs::create_dir_all(crash_reports_dir(dir.path())).unwrap();
+        for i in 0..5 {
+            fs::write(crash_reports_dir(dir.path()).join(format!("crash-{}.txt", i)), "crash").unwrap();
+        }
+        let install = dir.path().join("evento-forge-47.2.0-1-failed.log");
+        fs::write(&install, "installer").unwrap();
+
+        let names: Vec<String> = bundle_files(dir.path(), vec![install]).into_iter().map(|(name, _)| name).collect();
+        assert_eq!(names.len(), 1 + CRASH_REPORTS + 1);
+        assert_eq!(names[0], "logs/latest.log");
+        assert_eq!(names.last().unwrap(), "loader-install/evento-forge-47.2.0-1-failed.log");
+    }
+}
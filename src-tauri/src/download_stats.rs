@@ -0,0 +1,313 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::io::Read;
+use std::time::{Duration, Instant};
+
+const STATS_FILE: &str = "download-stats.json";
+const SPEED_HISTORY: usize = 20;
+// Asset verification records thousands of files; the file is rewritten at most this often
+const SAVE_INTERVAL: Duration = Duration::from_secs(5);
+// Below this a host counts as unreliable, whatever its speed
+const MIN_SUCCESS_RATE: f64 = 0.5;
+const PROBE_TIMEOUT: Duration = Duration::from_secs(20);
+// Enough to measure throughput; manifests are read whole up to this
+const MAX_PROBE_BYTES: u64 = 2 * 1024 * 1024;
+// After this a speedtest says more about last week's network than tonight's
+const SPEEDTEST_VALID_SECS: u64 = 24 * 60 * 60;
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct HostStats {
+    pub bytes: u64,
+    pub downloads: u64,
+    pub failures: u64,
+    #[serde(rename = "totalMillis", alias = "total_millis")]
+    pub total_millis: u64,
+    /// Bytes per second of the most recent downloads, oldest first
+    #[serde(rename = "speedHistory", alias = "speed_history", default)]
+    pub speed_history: VecDeque<u64>,
+}
+
+impl HostStats {
+    /// Average bytes per second over every successful download.
+    pub fn average_speed(&self) -> u64 {
+        (self.bytes * 1000).checked_div(self.total_millis).unwrap_or(0)
+    }
+
+    fn reliable(&self) -> bool {
+        let attempts = self.downloads + self.failures;
+        attempts < 3 || self.downloads as f64 / attempts as f64 >= MIN_SUCCESS_RATE
+    }
+}
+
+/// One probe download of a speedtest run.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ProbeResult {
+    pub url: String,
+    pub host: String,
+    /// Time until the response headers arrived
+    #[serde(rename = "latencyMs", alias = "latency_ms", default)]
+    pub latency_ms: Option<u64>,
+    /// Bytes per second over the whole request, latency included
+    #[serde(rename = "bytesPerSec", alias = "bytes_per_sec", default)]
+    pub bytes_per_sec: Option<u64>,
+    #[serde(default)]
+    pub bytes: u64,
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct Speedtest {
+    /// Unix seconds
+    pub at: u64,
+    pub results: Vec<ProbeResult>,
+}
+
+impl Speedtest {
+    /// Best measured speed of `host`; `Some(0)` when every probe to it failed.
+    fn speed_of(&self, host: &str) -> Option<u64> {
+        self.results.iter()
+            .filter(|r| r.host == host)
+            .map(|r| r.bytes_per_sec.unwrap_or(0))
+            .max()
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, JsonSchema)]
+pub struct DownloadStats {
+    #[serde(default)]
+    pub hosts: HashMap<String, HostStats>,
+    /// Latest `run_mirror_speedtest`
+    #[serde(default)]
+    pub speedtest: Option<Speedtest>,
+}
+
+impl DownloadStats {
+    pub fn record_success(&mut self, host: &str, bytes: u64, elapsed: Duration) {
+        let stats = self.hosts.entry(host.to_string()).or_default();
+        // Tiny files are dominated by latency; they still count toward the totals
+        let millis = elapsed.as_millis().max(1) as u64;
+        stats.bytes += bytes;
+        stats.downloads += 1;
+        stats.total_millis += millis;
+        stats.speed_history.push_back(bytes * 1000 / millis);
+        while stats.speed_history.len() > SPEED_HISTORY {
+            stats.speed_history.pop_front();
+        }
+    }
+
+    pub fn record_failure(&mut self, host: &str) {
+        self.hosts.entry(host.to_string()).or_default().failures += 1;
+    }
+
+    /// Mirrors ordered fastest first. A recent speedtest decides over the running averages;
+    /// hosts without data keep their place after the measured ones; unreliable hosts go last.
+    pub fn rank(&self, mirrors: &[&str]) -> Vec<String> {
+        self.rank_at(mirrors, crate::unix_now())
+    }
+
+    fn rank_at(&self, mirrors: &[&str], now: u64) -> Vec<String> {
+        let speedtest = self.speedtest.as_ref().filter(|t| now.saturating_sub(t.at) < SPEEDTEST_VALID_SECS);
+        let mut ranked = mirrors.iter().map(|m| m.to_string()).collect::<Vec<_>>();
+        ranked.sort_by_key(|mirror| {
+            let host = host_of(mirror);
+            match (speedtest.and_then(|t| t.speed_of(&host)), self.hosts.get(&host)) {
+                (Some(0), _) => (2, 0),
+                (Some(speed), _) => (0, u64::MAX - speed),
+                (None, Some(stats)) if !stats.reliable() => (2, 0),
+                (None, Some(stats)) if stats.downloads > 0 => (0, u64::MAX - stats.average_speed()),
+                _ => (1, 0),
+            }
+        });
+        ranked
+    }
+}
+
+pub fn host_of(url: &str) -> String {
+    url::Url::parse(url).ok().and_then(|u| u.host_str().map(str::to_string)).unwrap_or_default()
+}
+
+struct Store {
+    stats: DownloadStats,
+    last_save: Option<Instant>,
+}
+
+static STORE: Mutex<Option<Store>> = Mutex::new(None);
+
+fn stats_path() -> PathBuf {
+    PathBuf::from(crate::get_instances_dir()).join(STATS_FILE)
+}
+
+fn with_store<T>(f: impl FnOnce(&mut Store) -> T) -> T {
+    let mut guard = STORE.lock().unwrap_or_else(|e| e.into_inner());
+    let store = guard.get_or_insert_with(|| Store {
+        stats: fs::read_to_string(stats_path())
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default(),
+        last_save: None,
+    });
+    f(store)
+}
+
+fn save_throttled(store: &mut Store) {
+    if store.last_save.map(|t| t.elapsed() < SAVE_INTERVAL).unwrap_or(false) {
+        return;
+    }
+    store.last_save = Some(Instant::now());
+    if let Ok(json) = serde_json::to_string_pretty(&store.stats) {
+        let _ = fs::write(stats_path(), json);
+    }
+}
+
+pub fn record_success(url: &str, bytes: u64, elapsed: Duration) {
+    crate::metrics::record_download(bytes);
+    with_store(|store| {
+        store.stats.record_success(&host_of(url), bytes, elapsed);
+        save_throttled(store);
+    });
+}
+
+pub fn record_failure(url: &str) {
+    crate::metrics::record_failure();
+    with_store(|store| {
+        store.stats.record_failure(&host_of(url));
+        save_throttled(store);
+    });
+}
+
+/// `mirrors` reordered by the recorded speed of their hosts.
+pub fn rank_mirrors(mirrors: &[&str]) -> Vec<String> {
+    with_store(|store| store.stats.rank(mirrors))
+}
+
+/// Downloads `url` (up to `MAX_PROBE_BYTES`) and times it.
+fn probe(client: &reqwest::blocking::Client, url: &str) -> ProbeResult {
+    let mut result = ProbeResult { url: url.to_string(), host: host_of(url), latency_ms: None, bytes_per_sec: None, bytes: 0, error: None };
+    let started = Instant::now();
+    let response = match client.get(url).send().and_then(|r| r.error_for_status()) {
+        Ok(response) => response,
+        Err(e) => {
+            result.error = Some(e.to_string());
+            return result;
+        }
+    };
+    result.latency_ms = Some(started.elapsed().as_millis() as u64);
+    let mut buf = [0u8; 64 * 1024];
+    let mut body = response.take(MAX_PROBE_BYTES);
+    loop {
+        match body.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => result.bytes += n as u64,
+            Err(e) => {
+                result.error = Some(e.to_string());
+                return result;
+            }
+        }
+    }
+    let millis = started.elapsed().as_millis().max(1) as u64;
+    result.bytes_per_sec = Some(result.bytes * 1000 / millis);
+    result
+}
+
+/// Every mirror the launcher picks from: the version manifests, and one asset object per
+/// asset mirror when an index is on disk.
+fn probe_urls() -> Vec<String> {
+    let assets_dir = PathBuf::from(crate::get_instances_dir()).join("assets");
+    crate::minecraft::launch_logic::MANIFEST_MIRRORS.iter()
+        .map(|url| url.to_string())
+        .chain(crate::minecraft::launch_logic::asset_probe_urls(&assets_dir))
+        .collect()
+}
+
+#[tauri::command]
+pub fn get_download_stats() -> DownloadStats {
+    with_store(|store| store.stats.clone())
+}
+
+/// Times a small download from each content mirror one after another (so they don't share
+/// the bandwidth) and keeps the result for mirror selection during the next day.
+#[tauri::command]
+pub async fn run_mirror_speedtest() -> Result<Speedtest, String> {
+    crate::run_blocking(|| {
+        let client = reqwest::blocking::Client::builder()
+            .user_agent(crate::http::user_agent())
+            .timeout(PROBE_TIMEOUT)
+            .build()
+            .map_err(|e| format!("Failed to build http client: {}", e))?;
+        let results = probe_urls().iter().map(|url| probe(&client, url)).collect();
+        let speedtest = Speedtest { at: crate::unix_now(), results };
+        with_store(|store| {
+            store.stats.speedtest = Some(speedtest.clone());
+            store.last_save = None;
+            save_throttled(store);
+        });
+        Ok(speedtest)
+    }).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn speed_history_is_bounded() {
+        let mut stats = DownloadStats::default();
+        for _ in 0..30 {
+            stats.record_success("cdn.example.com", 2000, Duration::from_secs(1));
+        }
+        let host = &stats.hosts["cdn.example.com"];
+        assert_eq!(host.speed_history.len(), SPEED_HISTORY);
+        assert_eq!(host.average_speed(), 2000);
+        assert_eq!(host.downloads, 30);
+    }
+
+    #[test]
+    fn fastest_reliable_mirror_comes_first() {
+        let mut stats = DownloadStats::default();
+        stats.record_success("slow.example.com", 1000, Duration::from_secs(1));
+        stats.record_success("fast.example.com", 9000, Duration::from_secs(1));
+        for _ in 0..3 {
+            stats.record_failure("fast.example.com");
+        }
+        stats.record_success("quick.example.com", 5000, Duration::from_secs(1));
+        let mirrors = ["https://fast.example.com/a", "https://new.example.com/a", "https://slow.example.com/a", "https://quick.example.com/a"];
+        assert_eq!(stats.rank(&mirrors), vec![
+            "https://quick.example.com/a",
+            "https://slow.example.com/a",
+            "https://new.example.com/a",
+            "https://fast.example.com/a",
+        ]);
+    }
+
+    #[test]
+    fn recent_speedtest_overrides_the_averages() {
+        let probe = |host: &str, speed: Option<u64>| ProbeResult {
+            url: format!("https://{}/probe", host),
+            host: host.to_string(),
+            latency_ms: speed.map(|_| 50),
+            bytes_per_sec: speed,
+            bytes: 0,
+            error: speed.is_none().then(|| "timed out".to_string()),
+        };
+        let mut stats = DownloadStats::default();
+        stats.record_success("a.example.com", 9000, Duration::from_secs(1));
+        stats.record_success("b.example.com", 1000, Duration::from_secs(1));
+        stats.speedtest = Some(Speedtest {
+            at: 10_000,
+            results: vec![probe("a.example.com", None), probe("b.example.com", Some(500)), probe("c.example.com", Some(800))],
+        });
+        let mirrors = ["https://a.example.com/x", "https://b.example.com/x", "https://c.example.com/x"];
+        assert_eq!(stats.rank_at(&mirrors, 10_060), vec![
+            "https://c.example.com/x",
+            "https://b.example.com/x",
+            "https://a.example.com/x",
+        ]);
+        // An old speedtest no longer counts
+        assert_eq!(stats.rank_at(&mirrors, 10_000 + SPEEDTEST_VALID_SECS)[0], "https://a.example.com/x");
+    }
+}
@@ -0,0 +1,285 @@
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Mutex;
+use tauri::{AppHandle, DragDropEvent, Emitter, Manager, State, Window, WindowEvent};
+use zip::ZipArchive;
+use crate::minecraft::content_source::{self, ContentKind, Target};
+use crate::minecraft::downloader::download_file;
+use crate::minecraft::modpack::{ensure_not_encrypted, inspect_modpack, open_archive};
+
+/// Files inside a jar that only mods have (Fabric, Quilt, Forge, NeoForge, legacy Forge).
+const MOD_METADATA: [&str; 5] = ["fabric.mod.json", "quilt.mod.json", "META-INF/mods.toml", "META-INF/neoforge.mods.toml", "mcmod.info"];
+
+/// Instance selected in the UI; files dropped on the window are installed into it.
+#[derive(Default)]
+pub struct DropTarget(pub Mutex<Option<String>>);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DropKind {
+    Mod,
+    Modrinth,
+    CurseForge,
+}
+
+fn archive_has(path: &Path, names: &[&str]) -> Result<bool, String> {
+    let file = File::open(path).map_err(|e| e.to_string())?;
+    let archive = ZipArchive::new(file).map_err(|_| "El archivo está dañado o no es un zip".to_string())?;
+    let found = archive.file_names().any(|n| names.contains(&n));
+    Ok(found)
+}
+
+/// What a dropped file is, checked against its content and not only its extension.
+pub fn classify(path: &Path) -> Result<DropKind, String> {
+    let extension = path.extension().map(|e| e.to_string_lossy().to_lowercase()).unwrap_or_default();
+    match extension.as_str() {
+        "jar" if archive_has(path, &MOD_METADATA)? => Ok(DropKind::Mod),
+        "jar" => Err("El jar no es un mod de Fabric, Quilt o Forge".to_string()),
+        "mrpack" | "zip" if archive_has(path, &["modrinth.index.json"])? => Ok(DropKind::Modrinth),
+        "zip" if archive_has(path, &["manifest.json"])? => Ok(DropKind::CurseForge),
+        "mrpack" | "zip" => Err("Formato de modpack no reconocido".to_string()),
+        _ => Err("Solo se pueden soltar archivos .mrpack, .zip de CurseForge o mods .jar".to_string()),
+    }
+}
+
+/// Relative paths from pack metadata, refusing anything that would land outside the instance.
+pub fn safe_relative(path: &str) -> Option<PathBuf> {
+    let path = Path::new(path);
+    path.components().all(|c| matches!(c, Component::Normal(_))).then(|| path.to_path_buf())
+}
+
+/// `drop_install_progress` payload. `instanceId` is null when nothing was selected to drop on.
+#[derive(Serialize, Clone, JsonSchema)]
+pub struct DropInstallProgress {
+    #[serde(rename = "instanceId")]
+    pub instance_id: Option<String>,
+    pub file: Option<String>,
+    pub percent: u8,
+    pub message: String,
+    pub done: bool,
+    pub error: Option<String>,
+}
+
+struct Progress<'a> {
+    app: &'a AppHandle,
+    instance_id: &'a str,
+    file: String,
+}
+
+impl Progress<'_> {
+    fn emit(&self, percent: u8, message: &str) {
+        self.send(percent, message, false, None);
+    }
+
+    fn send(&self, percent: u8, message: &str, done: bool, error: Option<&str>) {
+        let _ = self.app.emit("drop_install_progress", DropInstallProgress {
+            instance_id: Some(self.instance_id.to_string()),
+            file: Some(self.file.clone()),
+            percent,
+            message: message.to_string(),
+            done,
+            error: error.map(str::to_string),
+        });
+    }
+}
+
+fn install_mod(path: &Path, minecraft_dir: &Path) -> Result<String, String> {
+    let name = path.file_name().ok_or("Nombre de archivo no válido")?;
+    let mods_dir = minecraft_dir.join("mods");
+    fs::create_dir_all(&mods_dir).map_err(|e| e.to_string())?;
+    // Through a temp file: the old jar may be hard linked from the mods store
+    let mut source = File::open(path).map_err(|e| format!("No se pudo copiar el mod: {}", e))?;
+    crate::temp_files::write_atomic(&mods_dir.join(name), |out| {
+        std::io::copy(&mut source, out).map(|_| ()).map_err(|e| format!("No se pudo copiar el mod: {}", e))
+    })?;
+    Ok(format!("Mod {} instalado", name.to_string_lossy()))
+}
+
+/// Copies the pack's override folders into the instance, through temp files like `extract_modpack`.
+fn extract_overrides(path: &Path, prefixes: &[String], minecraft_dir: &Path) -> Result<(), String> {
+    let file = File::open(path).map_err(|e| e.to_string())?;
+    let mut archive = open_archive(file)?;
+    ensure_not_encrypted(&mut archive, usize::MAX)?;
+    for i in 0..archive.len() {
+        let mut entry = archive.by_index(i).map_err(crate::minecraft::modpack::archive_error)?;
+        let Some(name) = entry.enclosed_name().map(|n| n.to_string_lossy().replace('\\', "/")) else { continue; };
+        let Some(relative) = prefixes.iter().find_map(|p| name.strip_prefix(p.as_str())) else { continue; };
+        if relative.is_empty() || entry.is_dir() {
+            continue;
+        }
+        let target = minecraft_dir.join(relative);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        crate::temp_files::write_atomic(&target, |out| std::io::copy(&mut entry, out).map(|_| ()).map_err(|e| e.to_string()))?;
+    }
+    Ok(())
+}
+
+fn read_index(path: &Path, name: &str) -> Result<serde_json::Value, String> {
+    let file = File::open(path).map_err(|e| e.to_string())?;
+    let mut archive = open_archive(file)?;
+    let mut content = String::new();
+    archive.by_name(name).map_err(|e| e.to_string())?.read_to_string(&mut content).map_err(|e| e.to_string())?;
+    serde_json::from_str(&content).map_err(|e| format!("{} no válido: {}", name, e))
+}
+
+fn install_modrinth_files(index: &serde_json::Value, minecraft_dir: &Path, progress: &Progress) -> Result<(), String> {
+    let files = index["files"].as_array().cloned().unwrap_or_default();
+    let files = files.iter().filter(|f| f["env"]["client"].as_str() != Some("unsupported")).collect::<Vec<_>>();
+    for (i, file) in files.iter().enumerate() {
+        let relative = file["path"].as_str().and_then(safe_relative).ok_or("Ruta no válida en modrinth.index.json")?;
+        let url = file["downloads"][0].as_str().ok_or("Archivo sin enlace de descarga en modrinth.index.json")?;
+        progress.emit((20 + i * 75 / files.len().max(1)) as u8, &format!("Descargando {}", relative.display()));
+        download_file(url, &minecraft_dir.join(&relative), file["hashes"]["sha1"].as_str())?;
+    }
+    Ok(())
+}
+
+fn install_curseforge_files(manifest: &serde_json::Value, minecraft_dir: &Path, target: &Target, progress: &Progress) -> Result<(), String> {
+    let files = manifest["files"].as_array().cloned().unwrap_or_default();
+    for (i, file) in files.iter().enumerate() {
+        let (Some(project_id), Some(file_id)) = (file["projectID"].as_u64(), file["fileID"].as_u64()) else { continue; };
+        progress.emit((20 + i * 75 / files.len().max(1)) as u8, &format!("Descargando mod {}/{}", i + 1, files.len()));
+        let resolved = content_source::resolve(&format!("curseforge:{}/{}", project_id, file_id), target)?;
+        let name = safe_relative(&resolved.file_name).ok_or("Nombre de archivo no válido")?;
+        content_source::download(&resolved, &minecraft_dir.join("mods").join(name), ContentKind::Mod)?;
+    }
+    Ok(())
+}
+
+fn install_pack(kind: DropKind, path: &Path, instance: &crate::Instance, minecraft_dir: &Path, progress: &Progress) -> Result<String, String> {
+    let info = inspect_modpack(&path.to_string_lossy())?;
+    if let Some(mc_version) = info.mc_version.as_deref().filter(|v| *v != instance.version) {
+        return Err(format!("El modpack es para Minecraft {} y la instancia usa {}", mc_version, instance.version));
+    }
+    progress.emit(10, "Extrayendo archivos del modpack...");
+    if kind == DropKind::Modrinth {
+        extract_overrides(path, &["overrides/".to_string(), "client-overrides/".to_string()], minecraft_dir)?;
+        install_modrinth_files(&read_index(path, "modrinth.index.json")?, minecraft_dir, progress)?;
+    } else {
+        let manifest = read_index(path, "manifest.json")?;
+        let overrides = format!("{}/", manifest["overrides"].as_str().unwrap_or("overrides").trim_end_matches('/'));
+        extract_overrides(path, &[overrides], minecraft_dir)?;
+        install_curseforge_files(&manifest, minecraft_dir, &Target::of(instance), progress)?;
+    }
+    Ok(format!("Modpack {} instalado", info.name.unwrap_or_else(|| path.file_name().unwrap_or_default().to_string_lossy().to_string())))
+}
+
+/// Installs one dropped file into the instance, reporting through `drop_install_progress`.
+pub fn install_dropped_file(app: &AppHandle, instance_id: &str, path: &Path) -> Result<String, String> {
+    crate::settings::ensure_not_guest()?;
+    let instance = crate::load_instances().into_iter().find(|i| i.id == instance_id)
+        .ok_or("Instance not found")?;
+    let minecraft_dir = Path::new(&instance.path).join("minecraft");
+    let progress = Progress { app, instance_id, file: path.to_string_lossy().to_string() };
+    progress.emit(0, "Comprobando archivo...");
+    match classify(path)? {
+        DropKind::Mod => install_mod(path, &minecraft_dir),
+        kind => install_pack(kind, path, &instance, &minecraft_dir, &progress),
+    }
+}
+
+fn install_dropped_files(app: AppHandle, instance_id: String, paths: Vec<PathBuf>) {
+    for path in paths {
+        let progress = Progress { app: &app, instance_id: &instance_id, file: path.to_string_lossy().to_string() };
+        match install_dropped_file(&app, &instance_id, &path) {
+            Ok(message) => progress.send(100, &message, true, None),
+            Err(e) => progress.send(100, &e, true, Some(&e)),
+        }
+    }
+}
+
+/// Window event hook: files dropped on the launcher go to the selected instance.
+pub fn handle_window_event(window: &Window, event: &WindowEvent) {
+    let WindowEvent::DragDrop(DragDropEvent::Drop { paths, .. }) = event else { return; };
+    let target = window.state::<DropTarget>().0.lock().ok().and_then(|t| t.clone());
+    let app = window.app_handle().clone();
+    match target {
+        Some(instance_id) => {
+            let paths = paths.clone();
+            std::thread::spawn(move || install_dropped_files(app, instance_id, paths));
+        }
+        None => {
+            let message = "Selecciona una instancia antes de soltar archivos".to_string();
+            let _ = app.emit("drop_install_progress", DropInstallProgress {
+                instance_id: None,
+                file: None,
+                percent: 100,
+                message: message.clone(),
+                done: true,
+                error: Some(message),
+            });
+        }
+    }
+}
+
+#[tauri::command]
+pub fn set_drop_target(state: State<'_, DropTarget>, instance_id: Option<String>) {
+    if let Ok(mut target) = state.0.lock() {
+        *target = instance_id;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn write_zip(path: &Path, entries: &[(&str, &str)]) {
+        let mut zip = zip::ZipWriter::new(File::create(path).unwrap());
+        for (name, content) in entries {
+            zip.start_file(*name, zip::write::FileOptions::default()).unwrap();
+            zip.write_all(content.as_bytes()).unwrap();
+        }
+        zip.finish().unwrap();
+    }
+
+    #[test]
+    fn drops_are_classified_by_content() {
+        let dir = tempfile::tempdir().unwrap();
+        let fabric_mod = dir.path().join("sodium.jar");
+        write_zip(&fabric_mod, &[("fabric.mod.json", "{}")]);
+        let library = dir.path().join("lib.jar");
+        write_zip(&library, &[("org/lib/A.class", "")]);
+        let mrpack = dir.path().join("pack.mrpack");
+        write_zip(&mrpack, &[("modrinth.index.json", "{}")]);
+        let curseforge = dir.path().join("pack.zip");
+        write_zip(&curseforge, &[("manifest.json", "{}"), ("overrides/config/a.toml", "")]);
+        let plain = dir.path().join("fotos.zip");
+        write_zip(&plain, &[("a.png", "")]);
+
+        assert_eq!(classify(&fabric_mod), Ok(DropKind::Mod));
+        assert!(classify(&library).is_err());
+        assert_eq!(classify(&mrpack), Ok(DropKind::Modrinth));
+        assert_eq!(classify(&curseforge), Ok(DropKind::CurseForge));
+        assert!(classify(&plain).is_err());
+        assert!(classify(&dir.path().join("notas.txt")).is_err());
+    }
+
+    #[test]
+    fn overrides_land_in_the_instance() {
+        let dir = tempfile::tempdir().unwrap();
+        let pack = dir.path().join("pack.mrpack");
+        write_zip(&pack, &[
+            ("modrinth.index.json", "{}"),
+            ("overrides/config/a.toml", "a=1"),
+            ("client-overrides/options.txt", "fov:90"),
+            ("server-overrides/server.properties", ""),
+        ]);
+        let minecraft_dir = dir.path().join("minecraft");
+        extract_overrides(&pack, &["overrides/".to_string(), "client-overrides/".to_string()], &minecraft_dir).unwrap();
+        assert_eq!(fs::read_to_string(minecraft_dir.join("config/a.toml")).unwrap(), "a=1");
+        assert!(minecraft_dir.join("options.txt").is_file());
+        assert!(!minecraft_dir.join("server.properties").exists());
+    }
+
+    #[test]
+    fn pack_paths_stay_inside_the_instance() {
+        assert_eq!(safe_relative("mods/sodium.jar"), Some(PathBuf::from("mods/sodium.jar")));
+        assert_eq!(safe_relative("../../evil.jar"), None);
+        assert_eq!(safe_relative("/etc/passwd"), None);
+    }
+}
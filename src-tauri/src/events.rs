@@ -0,0 +1,194 @@
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{Mutex, Once};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
+
+/// Bumped when the `launch_progress` payload changes shape; sent as `v` in every event.
+pub const EVENT_VERSION: u32 = 1;
+// ~10 events per second per instance; asset downloads otherwise emit thousands
+const MIN_INTERVAL: Duration = Duration::from_millis(100);
+
+/// `launch_progress` payload. Only the final stages fill in the optional fields.
+#[derive(Serialize, Clone, Debug, Default, JsonSchema)]
+pub struct LaunchProgress {
+    #[serde(rename = "instanceId")]
+    pub instance_id: String,
+    pub stage: String,
+    pub percent: u8,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub debug: Option<bool>,
+    /// Sent with `iniciado` and launch errors
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timings: Option<crate::launch_history::PrepareTimings>,
+    /// Event rewards result, sent with `cerrado` and `crasheado`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reward: Option<crate::rewards::RewardReport>,
+    #[serde(rename = "logUrl", skip_serializing_if = "Option::is_none")]
+    pub log_url: Option<String>,
+    /// Sent while a modpack is cleaned out and extracted
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub extraction: Option<ExtractionProgress>,
+    /// Stamped by `emit_progress`: `EVENT_VERSION`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub v: Option<u32>,
+    /// Stamped by `emit_progress`: per-instance sequence number
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seq: Option<u64>,
+}
+
+#[derive(Serialize, Clone, Copy, Debug, Default, PartialEq, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ExtractionPhase {
+    /// Emptying the folders the pack manages
+    #[default]
+    Cleaning,
+    Extracting,
+}
+
+/// Counts for one folder while cleaning, for the whole pack while extracting.
+#[derive(Serialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct ExtractionProgress {
+    pub phase: ExtractionPhase,
+    #[serde(rename = "doneFiles")]
+    pub done_files: u64,
+    #[serde(rename = "totalFiles")]
+    pub total_files: u64,
+    #[serde(rename = "doneBytes")]
+    pub done_bytes: u64,
+    #[serde(rename = "totalBytes")]
+    pub total_bytes: u64,
+    /// Folder being cleaned or the last file extracted
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub current: Option<String>,
+}
+
+impl LaunchProgress {
+    pub fn new(instance_id: &str, stage: &str, percent: u8, message: &str) -> Self {
+        LaunchProgress {
+            instance_id: instance_id.to_string(),
+            stage: stage.to_string(),
+            percent,
+            message: message.to_string(),
+            ..LaunchProgress::default()
+        }
+    }
+}
+
+/// What to do with a progress event right now.
+#[derive(Debug, PartialEq)]
+pub enum Decision {
+    Emit,
+    /// Held back; the flusher sends the latest one once the interval has passed
+    Coalesce,
+}
+
+#[derive(Default)]
+struct Channel {
+    seq: u64,
+    stage: Option<String>,
+    last_emit: Option<Instant>,
+    pending: Option<serde_json::Value>,
+}
+
+/// Per-instance rate limiter. Stage changes always go out at once so the UI never misses one.
+#[derive(Default)]
+pub struct Coalescer {
+    channels: HashMap<String, Channel>,
+}
+
+impl Coalescer {
+    /// Stamps `payload` with the version and the instance's sequence number and decides whether
+    /// it goes out now.
+    pub fn offer(&mut self, instance_id: &str, payload: &mut serde_json::Value, now: Instant) -> Decision {
+        let channel = self.channels.entry(instance_id.to_string()).or_default();
+        channel.seq += 1;
+        payload["v"] = EVENT_VERSION.into();
+        payload["seq"] = channel.seq.into();
+        let stage = payload["stage"].as_str().map(str::to_string);
+        let due = channel.last_emit.map(|at| now.duration_since(at) >= MIN_INTERVAL).unwrap_or(true);
+        if stage != channel.stage || due {
+            channel.stage = stage;
+            channel.last_emit = Some(now);
+            // Anything held back is older than this one
+            channel.pending = None;
+            Decision::Emit
+        } else {
+            channel.pending = Some(payload.clone());
+            Decision::Coalesce
+        }
+    }
+
+    /// Held-back events whose interval has passed.
+    pub fn due(&mut self, now: Instant) -> Vec<serde_json::Value> {
+        self.channels.values_mut()
+            .filter(|c| c.pending.is_some() && c.last_emit.map(|at| now.duration_since(at) >= MIN_INTERVAL).unwrap_or(true))
+            .filter_map(|c| {
+                c.last_emit = Some(now);
+                c.pending.take()
+            })
+            .collect()
+    }
+}
+
+static COALESCER: Mutex<Option<Coalescer>> = Mutex::new(None);
+static FLUSHER: Once = Once::new();
+
+fn start_flusher(app: &AppHandle) {
+    let app = app.clone();
+    FLUSHER.call_once(move || {
+        std::thread::spawn(move || loop {
+            std::thread::sleep(MIN_INTERVAL / 2);
+            let due = COALESCER.lock().ok()
+                .and_then(|mut c| c.as_mut().map(|c| c.due(Instant::now())))
+                .unwrap_or_default();
+            for payload in due {
+                let _ = app.emit("launch_progress", payload);
+            }
+        });
+    });
+}
+
+/// Emits a `launch_progress` event through the per-instance rate limiter.
+pub fn emit_progress(app: &AppHandle, progress: LaunchProgress) {
+    let instance_id = progress.instance_id.clone();
+    let Ok(mut payload) = serde_json::to_value(progress) else { return; };
+    start_flusher(app);
+    let decision = COALESCER.lock()
+        .map(|mut c| c.get_or_insert_with(Coalescer::default).offer(&instance_id, &mut payload, Instant::now()))
+        .unwrap_or(Decision::Emit);
+    if decision == Decision::Emit {
+        let _ = app.emit("launch_progress", payload);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn bursts_are_coalesced_but_stage_changes_go_out() {
+        let mut coalescer = Coalescer::default();
+        let start = Instant::now();
+        let event = |stage: &str, percent: u64| json!({"instanceId": "evento", "stage": stage, "percent": percent});
+
+        assert_eq!(coalescer.offer("evento", &mut event("assets", 60), start), Decision::Emit);
+        for (i, percent) in (61..70).enumerate() {
+            let at = start + Duration::from_millis(i as u64 * 5);
+            assert_eq!(coalescer.offer("evento", &mut event("assets", percent), at), Decision::Coalesce);
+        }
+        assert!(coalescer.due(start + Duration::from_millis(50)).is_empty());
+        let flushed = coalescer.due(start + MIN_INTERVAL);
+        assert_eq!(flushed.len(), 1);
+        assert_eq!(flushed[0]["percent"], 69);
+        assert_eq!(flushed[0]["seq"], 10);
+        assert_eq!(flushed[0]["v"], EVENT_VERSION);
+
+        let later = start + MIN_INTERVAL + Duration::from_millis(1);
+        assert_eq!(coalescer.offer("evento", &mut event("librerias", 65), later), Decision::Emit);
+        assert_eq!(coalescer.offer("otro", &mut event("assets", 60), later), Decision::Emit);
+    }
+}
@@ -0,0 +1,164 @@
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::process::ExitStatus;
+
+// What the JVM exits with after `System.exit(-1)`, Minecraft's own crash path
+const JVM_CRASH_EXIT: i32 = -1;
+// Shells and `ExitStatus` on Unix: 128 + signal number
+const SIGNAL_BASE: i32 = 128;
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum ExitCategory {
+    /// Graphics driver or another native library
+    Driver,
+    /// Not enough RAM for the heap, or for the whole system
+    Memory,
+    /// A mod threw, looped or called `System.exit`
+    Mods,
+    /// Java itself didn't start: arguments, install or antivirus
+    Java,
+    /// Closed from outside: task manager, shutdown, another program
+    Killed,
+}
+
+/// A game exit code in words.
+#[derive(Serialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ExitCodeInfo {
+    pub code: i32,
+    /// `0xC0000005`; what Windows tools and forum posts show
+    pub hex: String,
+    /// `STATUS_ACCESS_VIOLATION`, `SIGKILL`...
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    pub explanation: String,
+    pub likely: ExitCategory,
+}
+
+/// The exit code of a finished game, with Unix signals as `128 + signal` instead of no code.
+pub fn exit_code(status: &ExitStatus) -> i32 {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::ExitStatusExt;
+        if let Some(signal) = status.signal() {
+            return SIGNAL_BASE + signal;
+        }
+    }
+    status.code().unwrap_or(JVM_CRASH_EXIT)
+}
+
+/// What `code` usually means for Minecraft, for the codes that say something.
+pub fn decode(code: i32) -> Option<ExitCodeInfo> {
+    let (name, likely, explanation) = match code as u32 {
+        0xC000_0005 => (
+            Some("STATUS_ACCESS_VIOLATION"),
+            ExitCategory::Driver,
+            "Una librería nativa accedió a memoria que no debía. Casi siempre es el driver de la tarjeta gráfica; también pasa con mods de shaders o de rendimiento.",
+        ),
+        0xC000_0409 => (
+            Some("STATUS_STACK_BUFFER_OVERRUN"),
+            ExitCategory::Driver,
+            "Windows cerró el juego por un fallo grave en código nativo, normalmente el driver de la tarjeta gráfica o un programa superpuesto (grabación, FPS, chat de voz).",
+        ),
+        0xC000_0374 => (
+            Some("STATUS_HEAP_CORRUPTION"),
+            ExitCategory::Driver,
+            "Se dañó la memoria de una librería nativa. Suele ser el driver de la tarjeta gráfica o un programa superpuesto.",
+        ),
+        0xC000_00FD => (
+            Some("STATUS_STACK_OVERFLOW"),
+            ExitCategory::Mods,
+            "Un hilo se quedó sin pila, casi siempre por un mod que entra en un bucle de llamadas.",
+        ),
+        0xC000_0017 | 0xC000_012D => (
+            Some(if code as u32 == 0xC000_0017 { "STATUS_NO_MEMORY" } else { "STATUS_COMMITMENT_LIMIT" }),
+            ExitCategory::Memory,
+            "Windows se quedó sin memoria. Cierra otros programas, baja la RAM asignada o aumenta el archivo de paginación.",
+        ),
+        0xCFFF_FFFF => (
+            None,
+            ExitCategory::Memory,
+            "El juego dejó de responder y se cerró. Suele deberse a falta de memoria: demasiada RAM asignada para el equipo o demasiado poca para los mods.",
+        ),
+        0xC000_0135 | 0xC000_0142 => (
+            Some(if code as u32 == 0xC000_0135 { "STATUS_DLL_NOT_FOUND" } else { "STATUS_DLL_INIT_FAILED" }),
+            ExitCategory::Java,
+            "Java no pudo cargar sus librerías. Reinstala el Java de la instancia o revisa el antivirus.",
+        ),
+        0xC000_013A => (
+            Some("STATUS_CONTROL_C_EXIT"),
+            ExitCategory::Killed,
+            "El juego se cerró desde fuera: al apagar o cerrar sesión en Windows, o desde otro programa.",
+        ),
+        0x4001_0004 => (
+            Some("DBG_TERMINATE_PROCESS"),
+            ExitCategory::Killed,
+            "Otro programa terminó el juego, por ejemplo el administrador de tareas o un antivirus.",
+        ),
+        _ => match code {
+            JVM_CRASH_EXIT | 255 => (
+                None,
+                ExitCategory::Mods,
+                "Minecraft se cerró tras un error de Java. El informe de error (crash-reports) suele nombrar el mod responsable.",
+            ),
+            1 => (
+                None,
+                ExitCategory::Java,
+                "Java terminó con un error general: no pudo crear la máquina virtual (RAM o argumentos no válidos) o un mod detuvo el juego. El registro lo aclara.",
+            ),
+            134 => (
+                Some("SIGABRT"),
+                ExitCategory::Driver,
+                "Java abortó por un fallo en código nativo (mira los hs_err_pid*.log). Suele ser el driver gráfico.",
+            ),
+            137 => (
+                Some("SIGKILL"),
+                ExitCategory::Memory,
+                "El sistema mató el juego, normalmente por falta de memoria (el OOM killer de Linux).",
+            ),
+            139 => (
+                Some("SIGSEGV"),
+                ExitCategory::Driver,
+                "Fallo de segmentación en código nativo, casi siempre el driver de la tarjeta gráfica.",
+            ),
+            143 => (
+                Some("SIGTERM"),
+                ExitCategory::Killed,
+                "Otro programa o el sistema pidió cerrar el juego.",
+            ),
+            _ => return None,
+        },
+    };
+    Some(ExitCodeInfo {
+        code,
+        hex: format!("0x{:08X}", code as u32),
+        name: name.map(str::to_string),
+        explanation: explanation.to_string(),
+        likely,
+    })
+}
+
+/// Whether the game died inside the graphics driver (or another native library).
+pub fn is_driver_crash(code: i32) -> bool {
+    decode(code).is_some_and(|info| info.likely == ExitCategory::Driver)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn common_codes_are_explained() {
+        let access = decode(-1073741819).unwrap();
+        assert_eq!(access.hex, "0xC0000005");
+        assert_eq!(access.name.as_deref(), Some("STATUS_ACCESS_VIOLATION"));
+        assert!(is_driver_crash(-1073740791));
+        assert_eq!(decode(-805306369).unwrap().likely, ExitCategory::Memory);
+        assert_eq!(decode(-1).unwrap().likely, ExitCategory::Mods);
+        assert_eq!(decode(137).unwrap().likely, ExitCategory::Memory);
+        assert_eq!(decode(-1073741510).unwrap().likely, ExitCategory::Killed);
+        assert!(!is_driver_crash(1));
+        assert!(decode(0).is_none());
+        assert!(decode(42).is_none());
+    }
+}
@@ -0,0 +1,335 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::Mutex;
+use tauri::State;
+
+/// Command line flag the registered file types and `drk://` links open the launcher with.
+pub const OPEN_FLAG: &str = "--open";
+pub const URL_SCHEME: &str = "drk";
+/// Pack files the launcher offers to import
+pub const PACK_EXTENSIONS: [&str; 2] = ["mrpack", "zip"];
+// Registry entries exist only on Windows; the tests build them everywhere
+#[cfg(any(target_os = "windows", test))]
+const PROG_ID: &str = "DrkLauncher.Modpack";
+#[cfg(any(target_os = "windows", test))]
+const URL_DESCRIPTION: &str = "URL:DrkLauncher";
+
+/// Whether the launcher registers itself as the handler for pack files and `drk://` links.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct FileAssociationSettings {
+    #[serde(default = "crate::settings::default_true")]
+    pub enabled: bool,
+    #[serde(rename = "deepLinks", alias = "deep_links", default = "crate::settings::default_true")]
+    pub deep_links: bool,
+    /// Executable the current registration points at; it is redone when the launcher moves
+    #[serde(rename = "registeredExe", alias = "registered_exe", default)]
+    pub registered_exe: Option<String>,
+}
+
+impl Default for FileAssociationSettings {
+    fn default() -> Self {
+        FileAssociationSettings { enabled: true, deep_links: true, registered_exe: None }
+    }
+}
+
+/// What the launcher was opened with, routed by the frontend once it has loaded.
+#[derive(Serialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum OpenRequest {
+    /// A `.mrpack` or CurseForge `.zip` to create an instance from
+    Modpack { path: String },
+    /// `drk://import/<code>`: a share code (see `crate::share_code`)
+    ShareCode { code: String },
+    /// `drk://launch/<instance id>`
+    Launch {
+        #[serde(rename = "instanceId")]
+        instance_id: String,
+    },
+}
+
+#[derive(Default)]
+pub struct PendingOpen(pub Mutex<Option<OpenRequest>>);
+
+fn parse_link(link: &str) -> Option<OpenRequest> {
+    let url = url::Url::parse(link).ok()?;
+    let query_value = |key: &str| url.query_pairs().find(|(k, _)| k == key).map(|(_, v)| v.to_string());
+    let path = url.path().trim_matches('/').to_string();
+    let value = |key: &str| query_value(key).or_else(|| (!path.is_empty()).then(|| path.clone()));
+    match url.host_str()? {
+        "import" => value("code").map(|code| OpenRequest::ShareCode { code }),
+        "launch" | "play" => value("instance").map(|instance_id| OpenRequest::Launch { instance_id }),
+        _ => None,
+    }
+}
+
+pub fn parse_open(target: &str) -> Option<OpenRequest> {
+    let target = target.trim();
+    if target.to_lowercase().starts_with(&format!("{}:", URL_SCHEME)) {
+        return parse_link(target);
+    }
+    let extension = Path::new(target).extension()?.to_string_lossy().to_lowercase();
+    PACK_EXTENSIONS.contains(&extension.as_str()).then(|| OpenRequest::Modpack { path: target.to_string() })
+}
+
+/// `--open <file or link>` (or `--open=...`) from the command line.
+pub fn open_arg(args: &[String]) -> Option<OpenRequest> {
+    args.iter()
+        .position(|a| a == OPEN_FLAG)
+        .and_then(|i| args.get(i + 1).cloned())
+        .or_else(|| args.iter().find_map(|a| a.strip_prefix(OPEN_FLAG)?.strip_prefix('=').map(str::to_string)))
+        .and_then(|target| parse_open(&target))
+}
+
+#[tauri::command]
+pub fn take_pending_open(state: State<'_, PendingOpen>) -> Option<OpenRequest> {
+    state.0.lock().ok().and_then(|mut pending| pending.take())
+}
+
+/// One `reg add` under `HKCU\Software\Classes`; `None` is the key's default value.
+#[cfg(any(target_os = "windows", test))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct RegValue {
+    pub key: String,
+    pub name: Option<String>,
+    pub data: String,
+}
+
+#[cfg(any(target_os = "windows", test))]
+fn reg_value(key: &str, name: Option<&str>, data: &str) -> RegValue {
+    RegValue { key: key.to_string(), name: name.map(str::to_string), data: data.to_string() }
+}
+
+/// Registry values that make `exe` open `.mrpack` files by default, appear under "Open with"
+/// for `.zip` (which keeps its usual handler) and, with `deep_links`, handle `drk://`.
+#[cfg(any(target_os = "windows", test))]
+pub fn registry_values(exe: &Path, deep_links: bool) -> Vec<RegValue> {
+    let exe_str = exe.to_string_lossy();
+    let command = format!("\"{}\" {} \"%1\"", exe_str, OPEN_FLAG);
+    let exe_name = exe.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    let app_key = format!("Applications\\{}", exe_name);
+    let mut values = vec![
+        reg_value(PROG_ID, None, "Modpack de DrkLauncher"),
+        reg_value(&format!("{}\\DefaultIcon", PROG_ID), None, &format!("\"{}\",0", exe_str)),
+        reg_value(&format!("{}\\shell\\open\\command", PROG_ID), None, &command),
+        reg_value(".mrpack", None, PROG_ID),
+        reg_value(&format!("{}\\shell\\open\\command", app_key), None, &command),
+    ];
+    for extension in PACK_EXTENSIONS {
+        values.push(reg_value(&format!(".{}\\OpenWithProgids", extension), Some(PROG_ID), ""));
+        values.push(reg_value(&format!("{}\\SupportedTypes", app_key), Some(&format!(".{}", extension)), ""));
+    }
+    if deep_links {
+        values.push(reg_value(URL_SCHEME, None, URL_DESCRIPTION));
+        values.push(reg_value(URL_SCHEME, Some("URL Protocol"), ""));
+        values.push(reg_value(&format!("{}\\shell\\open\\command", URL_SCHEME), None, &command));
+    }
+    values
+}
+
+#[cfg(target_os = "windows")]
+mod registry {
+    use std::process::Command;
+    use super::RegValue;
+
+    fn classes(key: &str) -> String {
+        format!("HKCU\\Software\\Classes\\{}", key)
+    }
+
+    pub fn add(value: &RegValue) -> Result<(), String> {
+        let mut cmd = Command::new("reg");
+        cmd.args(["add", &classes(&value.key)]);
+        match &value.name {
+            Some(name) => cmd.args(["/v", name]),
+            None => cmd.arg("/ve"),
+        };
+        let status = cmd.args(["/t", "REG_SZ", "/d", &value.data, "/f"]).output().map_err(|e| e.to_string())?.status;
+        if !status.success() {
+            return Err(format!("No se pudo escribir {} en el registro", classes(&value.key)));
+        }
+        Ok(())
+    }
+
+    /// Default value of a key; `reg query` prints it as `(Default)` in the Windows language, so
+    /// only the data after the type is read.
+    pub fn default_value(key: &str) -> Option<String> {
+        let output = Command::new("reg").args(["query", &classes(key), "/ve"]).output().ok()?;
+        String::from_utf8_lossy(&output.stdout).lines()
+            .find_map(|line| line.split_once("REG_SZ").map(|(_, data)| data.trim().to_string()))
+    }
+
+    /// Deletes a key, or one value of it; missing keys are not an error.
+    pub fn delete(key: &str, name: Option<&str>) {
+        let mut cmd = Command::new("reg");
+        cmd.args(["delete", &classes(key)]);
+        match name {
+            Some(name) => cmd.args(["/v", name]),
+            None => cmd.arg("/ve"),
+        };
+        let _ = cmd.arg("/f").output();
+    }
+
+    pub fn delete_key(key: &str) {
+        let _ = Command::new("reg").args(["delete", &classes(key), "/f"]).output();
+    }
+}
+
+/// Start Menu entry so the launcher shows up by name in "Open with"; the installer's own
+/// shortcut (same name) is kept when there is one.
+#[cfg(target_os = "windows")]
+fn ensure_start_menu_shortcut(exe: &Path) -> Result<(), String> {
+    let programs = std::env::var_os("APPDATA")
+        .map(|p| std::path::PathBuf::from(p).join("Microsoft").join("Windows").join("Start Menu").join("Programs"))
+        .ok_or("APPDATA not set")?;
+    let link = programs.join("DrkLauncher.lnk");
+    if link.exists() {
+        return Ok(());
+    }
+    let quote = |s: &str| s.replace('\'', "''");
+    let script = format!(
+        "$s=(New-Object -ComObject WScript.Shell).CreateShortcut('{}');$s.TargetPath='{}';$s.WorkingDirectory='{}';$s.Save()",
+        quote(&link.to_string_lossy()),
+        quote(&exe.to_string_lossy()),
+        quote(&exe.parent().map(|p| p.to_string_lossy().to_string()).unwrap_or_default()),
+    );
+    let status = std::process::Command::new("powershell")
+        .args(["-NoProfile", "-NonInteractive", "-Command", &script])
+        .status()
+        .map_err(|e| e.to_string())?;
+    if !status.success() {
+        return Err("No se pudo crear el acceso directo del menú Inicio".to_string());
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "windows")]
+fn register(exe: &Path, deep_links: bool) -> Result<(), String> {
+    for value in registry_values(exe, deep_links) {
+        registry::add(&value)?;
+    }
+    if !deep_links {
+        unregister_links();
+    }
+    if let Err(e) = ensure_start_menu_shortcut(exe) {
+        eprintln!("[file_associations] Start Menu shortcut: {}", e);
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "windows")]
+fn unregister_links() {
+    // Another launcher may own drk:// by now
+    if registry::default_value(URL_SCHEME).as_deref() == Some(URL_DESCRIPTION) {
+        registry::delete_key(URL_SCHEME);
+    }
+}
+
+/// Removes what `register` wrote, leaving `.mrpack` alone when another program took it over.
+#[cfg(target_os = "windows")]
+fn unregister(exe: &Path) -> Result<(), String> {
+    registry::delete_key(PROG_ID);
+    if let Some(name) = exe.file_name() {
+        registry::delete_key(&format!("Applications\\{}", name.to_string_lossy()));
+    }
+    for extension in PACK_EXTENSIONS {
+        registry::delete(&format!(".{}\\OpenWithProgids", extension), Some(PROG_ID));
+    }
+    if registry::default_value(".mrpack").as_deref() == Some(PROG_ID) {
+        registry::delete(".mrpack", None);
+    }
+    unregister_links();
+    Ok(())
+}
+
+#[cfg(not(target_os = "windows"))]
+fn register(_exe: &Path, _deep_links: bool) -> Result<(), String> {
+    Err("Las asociaciones de archivos solo se registran en Windows".to_string())
+}
+
+#[cfg(not(target_os = "windows"))]
+fn unregister(_exe: &Path) -> Result<(), String> {
+    Err("Las asociaciones de archivos solo se registran en Windows".to_string())
+}
+
+/// First run (or a launcher that moved): registers the associations in the background unless
+/// they were turned off.
+pub fn start() {
+    if !cfg!(target_os = "windows") {
+        return;
+    }
+    std::thread::spawn(|| {
+        let mut settings = crate::settings::load_settings();
+        let Ok(exe) = crate::shortcuts::launcher_exe() else { return };
+        let exe_str = exe.to_string_lossy().to_string();
+        let associations = &mut settings.file_associations;
+        if !associations.enabled || associations.registered_exe.as_deref() == Some(exe_str.as_str()) {
+            return;
+        }
+        match register(&exe, associations.deep_links) {
+            Ok(()) => {
+                associations.registered_exe = Some(exe_str);
+                let _ = crate::settings::save_settings(&settings);
+            }
+            Err(e) => eprintln!("[file_associations] {}", e),
+        }
+    });
+}
+
+#[tauri::command]
+pub fn get_file_associations() -> FileAssociationSettings {
+    crate::settings::load_settings().file_associations
+}
+
+/// Registers or removes the launcher as the handler of pack files and `drk://` links.
+#[tauri::command]
+pub async fn set_file_associations(enabled: bool, deep_links: bool) -> Result<(), String> {
+    crate::settings::ensure_not_guest()?;
+    crate::run_blocking(move || {
+        let exe = crate::shortcuts::launcher_exe()?;
+        let mut settings = crate::settings::load_settings();
+        if enabled {
+            register(&exe, deep_links)?;
+        } else {
+            unregister(&exe)?;
+        }
+        settings.file_associations = FileAssociationSettings {
+            enabled,
+            deep_links,
+            registered_exe: enabled.then(|| exe.to_string_lossy().to_string()),
+        };
+        crate::settings::save_settings(&settings)
+    }).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(items: &[&str]) -> Vec<String> {
+        items.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn opens_are_routed_by_kind() {
+        assert_eq!(open_arg(&args(&["drk.exe", "--open", "C:\\Descargas\\Pack.MRPACK"])), Some(OpenRequest::Modpack { path: "C:\\Descargas\\Pack.MRPACK".into() }));
+        assert_eq!(open_arg(&args(&["drk.exe", "--open=drk://import/drk1.abc.12"])), Some(OpenRequest::ShareCode { code: "drk1.abc.12".into() }));
+        assert_eq!(parse_open("drk://import?code=drk1.a%2Bb.1"), Some(OpenRequest::ShareCode { code: "drk1.a+b.1".into() }));
+        assert_eq!(parse_open("drk://launch/evento-2024/"), Some(OpenRequest::Launch { instance_id: "evento-2024".into() }));
+        assert_eq!(parse_open("drk://borrar/todo"), None);
+        assert_eq!(parse_open("C:\\mods\\sodium.jar"), None);
+        assert_eq!(open_arg(&args(&["drk.exe", "--launch-instance", "x"])), None);
+    }
+
+    #[test]
+    fn zip_is_only_offered_under_open_with() {
+        let values = registry_values(Path::new("C:\\Program Files\\drklauncher\\drklauncher.exe"), false);
+        let command = "\"C:\\Program Files\\drklauncher\\drklauncher.exe\" --open \"%1\"";
+        assert!(values.contains(&reg_value("DrkLauncher.Modpack\\shell\\open\\command", None, command)));
+        assert!(values.contains(&reg_value(".mrpack", None, "DrkLauncher.Modpack")));
+        assert!(values.contains(&reg_value(".zip\\OpenWithProgids", Some("DrkLauncher.Modpack"), "")));
+        assert!(!values.iter().any(|v| v.key == ".zip" && v.name.is_none()));
+        assert!(!values.iter().any(|v| v.key.starts_with(URL_SCHEME)));
+        let with_links = registry_values(Path::new("C:\\drk\\drklauncher.exe"), true);
+        assert!(with_links.contains(&reg_value("drk", Some("URL Protocol"), "")));
+    }
+}
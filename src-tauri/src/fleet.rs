@@ -0,0 +1,374 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use crate::host_pins::PinMode;
+use crate::launch_history::FailureKind;
+use crate::settings::{load_settings, save_settings, LauncherSettings};
+
+const DEFAULT_REPORT_MINUTES: u64 = 15;
+const MAX_REPORT_MINUTES: u64 = 240;
+const REPORT_TIMEOUT: Duration = Duration::from_secs(15);
+/// Failed launches sent with each report
+const REPORTED_ERRORS: usize = 5;
+const MAX_ERROR_CHARS: usize = 200;
+/// Older override answers are replays (or a clock far off)
+const MAX_OVERRIDE_AGE_SECS: u64 = 300;
+
+/// Where this machine reports itself so organizers see every booth PC in one place.
+#[derive(Serialize, Deserialize, Clone, Default, PartialEq, Debug, JsonSchema)]
+pub struct FleetSettings {
+    /// Receives the `MachineReport` JSON as a POST; none turns reporting off
+    #[serde(default)]
+    pub url: Option<String>,
+    /// Shown to organizers next to the machine id, e.g. `Stand 4`
+    #[serde(default)]
+    pub label: Option<String>,
+    /// Unix seconds of the last report the fleet server accepted
+    #[serde(rename = "lastReportAt", alias = "last_report_at", default)]
+    pub last_report_at: Option<u64>,
+    #[serde(rename = "lastError", alias = "last_error", default)]
+    pub last_error: Option<String>,
+    /// Minutes between reports; the fleet server may change it
+    #[serde(rename = "reportMinutes", alias = "report_minutes", default)]
+    pub report_minutes: Option<u64>,
+    /// Settings the fleet server changed in its last override
+    #[serde(default)]
+    pub overridden: Vec<String>,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct InstanceSummary {
+    pub id: String,
+    pub name: String,
+    pub version: String,
+    pub modloader: Option<String>,
+    /// Everything needed to play is on disk (see `check_instance_ready`)
+    pub ready: bool,
+    #[serde(rename = "packVersion")]
+    pub pack_version: Option<String>,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct RunningGame {
+    #[serde(rename = "instanceId")]
+    pub instance_id: String,
+    /// Unix seconds
+    pub since: u64,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct ReportedError {
+    #[serde(rename = "instanceId")]
+    pub instance_id: String,
+    /// Unix seconds the launch started
+    pub at: u64,
+    pub failure: Option<FailureKind>,
+    pub message: Option<String>,
+}
+
+/// The at-a-glance part of a report, what the organizers' dashboard colors machines by.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct Health {
+    /// Share of instances with everything on disk, 0-100; 100 with no instances
+    #[serde(rename = "readyPercent")]
+    pub ready_percent: u8,
+    #[serde(rename = "runningGames")]
+    pub running_games: Vec<RunningGame>,
+    /// Newest failed or crashed launches first
+    #[serde(rename = "lastErrors")]
+    pub last_errors: Vec<ReportedError>,
+}
+
+/// What the fleet server gets every report.
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct MachineReport {
+    #[serde(rename = "machineId")]
+    pub machine_id: String,
+    pub label: Option<String>,
+    #[serde(rename = "launcherVersion")]
+    pub launcher_version: String,
+    /// Mojang's names: `windows`, `osx`, `linux`
+    pub os: String,
+    #[serde(rename = "availableDiskBytes")]
+    pub available_disk_bytes: Option<u64>,
+    pub instances: Vec<InstanceSummary>,
+    pub health: Health,
+    #[serde(rename = "reportedAt")]
+    pub reported_at: u64,
+}
+
+pub fn summarize(instance: &crate::Instance, ready: bool) -> InstanceSummary {
+    InstanceSummary {
+        id: instance.id.clone(),
+        name: instance.name.clone(),
+        version: instance.version.clone(),
+        modloader: instance.modloader.clone(),
+        ready,
+        pack_version: instance.info_card.as_ref().and_then(|c| c.pack_version.clone()),
+    }
+}
+
+pub fn ready_percent(instances: &[InstanceSummary]) -> u8 {
+    if instances.is_empty() {
+        return 100;
+    }
+    (instances.iter().filter(|i| i.ready).count() * 100 / instances.len()) as u8
+}
+
+fn health(instances: &[InstanceSummary]) -> Health {
+    let running_games = crate::launch_history::running().into_iter()
+        .map(|(instance_id, since)| RunningGame { instance_id, since })
+        .collect();
+    let last_errors = crate::launch_history::latest_failures(REPORTED_ERRORS).into_iter()
+        .map(|(instance_id, attempt)| ReportedError {
+            instance_id,
+            at: attempt.started_at,
+            failure: attempt.failure,
+            message: attempt.message.map(|m| m.chars().take(MAX_ERROR_CHARS).collect()),
+        })
+        .collect();
+    Health { ready_percent: ready_percent(instances), running_games, last_errors }
+}
+
+/// Checks every instance on disk, so it must run off the async runtime.
+pub fn build_report(label: Option<String>) -> MachineReport {
+    let instances: Vec<_> = crate::load_instances().iter()
+        .map(|i| summarize(i, crate::instance_readiness(&i.id).map(|r| r.ready).unwrap_or(false)))
+        .collect();
+    MachineReport {
+        health: health(&instances),
+        machine_id: crate::platform::machine_id(),
+        label,
+        launcher_version: env!("CARGO_PKG_VERSION").to_string(),
+        os: crate::minecraft::utils::get_os_name().to_string(),
+        available_disk_bytes: crate::platform::data_dir_available_space(),
+        instances,
+        reported_at: crate::unix_now(),
+    }
+}
+
+/// Settings the fleet server may change on one machine, e.g. a slower report interval or the
+/// data saver on a booth with a bad connection. Missing fields stay as they are.
+#[derive(Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct FleetOverrides {
+    #[serde(default)]
+    pub label: Option<String>,
+    #[serde(rename = "reportMinutes", alias = "report_minutes", default)]
+    pub report_minutes: Option<u64>,
+    #[serde(rename = "dataSaver", alias = "data_saver", default)]
+    pub data_saver: Option<bool>,
+    #[serde(rename = "statusUrl", alias = "status_url", default)]
+    pub status_url: Option<String>,
+    #[serde(rename = "maintenanceUrl", alias = "maintenance_url", default)]
+    pub maintenance_url: Option<String>,
+    #[serde(rename = "hostPinning", alias = "host_pinning", default)]
+    pub host_pinning: Option<PinMode>,
+}
+
+impl FleetOverrides {
+    /// Writes the overrides into `settings`; returns the names of the settings that changed.
+    pub fn apply(self, settings: &mut LauncherSettings) -> Vec<String> {
+        fn set<T: PartialEq>(changed: &mut Vec<String>, name: &str, field: &mut T, value: Option<T>) {
+            if let Some(value) = value.filter(|v| *v != *field) {
+                *field = value;
+                changed.push(name.to_string());
+            }
+        }
+        let blank_to_none = |url: String| Some(url.trim().to_string()).filter(|u| !u.is_empty());
+        let mut changed = Vec::new();
+        set(&mut changed, "label", &mut settings.fleet.label, self.label.map(blank_to_none));
+        set(&mut changed, "reportMinutes", &mut settings.fleet.report_minutes, self.report_minutes.map(|m| Some(m.clamp(1, MAX_REPORT_MINUTES))));
+        set(&mut changed, "dataSaver", &mut settings.data_saver, self.data_saver);
+        set(&mut changed, "statusUrl", &mut settings.status_url, self.status_url.map(blank_to_none));
+        set(&mut changed, "maintenanceUrl", &mut settings.maintenance_url, self.maintenance_url.map(blank_to_none));
+        set(&mut changed, "hostPinning", &mut settings.host_pinning, self.host_pinning);
+        changed
+    }
+}
+
+/// Same envelope as remote commands: `payload` is the JSON exactly as signed, `signature` its
+/// HMAC-SHA256 in hex with the command secret.
+#[derive(Deserialize)]
+struct Envelope {
+    #[serde(rename = "type")]
+    kind: String,
+    payload: String,
+    signature: String,
+}
+
+#[derive(Deserialize)]
+struct SignedOverrides {
+    #[serde(rename = "machineId", alias = "machine_id")]
+    machine_id: String,
+    #[serde(rename = "issuedAt", alias = "issued_at")]
+    issued_at: u64,
+    overrides: FleetOverrides,
+}
+
+/// Overrides in the fleet server's answer to a report. Any other answer (empty, `ok`, plain
+/// JSON) carries none; overrides are only taken signed and addressed to this machine.
+pub fn parse_response(body: &str, secret: Option<&str>, machine_id: &str, now: u64) -> Result<Option<FleetOverrides>, String> {
+    let Some(envelope) = serde_json::from_str::<Envelope>(body).ok().filter(|e| e.kind == "fleetOverrides") else {
+        return Ok(None);
+    };
+    let secret = secret.filter(|s| !s.is_empty()).ok_or("Ajustes de la flota ignorados: no hay secreto de comandos configurado")?;
+    if !crate::remote_commands::signature_valid(secret, &envelope.payload, &envelope.signature) {
+        return Err("Ajustes de la flota ignorados: firma no válida".to_string());
+    }
+    let signed: SignedOverrides = serde_json::from_str(&envelope.payload).map_err(|e| format!("Ajustes de la flota no válidos: {}", e))?;
+    if signed.machine_id != machine_id {
+        return Err("Ajustes de la flota ignorados: son para otro equipo".to_string());
+    }
+    if signed.issued_at + MAX_OVERRIDE_AGE_SECS < now || signed.issued_at > now + MAX_OVERRIDE_AGE_SECS {
+        return Err("Ajustes de la flota ignorados: caducados".to_string());
+    }
+    Ok(Some(signed.overrides))
+}
+
+/// Posts the report and returns the server's answer.
+fn post(url: &str, report: &MachineReport) -> Result<String, String> {
+    let client = reqwest::blocking::Client::builder()
+        .user_agent(crate::http::user_agent())
+        .timeout(REPORT_TIMEOUT)
+        .build()
+        .map_err(|e| e.to_string())?;
+    let resp = crate::http::send(&client, client.post(url).json(report))?;
+    if !resp.status().is_success() {
+        return Err(format!("El servidor de la flota respondió {}", resp.status()));
+    }
+    resp.text().map_err(|e| e.to_string())
+}
+
+/// Sends one report when a fleet URL is set, applies the overrides that came back and records
+/// how it went.
+fn report() -> Result<(), String> {
+    let fleet = load_settings().fleet;
+    let Some(url) = fleet.url.filter(|u| !u.trim().is_empty()) else { return Ok(()) };
+    let report = build_report(fleet.label);
+    let result = post(&url, &report);
+    let mut settings = load_settings();
+    let result = result.and_then(|body| {
+        settings.fleet.last_report_at = Some(crate::unix_now());
+        let secret = settings.announcements.command_secret.clone();
+        let overrides = parse_response(&body, secret.as_deref(), &report.machine_id, crate::unix_now())?;
+        if let Some(overrides) = overrides {
+            let changed = overrides.apply(&mut settings);
+            if !changed.is_empty() {
+                eprintln!("[fleet] Overrides applied: {}", changed.join(", "));
+            }
+            settings.fleet.overridden = changed;
+        }
+        Ok(())
+    });
+    settings.fleet.last_error = result.as_ref().err().cloned();
+    save_settings(&settings)?;
+    result
+}
+
+fn report_interval() -> Duration {
+    let minutes = load_settings().fleet.report_minutes.unwrap_or(DEFAULT_REPORT_MINUTES);
+    Duration::from_secs(minutes.clamp(1, MAX_REPORT_MINUTES) * 60)
+}
+
+/// Fixes the machine id on first run, then reports on startup and every few minutes while a
+/// fleet URL is set.
+pub fn start() {
+    std::thread::spawn(|| {
+        crate::platform::machine_id();
+        loop {
+            if let Err(e) = report() {
+                eprintln!("[fleet] Report: {}", e);
+            }
+            std::thread::sleep(report_interval());
+        }
+    });
+}
+
+#[tauri::command]
+pub fn get_fleet_registration() -> FleetSettings {
+    load_settings().fleet
+}
+
+/// Enrolls this machine in the organizers' fleet view and sends the first report right away;
+/// an empty URL stops reporting.
+#[tauri::command]
+pub async fn register_machine(password: String, fleet_url: String, label: Option<String>) -> Result<FleetSettings, String> {
+    crate::ensure_admin(password)?;
+    let url = Some(fleet_url.trim().to_string()).filter(|u| !u.is_empty());
+    if let Some(url) = &url {
+        reqwest::Url::parse(url).map_err(|e| format!("URL de la flota no válida: {}", e))?;
+    }
+    let mut settings = load_settings();
+    settings.fleet = FleetSettings {
+        url,
+        label: label.map(|l| l.trim().to_string()).filter(|l| !l.is_empty()),
+        ..FleetSettings::default()
+    };
+    save_settings(&settings)?;
+    crate::run_blocking(|| {
+        report()?;
+        Ok(load_settings().fleet)
+    }).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_use_the_fleet_field_names() {
+        let instance: crate::Instance = serde_json::from_value(serde_json::json!({
+            "id": "evento", "name": "Evento", "version": "1.20.1", "icon": "", "path": "/x",
+            "modloader": "forge", "infoCard": {"pack_version": "2.3.1"}
+        })).unwrap();
+        let report = MachineReport {
+            machine_id: "A1B2-C3D4-E5F6-0718".to_string(),
+            label: Some("Stand 4".to_string()),
+            launcher_version: "1.0.0".to_string(),
+            os: "windows".to_string(),
+            available_disk_bytes: Some(10),
+            instances: vec![summarize(&instance, true)],
+            health: Health { ready_percent: 100, running_games: vec![RunningGame { instance_id: "evento".to_string(), since: 1 }], last_errors: vec![] },
+            reported_at: 1,
+        };
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["machineId"], "A1B2-C3D4-E5F6-0718");
+        assert_eq!(json["availableDiskBytes"], 10);
+        assert_eq!(json["instances"][0]["packVersion"], "2.3.1");
+        assert_eq!(json["instances"][0]["ready"], true);
+        assert_eq!(json["health"]["runningGames"][0]["instanceId"], "evento");
+        assert_eq!(serde_json::from_str::<FleetSettings>("{}").unwrap(), FleetSettings::default());
+    }
+
+    #[test]
+    fn ready_share_counts_prepared_instances() {
+        let summary = |ready| InstanceSummary { id: String::new(), name: String::new(), version: String::new(), modloader: None, ready, pack_version: None };
+        assert_eq!(ready_percent(&[]), 100);
+        assert_eq!(ready_percent(&[summary(true), summary(false), summary(false)]), 33);
+    }
+
+    fn envelope(payload: &serde_json::Value, secret: &str) -> String {
+        let payload = payload.to_string();
+        serde_json::json!({ "type": "fleetOverrides", "signature": crate::remote_commands::sign(secret, &payload), "payload": payload }).to_string()
+    }
+
+    #[test]
+    fn only_signed_overrides_for_this_machine_apply() {
+        let now = 1_000_000;
+        let payload = serde_json::json!({ "machineId": "A1B2", "issuedAt": now, "overrides": { "dataSaver": true, "reportMinutes": 9999, "statusUrl": " " } });
+        let overrides = parse_response(&envelope(&payload, "s"), Some("s"), "A1B2", now).unwrap().unwrap();
+
+        let mut settings = LauncherSettings { status_url: Some("https://estado.example".to_string()), ..Default::default() };
+        assert_eq!(overrides.clone().apply(&mut settings), vec!["reportMinutes", "dataSaver", "statusUrl"]);
+        assert_eq!(settings.fleet.report_minutes, Some(MAX_REPORT_MINUTES));
+        assert!(settings.data_saver && settings.status_url.is_none());
+        assert!(overrides.apply(&mut settings).is_empty());
+
+        assert_eq!(parse_response("", Some("s"), "A1B2", now), Ok(None));
+        assert_eq!(parse_response(r#"{"ok": true}"#, Some("s"), "A1B2", now), Ok(None));
+        assert!(parse_response(&envelope(&payload, "otro"), Some("s"), "A1B2", now).is_err());
+        assert!(parse_response(&envelope(&payload, "s"), None, "A1B2", now).is_err());
+        assert!(parse_response(&envelope(&payload, "s"), Some("s"), "FFFF", now).is_err());
+        assert!(parse_response(&envelope(&payload, "s"), Some("s"), "A1B2", now + 3600).is_err());
+    }
+}
@@ -0,0 +1,75 @@
+use schemars::JsonSchema;
+use serde::Serialize;
+use crate::minecraft::modrinth::latest_file_url;
+
+/// Steam Deck native resolution
+pub const DECK_RESOLUTION: (u32, u32) = (1280, 800);
+
+/// Controller support mod per loader, as Modrinth project slugs.
+const CONTROLLER_MODS: [(&str, &str); 2] = [("fabric", "controlify"), ("forge", "controllable")];
+
+pub fn controller_mod_for(loader: &str) -> Option<&'static str> {
+    CONTROLLER_MODS.iter().find(|(l, _)| *l == loader).map(|(_, slug)| *slug)
+}
+
+#[derive(Serialize, Clone, Debug, Default, JsonSchema)]
+pub struct GamepadProfileResult {
+    #[serde(rename = "modUrl")]
+    pub mod_url: Option<String>,
+    pub shortcuts: Vec<String>,
+    /// Parts of the profile that could not be applied; the rest still is
+    pub warnings: Vec<String>,
+}
+
+/// Turns an instance into a controller/Steam Deck profile: 1280x800, the loader's controller
+/// mod added to the mod list, and optionally a Steam shortcut for Game Mode.
+#[tauri::command]
+pub async fn apply_gamepad_profile(instance_id: String, steam_shortcut: bool) -> Result<GamepadProfileResult, String> {
+    crate::run_blocking(move || {
+        crate::settings::ensure_not_guest()?;
+        let mut instances = crate::load_instances();
+        let instance = instances.iter_mut().find(|i| i.id == instance_id)
+            .ok_or("Instance not found")?;
+        let mut result = GamepadProfileResult::default();
+
+        instance.resolution_width = Some(DECK_RESOLUTION.0);
+        instance.resolution_height = Some(DECK_RESOLUTION.1);
+        instance.gamepad_profile = Some(true);
+
+        let loader = instance.modloader.clone().unwrap_or_else(|| "vanilla".to_string());
+        match controller_mod_for(&loader) {
+            Some(slug) => match latest_file_url(slug, &loader, &instance.version) {
+                Ok(url) => {
+                    let mods = instance.mods.get_or_insert_with(Vec::new);
+                    if !mods.contains(&url) {
+                        mods.push(url.clone());
+                    }
+                    result.mod_url = Some(url);
+                }
+                Err(e) => result.warnings.push(e),
+            },
+            None => result.warnings.push("Vanilla no admite mods de mando; usa Fabric o Forge".to_string()),
+        }
+        crate::save_instances(&instances);
+
+        if steam_shortcut {
+            match crate::shortcuts::create_instance_shortcut(instance_id, true) {
+                Ok(paths) => result.shortcuts = paths,
+                Err(e) => result.warnings.push(e),
+            }
+        }
+        Ok(result)
+    }).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn controller_mods_exist_only_for_mod_loaders() {
+        assert_eq!(controller_mod_for("fabric"), Some("controlify"));
+        assert_eq!(controller_mod_for("forge"), Some("controllable"));
+        assert_eq!(controller_mod_for("vanilla"), None);
+    }
+}
@@ -0,0 +1,376 @@
+use chrono::NaiveDate;
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::process::Command;
+use std::sync::OnceLock;
+use crate::minecraft::launch_logic::{parse_mc_version, LinuxTweaks};
+
+// Drivers this old predate most of the OpenGL fixes modern Minecraft relies on
+const OLD_DRIVER_DAYS: i64 = 4 * 365;
+// GLFW failing to get an OpenGL context: 65542 "driver does not appear to support OpenGL",
+// 65543 "requested version not available"
+const GL_CONTEXT_ERRORS: [&str; 3] = ["GLFW error 65542", "GLFW error 65543", "Pixel format not accelerated"];
+
+#[derive(Serialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct GpuAdapter {
+    pub name: String,
+    #[serde(rename = "driverVersion", skip_serializing_if = "Option::is_none")]
+    pub driver_version: Option<String>,
+    /// `YYYY-MM-DD`; Windows only
+    #[serde(rename = "driverDate", skip_serializing_if = "Option::is_none")]
+    pub driver_date: Option<String>,
+    /// Highest version a real context reported (Linux, through `glxinfo`)
+    #[serde(rename = "openglVersion", skip_serializing_if = "Option::is_none")]
+    pub opengl_version: Option<String>,
+}
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum GraphicsStatus {
+    Ok,
+    /// Should start, but an old driver is the usual suspect if it crashes
+    Warning,
+    /// The game can't create its OpenGL context here
+    Unsupported,
+}
+
+#[derive(Serialize, Clone, Debug, JsonSchema)]
+pub struct DriverLink {
+    pub vendor: String,
+    pub url: String,
+}
+
+#[derive(Serialize, Clone, Debug, JsonSchema)]
+pub struct GraphicsCheck {
+    pub status: GraphicsStatus,
+    /// OpenGL version the Minecraft version needs, e.g. `3.2`
+    pub required: String,
+    pub adapters: Vec<GpuAdapter>,
+    pub problems: Vec<String>,
+    /// Driver downloads for the adapters with problems
+    pub links: Vec<DriverLink>,
+}
+
+/// Minecraft 1.17 moved to the OpenGL 3.2 core profile; older versions get by with 2.0.
+pub fn required_opengl(mc_version: &str) -> (u32, u32) {
+    match parse_mc_version(mc_version) {
+        Some((minor, _)) if minor < 17 => (2, 0),
+        Some(_) => (3, 2),
+        // Snapshots and odd ids: only adapters without any real driver fail 2.0
+        None => (2, 0),
+    }
+}
+
+fn vendor(name: &str) -> Option<&'static str> {
+    let name = name.to_lowercase();
+    if name.contains("nvidia") || name.contains("geforce") || name.contains("quadro") {
+        Some("NVIDIA")
+    } else if name.contains("amd") || name.contains("radeon") || name.starts_with("ati ") {
+        Some("AMD")
+    } else if name.contains("intel") {
+        Some("Intel")
+    } else {
+        None
+    }
+}
+
+fn add_link(links: &mut Vec<DriverLink>, vendor: &str) {
+    if links.iter().any(|l| l.vendor == vendor) {
+        return;
+    }
+    let url = match vendor {
+        "NVIDIA" => "https://www.nvidia.com/es-es/drivers/",
+        "AMD" => "https://www.amd.com/es/support/download/drivers.html",
+        _ => "https://www.intel.es/content/www/es/es/support/detect.html",
+    };
+    links.push(DriverLink { vendor: vendor.to_string(), url: url.to_string() });
+}
+
+/// `4.6.0 NVIDIA 535.54.03` -> (4, 6)
+fn parse_gl_version(text: &str) -> Option<(u32, u32)> {
+    let number = text.split_whitespace().next()?;
+    let mut parts = number.split('.');
+    Some((parts.next()?.parse().ok()?, parts.next()?.parse().ok()?))
+}
+
+/// Highest OpenGL version adapters of this name ever got a driver for, for the ones too old to
+/// run modern Minecraft. Windows names; `None` when the name says nothing.
+fn known_max_opengl(name: &str) -> Option<(u32, u32)> {
+    let name = name.to_lowercase();
+    // No vendor driver installed: Windows falls back to its GDI renderer
+    if name.contains("microsoft basic") || name.contains("standard vga") {
+        return Some((1, 1));
+    }
+    if name.contains("gma") || name.contains("graphics media accelerator") || name.contains("express chipset") || name.contains("4 series") {
+        return Some((2, 1));
+    }
+    if name.contains("hd graphics 2000") || name.contains("hd graphics 3000") {
+        return Some((3, 1));
+    }
+    let geforce_series = name.split("geforce ").nth(1).and_then(|rest| rest.chars().next());
+    if matches!(geforce_series, Some('6' | '7')) && !name.contains("gtx") && !name.contains("rtx") {
+        return Some((2, 1));
+    }
+    if name.contains("radeon x") || name.contains("radeon xpress") {
+        return Some((2, 0));
+    }
+    None
+}
+
+fn max_opengl(adapter: &GpuAdapter) -> Option<(u32, u32)> {
+    adapter.opengl_version.as_deref().and_then(parse_gl_version).or_else(|| known_max_opengl(&adapter.name))
+}
+
+fn driver_age_days(adapter: &GpuAdapter, today: NaiveDate) -> Option<i64> {
+    let date = NaiveDate::parse_from_str(adapter.driver_date.as_deref()?, "%Y-%m-%d").ok()?;
+    Some((today - date).num_days())
+}
+
+/// One adapter against what the game needs.
+fn assess(adapter: &GpuAdapter, required: (u32, u32), today: NaiveDate) -> (GraphicsStatus, Option<String>) {
+    let (major, minor) = required;
+    let name = adapter.name.to_lowercase();
+    if name.contains("llvmpipe") || name.contains("softpipe") || name.contains("software rasterizer") {
+        return (GraphicsStatus::Warning, Some(format!("{} dibuja por software: el juego irá muy lento. Instala el driver de tu tarjeta gráfica.", adapter.name)));
+    }
+    match max_opengl(adapter) {
+        Some(found) if found < required => {
+            let detail = if found <= (1, 1) {
+                format!("{} no tiene instalado el driver de la tarjeta gráfica", adapter.name)
+            } else {
+                format!("{} solo llega a OpenGL {}.{} y el juego necesita {}.{}", adapter.name, found.0, found.1, major, minor)
+            };
+            return (GraphicsStatus::Unsupported, Some(detail));
+        }
+        _ => {}
+    }
+    match driver_age_days(adapter, today) {
+        Some(days) if days > OLD_DRIVER_DAYS && vendor(&adapter.name).is_some() => (
+            GraphicsStatus::Warning,
+            Some(format!("El driver de {} es de hace {} años; si el juego se cierra al abrir, actualízalo", adapter.name, days / 365)),
+        ),
+        _ => (GraphicsStatus::Ok, None),
+    }
+}
+
+/// The game renders on one adapter, usually the best one; a hybrid laptop where only the
+/// integrated one is too old still works once Java is set to the dedicated GPU.
+pub fn evaluate(adapters: Vec<GpuAdapter>, mc_version: &str, today: NaiveDate) -> GraphicsCheck {
+    let required = required_opengl(mc_version);
+    let assessed: Vec<(GraphicsStatus, Option<String>)> = adapters.iter().map(|a| assess(a, required, today)).collect();
+    let best = assessed.iter().map(|(status, _)| *status).min().unwrap_or(GraphicsStatus::Ok);
+    let mut problems = Vec::new();
+    let mut links = Vec::new();
+    for (adapter, (status, problem)) in adapters.iter().zip(&assessed) {
+        let Some(problem) = problem else { continue };
+        if *status == GraphicsStatus::Unsupported && best < GraphicsStatus::Unsupported {
+            problems.push(format!("{}. En portátiles con dos gráficas, configura Java para usar la tarjeta dedicada.", problem));
+        } else {
+            problems.push(problem.clone());
+        }
+        // Unnamed adapters without a driver are nearly always Intel integrated graphics
+        add_link(&mut links, vendor(&adapter.name).unwrap_or("Intel"));
+    }
+    let status = if best == GraphicsStatus::Ok && !problems.is_empty() { GraphicsStatus::Warning } else { best };
+    GraphicsCheck { status, required: format!("{}.{}", required.0, required.1), adapters, problems, links }
+}
+
+/// `Name|DriverVersion|YYYY-MM-DD` lines from the PowerShell query in `probe`.
+fn parse_windows(output: &str) -> Vec<GpuAdapter> {
+    output.lines()
+        .filter_map(|line| {
+            let mut parts = line.trim().split('|');
+            let name = parts.next()?.trim();
+            let non_empty = |s: Option<&str>| s.map(str::trim).filter(|s| !s.is_empty()).map(str::to_string);
+            (!name.is_empty()).then(|| GpuAdapter {
+                name: name.to_string(),
+                driver_version: non_empty(parts.next()),
+                driver_date: non_empty(parts.next()),
+                opengl_version: None,
+            })
+        })
+        .collect()
+}
+
+/// `glxinfo -B`: the renderer that actually answered and the best profile it offers.
+fn parse_glxinfo(output: &str) -> Vec<GpuAdapter> {
+    let field = |key: &str| output.lines().find_map(|l| l.trim().strip_prefix(key)).map(|v| v.trim().to_string());
+    let Some(name) = field("OpenGL renderer string:") else { return Vec::new() };
+    let versions = [field("OpenGL core profile version string:"), field("OpenGL version string:")];
+    let best = versions.iter().flatten().max_by_key(|v| parse_gl_version(v));
+    let driver_version = best.and_then(|v| v.split_once(' ')).map(|(_, driver)| driver.trim().to_string());
+    vec![GpuAdapter {
+        name,
+        driver_version,
+        driver_date: None,
+        opengl_version: best.and_then(|v| v.split_whitespace().next()).map(str::to_string),
+    }]
+}
+
+fn probe(tweaks: &LinuxTweaks) -> Vec<GpuAdapter> {
+    if cfg!(target_os = "windows") {
+        let mut cmd = Command::new("powershell");
+        cmd.args([
+            "-NoProfile",
+            "-Command",
+            "Get-CimInstance Win32_VideoController | ForEach-Object { '{0}|{1}|{2}' -f $_.Name, $_.DriverVersion, $(if ($_.DriverDate) { $_.DriverDate.ToString('yyyy-MM-dd') }) }",
+        ]);
+        #[cfg(target_os = "windows")]
+        {
+            use std::os::windows::process::CommandExt;
+            cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
+        }
+        return cmd.output().map(|o| parse_windows(&String::from_utf8_lossy(&o.stdout))).unwrap_or_default();
+    }
+    if cfg!(target_os = "linux") {
+        // Same environment as the game, so PRIME offload probes the GPU it will render on
+        let (set, remove) = tweaks.env();
+        let mut cmd = Command::new("glxinfo");
+        cmd.arg("-B").envs(set);
+        for name in remove {
+            cmd.env_remove(name);
+        }
+        return cmd.output()
+            .ok()
+            .filter(|o| o.status.success())
+            .map(|o| parse_glxinfo(&String::from_utf8_lossy(&o.stdout)))
+            .unwrap_or_default();
+    }
+    // Every Mac that runs a supported macOS has OpenGL 4.1
+    Vec::new()
+}
+
+/// Detected once per run, on first launch, for each PRIME offload setting.
+fn adapters(tweaks: &LinuxTweaks) -> Vec<GpuAdapter> {
+    static PROBES: [OnceLock<Vec<GpuAdapter>>; 2] = [OnceLock::new(), OnceLock::new()];
+    PROBES[tweaks.prime_offload as usize].get_or_init(|| probe(tweaks)).clone()
+}
+
+/// Graphics support for `mc_version` on this machine. Blocking: the first call runs the probe.
+pub fn check(mc_version: &str, tweaks: &LinuxTweaks) -> GraphicsCheck {
+    evaluate(adapters(tweaks), mc_version, chrono::Local::now().date_naive())
+}
+
+fn with_links(message: String, check: &GraphicsCheck) -> String {
+    let links: Vec<String> = check.links.iter().map(|l| format!("Driver de {}: {}", l.vendor, l.url)).collect();
+    if links.is_empty() {
+        message
+    } else {
+        format!("{}\n\n{}", message, links.join("\n"))
+    }
+}
+
+/// Refuses launching what the GPU can't run, naming the adapter and the driver download.
+pub fn ensure_supported(check: &GraphicsCheck, mc_version: &str) -> Result<(), String> {
+    if check.status != GraphicsStatus::Unsupported {
+        return Ok(());
+    }
+    let message = format!(
+        "Minecraft {} necesita OpenGL {} y tu tarjeta gráfica no lo soporta:\n{}",
+        mc_version, check.required, check.problems.join("\n")
+    );
+    Err(with_links(message, check))
+}
+
+/// What to tell the player instead of a bare exit code when the crash looks like the graphics
+/// driver: GLFW couldn't get a context, or a driver crash code on an adapter with problems.
+pub fn crash_advice(code: i32, log: &str, check: &GraphicsCheck) -> Option<String> {
+    let no_context = GL_CONTEXT_ERRORS.iter().any(|e| log.contains(e));
+    let driver_crash = crate::exit_codes::is_driver_crash(code) && !check.problems.is_empty();
+    if !no_context && !driver_crash {
+        return None;
+    }
+    let mut message = if no_context {
+        format!("El juego no pudo usar OpenGL {} (Código: {}): el driver de la tarjeta gráfica no lo soporta o falta.", check.required, code)
+    } else {
+        format!("El driver de la tarjeta gráfica se cerró con el juego (Código: {}).", code)
+    };
+    for problem in &check.problems {
+        message.push_str(&format!("\n{}", problem));
+    }
+    message.push_str("\nActualiza el driver de la tarjeta gráfica y vuelve a intentarlo.");
+    let mut check = check.clone();
+    if check.links.is_empty() {
+        // GLFW said so even though the probe saw nothing wrong: offer every vendor's download
+        for vendor in check.adapters.iter().filter_map(|a| vendor(&a.name)) {
+            add_link(&mut check.links, vendor);
+        }
+    }
+    Some(with_links(message, &check))
+}
+
+/// Graphics support for an instance's Minecraft version, for the UI to warn before launching.
+#[tauri::command]
+pub async fn check_graphics(instance_id: String) -> Result<GraphicsCheck, String> {
+    crate::run_blocking(move || {
+        let instance = crate::load_instances().into_iter().find(|i| i.id == instance_id)
+            .ok_or("Instance not found")?;
+        Ok(check(&instance.version, &instance.linux_tweaks.unwrap_or_default()))
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn adapter(name: &str, date: Option<&str>) -> GpuAdapter {
+        GpuAdapter { name: name.to_string(), driver_version: None, driver_date: date.map(str::to_string), opengl_version: None }
+    }
+
+    #[test]
+    fn old_gpus_are_caught_for_modern_versions() {
+        let today = NaiveDate::from_ymd_opt(2026, 1, 1).unwrap();
+        let hd3000 = || vec![adapter("Intel(R) HD Graphics 3000", Some("2015-06-01"))];
+        let check = evaluate(hd3000(), "1.20.1", today);
+        assert_eq!(check.status, GraphicsStatus::Unsupported);
+        assert_eq!(check.links[0].vendor, "Intel");
+        assert!(ensure_supported(&check, "1.20.1").unwrap_err().contains("intel"));
+        // Runs 1.16, but the driver is old
+        assert_eq!(evaluate(hd3000(), "1.16.5", today).status, GraphicsStatus::Warning);
+
+        let basic = evaluate(vec![adapter("Microsoft Basic Display Adapter", None)], "1.12.2", today);
+        assert_eq!(basic.status, GraphicsStatus::Unsupported);
+        assert!(basic.problems[0].contains("no tiene instalado el driver"));
+
+        // Optimus: the NVIDIA one can run it
+        let hybrid = evaluate(vec![adapter("Intel(R) HD Graphics 3000", Some("2024-01-01")), adapter("NVIDIA GeForce GT 540M", Some("2024-01-01"))], "1.20.1", today);
+        assert_eq!(hybrid.status, GraphicsStatus::Warning);
+        assert!(hybrid.problems[0].contains("tarjeta dedicada"));
+
+        let modern = evaluate(vec![adapter("NVIDIA GeForce RTX 3060", Some("2025-11-02")), adapter("GeForce 7600 GT", None)], "1.21", today);
+        assert_eq!(modern.status, GraphicsStatus::Warning);
+        assert_eq!(evaluate(vec![adapter("AMD Radeon RX 6600", Some("2025-09-01"))], "1.21", today).status, GraphicsStatus::Ok);
+        assert_eq!(evaluate(Vec::new(), "1.21", today).status, GraphicsStatus::Ok);
+    }
+
+    #[test]
+    fn probe_output_is_parsed() {
+        let windows = parse_windows("NVIDIA GeForce GTX 1050|31.0.15.3623|2023-06-21\r\nIntel(R) UHD Graphics 630||\r\n\r\n");
+        assert_eq!(windows.len(), 2);
+        assert_eq!(windows[0].driver_date.as_deref(), Some("2023-06-21"));
+        assert_eq!(windows[1].driver_version, None);
+
+        let glx = parse_glxinfo("name of display: :0\n    OpenGL renderer string: Mesa Intel(R) HD Graphics 3000 (SNB GT2)\n    OpenGL core profile version string: 3.3 (Core Profile) Mesa 23.2.1\n    OpenGL version string: 3.0 Mesa 23.2.1\n");
+        assert_eq!(glx[0].opengl_version.as_deref(), Some("3.3"));
+        // A real context beats the name list
+        assert_eq!(evaluate(glx, "1.20.1", NaiveDate::from_ymd_opt(2026, 1, 1).unwrap()).status, GraphicsStatus::Ok);
+        let old = parse_glxinfo("OpenGL renderer string: Mesa DRI Intel(R) 965GM\nOpenGL version string: 2.1 Mesa 20.0.8\n");
+        assert_eq!(evaluate(old, "1.18.2", NaiveDate::from_ymd_opt(2026, 1, 1).unwrap()).status, GraphicsStatus::Unsupported);
+        assert!(parse_glxinfo("Error: unable to open display").is_empty());
+    }
+
+    #[test]
+    fn driver_crashes_get_targeted_advice() {
+        let today = NaiveDate::from_ymd_opt(2026, 1, 1).unwrap();
+        let old = evaluate(vec![adapter("AMD Radeon HD 7700 Series", Some("2019-02-01"))], "1.20.1", today);
+        let advice = crash_advice(-1073740791, "", &old).unwrap();
+        assert!(advice.contains("amd.com"));
+        assert!(crash_advice(1, "", &old).is_none());
+
+        let fine = evaluate(vec![adapter("NVIDIA GeForce GTX 1660", Some("2025-10-01"))], "1.20.1", today);
+        assert!(crash_advice(-1073740791, "", &fine).is_none());
+        let advice = crash_advice(-1, "[Render thread/ERROR]: GLFW error 65542: WGL: The driver does not appear to support OpenGL", &fine).unwrap();
+        assert!(advice.contains("nvidia.com"));
+    }
+}
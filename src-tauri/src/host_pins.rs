@@ -0,0 +1,183 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use crate::settings::{load_settings, save_settings};
+
+/// Starts download errors refused because a pack host's certificate changed.
+pub const PIN_MARKER: &str = "[host-pin]";
+const PINS_FILE: &str = "host-pins.json";
+/// Certificates remembered per host; CDNs serve a few at once and rotate them
+const MAX_FINGERPRINTS: usize = 8;
+
+/// What happens when a modpack host presents a certificate never seen for it before.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Default, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum PinMode {
+    Off,
+    /// The download goes on, the change is reported and the new certificate remembered
+    #[default]
+    Warn,
+    /// The download fails until an admin forgets the host's pin
+    Block,
+}
+
+/// Certificates seen for one host, trusted on first use.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct HostPin {
+    /// SHA-256 of the leaf certificates, oldest first
+    pub fingerprints: Vec<String>,
+    #[serde(rename = "firstSeen", alias = "first_seen")]
+    pub first_seen: u64,
+    #[serde(rename = "lastSeen", alias = "last_seen")]
+    pub last_seen: u64,
+    /// Last time an unknown certificate showed up
+    #[serde(rename = "changedAt", alias = "changed_at", default)]
+    pub changed_at: Option<u64>,
+}
+
+pub type Pins = BTreeMap<String, HostPin>;
+
+#[derive(Debug, PartialEq)]
+pub enum PinCheck {
+    FirstUse,
+    Known,
+    /// Unknown certificate for a pinned host
+    Changed,
+}
+
+/// Serializes the read-modify-write of the pins file across parallel downloads.
+static LOCK: Mutex<()> = Mutex::new(());
+
+fn pins_path() -> PathBuf {
+    crate::data_dir::root().join(PINS_FILE)
+}
+
+fn load_pins() -> Pins {
+    fs::read_to_string(pins_path()).ok()
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+
+fn save_pins(pins: &Pins) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(pins).map_err(|e| e.to_string())?;
+    fs::write(pins_path(), json).map_err(|e| e.to_string())
+}
+
+pub fn fingerprint(der: &[u8]) -> String {
+    hex::encode(Sha256::digest(der))
+}
+
+/// Looks `fingerprint` up for `host`. Unless `remember` is false (a blocked change), the
+/// certificate is added to what the host is known by.
+pub fn check(pins: &mut Pins, host: &str, fingerprint: &str, remember: bool, now: u64) -> PinCheck {
+    let Some(pin) = pins.get_mut(host) else {
+        pins.insert(host.to_string(), HostPin { fingerprints: vec![fingerprint.to_string()], first_seen: now, last_seen: now, changed_at: None });
+        return PinCheck::FirstUse;
+    };
+    if pin.fingerprints.iter().any(|f| f == fingerprint) {
+        pin.last_seen = now;
+        return PinCheck::Known;
+    }
+    if remember {
+        pin.fingerprints.push(fingerprint.to_string());
+        let excess = pin.fingerprints.len().saturating_sub(MAX_FINGERPRINTS);
+        pin.fingerprints.drain(..excess);
+        pin.last_seen = now;
+        pin.changed_at = Some(now);
+    }
+    PinCheck::Changed
+}
+
+/// Checks the certificate a pack was served with (`url` after redirects). Returns the warning
+/// to show when the certificate changed and the mode only warns.
+pub fn verify(url: &reqwest::Url, certificate: Option<&[u8]>) -> Result<Option<String>, String> {
+    let mode = load_settings().host_pinning;
+    // Plain HTTP has nothing to pin; servers without TLS info can't be checked either
+    let (Some(host), Some(der)) = (url.host_str(), certificate) else { return Ok(None) };
+    if mode == PinMode::Off || url.scheme() != "https" {
+        return Ok(None);
+    }
+    let host = host.to_lowercase();
+    let _guard = LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let mut pins = load_pins();
+    let result = check(&mut pins, &host, &fingerprint(der), mode == PinMode::Warn, crate::unix_now());
+    if let Err(e) = save_pins(&pins) {
+        eprintln!("[host_pins] {}", e);
+    }
+    match (result, mode) {
+        (PinCheck::Changed, PinMode::Block) => Err(format!(
+            "{} El certificado de {} no es el que se usó antes. Puede que la red esté interceptando la descarga; prueba con otra conexión o pide al staff que lo revise.",
+            PIN_MARKER, host
+        )),
+        (PinCheck::Changed, _) => Ok(Some(format!("El certificado de {} cambió desde la última descarga; comprueba que la red es de confianza", host))),
+        _ => Ok(None),
+    }
+}
+
+#[tauri::command]
+pub fn get_host_pins(password: String) -> Result<Pins, String> {
+    crate::ensure_admin(password)?;
+    Ok(load_pins())
+}
+
+/// Trusts whatever certificate `host` presents next, e.g. after a legitimate renewal.
+#[tauri::command]
+pub fn forget_host_pin(password: String, host: String) -> Result<(), String> {
+    crate::ensure_admin(password)?;
+    let _guard = LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let mut pins = load_pins();
+    pins.remove(&host.trim().to_lowercase());
+    save_pins(&pins)
+}
+
+#[tauri::command]
+pub fn get_host_pin_mode() -> PinMode {
+    load_settings().host_pinning
+}
+
+#[tauri::command]
+pub fn set_host_pin_mode(password: String, mode: PinMode) -> Result<(), String> {
+    crate::ensure_admin(password)?;
+    let mut settings = load_settings();
+    settings.host_pinning = mode;
+    save_settings(&settings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn first_certificate_is_trusted_and_changes_are_caught() {
+        let mut pins = Pins::new();
+        assert_eq!(check(&mut pins, "packs.example", "aa", true, 1), PinCheck::FirstUse);
+        assert_eq!(check(&mut pins, "packs.example", "aa", true, 2), PinCheck::Known);
+        assert_eq!(pins["packs.example"].last_seen, 2);
+
+        // Blocked: the pin stays as it was, so the next try is refused too
+        assert_eq!(check(&mut pins, "packs.example", "bb", false, 3), PinCheck::Changed);
+        assert_eq!(check(&mut pins, "packs.example", "bb", false, 4), PinCheck::Changed);
+        assert_eq!(pins["packs.example"].changed_at, None);
+
+        // Warned: remembered next to the old one
+        assert_eq!(check(&mut pins, "packs.example", "bb", true, 5), PinCheck::Changed);
+        assert_eq!(check(&mut pins, "packs.example", "bb", true, 6), PinCheck::Known);
+        assert_eq!(check(&mut pins, "packs.example", "aa", true, 7), PinCheck::Known);
+        assert_eq!(pins["packs.example"].changed_at, Some(5));
+    }
+
+    #[test]
+    fn old_certificates_are_forgotten() {
+        let mut pins = Pins::new();
+        for i in 0..=MAX_FINGERPRINTS {
+            check(&mut pins, "cdn.example", &i.to_string(), true, i as u64);
+        }
+        assert_eq!(pins["cdn.example"].fingerprints.len(), MAX_FINGERPRINTS);
+        assert_eq!(check(&mut pins, "cdn.example", "0", false, 99), PinCheck::Changed);
+        assert_eq!(fingerprint(b"x"), "2d711642b726b04401627ca9fbac32f5c8530fb1903cc4db02258717921a4881");
+    }
+}
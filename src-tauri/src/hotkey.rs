@@ -0,0 +1,78 @@
+use schemars::JsonSchema;
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, ShortcutState};
+use crate::settings::{ensure_not_guest, load_settings, save_settings};
+
+#[derive(Serialize, Clone, JsonSchema)]
+pub struct HotkeyLaunch {
+    #[serde(rename = "instanceId")]
+    instance_id: String,
+    name: String,
+    error: Option<String>,
+}
+
+fn last_played_instance() -> Option<crate::Instance> {
+    crate::load_instances().into_iter().max_by_key(|i| i.last_played)
+}
+
+fn launch_last_played(app: &AppHandle) {
+    let Some(instance) = last_played_instance() else { return; };
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let _ = app.emit("hotkey_launch", HotkeyLaunch {
+            instance_id: instance.id.clone(),
+            name: instance.name.clone(),
+            error: None,
+        });
+        let state = app.state::<crate::AppState>();
+        if let Err(e) = crate::launch_instance_with(app.clone(), instance.id.clone(), None, false, state).await {
+            let _ = app.emit("hotkey_launch", HotkeyLaunch { instance_id: instance.id, name: instance.name, error: Some(e) });
+        }
+    });
+}
+
+fn register(app: &AppHandle, accelerator: &str) -> Result<(), String> {
+    app.global_shortcut()
+        .on_shortcut(accelerator, |app, _shortcut, event| {
+            if event.state == ShortcutState::Pressed {
+                launch_last_played(app);
+            }
+        })
+        .map_err(|e| format!("Atajo no válido ({}): {}", accelerator, e))
+}
+
+/// Registers the saved hotkey at startup. A bad or already taken accelerator is only logged.
+pub fn init(app: &AppHandle) {
+    if let Some(accelerator) = load_settings().launch_hotkey.filter(|a| !a.trim().is_empty()) {
+        if let Err(e) = register(app, &accelerator) {
+            eprintln!("[hotkey] {}", e);
+        }
+    }
+}
+
+#[tauri::command]
+pub fn get_launch_hotkey() -> Option<String> {
+    load_settings().launch_hotkey
+}
+
+/// Replaces the launch hotkey; `None` or an empty string turns it off.
+#[tauri::command]
+pub fn set_launch_hotkey(app: AppHandle, hotkey: Option<String>) -> Result<(), String> {
+    ensure_not_guest()?;
+    let hotkey = hotkey.map(|h| h.trim().to_string()).filter(|h| !h.is_empty());
+    let mut settings = load_settings();
+    if let Some(old) = settings.launch_hotkey.as_deref() {
+        let _ = app.global_shortcut().unregister(old);
+    }
+    if let Some(accelerator) = hotkey.as_deref() {
+        if let Err(e) = register(&app, accelerator) {
+            if let Some(old) = settings.launch_hotkey.as_deref() {
+                let _ = register(&app, old);
+            }
+            return Err(e);
+        }
+    }
+    settings.launch_hotkey = hotkey;
+    save_settings(&settings)
+}
@@ -0,0 +1,275 @@
+use std::collections::HashMap;
+use std::io::{self, Read};
+use std::ops::{Deref, DerefMut};
+use std::sync::{Condvar, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use reqwest::blocking::{Client, RequestBuilder, Response};
+use reqwest::header::{AUTHORIZATION, RETRY_AFTER};
+use reqwest::StatusCode;
+
+const DEFAULT_USER_AGENT: &str = "DrkLauncher/1.0";
+// Some file hosts reject anything that doesn't look like a browser
+const BROWSER_USER_AGENT: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36";
+
+fn custom_user_agent() -> Option<String> {
+    crate::settings::load_settings().credentials.user_agent.filter(|ua| !ua.trim().is_empty())
+}
+
+/// User agent for API calls: the admin-configured one or `DrkLauncher/1.0`.
+pub fn user_agent() -> String {
+    custom_user_agent().unwrap_or_else(|| DEFAULT_USER_AGENT.to_string())
+}
+
+/// User agent for file downloads: the admin-configured one or a browser UA.
+pub fn download_user_agent() -> String {
+    custom_user_agent().unwrap_or_else(|| BROWSER_USER_AGENT.to_string())
+}
+
+/// Adds the stored API credentials for hosts that need them (CurseForge key, GitHub token).
+pub fn with_credentials(request: RequestBuilder, url: &str) -> RequestBuilder {
+    if crate::mirror::on_mirror(url) {
+        return request;
+    }
+    let host = url::Url::parse(url).ok().and_then(|u| u.host_str().map(|h| h.to_lowercase())).unwrap_or_default();
+    let credentials = crate::settings::load_settings().credentials;
+    match host.as_str() {
+        "api.curseforge.com" => match credentials.curseforge_api_key.filter(|k| !k.is_empty()) {
+            Some(key) => request.header("x-api-key", key),
+            None => request,
+        },
+        "api.github.com" | "github.com" | "objects.githubusercontent.com" => match credentials.github_token.filter(|t| !t.is_empty()) {
+            Some(token) => request.header(AUTHORIZATION, format!("Bearer {}", token)),
+            None => request,
+        },
+        _ => request,
+    }
+}
+
+// Requests in flight per host; event nights push hundreds of asset/mod downloads at once
+const MAX_PER_HOST: usize = 8;
+const MAX_RATE_LIMIT_RETRIES: u32 = 5;
+// Never sleep longer than this on a single Retry-After, some CDNs send absurd values
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(120);
+
+#[derive(Default)]
+struct HostState {
+    in_flight: usize,
+    /// Set from the last 429/503; nobody talks to the host before it
+    not_before: Option<Instant>,
+}
+
+static HOSTS: Mutex<Option<HashMap<String, HostState>>> = Mutex::new(None);
+static HOST_SLOT_FREED: Condvar = Condvar::new();
+
+/// Blocks until the host is out of backoff and has a free slot.
+fn acquire_host(host: &str) {
+    let mut guard = HOSTS.lock().unwrap_or_else(|e| e.into_inner());
+    loop {
+        let state = guard.get_or_insert_with(HashMap::new).entry(host.to_string()).or_default();
+        let wait = state.not_before.and_then(|t| t.checked_duration_since(Instant::now()));
+        match wait {
+            Some(wait) => {
+                guard = HOST_SLOT_FREED.wait_timeout(guard, wait).unwrap_or_else(|e| e.into_inner()).0;
+            }
+            None if state.in_flight >= MAX_PER_HOST => {
+                guard = HOST_SLOT_FREED.wait(guard).unwrap_or_else(|e| e.into_inner());
+            }
+            None => {
+                state.in_flight += 1;
+                return;
+            }
+        }
+    }
+}
+
+fn release_host(host: &str, backoff: Option<Duration>) {
+    let mut guard = HOSTS.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(state) = guard.get_or_insert_with(HashMap::new).get_mut(host) {
+        state.in_flight = state.in_flight.saturating_sub(1);
+        if let Some(delay) = backoff {
+            let until = Instant::now() + delay;
+            state.not_before = Some(state.not_before.map_or(until, |t| t.max(until)));
+        }
+    }
+    HOST_SLOT_FREED.notify_all();
+}
+
+/// One of the host's `MAX_PER_HOST` slots, given back when dropped.
+struct HostSlot {
+    host: String,
+    backoff: Option<Duration>,
+}
+
+impl HostSlot {
+    fn acquire(host: &str) -> Self {
+        acquire_host(host);
+        HostSlot { host: host.to_string(), backoff: None }
+    }
+}
+
+impl Drop for HostSlot {
+    fn drop(&mut self) {
+        release_host(&self.host, self.backoff);
+    }
+}
+
+/// A response from `send`. Keeps its host slot until the body has been read and it is dropped,
+/// so a large download counts against the host for as long as it streams, not just until the
+/// headers arrive.
+pub struct HostResponse {
+    response: Response,
+    _slot: HostSlot,
+}
+
+impl HostResponse {
+    pub fn text(self) -> reqwest::Result<String> {
+        self.response.text()
+    }
+
+    pub fn json<T: serde::de::DeserializeOwned>(self) -> reqwest::Result<T> {
+        self.response.json()
+    }
+}
+
+impl Deref for HostResponse {
+    type Target = Response;
+
+    fn deref(&self) -> &Response {
+        &self.response
+    }
+}
+
+impl DerefMut for HostResponse {
+    fn deref_mut(&mut self) -> &mut Response {
+        &mut self.response
+    }
+}
+
+impl Read for HostResponse {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.response.read(buf)
+    }
+}
+
+/// Hosts currently rate limited and how long until they accept requests again.
+pub fn backoff_state() -> Vec<(String, Duration)> {
+    let guard = HOSTS.lock().unwrap_or_else(|e| e.into_inner());
+    let now = Instant::now();
+    guard.iter()
+        .flat_map(|hosts| hosts.iter())
+        .filter_map(|(host, state)| Some((host.clone(), state.not_before?.checked_duration_since(now)?)))
+        .collect()
+}
+
+/// Suffix for progress messages while some host is making us wait, empty otherwise.
+pub fn backoff_note() -> String {
+    match backoff_state().into_iter().max_by_key(|(_, wait)| *wait) {
+        Some((host, wait)) => format!(" (esperando {}s por límite de {})", wait.as_secs().max(1), host),
+        None => String::new(),
+    }
+}
+
+/// `Retry-After` is either delta-seconds or an IMF-fixdate (`Sun, 06 Nov 1994 08:49:37 GMT`).
+pub fn parse_retry_after(value: &str, now: SystemTime) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+    let parts = value.split_whitespace().collect::<Vec<_>>();
+    if parts.len() != 6 || parts[5] != "GMT" {
+        return None;
+    }
+    let day: u64 = parts[1].parse().ok()?;
+    let month = ["Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec"]
+        .iter()
+        .position(|m| *m == parts[2])? as u64 + 1;
+    let year: u64 = parts[3].parse().ok()?;
+    let hms = parts[4].split(':').map(|p| p.parse::<u64>().ok()).collect::<Option<Vec<_>>>()?;
+    if hms.len() != 3 || year < 1970 {
+        return None;
+    }
+    // Days from civil date (Howard Hinnant's algorithm)
+    let (y, m) = if month <= 2 { (year - 1, month + 9) } else { (year, month - 3) };
+    let era = y / 400;
+    let yoe = y - era * 400;
+    let doy = (153 * m + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = era * 146097 + doe - 719468;
+    let target = UNIX_EPOCH + Duration::from_secs(days * 86400 + hms[0] * 3600 + hms[1] * 60 + hms[2]);
+    Some(target.duration_since(now).unwrap_or(Duration::ZERO))
+}
+
+/// Sends through the per-host queue. 429s (and 503s with `Retry-After`) put the whole host in
+/// backoff for the announced time instead of letting every worker retry immediately. The URL is
+/// used as given; game downloads move onto the self-hosted mirror before getting here
+/// (see `mirror::rewrite`).
+pub fn send(client: &Client, request: RequestBuilder) -> Result<HostResponse, String> {
+    let request = request.build().map_err(|e| e.to_string())?;
+    let host = request.url().host_str().unwrap_or("").to_string();
+    let mut attempt = 0;
+    loop {
+        let to_send = request.try_clone().ok_or("Request body can't be retried")?;
+        let mut slot = HostSlot::acquire(&host);
+        let result = client.execute(to_send);
+        let rate_limited = match &result {
+            Ok(resp) if resp.status() == StatusCode::TOO_MANY_REQUESTS
+                || (resp.status() == StatusCode::SERVICE_UNAVAILABLE && resp.headers().contains_key(RETRY_AFTER)) =>
+            {
+                let announced = resp.headers().get(RETRY_AFTER)
+                    .and_then(|v| v.to_str().ok())
+                    .and_then(|v| parse_retry_after(v, SystemTime::now()));
+                Some(announced.unwrap_or_else(|| Duration::from_secs(2u64.pow(attempt + 1))).min(MAX_RETRY_AFTER))
+            }
+            _ => None,
+        };
+        match rate_limited {
+            Some(backoff) if attempt < MAX_RATE_LIMIT_RETRIES => {
+                slot.backoff = Some(backoff);
+                attempt += 1;
+            }
+            _ => {
+                slot.backoff = rate_limited;
+                return result
+                    .map(|response| HostResponse { response, _slot: slot })
+                    .map_err(|e| format!("Network error: {}", e));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn retry_after_accepts_seconds_and_http_dates() {
+        let now = UNIX_EPOCH + Duration::from_secs(784111777); // Sun, 06 Nov 1994 08:49:37 GMT
+        assert_eq!(parse_retry_after("120", now), Some(Duration::from_secs(120)));
+        assert_eq!(parse_retry_after("Sun, 06 Nov 1994 08:50:07 GMT", now), Some(Duration::from_secs(30)));
+        assert_eq!(parse_retry_after("Sun, 06 Nov 1994 08:00:00 GMT", now), Some(Duration::ZERO));
+        assert_eq!(parse_retry_after("soon", now), None);
+    }
+
+    #[test]
+    fn backoff_blocks_host_until_deadline() {
+        let host = "backoff.test";
+        acquire_host(host);
+        release_host(host, Some(Duration::from_millis(150)));
+        assert!(backoff_state().iter().any(|(h, _)| h == host));
+        let start = Instant::now();
+        acquire_host(host);
+        assert!(start.elapsed() >= Duration::from_millis(100));
+        release_host(host, None);
+        assert!(!backoff_state().iter().any(|(h, _)| h == host));
+    }
+
+    #[test]
+    fn host_slot_is_held_until_dropped() {
+        let host = "slot.test";
+        let in_flight = || HOSTS.lock().unwrap().as_ref().and_then(|h| h.get(host)).map_or(0, |s| s.in_flight);
+        let slot = HostSlot::acquire(host);
+        assert_eq!(in_flight(), 1);
+        drop(slot);
+        assert_eq!(in_flight(), 0);
+    }
+}
@@ -0,0 +1,353 @@
+use image::ImageFormat;
+use serde::{Deserialize, Serialize};
+use sha1::{Digest, Sha1};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+const CACHE_DIR: &str = "image-cache";
+// Within this an image is served without asking the server
+const FRESH_FOR: Duration = Duration::from_secs(10 * 60);
+const MEMORY_BUDGET: usize = 48 * 1024 * 1024;
+const MAX_IMAGE_BYTES: u64 = 20 * 1024 * 1024;
+const PREFETCH_INTERVAL: Duration = Duration::from_secs(30 * 60);
+// The account picker's skin renders, the only remote images that don't come from an instance
+const SKIN_RENDER_HOSTS: [&str; 3] = ["mc-heads.net", "api.mineskin.org", "crafatar.com"];
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+struct Meta {
+    url: String,
+    #[serde(default)]
+    etag: Option<String>,
+    #[serde(rename = "lastModified", default)]
+    last_modified: Option<String>,
+    #[serde(rename = "contentType")]
+    content_type: String,
+    /// When the server last confirmed the bytes
+    #[serde(rename = "checkedAt")]
+    checked_at: u64,
+}
+
+/// An image as served to the webview.
+#[derive(Clone)]
+pub struct Cached {
+    pub bytes: Arc<Vec<u8>>,
+    pub content_type: String,
+}
+
+struct MemoryEntry {
+    meta: Meta,
+    bytes: Arc<Vec<u8>>,
+    last_used: u64,
+}
+
+/// Most recently used images, kept under `MEMORY_BUDGET` bytes.
+#[derive(Default)]
+struct Memory {
+    entries: HashMap<String, MemoryEntry>,
+    bytes: usize,
+    tick: u64,
+}
+
+impl Memory {
+    fn get(&mut self, url: &str) -> Option<(Meta, Arc<Vec<u8>>)> {
+        self.tick += 1;
+        let tick = self.tick;
+        let entry = self.entries.get_mut(url)?;
+        entry.last_used = tick;
+        Some((entry.meta.clone(), Arc::clone(&entry.bytes)))
+    }
+
+    fn put(&mut self, meta: Meta, bytes: Arc<Vec<u8>>, budget: usize) {
+        self.tick += 1;
+        if let Some(old) = self.entries.remove(&meta.url) {
+            self.bytes -= old.bytes.len();
+        }
+        self.bytes += bytes.len();
+        self.entries.insert(meta.url.clone(), MemoryEntry { meta, bytes, last_used: self.tick });
+        while self.bytes > budget && self.entries.len() > 1 {
+            let Some(oldest) = self.entries.iter().min_by_key(|(_, e)| e.last_used).map(|(url, _)| url.clone()) else { break };
+            if let Some(old) = self.entries.remove(&oldest) {
+                self.bytes -= old.bytes.len();
+            }
+        }
+    }
+}
+
+static MEMORY: Mutex<Option<Memory>> = Mutex::new(None);
+
+/// What the server answered a (conditional) request with.
+pub enum Fetched {
+    NotModified,
+    Image { bytes: Vec<u8>, content_type: String, etag: Option<String>, last_modified: Option<String> },
+}
+
+pub fn cache_dir_in(data_root: &Path) -> PathBuf {
+    data_root.join("cache").join(CACHE_DIR)
+}
+
+fn cache_dir() -> PathBuf {
+    cache_dir_in(Path::new(&crate::get_instances_dir()))
+}
+
+fn file_stem(url: &str) -> String {
+    hex::encode(Sha1::digest(url.as_bytes()))
+}
+
+fn read_disk(dir: &Path, url: &str) -> Option<(Meta, Arc<Vec<u8>>)> {
+    let stem = file_stem(url);
+    let meta: Meta = serde_json::from_str(&fs::read_to_string(dir.join(format!("{}.json", stem))).ok()?).ok()?;
+    let bytes = fs::read(dir.join(&stem)).ok()?;
+    (meta.url == url).then(|| (meta, Arc::new(bytes)))
+}
+
+/// Content type recorded for the cached file `<dir>/<stem>`.
+pub fn stored_content_type(dir: &Path, stem: &str) -> Option<String> {
+    let meta: Meta = serde_json::from_str(&fs::read_to_string(dir.join(format!("{}.json", stem))).ok()?).ok()?;
+    Some(meta.content_type)
+}
+
+fn write_meta(dir: &Path, meta: &Meta) {
+    if let Ok(json) = serde_json::to_vec(meta) {
+        let _ = crate::temp_files::write_atomic(&dir.join(format!("{}.json", file_stem(&meta.url))), |file| file.write_all(&json).map_err(|e| e.to_string()));
+    }
+}
+
+fn write_disk(dir: &Path, meta: &Meta, bytes: &[u8]) -> Result<(), String> {
+    fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    crate::temp_files::write_atomic(&dir.join(file_stem(&meta.url)), |file| file.write_all(bytes).map_err(|e| e.to_string()))?;
+    write_meta(dir, meta);
+    Ok(())
+}
+
+/// Memory, then disk, then the server: fresh copies are served as they are, older ones are
+/// revalidated with their ETag / Last-Modified, and a stale copy still beats a network error.
+fn get_with(dir: &Path, url: &str, now: u64, fetch: impl FnOnce(Option<&Meta>) -> Result<Fetched, String>) -> Result<Cached, String> {
+    let cached = {
+        let mut memory = MEMORY.lock().unwrap_or_else(|e| e.into_inner());
+        memory.get_or_insert_with(Memory::default).get(url)
+    }
+    .or_else(|| read_disk(dir, url));
+    let remember = |meta: Meta, bytes: Arc<Vec<u8>>| {
+        let content_type = meta.content_type.clone();
+        let mut memory = MEMORY.lock().unwrap_or_else(|e| e.into_inner());
+        memory.get_or_insert_with(Memory::default).put(meta, Arc::clone(&bytes), MEMORY_BUDGET);
+        Cached { bytes, content_type }
+    };
+    if let Some((meta, bytes)) = &cached {
+        if now.saturating_sub(meta.checked_at) < FRESH_FOR.as_secs() {
+            return Ok(remember(meta.clone(), Arc::clone(bytes)));
+        }
+    }
+    match (fetch(cached.as_ref().map(|(meta, _)| meta)), cached) {
+        (Ok(Fetched::NotModified), Some((meta, bytes))) => {
+            let meta = Meta { checked_at: now, ..meta };
+            write_meta(dir, &meta);
+            Ok(remember(meta, bytes))
+        }
+        (Ok(Fetched::NotModified), None) => Err("El servidor no devolvió la imagen".to_string()),
+        (Ok(Fetched::Image { bytes, content_type, etag, last_modified }), _) => {
+            let meta = Meta { url: url.to_string(), etag, last_modified, content_type, checked_at: now };
+            if let Err(e) = write_disk(dir, &meta, &bytes) {
+                eprintln!("[image_cache] {}", e);
+            }
+            Ok(remember(meta, Arc::new(bytes)))
+        }
+        (Err(_), Some((meta, bytes))) => Ok(remember(meta, bytes)),
+        (Err(e), None) => Err(e),
+    }
+}
+
+/// For servers that send images as `application/octet-stream`.
+fn sniffed_type(bytes: &[u8]) -> Option<&'static str> {
+    match image::guess_format(bytes).ok()? {
+        ImageFormat::Png => Some("image/png"),
+        ImageFormat::Jpeg => Some("image/jpeg"),
+        ImageFormat::Gif => Some("image/gif"),
+        ImageFormat::WebP => Some("image/webp"),
+        _ => None,
+    }
+}
+
+fn fetch(url: &str, cached: Option<&Meta>) -> Result<Fetched, String> {
+    let client = reqwest::blocking::Client::builder()
+        .user_agent(crate::http::download_user_agent())
+        .timeout(Duration::from_secs(30))
+        .build()
+        .map_err(|e| e.to_string())?;
+    // reqwest 0.11 has its own `http` types, so its header names are used here
+    let mut request = client.get(url);
+    if let Some(etag) = cached.and_then(|m| m.etag.as_deref()) {
+        request = request.header(reqwest::header::IF_NONE_MATCH, etag);
+    }
+    if let Some(modified) = cached.and_then(|m| m.last_modified.as_deref()) {
+        request = request.header(reqwest::header::IF_MODIFIED_SINCE, modified);
+    }
+    let resp = crate::http::send(&client, request)?;
+    if resp.status() == reqwest::StatusCode::NOT_MODIFIED {
+        return Ok(Fetched::NotModified);
+    }
+    if !resp.status().is_success() {
+        return Err(format!("La imagen respondió {}", resp.status()));
+    }
+    let header_value = |name: reqwest::header::HeaderName| resp.headers().get(name).and_then(|v| v.to_str().ok()).map(str::to_string);
+    let etag = header_value(reqwest::header::ETAG);
+    let last_modified = header_value(reqwest::header::LAST_MODIFIED);
+    let declared_type = header_value(reqwest::header::CONTENT_TYPE);
+    let mut bytes = Vec::new();
+    resp.take(MAX_IMAGE_BYTES + 1).read_to_end(&mut bytes).map_err(|e| e.to_string())?;
+    if bytes.len() as u64 > MAX_IMAGE_BYTES {
+        return Err("La imagen es demasiado grande".to_string());
+    }
+    let content_type = declared_type
+        .filter(|t| t.starts_with("image/"))
+        .or_else(|| sniffed_type(&bytes).map(str::to_string))
+        .ok_or("La respuesta no es una imagen")?;
+    Ok(Fetched::Image { bytes, content_type, etag, last_modified })
+}
+
+/// A remote image for the webview, which gets it through `asset_protocol` (`remote/<url>`).
+pub fn get(url: &str) -> Result<Cached, String> {
+    if !url.starts_with("http://") && !url.starts_with("https://") {
+        return Err("Solo se guardan imágenes http(s)".to_string());
+    }
+    get_with(&cache_dir(), url, crate::unix_now(), |cached| fetch(url, cached))
+}
+
+pub fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes.get(i + 1..i + 3).and_then(|h| std::str::from_utf8(h).ok()).and_then(|h| u8::from_str_radix(h, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                out.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                out.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// Remote images the instances show: carousel images and card images.
+fn instance_image_urls(instances: &[crate::Instance]) -> Vec<String> {
+    let mut seen = HashSet::new();
+    instances.iter()
+        .flat_map(|i| {
+            i.images.iter().flatten().cloned()
+                .chain(i.event_card.as_ref().and_then(|c| c.image.clone()))
+                .chain(i.stats_card.as_ref().and_then(|c| c.image.clone()))
+                .chain(i.info_card.as_ref().and_then(|c| c.image.clone()))
+        })
+        .filter(|url| url.starts_with("http://") || url.starts_with("https://"))
+        .filter(|url| seen.insert(url.clone()))
+        .collect()
+}
+
+/// Whether the webview may have `url` fetched: an image of one of `instance_urls` or an https
+/// skin render. Anything else, localhost and LAN addresses included, is refused.
+pub fn allowed_in(url: &str, instance_urls: &[String]) -> bool {
+    if instance_urls.iter().any(|u| u == url) {
+        return true;
+    }
+    url::Url::parse(url).is_ok_and(|u| u.scheme() == "https" && u.host_str().is_some_and(|h| SKIN_RENDER_HOSTS.contains(&h)))
+}
+
+pub fn allowed(url: &str) -> bool {
+    allowed_in(url, &instance_image_urls(&crate::load_instances()))
+}
+
+fn prefetch(urls: &[String]) {
+    for url in urls {
+        if let Err(e) = get(url) {
+            eprintln!("[image_cache] Prefetch {}: {}", url, e);
+        }
+    }
+}
+
+/// Keeps the instances' remote images cached and revalidated in the background.
+pub fn start() {
+    std::thread::spawn(|| loop {
+        prefetch(&instance_image_urls(&crate::load_instances()));
+        std::thread::sleep(PREFETCH_INTERVAL);
+    });
+}
+
+/// Caches images the frontend is about to show, such as the account's skin render.
+#[tauri::command]
+pub fn prefetch_images(urls: Vec<String>) {
+    std::thread::spawn(move || {
+        let instance_urls = instance_image_urls(&crate::load_instances());
+        let urls: Vec<String> = urls.into_iter().filter(|url| allowed_in(url, &instance_urls)).collect();
+        prefetch(&urls);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn image(bytes: &[u8], etag: &str) -> Fetched {
+        Fetched::Image { bytes: bytes.to_vec(), content_type: "image/png".into(), etag: Some(etag.into()), last_modified: None }
+    }
+
+    #[test]
+    fn images_are_revalidated_with_their_etag() {
+        let dir = tempfile::tempdir().unwrap();
+        let url = "https://drk.example/cache-test/banner.png";
+        let first = get_with(dir.path(), url, 1000, |cached| {
+            assert!(cached.is_none());
+            Ok(image(b"v1", "\"a\""))
+        }).unwrap();
+        assert_eq!(*first.bytes, b"v1");
+
+        // Fresh: the server isn't asked
+        get_with(dir.path(), url, 1000 + 60, |_| panic!("fetched a fresh image")).unwrap();
+        let later = 1000 + FRESH_FOR.as_secs();
+        let revalidated = get_with(dir.path(), url, later, |cached| {
+            assert_eq!(cached.unwrap().etag.as_deref(), Some("\"a\""));
+            Ok(Fetched::NotModified)
+        }).unwrap();
+        assert_eq!(*revalidated.bytes, b"v1");
+        assert_eq!(read_disk(dir.path(), url).unwrap().0.checked_at, later);
+
+        let offline = get_with(dir.path(), url, later * 2, |_| Err("sin conexión".into())).unwrap();
+        assert_eq!(*offline.bytes, b"v1");
+        let changed = get_with(dir.path(), url, later * 3, |_| Ok(image(b"v2", "\"b\""))).unwrap();
+        assert_eq!(*changed.bytes, b"v2");
+        assert!(get_with(dir.path(), "https://drk.example/cache-test/none.png", 0, |_| Err("404".into())).is_err());
+    }
+
+    #[test]
+    fn memory_keeps_the_most_recently_used() {
+        let mut memory = Memory::default();
+        let meta = |url: &str| Meta { url: url.into(), etag: None, last_modified: None, content_type: "image/png".into(), checked_at: 0 };
+        memory.put(meta("a"), Arc::new(vec![0; 40]), 100);
+        memory.put(meta("b"), Arc::new(vec![0; 40]), 100);
+        memory.get("a");
+        memory.put(meta("c"), Arc::new(vec![0; 40]), 100);
+        assert!(memory.get("a").is_some() && memory.get("c").is_some());
+        assert!(memory.get("b").is_none());
+        assert_eq!(memory.bytes, 80);
+    }
+
+    #[test]
+    fn only_instance_images_and_skin_renders_are_fetched() {
+        let instance_urls = vec!["http://fotos.lan/banner.png".to_string()];
+        assert!(allowed_in("http://fotos.lan/banner.png", &instance_urls));
+        assert!(allowed_in("https://mc-heads.net/body/abc", &instance_urls));
+        assert!(allowed_in("https://api.mineskin.org/render/body/Steve", &instance_urls));
+        assert!(!allowed_in("http://mc-heads.net/body/abc", &instance_urls));
+        assert!(!allowed_in("http://127.0.0.1:9100/metrics", &instance_urls));
+        assert!(!allowed_in("http://192.168.1.1/", &instance_urls));
+        assert!(!allowed_in("https://mc-heads.net.evil.example/x.png", &instance_urls));
+    }
+}
@@ -0,0 +1,104 @@
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+use tauri::{AppHandle, Emitter};
+use crate::InfoCard;
+
+/// `instance_card_updated` payload.
+#[derive(Serialize, Clone, JsonSchema)]
+pub struct InstanceCardUpdated {
+    #[serde(rename = "instanceId")]
+    pub instance_id: String,
+    #[serde(rename = "infoCard")]
+    pub info_card: InfoCard,
+}
+
+/// The numbers on the instance's info card, read from its folder instead of typed by staff.
+#[derive(Debug, PartialEq)]
+pub struct CardStats {
+    pub mods_installed: u32,
+    /// Day the installed pack was downloaded (the newest mod jar without a pack), `YYYY-MM-DD`
+    pub last_update: Option<String>,
+    pub pack_version: Option<String>,
+}
+
+fn modified_secs(path: &Path) -> Option<u64> {
+    let modified = fs::metadata(path).and_then(|m| m.modified()).ok()?;
+    modified.duration_since(UNIX_EPOCH).ok().map(|d| d.as_secs())
+}
+
+pub fn compute(minecraft_dir: &Path) -> CardStats {
+    let jars: Vec<_> = fs::read_dir(minecraft_dir.join("mods"))
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| p.is_file() && p.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("jar")))
+        .collect();
+    let zip_path = minecraft_dir.join("modpack.zip");
+    let updated = modified_secs(&zip_path).or_else(|| jars.iter().filter_map(|p| modified_secs(p)).max());
+    CardStats {
+        mods_installed: jars.len() as u32,
+        last_update: updated.map(|secs| {
+            let (year, month, day) = crate::minecraft::utils::utc_date(secs);
+            format!("{:04}-{:02}-{:02}", year, month, day)
+        }),
+        pack_version: crate::minecraft::modpack::pack_version(&zip_path),
+    }
+}
+
+/// `card` with the computed numbers; the image staff picked is kept.
+pub fn apply(card: Option<InfoCard>, stats: CardStats) -> InfoCard {
+    InfoCard {
+        mods_installed: Some(stats.mods_installed),
+        last_update: stats.last_update,
+        pack_version: stats.pack_version,
+        ..card.unwrap_or_default()
+    }
+}
+
+/// Stores fresh numbers on the instance after a sync and tells the UI with `instance_card_updated`.
+pub fn refresh(app: Option<&AppHandle>, instance_id: &str, minecraft_dir: &Path) {
+    let mut instances = crate::load_instances();
+    let Some(instance) = instances.iter_mut().find(|i| i.id == instance_id) else { return };
+    let card = apply(instance.info_card.clone(), compute(minecraft_dir));
+    if instance.info_card.as_ref() == Some(&card) {
+        return;
+    }
+    instance.info_card = Some(card.clone());
+    crate::save_instances(&instances);
+    if let Some(app) = app {
+        let _ = app.emit("instance_card_updated", InstanceCardUpdated { instance_id: instance_id.to_string(), info_card: card });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn card_counts_jars_and_reads_the_pack_version() {
+        let dir = tempfile::tempdir().unwrap();
+        let mods = dir.path().join("mods");
+        fs::create_dir_all(&mods).unwrap();
+        for name in ["a.jar", "b.JAR", "c.jar.disabled", "notes.txt"] {
+            fs::write(mods.join(name), b"x").unwrap();
+        }
+        let mut zip = zip::ZipWriter::new(fs::File::create(dir.path().join("modpack.zip")).unwrap());
+        zip.start_file("manifest.json", zip::write::FileOptions::default()).unwrap();
+        std::io::Write::write_all(&mut zip, br#"{"name": "Evento", "version": "2.3.1"}"#).unwrap();
+        zip.finish().unwrap();
+
+        let stats = compute(dir.path());
+        assert_eq!(stats.mods_installed, 2);
+        assert_eq!(stats.pack_version.as_deref(), Some("2.3.1"));
+        assert_eq!(stats.last_update.map(|d| d.len()), Some(10));
+
+        let staff_card = InfoCard { image: Some("card.png".to_string()), mods_installed: Some(99), ..Default::default() };
+        let card = apply(Some(staff_card), compute(dir.path()));
+        assert_eq!(card.image.as_deref(), Some("card.png"));
+        assert_eq!(card.mods_installed, Some(2));
+    }
+}
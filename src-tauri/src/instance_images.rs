@@ -0,0 +1,209 @@
+use base64::Engine;
+use image::ImageFormat;
+use sha1::{Digest, Sha1};
+use std::collections::HashSet;
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+use std::time::Duration;
+
+/// Imported images live in `<instance>/images`; `Instance.images` refers to them as `images/<file>`.
+pub const IMAGES_DIR: &str = "images";
+const THUMBS_DIR: &str = "thumbs";
+// Cards and the sidebar never show more than this; the carousel uses the original
+const THUMB_SIZE: u32 = 480;
+const MAX_IMAGE_BYTES: u64 = 20 * 1024 * 1024;
+
+/// True for entries already stored in the instance folder.
+pub fn is_stored(entry: &str) -> bool {
+    entry.strip_prefix("images/").map(|name| !name.is_empty() && !name.contains(['/', '\\']) && !name.starts_with('.')).unwrap_or(false)
+}
+
+/// `images/thumbs/<hash>.jpg` for a stored image.
+pub fn thumbnail_ref(entry: &str) -> Option<String> {
+    let name = entry.strip_prefix("images/").filter(|_| is_stored(entry))?;
+    let stem = name.rsplit_once('.').map(|(stem, _)| stem).unwrap_or(name);
+    Some(format!("images/{}/{}.jpg", THUMBS_DIR, stem))
+}
+
+fn decode_data_url(source: &str) -> Option<Vec<u8>> {
+    let (meta, data) = source.strip_prefix("data:")?.split_once(',')?;
+    if !meta.ends_with(";base64") {
+        return None;
+    }
+    base64::engine::general_purpose::STANDARD.decode(data.trim()).ok()
+}
+
+fn download(url: &str) -> Result<Vec<u8>, String> {
+    let client = reqwest::blocking::Client::builder()
+        .user_agent(crate::http::download_user_agent())
+        .timeout(Duration::from_secs(30))
+        .build()
+        .map_err(|e| e.to_string())?;
+    let resp = crate::http::send(&client, client.get(url))?;
+    if !resp.status().is_success() {
+        return Err(format!("La imagen respondió {}", resp.status()));
+    }
+    let mut bytes = Vec::new();
+    resp.take(MAX_IMAGE_BYTES + 1).read_to_end(&mut bytes).map_err(|e| e.to_string())?;
+    Ok(bytes)
+}
+
+/// Bytes of a data URL, an http(s) URL or a local file picked by the user.
+fn read_source(source: &str, allow_network: bool) -> Result<Vec<u8>, String> {
+    let source = source.trim();
+    if source.starts_with("data:") {
+        return decode_data_url(source).ok_or_else(|| "Imagen incrustada no válida".to_string());
+    }
+    if source.starts_with("http://") || source.starts_with("https://") {
+        if !allow_network {
+            return Err("Imagen remota".to_string());
+        }
+        return download(source);
+    }
+    let len = fs::metadata(source).map_err(|e| format!("No se pudo leer la imagen: {}", e))?.len();
+    if len > MAX_IMAGE_BYTES {
+        return Err("La imagen es demasiado grande".to_string());
+    }
+    fs::read(source).map_err(|e| format!("No se pudo leer la imagen: {}", e))
+}
+
+/// Writes the image under its content hash plus a JPEG thumbnail; returns the `images/...` entry.
+pub fn store(instance_dir: &Path, bytes: &[u8]) -> Result<String, String> {
+    if bytes.len() as u64 > MAX_IMAGE_BYTES {
+        return Err("La imagen es demasiado grande".to_string());
+    }
+    let format = image::guess_format(bytes).map_err(|_| "El archivo no es una imagen".to_string())?;
+    let ext = match format {
+        ImageFormat::Png => "png",
+        ImageFormat::Jpeg => "jpg",
+        ImageFormat::Gif => "gif",
+        ImageFormat::WebP => "webp",
+        _ => return Err("Formato de imagen no soportado (usa PNG, JPG, GIF o WebP)".to_string()),
+    };
+    let decoded = image::load_from_memory_with_format(bytes, format).map_err(|e| format!("Imagen dañada: {}", e))?;
+    let hash = hex::encode(Sha1::digest(bytes));
+    let stem = &hash[..16];
+
+    let images_dir = instance_dir.join(IMAGES_DIR);
+    let thumbs_dir = images_dir.join(THUMBS_DIR);
+    fs::create_dir_all(&thumbs_dir).map_err(|e| e.to_string())?;
+    let original = images_dir.join(format!("{}.{}", stem, ext));
+    if !original.exists() {
+        fs::write(&original, bytes).map_err(|e| e.to_string())?;
+    }
+    let thumb = thumbs_dir.join(format!("{}.jpg", stem));
+    if !thumb.exists() {
+        decoded.thumbnail(THUMB_SIZE, THUMB_SIZE).to_rgb8()
+            .save_with_format(&thumb, ImageFormat::Jpeg)
+            .map_err(|e| format!("No se pudo crear la miniatura: {}", e))?;
+    }
+    Ok(format!("{}/{}.{}", IMAGES_DIR, stem, ext))
+}
+
+/// Imports one image from any supported source into the instance folder.
+pub fn import(instance_dir: &Path, source: &str, allow_network: bool) -> Result<String, String> {
+    if is_stored(source) {
+        return Ok(source.to_string());
+    }
+    store(instance_dir, &read_source(source, allow_network)?)
+}
+
+/// Save-time pass: embedded and local images move into the instance folder. Remote URLs stay as
+/// they are (saving must not wait on the network; `attach_instance_image` downloads them).
+pub fn import_all(instance_dir: &Path, images: Vec<String>) -> Vec<String> {
+    images.into_iter()
+        .map(|source| {
+            if source.starts_with("http://") || source.starts_with("https://") {
+                return source;
+            }
+            import(instance_dir, &source, false).unwrap_or_else(|e| {
+                eprintln!("[instance_images] Import failed: {}", e);
+                source
+            })
+        })
+        .collect()
+}
+
+/// Removes stored images (and thumbnails) the instance no longer lists.
+pub fn collect_garbage(instance_dir: &Path, images: &[String]) {
+    let keep: HashSet<String> = images.iter()
+        .filter(|entry| is_stored(entry))
+        .flat_map(|entry| [entry.clone()].into_iter().chain(thumbnail_ref(entry)))
+        .collect();
+    for dir in [IMAGES_DIR.to_string(), format!("{}/{}", IMAGES_DIR, THUMBS_DIR)] {
+        let Ok(entries) = fs::read_dir(instance_dir.join(&dir)) else { continue; };
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if entry.path().is_file() && !keep.contains(&format!("{}/{}", dir, name)) {
+                let _ = fs::remove_file(entry.path());
+            }
+        }
+    }
+}
+
+/// Adds an image from a URL or a local file to an existing instance; returns the stored entry.
+#[tauri::command]
+pub async fn attach_instance_image(instance_id: String, source: String) -> Result<String, String> {
+    crate::settings::ensure_not_guest()?;
+    crate::run_blocking(move || {
+        let mut instances = crate::load_instances();
+        let instance = instances.iter_mut().find(|i| i.id == instance_id).ok_or("Instance not found")?;
+        let entry = import(Path::new(&instance.path), &source, true)?;
+        let images = instance.images.get_or_insert_with(Vec::new);
+        if !images.contains(&entry) {
+            images.push(entry.clone());
+        }
+        crate::save_instances(&instances);
+        Ok(entry)
+    }).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn png_bytes(width: u32, height: u32) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        image::RgbaImage::new(width, height)
+            .write_to(&mut std::io::Cursor::new(&mut bytes), ImageFormat::Png)
+            .unwrap();
+        bytes
+    }
+
+    #[test]
+    fn images_are_stored_by_hash_with_thumbnails() {
+        let dir = tempfile::tempdir().unwrap();
+        let entry = store(dir.path(), &png_bytes(1920, 1080)).unwrap();
+        assert!(is_stored(&entry) && entry.ends_with(".png"));
+        let thumb = dir.path().join(thumbnail_ref(&entry).unwrap());
+        let (width, height) = image::image_dimensions(&thumb).unwrap();
+        assert_eq!((width, height), (480, 270));
+        assert_eq!(store(dir.path(), &png_bytes(1920, 1080)).unwrap(), entry);
+        assert!(store(dir.path(), b"not an image").is_err());
+    }
+
+    #[test]
+    fn data_urls_import_and_unused_images_are_collected() {
+        let dir = tempfile::tempdir().unwrap();
+        let data_url = format!("data:image/png;base64,{}", base64::engine::general_purpose::STANDARD.encode(png_bytes(8, 8)));
+        let images = import_all(dir.path(), vec![data_url, "https://drk.example/banner.png".to_string()]);
+        assert!(is_stored(&images[0]));
+        assert_eq!(images[1], "https://drk.example/banner.png");
+
+        let other = store(dir.path(), &png_bytes(16, 16)).unwrap();
+        collect_garbage(dir.path(), &images);
+        assert!(dir.path().join(&images[0]).exists());
+        assert!(dir.path().join(thumbnail_ref(&images[0]).unwrap()).exists());
+        assert!(!dir.path().join(&other).exists());
+        assert!(!dir.path().join(thumbnail_ref(&other).unwrap()).exists());
+    }
+
+    #[test]
+    fn only_plain_names_count_as_stored() {
+        assert!(is_stored("images/abc.png"));
+        assert!(!is_stored("images/../admin.json"));
+        assert!(!is_stored("images/thumbs/abc.jpg"));
+        assert!(!is_stored("https://drk.example/a.png"));
+    }
+}
@@ -0,0 +1,76 @@
+const MAX_NAME_CHARS: usize = 64;
+// Device names Windows refuses as file or folder names, with or without an extension
+const RESERVED_WINDOWS_NAMES: [&str; 22] = [
+    "CON", "PRN", "AUX", "NUL",
+    "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8", "COM9",
+    "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// Id for a new instance; ids from the webview are only trusted when they match an existing one.
+pub fn new_id() -> String {
+    uuid::Uuid::new_v4().to_string()
+}
+
+fn is_reserved_windows_name(name: &str) -> bool {
+    let stem = name.split('.').next().unwrap_or(name).trim_end();
+    RESERVED_WINDOWS_NAMES.iter().any(|reserved| reserved.eq_ignore_ascii_case(stem))
+}
+
+/// Trimmed display name, or why it can't be used.
+pub fn normalize_name(name: &str) -> Result<String, String> {
+    let name = name.split_whitespace().collect::<Vec<_>>().join(" ");
+    if name.is_empty() {
+        return Err("El nombre de la instancia está vacío".to_string());
+    }
+    if name.chars().count() > MAX_NAME_CHARS {
+        return Err(format!("El nombre de la instancia no puede superar {} caracteres", MAX_NAME_CHARS));
+    }
+    if name.chars().any(char::is_control) {
+        return Err("El nombre de la instancia contiene caracteres no válidos".to_string());
+    }
+    if is_reserved_windows_name(&name) || is_reserved_windows_name(&folder_name(&name)) {
+        return Err(format!("\"{}\" es un nombre reservado de Windows", name));
+    }
+    Ok(name)
+}
+
+/// Folder for an instance name: anything but letters, digits, space, `-` and `_` becomes `_`,
+/// so separators and `..` can't reach the path.
+pub fn folder_name(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_alphanumeric() || c == ' ' || c == '-' || c == '_' { c } else { '_' })
+        .collect::<String>()
+        .trim()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_are_trimmed_and_checked() {
+        assert_eq!(normalize_name("  Evento   DRK  "), Ok("Evento DRK".to_string()));
+        assert!(normalize_name("   ").is_err());
+        assert!(normalize_name(&"a".repeat(MAX_NAME_CHARS + 1)).is_err());
+        assert!(normalize_name("con").is_err());
+        assert!(normalize_name("NUL.txt").is_err());
+        assert!(normalize_name("Com1").is_err());
+        assert!(normalize_name("Console").is_ok());
+        assert!(normalize_name("COM10").is_ok());
+    }
+
+    #[test]
+    fn folders_never_traverse() {
+        assert_eq!(folder_name("../../Windows"), "______Windows");
+        assert_eq!(folder_name("Evento: Final!"), "Evento_ Final_");
+        assert_eq!(folder_name(" Evento "), "Evento");
+    }
+
+    #[test]
+    fn generated_ids_are_unique_uuids() {
+        let (a, b) = (new_id(), new_id());
+        assert_ne!(a, b);
+        assert!(uuid::Uuid::parse_str(&a).is_ok());
+    }
+}
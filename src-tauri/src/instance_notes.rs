@@ -0,0 +1,65 @@
+// Long enough for an event's rules and known issues; keeps instances.json reasonable
+const MAX_NOTES_CHARS: usize = 20_000;
+
+/// Trimmed notes, `None` when empty. Rejects notes over the size limit.
+pub fn normalize_notes(notes: Option<String>) -> Result<Option<String>, String> {
+    let notes = notes.map(|n| n.trim().to_string()).filter(|n| !n.is_empty());
+    if notes.as_ref().map(|n| n.chars().count() > MAX_NOTES_CHARS).unwrap_or(false) {
+        return Err(format!("Las notas no pueden superar {} caracteres", MAX_NOTES_CHARS));
+    }
+    Ok(notes)
+}
+
+/// The instance as the UI sees it: admin remarks only go out through `get_instance_admin_notes`,
+/// config patches through `get_instance_config_patches`.
+pub fn for_display(mut instance: crate::Instance) -> crate::Instance {
+    instance.admin_notes = None;
+    instance.config_patches = None;
+    instance
+}
+
+fn update_instance(instance_id: &str, update: impl FnOnce(&mut crate::Instance)) -> Result<(), String> {
+    let mut instances = crate::load_instances();
+    let instance = instances.iter_mut().find(|i| i.id == instance_id)
+        .ok_or("Instance not found")?;
+    update(instance);
+    crate::save_instances(&instances);
+    Ok(())
+}
+
+/// Notes shown to players on the instance page.
+#[tauri::command]
+pub fn set_instance_notes(password: String, instance_id: String, notes: Option<String>) -> Result<(), String> {
+    crate::ensure_admin(password)?;
+    let notes = normalize_notes(notes)?;
+    update_instance(&instance_id, |instance| instance.notes = notes)
+}
+
+#[tauri::command]
+pub fn get_instance_admin_notes(password: String, instance_id: String) -> Result<Option<String>, String> {
+    crate::ensure_admin(password)?;
+    let instance = crate::load_instances().into_iter().find(|i| i.id == instance_id)
+        .ok_or("Instance not found")?;
+    Ok(instance.admin_notes)
+}
+
+/// Staff-only remarks (event quirks, known fixes); never sent to the UI without the password.
+#[tauri::command]
+pub fn set_instance_admin_notes(password: String, instance_id: String, notes: Option<String>) -> Result<(), String> {
+    crate::ensure_admin(password)?;
+    let notes = normalize_notes(notes)?;
+    update_instance(&instance_id, |instance| instance.admin_notes = notes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn notes_are_trimmed_and_bounded() {
+        assert_eq!(normalize_notes(Some("  Usar Java 17\n".into())).unwrap(), Some("Usar Java 17".to_string()));
+        assert_eq!(normalize_notes(Some("   ".into())).unwrap(), None);
+        assert_eq!(normalize_notes(None).unwrap(), None);
+        assert!(normalize_notes(Some("x".repeat(MAX_NOTES_CHARS + 1))).is_err());
+    }
+}
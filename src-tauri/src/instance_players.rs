@@ -0,0 +1,128 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+/// An account that has played an instance, as the game remembers it.
+#[derive(Serialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct InstancePlayer {
+    /// Dashed, lowercase
+    pub uuid: String,
+    /// `None` when only a world's `playerdata` knows the account
+    pub name: Option<String>,
+    /// When the game stops trusting the cached name, e.g. `2024-06-01 12:00:00 +0000`
+    #[serde(rename = "expiresOn")]
+    pub expires_on: Option<String>,
+    /// World folders holding a `playerdata/<uuid>.dat` for the account
+    pub worlds: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct UserCacheEntry {
+    name: String,
+    uuid: String,
+    #[serde(rename = "expiresOn", default)]
+    expires_on: Option<String>,
+}
+
+/// `0123456789abcdef0123456789abcdef` or any casing of the dashed form, as the dashed form.
+fn normalize_uuid(uuid: &str) -> Option<String> {
+    let hex: String = uuid.trim().chars().filter(|c| *c != '-').collect::<String>().to_ascii_lowercase();
+    if hex.len() != 32 || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    Some(format!("{}-{}-{}-{}-{}", &hex[..8], &hex[8..12], &hex[12..16], &hex[16..20], &hex[20..]))
+}
+
+fn player<'a>(players: &'a mut BTreeMap<String, InstancePlayer>, uuid: &str) -> Option<&'a mut InstancePlayer> {
+    let uuid = normalize_uuid(uuid)?;
+    Some(players.entry(uuid.clone()).or_insert(InstancePlayer { uuid, name: None, expires_on: None, worlds: Vec::new() }))
+}
+
+/// Accounts from the vanilla `usercache.json`, Forge's `usernamecache.json` and the worlds'
+/// `playerdata`, one entry per UUID, sorted by name (unknown names last).
+pub fn players_in(minecraft_dir: &Path) -> Vec<InstancePlayer> {
+    let mut players = BTreeMap::new();
+    let usercache: Vec<UserCacheEntry> = fs::read_to_string(minecraft_dir.join("usercache.json")).ok()
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default();
+    for entry in usercache {
+        if let Some(player) = player(&mut players, &entry.uuid) {
+            player.name = Some(entry.name);
+            player.expires_on = entry.expires_on;
+        }
+    }
+    let usernamecache: BTreeMap<String, String> = fs::read_to_string(minecraft_dir.join("usernamecache.json")).ok()
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default();
+    for (uuid, name) in usernamecache {
+        if let Some(player) = player(&mut players, &uuid) {
+            player.name.get_or_insert(name);
+        }
+    }
+    for world in fs::read_dir(minecraft_dir.join("saves")).into_iter().flatten().flatten() {
+        let folder = world.file_name().to_string_lossy().to_string();
+        for data in fs::read_dir(world.path().join("playerdata")).into_iter().flatten().flatten() {
+            let name = data.file_name().to_string_lossy().to_string();
+            let Some(uuid) = name.strip_suffix(".dat") else { continue };
+            if let Some(player) = player(&mut players, uuid) {
+                if !player.worlds.contains(&folder) {
+                    player.worlds.push(folder.clone());
+                }
+            }
+        }
+    }
+    let mut players: Vec<InstancePlayer> = players.into_values().collect();
+    for player in &mut players {
+        player.worlds.sort();
+    }
+    players.sort_by_key(|p| (p.name.is_none(), p.name.as_deref().map(str::to_lowercase)));
+    players
+}
+
+/// Who has played an instance on this machine, for staff chasing per-player data problems.
+#[tauri::command]
+pub fn get_instance_players(password: String, instance_id: String) -> Result<Vec<InstancePlayer>, String> {
+    crate::ensure_admin(password)?;
+    let instance = crate::load_instances().into_iter().find(|i| i.id == instance_id)
+        .ok_or("Instance not found")?;
+    Ok(players_in(&Path::new(&instance.path).join("minecraft")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn caches_and_playerdata_merge_by_uuid() {
+        let dir = tempfile::tempdir().unwrap();
+        let minecraft = dir.path();
+        fs::write(minecraft.join("usercache.json"), r#"[
+            {"name": "Drk", "uuid": "069A79F4-44E9-4726-A5BE-FCA90E38AAF5", "expiresOn": "2024-06-01 12:00:00 +0000"},
+            {"name": "roto", "uuid": "no-es-un-uuid"}
+        ]"#).unwrap();
+        fs::write(minecraft.join("usernamecache.json"), r#"{
+            "069a79f444e94726a5befca90e38aaf5": "NombreViejo",
+            "853c80ef-3c37-49fd-aa49-938b674adae6": "alex"
+        }"#).unwrap();
+        for world in ["Mundo", "Evento"] {
+            fs::create_dir_all(minecraft.join("saves").join(world).join("playerdata")).unwrap();
+            fs::write(minecraft.join("saves").join(world).join("playerdata").join("069a79f4-44e9-4726-a5be-fca90e38aaf5.dat"), "").unwrap();
+        }
+        fs::write(minecraft.join("saves").join("Evento").join("playerdata").join("11111111-2222-3333-4444-555555555555.dat"), "").unwrap();
+
+        let players = players_in(minecraft);
+        let summary: Vec<(Option<&str>, &str, usize)> = players.iter()
+            .map(|p| (p.name.as_deref(), p.uuid.as_str(), p.worlds.len()))
+            .collect();
+        assert_eq!(summary, vec![
+            (Some("alex"), "853c80ef-3c37-49fd-aa49-938b674adae6", 0),
+            (Some("Drk"), "069a79f4-44e9-4726-a5be-fca90e38aaf5", 2),
+            (None, "11111111-2222-3333-4444-555555555555", 1),
+        ]);
+        assert_eq!(players[1].worlds, vec!["Evento".to_string(), "Mundo".to_string()]);
+        assert_eq!(players[1].expires_on.as_deref(), Some("2024-06-01 12:00:00 +0000"));
+        assert!(players_in(&minecraft.join("vacía")).is_empty());
+    }
+}
@@ -0,0 +1,186 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use crate::settings::{ensure_not_guest, load_settings, save_settings};
+
+/// JVM crash logs kept by the `archive` policy, under the game's own logs folder.
+const ARCHIVE_DIR: &str = "jvm";
+
+/// What happens to the transient files a session leaves in the game folder. Files the launcher
+/// regenerates, duplicates and heap dumps are always deleted; the policy decides for the
+/// JVM's own crash logs.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Default, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum JanitorPolicy {
+    /// Crash logs go to `logs/jvm` and are pruned after `archiveDays`
+    #[default]
+    Archive,
+    Delete,
+    /// Leave the folder alone
+    Off,
+}
+
+#[derive(Serialize, Deserialize, Clone, JsonSchema)]
+pub struct JanitorSettings {
+    #[serde(default)]
+    pub policy: JanitorPolicy,
+    #[serde(rename = "archiveDays", alias = "archive_days", default = "default_archive_days")]
+    pub archive_days: u64,
+}
+
+impl Default for JanitorSettings {
+    fn default() -> Self {
+        JanitorSettings { policy: JanitorPolicy::default(), archive_days: default_archive_days() }
+    }
+}
+
+fn default_archive_days() -> u64 {
+    14
+}
+
+#[derive(Debug, PartialEq)]
+enum Transient {
+    /// Worth keeping a while to diagnose JVM crashes
+    Diagnostic,
+    /// Rewritten on every launch, a duplicate, or too big to keep
+    Disposable,
+}
+
+fn numbered(name: &str, prefix: &str, suffix: &str) -> bool {
+    name.strip_prefix(prefix)
+        .and_then(|rest| rest.strip_suffix(suffix))
+        .is_some_and(|pid| !pid.is_empty() && pid.bytes().all(|b| b.is_ascii_digit()))
+}
+
+/// Only these names are ever touched; anything else in the folder (saves, config, options,
+/// mods) isn't the janitor's business.
+fn classify(name: &str) -> Option<Transient> {
+    if numbered(name, "hs_err_pid", ".log") || numbered(name, "replay_pid", ".log") {
+        return Some(Transient::Diagnostic);
+    }
+    let usercache_copy = name != "usercache.json" && name.starts_with("usercache") && name.contains(".json");
+    if name == "args.txt" || name == "classpath.jar" || numbered(name, "java_pid", ".hprof") || usercache_copy {
+        return Some(Transient::Disposable);
+    }
+    None
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct Cleanup {
+    pub archived: usize,
+    pub deleted: usize,
+}
+
+/// Tidies the top level of `minecraft_dir` after a session; subfolders are never entered.
+pub fn clean(minecraft_dir: &Path, settings: &JanitorSettings, now: SystemTime) -> Cleanup {
+    let mut cleanup = Cleanup::default();
+    if settings.policy == JanitorPolicy::Off {
+        return cleanup;
+    }
+    let archive = minecraft_dir.join("logs").join(ARCHIVE_DIR);
+    for entry in fs::read_dir(minecraft_dir).into_iter().flatten().flatten() {
+        // Symlinks point somewhere the janitor doesn't own
+        if !entry.file_type().map(|t| t.is_file()).unwrap_or(false) {
+            continue;
+        }
+        let name = entry.file_name().to_string_lossy().to_string();
+        let Some(kind) = classify(&name) else { continue };
+        if kind == Transient::Diagnostic && settings.policy == JanitorPolicy::Archive {
+            // A log that can't be moved stays where it is rather than being lost
+            if fs::create_dir_all(&archive).and_then(|_| fs::rename(entry.path(), archive.join(&name))).is_ok() {
+                cleanup.archived += 1;
+            }
+        } else if fs::remove_file(entry.path()).is_ok() {
+            cleanup.deleted += 1;
+        }
+    }
+    cleanup.deleted += prune(&archive, Duration::from_secs(settings.archive_days * 24 * 60 * 60), now);
+    cleanup
+}
+
+/// Removes archived crash logs older than `max_age`.
+fn prune(archive: &Path, max_age: Duration, now: SystemTime) -> usize {
+    let expired = |path: &PathBuf| {
+        fs::metadata(path)
+            .and_then(|m| m.modified())
+            .map(|modified| now.duration_since(modified).unwrap_or_default() >= max_age)
+            .unwrap_or(false)
+    };
+    fs::read_dir(archive).into_iter().flatten().flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file() && expired(path))
+        .filter(|path| fs::remove_file(path).is_ok())
+        .count()
+}
+
+/// Runs once the game process of an instance has exited.
+pub fn after_session(instance_path: &Path) {
+    let cleanup = clean(&instance_path.join("minecraft"), &load_settings().janitor, SystemTime::now());
+    if cleanup != Cleanup::default() {
+        eprintln!("[janitor] Archived {} and deleted {} file(s) in {}", cleanup.archived, cleanup.deleted, instance_path.display());
+    }
+}
+
+#[tauri::command]
+pub fn get_janitor_settings() -> JanitorSettings {
+    load_settings().janitor
+}
+
+#[tauri::command]
+pub fn set_janitor_settings(janitor: JanitorSettings) -> Result<(), String> {
+    ensure_not_guest()?;
+    let mut settings = load_settings();
+    settings.janitor = janitor;
+    save_settings(&settings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_known_transient_names_match() {
+        assert_eq!(classify("hs_err_pid1234.log"), Some(Transient::Diagnostic));
+        assert_eq!(classify("replay_pid99.log"), Some(Transient::Diagnostic));
+        assert_eq!(classify("java_pid1234.hprof"), Some(Transient::Disposable));
+        assert_eq!(classify("args.txt"), Some(Transient::Disposable));
+        assert_eq!(classify("usercache (1).json"), Some(Transient::Disposable));
+        assert_eq!(classify("usercache.json.bak"), Some(Transient::Disposable));
+        assert_eq!(classify("usercache.json"), None);
+        assert_eq!(classify("hs_err_pid.log"), None);
+        assert_eq!(classify("hs_err_pidabc.log"), None);
+        assert_eq!(classify("options.txt"), None);
+        assert_eq!(classify("servers.dat"), None);
+    }
+
+    #[test]
+    fn sessions_leave_saves_and_config_alone() {
+        let dir = tempfile::tempdir().unwrap();
+        let minecraft = dir.path().join("minecraft");
+        for folder in ["saves/Mundo", "config"] {
+            fs::create_dir_all(minecraft.join(folder)).unwrap();
+        }
+        for file in ["hs_err_pid42.log", "args.txt", "java_pid42.hprof", "options.txt", "saves/Mundo/args.txt", "config/hs_err_pid1.log"] {
+            fs::write(minecraft.join(file), "x").unwrap();
+        }
+
+        let settings = JanitorSettings::default();
+        let cleanup = clean(&minecraft, &settings, SystemTime::now());
+        assert_eq!(cleanup, Cleanup { archived: 1, deleted: 2 });
+        assert!(minecraft.join("logs").join(ARCHIVE_DIR).join("hs_err_pid42.log").is_file());
+        assert!(!minecraft.join("args.txt").exists());
+        for kept in ["options.txt", "saves/Mundo/args.txt", "config/hs_err_pid1.log"] {
+            assert!(minecraft.join(kept).is_file(), "{} was touched", kept);
+        }
+
+        // Archived logs expire after `archiveDays`
+        let later = SystemTime::now() + Duration::from_secs(15 * 24 * 60 * 60);
+        assert_eq!(clean(&minecraft, &settings, later), Cleanup { archived: 0, deleted: 1 });
+        let off = JanitorSettings { policy: JanitorPolicy::Off, ..JanitorSettings::default() };
+        fs::write(minecraft.join("args.txt"), "x").unwrap();
+        assert_eq!(clean(&minecraft, &off, SystemTime::now()), Cleanup::default());
+        assert!(minecraft.join("args.txt").exists());
+    }
+}
@@ -0,0 +1,147 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Manager, State};
+use crate::settings::{load_settings, save_settings};
+
+/// Command line flag for station shortcuts: `--kiosk <instance id>` runs `kiosk_start` once the
+/// launcher has loaded.
+pub const KIOSK_FLAG: &str = "--kiosk";
+const DEFAULT_MAX_CATALOG_AGE_HOURS: u64 = 24;
+
+/// Event-night stations: which account they play with and how stale the catalog may be.
+#[derive(Serialize, Deserialize, Clone, Default, JsonSchema)]
+pub struct KioskSettings {
+    /// Offline account the station signs in with; `None` keeps whoever is signed in
+    #[serde(default)]
+    pub username: Option<String>,
+    /// A cached catalog older than this stops the start; `None` is 24 hours
+    #[serde(rename = "maxCatalogAgeHours", alias = "max_catalog_age_hours", default)]
+    pub max_catalog_age_hours: Option<u64>,
+}
+
+/// Instance given with `--kiosk`, consumed by the frontend once it has loaded.
+#[derive(Default)]
+pub struct PendingKiosk(pub Mutex<Option<String>>);
+
+pub fn kiosk_arg(args: &[String]) -> Option<String> {
+    crate::shortcuts::flag_value(args, KIOSK_FLAG)
+}
+
+/// Why the catalog can't be trusted for tonight: no usable copy at all, or only a cached one
+/// older than `max_age`.
+pub fn catalog_problem(age: Option<Duration>, max_age: Duration) -> Option<String> {
+    match age {
+        None => Some("No se pudo descargar el catálogo del evento y no hay copia guardada".to_string()),
+        Some(age) if age > max_age => Some(format!(
+            "El catálogo del evento no se actualiza desde hace {} horas; revisa la conexión del puesto",
+            age.as_secs() / 3600
+        )),
+        Some(_) => None,
+    }
+}
+
+/// Fetches the admin catalog and refuses a stale one. Nothing to check without a catalog URL.
+fn ensure_catalog_fresh(max_age: Duration) -> Result<(), String> {
+    let configured = load_settings().mod_scan.catalog_url.is_some_and(|u| !u.trim().is_empty());
+    if !configured {
+        return Ok(());
+    }
+    let age = crate::minecraft::mod_scan::catalog_body().and_then(|_| crate::minecraft::mod_scan::catalog_age());
+    match catalog_problem(age, max_age) {
+        Some(problem) => Err(problem),
+        None => Ok(()),
+    }
+}
+
+/// Signs in the station account. Called at startup too, so the UI opens past the login screen.
+pub fn login_station(state: &crate::AppState) -> Result<crate::auth::MinecraftProfile, String> {
+    if let Some(username) = load_settings().kiosk.username.filter(|u| !u.trim().is_empty()) {
+        return crate::auth::login_offline(state, &username);
+    }
+    let auth_state = state.auth.lock().map_err(|_| "Failed to lock auth state")?;
+    auth_state.profile.clone().ok_or_else(|| "Configura la cuenta del puesto (modo kiosco) o inicia sesión".to_string())
+}
+
+#[tauri::command]
+pub fn take_pending_kiosk(state: State<'_, PendingKiosk>) -> Option<String> {
+    state.0.lock().ok().and_then(|mut pending| pending.take())
+}
+
+/// Event-night start for one station: checks the admin catalog is fresh, signs in the station
+/// account, verifies the instance and launches it fullscreen onto its server. Progress goes out
+/// as `launch_progress`, like a normal launch.
+#[tauri::command]
+pub async fn kiosk_start(app: AppHandle, instance_id: String, state: State<'_, crate::AppState>) -> Result<(), String> {
+    crate::settings::ensure_launch_allowed(&instance_id)?;
+    let instance = crate::load_instances().into_iter().find(|i| i.id == instance_id)
+        .ok_or("Instance not found")?;
+    if instance.launcher.is_none() {
+        return Err("Launcher not configured".to_string());
+    }
+    let hours = load_settings().kiosk.max_catalog_age_hours.unwrap_or(DEFAULT_MAX_CATALOG_AGE_HOURS);
+    crate::run_blocking(move || ensure_catalog_fresh(Duration::from_secs(hours * 3600))).await?;
+    let profile = login_station(&state)?;
+    let verify_app = app.clone();
+    crate::run_blocking(move || crate::verify_instance_files(&verify_app, &instance, &profile)).await?;
+    crate::launch_instance_with(app, instance_id, None, true, state).await
+}
+
+#[tauri::command]
+pub fn get_kiosk_settings() -> KioskSettings {
+    load_settings().kiosk
+}
+
+#[tauri::command]
+pub fn set_kiosk_settings(password: String, kiosk: KioskSettings) -> Result<(), String> {
+    crate::ensure_admin(password)?;
+    if let Some(username) = kiosk.username.as_deref().filter(|u| !u.trim().is_empty()) {
+        if !crate::auth::is_valid_username(username.trim()) {
+            return Err("El nombre de usuario debe tener entre 3 y 16 caracteres (letras, números o _)".to_string());
+        }
+    }
+    let mut settings = load_settings();
+    settings.kiosk = kiosk;
+    save_settings(&settings)
+}
+
+/// Desktop shortcut that starts `instance_id` as a kiosk station.
+#[tauri::command]
+pub fn create_kiosk_shortcut(password: String, instance_id: String) -> Result<String, String> {
+    crate::ensure_admin(password)?;
+    let instance = crate::load_instances().into_iter().find(|i| i.id == instance_id)
+        .ok_or("Instance not found")?;
+    let exe = crate::shortcuts::launcher_exe()?;
+    let name = format!("{} (puesto)", instance.name);
+    let path = crate::shortcuts::create_desktop_shortcut(&name, &exe, KIOSK_FLAG, &instance.id)?;
+    Ok(path.to_string_lossy().to_string())
+}
+
+/// Startup half of `--kiosk`: the station account, before the window asks for a login.
+pub fn init(app: &AppHandle) {
+    if app.state::<PendingKiosk>().0.lock().map(|pending| pending.is_none()).unwrap_or(true) {
+        return;
+    }
+    if let Err(e) = login_station(&app.state::<crate::AppState>()) {
+        eprintln!("[kiosk] Station login failed: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stale_or_missing_catalogs_stop_the_station() {
+        let day = Duration::from_secs(24 * 3600);
+        assert_eq!(catalog_problem(Some(Duration::from_secs(5)), day), None);
+        assert!(catalog_problem(None, day).unwrap().contains("no hay copia"));
+        assert!(catalog_problem(Some(day * 3), day).unwrap().contains("72 horas"));
+
+        let args = |v: &[&str]| v.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        assert_eq!(kiosk_arg(&args(&["drk", "--kiosk", "evento"])).as_deref(), Some("evento"));
+        assert_eq!(kiosk_arg(&args(&["drk", "--kiosk=evento"])).as_deref(), Some("evento"));
+        assert_eq!(kiosk_arg(&args(&["drk", "--launch-instance", "evento"])), None);
+    }
+}
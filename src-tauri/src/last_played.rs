@@ -0,0 +1,175 @@
+use chrono::{DateTime, Datelike, Local, NaiveDateTime, SecondsFormat, TimeZone, Utc};
+use schemars::JsonSchema;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// `lastPlayed` is written as RFC 3339. Older files hold unix seconds (written by Rust) or the
+/// UI's `toISOString`; what can't be read at all becomes the epoch, shown as "never".
+pub fn serialize<S: Serializer>(at: &DateTime<Utc>, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&at.to_rfc3339_opts(SecondsFormat::Secs, true))
+}
+
+pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<DateTime<Utc>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Raw {
+        Secs(i64),
+        Text(String),
+    }
+    let parsed = match Raw::deserialize(deserializer)? {
+        Raw::Secs(secs) => Utc.timestamp_opt(secs, 0).single(),
+        Raw::Text(text) => parse(&text),
+    };
+    Ok(parsed.unwrap_or(DateTime::UNIX_EPOCH))
+}
+
+pub fn parse(value: &str) -> Option<DateTime<Utc>> {
+    let value = value.trim();
+    if let Ok(secs) = value.parse::<i64>() {
+        return Utc.timestamp_opt(secs, 0).single();
+    }
+    DateTime::parse_from_rfc3339(value).map(|at| at.with_timezone(&Utc)).ok()
+        // No offset: taken as UTC, like the seconds
+        .or_else(|| NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S%.f").ok().map(|at| at.and_utc()))
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Lang {
+    Es,
+    En,
+}
+
+impl Lang {
+    /// Spanish unless the UI asks for English (`navigator.language`, e.g. `en-US`).
+    fn from_locale(locale: Option<&str>) -> Self {
+        match locale.map(|l| l.to_ascii_lowercase()) {
+            Some(l) if l.starts_with("en") => Lang::En,
+            _ => Lang::Es,
+        }
+    }
+}
+
+fn ago(lang: Lang, n: i64, es: (&str, &str), en: &str) -> String {
+    match lang {
+        Lang::Es => format!("hace {} {}", n, if n == 1 { es.0 } else { es.1 }),
+        Lang::En => format!("{} {}{} ago", n, en, if n == 1 { "" } else { "s" }),
+    }
+}
+
+/// "hace 3 días" / "3 days ago". Days count calendar days in `now`'s time zone, so something
+/// played last night is "ayer" even if it was six hours ago.
+fn relative<Tz: TimeZone>(at: &DateTime<Tz>, now: &DateTime<Tz>, lang: Lang) -> String {
+    if at.timestamp() <= 0 {
+        return match lang { Lang::Es => "Nunca", Lang::En => "Never" }.to_string();
+    }
+    let seconds = now.clone().signed_duration_since(at.clone()).num_seconds().max(0);
+    let days = (now.date_naive() - at.date_naive()).num_days().max(0);
+    match (seconds, days) {
+        (0..=59, _) => match lang { Lang::Es => "ahora mismo", Lang::En => "just now" }.to_string(),
+        (60..=3599, _) => ago(lang, seconds / 60, ("minuto", "minutos"), "minute"),
+        (_, 0) => ago(lang, seconds / 3600, ("hora", "horas"), "hour"),
+        (_, 1) => match lang { Lang::Es => "ayer", Lang::En => "yesterday" }.to_string(),
+        (_, 2..=6) => ago(lang, days, ("día", "días"), "day"),
+        (_, 7..=29) => ago(lang, days / 7, ("semana", "semanas"), "week"),
+        (_, 30..=364) => ago(lang, days / 30, ("mes", "meses"), "month"),
+        _ => ago(lang, days / 365, ("año", "años"), "year"),
+    }
+}
+
+/// "14 nov 2023" / "Nov 14, 2023".
+fn date<Tz: TimeZone>(at: &DateTime<Tz>, lang: Lang) -> String {
+    const ES: [&str; 12] = ["ene", "feb", "mar", "abr", "may", "jun", "jul", "ago", "sep", "oct", "nov", "dic"];
+    const EN: [&str; 12] = ["Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec"];
+    let month = at.month0() as usize;
+    match lang {
+        Lang::Es => format!("{} {} {}", at.day(), ES[month], at.year()),
+        Lang::En => format!("{} {}, {}", EN[month], at.day(), at.year()),
+    }
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LastPlayed {
+    #[serde(rename = "instanceId")]
+    pub instance_id: String,
+    /// RFC 3339, UTC
+    pub at: String,
+    /// "hace 3 días", or "Nunca" for instances never launched
+    pub relative: String,
+    /// Calendar date in this machine's time zone
+    pub date: String,
+}
+
+/// Records a real start of the game; the UI no longer writes `lastPlayed` itself.
+pub fn record_launch(instance_id: &str) {
+    let mut instances = crate::load_instances();
+    if let Some(instance) = instances.iter_mut().find(|i| i.id == instance_id) {
+        instance.last_played = Utc::now();
+        crate::save_instances(&instances);
+    }
+}
+
+/// When `instance_id` was last played, worded for the UI in `locale` (Spanish or English).
+#[tauri::command]
+pub fn format_last_played(instance_id: String, locale: Option<String>) -> Result<LastPlayed, String> {
+    let instance = crate::load_instances().into_iter().find(|i| i.id == instance_id)
+        .ok_or("Instance not found")?;
+    let lang = Lang::from_locale(locale.as_deref());
+    let at = instance.last_played.with_timezone(&Local);
+    Ok(LastPlayed {
+        instance_id,
+        at: instance.last_played.to_rfc3339_opts(SecondsFormat::Secs, true),
+        relative: relative(&at, &Local::now(), lang),
+        date: date(&at, lang),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Deserialize, Serialize)]
+    struct Stored {
+        #[serde(with = "super")]
+        at: DateTime<Utc>,
+    }
+
+    fn read(json: &str) -> DateTime<Utc> {
+        serde_json::from_str::<Stored>(json).unwrap().at
+    }
+
+    #[test]
+    fn old_and_new_values_read_back() {
+        let expected = Utc.timestamp_opt(1_700_000_000, 0).unwrap();
+        assert_eq!(read(r#"{"at": "1700000000"}"#), expected);
+        assert_eq!(read(r#"{"at": 1700000000}"#), expected);
+        assert_eq!(read(r#"{"at": "2023-11-14T22:13:20.000Z"}"#), expected);
+        assert_eq!(read(r#"{"at": "2023-11-14T23:13:20+01:00"}"#), expected);
+        assert_eq!(read(r#"{"at": "2023-11-14T22:13:20"}"#), expected);
+        assert_eq!(read(r#"{"at": "ayer"}"#), DateTime::UNIX_EPOCH);
+
+        let written = serde_json::to_string(&Stored { at: expected }).unwrap();
+        assert_eq!(written, r#"{"at":"2023-11-14T22:13:20Z"}"#);
+        assert_eq!(read(&written), expected);
+    }
+
+    #[test]
+    fn relative_times_follow_the_calendar() {
+        let now = Utc.with_ymd_and_hms(2024, 3, 10, 9, 0, 0).unwrap();
+        let at = |d: u32, h: u32, m: u32| Utc.with_ymd_and_hms(2024, 3, d, h, m, 0).unwrap();
+        assert_eq!(relative(&now, &now, Lang::Es), "ahora mismo");
+        assert_eq!(relative(&at(10, 8, 59), &now, Lang::Es), "hace 1 minuto");
+        assert_eq!(relative(&at(10, 8, 55), &now, Lang::Es), "hace 5 minutos");
+        assert_eq!(relative(&at(10, 8, 0), &now, Lang::En), "1 hour ago");
+        assert_eq!(relative(&at(9, 23, 0), &now, Lang::Es), "ayer");
+        assert_eq!(relative(&at(7, 9, 0), &now, Lang::En), "3 days ago");
+        assert_eq!(relative(&at(1, 9, 0), &now, Lang::Es), "hace 1 semana");
+        assert_eq!(relative(&Utc.with_ymd_and_hms(2023, 11, 14, 0, 0, 0).unwrap(), &now, Lang::Es), "hace 3 meses");
+        assert_eq!(relative(&DateTime::UNIX_EPOCH, &now, Lang::En), "Never");
+        // Clock moved back since the last launch
+        assert_eq!(relative(&at(10, 9, 30), &now, Lang::Es), "ahora mismo");
+
+        assert_eq!(date(&now, Lang::Es), "10 mar 2024");
+        assert_eq!(date(&now, Lang::En), "Mar 10, 2024");
+        assert_eq!(Lang::from_locale(Some("en-US")), Lang::En);
+        assert_eq!(Lang::from_locale(Some("es-ES")), Lang::Es);
+    }
+}
@@ -0,0 +1,404 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use tauri::{AppHandle, EventId, Listener};
+
+const HISTORY_FILE: &str = "launch-history.json";
+const MAX_ATTEMPTS: usize = 50;
+// Reported by the launch thread itself once preparation is over; not preparation stages
+const FINAL_STAGES: [&str; 4] = ["iniciado", "cerrado", "crasheado", "error"];
+// `launch_progress` stage ids and the preparation phase each one starts
+const PHASES: [(&str, &str); 10] = [
+    ("iniciando", "manifest"),
+    ("cliente", "client"),
+    ("librerias", "libraries"),
+    ("assets", "assets"),
+    ("fabric", "loader"),
+    ("forge", "loader"),
+    // Forge fetches assets and libraries at the same time
+    ("descargas", "downloads"),
+    ("mods", "mods"),
+    ("java", "java"),
+    ("comando", "command"),
+];
+
+/// Phase name for a progress stage; "listo" until the process is up is the spawn itself.
+fn phase_of(stage: &str) -> &str {
+    match PHASES.iter().find(|(id, _)| *id == stage) {
+        Some((_, phase)) => phase,
+        None if stage == "listo" => "spawn",
+        None => stage,
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum Outcome {
+    /// The game ran and exited with code 0
+    Closed,
+    /// The game ran and exited with an error code
+    Crashed,
+    /// The game never started: preparation or spawning failed
+    Failed,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum FailureKind {
+    Network,
+    Disk,
+    Antivirus,
+    Crash,
+    Other,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct StageTiming {
+    pub stage: String,
+    pub millis: u64,
+}
+
+/// Where the preparation time of a launch went, for the `iniciado` progress payload.
+#[derive(Serialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct PrepareTimings {
+    pub stages: Vec<StageTiming>,
+    #[serde(rename = "prepareMillis")]
+    pub prepare_millis: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LaunchAttempt {
+    /// Unix seconds
+    #[serde(rename = "startedAt", alias = "started_at")]
+    pub started_at: u64,
+    /// Preparation phases in the order they ran; a phase seen twice is added up
+    #[serde(default)]
+    pub stages: Vec<StageTiming>,
+    /// Download, sync and command building time; `None` when preparation failed
+    #[serde(rename = "prepareMillis", alias = "prepare_millis", default)]
+    pub prepare_millis: Option<u64>,
+    /// How long the game process ran
+    #[serde(rename = "playMillis", alias = "play_millis", default)]
+    pub play_millis: Option<u64>,
+    pub outcome: Outcome,
+    #[serde(rename = "errorCode", alias = "error_code", default)]
+    pub error_code: Option<i32>,
+    #[serde(default)]
+    pub failure: Option<FailureKind>,
+    #[serde(default)]
+    pub message: Option<String>,
+    /// Safe mode step the session ran with
+    #[serde(rename = "safeMode", alias = "safe_mode", default, skip_serializing_if = "Option::is_none")]
+    pub safe_mode: Option<crate::safe_mode::SafeModeStep>,
+}
+
+/// What a failed attempt points at, from its outcome and the error reported to the player.
+pub fn classify_failure(outcome: Outcome, message: &str) -> Option<FailureKind> {
+    let lower = message.to_lowercase();
+    let any = |needles: &[&str]| needles.iter().any(|n| lower.contains(n));
+    if outcome == Outcome::Closed {
+        return None;
+    }
+    if message.contains(crate::antivirus::ERROR_MARKER) {
+        Some(FailureKind::Antivirus)
+    } else if any(&["no space left", "os error 28", "os error 112", "disk full", "espacio en disco", "read-only file system", "os error 30"]) {
+        Some(FailureKind::Disk)
+    } else if outcome == Outcome::Crashed {
+        Some(FailureKind::Crash)
+    } else if any(&["failed to download", "error sending request", "timed out", "connection", "dns error", "http "]) {
+        Some(FailureKind::Network)
+    } else {
+        Some(FailureKind::Other)
+    }
+}
+
+/// Phase durations of one launch, fed from its `launch_progress` events.
+pub struct Timeline {
+    started_at: u64,
+    start: Instant,
+    current: Option<(String, Instant)>,
+    stages: Vec<StageTiming>,
+    prepare_millis: Option<u64>,
+    running_since: Option<Instant>,
+}
+
+impl Timeline {
+    pub fn new() -> Self {
+        Timeline {
+            started_at: crate::unix_now(),
+            start: Instant::now(),
+            current: None,
+            stages: Vec::new(),
+            prepare_millis: None,
+            running_since: None,
+        }
+    }
+
+    fn close_stage(&mut self) {
+        if let Some((stage, since)) = self.current.take() {
+            let millis = since.elapsed().as_millis() as u64;
+            match self.stages.iter_mut().find(|s| s.stage == stage) {
+                Some(timing) => timing.millis += millis,
+                None => self.stages.push(StageTiming { stage, millis }),
+            }
+        }
+    }
+
+    pub fn stage(&mut self, stage: &str) {
+        if self.prepare_millis.is_some() || FINAL_STAGES.contains(&stage) {
+            return;
+        }
+        let phase = phase_of(stage);
+        if self.current.as_ref().map(|(s, _)| s == phase).unwrap_or(false) {
+            return;
+        }
+        self.close_stage();
+        self.current = Some((phase.to_string(), Instant::now()));
+    }
+
+    /// Preparation is over and the game process is running.
+    pub fn started(&mut self) {
+        self.close_stage();
+        self.prepare_millis = Some(self.start.elapsed().as_millis() as u64);
+        self.running_since = Some(Instant::now());
+    }
+
+    pub fn timings(&self) -> PrepareTimings {
+        PrepareTimings { stages: self.stages.clone(), prepare_millis: self.prepare_millis }
+    }
+
+    pub fn finish(mut self, outcome: Outcome, error_code: Option<i32>, message: Option<&str>) -> LaunchAttempt {
+        self.close_stage();
+        LaunchAttempt {
+            started_at: self.started_at,
+            stages: self.stages,
+            prepare_millis: self.prepare_millis,
+            play_millis: self.running_since.map(|t| t.elapsed().as_millis() as u64),
+            outcome,
+            error_code,
+            failure: classify_failure(outcome, message.unwrap_or_default()),
+            message: message.map(str::to_string),
+            safe_mode: None,
+        }
+    }
+}
+
+impl Default for Timeline {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+type History = HashMap<String, VecDeque<LaunchAttempt>>;
+
+static FILE_LOCK: Mutex<()> = Mutex::new(());
+/// Instances whose game process is up right now, with the unix second it started.
+static RUNNING: Mutex<Vec<(String, u64)>> = Mutex::new(Vec::new());
+
+fn history_path() -> PathBuf {
+    PathBuf::from(crate::get_instances_dir()).join(HISTORY_FILE)
+}
+
+fn load_history() -> History {
+    fs::read_to_string(history_path())
+        .ok()
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+
+/// Appends an attempt, keeping the newest `MAX_ATTEMPTS` per instance.
+pub fn push_attempt(history: &mut History, instance_id: &str, attempt: LaunchAttempt) {
+    let attempts = history.entry(instance_id.to_string()).or_default();
+    attempts.push_back(attempt);
+    while attempts.len() > MAX_ATTEMPTS {
+        attempts.pop_front();
+    }
+}
+
+pub fn record(instance_id: &str, attempt: LaunchAttempt) {
+    let _guard = FILE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let mut history = load_history();
+    push_attempt(&mut history, instance_id, attempt);
+    if let Ok(json) = serde_json::to_string_pretty(&history) {
+        let _ = fs::write(history_path(), json);
+    }
+}
+
+/// Times one launch from the progress events the loaders already emit. Call `started` once
+/// the game process is up and `finish` with the result; the attempt is then persisted.
+pub struct LaunchRecorder {
+    app: AppHandle,
+    instance_id: String,
+    listener: EventId,
+    timeline: Arc<Mutex<Timeline>>,
+    safe_mode: Option<crate::safe_mode::SafeModeStep>,
+}
+
+impl LaunchRecorder {
+    pub fn start(app: &AppHandle, instance_id: &str) -> Self {
+        let timeline = Arc::new(Mutex::new(Timeline::new()));
+        let events = timeline.clone();
+        let id = instance_id.to_string();
+        let listener = app.listen_any("launch_progress", move |event| {
+            let Ok(payload) = serde_json::from_str::<serde_json::Value>(event.payload()) else { return };
+            if payload["instanceId"].as_str() != Some(id.as_str()) {
+                return;
+            }
+            if let (Some(stage), Ok(mut timeline)) = (payload["stage"].as_str(), events.lock()) {
+                timeline.stage(stage);
+            }
+        });
+        LaunchRecorder { app: app.clone(), instance_id: instance_id.to_string(), listener, timeline, safe_mode: None }
+    }
+
+    /// Closes the preparation and returns its timings.
+    pub fn started(&self) -> PrepareTimings {
+        if let Ok(mut running) = RUNNING.lock() {
+            running.push((self.instance_id.clone(), crate::unix_now()));
+        }
+        let mut timeline = self.timeline.lock().unwrap_or_else(|e| e.into_inner());
+        timeline.started();
+        timeline.timings()
+    }
+
+    /// Marks the session as played in safe mode.
+    pub fn safe_mode(&mut self, step: Option<crate::safe_mode::SafeModeStep>) {
+        self.safe_mode = step;
+    }
+
+    /// Phases timed so far, for reporting a preparation that failed.
+    pub fn timings(&self) -> PrepareTimings {
+        let mut timeline = self.timeline.lock().unwrap_or_else(|e| e.into_inner());
+        timeline.close_stage();
+        timeline.timings()
+    }
+
+    pub fn finish(self, outcome: Outcome, error_code: Option<i32>, message: Option<&str>) {
+        self.app.unlisten(self.listener);
+        if let Ok(mut running) = RUNNING.lock() {
+            if let Some(i) = running.iter().position(|(id, _)| *id == self.instance_id) {
+                running.remove(i);
+            }
+        }
+        let timeline = std::mem::take(&mut *self.timeline.lock().unwrap_or_else(|e| e.into_inner()));
+        let attempt = LaunchAttempt { safe_mode: self.safe_mode, ..timeline.finish(outcome, error_code, message) };
+        record(&self.instance_id, attempt);
+    }
+}
+
+/// Instance ids with a game running, and since when.
+pub fn running() -> Vec<(String, u64)> {
+    RUNNING.lock().map(|running| running.clone()).unwrap_or_default()
+}
+
+/// The `limit` newest attempts that didn't end well, across instances, newest first.
+fn recent_failures(history: &History, limit: usize) -> Vec<(String, LaunchAttempt)> {
+    let mut failures: Vec<_> = history.iter()
+        .flat_map(|(id, attempts)| attempts.iter().filter(|a| a.outcome != Outcome::Closed).map(move |a| (id.clone(), a.clone())))
+        .collect();
+    failures.sort_by_key(|(_, attempt)| std::cmp::Reverse(attempt.started_at));
+    failures.truncate(limit);
+    failures
+}
+
+pub fn latest_failures(limit: usize) -> Vec<(String, LaunchAttempt)> {
+    let _guard = FILE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    recent_failures(&load_history(), limit)
+}
+
+/// Crashes in a row at the end of the history. Preparations that failed (network, disk) say
+/// nothing about the game and are skipped; a clean exit ends the streak.
+fn crash_streak<'a>(attempts: impl DoubleEndedIterator<Item = &'a LaunchAttempt>) -> usize {
+    attempts.rev().filter(|a| a.outcome != Outcome::Failed).take_while(|a| a.outcome == Outcome::Crashed).count()
+}
+
+pub fn consecutive_crashes(instance_id: &str) -> usize {
+    let _guard = FILE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    load_history().get(instance_id).map(|attempts| crash_streak(attempts.iter())).unwrap_or(0)
+}
+
+/// The newest recorded launch of an instance.
+pub fn last_attempt(instance_id: &str) -> Option<LaunchAttempt> {
+    let _guard = FILE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    load_history().get(instance_id).and_then(|attempts| attempts.back().cloned())
+}
+
+/// Every recorded launch of an instance, oldest first, so staff can tell whether a player's
+/// failures are network, disk or crash related over time.
+#[tauri::command]
+pub fn get_launch_history(instance_id: String) -> Vec<LaunchAttempt> {
+    let _guard = FILE_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    load_history().remove(&instance_id).map(Vec::from).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn failures_are_classified() {
+        assert_eq!(classify_failure(Outcome::Closed, ""), None);
+        assert_eq!(classify_failure(Outcome::Failed, "Failed to download https://x/y after 3 attempts. Last error: timed out"), Some(FailureKind::Network));
+        assert_eq!(classify_failure(Outcome::Failed, "No space left on device (os error 28)"), Some(FailureKind::Disk));
+        assert_eq!(classify_failure(Outcome::Crashed, "El juego se cerró con error (Código: 1)"), Some(FailureKind::Crash));
+        assert_eq!(classify_failure(Outcome::Crashed, "[antivirus] Posible bloqueo del antivirus en java.exe"), Some(FailureKind::Antivirus));
+        assert_eq!(classify_failure(Outcome::Failed, "Unsupported loader: rift"), Some(FailureKind::Other));
+    }
+
+    #[test]
+    fn stages_are_timed_until_the_game_starts() {
+        let mut timeline = Timeline::new();
+        timeline.stage("iniciando");
+        timeline.stage("librerias");
+        timeline.stage("librerias");
+        timeline.stage("assets");
+        timeline.stage("librerias");
+        timeline.stage("comando");
+        timeline.stage("listo");
+        timeline.started();
+        timeline.stage("iniciado");
+        assert_eq!(timeline.timings().stages.len(), 5);
+        let attempt = timeline.finish(Outcome::Closed, Some(0), None);
+        let stages = attempt.stages.iter().map(|s| s.stage.as_str()).collect::<Vec<_>>();
+        assert_eq!(stages, vec!["manifest", "libraries", "assets", "command", "spawn"]);
+        assert!(attempt.prepare_millis.is_some() && attempt.play_millis.is_some());
+        assert_eq!(attempt.failure, None);
+    }
+
+    #[test]
+    fn history_is_capped_per_instance() {
+        let mut history = History::new();
+        for code in 0..(MAX_ATTEMPTS as i32 + 5) {
+            push_attempt(&mut history, "a", Timeline::new().finish(Outcome::Crashed, Some(code), None));
+        }
+        push_attempt(&mut history, "b", Timeline::new().finish(Outcome::Failed, None, Some("timed out")));
+        assert_eq!(history["a"].len(), MAX_ATTEMPTS);
+        assert_eq!(history["a"][0].error_code, Some(5));
+        assert_eq!(history["b"][0].failure, Some(FailureKind::Network));
+    }
+
+    #[test]
+    fn recent_failures_skip_clean_exits() {
+        let mut history = History::new();
+        let attempt = |started_at, outcome| LaunchAttempt { started_at, ..Timeline::new().finish(outcome, None, Some("timed out")) };
+        push_attempt(&mut history, "a", attempt(1, Outcome::Failed));
+        push_attempt(&mut history, "a", attempt(3, Outcome::Closed));
+        push_attempt(&mut history, "b", attempt(2, Outcome::Crashed));
+        push_attempt(&mut history, "b", attempt(4, Outcome::Failed));
+        let failures = recent_failures(&history, 2);
+        assert_eq!(failures.iter().map(|(id, a)| (id.as_str(), a.started_at)).collect::<Vec<_>>(), vec![("b", 4), ("b", 2)]);
+    }
+
+    #[test]
+    fn crash_streak_ignores_failed_preparations() {
+        let attempts = [Outcome::Crashed, Outcome::Closed, Outcome::Crashed, Outcome::Failed, Outcome::Crashed]
+            .map(|outcome| Timeline::new().finish(outcome, None, None));
+        assert_eq!(crash_streak(attempts.iter()), 2);
+        assert_eq!(crash_streak(attempts[..2].iter()), 0);
+        assert_eq!(crash_streak([].iter()), 0);
+    }
+}
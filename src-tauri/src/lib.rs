@@ -1,19 +1,85 @@
 // Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 use std::time::{SystemTime, UNIX_EPOCH};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::sync::Mutex;
 
+mod accessibility;
+mod admin_reset;
+mod admin_setup;
+mod announcements;
+mod antivirus;
+mod asset_protocol;
+mod attestation;
 mod auth;
+mod content_audit;
+mod content_index;
+mod crash_analysis;
+mod crash_upload;
+mod data_dir;
+mod diagnostics;
+mod download_stats;
+mod drop_install;
+mod events;
+mod exit_codes;
+mod file_associations;
+mod fleet;
+mod gamepad;
+mod graphics;
+mod host_pins;
+mod hotkey;
+mod http;
+mod image_cache;
+mod info_card;
+mod instance_images;
+mod instance_input;
+mod instance_notes;
+mod instance_players;
+mod janitor;
+mod kiosk;
+mod last_played;
+mod launch_history;
+mod maintenance;
+mod metrics;
 mod minecraft;
+mod mirror;
+mod mod_links;
+mod notifications;
+mod offline_bundle;
+mod optimize;
+mod path_policy;
+mod platform;
+mod progress_snapshot;
+mod remote_commands;
+mod remover;
+mod rewards;
+mod safe_mode;
+mod sandbox;
+mod save_recovery;
+mod save_sync;
+mod scheduler;
+mod schema;
+mod server_history;
+mod server_queue;
+mod service_status;
+mod settings;
+mod share_code;
+mod shortcuts;
+mod smoke_test;
+mod storage;
+mod temp_files;
+mod trash;
+mod tuning;
+mod undo;
+mod updater;
 use auth::{AppState, AuthState};
 use tauri::State;
-use tauri::Emitter;
 use sha1::{Sha1, Digest};
 
-#[derive(Serialize, Deserialize, Clone)]
+#[derive(Serialize, Deserialize, Clone, JsonSchema)]
 pub struct EventCard {
     #[serde(default)]
     pub image: Option<String>,
@@ -27,7 +93,7 @@ pub struct EventCard {
     pub rewards: Option<String>,
 }
 
-#[derive(Serialize, Deserialize, Clone)]
+#[derive(Serialize, Deserialize, Clone, JsonSchema)]
 pub struct StatsCard {
     #[serde(default)]
     pub image: Option<String>,
@@ -39,7 +105,8 @@ pub struct StatsCard {
     pub status: Option<String>,
 }
 
-#[derive(Serialize, Deserialize, Clone)]
+/// Numbers are refreshed from the instance folder after each sync (see `info_card::refresh`).
+#[derive(Serialize, Deserialize, Clone, Default, PartialEq, Debug, JsonSchema)]
 pub struct InfoCard {
     #[serde(default)]
     pub image: Option<String>,
@@ -47,15 +114,19 @@ pub struct InfoCard {
     pub mods_installed: Option<u32>,
     #[serde(default)]
     pub last_update: Option<String>,
+    #[serde(default)]
+    pub pack_version: Option<String>,
 }
 
-#[derive(Serialize, Deserialize, Clone)]
+#[derive(Serialize, Deserialize, Clone, JsonSchema)]
 pub struct Instance {
     pub id: String,
     pub name: String,
     pub version: String,
-    #[serde(rename = "lastPlayed", alias = "last_played", default = "get_current_timestamp")]
-    pub last_played: String,
+    /// Set when the game really starts (see `last_played::record_launch`)
+    #[serde(rename = "lastPlayed", alias = "last_played", with = "last_played", default = "chrono::Utc::now")]
+    #[schemars(with = "String")]
+    pub last_played: chrono::DateTime<chrono::Utc>,
     pub icon: String,
     pub path: String,
     #[serde(default)]
@@ -86,11 +157,48 @@ pub struct Instance {
     pub resolution_width: Option<u32>,
     #[serde(default)]
     pub resolution_height: Option<u32>,
+    #[serde(rename = "debugLaunch", alias = "debug_launch", default)]
+    pub debug_launch: Option<bool>,
+    #[serde(rename = "logLevel", alias = "log_level", default)]
+    pub log_level: Option<String>,
+    /// Follow new patch releases of the same minor (1.21.x) after user confirmation
+    #[serde(rename = "trackLatestPatch", alias = "track_latest_patch", default)]
+    pub track_latest_patch: Option<bool>,
+    /// Controller/Steam Deck profile applied (see `gamepad::apply_gamepad_profile`)
+    #[serde(rename = "gamepadProfile", alias = "gamepad_profile", default)]
+    pub gamepad_profile: Option<bool>,
+    #[serde(rename = "linuxTweaks", alias = "linux_tweaks", default)]
+    pub linux_tweaks: Option<minecraft::launch_logic::LinuxTweaks>,
+    #[serde(rename = "gameLanguage", alias = "game_language", default)]
+    pub game_language: Option<String>,
+    #[serde(rename = "translationPackUrl", alias = "translation_pack_url", default)]
+    pub translation_pack_url: Option<String>,
+    /// JVM flag preset (see `minecraft::jvm_presets`)
+    #[serde(rename = "jvmPreset", alias = "jvm_preset", default)]
+    pub jvm_preset: Option<String>,
+    /// Launch with multiplayer and chat disabled, for supervised stations
+    #[serde(rename = "singleplayerOnly", alias = "singleplayer_only", default)]
+    pub singleplayer_only: Option<bool>,
+    /// Set on admin test clones (see `sandbox`): the root holding their own libraries, assets and Java
+    #[serde(rename = "sandboxRoot", alias = "sandbox_root", default)]
+    pub sandbox_root: Option<String>,
+    /// Shown to players on the instance page (see `instance_notes`)
+    #[serde(default)]
+    pub notes: Option<String>,
+    /// Staff-only remarks; stripped from what `get_instances` returns
+    #[serde(rename = "adminNotes", alias = "admin_notes", default)]
+    pub admin_notes: Option<String>,
+    /// Config values set after every sync (see `minecraft::config_patch`); staff-only
+    #[serde(rename = "configPatches", alias = "config_patches", default)]
+    pub config_patches: Option<Vec<minecraft::config_patch::ConfigPatch>>,
+    /// Runtime folder under the data root (`java/runtimes/<build>`) this instance launches with
+    /// instead of any other Java of the same major; set on instances from offline bundles
+    #[serde(rename = "javaRuntime", alias = "java_runtime", default)]
+    pub java_runtime: Option<String>,
 }
 
 const INSTANCES_FILE: &str = "instances.json";
 const ADMIN_FILE: &str = "admin.json";
-const INSTANCES_DIR: &str = "Eventos DRK";
 const INSTANCES_SUBDIR: &str = "instancias";
 
 fn get_instances_path() -> String {
@@ -102,44 +210,19 @@ fn get_admin_path() -> String {
 }
 
 fn get_instances_dir() -> String {
-    get_appdata_dir().join(INSTANCES_DIR).to_string_lossy().to_string()
+    data_dir::root().to_string_lossy().to_string()
 }
 
 fn get_instances_subdir() -> String {
     Path::new(&get_instances_dir()).join(INSTANCES_SUBDIR).to_string_lossy().to_string()
 }
 
-fn get_appdata_dir() -> std::path::PathBuf {
-    #[cfg(target_os = "windows")]
-    {
-        let appdata = std::env::var("APPDATA").unwrap_or_else(|_| "".to_string());
-        std::path::PathBuf::from(appdata)
-    }
-    #[cfg(target_os = "linux")]
-    {
-        let home = std::env::var("HOME").unwrap_or_else(|_| "".to_string());
-        std::path::PathBuf::from(home).join(".local").join("share")
-    }
-    #[cfg(target_os = "macos")]
-    {
-        let home = std::env::var("HOME").unwrap_or_else(|_| "".to_string());
-        std::path::PathBuf::from(home).join("Library").join("Application Support")
-    }
-    #[cfg(not(any(target_os = "windows", target_os = "linux", target_os = "macos")))]
-    {
-        std::env::current_dir().unwrap()
-    }
-}
-
 fn load_instances() -> Vec<Instance> {
     let path = get_instances_path();
     if Path::new(&path).exists() {
         match fs::read_to_string(&path) {
             Ok(content) => {
-                match serde_json::from_str::<Vec<Instance>>(&content) {
-                    Ok(instances) => instances,
-                    Err(_) => Vec::new(),
-                }
+                serde_json::from_str::<Vec<Instance>>(&content).unwrap_or_default()
             }
             Err(_) => Vec::new(),
         }
@@ -155,36 +238,44 @@ fn save_instances(instances: &Vec<Instance>) {
     }
 }
 
-fn get_current_timestamp() -> String {
-    SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap()
-        .as_secs()
-        .to_string()
+/// Seconds since the Unix epoch, 0 if the clock is set before it.
+pub fn unix_now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
 }
 
-#[tauri::command]
-fn get_system_ram() -> Result<u64, String> {
-    use sysinfo::{System, SystemExt};
-    let mut sys = System::new_all();
-    sys.refresh_memory();
-    let total_bytes = sys.total_memory(); // in KiB for older versions, bytes for newer
-    if total_bytes == 0 {
-        return Err("Could not read system RAM".to_string());
-    }
-    // sysinfo total_memory unit varies per version; convert robustly to MB
-    let mb = total_bytes / 1024 / 1024; // if bytes; if KiB, this yields KB->MB correctly too
-    Ok(mb)
+fn get_current_timestamp() -> String {
+    unix_now().to_string()
 }
 
 #[tauri::command]
 fn get_instances() -> Vec<Instance> {
-    load_instances()
+    load_instances().into_iter().map(instance_notes::for_display).collect()
 }
 
 #[tauri::command]
-fn save_instance(instance: Instance) -> Result<(), String> {
+fn save_instance(mut instance: Instance) -> Result<Instance, String> {
+    settings::ensure_not_guest()?;
     let mut instances = load_instances();
+    instance.name = instance_input::normalize_name(&instance.name)?;
+    if !instances.iter().any(|i| i.id == instance.id) {
+        instance.id = instance_input::new_id();
+        if instance.ram.is_none() {
+            instance.ram = Some(platform::default_ram_for(instance.modloader.as_deref()));
+        }
+    }
+
+    // Worlds saved by a newer version get corrupted when opened with an older one
+    if let Some(existing) = instances.iter().find(|i| i.id == instance.id && i.version != instance.version) {
+        let minecraft_dir = Path::new(&existing.path).join("minecraft");
+        let plan = minecraft::worlds::plan_migration(&minecraft_dir, &existing.version, &instance.version);
+        if !plan.blocked_worlds.is_empty() {
+            return Err(format!(
+                "No se puede bajar a {}: los mundos {} se guardaron con una versión más nueva",
+                instance.version,
+                plan.blocked_worlds.join(", ")
+            ));
+        }
+    }
     
     // Ensure root directory exists (AppData/Roaming/Eventos DRK)
     let root_dir = get_instances_dir();
@@ -202,9 +293,7 @@ fn save_instance(instance: Instance) -> Result<(), String> {
     }
     
     // Sanitize name for folder use (replace invalid chars)
-    let folder_name = instance.name.chars()
-        .map(|c| if c.is_alphanumeric() || c == ' ' || c == '-' || c == '_' { c } else { '_' })
-        .collect::<String>();
+    let folder_name = instance_input::folder_name(&instance.name);
     
     // Create instance directory structure using the NAME
     let instances_subdir = get_instances_subdir();
@@ -297,20 +386,42 @@ fn save_instance(instance: Instance) -> Result<(), String> {
      // Update instance path
     let mut updated_instance = instance;
     updated_instance.path = instance_path.to_string_lossy().to_string();
+    if let Some(images) = updated_instance.images.take() {
+        let images = instance_images::import_all(&instance_path, images);
+        instance_images::collect_garbage(&instance_path, &images);
+        updated_instance.images = Some(images);
+    }
     
+    updated_instance.java_runtime = minecraft::java::kept_runtime(
+        instances.iter().find(|i| i.id == updated_instance.id),
+        &updated_instance.version,
+    );
+
     // Check if instance with this id exists
     if let Some(existing) = instances.iter_mut().find(|i| i.id == updated_instance.id) {
-        *existing = updated_instance;
+        // Notes and config patches have their own commands; the settings form neither shows nor sends them
+        updated_instance.notes = existing.notes.take();
+        updated_instance.admin_notes = existing.admin_notes.take();
+        updated_instance.config_patches = existing.config_patches.take();
+        // Only real launches move it; the form sends back whatever it loaded
+        updated_instance.last_played = existing.last_played;
+        *existing = updated_instance.clone();
     } else {
-        instances.push(updated_instance);
+        instances.push(updated_instance.clone());
     }
     
     save_instances(&instances);
-    Ok(())
+    Ok(instance_notes::for_display(updated_instance))
 }
 
+/// Opens a folder in the file manager. Only inside the data root unless the admin password is given.
 #[tauri::command]
-fn open_folder(path: String) -> Result<(), String> {
+fn open_folder(path: String, password: Option<String>) -> Result<(), String> {
+    let path = path_policy::checked(Path::new(&path), password)?;
+    open_path(&path)
+}
+
+fn open_path(path: &Path) -> Result<(), String> {
     #[cfg(target_os = "windows")]
     {
         Command::new("explorer")
@@ -322,7 +433,7 @@ fn open_folder(path: String) -> Result<(), String> {
     #[cfg(target_os = "linux")]
     {
         Command::new("xdg-open")
-            .arg(&path)
+            .arg(path)
             .spawn()
             .map_err(|e| format!("Failed to open folder: {}", e))?;
     }
@@ -338,8 +449,105 @@ fn open_folder(path: String) -> Result<(), String> {
     Ok(())
 }
 
+/// Folder of an instance the UI can open by name: `mods`, `saves`, `logs`, `screenshots`,
+/// `config`, `resourcepacks` or `minecraft`. Launcher logs live next to `minecraft/`.
+fn instance_subfolder(instance_path: &Path, which: &str) -> Option<PathBuf> {
+    let minecraft = instance_path.join("minecraft");
+    match which {
+        "logs" => Some(instance_path.join("logs")),
+        "minecraft" => Some(minecraft),
+        "mods" | "saves" | "screenshots" | "config" | "resourcepacks" => Some(minecraft.join(which)),
+        _ => None,
+    }
+}
+
+#[tauri::command]
+fn open_instance_folder(instance_id: String, which: String) -> Result<(), String> {
+    let instance = load_instances().into_iter().find(|i| i.id == instance_id)
+        .ok_or("Instance not found")?;
+    let folder = instance_subfolder(Path::new(&instance.path), &which)
+        .ok_or_else(|| format!("Carpeta desconocida: {}", which))?;
+    // Screenshots/saves only exist once the game made them; open an empty folder rather than fail
+    fs::create_dir_all(&folder).map_err(|e| format!("Failed to open folder: {}", e))?;
+    open_path(&path_policy::inside_data_root(&folder)?)
+}
+
+/// Opens the file manager with `path` selected (Linux file managers without selection support
+/// just get the containing folder).
+#[tauri::command]
+fn reveal_file(path: String) -> Result<(), String> {
+    let path = path_policy::inside_data_root(Path::new(&path))?;
+    if !path.exists() {
+        return Err(format!("El archivo no existe: {}", path.display()));
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        use std::os::windows::process::CommandExt;
+        // explorer wants `/select,"path"` verbatim; default argument quoting breaks paths with spaces
+        let path = path.to_string_lossy();
+        Command::new("explorer")
+            .raw_arg(format!("/select,\"{}\"", path.trim_start_matches(r"\\?\")))
+            .spawn()
+            .map_err(|e| format!("Failed to open folder: {}", e))?;
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        Command::new("open")
+            .arg("-R")
+            .arg(&path)
+            .spawn()
+            .map_err(|e| format!("Failed to open folder: {}", e))?;
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        let folder = if path.is_dir() { path.clone() } else { path.parent().map(Path::to_path_buf).unwrap_or(path.clone()) };
+        Command::new("xdg-open")
+            .arg(&folder)
+            .spawn()
+            .map_err(|e| format!("Failed to open folder: {}", e))?;
+    }
+
+    Ok(())
+}
+
+/// Opens a game log for writing. Normal launches truncate it; debug launches append a session header instead.
+fn open_launch_log(path: &Path, debug: bool) -> std::io::Result<fs::File> {
+    if !debug {
+        return fs::File::create(path);
+    }
+    use std::io::Write;
+    let mut file = fs::OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "\n===== DEBUG SESSION {} =====", get_current_timestamp())?;
+    Ok(file)
+}
+
 #[tauri::command]
 async fn launch_instance(app: tauri::AppHandle, instance_id: String, state: State<'_, AppState>) -> Result<(), String> {
+    launch_instance_with(app, instance_id, None, false, state).await
+}
+
+/// Launches straight into a singleplayer world (Quick Play, 1.20+; older versions open the menu).
+#[tauri::command]
+async fn launch_world(app: tauri::AppHandle, instance_id: String, world_name: String, state: State<'_, AppState>) -> Result<(), String> {
+    let instance = load_instances().into_iter().find(|i| i.id == instance_id)
+        .ok_or("Instance not found")?;
+    if world_name.is_empty() || world_name.contains(['/', '\\']) || world_name == ".." {
+        return Err("Nombre de mundo inválido".to_string());
+    }
+    if !Path::new(&instance.path).join("minecraft").join("saves").join(&world_name).join("level.dat").is_file() {
+        return Err(format!("El mundo {} no existe", world_name));
+    }
+    launch_instance_with(app, instance_id, Some(world_name), false, state).await
+}
+
+/// `kiosk` launches fullscreen straight onto the instance's server (see `kiosk::kiosk_start`).
+async fn launch_instance_with(app: tauri::AppHandle, instance_id: String, quick_play_world: Option<String>, kiosk: bool, state: State<'_, AppState>) -> Result<(), String> {
+    settings::ensure_launch_allowed(&instance_id)?;
+    let id = instance_id.clone();
+    run_blocking(move || service_status::ensure_instance_supported(&id)).await?;
     let instances = load_instances();
     let instance = instances.iter().find(|i| i.id == instance_id)
         .ok_or("Instance not found")?;
@@ -349,6 +557,11 @@ async fn launch_instance(app: tauri::AppHandle, instance_id: String, state: Stat
         return Err("Launcher not configured".to_string());
     }
 
+    // A GPU without OpenGL for this version only shows up as a bare driver exit code otherwise
+    let (check_version, check_tweaks) = (instance.version.clone(), instance.linux_tweaks.clone().unwrap_or_default());
+    let graphics_check = run_blocking(move || Ok(graphics::check(&check_version, &check_tweaks))).await?;
+    graphics::ensure_supported(&graphics_check, &instance.version)?;
+
     // Get Auth Profile
     let auth_profile = {
         let auth_state = state.auth.lock().map_err(|_| "Failed to lock auth state")?;
@@ -356,17 +569,35 @@ async fn launch_instance(app: tauri::AppHandle, instance_id: String, state: Stat
     };
 
     let instance_path = std::path::PathBuf::from(&instance.path);
-    let app_data = get_instances_dir(); // This returns ".../Eventos DRK" which is our root
-    let root_path = std::path::PathBuf::from(app_data);
+    // ".../Eventos DRK", or the sandbox's own root for test clones
+    let root_path = sandbox::base_path(instance);
 
-    let ram = instance.ram.unwrap_or(4096);
+    let ram = instance.ram.unwrap_or_else(|| platform::default_ram_for(instance.modloader.as_deref()));
     let version = instance.version.clone();
     let mods_urls = instance.mods.clone();
     let modpack_url = instance.modpack_url.clone();
     let loader = instance.modloader.clone();
-    let width = instance.resolution_width;
-    let height = instance.resolution_height;
+    let debug = instance.debug_launch.unwrap_or(false);
+    let launch_options = minecraft::launch_logic::LaunchOptions {
+        width: instance.resolution_width,
+        height: instance.resolution_height,
+        debug,
+        log_level: instance.log_level.clone(),
+        quick_play_world,
+        quick_play_server: instance.server_ip.clone().filter(|_| kiosk),
+        fullscreen: kiosk,
+        linux: instance.linux_tweaks.clone().unwrap_or_default(),
+        instance_name: instance.name.clone(),
+        game_language: instance.game_language.clone(),
+        translation_pack_url: instance.translation_pack_url.clone(),
+        jvm_preset: instance.jvm_preset.clone(),
+        singleplayer_only: instance.singleplayer_only.unwrap_or(false),
+        java_runtime: minecraft::java::pinned_runtime(&root_path, instance),
+    };
     
+    // Keeps the background updater away until the game exits
+    let active = updater::mark_launching(&app, &instance_id)?;
+
     // Run in background thread to avoid blocking UI
     let instance_path_clone = instance_path.clone();
     let root_path_clone = root_path.clone();
@@ -375,8 +606,12 @@ async fn launch_instance(app: tauri::AppHandle, instance_id: String, state: Stat
     let modpack_url_clone = modpack_url.clone();
     let loader_clone = loader.clone();
     let app_clone = app.clone();
+    let instance_name = instance.name.clone();
+    let instance_clone = instance.clone();
     
     tauri::async_runtime::spawn_blocking(move || {
+        let _active = active;
+        let mut history = launch_history::LaunchRecorder::start(&app_clone, &instance_id);
         // Create logs dir
         let _ = fs::create_dir_all(instance_path_clone.join("logs"));
         
@@ -387,6 +622,9 @@ async fn launch_instance(app: tauri::AppHandle, instance_id: String, state: Stat
         let coremods = instance_path_clone.join("minecraft").join("coremods");
         if coremods.exists() { let _ = std::fs::remove_dir_all(coremods); }
 
+        // Newer copies of opted-in worlds before the game opens them
+        save_sync::sync_instance(&app_clone, &instance_id, &instance_path_clone, &version, &auth_profile_clone.id, save_sync::Direction::Pull);
+
         // Prepare and Launch
     match minecraft::launch_logic::prepare_and_launch(
         &root_path_clone,
@@ -397,35 +635,62 @@ async fn launch_instance(app: tauri::AppHandle, instance_id: String, state: Stat
         mods_urls_clone,
         modpack_url_clone,
         loader_clone,
-        width,
-        height,
         Some(app_clone.clone()),
         &instance_id,
-        false // force_update: false for normal launch
+        false, // force_update: false for normal launch
+        &launch_options
     ) {
             Ok(mut cmd) => {
-                // Redirect output to files
-                if let Ok(stdout_file) = std::fs::File::create(instance_path_clone.join("logs").join("latest.log")) {
+                // After the sync, which brings back what an earlier safe mode launch disabled
+                let minecraft_dir = instance_path_clone.join("minecraft");
+                let safe_mode = match safe_mode::before_launch(&instance_clone, &minecraft_dir) {
+                    Ok(step) => step,
+                    Err(e) => {
+                        let message = format!("No se pudo aplicar el modo seguro: {}", e);
+                        crate::events::emit_progress(&app_clone, crate::events::LaunchProgress::new(&instance_id, "error", 100, &message));
+                        history.finish(launch_history::Outcome::Failed, None, Some(&message));
+                        return Err(message);
+                    }
+                };
+                if safe_mode.is_some() {
+                    crate::events::emit_progress(&app_clone, crate::events::LaunchProgress::new(&instance_id, "listo", 95, "Modo seguro activado"));
+                }
+                history.safe_mode(safe_mode);
+                attestation::at_launch(&instance_id, &instance_name, &instance_path_clone, &auth_profile_clone);
+                // Redirect output to files; debug sessions append so earlier runs stay available to staff
+                let logs_dir = instance_path_clone.join("logs");
+                if let Ok(stdout_file) = open_launch_log(&logs_dir.join("latest.log"), debug) {
                      cmd.stdout(stdout_file);
                 }
-                if let Ok(stderr_file) = std::fs::File::create(instance_path_clone.join("logs").join("latest_err.log")) {
+                if let Ok(stderr_file) = open_launch_log(&logs_dir.join("latest_err.log"), debug) {
                      cmd.stderr(stderr_file);
                 }
                 
+                let logs_len = || ["latest.log", "latest_err.log"].iter()
+                    .filter_map(|name| fs::metadata(logs_dir.join(name)).ok())
+                    .map(|m| m.len())
+                    .sum::<u64>();
+                let logs_before = logs_len();
                 match cmd.spawn() {
                     Ok(mut child) => {
-                        let _ = app_clone.emit("launch_progress", serde_json::json!({
-                            "instanceId": instance_id,
-                            "stage": "iniciado",
-                            "percent": 100,
-                            "message": "Juego iniciado"
-                        }));
+                        let started = std::time::Instant::now();
+                        let session_start = std::time::SystemTime::now();
+                        let timings = history.started();
+                        last_played::record_launch(&instance_id);
+                        let message = if debug { "Juego iniciado (modo depuración)" } else { "Juego iniciado" };
+                        crate::events::emit_progress(&app_clone, crate::events::LaunchProgress {
+                            debug: Some(debug),
+                            timings: Some(timings),
+                            ..crate::events::LaunchProgress::new(&instance_id, "iniciado", 100, message)
+                        });
                         
                         // Monitor process execution
-                        match child.wait() {
+                        let exited_cleanly = match child.wait() {
                             Ok(status) => {
+                                let summary = rewards::SessionSummary::new(&auth_profile_clone, &instance_id, &instance_name, started.elapsed(), status.code());
+                                let reward = rewards::report_session(&app_clone, &summary);
                                 if !status.success() {
-                                    let code = status.code().unwrap_or(-1);
+                                    let code = exit_codes::exit_code(&status);
                                     
                                     // Try to read last lines of error log
                                     let mut error_details = String::new();
@@ -435,57 +700,76 @@ async fn launch_instance(app: tauri::AppHandle, instance_id: String, state: Stat
                                         error_details = content.lines().rev().take(10).collect::<Vec<_>>().into_iter().rev().collect::<Vec<_>>().join("\n");
                                     }
 
-                                    let message = if !error_details.is_empty() {
+                                    let message = if antivirus::java_killed(started.elapsed(), logs_len().saturating_sub(logs_before)) {
+                                        antivirus::record_blocked(None, Path::new(cmd.get_program()), None, antivirus::Symptom::JavaKilled)
+                                    } else if let Some(advice) = graphics::crash_advice(code, &crash_analysis::read_logs(&instance_path_clone), &graphics_check) {
+                                        advice
+                                    } else if !error_details.is_empty() {
                                         format!("El juego se cerró con error (Código: {}). Detalles:\n{}", code, error_details)
                                     } else {
                                         format!("El juego se cerró con error (Código: {})", code)
                                     };
 
-                                    let _ = app_clone.emit("launch_progress", serde_json::json!({
-                                        "instanceId": instance_id,
-                                        "stage": "crasheado",
-                                        "percent": 100,
-                                        "message": message
-                                    }));
+                                    // One link to paste instead of screenshots, for players who opted in
+                                    let log_url = crash_upload::upload_after_crash(&instance_id, &instance_name, &instance_path_clone, session_start, code);
+
+                                    // Recorded before the event: the crash dialog reads the streak for its safe mode offer
+                                    safe_mode::after_session(&instance_id, &minecraft_dir, launch_history::Outcome::Crashed);
+                                    history.finish(launch_history::Outcome::Crashed, Some(code), Some(&message));
+                                    crate::events::emit_progress(&app_clone, crate::events::LaunchProgress {
+                                        debug: Some(debug),
+                                        reward,
+                                        log_url,
+                                        ..crate::events::LaunchProgress::new(&instance_id, "crasheado", 100, &message)
+                                    });
                                 } else {
-                                    let _ = app_clone.emit("launch_progress", serde_json::json!({
-                                        "instanceId": instance_id,
-                                        "stage": "cerrado",
-                                        "percent": 100,
-                                        "message": "Juego cerrado correctamente"
-                                    }));
+                                    safe_mode::after_session(&instance_id, &minecraft_dir, launch_history::Outcome::Closed);
+                                    crate::events::emit_progress(&app_clone, crate::events::LaunchProgress {
+                                        debug: Some(debug),
+                                        reward,
+                                        ..crate::events::LaunchProgress::new(&instance_id, "cerrado", 100, "Juego cerrado correctamente")
+                                    });
+                                    history.finish(launch_history::Outcome::Closed, Some(0), None);
                                 }
+                                status.success()
                             }
                             Err(e) => {
-                                let _ = app_clone.emit("launch_progress", serde_json::json!({
-                                    "instanceId": instance_id,
-                                    "stage": "error",
-                                    "percent": 100,
-                                    "message": format!("Error monitoring process: {}", e)
-                                }));
+                                let message = format!("Error monitoring process: {}", e);
+                                crate::events::emit_progress(&app_clone, crate::events::LaunchProgress::new(&instance_id, "error", 100, &message));
+                                history.finish(launch_history::Outcome::Crashed, None, Some(&message));
+                                false
                             }
+                        };
+                        // A crash can leave half-written saves; checked before anything uploads them
+                        let damaged = if exited_cleanly {
+                            Vec::new()
+                        } else {
+                            save_recovery::check_after_crash(&app_clone, &instance_id, &instance_path_clone, session_start)
+                        };
+                        janitor::after_session(&instance_path_clone);
+                        if damaged.is_empty() {
+                            save_sync::sync_instance(&app_clone, &instance_id, &instance_path_clone, &version, &auth_profile_clone.id, save_sync::Direction::Push);
                         }
                         Ok(())
                     },
                     Err(e) => {
-                        let message = format!("Failed to spawn process: {}", e);
-                        let _ = app_clone.emit("launch_progress", serde_json::json!({
-                            "instanceId": instance_id,
-                            "stage": "error",
-                            "percent": 100,
-                            "message": message
-                        }));
+                        let message = if e.kind() == std::io::ErrorKind::PermissionDenied {
+                            antivirus::record_blocked(None, Path::new(cmd.get_program()), None, antivirus::Symptom::AccessDenied)
+                        } else {
+                            format!("Failed to spawn process: {}", e)
+                        };
+                        crate::events::emit_progress(&app_clone, crate::events::LaunchProgress::new(&instance_id, "error", 100, &message));
+                        history.finish(launch_history::Outcome::Failed, None, Some(&message));
                         Err(message)
                     }
                 }
             }
             Err(e) => {
-                let _ = app_clone.emit("launch_progress", serde_json::json!({
-                    "instanceId": instance_id,
-                    "stage": "error",
-                    "percent": 100,
-                    "message": e
-                }));
+                crate::events::emit_progress(&app_clone, crate::events::LaunchProgress {
+                    timings: Some(history.timings()),
+                    ..crate::events::LaunchProgress::new(&instance_id, "error", 100, &e)
+                });
+                history.finish(launch_history::Outcome::Failed, None, Some(&e));
                 Err(e)
             }
         }
@@ -496,8 +780,9 @@ async fn launch_instance(app: tauri::AppHandle, instance_id: String, state: Stat
 
 #[tauri::command]
 async fn prepare_instance(app: tauri::AppHandle, instance_id: String, state: State<'_, AppState>) -> Result<(), String> {
+    settings::ensure_launch_allowed(&instance_id)?;
     let instances = load_instances();
-    let instance = instances.iter().find(|i| i.id == instance_id)
+    let instance = instances.into_iter().find(|i| i.id == instance_id)
         .ok_or("Instance not found")?;
     if instance.launcher.is_none() {
         return Err("Launcher not configured".to_string());
@@ -506,64 +791,10 @@ async fn prepare_instance(app: tauri::AppHandle, instance_id: String, state: Sta
         let auth_state = state.auth.lock().map_err(|_| "Failed to lock auth state")?;
         auth_state.profile.clone().ok_or("Not logged in. Please login first.")?
     };
-    let instance_path = std::path::PathBuf::from(&instance.path);
-    let app_data = get_instances_dir();
-    let root_path = std::path::PathBuf::from(app_data);
-    let ram = instance.ram.unwrap_or(4096);
-    let version = instance.version.clone();
-    let mods_urls = instance.mods.clone();
-    let modpack_url = instance.modpack_url.clone();
-    let loader = instance.modloader.clone();
-    let width = instance.resolution_width;
-    let height = instance.resolution_height;
-    let instance_path_clone = instance_path.clone();
-    let root_path_clone = root_path.clone();
-    let auth_profile_clone = auth_profile.clone();
-    let mods_urls_clone = mods_urls.clone();
-    let modpack_url_clone = modpack_url.clone();
-    let loader_clone = loader.clone();
-    let app_clone = app.clone();
     let result = tauri::async_runtime::spawn_blocking(move || {
-        let _ = fs::create_dir_all(instance_path_clone.join("logs"));
-        let _ = std::fs::create_dir_all(instance_path_clone.join("minecraft"));
-        let _ = std::fs::create_dir_all(instance_path_clone.join("minecraft").join("mods"));
-        let _ = std::fs::create_dir_all(instance_path_clone.join("minecraft").join("resourcepacks"));
-        let coremods = instance_path_clone.join("minecraft").join("coremods");
-        if coremods.exists() { let _ = std::fs::remove_dir_all(coremods); }
-        match minecraft::launch_logic::prepare_and_launch(
-            &root_path_clone,
-            &instance_path_clone,
-            &version,
-            &auth_profile_clone,
-            ram,
-            mods_urls_clone,
-            modpack_url_clone,
-            loader_clone,
-            width,
-            height,
-            Some(app_clone.clone()),
-            &instance_id,
-            true // force_update: true for manual verify/repair
-        ) {
-            Ok(_) => {
-                let _ = app_clone.emit("launch_progress", serde_json::json!({
-                    "instanceId": instance_id,
-                    "stage": "descarga_completa",
-                    "percent": 100,
-                    "message": "Descarga completa"
-                }));
-                Ok(())
-            },
-            Err(e) => {
-                let _ = app_clone.emit("launch_progress", serde_json::json!({
-                    "instanceId": instance_id,
-                    "stage": "error",
-                    "percent": 100,
-                    "message": e
-                }));
-                Err(e)
-            }
-        }
+        let result = verify_instance_files(&app, &instance, &auth_profile);
+        notifications::notify_completion(&app, &instance.name, "Verificación", &result);
+        result
     }).await;
 
     match result {
@@ -572,64 +803,171 @@ async fn prepare_instance(app: tauri::AppHandle, instance_id: String, state: Sta
     }
 }
 
-#[tauri::command]
-fn check_instance_ready(instance_id: String) -> Result<bool, String> {
-    let instances = load_instances();
-    let instance = instances.iter().find(|i| i.id == instance_id)
-        .ok_or("Instance not found")?;
-    
+/// Full verify/repair of an instance (loader files, modpack and mods), reporting through
+/// `launch_progress`. Blocking; used by `prepare_instance` and scheduled jobs.
+fn verify_instance_files(app: &tauri::AppHandle, instance: &Instance, auth_profile: &auth::MinecraftProfile) -> Result<(), String> {
+    let _active = updater::mark_active(app, &instance.id);
     let instance_path = std::path::PathBuf::from(&instance.path);
-    if !instance_path.exists() {
-        return Ok(false);
+    let root_path = sandbox::base_path(instance);
+    let launch_options = minecraft::launch_logic::LaunchOptions {
+        width: instance.resolution_width,
+        height: instance.resolution_height,
+        debug: instance.debug_launch.unwrap_or(false),
+        log_level: instance.log_level.clone(),
+        quick_play_world: None,
+        quick_play_server: None,
+        fullscreen: false,
+        linux: instance.linux_tweaks.clone().unwrap_or_default(),
+        instance_name: instance.name.clone(),
+        game_language: instance.game_language.clone(),
+        translation_pack_url: instance.translation_pack_url.clone(),
+        jvm_preset: instance.jvm_preset.clone(),
+        singleplayer_only: instance.singleplayer_only.unwrap_or(false),
+        java_runtime: minecraft::java::pinned_runtime(&root_path, instance),
+    };
+    let _ = fs::create_dir_all(instance_path.join("logs"));
+    let _ = std::fs::create_dir_all(instance_path.join("minecraft"));
+    let _ = std::fs::create_dir_all(instance_path.join("minecraft").join("mods"));
+    let _ = std::fs::create_dir_all(instance_path.join("minecraft").join("resourcepacks"));
+    let coremods = instance_path.join("minecraft").join("coremods");
+    if coremods.exists() { let _ = std::fs::remove_dir_all(coremods); }
+    match minecraft::launch_logic::prepare_and_launch(
+        &root_path,
+        &instance_path,
+        &instance.version,
+        auth_profile,
+        instance.ram.unwrap_or_else(|| platform::default_ram_for(instance.modloader.as_deref())),
+        instance.mods.clone(),
+        instance.modpack_url.clone(),
+        instance.modloader.clone(),
+        Some(app.clone()),
+        &instance.id,
+        true, // force_update: true for manual verify/repair
+        &launch_options
+    ) {
+        Ok(_) => {
+            crate::events::emit_progress(app, crate::events::LaunchProgress::new(&instance.id, "descarga_completa", 100, "Descarga completa"));
+            Ok(())
+        },
+        Err(e) => {
+            crate::events::emit_progress(app, crate::events::LaunchProgress::new(&instance.id, "error", 100, &e));
+            Err(e)
+        }
     }
+}
 
-    let root_path = std::path::PathBuf::from(get_instances_dir());
-    
-    // 1. Check if modpack is downloaded
-    if let Some(url) = &instance.modpack_url {
-        if !url.is_empty() {
-             let zip_path = instance_path.join("minecraft").join("modpack.zip");
-             if !zip_path.exists() {
-                 return Ok(false);
-             }
-             // If zip exists, we assume it's ready. Launch logic handles extraction.
+// Adoptium JRE archives are 40-55 MB across majors and platforms
+const JAVA_ESTIMATE_BYTES: u64 = 50 * 1024 * 1024;
+
+/// What preparing or launching the instance would download right now.
+#[tauri::command]
+async fn estimate_instance_download(instance_id: String) -> Result<minecraft::readiness::DownloadEstimate, String> {
+    run_blocking(move || {
+        let instance = load_instances().into_iter().find(|i| i.id == instance_id)
+            .ok_or("Instance not found")?;
+        let root_path = sandbox::base_path(&instance);
+        let mut estimate = minecraft::readiness::DownloadEstimate::default();
+
+        if let Some(url) = instance.modpack_url.as_deref().filter(|u| !u.is_empty()) {
+            let zip_path = std::path::PathBuf::from(&instance.path).join("minecraft").join("modpack.zip");
+            let local_len = fs::metadata(&zip_path).map(|m| m.len()).ok();
+            match updater::remote_len(url, &minecraft::content_source::Target::of(&instance)) {
+                Ok(Some(remote)) if local_len != Some(remote) => estimate.modpack_bytes = remote,
+                Ok(Some(_)) => {}
+                Ok(None) | Err(_) => estimate.unknown.push("modpack".to_string()),
+            }
         }
-    }
+        let java_major = minecraft::java::get_required_java_version(&instance.version);
+        let pinned = minecraft::java::pinned_runtime(&root_path, &instance);
+        if minecraft::java::get_instance_java_path(&root_path, java_major, pinned.as_deref()).is_err() {
+            estimate.java_bytes = JAVA_ESTIMATE_BYTES;
+        }
+        match minecraft::readiness::missing_asset_bytes(&root_path.join("assets"), &root_path.join("versions"), &instance.version) {
+            Some(bytes) => estimate.assets_bytes = bytes,
+            None => estimate.unknown.push("assets".to_string()),
+        }
+        estimate.total_bytes = estimate.modpack_bytes + estimate.java_bytes + estimate.assets_bytes;
+        Ok(estimate)
+    }).await
+}
+
+#[tauri::command]
+async fn check_instance_ready(instance_id: String) -> Result<minecraft::readiness::InstanceReadiness, String> {
+    // Stats every asset object
+    run_blocking(move || instance_readiness(&instance_id)).await
+}
 
+fn instance_readiness(instance_id: &str) -> Result<minecraft::readiness::InstanceReadiness, String> {
+    use minecraft::readiness::{asset_status, client_status, mods_status, ComponentState, ComponentStatus, InstanceReadiness};
+    let instances = load_instances();
+    let instance = instances.iter().find(|i| i.id == instance_id)
+        .ok_or("Instance not found")?;
+
+    let root_path = sandbox::base_path(instance);
+    let minecraft_dir = std::path::PathBuf::from(&instance.path).join("minecraft");
     let versions_dir = root_path.join("versions");
     let is_vanilla = instance.modloader.as_deref().map(|l| l == "vanilla").unwrap_or(true);
-    if is_vanilla {
-        let client_jar = instance_path.join("minecraft").join("client.jar");
-        if !client_jar.exists() {
-            return Ok(false);
-        }
-    } else {
-        let profile_json = versions_dir.join(&instance.version).join("version.json");
-        if !profile_json.exists() {
-            return Ok(false);
-        }
-    }
-    
-    Ok(true)
+
+    let java_major = minecraft::java::get_required_java_version(&instance.version);
+    let pinned = minecraft::java::pinned_runtime(&root_path, instance);
+    let java = match minecraft::java::get_instance_java_path(&root_path, java_major, pinned.as_deref()) {
+        Ok(_) => ComponentStatus::ready(),
+        Err(_) => ComponentStatus::missing(),
+    };
+    let client = client_status(&minecraft_dir, &versions_dir, &instance.version, is_vanilla);
+    let assets = asset_status(&root_path.join("assets"), &versions_dir, &instance.version);
+    let mods = mods_status(&minecraft_dir, instance.modpack_url.as_deref(), instance.mods.as_deref().unwrap_or_default());
+    let ready = [&java, &client, &assets, &mods].iter().all(|c| c.state == ComponentState::Ready);
+    let pending_mods = minecraft::mod_scan::pending_mods(&minecraft_dir);
+    Ok(InstanceReadiness { ready, java, client, assets, mods, pending_mods })
+}
+
+/// What `delete_instance` did with the folder: moved to the trash (restorable with
+/// `restore_instance`) or being erased in the background (see `remover::spawn`).
+#[derive(Serialize, JsonSchema)]
+struct DeletedInstance {
+    #[serde(rename = "trashId")]
+    trash_id: Option<String>,
+    #[serde(rename = "jobId")]
+    job_id: Option<String>,
 }
 
+/// Removes the instance from the list at once. Its folder goes to the launcher's trash unless
+/// that's turned off (or `permanent` is set), in which case it's deleted in the background.
 #[tauri::command]
-fn delete_instance(instance_id: String) -> Result<(), String> {
+fn delete_instance(app: tauri::AppHandle, instance_id: String, permanent: Option<bool>) -> Result<DeletedInstance, String> {
+    settings::ensure_not_guest()?;
     let mut instances = load_instances();
     if let Some(index) = instances.iter().position(|i| i.id == instance_id) {
         let instance = &instances[index];
-        let path = std::path::PathBuf::from(&instance.path);
-        
-        // Safety check: Ensure path is within instances_subdir
-        let instances_subdir = std::path::PathBuf::from(get_instances_subdir());
-        // Only delete if it looks like a valid subfolder
-        if path.starts_with(&instances_subdir) && path != instances_subdir && path.exists() {
-             let _ = fs::remove_dir_all(&path);
+        // A sandbox goes with its whole root (its own libraries, assets and Java); never to the trash
+        if let Some(root) = sandbox::removable_root(instance) {
+            let job_id = remover::spawn(&app, &instance.name, remover::retire(&root));
+            instances.remove(index);
+            save_instances(&instances);
+            return Ok(DeletedInstance { trash_id: None, job_id: Some(job_id) });
+        }
+        // Safety check: the folder must resolve to a subfolder of instances_subdir (no `..`, no symlinks out)
+        let instances_subdir = path_policy::resolve(Path::new(&get_instances_subdir()));
+        let path = path_policy::inside(Path::new(&instance.path), Path::new(&get_instances_subdir()));
+        let mut deleted = DeletedInstance { trash_id: None, job_id: None };
+        if let (Ok(path), Ok(instances_subdir)) = (path, instances_subdir) {
+            if path != instances_subdir && path.exists() {
+                if permanent.unwrap_or(false) {
+                    deleted.job_id = Some(remover::spawn(&app, &instance.name, remover::retire(&path)));
+                } else if settings::load_settings().trash.enabled {
+                    let trash_id = trash::move_to_trash(&trash::root(), instance, &path)?.id;
+                    let _ = undo::record_trashed(&app, &instance.name, &trash_id);
+                    deleted.trash_id = Some(trash_id);
+                } else {
+                    undo::stage_and_announce(&app, "delete_instance", &instance.name, &[path], Some(instance.clone()))?;
+                }
+            }
         }
 
         instances.remove(index);
         save_instances(&instances);
-        Ok(())
+        Ok(deleted)
     } else {
         Err("Instance not found".to_string())
     }
@@ -638,9 +976,9 @@ fn delete_instance(instance_id: String) -> Result<(), String> {
 #[tauri::command]
 fn check_admin_password(password: String) -> bool {
     let path = get_admin_path();
+    // Nothing is admin until the first-run setup (see `admin_setup`) sets a password
     if !Path::new(&path).exists() {
-        // Default password if not set: "DrkAdmin2026Secure!"
-        return password == "DrkAdmin2026Secure!";
+        return false;
     }
     if let Ok(content) = fs::read_to_string(&path) {
         if let Ok(json) = serde_json::from_str::<serde_json::Value>(&content) {
@@ -655,8 +993,25 @@ fn check_admin_password(password: String) -> bool {
     false
 }
 
+/// `Err` with the usual message unless `password` is the admin password.
+pub fn ensure_admin(password: String) -> Result<(), String> {
+    if check_admin_password(password) {
+        Ok(())
+    } else {
+        Err("Contraseña de administrador incorrecta".to_string())
+    }
+}
+
+/// Changes the admin password; the first one is set through `admin_setup::complete_admin_setup`
+/// and a forgotten one reset through `admin_reset::reset_admin_password`.
 #[tauri::command]
-fn set_admin_password(password: String) -> Result<(), String> {
+fn set_admin_password(current_password: String, password: String) -> Result<(), String> {
+    ensure_admin(current_password)?;
+    admin_setup::validate_password(&password)?;
+    store_admin_password(&password)
+}
+
+fn store_admin_password(password: &str) -> Result<(), String> {
     let path = get_admin_path();
     let mut hasher = Sha1::new();
     hasher.update(password.as_bytes());
@@ -679,95 +1034,381 @@ fn is_admin_configured() -> bool {
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+    let args = std::env::args().collect::<Vec<_>>();
+    data_dir::init(&args);
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
+        .plugin(tauri_plugin_notification::init())
+        .plugin(tauri_plugin_global_shortcut::Builder::new().build())
         .manage(AppState {
             auth: Mutex::new(AuthState::default()),
         })
+        .manage(scheduler::SchedulerState::default())
+        .manage(minecraft::prefetch::PrefetchState::default())
+        .manage(updater::ActiveInstances::default())
+        .manage(shortcuts::PendingLaunch(Mutex::new(shortcuts::launch_arg(&args))))
+        .manage(kiosk::PendingKiosk(Mutex::new(kiosk::kiosk_arg(&args))))
+        .manage(file_associations::PendingOpen(Mutex::new(file_associations::open_arg(&args))))
+        .manage(drop_install::DropTarget::default())
+        .manage(server_queue::ServerQueue::default())
+        .manage(progress_snapshot::ProgressSnapshots::default())
+        .register_asynchronous_uri_scheme_protocol(asset_protocol::SCHEME, asset_protocol::handle_request)
+        .on_window_event(drop_install::handle_window_event)
+        .setup(|app| {
+            // The UI skips the login screen once the remembered account is back (`session_restored`)
+            auth::start_session_restore(app.handle().clone());
+            kiosk::init(app.handle());
+            scheduler::start(app.handle().clone());
+            hotkey::init(app.handle());
+            updater::start(app.handle().clone());
+            announcements::start(app.handle().clone());
+            maintenance::start(app.handle().clone());
+            service_status::start(app.handle().clone());
+            accessibility::start(app.handle().clone());
+            admin_setup::ensure_code();
+            fleet::start();
+            if let Err(e) = metrics::start(app.handle().clone()) {
+                eprintln!("[metrics] Endpoint: {}", e);
+            }
+            remover::sweep_leftovers(PathBuf::from(get_instances_subdir()));
+            remover::sweep_leftovers(PathBuf::from(get_instances_dir()));
+            remover::sweep_leftovers(sandbox::root());
+            temp_files::start_sweep(PathBuf::from(get_instances_dir()));
+            let versions_dir = PathBuf::from(get_instances_dir()).join("versions");
+            std::thread::spawn(move || minecraft::version_store::LocalVersions::new(&versions_dir).migrate_all());
+            trash::start();
+            file_associations::start();
+            image_cache::start();
+            std::thread::spawn(platform::check_hardware_change);
+            undo::start();
+            server_history::start();
+            progress_snapshot::install(app.handle());
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             get_instances,
             save_instance,
             delete_instance,
             launch_instance,
+            launch_world,
             prepare_instance,
             check_instance_ready,
-            get_system_ram,
+            estimate_instance_download,
+            platform::get_platform_info,
+            platform::get_hardware_profile,
+            platform::get_default_ram,
+            platform::check_version_support,
+            platform::get_machine_id,
+            fleet::get_fleet_registration,
+            metrics::get_metrics_settings,
+            metrics::set_metrics_settings,
+            fleet::register_machine,
+            minecraft::jvm_presets::get_jvm_presets,
+            optimize::optimize_instance,
+            launch_history::get_launch_history,
+            instance_notes::set_instance_notes,
+            instance_notes::get_instance_admin_notes,
+            instance_notes::set_instance_admin_notes,
+            instance_players::get_instance_players,
+            crash_analysis::analyze_crash,
+            crash_analysis::apply_crash_suggestion,
+            diagnostics::export_diagnostics,
+            crash_upload::get_crash_upload,
+            crash_upload::set_crash_upload,
+            crash_upload::set_crash_upload_endpoint,
+            share_code::import_instance_from_code,
+            share_code::export_instance_code,
+            share_code::get_share_code_endpoint,
+            share_code::set_share_code_endpoint,
+            rewards::get_rewards_endpoint,
+            rewards::set_rewards_endpoint,
+            announcements::get_announcement_settings,
+            announcements::set_announcement_settings,
+            maintenance::get_maintenance,
+            maintenance::set_maintenance,
+            maintenance::get_maintenance_url,
+            maintenance::set_maintenance_url,
+            service_status::get_service_status,
+            service_status::get_status_url,
+            service_status::set_status_url,
+            accessibility::get_accessibility_prefs,
+            server_queue::check_server_capacity,
+            server_queue::wait_for_server_slot,
+            server_queue::cancel_server_queue,
+            server_history::get_server_history,
+            server_history::get_server_history_settings,
+            server_history::set_server_history_settings,
+            remote_commands::get_remote_command_log,
+            drop_install::set_drop_target,
             get_mc_versions,
+            get_latest_versions,
+            check_instance_update,
+            apply_instance_update,
+            check_instance_compatibility,
+            get_game_languages,
+            get_world_migration,
+            shortcuts::create_instance_shortcut,
+            shortcuts::take_pending_launch,
+            kiosk::take_pending_kiosk,
+            kiosk::kiosk_start,
+            kiosk::get_kiosk_settings,
+            kiosk::set_kiosk_settings,
+            kiosk::create_kiosk_shortcut,
+            file_associations::take_pending_open,
+            file_associations::get_file_associations,
+            file_associations::set_file_associations,
+            gamepad::apply_gamepad_profile,
+            graphics::check_graphics,
+            data_dir::get_data_dir_info,
+            data_dir::migrate_data_dir,
+            backup_instance_worlds,
             get_loader_recommendation,
             get_java_info,
             download_java,
+            get_asset_stats,
+            prune_assets,
+            inspect_modpack,
+            minecraft::prefetch::prefetch_instance,
+            minecraft::server::download_server,
             open_folder,
+            open_instance_folder,
+            reveal_file,
+            instance_images::attach_instance_image,
+            image_cache::prefetch_images,
+            storage::get_storage_summary,
+            remover::cancel_removal,
+            minecraft::launch_logic::cancel_modpack_extraction,
+            storage::clear_caches,
+            trash::get_trash_settings,
+            janitor::get_janitor_settings,
+            janitor::set_janitor_settings,
+            trash::set_trash_settings,
+            trash::list_trash,
+            trash::restore_instance,
+            trash::purge_trash,
+            undo::undo_last_operation,
+            tuning::get_performance_settings,
+            tuning::set_performance_settings,
+            minecraft::version_store::get_version_meta_settings,
+            minecraft::version_store::set_version_meta_settings,
+            mirror::get_mirror_settings,
+            mirror::set_mirror_settings,
+            mirror::validate_mirror,
+            offline_bundle::export_offline_bundle,
+            save_sync::get_save_sync,
+            save_sync::set_synced_worlds,
+            save_sync::get_save_sync_target,
+            save_sync::set_save_sync_target,
+            save_sync::resolve_save_conflict,
+            attestation::get_attestation_settings,
+            attestation::set_attestation_settings,
+            minecraft::content_source::search_content,
+            minecraft::mod_scan::list_quarantined_mods,
+            minecraft::mod_scan::approve_quarantined_mod,
+            minecraft::mod_scan::reject_quarantined_mod,
+            minecraft::mod_scan::list_pending_mods,
+            minecraft::mod_scan::approve_pending_mod,
+            minecraft::mod_scan::reject_pending_mod,
+            minecraft::mod_scan::get_mod_scan_settings,
+            minecraft::mod_scan::set_mod_scan_settings,
+            minecraft::loader_pin::get_loader_locks,
+            minecraft::loader_pin::clear_loader_lock,
+            minecraft::instance_lock::get_instance_lock,
+            minecraft::instance_lock::clear_instance_lock,
+            minecraft::config_patch::get_instance_config_patches,
+            minecraft::config_patch::set_instance_config_patches,
+            progress_snapshot::get_progress_snapshot,
+            progress_snapshot::get_active_progress,
             auth::start_microsoft_login,
             auth::get_auth_profile,
             auth::start_offline_login,
+            auth::get_account_avatar,
+            auth::get_startup_auth_state,
             check_admin_password,
             set_admin_password,
-            is_admin_configured
+            admin_setup::get_admin_setup_status,
+            admin_setup::complete_admin_setup,
+            admin_reset::reset_admin_password,
+            admin_reset::get_admin_reset_log,
+            is_admin_configured,
+            settings::get_guest_mode,
+            settings::set_guest_mode,
+            settings::get_credentials,
+            settings::set_credentials,
+            scheduler::get_scheduled_jobs,
+            scheduler::schedule_job,
+            scheduler::cancel_scheduled_job,
+            settings::get_notification_settings,
+            settings::set_notification_settings,
+            hotkey::get_launch_hotkey,
+            hotkey::set_launch_hotkey,
+            updater::get_updater_settings,
+            download_stats::get_download_stats,
+            download_stats::run_mirror_speedtest,
+            antivirus::get_antivirus_guidance,
+            antivirus::reverify_blocked_files,
+            content_audit::audit_instance_content,
+            sandbox::create_sandbox_clone,
+            smoke_test::smoke_test_instance,
+            content_index::export_content_index,
+            content_index::verify_against_index,
+            updater::set_updater_settings,
+            updater::preview_modpack_update,
+            updater::apply_modpack_update,
+            host_pins::get_host_pins,
+            host_pins::forget_host_pin,
+            host_pins::get_host_pin_mode,
+            host_pins::set_host_pin_mode,
+            settings::get_data_saver,
+            settings::set_data_saver,
+            schema::get_api_schema,
+            last_played::format_last_played,
+            mod_links::validate_mod_urls,
+            safe_mode::get_safe_mode,
+            safe_mode::start_safe_mode,
+            safe_mode::exit_safe_mode,
+            save_recovery::check_instance_saves,
+            save_recovery::restore_world
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
+/// Runs blocking work (network, hashing, Java installs) off the command thread so the UI never
+/// waits on it.
+async fn run_blocking<T: Send + 'static>(f: impl FnOnce() -> Result<T, String> + Send + 'static) -> Result<T, String> {
+    tauri::async_runtime::spawn_blocking(f)
+        .await
+        .map_err(|e| format!("Task panicked: {}", e))?
+}
+
 #[tauri::command]
-fn get_mc_versions(limit: usize) -> Result<Vec<String>, String> {
+async fn get_mc_versions(limit: usize) -> Result<Vec<String>, String> {
     // If limit is small (e.g. 12 from frontend default), we bump it to 100 to show more versions
     let effective_limit = if limit < 20 { 100 } else { limit };
-    minecraft::versions::get_release_versions(effective_limit)
+    run_blocking(move || minecraft::versions::get_release_versions(effective_limit)).await
 }
 
 #[tauri::command]
-fn get_loader_recommendation(loader: String, mc_version: String) -> Result<String, String> {
-    let client = reqwest::blocking::Client::builder()
-        .user_agent("DrkLauncher/1.0")
-        .build()
-        .map_err(|e| e.to_string())?;
-
-    match loader.as_str() {
-        "fabric" => {
-            // Query Fabric meta for recommended stable loader
-            let url = format!("https://meta.fabricmc.net/v2/versions/loader/{}", mc_version);
-            let list: serde_json::Value = client.get(&url)
-                .send()
-                .map_err(|e| e.to_string())?
-                .json()
-                .map_err(|e| e.to_string())?;
-            let loader_version = list.as_array()
-                .and_then(|arr| arr.iter().find(|item| item["loader"]["stable"].as_bool().unwrap_or(true)))
-                .and_then(|item| item["loader"]["version"].as_str())
-                .ok_or("No fabric loader version found")?;
-            Ok(loader_version.to_string())
-        }
-        "forge" => {
-            // Use Forge promotions.json to get recommended build for a MC version
-            let promos: serde_json::Value = client.get("https://files.minecraftforge.net/net/minecraftforge/forge/promotions.json")
-                .send()
-                .map_err(|e| e.to_string())?
-                .json()
-                .map_err(|e| e.to_string())?;
-            if let Some(map) = promos.get("promos").and_then(|v| v.as_object()) {
-                let key = format!("{}-recommended", mc_version);
-                if let Some(ver) = map.get(&key).and_then(|v| v.as_str()) {
-                    return Ok(ver.to_string());
-                }
-            }
-            Err("No forge recommended build found".to_string())
+async fn get_latest_versions() -> Result<minecraft::versions::LatestVersions, String> {
+    run_blocking(|| {
+        let manifest = minecraft::launch_logic::fetch_manifest_with_fallback()?;
+        Ok(minecraft::versions::latest_versions(&manifest))
+    }).await
+}
+
+#[tauri::command]
+async fn check_instance_update(instance_id: String) -> Result<Option<minecraft::versions::PatchUpdate>, String> {
+    run_blocking(move || {
+        let instance = load_instances().into_iter().find(|i| i.id == instance_id)
+            .ok_or("Instance not found")?;
+        if !instance.track_latest_patch.unwrap_or(false) {
+            return Ok(None);
         }
-        _ => Err("Unsupported loader".to_string())
+        minecraft::versions::check_patch_update(&instance.version, instance.modloader.as_deref())
+    }).await
+}
+
+#[tauri::command]
+fn get_world_migration(instance_id: String, new_version: String) -> Result<minecraft::worlds::WorldMigrationPlan, String> {
+    let instance = load_instances().into_iter().find(|i| i.id == instance_id)
+        .ok_or("Instance not found")?;
+    let minecraft_dir = Path::new(&instance.path).join("minecraft");
+    Ok(minecraft::worlds::plan_migration(&minecraft_dir, &instance.version, &new_version))
+}
+
+/// Zips the instance worlds into `backups/worlds` before a version change.
+#[tauri::command]
+async fn backup_instance_worlds(instance_id: String) -> Result<Vec<String>, String> {
+    run_blocking(move || {
+        let instance = load_instances().into_iter().find(|i| i.id == instance_id)
+            .ok_or("Instance not found")?;
+        let instance_path = Path::new(&instance.path);
+        let timestamp = unix_now();
+        let backups = minecraft::worlds::backup_worlds(
+            &instance_path.join("minecraft"),
+            &instance_path.join("backups").join("worlds"),
+            &instance.version,
+            timestamp,
+        )?;
+        Ok(backups.iter().map(|p| p.to_string_lossy().to_string()).collect())
+    }).await
+}
+
+#[tauri::command]
+async fn get_game_languages(version: String) -> Result<Vec<String>, String> {
+    run_blocking(move || minecraft::game_options::available_languages(Path::new(&get_instances_dir()), &version)).await
+}
+
+#[tauri::command]
+async fn check_instance_compatibility(version: String, modloader: Option<String>) -> Result<minecraft::versions::CompatibilityReport, String> {
+    run_blocking(move || minecraft::versions::check_compatibility(&version, modloader.as_deref())).await
+}
+
+/// Moves a tracking instance to a newer patch; the frontend re-prepares it right after.
+#[tauri::command]
+fn apply_instance_update(instance_id: String, version: String) -> Result<(), String> {
+    settings::ensure_not_guest()?;
+    let mut instances = load_instances();
+    let instance = instances.iter_mut().find(|i| i.id == instance_id)
+        .ok_or("Instance not found")?;
+    let current = minecraft::launch_logic::parse_mc_version(&instance.version);
+    let target = minecraft::launch_logic::parse_mc_version(&version);
+    match (current, target) {
+        (Some((cur_minor, cur_patch)), Some((minor, patch))) if minor == cur_minor && patch > cur_patch => {}
+        _ => return Err(format!("{} no es un parche posterior de {}", version, instance.version)),
     }
+    instance.version = version;
+    save_instances(&instances);
+    Ok(())
+}
+
+#[tauri::command]
+async fn get_loader_recommendation(loader: String, mc_version: String) -> Result<minecraft::versions::LoaderVersions, String> {
+    run_blocking(move || minecraft::versions::loader_versions(&loader, &mc_version)).await
+}
+
+#[tauri::command]
+async fn get_java_info(mc_version: String) -> Result<serde_json::Value, String> {
+    // Probing `java -version` spawns a process; keep it off the command thread too
+    run_blocking(move || {
+        let required = minecraft::java::get_required_java_version(&mc_version);
+        let path = minecraft::java::get_java_path(&mc_version)?;
+        let installed = path.to_string_lossy() != "java" || minecraft::java::get_system_java_version("java").unwrap_or(0) >= required;
+        Ok(serde_json::json!({
+            "recommended": required,
+            "installed": installed,
+            "path": path.to_string_lossy()
+        }))
+    }).await
+}
+
+#[tauri::command]
+async fn get_asset_stats() -> Result<minecraft::assets::AssetStats, String> {
+    run_blocking(move || minecraft::assets::compute_asset_stats(&Path::new(&get_instances_dir()).join("assets"))).await
+}
+
+/// Unreferenced objects are staged for undo (see `undo`) rather than deleted right away.
+#[tauri::command]
+async fn prune_assets(app: tauri::AppHandle, password: String) -> Result<minecraft::assets::PruneResult, String> {
+    run_blocking(move || {
+        ensure_admin(password)?;
+        let objects = minecraft::assets::unreferenced_objects(&Path::new(&get_instances_dir()).join("assets"))?;
+        let paths: Vec<PathBuf> = objects.iter().map(|(path, _)| path.clone()).collect();
+        undo::stage_and_announce(&app, "prune_assets", "Assets sin usar", &paths, None)?;
+        Ok(minecraft::assets::PruneResult {
+            removed_count: objects.len(),
+            freed_bytes: objects.iter().map(|(_, size)| size).sum(),
+        })
+    }).await
 }
 
 #[tauri::command]
-fn get_java_info(mc_version: String) -> Result<serde_json::Value, String> {
-    let required = minecraft::java::get_required_java_version(&mc_version);
-    let path = minecraft::java::get_java_path(&mc_version)?;
-    let installed = path.to_string_lossy() != "java" || minecraft::java::get_system_java_version("java").unwrap_or(0) >= required;
-    Ok(serde_json::json!({
-        "recommended": required,
-        "installed": installed,
-        "path": path.to_string_lossy()
-    }))
+async fn inspect_modpack(url_or_path: String) -> Result<minecraft::modpack::ModpackInfo, String> {
+    run_blocking(move || minecraft::modpack::inspect_modpack(url_or_path.trim())).await
 }
 
+/// Progress is reported through `java_download_progress`.
 #[tauri::command]
-fn download_java(major: u32) -> Result<String, String> {
-    minecraft::java::download_java(major, None, None)
+async fn download_java(app: tauri::AppHandle, major: u32) -> Result<String, String> {
+    run_blocking(move || minecraft::java::download_java(major, Some(&app), None)).await
 }
@@ -0,0 +1,174 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+use crate::settings::{load_settings, save_settings};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Blocks launching while the event server restarts. Set by staff locally, by a signed remote
+/// command (see `crate::remote_commands`) or by the maintenance feed.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct Maintenance {
+    #[serde(default)]
+    pub message: String,
+    /// Unix seconds when it ends by itself; shown as a countdown. `None` lasts until lifted
+    #[serde(default)]
+    pub until: Option<u64>,
+    /// Instances it applies to; empty means all of them
+    #[serde(rename = "instanceIds", alias = "instance_ids", default)]
+    pub instance_ids: Vec<String>,
+}
+
+impl Maintenance {
+    pub fn is_over(&self, now: u64) -> bool {
+        self.until.map(|until| now >= until).unwrap_or(false)
+    }
+
+    pub fn applies_to(&self, instance_id: &str, now: u64) -> bool {
+        !self.is_over(now) && (self.instance_ids.is_empty() || self.instance_ids.iter().any(|id| id == instance_id))
+    }
+
+    /// "Mantenimiento: <message> (quedan 12 min)"
+    pub fn launch_error(&self, now: u64) -> String {
+        let message = if self.message.trim().is_empty() { "el servidor se está reiniciando" } else { self.message.trim() };
+        match self.until {
+            Some(until) => format!("Mantenimiento: {} (quedan {} min)", message, until.saturating_sub(now).div_ceil(60).max(1)),
+            None => format!("Mantenimiento: {}", message),
+        }
+    }
+}
+
+/// Last answer of the maintenance feed.
+static FEED: Mutex<Vec<Maintenance>> = Mutex::new(Vec::new());
+
+/// The feed may answer `null`, `{}`, one entry or a list of them.
+pub fn parse_feed(body: &str) -> Result<Vec<Maintenance>, String> {
+    let value: serde_json::Value = serde_json::from_str(body).map_err(|e| format!("Respuesta de mantenimiento no válida: {}", e))?;
+    let value = match value {
+        serde_json::Value::Object(mut object) if object.contains_key("maintenance") => object.remove("maintenance").unwrap_or_default(),
+        value => value,
+    };
+    let entries = match value {
+        serde_json::Value::Null => Vec::new(),
+        serde_json::Value::Array(_) => serde_json::from_value(value).map_err(|e| e.to_string())?,
+        serde_json::Value::Object(ref object) if object.is_empty() => Vec::new(),
+        value => vec![serde_json::from_value(value).map_err(|e| e.to_string())?],
+    };
+    Ok(entries)
+}
+
+/// Every entry in effect right now, local and from the feed.
+pub fn active() -> Vec<Maintenance> {
+    let now = crate::unix_now();
+    let feed = FEED.lock().map(|feed| feed.clone()).unwrap_or_default();
+    load_settings().maintenance_mode.into_iter().chain(feed).filter(|m| !m.is_over(now)).collect()
+}
+
+/// Launch error for `instance_id` if a maintenance window covers it.
+pub fn launch_blocked(instance_id: &str) -> Option<String> {
+    let now = crate::unix_now();
+    active().into_iter().find(|m| m.applies_to(instance_id, now)).map(|m| m.launch_error(now))
+}
+
+fn notify(app: &AppHandle) {
+    let _ = app.emit("maintenance", active());
+}
+
+/// Sets or lifts the local maintenance window and tells the UI.
+pub fn set_local(app: &AppHandle, maintenance: Option<Maintenance>) -> Result<(), String> {
+    let mut settings = load_settings();
+    settings.maintenance_mode = maintenance;
+    save_settings(&settings)?;
+    notify(app);
+    Ok(())
+}
+
+fn fetch_feed(url: &str) -> Result<Vec<Maintenance>, String> {
+    let client = reqwest::blocking::Client::builder()
+        .user_agent(crate::http::user_agent())
+        .timeout(Duration::from_secs(10))
+        .build()
+        .map_err(|e| e.to_string())?;
+    let resp = crate::http::send(&client, client.get(url))?;
+    if !resp.status().is_success() {
+        return Err(format!("El servidor de mantenimiento respondió {}", resp.status()));
+    }
+    parse_feed(&resp.text().map_err(|e| e.to_string())?)
+}
+
+/// Polls the maintenance feed, if configured, for the lifetime of the app.
+pub fn start(app: AppHandle) {
+    std::thread::spawn(move || loop {
+        let entries = match load_settings().maintenance_url.filter(|u| !u.trim().is_empty()) {
+            Some(url) => fetch_feed(&url),
+            None => Ok(Vec::new()),
+        };
+        match entries {
+            Ok(entries) => {
+                let changed = FEED.lock().map(|mut feed| std::mem::replace(&mut *feed, entries.clone()) != entries).unwrap_or(false);
+                if changed {
+                    notify(&app);
+                }
+            }
+            // Keep the last known state; a flaky feed shouldn't lift or impose maintenance
+            Err(e) => eprintln!("[maintenance] Feed: {}", e),
+        }
+        std::thread::sleep(POLL_INTERVAL);
+    });
+}
+
+#[tauri::command]
+pub fn get_maintenance() -> Vec<Maintenance> {
+    active()
+}
+
+#[tauri::command]
+pub fn set_maintenance(app: AppHandle, password: String, maintenance: Option<Maintenance>) -> Result<(), String> {
+    crate::ensure_admin(password)?;
+    set_local(&app, maintenance)
+}
+
+#[tauri::command]
+pub fn get_maintenance_url() -> Option<String> {
+    load_settings().maintenance_url
+}
+
+#[tauri::command]
+pub fn set_maintenance_url(password: String, url: Option<String>) -> Result<(), String> {
+    crate::ensure_admin(password)?;
+    let mut settings = load_settings();
+    settings.maintenance_url = url.map(|u| u.trim().to_string()).filter(|u| !u.is_empty());
+    save_settings(&settings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn windows_cover_their_instances_until_they_end() {
+        let global = Maintenance { message: "Reinicio".into(), until: Some(1000), instance_ids: Vec::new() };
+        assert!(global.applies_to("cualquiera", 999));
+        assert!(!global.applies_to("cualquiera", 1000));
+        assert_eq!(global.launch_error(881), "Mantenimiento: Reinicio (quedan 2 min)");
+
+        let scoped = Maintenance { message: String::new(), until: None, instance_ids: vec!["evento".into()] };
+        assert!(scoped.applies_to("evento", u64::MAX));
+        assert!(!scoped.applies_to("otro", 0));
+        assert_eq!(scoped.launch_error(0), "Mantenimiento: el servidor se está reiniciando");
+    }
+
+    #[test]
+    fn feeds_accept_single_entries_lists_and_nothing() {
+        assert_eq!(parse_feed("null").unwrap(), Vec::new());
+        assert_eq!(parse_feed("{}").unwrap(), Vec::new());
+        assert_eq!(parse_feed(r#"{"maintenance":null}"#).unwrap(), Vec::new());
+        assert_eq!(parse_feed(r#"{"message":"Reinicio","until":5}"#).unwrap()[0].until, Some(5));
+        let list = parse_feed(r#"{"maintenance":[{"message":"A","instanceIds":["x"]},{"message":"B"}]}"#).unwrap();
+        assert_eq!(list.len(), 2);
+        assert_eq!(list[0].instance_ids, vec!["x".to_string()]);
+        assert!(parse_feed("<html>").is_err());
+    }
+}
@@ -0,0 +1,198 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::io::{Read, Write};
+use std::net::{Ipv4Addr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::AppHandle;
+use crate::settings::{load_settings, save_settings};
+
+const DEFAULT_PORT: u16 = 9464;
+// Downloads finish in bursts; the rate is averaged over this window
+const RATE_WINDOW: Duration = Duration::from_secs(10);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Prometheus-style endpoint for organizers watching many stations. Only ever listens on
+/// 127.0.0.1; a scraper on the station (or a forwarding agent) collects it.
+#[derive(Serialize, Deserialize, Clone, Default, JsonSchema)]
+pub struct MetricsSettings {
+    #[serde(default)]
+    pub enabled: bool,
+    /// `None` is 9464; a new port applies after restarting the launcher
+    #[serde(default)]
+    pub port: Option<u16>,
+}
+
+/// What one scrape reports.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MetricsSnapshot {
+    pub downloads_in_flight: u64,
+    pub download_bytes_total: u64,
+    pub download_bytes_per_second: u64,
+    pub download_failures_total: u64,
+    /// Instances being prepared, verified or updated without their game running yet
+    pub prepare_queue_depth: u64,
+    pub running_instances: u64,
+}
+
+static IN_FLIGHT: AtomicU64 = AtomicU64::new(0);
+static BYTES_TOTAL: AtomicU64 = AtomicU64::new(0);
+static FAILURES_TOTAL: AtomicU64 = AtomicU64::new(0);
+/// Finished downloads inside `RATE_WINDOW`, oldest first.
+static RECENT: Mutex<VecDeque<(Instant, u64)>> = Mutex::new(VecDeque::new());
+/// Port the endpoint is listening on, once started.
+static LISTENING: Mutex<Option<u16>> = Mutex::new(None);
+
+/// Counts one file download as in flight until dropped.
+pub struct InFlight(());
+
+pub fn download_started() -> InFlight {
+    IN_FLIGHT.fetch_add(1, Ordering::Relaxed);
+    InFlight(())
+}
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        IN_FLIGHT.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// Fed by `download_stats`, so every path that records a download shows up here.
+pub fn record_download(bytes: u64) {
+    BYTES_TOTAL.fetch_add(bytes, Ordering::Relaxed);
+    let now = Instant::now();
+    if let Ok(mut recent) = RECENT.lock() {
+        recent.push_back((now, bytes));
+        prune(&mut recent, now);
+    }
+}
+
+pub fn record_failure() {
+    FAILURES_TOTAL.fetch_add(1, Ordering::Relaxed);
+}
+
+fn prune(recent: &mut VecDeque<(Instant, u64)>, now: Instant) {
+    while recent.front().is_some_and(|(at, _)| now.duration_since(*at) > RATE_WINDOW) {
+        recent.pop_front();
+    }
+}
+
+/// Bytes per second over the last `RATE_WINDOW` of finished downloads.
+pub fn bytes_per_second(recent: &VecDeque<(Instant, u64)>, now: Instant) -> u64 {
+    let bytes: u64 = recent.iter()
+        .filter(|(at, _)| now.duration_since(*at) <= RATE_WINDOW)
+        .map(|(_, bytes)| bytes)
+        .sum();
+    bytes / RATE_WINDOW.as_secs()
+}
+
+fn snapshot(app: &AppHandle) -> MetricsSnapshot {
+    let running = crate::launch_history::running().len() as u64;
+    let now = Instant::now();
+    let rate = RECENT.lock().map(|recent| bytes_per_second(&recent, now)).unwrap_or(0);
+    MetricsSnapshot {
+        downloads_in_flight: IN_FLIGHT.load(Ordering::Relaxed),
+        download_bytes_total: BYTES_TOTAL.load(Ordering::Relaxed),
+        download_bytes_per_second: rate,
+        download_failures_total: FAILURES_TOTAL.load(Ordering::Relaxed),
+        // Launched games keep their instance active until they exit
+        prepare_queue_depth: (crate::updater::active_count(app) as u64).saturating_sub(running),
+        running_instances: running,
+    }
+}
+
+/// Text exposition format, version 0.0.4.
+pub fn render(snapshot: &MetricsSnapshot) -> String {
+    let metrics = [
+        ("drk_downloads_in_flight", "gauge", "Files downloading right now", snapshot.downloads_in_flight),
+        ("drk_download_bytes_total", "counter", "Bytes downloaded since the launcher started", snapshot.download_bytes_total),
+        ("drk_download_bytes_per_second", "gauge", "Download rate over the last 10 seconds", snapshot.download_bytes_per_second),
+        ("drk_download_failures_total", "counter", "Failed download attempts since the launcher started", snapshot.download_failures_total),
+        ("drk_prepare_queue_depth", "gauge", "Instances being prepared, verified or updated", snapshot.prepare_queue_depth),
+        ("drk_running_instances", "gauge", "Instances with a game running", snapshot.running_instances),
+    ];
+    metrics.iter()
+        .map(|(name, kind, help, value)| format!("# HELP {name} {help}\n# TYPE {name} {kind}\n{name} {value}\n"))
+        .collect()
+}
+
+fn respond(mut stream: TcpStream, app: &AppHandle) {
+    let _ = stream.set_read_timeout(Some(REQUEST_TIMEOUT));
+    let mut request = [0u8; 1024];
+    let read = stream.read(&mut request).unwrap_or(0);
+    let request_line = String::from_utf8_lossy(&request[..read]);
+    let path = request_line.split_whitespace().nth(1).unwrap_or("");
+    let response = if !load_settings().metrics.enabled {
+        "HTTP/1.1 503 Service Unavailable\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_string()
+    } else if request_line.starts_with("GET ") && (path == "/metrics" || path.starts_with("/metrics?")) {
+        let body = render(&snapshot(app));
+        format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            body.len(),
+            body
+        )
+    } else {
+        "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_string()
+    };
+    let _ = stream.write_all(response.as_bytes());
+}
+
+/// Starts the endpoint if it's enabled and not already up. Turning it off again only makes it
+/// answer 503, since the port stays taken until the launcher closes.
+pub fn start(app: AppHandle) -> Result<(), String> {
+    let settings = load_settings().metrics;
+    if !settings.enabled {
+        return Ok(());
+    }
+    let mut listening = LISTENING.lock().map_err(|_| "Failed to lock metrics state")?;
+    if listening.is_some() {
+        return Ok(());
+    }
+    let port = settings.port.unwrap_or(DEFAULT_PORT);
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, port))
+        .map_err(|e| format!("No se pudo abrir el puerto de métricas {}: {}", port, e))?;
+    *listening = Some(port);
+    std::thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            respond(stream, &app);
+        }
+    });
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_metrics_settings() -> MetricsSettings {
+    load_settings().metrics
+}
+
+#[tauri::command]
+pub fn set_metrics_settings(app: AppHandle, password: String, metrics: MetricsSettings) -> Result<(), String> {
+    crate::ensure_admin(password)?;
+    if metrics.port == Some(0) {
+        return Err("El puerto de métricas no es válido".to_string());
+    }
+    let mut settings = load_settings();
+    settings.metrics = metrics;
+    save_settings(&settings)?;
+    start(app)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snapshot_renders_as_exposition_text() {
+        let text = render(&MetricsSnapshot { downloads_in_flight: 3, download_bytes_total: 1024, running_instances: 1, ..Default::default() });
+        assert!(text.contains("# TYPE drk_downloads_in_flight gauge\ndrk_downloads_in_flight 3\n"));
+        assert!(text.contains("# TYPE drk_download_bytes_total counter\ndrk_download_bytes_total 1024\n"));
+        assert!(text.contains("drk_prepare_queue_depth 0\n"));
+        assert!(text.contains("drk_running_instances 1\n"));
+
+        let now = Instant::now();
+        let recent = VecDeque::from([(now - Duration::from_secs(30), 1_000_000), (now - Duration::from_secs(2), 50_000), (now, 30_000)]);
+        assert_eq!(bytes_per_second(&recent, now), 8_000);
+    }
+}
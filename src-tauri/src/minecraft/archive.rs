@@ -0,0 +1,140 @@
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use super::modpack::{CORRUPT_MARKER, ENCRYPTED_MARKER};
+
+/// Pack archive formats, told apart by their magic bytes since creators rename files freely.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ArchiveFormat {
+    Zip,
+    SevenZip,
+    Rar,
+}
+
+const ZIP_MAGICS: [&[u8]; 3] = [b"PK\x03\x04", b"PK\x05\x06", b"PK\x07\x08"];
+const SEVEN_ZIP_MAGIC: &[u8] = b"7z\xBC\xAF\x27\x1C";
+// RAR 4 continues with 0x00 and RAR 5 with 0x01 0x00; the library reads both
+const RAR_MAGIC: &[u8] = b"Rar!\x1A\x07";
+
+pub fn format_of(head: &[u8]) -> Option<ArchiveFormat> {
+    if ZIP_MAGICS.iter().any(|magic| head.starts_with(magic)) {
+        Some(ArchiveFormat::Zip)
+    } else if head.starts_with(SEVEN_ZIP_MAGIC) {
+        Some(ArchiveFormat::SevenZip)
+    } else if head.starts_with(RAR_MAGIC) {
+        Some(ArchiveFormat::Rar)
+    } else {
+        None
+    }
+}
+
+pub fn detect_format(path: &Path) -> Option<ArchiveFormat> {
+    let mut head = Vec::with_capacity(8);
+    File::open(path).ok()?.take(8).read_to_end(&mut head).ok()?;
+    format_of(&head)
+}
+
+fn is_unsafe_name(name: &str) -> bool {
+    name.starts_with('/') || name.starts_with('\\') || name.contains(':') || name.split(['/', '\\']).any(|part| part == "..")
+}
+
+fn extraction_error(format: &str, e: String) -> String {
+    if e.to_lowercase().contains("password") || e.to_lowercase().contains("encrypt") {
+        format!("{} El modpack ({}) está protegido con contraseña. Pide al autor una versión sin cifrar.", ENCRYPTED_MARKER, format)
+    } else {
+        format!("{} No se pudo extraer el modpack ({}): {}. Vuelve a descargarlo.", CORRUPT_MARKER, format, e)
+    }
+}
+
+/// Extracts a 7z or rar pack into `dest`, skipping entries that would land outside it.
+/// Zip packs are streamed by the caller instead. Rar needs the `rar` feature.
+pub fn extract_to(path: &Path, format: ArchiveFormat, dest: &Path) -> Result<(), String> {
+    fs::create_dir_all(dest).map_err(|e| e.to_string())?;
+    match format {
+        ArchiveFormat::SevenZip => sevenz_rust::decompress_file_with_extract_fn(path, dest, |entry, reader, target| {
+            if is_unsafe_name(entry.name()) {
+                return Ok(true);
+            }
+            sevenz_rust::default_entry_extract_fn(entry, reader, target)
+        })
+        .map_err(|e| extraction_error("7z", e.to_string())),
+        #[cfg(feature = "rar")]
+        ArchiveFormat::Rar => {
+            let mut archive = unrar::Archive::new(path).open_for_processing().map_err(|e| extraction_error("rar", e.to_string()))?;
+            while let Some(header) = archive.read_header().map_err(|e| extraction_error("rar", e.to_string()))? {
+                let name = header.entry().filename.to_string_lossy().to_string();
+                archive = if header.entry().is_file() && !is_unsafe_name(&name) {
+                    header.extract_with_base(dest)
+                } else {
+                    header.skip()
+                }
+                .map_err(|e| extraction_error("rar", e.to_string()))?;
+            }
+            Ok(())
+        }
+        #[cfg(not(feature = "rar"))]
+        ArchiveFormat::Rar => Err("Esta versión del launcher no abre modpacks rar. Pide el modpack en zip o 7z.".to_string()),
+        ArchiveFormat::Zip => Err("Los zip se extraen sin pasar por una carpeta temporal".to_string()),
+    }
+}
+
+/// Every file under `dir` with its `/` separated name relative to `dir`, like zip entry names.
+pub fn walk_files(dir: &Path) -> Vec<(String, PathBuf)> {
+    let mut files = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(current) = pending.pop() {
+        let Ok(entries) = fs::read_dir(&current) else { continue };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            match entry.file_type() {
+                Ok(t) if t.is_dir() => pending.push(path),
+                Ok(t) if t.is_file() => {
+                    if let Ok(relative) = path.strip_prefix(dir) {
+                        let name = relative.components().map(|c| c.as_os_str().to_string_lossy()).collect::<Vec<_>>().join("/");
+                        files.push((name, path));
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+    files.sort();
+    files
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_are_detected_by_magic_bytes() {
+        assert_eq!(format_of(b"PK\x03\x04\x14\x00"), Some(ArchiveFormat::Zip));
+        assert_eq!(format_of(b"7z\xBC\xAF\x27\x1C\x00\x04"), Some(ArchiveFormat::SevenZip));
+        assert_eq!(format_of(b"Rar!\x1A\x07\x01\x00"), Some(ArchiveFormat::Rar));
+        assert_eq!(format_of(b"Rar!\x1A\x07\x00"), Some(ArchiveFormat::Rar));
+        assert_eq!(format_of(b"<!DOCTYPE html>"), None);
+
+        let dir = tempfile::tempdir().unwrap();
+        let renamed = dir.path().join("pack.zip");
+        fs::write(&renamed, b"7z\xBC\xAF\x27\x1Crest").unwrap();
+        assert_eq!(detect_format(&renamed), Some(ArchiveFormat::SevenZip));
+    }
+
+    #[test]
+    fn traversal_names_are_unsafe() {
+        assert!(is_unsafe_name("../evil.jar"));
+        assert!(is_unsafe_name("overrides\\..\\..\\evil.jar"));
+        assert!(is_unsafe_name("C:/Windows/evil.dll"));
+        assert!(!is_unsafe_name("overrides/config/a..b.toml"));
+    }
+
+    #[test]
+    fn walked_names_use_forward_slashes() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("overrides/config")).unwrap();
+        fs::write(dir.path().join("overrides/config/a.toml"), b"").unwrap();
+        fs::write(dir.path().join("manifest.json"), b"{}").unwrap();
+        let names = walk_files(dir.path()).into_iter().map(|(name, _)| name).collect::<Vec<_>>();
+        assert_eq!(names, vec!["manifest.json", "overrides/config/a.toml"]);
+    }
+}
@@ -0,0 +1,128 @@
+use serde::de::{self, DeserializeSeed, Deserializer, IgnoredAny, MapAccess, Visitor};
+use std::fmt;
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::Path;
+use super::models::AssetObject;
+
+// Sentinel error that unwinds the parser when the callback asks to stop
+const STOPPED: &str = "asset index walk stopped";
+
+/// Walks the `objects` map of an asset index one entry at a time, without building the whole
+/// map (the 1.20+ indexes with every language have ~90k objects). `on_object` returns false to
+/// stop early. Returns how many objects were visited.
+pub fn for_each_object(reader: impl Read, mut on_object: impl FnMut(&str, AssetObject) -> bool) -> Result<u64, String> {
+    let mut deserializer = serde_json::Deserializer::from_reader(reader);
+    let mut count = 0;
+    let result = IndexSeed { on_object: &mut on_object, count: &mut count }.deserialize(&mut deserializer);
+    match result {
+        Ok(()) => Ok(count),
+        Err(e) if e.to_string().starts_with(STOPPED) => Ok(count),
+        Err(e) => Err(format!("Invalid asset index: {}", e)),
+    }
+}
+
+pub fn for_each_object_in(path: &Path, on_object: impl FnMut(&str, AssetObject) -> bool) -> Result<u64, String> {
+    let file = File::open(path).map_err(|e| format!("Failed to read asset index: {}", e))?;
+    for_each_object(BufReader::new(file), on_object)
+}
+
+/// Number of objects in the index at `path`.
+pub fn count_objects(path: &Path) -> Result<u64, String> {
+    for_each_object_in(path, |_, _| true)
+}
+
+struct IndexSeed<'a, F> {
+    on_object: &'a mut F,
+    count: &'a mut u64,
+}
+
+impl<'de, F: FnMut(&str, AssetObject) -> bool> DeserializeSeed<'de> for IndexSeed<'_, F> {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_map(self)
+    }
+}
+
+impl<'de, F: FnMut(&str, AssetObject) -> bool> Visitor<'de> for IndexSeed<'_, F> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("an asset index")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<(), A::Error> {
+        while let Some(key) = map.next_key::<String>()? {
+            if key == "objects" {
+                map.next_value_seed(ObjectsSeed { on_object: &mut *self.on_object, count: &mut *self.count })?;
+            } else {
+                map.next_value::<IgnoredAny>()?;
+            }
+        }
+        Ok(())
+    }
+}
+
+struct ObjectsSeed<'a, F> {
+    on_object: &'a mut F,
+    count: &'a mut u64,
+}
+
+impl<'de, F: FnMut(&str, AssetObject) -> bool> DeserializeSeed<'de> for ObjectsSeed<'_, F> {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_map(self)
+    }
+}
+
+impl<'de, F: FnMut(&str, AssetObject) -> bool> Visitor<'de> for ObjectsSeed<'_, F> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a map of asset objects")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<(), A::Error> {
+        while let Some(name) = map.next_key::<String>()? {
+            let object: AssetObject = map.next_value()?;
+            *self.count += 1;
+            if !(self.on_object)(&name, object) {
+                return Err(de::Error::custom(STOPPED));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INDEX: &str = r#"{
+        "map_to_resources": false,
+        "objects": {
+            "icons/icon_16x16.png": {"hash": "bdf48ef6b5d0d23bbb02e17d04865216179f510a", "size": 3665},
+            "minecraft/lang/es_es.json": {"hash": "0a1b2c3d4e5f60718293a4b5c6d7e8f901234567", "size": 420}
+        },
+        "virtual": false
+    }"#;
+
+    #[test]
+    fn objects_are_visited_one_by_one() {
+        let mut seen = Vec::new();
+        let count = for_each_object(INDEX.as_bytes(), |name, object| {
+            seen.push((name.to_string(), object.size));
+            true
+        }).unwrap();
+        assert_eq!(count, 2);
+        assert_eq!(seen, vec![("icons/icon_16x16.png".to_string(), 3665), ("minecraft/lang/es_es.json".to_string(), 420)]);
+    }
+
+    #[test]
+    fn walks_stop_early_and_broken_indexes_fail() {
+        assert_eq!(for_each_object(INDEX.as_bytes(), |_, _| false), Ok(1));
+        assert!(for_each_object(r#"{"objects": {"a": {"hash": 1}}}"#.as_bytes(), |_, _| true).is_err());
+    }
+}
@@ -0,0 +1,177 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use schemars::JsonSchema;
+use serde::Serialize;
+use super::models::AssetIndex;
+
+/// Per-index view of the shared `assets/objects` store.
+#[derive(Serialize, Clone, Debug, JsonSchema)]
+pub struct AssetIndexStats {
+    pub id: String,
+    #[serde(rename = "objectCount")]
+    pub object_count: usize,
+    #[serde(rename = "sizeBytes")]
+    pub size_bytes: u64,
+    /// Objects the index lists that are not on disk
+    pub missing: usize,
+}
+
+#[derive(Serialize, Clone, Debug, JsonSchema)]
+pub struct AssetStats {
+    #[serde(rename = "objectCount")]
+    pub object_count: usize,
+    #[serde(rename = "totalBytes")]
+    pub total_bytes: u64,
+    pub indexes: Vec<AssetIndexStats>,
+    /// Objects referenced by more than one index
+    #[serde(rename = "sharedObjects")]
+    pub shared_objects: usize,
+    #[serde(rename = "unreferencedCount")]
+    pub unreferenced_count: usize,
+    #[serde(rename = "reclaimableBytes")]
+    pub reclaimable_bytes: u64,
+}
+
+#[derive(Serialize, Clone, Debug, JsonSchema)]
+pub struct PruneResult {
+    #[serde(rename = "removedCount")]
+    pub removed_count: usize,
+    #[serde(rename = "freedBytes")]
+    pub freed_bytes: u64,
+}
+
+/// Reads every `assets/indexes/*.json`. Fails on an unreadable index so pruning never deletes
+/// objects that index might still need.
+fn load_indexes(assets_dir: &Path) -> Result<Vec<(String, AssetIndex)>, String> {
+    let mut indexes = Vec::new();
+    let entries = match fs::read_dir(assets_dir.join("indexes")) {
+        Ok(entries) => entries,
+        Err(_) => return Ok(indexes),
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.extension().and_then(|e| e.to_str()) != Some("json") {
+            continue;
+        }
+        let id = path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+        let content = fs::read_to_string(&path).map_err(|e| format!("Failed to read asset index {}: {}", id, e))?;
+        let index: AssetIndex = serde_json::from_str(&content).map_err(|e| format!("Invalid asset index {}: {}", id, e))?;
+        indexes.push((id, index));
+    }
+    indexes.sort_by(|a, b| a.0.cmp(&b.0));
+    Ok(indexes)
+}
+
+/// Hash -> (path, size) for every file under `assets/objects/<xx>/`.
+fn scan_objects(assets_dir: &Path) -> HashMap<String, (PathBuf, u64)> {
+    let mut objects = HashMap::new();
+    let Ok(buckets) = fs::read_dir(assets_dir.join("objects")) else { return objects; };
+    for bucket in buckets.flatten().filter(|b| b.path().is_dir()) {
+        for file in fs::read_dir(bucket.path()).into_iter().flatten().flatten() {
+            let path = file.path();
+            if let (Some(name), Ok(meta)) = (path.file_name().map(|n| n.to_string_lossy().to_string()), file.metadata()) {
+                if meta.is_file() {
+                    objects.insert(name, (path, meta.len()));
+                }
+            }
+        }
+    }
+    objects
+}
+
+pub fn compute_asset_stats(assets_dir: &Path) -> Result<AssetStats, String> {
+    let indexes = load_indexes(assets_dir)?;
+    let objects = scan_objects(assets_dir);
+
+    let mut references: HashMap<&str, usize> = HashMap::new();
+    let mut index_stats = Vec::new();
+    for (id, index) in &indexes {
+        let hashes = index.objects.values().map(|o| o.hash.as_str()).collect::<HashSet<_>>();
+        let mut size_bytes: u64 = 0;
+        let mut missing = 0;
+        for hash in hashes.iter().copied() {
+            *references.entry(hash).or_insert(0) += 1;
+            match objects.get(hash) {
+                Some((_, size)) => size_bytes += size,
+                None => missing += 1,
+            }
+        }
+        index_stats.push(AssetIndexStats { id: id.clone(), object_count: hashes.len(), size_bytes, missing });
+    }
+
+    let unreferenced = objects.iter().filter(|(hash, _)| !references.contains_key(hash.as_str())).collect::<Vec<_>>();
+    Ok(AssetStats {
+        object_count: objects.len(),
+        total_bytes: objects.values().map(|(_, size)| size).sum(),
+        indexes: index_stats,
+        shared_objects: references.values().filter(|count| **count > 1).count(),
+        unreferenced_count: unreferenced.len(),
+        reclaimable_bytes: unreferenced.iter().map(|(_, (_, size))| size).sum(),
+    })
+}
+
+/// Objects no asset index references anymore, with their sizes.
+pub fn unreferenced_objects(assets_dir: &Path) -> Result<Vec<(PathBuf, u64)>, String> {
+    let indexes = load_indexes(assets_dir)?;
+    if indexes.is_empty() {
+        return Err("No asset indexes found; refusing to prune".to_string());
+    }
+    let referenced = indexes.iter()
+        .flat_map(|(_, index)| index.objects.values().map(|o| o.hash.clone()))
+        .collect::<HashSet<_>>();
+    Ok(scan_objects(assets_dir)
+        .into_iter()
+        .filter(|(hash, _)| !referenced.contains(hash))
+        .map(|(_, object)| object)
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_object(assets_dir: &Path, hash: &str, bytes: &[u8]) {
+        let dir = assets_dir.join("objects").join(&hash[..2]);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join(hash), bytes).unwrap();
+    }
+
+    fn write_index(assets_dir: &Path, id: &str, hashes: &[&str]) {
+        let objects = hashes.iter().enumerate()
+            .map(|(i, h)| (format!("minecraft/file{}", i), serde_json::json!({"hash": h, "size": 1})))
+            .collect::<serde_json::Map<_, _>>();
+        let dir = assets_dir.join("indexes");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join(format!("{}.json", id)), serde_json::json!({"objects": objects}).to_string()).unwrap();
+    }
+
+    #[test]
+    fn stats_and_prune_only_touch_unreferenced_objects() {
+        let dir = tempfile::tempdir().unwrap();
+        let assets = dir.path();
+        write_object(assets, "aa11", b"shared");
+        write_object(assets, "bb22", b"only-1.12");
+        write_object(assets, "cc33", b"orphan!");
+        write_index(assets, "1.12", &["aa11", "bb22"]);
+        write_index(assets, "5", &["aa11", "dd44"]);
+
+        let stats = compute_asset_stats(assets).unwrap();
+        assert_eq!(stats.object_count, 3);
+        assert_eq!(stats.shared_objects, 1);
+        assert_eq!(stats.unreferenced_count, 1);
+        assert_eq!(stats.reclaimable_bytes, 7);
+        assert_eq!(stats.indexes[1].id, "5");
+        assert_eq!(stats.indexes[1].missing, 1);
+
+        let unreferenced = unreferenced_objects(assets).unwrap();
+        assert_eq!(unreferenced, vec![(assets.join("objects").join("cc").join("cc33"), 7)]);
+    }
+
+    #[test]
+    fn prune_refuses_without_indexes() {
+        let dir = tempfile::tempdir().unwrap();
+        write_object(dir.path(), "aa11", b"x");
+        assert!(unreferenced_objects(dir.path()).is_err());
+    }
+}
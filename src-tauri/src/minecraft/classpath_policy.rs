@@ -0,0 +1,365 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use super::launch_logic::parse_mc_version;
+use super::models::VersionInfo;
+use super::utils::{check_rules, get_os_name, maven_path, parse_maven_name, MavenName};
+
+/// How a Forge generation expects its jars on the JVM command line.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ForgeLayout {
+    /// 1.12 and older: everything on the classpath, LaunchWrapper from the profile
+    LaunchWrapper,
+    /// 1.13–1.16: everything on the classpath, ModLauncher from the profile
+    ModLauncher,
+    /// 1.17–1.20.4: BootstrapLauncher jars on the module path and their dependencies kept off
+    /// the classpath
+    BootstrapLauncher,
+    /// 1.20.6+: ForgeBootstrap builds the module layer itself from the classpath
+    ForgeBootstrap,
+}
+
+// First Forge major of each layout
+const LAYOUTS: [(u32, ForgeLayout); 4] = [
+    (0, ForgeLayout::LaunchWrapper),
+    (25, ForgeLayout::ModLauncher),
+    (37, ForgeLayout::BootstrapLauncher),
+    (50, ForgeLayout::ForgeBootstrap),
+];
+
+// Module path jars required by BootstrapLauncher
+const BOOTSTRAP_MODULE_ARTIFACTS: [&str; 3] = ["securejarhandler", "modlauncher", "bootstraplauncher"];
+
+// These libraries MUST NOT be in the classpath because BootstrapLauncher handles them through the module path
+const BOOTSTRAP_CLASSPATH_BLACKLIST: [&str; 12] = [
+    "asm", "asm-commons", "asm-tree", "asm-util", "asm-analysis",
+    "java-objc-bridge", "jna", "oshi-core",
+    "sponge-mixin", "mixin", "jakarta.activation", "jakarta.xml.bind"
+];
+
+// Kept on the classpath even when the profile doesn't list them, so the main class resolves
+const BOOTSTRAP_FORCED_CLASSPATH: [(&str, &str); 2] = [("cpw.mods", "bootstraplauncher"), ("net.minecraftforge", "fmlloader")];
+
+pub fn normalize_path_for_comparison(p: &Path) -> String {
+    let s = p.to_string_lossy().replace('\\', "/");
+    if get_os_name() == "windows" { s.to_lowercase() } else { s }
+}
+
+/// Normalized library path -> maven coordinate, for both the computed maven path and the declared artifact path.
+pub fn build_library_map(info: &VersionInfo, libraries_dir: &Path) -> HashMap<String, MavenName> {
+    let mut library_map = HashMap::new();
+    for lib in &info.libraries {
+        if let Some(maven) = parse_maven_name(&lib.name) {
+            if let Some(path_str) = maven_path(&maven) {
+                library_map.insert(normalize_path_for_comparison(&libraries_dir.join(path_str)), maven.clone());
+            }
+            if let Some(path_str) = lib.downloads.as_ref().and_then(|d| d.artifact.as_ref()).and_then(|a| a.path.as_ref()) {
+                library_map.insert(normalize_path_for_comparison(&libraries_dir.join(path_str)), maven.clone());
+            }
+        }
+    }
+    library_map
+}
+
+/// `47` from `1.20.1-forge-47.2.0` (or the `forge`/`fmlloader` library when the id is custom).
+pub fn forge_major(info: &VersionInfo) -> Option<u32> {
+    let mc_version = info.inherits_from.as_deref().unwrap_or("");
+    let from_id = info.id.rsplit_once("forge").map(|(_, rest)| rest.trim_start_matches('-').to_string());
+    let from_library = || info.libraries.iter()
+        .filter_map(|lib| parse_maven_name(&lib.name))
+        .find(|m| m.group == "net.minecraftforge" && (m.artifact == "forge" || m.artifact == "fmlloader"))
+        .map(|m| m.version);
+    let build = from_id.filter(|b| b.starts_with(|c: char| c.is_ascii_digit())).or_else(from_library)?;
+    let build = build.strip_prefix(&format!("{}-", mc_version)).unwrap_or(&build);
+    // Old ids repeat the game version: `1.12.2-forge1.12.2-14.23.5.2847`
+    let build = build.rsplit_once('-').filter(|(head, _)| head.starts_with("1.")).map(|(_, b)| b).unwrap_or(build);
+    build.split('.').next()?.parse().ok()
+}
+
+/// Forge major of the game version's generation, for profiles whose build can't be read.
+fn major_for_mc_version(mc_version: &str) -> u32 {
+    match parse_mc_version(mc_version) {
+        Some((minor, _)) if minor <= 12 => 14,
+        Some((minor, _)) if minor <= 16 => 36,
+        Some((minor, patch)) if minor <= 19 || (minor == 20 && patch <= 4) => 37,
+        _ => 50,
+    }
+}
+
+/// Which jars go on the module path and which on the classpath for one Forge profile.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ClasspathPolicy {
+    pub layout: ForgeLayout,
+}
+
+impl ClasspathPolicy {
+    pub fn for_version(info: &VersionInfo) -> ClasspathPolicy {
+        let major = forge_major(info).unwrap_or_else(|| major_for_mc_version(info.inherits_from.as_deref().unwrap_or(&info.id)));
+        ClasspathPolicy::for_major(major)
+    }
+
+    pub fn for_major(major: u32) -> ClasspathPolicy {
+        let layout = LAYOUTS.iter().rev().find(|(first, _)| major >= *first).map(|(_, l)| *l).unwrap_or(ForgeLayout::LaunchWrapper);
+        ClasspathPolicy { layout }
+    }
+
+    fn module_artifacts(&self) -> &'static [&'static str] {
+        match self.layout {
+            ForgeLayout::BootstrapLauncher => &BOOTSTRAP_MODULE_ARTIFACTS,
+            _ => &[],
+        }
+    }
+
+    fn blacklist(&self) -> &'static [&'static str] {
+        match self.layout {
+            ForgeLayout::BootstrapLauncher => &BOOTSTRAP_CLASSPATH_BLACKLIST,
+            _ => &[],
+        }
+    }
+
+    /// Coordinates appended to the classpath when missing from it.
+    pub fn forced_classpath(&self) -> &'static [(&'static str, &'static str)] {
+        match self.layout {
+            ForgeLayout::BootstrapLauncher => &BOOTSTRAP_FORCED_CLASSPATH,
+            _ => &[],
+        }
+    }
+
+    /// Module path jars of the profile. Sorted so args.txt is stable between launches.
+    pub fn module_path(&self, library_map: &HashMap<String, MavenName>, libraries_dir: &Path) -> Vec<PathBuf> {
+        let artifacts = self.module_artifacts();
+        let mut module_path = library_map.values()
+            .filter(|m| m.group == "cpw.mods" && artifacts.contains(&m.artifact.as_str()))
+            .filter_map(maven_path)
+            .map(|rel| libraries_dir.join(rel))
+            .collect::<Vec<_>>();
+        module_path.sort();
+        module_path.dedup();
+        module_path
+    }
+
+    pub fn allows(
+        &self,
+        entry: &Path,
+        library_map: &HashMap<String, MavenName>,
+        module_path_libs: &HashSet<String>,
+        module_path_artifacts: &HashSet<(String, String)>,
+    ) -> bool {
+        let normalized = normalize_path_for_comparison(entry);
+        let file_name = entry.file_name().and_then(|n| n.to_str()).unwrap_or("").to_lowercase();
+        let maven = library_map.get(&normalized);
+
+        // Always include bootstraplauncher in classpath to ensure main class is resolvable
+        let is_bootstrap = file_name.contains("bootstraplauncher")
+            || maven.map(|m| m.group == "cpw.mods" && m.artifact == "bootstraplauncher").unwrap_or(false);
+        if is_bootstrap {
+            return true;
+        }
+        let is_on_module_path = module_path_libs.contains(&normalized);
+        let is_artifact_conflict = maven
+            .map(|m| module_path_artifacts.contains(&(m.group.clone(), m.artifact.clone())))
+            .unwrap_or(false);
+        let is_blacklisted = self.blacklist().iter().any(|b| file_name.contains(b));
+        !is_on_module_path && !is_artifact_conflict && !is_blacklisted
+    }
+
+    /// Client jar + non-native libraries in profile order, minus anything the layout keeps off
+    /// the classpath, plus the forced jars the profile declares. Doesn't look at the disk.
+    pub fn classpath(
+        &self,
+        info: &VersionInfo,
+        client_jar: Option<PathBuf>,
+        libraries_dir: &Path,
+        library_map: &HashMap<String, MavenName>,
+        module_path: &[PathBuf],
+    ) -> Vec<PathBuf> {
+        let mut entries: Vec<PathBuf> = client_jar.into_iter().collect();
+        for lib in &info.libraries {
+            if !check_rules(&lib.rules) { continue; }
+            if let Some(downloads) = &lib.downloads {
+                if let Some(path_str) = downloads.artifact.as_ref().and_then(|a| a.path.as_ref()) {
+                    entries.push(libraries_dir.join(path_str));
+                }
+            } else if let Some(maven) = parse_maven_name(&lib.name) {
+                let is_native = maven.classifier.as_deref().map(|c| c.contains("natives")).unwrap_or(false);
+                if let Some(path_str) = maven_path(&maven).filter(|_| !is_native) {
+                    entries.push(libraries_dir.join(path_str));
+                }
+            }
+        }
+
+        let module_path_libs = module_path.iter().map(|p| normalize_path_for_comparison(p)).collect::<HashSet<_>>();
+        let module_path_artifacts = module_path_libs.iter()
+            .filter_map(|p| library_map.get(p))
+            .map(|m| (m.group.clone(), m.artifact.clone()))
+            .collect::<HashSet<_>>();
+
+        let mut seen = HashSet::new();
+        let mut classpath = Vec::new();
+        for entry in entries {
+            if seen.insert(normalize_path_for_comparison(&entry))
+                && self.allows(&entry, library_map, &module_path_libs, &module_path_artifacts) {
+                classpath.push(entry);
+            }
+        }
+        for (group, artifact) in self.forced_classpath() {
+            let declared = library_map.values()
+                .find(|m| m.group == *group && m.artifact == *artifact)
+                .and_then(maven_path)
+                .map(|rel| libraries_dir.join(rel));
+            if let Some(target) = declared.filter(|t| seen.insert(normalize_path_for_comparison(t))) {
+                classpath.push(target);
+            }
+        }
+        classpath
+    }
+
+    /// The profile's main class, except under BootstrapLauncher where installs that only ship
+    /// fmlloader start through ForgeBootstrap.
+    pub fn main_class(&self, info: &VersionInfo, bootstrap: Option<&Path>, fmlloader: Option<&Path>) -> String {
+        match self.layout {
+            ForgeLayout::BootstrapLauncher => {
+                let bootstrap_exists = bootstrap.map(|p| p.exists()).unwrap_or(false);
+                let fmlloader_exists = fmlloader.map(|p| p.exists()).unwrap_or(false);
+                if !bootstrap_exists && fmlloader_exists {
+                    "net.minecraftforge.bootstrap.ForgeBootstrap".to_string()
+                } else {
+                    "cpw.mods.bootstraplauncher.BootstrapLauncher".to_string()
+                }
+            }
+            _ => info.main_class.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Main class, module path and classpath of one launch, library paths relative to the data root
+    /// and the game jar as `client.jar`.
+    #[derive(Debug, PartialEq)]
+    struct CommandLine {
+        main: String,
+        module_path: Vec<String>,
+        classpath: Vec<String>,
+    }
+
+    /// The golden files in `testdata/classpath` hold Prism Launcher's command line for the profile
+    /// next to them: `-p` exactly as the profile's jvm arguments give it, `-cp` with every
+    /// non-native library in profile order and the game jar last. Prism starts Forge 1.13+ through
+    /// ForgeWrapper, which hands over to the profile's main class; its own jar isn't listed.
+    fn prism(golden: &str) -> CommandLine {
+        let mut prism = CommandLine { main: String::new(), module_path: Vec::new(), classpath: Vec::new() };
+        let mut section = "";
+        for line in golden.lines().filter(|l| !l.starts_with('#')) {
+            match (line.strip_prefix("main "), line.strip_prefix("  ")) {
+                (Some(main), _) => prism.main = main.to_string(),
+                (_, Some(path)) if section == "module-path" => prism.module_path.push(path.to_string()),
+                (_, Some(path)) if section == "classpath" => prism.classpath.push(path.to_string()),
+                _ => section = line,
+            }
+        }
+        prism
+    }
+
+    fn launcher(profile: &str) -> CommandLine {
+        let info: VersionInfo = serde_json::from_str(profile).unwrap();
+        let libraries_dir = Path::new("libraries");
+        let policy = ClasspathPolicy::for_version(&info);
+        let library_map = build_library_map(&info, libraries_dir);
+        let module_path = policy.module_path(&library_map, libraries_dir);
+        let classpath = policy.classpath(&info, Some(PathBuf::from("client.jar")), libraries_dir, &library_map, &module_path);
+        let lines = |paths: &[PathBuf]| paths.iter().map(|p| p.to_string_lossy().replace('\\', "/")).collect();
+        CommandLine { main: policy.main_class(&info, None, None), module_path: lines(&module_path), classpath: lines(&classpath) }
+    }
+
+    /// Where the launcher's command line differs from Prism's on purpose, by jar file name.
+    struct Differences {
+        /// Prism's `-cp` jars left off: BOOTSTRAP_CLASSPATH_BLACKLIST (matched by file name) and the
+        /// module path jars, where Prism relies on BootstrapLauncher's ignoreList instead
+        off_classpath: &'static [&'static str],
+        /// Jars of the profile's `-p` the launcher keeps off its module path
+        off_module_path: &'static [&'static str],
+        /// BOOTSTRAP_MODULE_ARTIFACTS jars the profile's `-p` doesn't have
+        extra_module_path: &'static [&'static str],
+    }
+
+    const SAME: Differences = Differences { off_classpath: &[], off_module_path: &[], extra_module_path: &[] };
+
+    #[test]
+    fn layouts_match_prism_but_for_the_listed_differences() {
+        for (name, profile, golden, differences) in [
+            ("1.12.2", include_str!("testdata/classpath/forge-1.12.2.json"), include_str!("testdata/classpath/forge-1.12.2.golden"), SAME),
+            ("1.16.5", include_str!("testdata/classpath/forge-1.16.5.json"), include_str!("testdata/classpath/forge-1.16.5.golden"), SAME),
+            ("1.18.2", include_str!("testdata/classpath/forge-1.18.2.json"), include_str!("testdata/classpath/forge-1.18.2.golden"), Differences {
+                off_classpath: &["securejarhandler-1.0.8.jar", "asm-9.5.jar", "asm-commons-9.5.jar", "modlauncher-9.1.3.jar", "mixin-0.8.5.jar", "jna-5.10.0.jar", "oshi-core-5.8.5.jar"],
+                off_module_path: &["asm-commons-9.5.jar", "asm-util-9.5.jar", "asm-analysis-9.5.jar", "asm-tree-9.5.jar", "asm-9.5.jar"],
+                extra_module_path: &["modlauncher-9.1.3.jar"],
+            }),
+            ("1.20.1", include_str!("testdata/classpath/forge-1.20.1.json"), include_str!("testdata/classpath/forge-1.20.1.golden"), Differences {
+                off_classpath: &["securejarhandler-2.1.10.jar", "asm-9.5.jar", "asm-analysis-9.5.jar", "modlauncher-10.0.9.jar", "mixin-0.8.5.jar", "jna-platform-5.12.1.jar"],
+                off_module_path: &["asm-commons-9.5.jar", "asm-util-9.5.jar", "asm-analysis-9.5.jar", "asm-tree-9.5.jar", "asm-9.5.jar", "JarJarFileSystems-0.3.19.jar"],
+                extra_module_path: &["modlauncher-10.0.9.jar"],
+            }),
+            ("1.21.1", include_str!("testdata/classpath/forge-1.21.1.json"), include_str!("testdata/classpath/forge-1.21.1.golden"), SAME),
+        ] {
+            let prism = prism(golden);
+            let file_name = |path: &String| path.rsplit('/').next().unwrap().to_string();
+            let listed = |names: &[&str], paths: &[String]| names.iter()
+                .map(|jar| paths.iter().find(|p| file_name(p) == *jar).unwrap_or_else(|| panic!("forge {}: {} no está en Prism", name, jar)).clone())
+                .collect::<Vec<_>>();
+            let off_classpath = listed(differences.off_classpath, &prism.classpath);
+            let off_module_path = listed(differences.off_module_path, &prism.module_path);
+
+            // Prism appends the game jar; here it goes first
+            let classpath = std::iter::once("client.jar".to_string())
+                .chain(prism.classpath.iter().filter(|p| *p != "client.jar" && !off_classpath.contains(p)).cloned())
+                .collect();
+            let mut module_path: Vec<String> = prism.module_path.iter()
+                .filter(|p| !off_module_path.contains(p))
+                .cloned()
+                .chain(listed(differences.extra_module_path, &prism.classpath))
+                .collect();
+            module_path.sort();
+            assert_eq!(launcher(profile), CommandLine { main: prism.main, module_path, classpath }, "forge {}", name);
+        }
+    }
+
+    #[test]
+    fn bootstrap_layout_excludes_module_path_and_blacklist_but_keeps_bootstrap() {
+        let policy = ClasspathPolicy::for_major(47);
+        let library_map = HashMap::new();
+        let module_jar = PathBuf::from("/libs/cpw/mods/modlauncher/10.0/modlauncher-10.0.jar");
+        let module_path_libs = [normalize_path_for_comparison(&module_jar)].into_iter().collect::<HashSet<_>>();
+        let artifacts = HashSet::new();
+
+        assert!(!policy.allows(&module_jar, &library_map, &module_path_libs, &artifacts));
+        assert!(!policy.allows(Path::new("/libs/org/ow2/asm/asm-tree-9.5.jar"), &library_map, &module_path_libs, &artifacts));
+        assert!(policy.allows(Path::new("/libs/cpw/mods/bootstraplauncher-1.1.2.jar"), &library_map, &module_path_libs, &artifacts));
+        assert!(policy.allows(Path::new("/libs/com/google/guava/guava-31.1.jar"), &library_map, &module_path_libs, &artifacts));
+        // ModLauncher needs ASM next to it on the classpath
+        assert!(ClasspathPolicy::for_major(36).allows(Path::new("/libs/org/ow2/asm/asm-tree-9.5.jar"), &library_map, &HashSet::new(), &artifacts));
+
+        let info: VersionInfo = serde_json::from_str(include_str!("testdata/classpath/forge-1.20.1.json")).unwrap();
+        assert_eq!(
+            policy.main_class(&info, Some(Path::new("/nonexistent/bootstraplauncher.jar")), Some(Path::new("/nonexistent/fmlloader.jar"))),
+            "cpw.mods.bootstraplauncher.BootstrapLauncher"
+        );
+    }
+
+    #[test]
+    fn forge_major_is_read_from_id_or_libraries() {
+        let profile = |id: &str, libraries: &str| -> VersionInfo {
+            serde_json::from_str(&format!(r#"{{"id": "{}", "inheritsFrom": "1.12.2", "type": "release", "mainClass": "x", "libraries": {}}}"#, id, libraries)).unwrap()
+        };
+        assert_eq!(forge_major(&profile("1.12.2-forge-14.23.5.2860", "[]")), Some(14));
+        assert_eq!(forge_major(&profile("1.12.2-forge1.12.2-14.23.5.2847", "[]")), Some(14));
+        assert_eq!(forge_major(&profile("evento", r#"[{"name": "net.minecraftforge:forge:1.12.2-14.23.5.2860"}]"#)), Some(14));
+        assert_eq!(forge_major(&profile("evento", "[]")), None);
+        assert_eq!(ClasspathPolicy::for_version(&profile("evento", "[]")).layout, ForgeLayout::LaunchWrapper);
+        assert_eq!(ClasspathPolicy::for_major(36).layout, ForgeLayout::ModLauncher);
+        assert_eq!(ClasspathPolicy::for_major(37).layout, ForgeLayout::BootstrapLauncher);
+        assert_eq!(ClasspathPolicy::for_major(49).layout, ForgeLayout::BootstrapLauncher);
+        assert_eq!(ClasspathPolicy::for_major(52).layout, ForgeLayout::ForgeBootstrap);
+    }
+}
@@ -0,0 +1,243 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs;
+use std::path::Path;
+
+/// One value staff set in a mod's config after every sync, so an event-specific setting (the
+/// server IP of a menu mod, say) doesn't need a repacked modpack.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ConfigPatch {
+    /// Relative to the game folder, e.g. `config/menu.toml`; `.json` or `.toml`
+    pub file: String,
+    /// JSON pointer for JSON files (`/server/ip`), dotted key for TOML (`server.ip`)
+    pub key: String,
+    /// String, number, boolean or a list of those
+    pub value: Value,
+}
+
+#[derive(Debug, PartialEq)]
+enum Format {
+    Json,
+    Toml,
+}
+
+fn format_of(file: &str) -> Result<Format, String> {
+    let lower = file.trim().to_ascii_lowercase();
+    if lower.ends_with(".json") {
+        Ok(Format::Json)
+    } else if lower.ends_with(".toml") {
+        Ok(Format::Toml)
+    } else {
+        Err(format!("Solo se pueden parchear archivos .json o .toml: {}", file))
+    }
+}
+
+/// `/a/b~1c` as `["a", "b/c"]` (RFC 6901 escapes).
+fn pointer_tokens(pointer: &str) -> Result<Vec<String>, String> {
+    let Some(rest) = pointer.strip_prefix('/') else {
+        return Err(format!("Puntero JSON no válido (debe empezar por /): {}", pointer));
+    };
+    Ok(rest.split('/').map(|t| t.replace("~1", "/").replace("~0", "~")).collect())
+}
+
+/// Sets `pointer` in `doc`, creating the objects missing on the way. Array items must exist.
+fn set_json(doc: &mut Value, pointer: &str, value: Value) -> Result<(), String> {
+    let tokens = pointer_tokens(pointer)?;
+    let mut current = doc;
+    for token in &tokens {
+        if current.is_null() {
+            *current = Value::Object(Default::default());
+        }
+        current = match current {
+            Value::Object(map) => map.entry(token.clone()).or_insert(Value::Null),
+            Value::Array(items) => token.parse::<usize>().ok()
+                .and_then(|i| items.get_mut(i))
+                .ok_or_else(|| format!("{} no existe en la lista", pointer))?,
+            _ => return Err(format!("{} atraviesa un valor que no es un objeto", pointer)),
+        };
+    }
+    *current = value;
+    Ok(())
+}
+
+fn toml_value(value: &Value) -> Result<toml_edit::Value, String> {
+    Ok(match value {
+        Value::String(s) => s.as_str().into(),
+        Value::Bool(b) => (*b).into(),
+        Value::Number(n) => match n.as_i64() {
+            Some(i) => i.into(),
+            None => n.as_f64().ok_or("Número no válido")?.into(),
+        },
+        Value::Array(items) => {
+            let mut array = toml_edit::Array::new();
+            for item in items {
+                array.push(toml_value(item)?);
+            }
+            array.into()
+        }
+        Value::Null | Value::Object(_) => return Err("Solo se admiten textos, números, booleanos y listas".to_string()),
+    })
+}
+
+/// Sets the dotted `key` in `doc`, creating missing tables; the comment around a replaced
+/// value stays, which matters for Forge configs documenting every option.
+fn set_toml(doc: &mut toml_edit::DocumentMut, key: &str, value: &Value) -> Result<(), String> {
+    let parts: Vec<&str> = key.split('.').map(str::trim).collect();
+    let Some((last, parents)) = parts.split_last().filter(|_| parts.iter().all(|p| !p.is_empty())) else {
+        return Err(format!("Clave TOML no válida: {}", key));
+    };
+    let mut table: &mut dyn toml_edit::TableLike = doc.as_table_mut();
+    for part in parents {
+        table = table.entry(part).or_insert(toml_edit::table())
+            .as_table_like_mut()
+            .ok_or_else(|| format!("{} atraviesa un valor que no es una tabla", key))?;
+    }
+    let mut new = toml_value(value)?;
+    match table.get_mut(last).and_then(|item| item.as_value_mut()) {
+        Some(existing) => {
+            *new.decor_mut() = existing.decor().clone();
+            *existing = new;
+        }
+        None => {
+            table.insert(last, toml_edit::Item::Value(new));
+        }
+    }
+    Ok(())
+}
+
+/// `text` with the patch applied. An empty file counts as an empty document.
+fn patched(text: &str, patch: &ConfigPatch) -> Result<String, String> {
+    match format_of(&patch.file)? {
+        Format::Json => {
+            let mut doc: Value = if text.trim().is_empty() {
+                Value::Object(Default::default())
+            } else {
+                serde_json::from_str(text).map_err(|e| format!("{} no es JSON válido: {}", patch.file, e))?
+            };
+            set_json(&mut doc, &patch.key, patch.value.clone())?;
+            serde_json::to_string_pretty(&doc).map_err(|e| e.to_string())
+        }
+        Format::Toml => {
+            let mut doc: toml_edit::DocumentMut = text.parse()
+                .map_err(|e| format!("{} no es TOML válido: {}", patch.file, e))?;
+            set_toml(&mut doc, &patch.key, &patch.value)?;
+            Ok(doc.to_string())
+        }
+    }
+}
+
+/// Refuses patches that could never apply, so they're caught when saved and not mid-sync.
+pub fn validate(patches: &[ConfigPatch]) -> Result<(), String> {
+    for patch in patches {
+        patched("", patch)?;
+    }
+    Ok(())
+}
+
+/// Applies `patches` in order inside `minecraft_dir`. A config the game hasn't generated yet is
+/// created with just the patched keys; mods fill in the rest on first start. Returns how many
+/// files changed.
+pub fn apply(minecraft_dir: &Path, patches: &[ConfigPatch]) -> Result<usize, String> {
+    let mut changed = 0;
+    for patch in patches {
+        let path = crate::path_policy::join_inside(minecraft_dir, &patch.file)?;
+        let text = if path.exists() {
+            fs::read_to_string(&path).map_err(|e| format!("{}: {}", path.display(), e))?
+        } else {
+            String::new()
+        };
+        let new = patched(&text, patch)?;
+        if new == text {
+            continue;
+        }
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        fs::write(&path, new).map_err(|e| format!("{}: {}", path.display(), e))?;
+        changed += 1;
+    }
+    Ok(changed)
+}
+
+/// The patches saved on `instance_id`, if any.
+pub fn for_instance(instance_id: &str) -> Vec<ConfigPatch> {
+    crate::load_instances().into_iter()
+        .find(|i| i.id == instance_id)
+        .and_then(|i| i.config_patches)
+        .unwrap_or_default()
+}
+
+#[tauri::command]
+pub fn get_instance_config_patches(password: String, instance_id: String) -> Result<Vec<ConfigPatch>, String> {
+    crate::ensure_admin(password)?;
+    let instance = crate::load_instances().into_iter().find(|i| i.id == instance_id)
+        .ok_or("Instance not found")?;
+    Ok(instance.config_patches.unwrap_or_default())
+}
+
+/// Replaces the patches of an instance; they apply from its next sync.
+#[tauri::command]
+pub fn set_instance_config_patches(password: String, instance_id: String, patches: Vec<ConfigPatch>) -> Result<(), String> {
+    crate::ensure_admin(password)?;
+    validate(&patches)?;
+    let mut instances = crate::load_instances();
+    let instance = instances.iter_mut().find(|i| i.id == instance_id)
+        .ok_or("Instance not found")?;
+    instance.config_patches = Some(patches).filter(|p| !p.is_empty());
+    crate::save_instances(&instances);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn patch(file: &str, key: &str, value: Value) -> ConfigPatch {
+        ConfigPatch { file: file.to_string(), key: key.to_string(), value }
+    }
+
+    #[test]
+    fn toml_edits_keep_comments() {
+        let text = "# Menú principal\n[server]\n\t# IP del botón \"Jugar\"\n\tip = \"viejo.example\" # por defecto\n\tport = 25565\n";
+        let new = patched(text, &patch("config/menu.toml", "server.ip", json!("evento.example"))).unwrap();
+        assert_eq!(new, "# Menú principal\n[server]\n\t# IP del botón \"Jugar\"\n\tip = \"evento.example\" # por defecto\n\tport = 25565\n");
+
+        let new = patched("", &patch("config/menu.toml", "server.extra.ports", json!([25565, 25566]))).unwrap();
+        let doc: toml_edit::DocumentMut = new.parse().unwrap();
+        assert_eq!(doc["server"]["extra"]["ports"].as_array().map(|a| a.len()), Some(2));
+        assert!(patched("ip = 1", &patch("a.toml", "ip.port", json!(1))).is_err());
+        assert!(patched("", &patch("a.toml", "server..ip", json!(1))).is_err());
+    }
+
+    #[test]
+    fn json_pointers_create_missing_objects() {
+        let new = patched(r#"{"servers": [{"ip": "viejo"}], "a/b": {}}"#, &patch("x.json", "/servers/0/ip", json!("nuevo"))).unwrap();
+        let doc: Value = serde_json::from_str(&new).unwrap();
+        assert_eq!(doc["servers"][0]["ip"], "nuevo");
+
+        let new = patched(r#"{"a/b": {}}"#, &patch("x.json", "/a~1b/c/d", json!(true))).unwrap();
+        assert_eq!(serde_json::from_str::<Value>(&new).unwrap()["a/b"]["c"]["d"], true);
+        assert!(patched("{}", &patch("x.json", "sin-barra", json!(1))).is_err());
+        assert!(patched(r#"{"l": []}"#, &patch("x.json", "/l/3", json!(1))).is_err());
+        assert!(validate(&[patch("opciones.txt", "a", json!(1))]).is_err());
+    }
+
+    #[test]
+    fn patches_apply_inside_the_game_folder_only() {
+        let dir = tempfile::tempdir().unwrap();
+        let minecraft = dir.path().join("minecraft");
+        fs::create_dir_all(&minecraft).unwrap();
+        let patches = vec![
+            patch("config/menu.toml", "server.ip", json!("evento.example")),
+            patch("config/evento.json", "/dia", json!(2)),
+        ];
+        assert_eq!(apply(&minecraft, &patches).unwrap(), 2);
+        // Already applied: nothing to rewrite
+        assert_eq!(apply(&minecraft, &patches).unwrap(), 0);
+        assert!(fs::read_to_string(minecraft.join("config").join("menu.toml")).unwrap().contains("evento.example"));
+        assert!(apply(&minecraft, &[patch("../fuera.json", "/a", json!(1))]).is_err());
+        assert!(!dir.path().join("fuera.json").exists());
+    }
+}
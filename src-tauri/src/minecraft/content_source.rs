@@ -0,0 +1,450 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use url::Url;
+use super::downloader::{download_file, download_pack};
+use super::{curseforge, modrinth};
+use super::url_resolver::resolve_download_url;
+use crate::temp_files::{commit, tmp_path};
+
+const SEARCH_LIMIT: usize = 20;
+const SIZE_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ContentKind {
+    Mod,
+    Modpack,
+    ResourcePack,
+}
+
+/// The game a reference is resolved for. Empty fields match any loader or version.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Target {
+    pub loader: String,
+    pub mc_version: String,
+}
+
+impl Target {
+    pub fn of(instance: &crate::Instance) -> Self {
+        Target {
+            loader: instance.modloader.clone().filter(|l| l != "vanilla").unwrap_or_default(),
+            mc_version: instance.version.clone(),
+        }
+    }
+
+    pub fn for_instance(instance_id: &str) -> Self {
+        crate::load_instances().iter().find(|i| i.id == instance_id).map(Target::of).unwrap_or_default()
+    }
+}
+
+/// A project found by `search`.
+#[derive(Serialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ContentHit {
+    pub source: String,
+    /// What goes in the instance's mod list or modpack field, e.g. `modrinth:sodium`
+    pub reference: String,
+    pub title: String,
+    pub description: String,
+    #[serde(rename = "iconUrl", skip_serializing_if = "Option::is_none")]
+    pub icon_url: Option<String>,
+    pub downloads: u64,
+}
+
+/// Where a reference's file is right now.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ResolvedContent {
+    /// `ContentSource::name` of the source that resolved it
+    pub source: &'static str,
+    /// Direct download URL, or a path for local files
+    pub url: String,
+    pub file_name: String,
+    pub sha1: Option<String>,
+}
+
+/// Somewhere mods and packs come from. An instance's mod list and modpack field hold references
+/// (`modrinth:sodium`, `curseforge:238222/4712345`, `admin:mods/x.jar`, a local path or a plain
+/// URL) and sync and install go through the source that handles each one. Add a source by
+/// implementing this and listing the type in `sources()`.
+pub trait ContentSource {
+    fn name(&self) -> &'static str;
+    fn handles(&self, reference: &str) -> bool;
+
+    /// Projects matching `query`; sources that can't be searched find nothing.
+    fn search(&self, _query: &str, _kind: ContentKind, _target: &Target) -> Result<Vec<ContentHit>, String> {
+        Ok(Vec::new())
+    }
+
+    fn resolve(&self, reference: &str, target: &Target) -> Result<ResolvedContent, String>;
+
+    /// Size of the file, which is how pack updates are noticed; `None` when the host doesn't say.
+    fn size(&self, resolved: &ResolvedContent) -> Result<Option<u64>, String> {
+        let client = reqwest::blocking::Client::builder()
+            .user_agent(crate::http::user_agent())
+            .timeout(SIZE_TIMEOUT)
+            .build()
+            .map_err(|e| e.to_string())?;
+        let resp = crate::http::send(&client, client.head(&resolved.url))?;
+        if !resp.status().is_success() {
+            return Err(format!("{} returned status: {}", resolved.file_name, resp.status()));
+        }
+        Ok(resp.content_length())
+    }
+
+    /// Puts the file at `dest`. Packs go through `download_pack` for the host pin check; returns
+    /// its warning.
+    fn download(&self, resolved: &ResolvedContent, dest: &Path, kind: ContentKind) -> Result<Option<String>, String> {
+        match kind {
+            ContentKind::Mod => {
+                download_file(&resolved.url, dest, resolved.sha1.as_deref())?;
+                // `download_file` records hashed downloads; launches check the jar against this record
+                if resolved.sha1.is_none() {
+                    crate::content_index::record(dest, &super::downloader::file_sha1(dest)?);
+                }
+                Ok(None)
+            }
+            ContentKind::Modpack | ContentKind::ResourcePack => download_pack(&resolved.url, dest),
+        }
+    }
+}
+
+/// `value` of a `scheme:value` reference.
+fn strip_scheme<'a>(reference: &'a str, scheme: &str) -> Option<&'a str> {
+    reference.trim().strip_prefix(scheme)?.strip_prefix(':')
+}
+
+fn last_segment(url: &str) -> String {
+    url.split(['?', '#']).next().unwrap_or(url).rsplit(['/', '\\']).next().unwrap_or("").to_string()
+}
+
+/// Modrinth projects by slug or id: `modrinth:sodium`, or `modrinth:sodium@mc1.20.1-0.5.3` for
+/// one version. Without a version the newest build for the instance is used.
+struct Modrinth;
+
+/// Quilt runs the Fabric builds.
+fn modrinth_loader(loader: &str) -> &str {
+    if loader == "quilt" { "fabric" } else { loader }
+}
+
+impl ContentSource for Modrinth {
+    fn name(&self) -> &'static str {
+        "modrinth"
+    }
+
+    fn handles(&self, reference: &str) -> bool {
+        strip_scheme(reference, "modrinth").is_some()
+    }
+
+    fn search(&self, query: &str, kind: ContentKind, target: &Target) -> Result<Vec<ContentHit>, String> {
+        let (project_type, loader) = match kind {
+            ContentKind::Mod => ("mod", modrinth_loader(&target.loader)),
+            ContentKind::Modpack => ("modpack", modrinth_loader(&target.loader)),
+            ContentKind::ResourcePack => ("resourcepack", ""),
+        };
+        let hits = modrinth::search(query, project_type, loader, &target.mc_version, SEARCH_LIMIT)?;
+        Ok(hits.into_iter().map(|hit| ContentHit {
+            source: self.name().to_string(),
+            reference: format!("modrinth:{}", hit.slug),
+            title: hit.title,
+            description: hit.description,
+            icon_url: hit.icon_url,
+            downloads: hit.downloads,
+        }).collect())
+    }
+
+    fn resolve(&self, reference: &str, target: &Target) -> Result<ResolvedContent, String> {
+        let spec = strip_scheme(reference, "modrinth").ok_or("Not a Modrinth reference")?;
+        let (project, version) = match spec.split_once('@') {
+            Some((project, version)) => (project, Some(version)),
+            None => (spec, None),
+        };
+        let versions = modrinth::project_versions(project, modrinth_loader(&target.loader), &target.mc_version)?;
+        let chosen = match version {
+            Some(wanted) => versions.iter().find(|v| v.version_number == wanted || v.id == wanted),
+            None => versions.first(),
+        };
+        let file = chosen.and_then(|v| v.primary_file())
+            .ok_or_else(|| format!("{} no tiene versión para {} {}", spec, target.loader, target.mc_version))?;
+        Ok(ResolvedContent {
+            source: self.name(),
+            url: file.url.clone(),
+            file_name: file.filename.clone(),
+            sha1: file.hashes.get("sha1").cloned(),
+        })
+    }
+}
+
+/// One CurseForge file: `curseforge:<project id>/<file id>`. Needs the API key in the credentials.
+struct CurseForge;
+
+fn curseforge_ids(reference: &str) -> Option<(u64, u64)> {
+    let (project, file) = strip_scheme(reference, "curseforge")?.split_once('/')?;
+    Some((project.trim().parse().ok()?, file.trim().parse().ok()?))
+}
+
+impl ContentSource for CurseForge {
+    fn name(&self) -> &'static str {
+        "curseforge"
+    }
+
+    fn handles(&self, reference: &str) -> bool {
+        strip_scheme(reference, "curseforge").is_some()
+    }
+
+    fn search(&self, query: &str, kind: ContentKind, target: &Target) -> Result<Vec<ContentHit>, String> {
+        if !curseforge::has_api_key() {
+            return Ok(Vec::new());
+        }
+        let (class_id, loader) = match kind {
+            ContentKind::Mod => (6, target.loader.as_str()),
+            ContentKind::Modpack => (4471, target.loader.as_str()),
+            ContentKind::ResourcePack => (12, ""),
+        };
+        let hits = curseforge::search(query, class_id, &target.mc_version, loader, SEARCH_LIMIT)?;
+        Ok(hits.into_iter().filter_map(|hit| {
+            let file_id = hit.latest_file(&target.mc_version, loader)?;
+            Some(ContentHit {
+                source: self.name().to_string(),
+                reference: format!("curseforge:{}/{}", hit.id, file_id),
+                title: hit.name,
+                description: hit.summary,
+                icon_url: hit.logo.and_then(|l| l.thumbnail_url),
+                downloads: hit.download_count as u64,
+            })
+        }).collect())
+    }
+
+    fn resolve(&self, reference: &str, _target: &Target) -> Result<ResolvedContent, String> {
+        let (project_id, file_id) = curseforge_ids(reference)
+            .ok_or_else(|| format!("Referencia de CurseForge no válida: {} (usa curseforge:<proyecto>/<archivo>)", reference.trim()))?;
+        let url = curseforge::download_url(project_id, file_id)?;
+        Ok(ResolvedContent { source: self.name(), file_name: last_segment(&url), url, sha1: None })
+    }
+}
+
+/// Files on the staff server: `admin:mods/evento.jar`, relative to the mirror's base URL (see
+/// `crate::mirror`), whether or not the mirror itself is switched on.
+struct AdminServer;
+
+fn admin_url(base: &str, path: &str) -> String {
+    format!("{}{}", super::utils::ensure_trailing_slash(base.trim()), path.trim().trim_start_matches('/'))
+}
+
+impl ContentSource for AdminServer {
+    fn name(&self) -> &'static str {
+        "admin"
+    }
+
+    fn handles(&self, reference: &str) -> bool {
+        strip_scheme(reference, "admin").is_some()
+    }
+
+    fn resolve(&self, reference: &str, _target: &Target) -> Result<ResolvedContent, String> {
+        let path = strip_scheme(reference, "admin").ok_or("Not an admin server reference")?;
+        let base = crate::settings::load_settings().mirror.base_url.filter(|b| !b.trim().is_empty())
+            .ok_or("Configura la dirección del servidor del administrador (mirror.baseUrl) para usar enlaces admin:")?;
+        let url = admin_url(&base, path);
+        Ok(ResolvedContent { source: self.name(), file_name: last_segment(&url), url, sha1: None })
+    }
+}
+
+/// Files on this machine or a shared folder: absolute paths or `file://` URLs.
+struct LocalFolder;
+
+fn local_path(reference: &str) -> Option<PathBuf> {
+    let reference = reference.trim();
+    if reference.starts_with("file://") {
+        return Url::parse(reference).ok()?.to_file_path().ok();
+    }
+    let path = Path::new(reference);
+    path.is_absolute().then(|| path.to_path_buf())
+}
+
+impl ContentSource for LocalFolder {
+    fn name(&self) -> &'static str {
+        "local"
+    }
+
+    fn handles(&self, reference: &str) -> bool {
+        local_path(reference).is_some()
+    }
+
+    fn resolve(&self, reference: &str, _target: &Target) -> Result<ResolvedContent, String> {
+        let path = local_path(reference).ok_or("Not a local path")?;
+        if !path.is_file() {
+            return Err(format!("No existe el archivo {}", path.display()));
+        }
+        Ok(ResolvedContent {
+            source: self.name(),
+            file_name: path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default(),
+            url: path.to_string_lossy().to_string(),
+            sha1: None,
+        })
+    }
+
+    fn size(&self, resolved: &ResolvedContent) -> Result<Option<u64>, String> {
+        fs::metadata(&resolved.url).map(|m| Some(m.len())).map_err(|e| e.to_string())
+    }
+
+    /// Copied again whenever the size differs, so a replaced file in the folder is picked up.
+    fn download(&self, resolved: &ResolvedContent, dest: &Path, _kind: ContentKind) -> Result<Option<String>, String> {
+        let source_len = fs::metadata(&resolved.url).map(|m| m.len()).map_err(|e| e.to_string())?;
+        if fs::metadata(dest).is_ok_and(|m| m.len() == source_len) {
+            return Ok(None);
+        }
+        if let Some(parent) = dest.parent() {
+            fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        let tmp = tmp_path(dest);
+        fs::copy(&resolved.url, &tmp).map_err(|e| format!("No se pudo copiar {}: {}", resolved.url, e))?;
+        commit(&tmp, dest).map_err(|e| format!("Failed to save {}: {}", dest.display(), e))?;
+        Ok(None)
+    }
+}
+
+/// Plain links, including the share links `url_resolver` knows. Takes whatever no other source
+/// claimed, as instance lists did before references existed.
+struct DirectUrl;
+
+impl ContentSource for DirectUrl {
+    fn name(&self) -> &'static str {
+        "url"
+    }
+
+    fn handles(&self, _reference: &str) -> bool {
+        true
+    }
+
+    fn resolve(&self, reference: &str, _target: &Target) -> Result<ResolvedContent, String> {
+        let url = resolve_download_url(reference)?;
+        Ok(ResolvedContent { source: self.name(), file_name: last_segment(&url), url, sha1: None })
+    }
+}
+
+fn sources() -> Vec<Box<dyn ContentSource>> {
+    vec![Box::new(Modrinth), Box::new(CurseForge), Box::new(AdminServer), Box::new(LocalFolder), Box::new(DirectUrl)]
+}
+
+fn source_for(reference: &str) -> Box<dyn ContentSource> {
+    sources().into_iter().find(|s| s.handles(reference)).unwrap_or_else(|| Box::new(DirectUrl))
+}
+
+pub fn resolve(reference: &str, target: &Target) -> Result<ResolvedContent, String> {
+    source_for(reference).resolve(reference, target)
+}
+
+/// The source that resolved `resolved`.
+fn source_of(resolved: &ResolvedContent) -> Box<dyn ContentSource> {
+    sources().into_iter().find(|s| s.name() == resolved.source).unwrap_or_else(|| Box::new(DirectUrl))
+}
+
+/// Downloads what `resolve` returned through the source that resolved it.
+pub fn download(resolved: &ResolvedContent, dest: &Path, kind: ContentKind) -> Result<Option<String>, String> {
+    source_of(resolved).download(resolved, dest, kind)
+}
+
+pub fn size(resolved: &ResolvedContent) -> Result<Option<u64>, String> {
+    source_of(resolved).size(resolved)
+}
+
+/// Resolves and downloads `reference` to `dest`; returns the pack pin warning, if any.
+pub fn fetch(reference: &str, target: &Target, dest: &Path, kind: ContentKind) -> Result<Option<String>, String> {
+    download(&resolve(reference, target)?, dest, kind)
+}
+
+/// Whether `path` still matches what `reference` points at now: by hash when the source gives
+/// one, by size otherwise (`true` when it gives neither). `None` when the source can't be reached.
+/// Asks the network, so only explicit syncs use it; launches go by `content_index::unchanged`.
+pub fn matches_source(reference: &str, target: &Target, path: &Path) -> Option<bool> {
+    let resolved = resolve(reference, target).ok()?;
+    if let Some(expected) = &resolved.sha1 {
+        return Some(super::downloader::file_sha1(path).is_ok_and(|actual| actual.eq_ignore_ascii_case(expected)));
+    }
+    match size(&resolved).ok()? {
+        Some(remote_len) => Some(fs::metadata(path).is_ok_and(|m| m.len() == remote_len)),
+        None => Some(true),
+    }
+}
+
+/// Searches every source that can. Fails only when every source that answered failed.
+pub fn search(query: &str, kind: ContentKind, target: &Target) -> Result<Vec<ContentHit>, String> {
+    let mut hits = Vec::new();
+    let mut errors = Vec::new();
+    for source in sources() {
+        match source.search(query, kind, target) {
+            Ok(found) => hits.extend(found),
+            Err(e) => errors.push(e),
+        }
+    }
+    if hits.is_empty() && !errors.is_empty() {
+        return Err(errors.join("; "));
+    }
+    Ok(hits)
+}
+
+/// Finds mods or packs across every source, for the loader and version of `instance_id` when
+/// given. Each hit's `reference` can go straight into the instance's mod list or modpack field.
+#[tauri::command]
+pub async fn search_content(instance_id: Option<String>, query: String, kind: ContentKind) -> Result<Vec<ContentHit>, String> {
+    crate::run_blocking(move || {
+        let target = instance_id.as_deref().map(Target::for_instance).unwrap_or_default();
+        search(query.trim(), kind, &target)
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn references_pick_their_source() {
+        assert_eq!(source_for("modrinth:sodium").name(), "modrinth");
+        assert_eq!(source_for(" curseforge:238222/4712345").name(), "curseforge");
+        assert_eq!(source_for("admin:mods/evento.jar").name(), "admin");
+        assert_eq!(source_for("https://cdn.example.com/mod.jar").name(), "url");
+        let local = std::env::temp_dir().join("evento.jar");
+        assert_eq!(source_for(&local.to_string_lossy()).name(), "local");
+        assert_eq!(source_for(Url::from_file_path(&local).unwrap().as_str()).name(), "local");
+
+        assert_eq!(curseforge_ids("curseforge:238222/4712345"), Some((238222, 4712345)));
+        assert_eq!(curseforge_ids("curseforge:238222"), None);
+        assert_eq!(admin_url("http://10.0.0.2/evento", "/mods/a.jar"), "http://10.0.0.2/evento/mods/a.jar");
+        assert_eq!(last_segment("https://edge.forgecdn.net/files/4712/345/jei-1.20.1.jar?x=1"), "jei-1.20.1.jar");
+    }
+
+    #[test]
+    fn local_files_are_copied_when_they_change() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = dir.path().join("evento.jar");
+        let dest = dir.path().join("mods").join("mod.jar");
+        fs::write(&source, b"v1").unwrap();
+        let resolved = resolve(&source.to_string_lossy(), &Target::default()).unwrap();
+        assert_eq!(resolved.file_name, "evento.jar");
+        assert_eq!(size(&resolved).unwrap(), Some(2));
+        download(&resolved, &dest, ContentKind::Mod).unwrap();
+        assert_eq!(fs::read(&dest).unwrap(), b"v1");
+        fs::write(&source, b"v2 bigger").unwrap();
+        download(&resolved, &dest, ContentKind::Mod).unwrap();
+        assert_eq!(fs::read(&dest).unwrap(), b"v2 bigger");
+        assert!(resolve(&dir.path().join("missing.jar").to_string_lossy(), &Target::default()).is_err());
+    }
+
+    #[test]
+    fn installed_mods_match_their_source_by_size() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = dir.path().join("evento.jar");
+        let installed = dir.path().join("mod.jar");
+        fs::write(&source, b"v1").unwrap();
+        fs::write(&installed, b"v1").unwrap();
+        let reference = source.to_string_lossy().to_string();
+        assert_eq!(matches_source(&reference, &Target::default(), &installed), Some(true));
+        fs::write(&installed, b"cut").unwrap();
+        assert_eq!(matches_source(&reference, &Target::default(), &installed), Some(false));
+        // Unreachable source: the caller keeps what is there
+        fs::remove_file(&source).unwrap();
+        assert_eq!(matches_source(&reference, &Target::default(), &installed), None);
+    }
+}
@@ -0,0 +1,240 @@
+use serde::Deserialize;
+use std::time::Duration;
+
+const API_BASE: &str = "https://api.curseforge.com/v1";
+const MINECRAFT_GAME_ID: u32 = 432;
+
+/// CurseForge's file fingerprint: MurmurHash2 (seed 1) of the file without whitespace bytes.
+pub fn fingerprint(bytes: &[u8]) -> u32 {
+    let normalized: Vec<u8> = bytes.iter().copied().filter(|b| !matches!(b, 9 | 10 | 13 | 32)).collect();
+    murmur2(&normalized, 1)
+}
+
+fn murmur2(data: &[u8], seed: u32) -> u32 {
+    const M: u32 = 0x5bd1_e995;
+    let mut h = seed ^ data.len() as u32;
+    let mut chunks = data.chunks_exact(4);
+    for chunk in &mut chunks {
+        let mut k = u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+        k = k.wrapping_mul(M);
+        k ^= k >> 24;
+        k = k.wrapping_mul(M);
+        h = h.wrapping_mul(M) ^ k;
+    }
+    let tail = chunks.remainder();
+    if !tail.is_empty() {
+        for (i, byte) in tail.iter().enumerate() {
+            h ^= (*byte as u32) << (8 * i);
+        }
+        h = h.wrapping_mul(M);
+    }
+    h ^= h >> 13;
+    h = h.wrapping_mul(M);
+    h ^ (h >> 15)
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct CurseForgeFile {
+    #[serde(rename = "modId")]
+    pub mod_id: u64,
+    #[serde(rename = "fileFingerprint", default)]
+    pub file_fingerprint: u32,
+}
+
+#[derive(Deserialize, Clone, Debug, Default)]
+pub struct CurseForgeLinks {
+    #[serde(rename = "websiteUrl", default)]
+    pub website_url: Option<String>,
+    #[serde(rename = "sourceUrl", default)]
+    pub source_url: Option<String>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct CurseForgeMod {
+    pub id: u64,
+    pub name: String,
+    #[serde(default)]
+    pub links: CurseForgeLinks,
+    /// `false` when the author opted out of third-party distribution; `null` on older projects
+    #[serde(rename = "allowModDistribution", default)]
+    pub allow_mod_distribution: Option<bool>,
+}
+
+#[derive(Deserialize)]
+struct Data<T> {
+    data: T,
+}
+
+#[derive(Deserialize)]
+struct FingerprintMatches {
+    #[serde(rename = "exactMatches", default)]
+    exact_matches: Vec<FingerprintMatch>,
+}
+
+#[derive(Deserialize)]
+struct FingerprintMatch {
+    file: CurseForgeFile,
+}
+
+#[derive(Deserialize, Clone, Debug, Default)]
+pub struct CurseForgeLogo {
+    #[serde(rename = "thumbnailUrl", default)]
+    pub thumbnail_url: Option<String>,
+}
+
+/// Newest file of a project for one game version and loader.
+#[derive(Deserialize, Clone, Debug)]
+pub struct CurseForgeFileIndex {
+    #[serde(rename = "gameVersion")]
+    pub game_version: String,
+    #[serde(rename = "fileId")]
+    pub file_id: u64,
+    #[serde(rename = "modLoader", default)]
+    pub mod_loader: Option<u32>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct CurseForgeSearchHit {
+    pub id: u64,
+    pub name: String,
+    #[serde(default)]
+    pub summary: String,
+    #[serde(default)]
+    pub logo: Option<CurseForgeLogo>,
+    #[serde(rename = "downloadCount", default)]
+    pub download_count: f64,
+    #[serde(rename = "latestFilesIndexes", default)]
+    pub latest_files_indexes: Vec<CurseForgeFileIndex>,
+}
+
+impl CurseForgeSearchHit {
+    /// Newest file for a game version and loader; empty ones match any.
+    pub fn latest_file(&self, mc_version: &str, loader: &str) -> Option<u64> {
+        let loader_type = mod_loader_type(loader);
+        self.latest_files_indexes.iter()
+            .filter(|f| mc_version.is_empty() || f.game_version == mc_version)
+            .find(|f| loader_type.is_none() || f.mod_loader.is_none() || f.mod_loader == loader_type)
+            .map(|f| f.file_id)
+    }
+}
+
+/// CurseForge's `modLoaderType` for a launcher loader name.
+pub fn mod_loader_type(loader: &str) -> Option<u32> {
+    match loader {
+        "forge" => Some(1),
+        "fabric" => Some(4),
+        "quilt" => Some(5),
+        "neoforge" => Some(6),
+        _ => None,
+    }
+}
+
+/// The API refuses requests without the key set in the launcher credentials.
+pub fn has_api_key() -> bool {
+    crate::settings::load_settings().credentials.curseforge_api_key.map(|k| !k.is_empty()).unwrap_or(false)
+}
+
+fn client() -> Result<reqwest::blocking::Client, String> {
+    reqwest::blocking::Client::builder()
+        .user_agent(crate::http::user_agent())
+        .timeout(Duration::from_secs(30))
+        .build()
+        .map_err(|e| e.to_string())
+}
+
+fn post<T: for<'de> Deserialize<'de>>(path: &str, body: serde_json::Value) -> Result<T, String> {
+    let client = client()?;
+    let url = format!("{}{}", API_BASE, path);
+    let request = crate::http::with_credentials(client.post(&url).json(&body), &url);
+    let resp = crate::http::send(&client, request)?;
+    if !resp.status().is_success() {
+        return Err(format!("CurseForge returned status {} for {}", resp.status(), path));
+    }
+    resp.json::<Data<T>>().map(|d| d.data).map_err(|e| format!("Invalid CurseForge response: {}", e))
+}
+
+/// Files CurseForge knows by fingerprint.
+pub fn files_by_fingerprint(fingerprints: &[u32]) -> Result<Vec<CurseForgeFile>, String> {
+    if fingerprints.is_empty() {
+        return Ok(Vec::new());
+    }
+    let matches: FingerprintMatches = post("/fingerprints", serde_json::json!({ "fingerprints": fingerprints }))?;
+    Ok(matches.exact_matches.into_iter().map(|m| m.file).collect())
+}
+
+pub fn mods(ids: &[u64]) -> Result<Vec<CurseForgeMod>, String> {
+    if ids.is_empty() {
+        return Ok(Vec::new());
+    }
+    post("/mods", serde_json::json!({ "modIds": ids }))
+}
+
+/// Where to download one file of a project.
+pub fn download_url(project_id: u64, file_id: u64) -> Result<String, String> {
+    let client = client()?;
+    let url = format!("{}/mods/{}/files/{}/download-url", API_BASE, project_id, file_id);
+    let resp = crate::http::send(&client, crate::http::with_credentials(client.get(&url), &url))?;
+    if resp.status() == reqwest::StatusCode::FORBIDDEN {
+        return Err("CurseForge rechazó la petición; configura una API key en las credenciales".to_string());
+    }
+    let body: serde_json::Value = resp.json().map_err(|e| e.to_string())?;
+    // Authors can opt out of third-party downloads; the API then returns a null url
+    body["data"].as_str().map(str::to_string)
+        .ok_or_else(|| format!("El autor del archivo {} no permite descargarlo fuera de CurseForge", file_id))
+}
+
+/// Minecraft projects matching `query` in a class (6 mods, 4471 modpacks, 12 resource packs).
+pub fn search(query: &str, class_id: u32, mc_version: &str, loader: &str, page_size: usize) -> Result<Vec<CurseForgeSearchHit>, String> {
+    let client = client()?;
+    let url = format!("{}/mods/search", API_BASE);
+    let mut params = vec![
+        ("gameId", MINECRAFT_GAME_ID.to_string()),
+        ("classId", class_id.to_string()),
+        ("searchFilter", query.to_string()),
+        ("pageSize", page_size.to_string()),
+    ];
+    if !mc_version.is_empty() {
+        params.push(("gameVersion", mc_version.to_string()));
+    }
+    if let Some(loader_type) = mod_loader_type(loader) {
+        params.push(("modLoaderType", loader_type.to_string()));
+    }
+    let request = crate::http::with_credentials(client.get(&url).query(&params), &url);
+    let resp = crate::http::send(&client, request)?;
+    if !resp.status().is_success() {
+        return Err(format!("CurseForge returned status {} for search", resp.status()));
+    }
+    resp.json::<Data<Vec<CurseForgeSearchHit>>>().map(|d| d.data).map_err(|e| format!("Invalid CurseForge response: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fingerprint_ignores_whitespace() {
+        assert_eq!(murmur2(b"", 0), 0);
+        assert_eq!(fingerprint(b"ab c\r\n\td"), fingerprint(b"abcd"));
+        assert_ne!(fingerprint(b"abcd"), fingerprint(b"abce"));
+        // Every tail length goes through the final mix
+        let lengths: std::collections::HashSet<u32> = (0..8).map(|n| fingerprint(&b"abcdefgh"[..n])).collect();
+        assert_eq!(lengths.len(), 8);
+    }
+
+    #[test]
+    fn latest_file_matches_version_and_loader() {
+        let hit: CurseForgeSearchHit = serde_json::from_value(serde_json::json!({
+            "id": 238222,
+            "name": "JEI",
+            "latestFilesIndexes": [
+                {"gameVersion": "1.20.1", "fileId": 11, "modLoader": 1},
+                {"gameVersion": "1.20.1", "fileId": 12, "modLoader": 4},
+                {"gameVersion": "1.19.2", "fileId": 13, "modLoader": 4}
+            ]
+        })).unwrap();
+        assert_eq!(hit.latest_file("1.20.1", "fabric"), Some(12));
+        assert_eq!(hit.latest_file("1.20.1", "forge"), Some(11));
+        assert_eq!(hit.latest_file("1.18.2", "forge"), None);
+        assert_eq!(hit.latest_file("", ""), Some(11));
+    }
+}
@@ -1,62 +1,131 @@
 use std::fs::{self, File};
-use std::io::Read;
+use std::io::{self, Read};
 use std::path::Path;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::time::{Duration, SystemTime};
 use sha1::{Sha1, Digest};
+use crate::antivirus::{diagnose, Symptom};
+use crate::temp_files::{commit, tmp_path};
+use super::archive::detect_format;
+use super::models::DownloadArtifact;
 
 pub fn download_file(url: &str, path: &Path, sha1: Option<&str>) -> Result<(), String> {
+    download(url, path, sha1, false).map(|_| ())
+}
+
+/// `download_file` for game files (version jars and profiles, libraries, assets, loaders, Java):
+/// fetched from the self-hosted mirror when one is set (see `mirror`). Mods and packs are not.
+pub fn download_game_file(url: &str, path: &Path, sha1: Option<&str>) -> Result<(), String> {
+    download_file(&crate::mirror::rewrite(url), path, sha1)
+}
+
+/// `download_file` for modpacks: the certificate of the host that serves the pack is checked
+/// against `host_pins`. Returns the warning to show when it changed and the mode only warns.
+pub fn download_pack(url: &str, path: &Path) -> Result<Option<String>, String> {
+    download(url, path, None, true)
+}
+
+fn download(url: &str, path: &Path, sha1: Option<&str>, pinned: bool) -> Result<Option<String>, String> {
     if path.exists() {
         if let Some(expected_hash) = sha1 {
             if verify_hash(path, expected_hash) {
-                return Ok(());
+                crate::content_index::record(path, expected_hash);
+                return Ok(None);
             }
         } else {
             // If no hash provided, assume existing file is valid for now
-            return Ok(()); 
+            return Ok(None); 
         }
     }
 
     if let Some(parent) = path.parent() {
         fs::create_dir_all(parent).map_err(|e| e.to_string())?;
     }
+    // A stale file may be a hard link into mods-store; never write through it
+    let _ = fs::remove_file(path);
+    // Same content elsewhere on disk (moved data dir, old library layout) beats the network
+    if sha1.map(|expected| crate::content_index::relink(path, expected)).unwrap_or(false) {
+        return Ok(None);
+    }
 
     let client = reqwest::blocking::Client::builder()
-        .user_agent("Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36")
-        .timeout(std::time::Duration::from_secs(3600)) // 1 hour timeout for large files
+        .user_agent(crate::http::download_user_agent())
+        .timeout(crate::tuning::download_timeout())
+        .tls_info(pinned)
         .build()
         .map_err(|e| format!("Failed to build http client: {}", e))?;
 
+    // Written to the side and renamed once verified: a cut download must not pass existence checks
+    let tmp = tmp_path(path);
+    let stall_timeout = crate::tuning::stall_timeout();
     let max_retries = 3;
     let mut last_error = String::new();
+    let mut symptoms = Vec::new();
+    let mut pin_warning = None;
+    let _in_flight = crate::metrics::download_started();
 
     for attempt in 1..=max_retries {
-        let result = (|| -> Result<(), String> {
-            let mut response = client.get(url)
-                .send()
-                .map_err(|e| format!("Network error: {}", e))?;
+        let started = std::time::Instant::now();
+        let result = (|| -> Result<(), (String, Symptom)> {
+            let response = crate::http::send(&client, crate::http::with_credentials(client.get(url), url))
+                .map_err(|e| (e, Symptom::Other))?;
             
             if !response.status().is_success() {
-                return Err(format!("Download failed with status: {}", response.status()));
+                return Err((format!("Download failed with status: {}", response.status()), Symptom::Other));
+            }
+            let content_type = response.headers().get(reqwest::header::CONTENT_TYPE).and_then(|v| v.to_str().ok()).unwrap_or_default();
+            if is_html(content_type) && !expects_html(path) {
+                return Err(("Received an HTML page instead of the file (captive portal or mirror error)".to_string(), Symptom::Other));
+            }
+            if pinned {
+                let certificate = response.extensions().get::<reqwest::tls::TlsInfo>().and_then(|info| info.peer_certificate());
+                pin_warning = crate::host_pins::verify(response.url(), certificate).map_err(|e| (e, Symptom::Other))?;
             }
 
-            let mut file = File::create(path).map_err(|e| format!("File creation error: {}", e))?;
-            std::io::copy(&mut response, &mut file).map_err(|e| format!("Write error: {}", e))?;
+            let mut file = File::create(&tmp).map_err(|e| (format!("File creation error: {}", e), Symptom::from_io(&e)))?;
+            let mut body = StallReader::new(response, stall_timeout);
+            io::copy(&mut body, &mut file).map_err(|e| match e.kind() {
+                // Not the disk's fault: the next attempt (or the next mirror) gets a new connection
+                io::ErrorKind::TimedOut => (format!("Download stalled: {}", e), Symptom::Other),
+                _ => (format!("Write error: {}", e), Symptom::from_io(&e)),
+            })?;
+            drop(file);
+            // Without a hash to check, a 200 error page would only surface later as a zip error.
+            // Packs may be 7z or rar under a .zip name
+            if expects_archive(path) && detect_format(&tmp).is_none() {
+                let _ = fs::remove_file(&tmp);
+                return Err(("Downloaded file is not a valid jar/zip/7z/rar archive (likely an error page)".to_string(), Symptom::Other));
+            }
             Ok(())
         })();
 
         match result {
             Ok(_) => {
-                if let Some(expected_hash) = sha1 {
-                    if verify_hash(path, expected_hash) {
-                        return Ok(());
-                    } else {
-                        last_error = format!("Hash mismatch for {} (attempt {}/{})", url, attempt, max_retries);
-                        let _ = fs::remove_file(path); // Clean up bad file
+                if sha1.map(|expected_hash| verify_hash(&tmp, expected_hash)).unwrap_or(true) {
+                    let bytes = fs::metadata(&tmp).map(|m| m.len()).unwrap_or(0);
+                    commit(&tmp, path).map_err(|e| format!("Failed to save {}: {}", path.display(), e))?;
+                    crate::download_stats::record_success(url, bytes, started.elapsed());
+                    if let Some(expected_hash) = sha1 {
+                        crate::content_index::record(path, expected_hash);
                     }
-                } else {
-                    return Ok(());
+                    return Ok(pin_warning);
                 }
+                crate::download_stats::record_failure(url);
+                // A file that vanished or got locked right after being written is an antivirus at work
+                symptoms.push(match File::open(&tmp) {
+                    Err(e) if e.kind() == std::io::ErrorKind::NotFound => Symptom::Vanished,
+                    Err(e) => Symptom::from_io(&e),
+                    Ok(_) => Symptom::HashMismatch,
+                });
+                last_error = format!("Hash mismatch for {} (attempt {}/{})", url, attempt, max_retries);
+                let _ = fs::remove_file(&tmp); // Clean up bad file
             },
-            Err(e) => {
+            // Another attempt would meet the same certificate
+            Err((e, _)) if e.starts_with(crate::host_pins::PIN_MARKER) => return Err(e),
+            Err((e, symptom)) => {
+                let _ = fs::remove_file(&tmp);
+                crate::download_stats::record_failure(url);
+                symptoms.push(symptom);
                 last_error = format!("{} (attempt {}/{})", e, attempt, max_retries);
             }
         }
@@ -67,24 +136,196 @@ pub fn download_file(url: &str, path: &Path, sha1: Option<&str>) -> Result<(), S
         }
     }
 
+    if let Some(symptom) = diagnose(&symptoms) {
+        return Err(crate::antivirus::record_blocked(Some(url), path, sha1, symptom));
+    }
     Err(format!("Failed to download {} after {} attempts. Last error: {}", url, max_retries, last_error))
 }
 
-fn verify_hash(path: &Path, expected: &str) -> bool {
-    if let Ok(mut file) = File::open(path) {
-        let mut hasher = Sha1::new();
-        let mut buffer = [0; 8192]; // 8KB buffer
-        loop {
-            let n = match file.read(&mut buffer) {
-                Ok(n) => n,
-                Err(_) => return false,
+/// Hands over the body read on a helper thread and fails with `TimedOut` once no data arrived
+/// for `stall`. A blocking read can't be interrupted, so a stalled helper is left behind; it
+/// ends when the connection drops or the client timeout fires.
+struct StallReader {
+    chunks: Receiver<io::Result<Vec<u8>>>,
+    current: Vec<u8>,
+    offset: usize,
+    stall: Duration,
+}
+
+impl StallReader {
+    fn new(mut source: impl Read + Send + 'static, stall: Duration) -> StallReader {
+        let (sender, chunks) = mpsc::sync_channel(4);
+        std::thread::spawn(move || {
+            let mut buf = vec![0u8; 64 * 1024];
+            loop {
+                let chunk = source.read(&mut buf).map(|n| buf[..n].to_vec());
+                let last = !matches!(&chunk, Ok(data) if !data.is_empty());
+                // The reader gave up on us
+                if sender.send(chunk).is_err() || last {
+                    break;
+                }
+            }
+        });
+        StallReader { chunks, current: Vec::new(), offset: 0, stall }
+    }
+}
+
+impl Read for StallReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.offset == self.current.len() {
+            self.current = match self.chunks.recv_timeout(self.stall) {
+                Ok(chunk) => chunk?,
+                Err(RecvTimeoutError::Timeout) => {
+                    return Err(io::Error::new(io::ErrorKind::TimedOut, format!("no data for {}s", self.stall.as_secs())));
+                }
+                Err(RecvTimeoutError::Disconnected) => Vec::new(),
             };
-            if n == 0 { break; }
-            hasher.update(&buffer[..n]);
+            self.offset = 0;
         }
-        let result = hasher.finalize();
-        let actual = hex::encode(result);
-        return actual == expected.to_lowercase();
+        let n = buf.len().min(self.current.len() - self.offset);
+        buf[..n].copy_from_slice(&self.current[self.offset..self.offset + n]);
+        self.offset += n;
+        Ok(n)
+    }
+}
+
+fn is_html(content_type: &str) -> bool {
+    let mime = content_type.split(';').next().unwrap_or_default().trim().to_lowercase();
+    mime == "text/html" || mime == "application/xhtml+xml"
+}
+
+fn extension(path: &Path) -> String {
+    path.extension().map(|e| e.to_string_lossy().to_lowercase()).unwrap_or_default()
+}
+
+fn expects_html(path: &Path) -> bool {
+    matches!(extension(path).as_str(), "html" | "htm")
+}
+
+fn expects_archive(path: &Path) -> bool {
+    matches!(extension(path).as_str(), "jar" | "zip" | "mrpack")
+}
+
+/// Size and modification time; a hard link shares both with its source.
+fn file_stamp(path: &Path) -> Option<(u64, SystemTime)> {
+    let meta = fs::metadata(path).ok()?;
+    Some((meta.len(), meta.modified().ok()?))
+}
+
+/// Keeps one client jar per version at `versions/<id>/<id>.jar` and hard links it into the
+/// instance as `client.jar`, so instances of the same version share the download and the disk
+/// space. Across drives the instance gets a copy stamped like the shared jar.
+pub fn link_client_jar(versions_dir: &Path, version_id: &str, minecraft_dir: &Path, client: &DownloadArtifact) -> Result<(), String> {
+    let shared = versions_dir.join(version_id).join(format!("{}.jar", version_id));
+    let local = minecraft_dir.join("client.jar");
+    // Instances from before the shared jar already hold a verified copy; adopt it instead of downloading
+    if !shared.exists() && verify_hash(&local, &client.sha1) {
+        if let Some(parent) = shared.parent() {
+            fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        if fs::hard_link(&local, &shared).is_err() {
+            fs::copy(&local, &shared).map_err(|e| format!("Failed to store client.jar: {}", e))?;
+        }
+    }
+    download_game_file(&client.url, &shared, Some(&client.sha1))?;
+    let stamp = file_stamp(&shared);
+    if stamp.is_some() && stamp == file_stamp(&local) {
+        return Ok(());
+    }
+
+    // Link next to the jar first so a failure never leaves the instance without one
+    let staging = tmp_path(&local);
+    let _ = fs::remove_file(&staging);
+    if fs::hard_link(&shared, &staging).is_err() {
+        fs::copy(&shared, &staging).map_err(|e| format!("Failed to copy client.jar: {}", e))?;
+        if let (Some((_, modified)), Ok(file)) = (stamp, File::options().write(true).open(&staging)) {
+            let _ = file.set_modified(modified);
+        }
+    }
+    fs::rename(&staging, &local).map_err(|e| format!("Failed to link client.jar: {}", e))
+}
+
+fn verify_hash(path: &Path, expected: &str) -> bool {
+    file_sha1(path).map(|actual| actual == expected.to_lowercase()).unwrap_or(false)
+}
+
+pub fn file_sha1(path: &Path) -> Result<String, String> {
+    let mut file = File::open(path).map_err(|e| e.to_string())?;
+    let mut hasher = Sha1::new();
+    let mut buffer = [0; 8192]; // 8KB buffer
+    loop {
+        let n = file.read(&mut buffer).map_err(|e| e.to_string())?;
+        if n == 0 { break; }
+        hasher.update(&buffer[..n]);
+    }
+    Ok(hex::encode(hasher.finalize()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn error_pages_are_not_archives() {
+        let dir = tempfile::tempdir().unwrap();
+        let page = dir.path().join("lib.jar");
+        fs::write(&page, b"<!DOCTYPE html><html>Portal</html>").unwrap();
+        assert!(expects_archive(&page) && detect_format(&page).is_none());
+        let jar = dir.path().join("real.JAR");
+        fs::write(&jar, b"PK\x03\x04rest").unwrap();
+        assert!(expects_archive(&jar) && detect_format(&jar).is_some());
+
+        assert!(is_html("text/html; charset=utf-8"));
+        assert!(!is_html("application/java-archive"));
+        assert!(expects_html(Path::new("index.HTM")) && !expects_html(Path::new("a.json")));
+    }
+
+    #[test]
+    fn stalled_body_times_out() {
+        struct Stalling(bool);
+        impl Read for Stalling {
+            fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+                if std::mem::replace(&mut self.0, true) {
+                    std::thread::sleep(Duration::from_secs(5));
+                    return Ok(0);
+                }
+                buf[..3].copy_from_slice(b"abc");
+                Ok(3)
+            }
+        }
+        let mut out = Vec::new();
+        let err = io::copy(&mut StallReader::new(Stalling(false), Duration::from_millis(100)), &mut out).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+        assert_eq!(out, b"abc");
+
+        let mut whole = Vec::new();
+        StallReader::new(io::Cursor::new(vec![7u8; 200_000]), Duration::from_secs(5)).read_to_end(&mut whole).unwrap();
+        assert_eq!(whole, vec![7u8; 200_000]);
+    }
+
+    #[test]
+    fn instances_share_one_client_jar() {
+        let dir = tempfile::tempdir().unwrap();
+        let versions_dir = dir.path().join("versions");
+        let first = dir.path().join("a").join("minecraft");
+        let second = dir.path().join("b").join("minecraft");
+        fs::create_dir_all(&first).unwrap();
+        fs::create_dir_all(&second).unwrap();
+        fs::write(first.join("client.jar"), b"client bytes").unwrap();
+        let client = DownloadArtifact {
+            sha1: file_sha1(&first.join("client.jar")).unwrap(),
+            size: 12,
+            // Never fetched: the existing instance copy is adopted as the shared jar
+            url: "http://127.0.0.1:9/client.jar".to_string(),
+            path: None,
+        };
+
+        link_client_jar(&versions_dir, "1.20.1", &first, &client).unwrap();
+        link_client_jar(&versions_dir, "1.20.1", &second, &client).unwrap();
+        let shared = versions_dir.join("1.20.1").join("1.20.1.jar");
+        assert_eq!(fs::read(&shared).unwrap(), b"client bytes");
+        assert_eq!(fs::read(second.join("client.jar")).unwrap(), b"client bytes");
+        assert_eq!(file_stamp(&shared), file_stamp(&second.join("client.jar")));
+        assert!(!tmp_path(&second.join("client.jar")).exists());
     }
-    false
 }
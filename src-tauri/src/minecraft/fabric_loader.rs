@@ -2,22 +2,19 @@ use std::path::{Path, PathBuf};
 use std::fs;
 use std::process::Command;
 use tauri::AppHandle;
-use tauri::Emitter;
 use crate::auth::MinecraftProfile;
 use super::models::{VersionInfo};
-use super::downloader::download_file;
-use super::java::{get_java_path_for_major, get_required_java_version, download_java};
-use super::utils::check_rules;
-use super::launch_logic::{load_fabric_profile_info};
+use super::downloader::{download_game_file, link_client_jar};
+use super::java::{get_instance_java_path, get_required_java_version, download_java_in};
+use super::utils::{check_rules, parse_maven_name, maven_path, ensure_trailing_slash, natives_dir_for, jvm_path_arg};
+use super::jvm_presets;
+use super::version_store::LocalVersions;
+use super::prepare_checkpoint::{Checkpoint, LOADER_LIBRARIES};
+use super::launch_logic::{LaunchOptions, classpath_jvm_args, quick_play_args, window_args, isolation_args, debug_jvm_flags, linux_jvm_flags, macos_jvm_flags, log4shell_jvm_flags, pack_fingerprint_flags, load_fabric_profile_info};
 
 fn emit(app: &Option<AppHandle>, instance_id: &str, stage: &str, percent: u8, message: &str) {
     if let Some(app) = app {
-        let _ = app.emit("launch_progress", serde_json::json!({
-            "instanceId": instance_id,
-            "stage": stage,
-            "percent": percent,
-            "message": message,
-        }));
+        crate::events::emit_progress(app, crate::events::LaunchProgress::new(instance_id, stage, percent, message));
     }
 }
 
@@ -26,51 +23,49 @@ pub fn download_fabric(
     instance_minecraft_dir: &Path,
     mc_version: &str,
     app: &Option<AppHandle>,
-    instance_id: &str
+    instance_id: &str,
+    checkpoint: &Checkpoint
 ) -> Result<VersionInfo, String> {
     let _assets_dir = base_path.join("assets");
     let libraries_dir = base_path.join("libraries");
     let versions_dir = base_path.join("versions");
     fs::create_dir_all(instance_minecraft_dir).map_err(|e| e.to_string())?;
-    super::vanilla_loader::download_vanilla(base_path, instance_minecraft_dir, mc_version, app, instance_id)?;
+    super::vanilla_loader::download_vanilla(base_path, instance_minecraft_dir, mc_version, app, instance_id, checkpoint)?;
     emit(app, instance_id, "fabric", 60, "Descargando perfil Fabric");
     let info = load_fabric_profile_info(mc_version)?;
-    let version_dir = versions_dir.join(&info.id);
-    fs::create_dir_all(&version_dir).map_err(|e| e.to_string())?;
-    let json_path = version_dir.join("version.json");
-    let json = serde_json::to_string_pretty(&info).map_err(|e| e.to_string())?;
-    fs::write(&json_path, json).map_err(|e| e.to_string())?;
+    LocalVersions::new(&versions_dir).save(&info.id, &info)?;
+    // The profile's client is the vanilla one `download_vanilla` already linked
     if let Some(downloads) = &info.downloads {
-        let client_path = instance_minecraft_dir.join("client.jar");
-        if !client_path.exists() {
-            download_file(&downloads.client.url, &client_path, Some(&downloads.client.sha1))?;
-        }
+        link_client_jar(&versions_dir, mc_version, instance_minecraft_dir, &downloads.client)?;
     }
-    if !info.libraries.is_empty() {
-        emit(app, instance_id, "librerias", 70, "Descargando librerías Fabric");
-        for lib in &info.libraries {
-            if !check_rules(&lib.rules) { continue; }
-            if let Some(downloads) = &lib.downloads {
-                if let Some(artifact) = &downloads.artifact {
-                    if let Some(path_str) = &artifact.path {
-                        let target = libraries_dir.join(path_str);
-                        let _ = fs::create_dir_all(target.parent().unwrap());
-                        download_file(&artifact.url, &target, Some(&artifact.sha1))?;
+    checkpoint.run(LOADER_LIBRARIES, || {
+        if !info.libraries.is_empty() {
+            emit(app, instance_id, "librerias", 70, "Descargando librerías Fabric");
+            for lib in &info.libraries {
+                if !check_rules(&lib.rules) { continue; }
+                if let Some(downloads) = &lib.downloads {
+                    if let Some(artifact) = &downloads.artifact {
+                        if let Some(path_str) = &artifact.path {
+                            let target = libraries_dir.join(path_str);
+                            let _ = fs::create_dir_all(target.parent().unwrap());
+                            download_game_file(&artifact.url, &target, Some(&artifact.sha1))?;
+                        }
                     }
-                }
-            } else if let Some(maven) = parse_maven_name(&lib.name) {
-                if let Some(path_str) = maven_path(&maven) {
-                    let base_url = lib.url.clone().unwrap_or_else(|| "https://maven.fabricmc.net/".to_string());
-                    let url = format!("{}{}", ensure_trailing_slash(&base_url), path_str);
-                    let target = libraries_dir.join(&path_str);
-                    if let Some(parent) = target.parent() {
-                        let _ = fs::create_dir_all(parent);
+                } else if let Some(maven) = parse_maven_name(&lib.name) {
+                    if let Some(path_str) = maven_path(&maven) {
+                        let base_url = lib.url.clone().unwrap_or_else(|| "https://maven.fabricmc.net/".to_string());
+                        let url = format!("{}{}", ensure_trailing_slash(&base_url), path_str);
+                        let target = libraries_dir.join(&path_str);
+                        if let Some(parent) = target.parent() {
+                            let _ = fs::create_dir_all(parent);
+                        }
+                        let _ = download_game_file(&url, &target, None);
                     }
-                    let _ = download_file(&url, &target, None);
                 }
             }
         }
-    }
+        Ok(())
+    })?;
     Ok(info)
 }
 
@@ -79,7 +74,8 @@ pub fn build_fabric_command(
     instance_minecraft_dir: &Path,
     info: &VersionInfo,
     auth: &MinecraftProfile,
-    ram_mb: u64
+    ram_mb: u64,
+    options: &LaunchOptions
 ) -> Result<Command, String> {
     let assets_dir = base_path.join("assets");
     let libraries_dir = base_path.join("libraries");
@@ -91,89 +87,71 @@ pub fn build_fabric_command(
     } else {
         get_required_java_version(&info.id)
     };
-    let java_path = match get_java_path_for_major(required_java) {
+    let java_path = match get_instance_java_path(base_path, required_java, options.java_runtime.as_deref()) {
         Ok(p) => p,
         Err(_) => {
-            let path_str = download_java(required_java, None, None)?;
+            let path_str = download_java_in(base_path, required_java, None, None)?;
             PathBuf::from(path_str)
         }
     };
     let mut jars: Vec<PathBuf> = Vec::new();
-    let meta_path = versions_dir.join(&info.id).join("version.json");
-    if meta_path.exists() {
-        if let Ok(content) = fs::read_to_string(&meta_path) {
-            if let Ok(meta) = serde_json::from_str::<VersionInfo>(&content) {
-                for lib in meta.libraries {
-                    if !check_rules(&lib.rules) { continue; }
-                    if let Some(downloads) = &lib.downloads {
-                        if let Some(artifact) = &downloads.artifact {
-                            if let Some(path_str) = &artifact.path {
-                                let p = libraries_dir.join(path_str);
-                                if !p.exists() {
-                                    let _ = fs::create_dir_all(p.parent().unwrap_or(&libraries_dir));
-                                    let _ = download_file(&artifact.url, &p, Some(&artifact.sha1));
-                                }
-                                if p.exists() { jars.push(p); }
-                            }
-                        }
-                    } else if let Some(maven) = parse_maven_name(&lib.name) {
-                        if let Some(path_str) = maven_path(&maven) {
-                            let p = libraries_dir.join(&path_str);
-                            if !p.exists() {
-                                let base_url = lib.url.clone().unwrap_or_else(|| "https://maven.fabricmc.net/".to_string());
-                                let url = format!("{}{}", ensure_trailing_slash(&base_url), path_str);
-                                let _ = fs::create_dir_all(p.parent().unwrap_or(&libraries_dir));
-                                let _ = download_file(&url, &p, None);
-                            }
-                            if p.exists() { jars.push(p); }
+    let store = LocalVersions::new(&versions_dir);
+    if let Ok(Some(meta)) = store.load(&info.id) {
+        for lib in meta.libraries {
+            if !check_rules(&lib.rules) { continue; }
+            if let Some(downloads) = &lib.downloads {
+                if let Some(artifact) = &downloads.artifact {
+                    if let Some(path_str) = &artifact.path {
+                        let p = libraries_dir.join(path_str);
+                        if !p.exists() {
+                            let _ = fs::create_dir_all(p.parent().unwrap_or(&libraries_dir));
+                            let _ = download_game_file(&artifact.url, &p, Some(&artifact.sha1));
                         }
+                        if p.exists() { jars.push(p); }
                     }
                 }
+            } else if let Some(maven) = parse_maven_name(&lib.name) {
+                if let Some(path_str) = maven_path(&maven) {
+                    let p = libraries_dir.join(&path_str);
+                    if !p.exists() {
+                        let base_url = lib.url.clone().unwrap_or_else(|| "https://maven.fabricmc.net/".to_string());
+                        let url = format!("{}{}", ensure_trailing_slash(&base_url), path_str);
+                        let _ = fs::create_dir_all(p.parent().unwrap_or(&libraries_dir));
+                        let _ = download_game_file(&url, &p, None);
+                    }
+                    if p.exists() { jars.push(p); }
+                }
             }
         }
     }
     // Merge parent (vanilla) libraries into classpath
     let mut parent_asset_index_id: Option<String> = None;
     if let Some(parent_id) = &info.inherits_from {
-        // Try both "version.json" and "<id>.json"
-        let parent_version_json = {
-            let path1 = versions_dir.join(parent_id).join("version.json");
-            if path1.exists() {
-                Some(path1)
-            } else {
-                let path2 = versions_dir.join(parent_id).join(format!("{}.json", parent_id));
-                if path2.exists() { Some(path2) } else { None }
-            }
-        };
-        if let Some(pmeta_path) = parent_version_json {
-            if let Ok(content) = fs::read_to_string(&pmeta_path) {
-                if let Ok(pmeta) = serde_json::from_str::<VersionInfo>(&content) {
-                    parent_asset_index_id = pmeta.asset_index.as_ref().map(|a| a.id.clone());
-                    for lib in pmeta.libraries {
-                        if !check_rules(&lib.rules) { continue; }
-                        if let Some(downloads) = &lib.downloads {
-                            if let Some(artifact) = &downloads.artifact {
-                                if let Some(path_str) = &artifact.path {
-                                    let p = libraries_dir.join(path_str);
-                                    if !p.exists() {
-                                        let _ = fs::create_dir_all(p.parent().unwrap_or(&libraries_dir));
-                                        let _ = download_file(&artifact.url, &p, Some(&artifact.sha1));
-                                    }
-                                    if p.exists() { jars.push(p); }
-                                }
-                            }
-                        } else if let Some(maven) = parse_maven_name(&lib.name) {
-                            if let Some(path_str) = maven_path(&maven) {
-                                let p = libraries_dir.join(&path_str);
-                                if !p.exists() {
-                                    let base_url = lib.url.clone().unwrap_or_else(|| "https://libraries.minecraft.net/".to_string());
-                                    let url = format!("{}{}", ensure_trailing_slash(&base_url), path_str);
-                                    let _ = fs::create_dir_all(p.parent().unwrap_or(&libraries_dir));
-                                    let _ = download_file(&url, &p, None);
-                                }
-                                if p.exists() { jars.push(p); }
+        if let Ok(Some(pmeta)) = store.load(parent_id) {
+            parent_asset_index_id = pmeta.asset_index.as_ref().map(|a| a.id.clone());
+            for lib in pmeta.libraries {
+                if !check_rules(&lib.rules) { continue; }
+                if let Some(downloads) = &lib.downloads {
+                    if let Some(artifact) = &downloads.artifact {
+                        if let Some(path_str) = &artifact.path {
+                            let p = libraries_dir.join(path_str);
+                            if !p.exists() {
+                                let _ = fs::create_dir_all(p.parent().unwrap_or(&libraries_dir));
+                                let _ = download_game_file(&artifact.url, &p, Some(&artifact.sha1));
                             }
+                            if p.exists() { jars.push(p); }
+                        }
+                    }
+                } else if let Some(maven) = parse_maven_name(&lib.name) {
+                    if let Some(path_str) = maven_path(&maven) {
+                        let p = libraries_dir.join(&path_str);
+                        if !p.exists() {
+                            let base_url = lib.url.clone().unwrap_or_else(|| "https://libraries.minecraft.net/".to_string());
+                            let url = format!("{}{}", ensure_trailing_slash(&base_url), path_str);
+                            let _ = fs::create_dir_all(p.parent().unwrap_or(&libraries_dir));
+                            let _ = download_game_file(&url, &p, None);
                         }
+                        if p.exists() { jars.push(p); }
                     }
                 }
             }
@@ -200,7 +178,7 @@ pub fn build_fabric_command(
         let fallback = libraries_dir.join("net/sf/jopt-simple/jopt-simple/5.0.4/jopt-simple-5.0.4.jar");
         if !fallback.exists() {
             let _ = fs::create_dir_all(fallback.parent().unwrap_or(&libraries_dir));
-            let _ = download_file(
+            let _ = download_game_file(
                 "https://libraries.minecraft.net/net/sf/jopt-simple/jopt-simple/5.0.4/jopt-simple-5.0.4.jar",
                 &fallback,
                 None
@@ -210,39 +188,29 @@ pub fn build_fabric_command(
             jars.push(fallback);
         }
     }
-    let sep = if cfg!(target_os = "windows") { ";" } else { ":" };
-    let classpath = jars.into_iter().map(|p| p.to_string_lossy().to_string()).collect::<Vec<_>>().join(sep);
+    let classpath_args = classpath_jvm_args(&jars, required_java, instance_minecraft_dir)?;
     let mut cmd = Command::new(java_path);
+    // Relative argfile paths, and the JVM's own crash logs, are relative to the game folder
+    cmd.current_dir(instance_minecraft_dir);
     let min_mem = std::cmp::max(512, ram_mb / 4);
     cmd.arg(format!("-Xms{}M", min_mem));
     cmd.arg(format!("-Xmx{}M", ram_mb));
-    cmd.arg("-XX:+UnlockExperimentalVMOptions");
-    cmd.arg("-XX:+UseG1GC");
-    cmd.arg("-XX:MaxGCPauseMillis=120");
-    cmd.arg("-XX:G1HeapRegionSize=8M");
-    cmd.arg("-XX:G1NewSizePercent=30");
-    cmd.arg("-XX:G1MaxNewSizePercent=40");
-    cmd.arg("-XX:G1ReservePercent=20");
-    cmd.arg("-XX:G1HeapWastePercent=5");
-    cmd.arg("-XX:G1MixedGCCountTarget=4");
-    cmd.arg("-XX:InitiatingHeapOccupancyPercent=15");
-    cmd.arg("-XX:G1MixedGCLiveThresholdPercent=90");
-    cmd.arg("-XX:G1RSetUpdatingPauseTimePercent=5");
-    cmd.arg("-XX:ReservedCodeCacheSize=768M");
-    cmd.arg("-XX:InitialCodeCacheSize=128M");
-    cmd.arg("-XX:+ParallelRefProcEnabled");
-    cmd.arg("-XX:+DisableExplicitGC");
-    cmd.arg("-XX:+AlwaysPreTouch");
-    cmd.arg("-XX:+UseStringDeduplication");
-    cmd.arg("-XX:+UseCompressedOops");
-    cmd.arg("-XX:+UseCompressedClassPointers");
-    cmd.arg("-XX:+PerfDisableSharedMem");
+    cmd.args(jvm_presets::preset_flags(options.jvm_preset.as_deref(), required_java, ram_mb));
     cmd.arg("-Djava.net.preferIPv4Stack=true");
     cmd.arg("-Dfile.encoding=UTF-8");
     cmd.arg("-Djava.awt.headless=false");
-    cmd.arg("-cp").arg(classpath);
-    cmd.arg(format!("-Dorg.lwjgl.librarypath={}", instance_minecraft_dir.join("natives").to_string_lossy()));
-    cmd.arg(format!("-Djava.library.path={}", instance_minecraft_dir.join("natives").to_string_lossy()));
+    cmd.args(debug_jvm_flags(options));
+    cmd.args(linux_jvm_flags(options));
+    cmd.args(macos_jvm_flags(options, info.inherits_from.as_deref().unwrap_or(&info.id), &assets_dir));
+    cmd.args(log4shell_jvm_flags(info.inherits_from.as_deref().unwrap_or(&info.id), &assets_dir));
+    cmd.args(pack_fingerprint_flags(instance_minecraft_dir));
+    cmd.args(classpath_args);
+    // Fabric ships no natives of its own; they were extracted for the parent vanilla version
+    let natives_dir = natives_dir_for(instance_minecraft_dir, info.inherits_from.as_deref().unwrap_or(&info.id));
+    // Same encoding concern as the classpath: a non-ASCII profile folder reaches the JVM mangled
+    let arg_natives_dir = jvm_path_arg(&natives_dir, instance_minecraft_dir);
+    cmd.arg(format!("-Dorg.lwjgl.librarypath={}", arg_natives_dir.to_string_lossy()));
+    cmd.arg(format!("-Djava.library.path={}", arg_natives_dir.to_string_lossy()));
     if required_java >= 16 {
         cmd.arg("--add-opens"); cmd.arg("java.base/java.util=ALL-UNNAMED");
         cmd.arg("--add-opens"); cmd.arg("java.base/java.lang=ALL-UNNAMED");
@@ -270,57 +238,10 @@ pub fn build_fabric_command(
     cmd.arg("--accessToken").arg(&auth.access_token);
     cmd.arg("--userType").arg("mojang");
     cmd.arg("--versionType").arg("loader");
-    cmd.arg("--width").arg("854");
-    cmd.arg("--height").arg("480");
+    cmd.arg("--width").arg(options.width.unwrap_or(854).to_string());
+    cmd.arg("--height").arg(options.height.unwrap_or(480).to_string());
+    cmd.args(quick_play_args(options, info.inherits_from.as_deref().unwrap_or(&info.id)));
+    cmd.args(window_args(options));
+    cmd.args(isolation_args(options));
     Ok(cmd)
 }
-
-#[derive(Clone)]
-struct MavenName {
-    group: String,
-    artifact: String,
-    version: String,
-    classifier: Option<String>,
-    ext: String,
-}
-
-fn parse_maven_name(name: &str) -> Option<MavenName> {
-    let mut parts = name.split('@');
-    let main = parts.next()?;
-    let ext = parts.next().unwrap_or("jar").to_string();
-    let items: Vec<&str> = main.split(':').collect();
-    if items.len() < 3 {
-        return None;
-    }
-    let classifier = if items.len() > 3 { Some(items[3].to_string()) } else { None };
-    Some(MavenName {
-        group: items[0].to_string(),
-        artifact: items[1].to_string(),
-        version: items[2].to_string(),
-        classifier,
-        ext,
-    })
-}
-
-fn maven_path(maven: &MavenName) -> Option<String> {
-    let group_path = maven.group.replace('.', "/");
-    let mut filename = format!("{}-{}", maven.artifact, maven.version);
-    if let Some(classifier) = &maven.classifier {
-        if !classifier.is_empty() {
-            filename = format!("{}-{}", filename, classifier);
-        }
-    }
-    filename = format!("{}.{}", filename, maven.ext);
-    Some(format!(
-        "{}/{}/{}/{}",
-        group_path, maven.artifact, maven.version, filename
-    ))
-}
-
-fn ensure_trailing_slash(value: &str) -> String {
-    if value.ends_with('/') {
-        value.to_string()
-    } else {
-        format!("{}/", value)
-    }
-}
@@ -2,82 +2,108 @@ use std::path::{Path, PathBuf};
 use std::fs;
 use std::process::Command;
 use tauri::AppHandle;
-use tauri::Emitter;
 use crate::auth::MinecraftProfile;
 use super::models::*;
-use super::downloader::download_file;
-use super::java::{get_java_path_for_major, get_required_java_version, download_java, get_system_java_version};
-use super::utils::{check_rules, get_os_name, get_arch, replace_vars, extract_natives};
-use super::launch_logic::{resolve_complete_version_info, ensure_forge_installed};
+use super::downloader::{download_game_file, link_client_jar};
+use super::java::{get_instance_java_path, get_required_java_version, download_java_in, get_system_java_version};
+use super::utils::{check_rules, get_os_name, replace_vars, natives_dir_for, reset_natives_dir, parse_maven_name, maven_path, ensure_trailing_slash, jvm_path_arg, MavenName};
+use super::jvm_presets;
+use super::classpath_policy::{build_library_map, normalize_path_for_comparison, ClasspathPolicy};
+use super::prepare_checkpoint::{Checkpoint, ASSETS, LIBRARIES};
+use super::launch_logic::{resolve_complete_version_info, ensure_forge_installed, extract_library_natives, escape_arg, LaunchOptions, debug_jvm_flags, linux_jvm_flags, macos_jvm_flags, effective_log_level, log4shell_jvm_flags, pack_fingerprint_flags, quick_play_args, window_args, isolation_args};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
 
  
 
 fn emit(app: &Option<AppHandle>, instance_id: &str, stage: &str, percent: u8, message: &str) {
     if let Some(app) = app {
-        let _ = app.emit("launch_progress", serde_json::json!({
-            "instanceId": instance_id,
-            "stage": stage,
-            "percent": percent,
-            "message": message,
-        }));
+        crate::events::emit_progress(app, crate::events::LaunchProgress::new(instance_id, stage, percent, message));
     }
 }
 
-fn escape_arg(arg: &str) -> String {
-    let mut escaped = String::new();
-    let needs_quotes = arg.contains(' ') || arg.contains('\t');
-    if needs_quotes { escaped.push('"'); }
-    for c in arg.chars() {
-        if c == '"' { escaped.push_str("\\\""); } else { escaped.push(c); }
-    }
-    if needs_quotes { escaped.push('"'); }
-    escaped
+/// Shared progress of the concurrent asset and library downloads, weighted by file count.
+#[derive(Default)]
+struct CombinedProgress {
+    assets_done: AtomicU64,
+    assets_total: AtomicU64,
+    libraries_done: AtomicU64,
+    libraries_total: AtomicU64,
 }
 
-fn normalize_path_for_comparison(p: &Path) -> String {
-    let s = p.to_string_lossy().replace('\\', "/");
-    if get_os_name() == "windows" { s.to_lowercase() } else { s }
-}
+impl CombinedProgress {
+    /// Position in the 60–75% band of the launch bar.
+    fn percent(&self) -> u8 {
+        let done = self.assets_done.load(Ordering::SeqCst) + self.libraries_done.load(Ordering::SeqCst);
+        let total = self.assets_total.load(Ordering::SeqCst) + self.libraries_total.load(Ordering::SeqCst);
+        60 + (done.min(total) * 15).checked_div(total).unwrap_or(0) as u8
+    }
 
-#[derive(Clone)]
-struct MavenName {
-    group: String,
-    artifact: String,
-    version: String,
-    classifier: Option<String>,
-    ext: String,
+    fn report(&self, app: &Option<AppHandle>, instance_id: &str) {
+        let msg = format!(
+            "Descargando assets {}/{} y librerías {}/{}{}",
+            self.assets_done.load(Ordering::SeqCst),
+            self.assets_total.load(Ordering::SeqCst),
+            self.libraries_done.load(Ordering::SeqCst),
+            self.libraries_total.load(Ordering::SeqCst),
+            crate::http::backoff_note()
+        );
+        emit(app, instance_id, "descargas", self.percent(), &msg);
+    }
 }
 
-fn parse_maven_name(name: &str) -> Option<MavenName> {
-    let mut parts = name.split('@');
-    let main = parts.next()?;
-    let ext = parts.next().unwrap_or("jar").to_string();
-    let items: Vec<&str> = main.split(':').collect();
-    if items.len() < 3 { return None; }
-    let classifier = if items.len() > 3 { Some(items[3].to_string()) } else { None };
-    Some(MavenName {
-        group: items[0].to_string(),
-        artifact: items[1].to_string(),
-        version: items[2].to_string(),
-        classifier,
-        ext,
-    })
+fn download_forge_assets(
+    assets_dir: &Path,
+    info: &VersionInfo,
+    progress: &Arc<CombinedProgress>,
+    app: &Option<AppHandle>,
+    instance_id: &str
+) -> Result<(), String> {
+    let Some(asset_index_ref) = &info.asset_index else { return Ok(()) };
+    let idx_path = assets_dir.join("indexes").join(format!("{}.json", asset_index_ref.id));
+    download_game_file(&asset_index_ref.url, &idx_path, Some(&asset_index_ref.sha1))?;
+    progress.assets_total.store(super::asset_stream::count_objects(&idx_path)?, Ordering::SeqCst);
+    let (shared, app, instance_id) = (Arc::clone(progress), app.clone(), instance_id.to_string());
+    super::launch_logic::download_assets_with_progress(assets_dir, &idx_path, Arc::new(move |done, _total| {
+        shared.assets_done.store(done, Ordering::SeqCst);
+        shared.report(&app, &instance_id);
+    }))
 }
 
-fn maven_path(maven: &MavenName) -> Option<String> {
-    let group_path = maven.group.replace('.', "/");
-    let mut filename = format!("{}-{}", maven.artifact, maven.version);
-    if let Some(classifier) = &maven.classifier {
-        if !classifier.is_empty() {
-            filename = format!("{}-{}", filename, classifier);
+fn download_forge_libraries(
+    info: &VersionInfo,
+    libraries_dir: &Path,
+    natives_dir: &Path,
+    progress: &CombinedProgress,
+    app: &Option<AppHandle>,
+    instance_id: &str
+) -> Result<(), String> {
+    let libraries = info.libraries.iter().filter(|lib| check_rules(&lib.rules)).collect::<Vec<_>>();
+    progress.libraries_total.store(libraries.len() as u64, Ordering::SeqCst);
+    for lib in libraries {
+        if let Some(downloads) = &lib.downloads {
+            if let Some(artifact) = &downloads.artifact {
+                if let Some(path_str) = &artifact.path {
+                    let target = libraries_dir.join(path_str);
+                    let _ = fs::create_dir_all(target.parent().unwrap());
+                    let _ = download_game_file(&artifact.url, &target, Some(&artifact.sha1));
+                }
+            }
+        } else if let Some(maven) = parse_maven_name(&lib.name) {
+            let base_url = lib.url.clone().unwrap_or_else(|| "https://libraries.minecraft.net/".to_string());
+            if let Some(path_str) = maven_path(&maven) {
+                let url = format!("{}{}", ensure_trailing_slash(&base_url), path_str);
+                let target = libraries_dir.join(&path_str);
+                let _ = fs::create_dir_all(target.parent().unwrap());
+                let _ = download_game_file(&url, &target, None);
+            }
         }
+        extract_library_natives(lib, libraries_dir, natives_dir, "https://libraries.minecraft.net/")?;
+        progress.libraries_done.fetch_add(1, Ordering::SeqCst);
+        progress.report(app, instance_id);
     }
-    filename = format!("{}.{}", filename, maven.ext);
-    Some(format!("{}/{}/{}/{}", group_path, maven.artifact, maven.version, filename))
-}
-
-fn ensure_trailing_slash(value: &str) -> String {
-    if value.ends_with('/') { value.to_string() } else { format!("{}/", value) }
+    Ok(())
 }
 
 pub fn download_forge(
@@ -85,7 +111,8 @@ pub fn download_forge(
     instance_minecraft_dir: &Path,
     mc_version: &str,
     app: &Option<AppHandle>,
-    instance_id: &str
+    instance_id: &str,
+    checkpoint: &Checkpoint
 ) -> Result<VersionInfo, String> {
     let assets_dir = base_path.join("assets");
     let libraries_dir = instance_minecraft_dir
@@ -93,94 +120,33 @@ pub fn download_forge(
         .map(|p| p.join("libraries"))
         .unwrap_or_else(|| instance_minecraft_dir.join("libraries"));
     let versions_dir = base_path.join("versions");
-    let natives_dir = instance_minecraft_dir.join("natives");
     fs::create_dir_all(instance_minecraft_dir).map_err(|e| e.to_string())?;
-    fs::create_dir_all(&natives_dir).map_err(|e| e.to_string())?;
     fs::create_dir_all(&libraries_dir).map_err(|e| e.to_string())?;
 
     let effective_id = ensure_forge_installed(base_path, mc_version, app, instance_id)?;
 
-    let manifest: VersionManifest = super::launch_logic::fetch_manifest_with_fallback()
-        .map_err(|e| format!("Failed to fetch manifest (with fallback): {}", e))?;
-
-    let info = resolve_complete_version_info(&effective_id, &versions_dir, &manifest)?;
-    let version_dir = versions_dir.join(&info.id);
-    fs::create_dir_all(&version_dir).map_err(|e| e.to_string())?;
-    let json_path = version_dir.join("version.json");
-    let json = serde_json::to_string_pretty(&info).map_err(|e| e.to_string())?;
-    fs::write(&json_path, json).map_err(|e| e.to_string())?;
+    // The installer's `<id>.json` stays as it is; the merged profile only lives in memory
+    let info = resolve_complete_version_info(&effective_id, &versions_dir)?;
 
+    // The merged profile carries the vanilla client; share it with vanilla instances of the version
     if let Some(downloads) = &info.downloads {
-        let client_path = instance_minecraft_dir.join("client.jar");
         emit(app, instance_id, "cliente", 55, "Descargando cliente");
-        download_file(&downloads.client.url, &client_path, Some(&downloads.client.sha1))?;
-    }
-
-    if let Some(asset_index_ref) = &info.asset_index {
-        let idx_path = assets_dir.join("indexes").join(format!("{}.json", asset_index_ref.id));
-        download_file(&asset_index_ref.url, &idx_path, Some(&asset_index_ref.sha1))?;
-        let asset_index: AssetIndex = serde_json::from_str(
-            &fs::read_to_string(&idx_path).map_err(|e| e.to_string())?
-        ).map_err(|e| e.to_string())?;
-        emit(app, instance_id, "assets", 60, "Descargando assets");
-        super::launch_logic::download_assets_parallel(&assets_dir, &asset_index, app, instance_id)?;
-        emit(app, instance_id, "assets", 75, "Assets descargados");
+        link_client_jar(&versions_dir, mc_version, instance_minecraft_dir, &downloads.client)?;
     }
 
-    if !info.libraries.is_empty() {
-        emit(app, instance_id, "librerias", 65, "Descargando librerías");
-        for lib in &info.libraries {
-            if !check_rules(&lib.rules) { continue; }
-            if let Some(downloads) = &lib.downloads {
-                if let Some(artifact) = &downloads.artifact {
-                    if let Some(path_str) = &artifact.path {
-                        let target = libraries_dir.join(path_str);
-                        let _ = fs::create_dir_all(target.parent().unwrap());
-                        let _ = download_file(&artifact.url, &target, Some(&artifact.sha1));
-                    }
-                }
-                if let Some(classifiers) = &downloads.classifiers {
-                    if let Some(natives_map) = &lib.natives {
-                        if let Some(classifier_key) = natives_map.get(get_os_name()) {
-                            let arch = get_arch();
-                            let key = classifier_key.replace("${arch}", if arch == "x86" { "32" } else { "64" });
-                            if let Some(artifact) = classifiers.get(&key) {
-                                if let Some(path_str) = &artifact.path {
-                                    let target = libraries_dir.join(path_str);
-                                    let _ = fs::create_dir_all(target.parent().unwrap());
-                                    let _ = download_file(&artifact.url, &target, Some(&artifact.sha1));
-                                    let _ = extract_natives(&target, &natives_dir);
-                                }
-                            }
-                        }
-                    }
-                }
-            } else if let Some(maven) = parse_maven_name(&lib.name) {
-                let base_url = lib.url.clone().unwrap_or_else(|| "https://libraries.minecraft.net/".to_string());
-                if let Some(path_str) = maven_path(&maven) {
-                    let url = format!("{}{}", ensure_trailing_slash(&base_url), path_str);
-                    let target = libraries_dir.join(&path_str);
-                    let _ = fs::create_dir_all(target.parent().unwrap());
-                    let _ = download_file(&url, &target, None);
-                }
-                if let Some(natives_map) = &lib.natives {
-                    if let Some(classifier_key) = natives_map.get(get_os_name()) {
-                        let arch = get_arch();
-                        let classifier = classifier_key.replace("${arch}", if arch == "x86" { "32" } else { "64" });
-                        let mut native_maven = maven.clone();
-                        native_maven.classifier = Some(classifier);
-                        if let Some(native_path) = maven_path(&native_maven) {
-                            let url = format!("{}{}", ensure_trailing_slash(&base_url), native_path);
-                            let target = libraries_dir.join(&native_path);
-                            let _ = fs::create_dir_all(target.parent().unwrap());
-                            let _ = download_file(&url, &target, None);
-                            let _ = extract_natives(&target, &natives_dir);
-                        }
-                    }
-                }
-            }
-        }
-    }
+    // Assets and libraries are independent: the asset pool runs beside the library loop
+    let progress = Arc::new(CombinedProgress::default());
+    emit(app, instance_id, "descargas", 60, "Descargando assets y librerías");
+    std::thread::scope(|scope| {
+        let assets = scope.spawn(|| checkpoint.run(ASSETS, || download_forge_assets(&assets_dir, &info, &progress, app, instance_id)));
+        let libraries = checkpoint.run(LIBRARIES, || {
+            let natives_dir = reset_natives_dir(instance_minecraft_dir, &info.id)?;
+            download_forge_libraries(&info, &libraries_dir, &natives_dir, &progress, app, instance_id)
+        });
+        let assets = assets.join().unwrap_or_else(|_| Err("La descarga de assets se interrumpió".to_string()));
+        libraries.and(assets)
+    })?;
+    emit(app, instance_id, "descargas", 75, "Assets y librerías descargados");
 
     Ok(info)
 }
@@ -190,7 +156,8 @@ pub fn build_forge_command(
     instance_minecraft_dir: &Path,
     info: &VersionInfo,
     auth: &MinecraftProfile,
-    ram_mb: u64
+    ram_mb: u64,
+    options: &LaunchOptions
 ) -> Result<Command, String> {
     let assets_dir = base_path.join("assets");
     let libraries_dir = instance_minecraft_dir
@@ -198,12 +165,12 @@ pub fn build_forge_command(
         .map(|p| p.join("libraries"))
         .unwrap_or_else(|| instance_minecraft_dir.join("libraries"));
     let _versions_dir = base_path.join("versions");
-    let natives_dir = instance_minecraft_dir.join("natives");
+    let natives_dir = natives_dir_for(instance_minecraft_dir, &info.id);
     let required_java = info.java_version.as_ref().map(|v| v.major_version).unwrap_or_else(|| get_required_java_version(&info.id));
-    let java_path = match get_java_path_for_major(required_java) {
+    let java_path = match get_instance_java_path(base_path, required_java, options.java_runtime.as_deref()) {
         Ok(p) => p,
         Err(_) => {
-            let path_str = download_java(required_java, None, None)?;
+            let path_str = download_java_in(base_path, required_java, None, None)?;
             PathBuf::from(path_str)
         }
     };
@@ -211,30 +178,14 @@ pub fn build_forge_command(
     let mut cmd = Command::new(java_path.clone());
     let mut jvm_flags: Vec<String> = Vec::new();
     let min_mem = std::cmp::max(512, ram_mb / 4);
+    let java_major = get_system_java_version(&java_path.to_string_lossy()).unwrap_or(required_java);
+    for flag in jvm_presets::preset_flags(options.jvm_preset.as_deref(), java_major, ram_mb) {
+        cmd.arg(&flag);
+        jvm_flags.push(flag);
+    }
     for flag in [
-        "-XX:+UnlockExperimentalVMOptions",
         &format!("-Xms{}M", min_mem),
         &format!("-Xmx{}M", ram_mb),
-        "-XX:+UseG1GC",
-        "-XX:MaxGCPauseMillis=120",
-        "-XX:G1HeapRegionSize=8M",
-        "-XX:G1NewSizePercent=30",
-        "-XX:G1MaxNewSizePercent=40",
-        "-XX:G1ReservePercent=20",
-        "-XX:G1HeapWastePercent=5",
-        "-XX:G1MixedGCCountTarget=4",
-        "-XX:InitiatingHeapOccupancyPercent=15",
-        "-XX:G1MixedGCLiveThresholdPercent=90",
-        "-XX:G1RSetUpdatingPauseTimePercent=5",
-        "-XX:ReservedCodeCacheSize=768M",
-        "-XX:InitialCodeCacheSize=128M",
-        "-XX:+ParallelRefProcEnabled",
-        "-XX:+DisableExplicitGC",
-        "-XX:+AlwaysPreTouch",
-        "-XX:+UseStringDeduplication",
-        "-XX:+UseCompressedOops",
-        "-XX:+UseCompressedClassPointers",
-        "-XX:+PerfDisableSharedMem",
         "-Djava.net.preferIPv4Stack=true",
         "-Dfile.encoding=UTF-8",
         "-Dfml.ignoreInvalidMinecraftCertificates=true",
@@ -243,7 +194,7 @@ pub fn build_forge_command(
         "-Dfml.earlyWindowControl=false",
         "-Dforge.logging.console.level=info",
         "-Djava.awt.headless=false",
-        &format!("-Djava.library.path={}", natives_dir.to_string_lossy()),
+        &format!("-Djava.library.path={}", jvm_path_arg(&natives_dir, instance_minecraft_dir).to_string_lossy()),
         "-Dminecraft.launcher.brand=drklauncher",
         "-Dminecraft.launcher.version=1.0",
     ] {
@@ -251,14 +202,31 @@ pub fn build_forge_command(
         jvm_flags.push(flag.to_string());
     }
 
-    let java_major = get_system_java_version(&java_path.to_string_lossy()).unwrap_or(required_java);
+    let mc_version = info.inherits_from.as_deref().unwrap_or(&info.id);
+    for flag in debug_jvm_flags(options).into_iter()
+        .chain(linux_jvm_flags(options))
+        .chain(macos_jvm_flags(options, mc_version, &assets_dir))
+        .chain(pack_fingerprint_flags(instance_minecraft_dir))
+    {
+        cmd.arg(&flag);
+        jvm_flags.push(flag);
+    }
+
+    let log4shell_flags = log4shell_jvm_flags(mc_version, &assets_dir);
+    // Mojang's patched config replaces whatever logging config the version json ships
+    let has_log_config_override = log4shell_flags.iter().any(|f| f.starts_with("-Dlog4j.configurationFile="));
+    for flag in log4shell_flags {
+        cmd.arg(&flag);
+        jvm_flags.push(flag);
+    }
+
     if java_major >= 21 {
         let flag = "--enable-native-access=ALL-UNNAMED";
         cmd.arg(flag);
         jvm_flags.push(flag.to_string());
     }
 
-    if let Some(logging) = &info.logging {
+    if let Some(logging) = info.logging.as_ref().filter(|_| !has_log_config_override) {
         if let Some(client_logging) = &logging.client {
             if let Some(file) = &client_logging.file {
                 let log_config_path = assets_dir.join("log_configs").join(&file.id);
@@ -267,7 +235,7 @@ pub fn build_forge_command(
                         if let Some(parent) = log_config_path.parent() {
                             let _ = fs::create_dir_all(parent);
                         }
-                        let _ = download_file(url, &log_config_path, Some(&file.sha1));
+                        let _ = download_game_file(url, &log_config_path, Some(&file.sha1));
                     }
                 }
                 if log_config_path.exists() {
@@ -280,417 +248,249 @@ pub fn build_forge_command(
     }
 
     let asset_index_id = info.asset_index.as_ref().map(|a| a.id.as_str()).unwrap_or("legacy");
+    // Everything below ends up in args.txt, so paths go through jvm_path_arg
+    let jvm_path = |p: &Path| jvm_path_arg(p, instance_minecraft_dir);
+    let (arg_assets_dir, arg_game_dir, arg_natives_dir, arg_libraries_dir) =
+        (jvm_path(&assets_dir), jvm_path(instance_minecraft_dir), jvm_path(&natives_dir), jvm_path(&libraries_dir));
+    let expand = |arg: &str| replace_vars(arg, auth, &info.id, &arg_assets_dir, &arg_game_dir, asset_index_id, &arg_natives_dir, &arg_libraries_dir);
+
+    let policy = ClasspathPolicy::for_version(info);
+    let library_map = build_library_map(info, &libraries_dir);
+    let module_path = build_module_path(&policy, &library_map, &libraries_dir);
+    let classpath = build_forge_classpath(&policy, info, instance_minecraft_dir, &libraries_dir, &library_map, &module_path);
+    let bootstrap_target = find_forge_library(&library_map, &libraries_dir, "cpw.mods", "bootstraplauncher");
+    let fmlloader_target = find_forge_library(&library_map, &libraries_dir, "net.minecraftforge", "fmlloader");
+    let main_class = policy.main_class(info, bootstrap_target.as_deref(), fmlloader_target.as_deref());
+
+    let jvm_args = filter_forge_jvm_args(&flatten_arguments(info.arguments.as_ref().and_then(|a| a.jvm.as_ref())))
+        .iter()
+        .map(|a| expand(a))
+        .filter(|v| !v.starts_with("-DignoreList="))
+        .collect::<Vec<_>>();
+    let game_args = match info.arguments.as_ref().and_then(|a| a.game.as_ref()) {
+        Some(game) => filter_game_args(&flatten_arguments(Some(game))),
+        None => filter_game_args(&info.minecraft_arguments.as_deref().unwrap_or("").split_whitespace().map(|s| s.to_string()).collect::<Vec<_>>()),
+    }
+    .iter()
+    .map(|a| expand(a))
+    .chain(quick_play_args(options, mc_version))
+    .chain(window_args(options))
+    .chain(isolation_args(options))
+    .collect::<Vec<_>>();
+
+    let console_level = effective_log_level(options).map(|l| l.to_lowercase()).unwrap_or_else(|| "info".to_string());
+    let args_content = build_forge_args_content(
+        &console_level,
+        &module_path.iter().map(|p| jvm_path(p)).collect::<Vec<_>>(),
+        &jvm_args,
+        &classpath.iter().map(|p| jvm_path(p)).collect::<Vec<_>>(),
+        &main_class,
+        options.width.unwrap_or(854),
+        options.height.unwrap_or(480),
+        &game_args,
+    );
     let args_file_path = instance_minecraft_dir.join("args.txt");
-    let mut args_content = String::new();
-    args_content.push_str("-Dfml.earlyprogresswindow=false\n");
-    args_content.push_str("-Dfml.earlyWindowControl=false\n");
-    args_content.push_str("-Dforge.logging.console.level=info\n");
-
-    let mut library_map: std::collections::HashMap<String, MavenName> = std::collections::HashMap::new();
-    for lib in &info.libraries {
-        if let Some(maven) = parse_maven_name(&lib.name) {
-            if let Some(path_str) = maven_path(&maven) {
-                let full_path = libraries_dir.join(path_str);
-                library_map.insert(normalize_path_for_comparison(&full_path), maven.clone());
-            }
-            if let Some(downloads) = &lib.downloads {
-                if let Some(artifact) = &downloads.artifact {
-                    if let Some(path_str) = &artifact.path {
-                        let full_path = libraries_dir.join(path_str);
-                        library_map.insert(normalize_path_for_comparison(&full_path), maven.clone());
-                    }
-                }
-            }
-        }
+    let _ = fs::write(&args_file_path, &args_content);
+
+    let debug_content = format!(
+        "JAVA_PATH={}\nJAVA_MAJOR={}\nJAVA_REQUIRED={}\nWORK_DIR={}\nNATIVES_DIR={}\nMAIN_CLASS={}\nMODULE_PATH={}\nCLASSPATH={}\nJVM_FLAGS={}\nARGS_FILE={}\nARGS_CONTENT_BEGIN\n{}\nARGS_CONTENT_END\nLIB_CHECKS_BEGIN\n{}\nLIB_CHECKS_END\n",
+        java_path.to_string_lossy(),
+        java_major,
+        required_java,
+        instance_minecraft_dir.to_string_lossy(),
+        natives_dir.to_string_lossy(),
+        main_class,
+        join_paths(&module_path),
+        join_paths(&classpath),
+        jvm_flags.join(" "),
+        args_file_path.to_string_lossy(),
+        args_content,
+        forge_library_checks(&library_map, &libraries_dir).join("\n"),
+    );
+    let logs_dir = instance_minecraft_dir.join("logs");
+    let _ = fs::create_dir_all(&logs_dir);
+    let _ = fs::write(logs_dir.join("launch-debug.txt"), debug_content);
+
+    cmd.arg(format!("@{}", args_file_path.to_string_lossy()));
+    cmd.current_dir(instance_minecraft_dir);
+    Ok(cmd)
+}
+
+fn classpath_separator() -> &'static str {
+    if get_os_name() == "windows" { ";" } else { ":" }
+}
+
+fn join_paths(paths: &[PathBuf]) -> String {
+    paths.iter().map(|p| p.to_string_lossy().to_string()).collect::<Vec<_>>().join(classpath_separator())
+}
+
+/// Module path of the policy, downloading any jar that is missing from Forge Maven.
+fn build_module_path(policy: &ClasspathPolicy, library_map: &HashMap<String, MavenName>, libraries_dir: &Path) -> Vec<PathBuf> {
+    let module_path = policy.module_path(library_map, libraries_dir);
+    for path in &module_path {
+        download_forge_library_if_missing(path, libraries_dir);
     }
+    module_path
+}
 
-    let mp_sep = if get_os_name() == "windows" { ";" } else { ":" };
-    let mut module_path_set: std::collections::HashSet<String> = std::collections::HashSet::new();
-    for (_, maven) in &library_map {
-        if maven.group == "cpw.mods" && (maven.artifact == "securejarhandler" || maven.artifact == "modlauncher" || maven.artifact == "bootstraplauncher") {
-            if let Some(rel) = maven_path(maven) {
-                let full = libraries_dir.join(rel).to_string_lossy().to_string();
-                module_path_set.insert(full);
-            }
-        }
+fn download_forge_library_if_missing(target: &Path, libraries_dir: &Path) {
+    if target.exists() {
+        return;
     }
-    for v in module_path_set.clone() {
-        let full_path = std::path::PathBuf::from(&v);
-        if !full_path.exists() {
-            let rel = if let Ok(stripped) = full_path.strip_prefix(&libraries_dir) {
-                stripped.to_string_lossy().replace('\\', "/")
-            } else {
-                continue;
-            };
-            let url = format!("https://maven.minecraftforge.net/{}", rel);
-            let _ = download_file(&url, &full_path, None);
-        }
+    if let Ok(stripped) = target.strip_prefix(libraries_dir) {
+        let rel = stripped.to_string_lossy().replace('\\', "/");
+        let url = format!("https://maven.minecraftforge.net/{}", rel);
+        let _ = download_game_file(&url, target, None);
     }
-    if !module_path_set.is_empty() {
-        let final_mp = module_path_set.clone().into_iter().collect::<Vec<_>>().join(mp_sep);
-        args_content.push_str("-p\n");
-        args_content.push_str(&format!("{}\n", escape_arg(&final_mp)));
+}
+
+/// Locates a library jar from version.json, falling back to scanning `libraries/<group>/<artifact>/*/`.
+fn find_forge_library(library_map: &HashMap<String, MavenName>, libraries_dir: &Path, group: &str, artifact: &str) -> Option<PathBuf> {
+    let declared = library_map.values()
+        .find(|m| m.group == group && m.artifact == artifact)
+        .and_then(maven_path)
+        .map(|rel| libraries_dir.join(rel));
+    if declared.is_some() {
+        return declared;
     }
-    let mut module_path_libs: std::collections::HashSet<String> = std::collections::HashSet::new();
-    for v in &module_path_set {
-        let norm = normalize_path_for_comparison(&std::path::PathBuf::from(v));
-        module_path_libs.insert(norm);
+    let mut base = libraries_dir.to_path_buf();
+    for segment in group.split('.') {
+        base.push(segment);
     }
+    base.push(artifact);
+    let prefix = format!("{}-", artifact);
+    fs::read_dir(&base).ok()?
+        .flatten()
+        .filter(|e| e.path().is_dir())
+        .filter_map(|e| fs::read_dir(e.path()).ok())
+        .flat_map(|files| files.flatten())
+        .map(|f| f.path())
+        .find(|p| p.file_name().and_then(|n| n.to_str()).map(|n| n.starts_with(&prefix) && n.ends_with(".jar")).unwrap_or(false))
+}
 
-    let mut classpath_entries: Vec<PathBuf> = Vec::new();
-    for lib in &info.libraries {
-        if !check_rules(&lib.rules) { continue; }
-        if let Some(downloads) = &lib.downloads {
-            if let Some(artifact) = &downloads.artifact {
-                if let Some(path_str) = &artifact.path {
-                    let is_native = false;
-                    if !is_native {
-                        classpath_entries.push(libraries_dir.join(path_str));
-                    }
-                }
-            }
-        } else if let Some(maven) = parse_maven_name(&lib.name) {
-            if let Some(path_str) = maven_path(&maven) {
-                let is_native = maven.classifier.as_deref().map(|c| c.contains("natives")).unwrap_or(false);
-                if !is_native {
-                    classpath_entries.push(libraries_dir.join(path_str));
-                }
+/// The policy's classpath plus forced jars only found by scanning `libraries/`. Only entries that
+/// exist on disk are kept.
+fn build_forge_classpath(
+    policy: &ClasspathPolicy,
+    info: &VersionInfo,
+    instance_minecraft_dir: &Path,
+    libraries_dir: &Path,
+    library_map: &HashMap<String, MavenName>,
+    module_path: &[PathBuf],
+) -> Vec<PathBuf> {
+    let client_path = instance_minecraft_dir.join("client.jar");
+    let client_jar = client_path.exists().then_some(client_path);
+    let mut classpath = policy.classpath(info, client_jar, libraries_dir, library_map, module_path);
+    let mut seen = classpath.iter().map(|p| normalize_path_for_comparison(p)).collect::<HashSet<_>>();
+    for (group, artifact) in policy.forced_classpath() {
+        if let Some(target) = find_forge_library(library_map, libraries_dir, group, artifact) {
+            download_forge_library_if_missing(&target, libraries_dir);
+            if seen.insert(normalize_path_for_comparison(&target)) {
+                classpath.push(target);
             }
         }
     }
-    let client_path = instance_minecraft_dir.join("client.jar");
-    if client_path.exists() {
-        classpath_entries.insert(0, client_path);
-    }
+    classpath.into_iter().filter(|p| p.exists()).collect()
+}
 
-    if let Some(args) = &info.arguments {
-        if let Some(jvm_args) = &args.jvm {
-            let mut skip_next = false;
-            for arg in jvm_args {
-                match arg {
-                    Argument::Simple(s) => {
-                        if skip_next { skip_next = false; continue; }
-                        if s == "-cp" { skip_next = true; continue; }
-                        if s == "-p" || s == "--module-path" { skip_next = true; continue; }
-                        if s == "${classpath}" { continue; }
-                        if s == "${module_path}" { continue; }
-                        if (s.starts_with("--add-exports") || s.starts_with("--add-opens")) && s.contains("cpw.mods.") { continue; }
-                        if s.starts_with("-DignoreList=") { continue; }
-                        let val = replace_vars(s, auth, &info.id, &assets_dir, instance_minecraft_dir, asset_index_id, &natives_dir, &libraries_dir);
-                        if val.starts_with("-DignoreList=") { continue; }
-                        args_content.push_str(&format!("{}\n", escape_arg(&val)));
-                    }
-                    Argument::Complex(c) => {
-                        if check_rules(&Some(c.rules.clone())) {
-                            match &c.value {
-                                ArgumentValue::Single(s) => {
-                                    if skip_next { skip_next = false; continue; }
-                                    if s == "-cp" { skip_next = true; continue; }
-                                    if s == "-p" || s == "--module-path" { skip_next = true; continue; }
-                                    if s == "${classpath}" { continue; }
-                                    if s == "${module_path}" { continue; }
-                                    if (s.starts_with("--add-exports") || s.starts_with("--add-opens")) && s.contains("cpw.mods.") { continue; }
-                                    if s.starts_with("-DignoreList=") { continue; }
-                                    let val = replace_vars(s, auth, &info.id, &assets_dir, instance_minecraft_dir, asset_index_id, &natives_dir, &libraries_dir);
-                                    if val.starts_with("-DignoreList=") { continue; }
-                                    args_content.push_str(&format!("{}\n", escape_arg(&val)));
-                                }
-                                ArgumentValue::Multiple(vec) => {
-                                    for s in vec {
-                                        if skip_next { skip_next = false; continue; }
-                                        if s == "-cp" { skip_next = true; continue; }
-                                        if s == "-p" || s == "--module-path" { skip_next = true; continue; }
-                                        if s == "${classpath}" { continue; }
-                                        if s == "${module_path}" { continue; }
-                                        if s.starts_with("-DignoreList=") { continue; }
-                                        let val = replace_vars(s, auth, &info.id, &assets_dir, instance_minecraft_dir, asset_index_id, &natives_dir, &libraries_dir);
-                                        if val.starts_with("-DignoreList=") { continue; }
-                                        args_content.push_str(&format!("{}\n", escape_arg(&val)));
-                                    }
-                                     
-   if (s.starts_with("--add-exports") || s.starts_with("--add-opens")) && s.contains("cpw.mods.") { continue; }
-                                }
-                            }
-                        }
+/// Resolves rule-gated `Complex` arguments into a flat list of raw (unexpanded) values.
+fn flatten_arguments(args: Option<&Vec<Argument>>) -> Vec<String> {
+    let mut out = Vec::new();
+    for arg in args.into_iter().flatten() {
+        match arg {
+            Argument::Simple(s) => out.push(s.clone()),
+            Argument::Complex(c) => {
+                if check_rules(&Some(c.rules.clone())) {
+                    match &c.value {
+                        ArgumentValue::Single(s) => out.push(s.clone()),
+                        ArgumentValue::Multiple(vec) => out.extend(vec.iter().cloned()),
                     }
                 }
             }
         }
     }
+    out
+}
 
-    let cp_sep = if get_os_name() == "windows" { ";" } else { ":" };
-    let mut unique_entries = std::collections::HashSet::new();
-    let mut final_classpath = Vec::new();
-    let mut module_path_artifacts = std::collections::HashSet::new();
-                }
-            }
-        }
+/// Drops the JVM args the launcher provides itself: classpath, module path (with their values),
+/// cpw.mods opens/exports that clash with our module path, the version's ignoreList, and the
+/// macOS main-thread flag (added by `macos_jvm_flags`).
+fn filter_forge_jvm_args(args: &[String]) -> Vec<String> {
+    let mut out = Vec::new();
+    let mut skip_next = false;
+    for s in args {
+        if skip_next { skip_next = false; continue; }
+        if s == "-cp" || s == "-p" || s == "--module-path" { skip_next = true; continue; }
+        if s == "${classpath}" || s == "${module_path}" { continue; }
+        if (s.starts_with("--add-exports") || s.starts_with("--add-opens")) && s.contains("cpw.mods.") { continue; }
+        if s.starts_with("-DignoreList=") { continue; }
+        if s == "-XstartOnFirstThread" { continue; }
+        out.push(s.clone());
     }
+    out
+}
 
-    let cp_sep = if get_os_name() == "windows" { ";" } else { ":" };
-    let mut unique_entries = std::collections::HashSet::new();
-    let mut final_classpath = Vec::new();
-    let mut module_path_artifacts = std::collections::HashSet::new();
-    for path in &module_path_libs {
-        if let Some(maven) = library_map.get(path) {
-            module_path_artifacts.insert((maven.group.clone(), maven.artifact.clone()));
-        }
-    }
-    let forge_classpath_blacklist = [
-        "asm", "asm-commons", "asm-tree", "asm-util", "asm-analysis",
-        "java-objc-bridge", "jna", "oshi-core",
-        "sponge-mixin", "mixin", "jakarta.activation", "jakarta.xml.bind"
-    ];
-    for entry in classpath_entries {
-        if unique_entries.insert(entry.clone()) {
-            let normalized_entry = normalize_path_for_comparison(&entry);
-            let is_on_module_path = module_path_libs.contains(&normalized_entry);
-            let is_artifact_conflict = if let Some(maven) = library_map.get(&normalized_entry) {
-                module_path_artifacts.contains(&(maven.group.clone(), maven.artifact.clone()))
-            } else { false };
-            let is_blacklisted = {
-                let file_name = entry.file_name().and_then(|n| n.to_str()).unwrap_or("").to_lowercase();
-                forge_classpath_blacklist.iter().any(|&b| file_name.contains(b))
-            };
-            let force_include_bootstrap = {
-                let file_name = entry.file_name().and_then(|n| n.to_str()).unwrap_or("").to_lowercase();
-                if file_name.contains("bootstraplauncher") {
-                    true
-                } else {
-                    if let Some(maven) = library_map.get(&normalized_entry) {
-                        maven.group == "cpw.mods" && maven.artifact == "bootstraplauncher"
-                    } else { false }
-                }
-            };
-            if force_include_bootstrap || (!is_on_module_path && !is_artifact_conflict && !is_blacklisted) {
-                final_classpath.push(entry);
-            }
-        }
-    }
-    for (_, maven) in &library_map {
-        if maven.group == "cpw.mods" && maven.artifact == "bootstraplauncher" {
-            if let Some(rel) = maven_path(maven) {
-                let full = libraries_dir.join(rel);
-                let full_norm = normalize_path_for_comparison(&full);
-                let exists_in_cp = final_classpath.iter().any(|p| normalize_path_for_comparison(p) == full_norm);
-                if !exists_in_cp {
-                    final_classpath.push(full);
-                }
-            }
-        }
-    }
-    let mut bootstrap_target_scan: Option<PathBuf> = None;
-    if bootstrap_target_scan.is_none() {
-        let base = libraries_dir.join("cpw").join("mods").join("bootstraplauncher");
-        if base.exists() {
-            if let Ok(entries) = fs::read_dir(&base) {
-                'outer_bootstrap: for entry in entries.flatten() {
-                    let p = entry.path();
-                    if p.is_dir() {
-                        if let Ok(files) = fs::read_dir(&p) {
-                            for f in files.flatten() {
-                                let fp = f.path();
-                                if let Some(name) = fp.file_name().and_then(|n| n.to_str()) {
-                                    if name.starts_with("bootstraplauncher-") && name.ends_with(".jar") {
-                                        bootstrap_target_scan = Some(fp);
-                                        break 'outer_bootstrap;
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-        }
-    }
-    if let Some(target) = bootstrap_target_scan.clone() {
-        if !target.exists() {
-            let rel = if let Ok(stripped) = target.strip_prefix(&libraries_dir) {
-                stripped.to_string_lossy().replace('\\', "/")
-            } else {
-                String::new()
-            };
-            if !rel.is_empty() {
-                let url = format!("https://maven.minecraftforge.net/{}", rel);
-                let _ = download_file(&url, &target, None);
-            }
-        }
-        let target_norm = normalize_path_for_comparison(&target);
-        let exists_in_cp = final_classpath.iter().any(|p| normalize_path_for_comparison(p) == target_norm);
-        if !exists_in_cp {
-            final_classpath.push(target);
-        }
+/// Drops `--demo` and the version's resolution args; the launcher writes its own `--width/--height`.
+fn filter_game_args(args: &[String]) -> Vec<String> {
+    let mut out = Vec::new();
+    let mut skip_next = false;
+    for s in args {
+        if skip_next { skip_next = false; continue; }
+        if s == "--demo" { continue; }
+        if s == "--width" || s == "--height" { skip_next = true; continue; }
+        if s == "${resolution_width}" || s == "${resolution_height}" { continue; }
+        out.push(s.clone());
     }
-    let mut fmlloader_target: Option<PathBuf> = None;
-    for (_, maven) in &library_map {
-        if maven.group == "net.minecraftforge" && maven.artifact == "fmlloader" {
-            if let Some(rel) = maven_path(maven) {
-                fmlloader_target = Some(libraries_dir.join(rel));
-                break;
-            }
-        }
-    }
-    if fmlloader_target.is_none() {
-        let base = libraries_dir.join("net").join("minecraftforge").join("fmlloader");
-        if base.exists() {
-            if let Ok(entries) = fs::read_dir(&base) {
-                'outer: for entry in entries.flatten() {
-                    let p = entry.path();
-                    if p.is_dir() {
-                        if let Ok(files) = fs::read_dir(&p) {
-                            for f in files.flatten() {
-                                let fp = f.path();
-                                if let Some(name) = fp.file_name().and_then(|n| n.to_str()) {
-                                    if name.starts_with("fmlloader-") && name.ends_with(".jar") {
-                                        fmlloader_target = Some(fp);
-                                        break 'outer;
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-        }
+    out
+}
+
+#[allow(clippy::too_many_arguments)]
+fn build_forge_args_content(
+    console_level: &str,
+    module_path: &[PathBuf],
+    jvm_args: &[String],
+    classpath: &[PathBuf],
+    main_class: &str,
+    width: u32,
+    height: u32,
+    game_args: &[String],
+) -> String {
+    let mut content = String::new();
+    // Force disable early window in args.txt
+    content.push_str("-Dfml.earlyprogresswindow=false\n");
+    content.push_str("-Dfml.earlyWindowControl=false\n");
+    content.push_str(&format!("-Dforge.logging.console.level={}\n", console_level));
+    if !module_path.is_empty() {
+        content.push_str("-p\n");
+        content.push_str(&format!("{}\n", escape_arg(&join_paths(module_path))));
     }
-    if let Some(target) = fmlloader_target.clone() {
-        if !target.exists() {
-            let rel = if let Ok(stripped) = target.strip_prefix(&libraries_dir) {
-                stripped.to_string_lossy().replace('\\', "/")
-            } else {
-                String::new()
-            };
-            if !rel.is_empty() {
-                let url = format!("https://maven.minecraftforge.net/{}", rel);
-                let _ = download_file(&url, &target, None);
-            }
-        }
-        let target_norm = normalize_path_for_comparison(&target);
-        let exists_in_cp = final_classpath.iter().any(|p| normalize_path_for_comparison(p) == target_norm);
-        if !exists_in_cp {
-            final_classpath.push(target);
-        }
+    for arg in jvm_args {
+        content.push_str(&format!("{}\n", escape_arg(arg)));
     }
-    let final_classpath_existing = final_classpath
-        .into_iter()
-        .filter(|p| p.exists())
-        .collect::<Vec<_>>();
-    let cp_str = final_classpath_existing
-        .iter()
-        .map(|p| p.to_string_lossy().to_string())
-        .collect::<Vec<_>>()
-        .join(cp_sep);
-    args_content.push_str("-cp\n");
-    args_content.push_str(&format!("{}\n", escape_arg(&cp_str)));
-    
-    // Select effective main class for Forge with fallback
-    let mut bootstrap_target: Option<PathBuf> = None;
-    for (_, maven) in &library_map {
-        if maven.group == "cpw.mods" && maven.artifact == "bootstraplauncher" {
-            if let Some(rel) = maven_path(maven) {
-                bootstrap_target = Some(libraries_dir.join(rel));
-                break;
-            }
-        }
+    content.push_str("-cp\n");
+    content.push_str(&format!("{}\n", escape_arg(&join_paths(classpath))));
+    content.push_str(&format!("{}\n", escape_arg(main_class)));
+    content.push_str(&format!("--width\n{}\n", width));
+    content.push_str(&format!("--height\n{}\n", height));
+    for arg in game_args {
+        content.push_str(&format!("{}\n", escape_arg(arg)));
     }
-    let effective_main_class = if let Some(bt) = bootstrap_target.or(bootstrap_target_scan.clone()) {
-        if bt.exists() {
-            "cpw.mods.bootstraplauncher.BootstrapLauncher".to_string()
-        } else if let Some(fml) = fmlloader_target.clone() {
-            if fml.exists() {
-                "net.minecraftforge.bootstrap.ForgeBootstrap".to_string()
-            } else {
-                "cpw.mods.bootstraplauncher.BootstrapLauncher".to_string()
-            }
-        } else {
-            "cpw.mods.bootstraplauncher.BootstrapLauncher".to_string()
-        }
-    } else if let Some(fml) = fmlloader_target.clone() {
-        if fml.exists() {
-            "net.minecraftforge.bootstrap.ForgeBootstrap".to_string()
-        } else {
-            "cpw.mods.bootstraplauncher.BootstrapLauncher".to_string()
-        }
-    } else {
-        "cpw.mods.bootstraplauncher.BootstrapLauncher".to_string()
-    };
-    args_content.push_str(&format!("{}\n", escape_arg(&effective_main_class)));
-
-    args_content.push_str("--width\n854\n");
-    args_content.push_str("--height\n480\n");
-
-    if let Some(args) = &info.arguments {
-        if let Some(game_args) = &args.game {
-            let mut skip_next = false;
-            for arg in game_args {
-                match arg {
-                    Argument::Simple(s) => {
-                        if skip_next { skip_next = false; continue; }
-                        if s == "--demo" { continue; }
-                        if s == "--width" || s == "--height" { skip_next = true; continue; }
-                        if s == "${resolution_width}" || s == "${resolution_height}" { continue; }
-                        let val = replace_vars(s, auth, &info.id, &assets_dir, instance_minecraft_dir, asset_index_id, &natives_dir, &libraries_dir);
-                        args_content.push_str(&format!("{}\n", escape_arg(&val)));
-                    }
-                    Argument::Complex(c) => {
-                        if check_rules(&Some(c.rules.clone())) {
-                            match &c.value {
-                                ArgumentValue::Single(s) => {
-                                    if skip_next { skip_next = false; continue; }
-                                    if s == "--demo" { continue; }
-                                    if s == "--width" || s == "--height" { skip_next = true; continue; }
-                                    if s == "${resolution_width}" || s == "${resolution_height}" { continue; }
-                                    let val = replace_vars(s, auth, &info.id, &assets_dir, instance_minecraft_dir, asset_index_id, &natives_dir, &libraries_dir);
-                                    args_content.push_str(&format!("{}\n", escape_arg(&val)));
-                                }
-                                ArgumentValue::Multiple(vec) => {
-                                    for s in vec {
-                                        if skip_next { skip_next = false; continue; }
-                                        if s == "--demo" { continue; }
-                                        if s == "--width" || s == "--height" { skip_next = true; continue; }
-                                        if s == "${resolution_width}" || s == "${resolution_height}" { continue; }
-                                        let val = replace_vars(s, auth, &info.id, &assets_dir, instance_minecraft_dir, asset_index_id, &natives_dir, &libraries_dir);
-                                        args_content.push_str(&format!("{}\n", escape_arg(&val)));
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-        }
-    } else if let Some(legacy_args) = &info.minecraft_arguments {
-        let mut skip_next = false;
-        for part in legacy_args.split_whitespace() {
-            if skip_next { skip_next = false; continue; }
-            if part == "--demo" { continue; }
-            if part == "--width" || part == "--height" { skip_next = true; continue; }
-            if part == "${resolution_width}" || part == "${resolution_height}" { continue; }
-            let val = replace_vars(part, auth, &info.id, &assets_dir, instance_minecraft_dir, asset_index_id, &natives_dir, &libraries_dir);
-            args_content.push_str(&format!("{}\n", escape_arg(&val)));
-        }
-    }
-
-    let _ = fs::write(&args_file_path, args_content.clone());
+    content
+}
 
-    let logs_dir = instance_minecraft_dir.join("logs");
-    let _ = fs::create_dir_all(&logs_dir);
-    let mut lib_checks: Vec<String> = Vec::new();
+/// Existence/size report of the core Forge jars for launch-debug.txt.
+fn forge_library_checks(library_map: &HashMap<String, MavenName>, libraries_dir: &Path) -> Vec<String> {
+    let mut lib_checks = Vec::new();
     for (grp, art) in [
         ("cpw.mods", "bootstraplauncher"),
         ("cpw.mods", "modlauncher"),
         ("cpw.mods", "securejarhandler"),
         ("net.minecraftforge", "fmlloader"),
     ] {
-        let mut found_path: Option<PathBuf> = None;
-        for (_, maven) in &library_map {
-            if maven.group == grp && maven.artifact == art {
-                if let Some(rel) = maven_path(maven) {
-                    found_path = Some(libraries_dir.join(rel));
-                    break;
-                }
-            }
-        }
+        let found_path = library_map.values()
+            .find(|m| m.group == grp && m.artifact == art)
+            .and_then(maven_path)
+            .map(|rel| libraries_dir.join(rel));
         if let Some(p) = found_path {
             let exists = p.exists();
             let size = if exists { fs::metadata(&p).map(|m| m.len()).unwrap_or(0) } else { 0 };
@@ -699,24 +499,77 @@ pub fn build_forge_command(
             lib_checks.push(format!("{}:{} path=<not-found-in-version.json>", grp, art));
         }
     }
-    let final_mp_dbg = module_path_set.into_iter().collect::<Vec<_>>().join(mp_sep);
-    let debug_content = format!(
-        "JAVA_PATH={}\nJAVA_MAJOR={}\nJAVA_REQUIRED={}\nWORK_DIR={}\nNATIVES_DIR={}\nMAIN_CLASS={}\nMODULE_PATH={}\nCLASSPATH={}\nJVM_FLAGS={}\nARGS_FILE={}\nARGS_CONTENT_BEGIN\n{}\nARGS_CONTENT_END\nLIB_CHECKS_BEGIN\n{}\nLIB_CHECKS_END\n",
-        java_path.to_string_lossy(),
-        java_major,
-        required_java,
-        instance_minecraft_dir.to_string_lossy(),
-        natives_dir.to_string_lossy(),
-        effective_main_class,
-        final_mp_dbg,
-        cp_str,
-        jvm_flags.join(" "),
-        args_file_path.to_string_lossy(),
-        args_content,
-        lib_checks.join("\n"),
-    );
-    let _ = fs::write(logs_dir.join("launch-debug.txt"), debug_content);
-    cmd.arg(format!("@{}", args_file_path.to_string_lossy()));
-    cmd.current_dir(instance_minecraft_dir);
-    Ok(cmd)
+    lib_checks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(args: &[&str]) -> Vec<String> {
+        args.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn jvm_filter_drops_classpath_and_module_path_with_values() {
+        let args = strings(&[
+            "-p", "${module_path}", "--module-path", "a.jar", "-cp", "${classpath}",
+            "--add-opens", "java.base/java.lang.invoke=cpw.mods.securejarhandler",
+            "--add-exports=java.base/sun.security.util=cpw.mods.securejarhandler",
+            "-DignoreList=bootstraplauncher,securejarhandler",
+            "-DlibraryDirectory=${library_directory}",
+        ]);
+        assert_eq!(
+            filter_forge_jvm_args(&args),
+            strings(&["--add-opens", "java.base/java.lang.invoke=cpw.mods.securejarhandler", "-DlibraryDirectory=${library_directory}"])
+        );
+    }
+
+    #[test]
+    fn game_filter_drops_demo_and_resolution() {
+        let args = strings(&["--username", "${auth_player_name}", "--demo", "--width", "${resolution_width}", "--height", "480", "${resolution_height}"]);
+        assert_eq!(filter_game_args(&args), strings(&["--username", "${auth_player_name}"]));
+    }
+
+    #[test]
+    fn combined_progress_weighs_both_downloads() {
+        let progress = CombinedProgress::default();
+        assert_eq!(progress.percent(), 60);
+        progress.libraries_total.store(100, Ordering::SeqCst);
+        progress.libraries_done.store(100, Ordering::SeqCst);
+        assert_eq!(progress.percent(), 75);
+        progress.assets_total.store(200, Ordering::SeqCst);
+        assert_eq!(progress.percent(), 65);
+        progress.assets_done.store(200, Ordering::SeqCst);
+        assert_eq!(progress.percent(), 75);
+    }
+
+    #[test]
+    fn flatten_keeps_simple_args_in_order() {
+        let args = vec![Argument::Simple("--launchTarget".into()), Argument::Simple("forgeclient".into())];
+        assert_eq!(flatten_arguments(Some(&args)), strings(&["--launchTarget", "forgeclient"]));
+        assert!(flatten_arguments(None).is_empty());
+    }
+
+    #[test]
+    fn args_content_orders_jvm_main_class_and_game_args() {
+        let content = build_forge_args_content(
+            "debug",
+            &[],
+            &strings(&["-Dfoo=bar"]),
+            &[PathBuf::from("client.jar")],
+            "cpw.mods.bootstraplauncher.BootstrapLauncher",
+            1280,
+            720,
+            &strings(&["--launchTarget", "forgeclient"]),
+        );
+        let lines = content.lines().collect::<Vec<_>>();
+        assert_eq!(lines[2], "-Dforge.logging.console.level=debug");
+        assert!(!lines.contains(&"-p"));
+        let cp = lines.iter().position(|l| *l == "-cp").unwrap();
+        assert_eq!(lines[cp - 1], "-Dfoo=bar");
+        assert_eq!(lines[cp + 1], "client.jar");
+        assert_eq!(lines[cp + 2], "cpw.mods.bootstraplauncher.BootstrapLauncher");
+        assert_eq!(&lines[cp + 3..], ["--width", "1280", "--height", "720", "--launchTarget", "forgeclient"]);
+    }
 }
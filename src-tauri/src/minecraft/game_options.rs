@@ -0,0 +1,220 @@
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::Path;
+use super::models::AssetIndex;
+use super::downloader::download_game_file;
+use super::launch_logic::{parse_mc_version, resolve_complete_version_info};
+
+/// `options.txt` language code for a version: `es_es` since 1.11, `es_ES` before.
+pub fn language_code(lang: &str, mc_version: &str) -> String {
+    let lang = lang.trim().to_lowercase();
+    match (parse_mc_version(mc_version), lang.split_once('_')) {
+        (Some((minor, _)), Some((language, region))) if minor < 11 => format!("{}_{}", language, region.to_uppercase()),
+        _ => lang,
+    }
+}
+
+/// Adds `lang:<code>` to `options.txt` contents that have no language yet. A language the player
+/// picked in game is kept; a missing or freshly reset file gets the instance default.
+pub fn with_default_language(contents: &str, code: &str) -> Option<String> {
+    if contents.lines().any(|l| l.starts_with("lang:")) {
+        return None;
+    }
+    let mut updated = contents.to_string();
+    if !updated.is_empty() && !updated.ends_with('\n') {
+        updated.push('\n');
+    }
+    updated.push_str(&format!("lang:{}\n", code));
+    Some(updated)
+}
+
+pub fn apply_game_language(minecraft_dir: &Path, lang: &str, mc_version: &str) -> Result<(), String> {
+    let options_path = minecraft_dir.join("options.txt");
+    let contents = fs::read_to_string(&options_path).unwrap_or_default();
+    if let Some(updated) = with_default_language(&contents, &language_code(lang, mc_version)) {
+        fs::write(&options_path, updated).map_err(|e| format!("Failed to write {}: {}", options_path.display(), e))?;
+    }
+    Ok(())
+}
+
+/// Puts `file/<pack_file>` last in `resourcePacks`, the top of the in-game list, so it wins over
+/// every other pack. Adds the key when the file has none; a list it can't read is left alone
+/// rather than replaced, so the player's packs aren't lost.
+pub fn with_resource_pack_last(contents: &str, pack_file: &str) -> String {
+    let entry = format!("file/{}", pack_file);
+    let mut found = false;
+    let mut lines = contents.lines()
+        .map(|line| match line.strip_prefix("resourcePacks:") {
+            Some(list) => {
+                found = true;
+                match serde_json::from_str::<Vec<String>>(list) {
+                    Ok(mut packs) => {
+                        packs.retain(|p| p != &entry);
+                        packs.push(entry.clone());
+                        format!("resourcePacks:{}", serde_json::to_string(&packs).unwrap_or_default())
+                    }
+                    Err(_) => line.to_string(),
+                }
+            }
+            None => line.to_string(),
+        })
+        .collect::<Vec<_>>();
+    if !found {
+        lines.push(format!("resourcePacks:{}", serde_json::to_string(&["vanilla", entry.as_str()]).unwrap_or_default()));
+    }
+    lines.join("\n") + "\n"
+}
+
+pub fn enable_resource_pack_last(minecraft_dir: &Path, pack_file: &str) -> Result<(), String> {
+    let options_path = minecraft_dir.join("options.txt");
+    let contents = fs::read_to_string(&options_path).unwrap_or_default();
+    let updated = with_resource_pack_last(&contents, pack_file);
+    if updated != contents {
+        fs::write(&options_path, updated).map_err(|e| format!("Failed to write {}: {}", options_path.display(), e))?;
+    }
+    Ok(())
+}
+
+/// Sets `key:value` pairs in `options.txt` contents, replacing existing keys and appending the rest.
+pub fn with_option_values(contents: &str, values: &[(&str, String)]) -> String {
+    let mut pending = values.to_vec();
+    let mut lines = contents.lines()
+        .map(|line| {
+            let key = line.split_once(':').map(|(k, _)| k).unwrap_or(line);
+            match pending.iter().position(|(k, _)| *k == key) {
+                Some(i) => {
+                    let (key, value) = pending.remove(i);
+                    format!("{}:{}", key, value)
+                }
+                None => line.to_string(),
+            }
+        })
+        .collect::<Vec<_>>();
+    lines.extend(pending.into_iter().map(|(key, value)| format!("{}:{}", key, value)));
+    lines.join("\n") + "\n"
+}
+
+/// Video settings for weak PCs: fast graphics (`graphicsMode` since 1.16, `fancyGraphics` before),
+/// shorter render distance, minimal particles and no entity shadows.
+pub fn low_end_options(mc_version: &str) -> Vec<(&'static str, String)> {
+    let mut options = match parse_mc_version(mc_version) {
+        Some((minor, _)) if minor < 16 => vec![("fancyGraphics", "false".to_string())],
+        _ => vec![("graphicsMode", "0".to_string())],
+    };
+    options.extend([
+        ("renderDistance", "8".to_string()),
+        ("particles", "2".to_string()),
+        ("entityShadows", "false".to_string()),
+    ]);
+    options
+}
+
+pub fn apply_option_values(minecraft_dir: &Path, values: &[(&str, String)]) -> Result<(), String> {
+    let options_path = minecraft_dir.join("options.txt");
+    let contents = fs::read_to_string(&options_path).unwrap_or_default();
+    let updated = with_option_values(&contents, values);
+    if updated != contents {
+        fs::create_dir_all(minecraft_dir).map_err(|e| e.to_string())?;
+        fs::write(&options_path, updated).map_err(|e| format!("Failed to write {}: {}", options_path.display(), e))?;
+    }
+    Ok(())
+}
+
+/// The `values` whose key `options.txt` contents don't have yet.
+pub fn missing_option_values(contents: &str, values: &[(&'static str, String)]) -> Vec<(&'static str, String)> {
+    values.iter()
+        .filter(|(key, _)| !contents.lines().any(|line| line.split_once(':').is_some_and(|(k, _)| k == *key)))
+        .cloned()
+        .collect()
+}
+
+/// `apply_option_values` for defaults: a key the player already has, changed in game or not, is kept.
+pub fn apply_missing_option_values(minecraft_dir: &Path, values: &[(&'static str, String)]) -> Result<(), String> {
+    let contents = fs::read_to_string(minecraft_dir.join("options.txt")).unwrap_or_default();
+    let missing = missing_option_values(&contents, values);
+    if missing.is_empty() {
+        return Ok(());
+    }
+    apply_option_values(minecraft_dir, &missing)
+}
+
+/// Language codes shipped in an asset index (`minecraft/lang/<code>.json` or `.lang`), plus
+/// `en_us`, which lives in the client jar.
+pub fn languages_in_index(index: &AssetIndex) -> Vec<String> {
+    let mut codes = index.objects.keys()
+        .filter_map(|key| key.strip_prefix("minecraft/lang/"))
+        .filter_map(|file| file.strip_suffix(".json").or_else(|| file.strip_suffix(".lang")))
+        .map(str::to_lowercase)
+        .collect::<BTreeSet<_>>();
+    codes.insert("en_us".to_string());
+    codes.into_iter().collect()
+}
+
+/// Languages available for `mc_version`, downloading its asset index if needed.
+pub fn available_languages(base_path: &Path, mc_version: &str) -> Result<Vec<String>, String> {
+    let info = resolve_complete_version_info(mc_version, &base_path.join("versions"))?;
+    let index_ref = info.asset_index.ok_or_else(|| format!("La versión {} no tiene índice de assets", mc_version))?;
+    let index_path = base_path.join("assets").join("indexes").join(format!("{}.json", index_ref.id));
+    download_game_file(&index_ref.url, &index_path, Some(&index_ref.sha1))?;
+    let index: AssetIndex = serde_json::from_str(&fs::read_to_string(&index_path).map_err(|e| e.to_string())?)
+        .map_err(|e| format!("Invalid asset index {}: {}", index_ref.id, e))?;
+    Ok(languages_in_index(&index))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn language_is_only_added_when_missing() {
+        assert_eq!(with_default_language("", "es_es").as_deref(), Some("lang:es_es\n"));
+        assert_eq!(with_default_language("fov:0.5", "es_es").as_deref(), Some("fov:0.5\nlang:es_es\n"));
+        assert_eq!(with_default_language("fov:0.5\nlang:en_us\n", "es_es"), None);
+    }
+
+    #[test]
+    fn translation_pack_is_moved_to_the_top() {
+        let contents = "fov:0.5\nresourcePacks:[\"file/es.zip\",\"vanilla\",\"file/faithful.zip\"]\n";
+        assert_eq!(
+            with_resource_pack_last(contents, "es.zip"),
+            "fov:0.5\nresourcePacks:[\"vanilla\",\"file/faithful.zip\",\"file/es.zip\"]\n"
+        );
+        assert_eq!(with_resource_pack_last("", "es.zip"), "resourcePacks:[\"vanilla\",\"file/es.zip\"]\n");
+        let broken = "resourcePacks:[\"file/faithful.zip\",\n";
+        assert_eq!(with_resource_pack_last(broken, "es.zip"), broken);
+    }
+
+    #[test]
+    fn option_values_replace_or_append() {
+        let values = low_end_options("1.20.1");
+        assert_eq!(
+            with_option_values("graphicsMode:1\nfov:0.5\nrenderDistance:16\n", &values),
+            "graphicsMode:0\nfov:0.5\nrenderDistance:8\nparticles:2\nentityShadows:false\n"
+        );
+        assert_eq!(low_end_options("1.12.2")[0], ("fancyGraphics", "false".to_string()));
+    }
+
+    #[test]
+    fn defaults_never_override_the_players_options() {
+        let values = [("fovEffectScale", "0.0".to_string()), ("screenEffectScale", "0.0".to_string())];
+        assert_eq!(missing_option_values("fovEffectScale:1.0\nfov:0.5\n", &values), [("screenEffectScale", "0.0".to_string())]);
+        assert!(missing_option_values("screenEffectScale:0.5\nfovEffectScale:1.0\n", &values).is_empty());
+    }
+
+    #[test]
+    fn legacy_versions_use_uppercase_regions() {
+        assert_eq!(language_code("ES_ES", "1.20.1"), "es_es");
+        assert_eq!(language_code("es_es", "1.8.9"), "es_ES");
+        assert_eq!(language_code("es_es", "24w14a"), "es_es");
+    }
+
+    #[test]
+    fn languages_come_from_lang_assets() {
+        let index: AssetIndex = serde_json::from_value(serde_json::json!({"objects": {
+            "minecraft/lang/es_es.json": {"hash": "aa", "size": 1},
+            "minecraft/lang/es_MX.lang": {"hash": "bb", "size": 1},
+            "minecraft/sounds/ambient/cave/cave1.ogg": {"hash": "cc", "size": 1}
+        }})).unwrap();
+        assert_eq!(languages_in_index(&index), vec!["en_us", "es_es", "es_mx"]);
+    }
+}
@@ -0,0 +1,260 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use super::java::JavaRelease;
+use super::models::VersionInfo;
+
+/// Bumped when the lock changes shape; a lock of another version is replaced, not compared.
+pub const LOCK_VERSION: u32 = 1;
+// Next to the instance's `minecraft/` folder, like `attestation.json`
+const LOCK_FILE: &str = "instance.lock";
+// A drift report names this many differences and counts the rest
+const SHOWN_DIFFERENCES: usize = 3;
+
+/// Exactly what a successful prepare of an instance resolved to, in `<instance>/instance.lock`.
+/// Written by the first prepare; later prepares compare against it and report drift, so an
+/// event pack plays the same on every station and every night until an admin clears it.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct InstanceLock {
+    pub v: u32,
+    /// Unix seconds; not compared
+    #[serde(rename = "lockedAt", alias = "locked_at")]
+    pub locked_at: u64,
+    #[serde(rename = "mcVersion", alias = "mc_version")]
+    pub mc_version: String,
+    pub loader: String,
+    /// Loader build, `None` for vanilla
+    #[serde(rename = "loaderVersion", alias = "loader_version", default)]
+    pub loader_version: Option<String>,
+    /// SHA-1 of each version json the profile was merged from, by version id
+    #[serde(rename = "versionJsons", alias = "version_jsons")]
+    pub version_jsons: BTreeMap<String, String>,
+    /// SHA-1 of every library that applies on this system, by maven name
+    pub libraries: BTreeMap<String, String>,
+    /// SHA-1 of every file in `minecraft/mods`, by path relative to it
+    pub mods: BTreeMap<String, String>,
+    /// Runtime the game is started with; `None` when its `release` file can't be read
+    #[serde(default)]
+    pub java: Option<JavaRelease>,
+}
+
+pub fn lock_path(instance_path: &Path) -> PathBuf {
+    instance_path.join(LOCK_FILE)
+}
+
+/// Loader build inside a resolved profile id: `0.15.0` from `fabric-loader-0.15.0-1.20.1`,
+/// `47.2.0` from `1.20.1-forge-47.2.0` or `1.12.2-forge1.12.2-14.23.5.2859`.
+pub fn loader_version(loader: &str, profile_id: &str, mc_version: &str) -> Option<String> {
+    let version = match loader {
+        "fabric" => profile_id.strip_prefix("fabric-loader-")?
+            .strip_suffix(&format!("-{}", mc_version))?,
+        "forge" => {
+            let (_, build) = profile_id.split_once("forge")?;
+            let build = build.trim_start_matches('-');
+            build.strip_prefix(&format!("{}-", mc_version)).unwrap_or(build)
+        }
+        _ => return None,
+    };
+    Some(version.to_string()).filter(|v| !v.is_empty())
+}
+
+fn library_hashes(info: &VersionInfo, libraries_dir: &Path) -> Result<BTreeMap<String, String>, String> {
+    let mut hashes = BTreeMap::new();
+    for lib in info.libraries.iter().filter(|lib| super::utils::check_rules(&lib.rules)) {
+        let declared = lib.downloads.as_ref()
+            .and_then(|d| d.artifact.as_ref())
+            .map(|a| a.sha1.clone())
+            .filter(|sha1| !sha1.is_empty());
+        // Maven-only libraries (Fabric, Forge) declare no hash; the jar on disk is what runs
+        let sha1 = match declared {
+            Some(sha1) => sha1,
+            None => {
+                let path = super::utils::parse_maven_name(&lib.name)
+                    .and_then(|maven| super::utils::maven_path(&maven))
+                    .map(|relative| libraries_dir.join(relative))
+                    .filter(|path| path.is_file());
+                match path {
+                    Some(path) => super::downloader::file_sha1(&path)?,
+                    None => continue,
+                }
+            }
+        };
+        hashes.insert(lib.name.clone(), sha1);
+    }
+    Ok(hashes)
+}
+
+fn version_json_hashes(info: &VersionInfo, versions_dir: &Path) -> Result<BTreeMap<String, String>, String> {
+    let store = super::version_store::LocalVersions::new(versions_dir);
+    let mut hashes = BTreeMap::new();
+    for id in [Some(&info.id), info.inherits_from.as_ref()].into_iter().flatten() {
+        let path = store.json_path(id);
+        if path.is_file() {
+            hashes.insert(id.clone(), super::downloader::file_sha1(&path)?);
+        }
+    }
+    Ok(hashes)
+}
+
+fn mod_hashes(minecraft_dir: &Path) -> Result<BTreeMap<String, String>, String> {
+    let mut hashes = BTreeMap::new();
+    for (name, path) in super::archive::walk_files(&minecraft_dir.join("mods")) {
+        hashes.insert(name, super::downloader::file_sha1(&path)?);
+    }
+    Ok(hashes)
+}
+
+/// The lock for what `prepare_and_launch` just prepared; `java` is the binary of its command.
+pub fn capture(
+    base_path: &Path,
+    minecraft_dir: &Path,
+    loader: &str,
+    mc_version: &str,
+    info: &VersionInfo,
+    java: &Path,
+) -> Result<InstanceLock, String> {
+    Ok(InstanceLock {
+        v: LOCK_VERSION,
+        locked_at: crate::unix_now(),
+        mc_version: mc_version.to_string(),
+        loader: loader.to_string(),
+        loader_version: loader_version(loader, &info.id, mc_version),
+        version_jsons: version_json_hashes(info, &base_path.join("versions"))?,
+        libraries: library_hashes(info, &base_path.join("libraries"))?,
+        mods: mod_hashes(minecraft_dir)?,
+        java: super::java::runtime_home(java).and_then(|home| super::java::read_release(&home)),
+    })
+}
+
+fn map_drift(what: &str, locked: &BTreeMap<String, String>, current: &BTreeMap<String, String>, drift: &mut Vec<String>) {
+    for (name, sha1) in locked {
+        match current.get(name) {
+            None => drift.push(format!("{} quitado: {}", what, name)),
+            Some(now) if now != sha1 => drift.push(format!("{} cambiado: {}", what, name)),
+            Some(_) => {}
+        }
+    }
+    for name in current.keys().filter(|name| !locked.contains_key(*name)) {
+        drift.push(format!("{} añadido: {}", what, name));
+    }
+}
+
+/// Every difference between `locked` and `current`, worded for the player and staff.
+pub fn drift(locked: &InstanceLock, current: &InstanceLock) -> Vec<String> {
+    let mut drift = Vec::new();
+    if locked.mc_version != current.mc_version {
+        drift.push(format!("Minecraft {} → {}", locked.mc_version, current.mc_version));
+    }
+    if locked.loader != current.loader || locked.loader_version != current.loader_version {
+        let describe = |lock: &InstanceLock| match &lock.loader_version {
+            Some(version) => format!("{} {}", lock.loader, version),
+            None => lock.loader.clone(),
+        };
+        drift.push(format!("Loader {} → {}", describe(locked), describe(current)));
+    }
+    map_drift("Perfil de versión", &locked.version_jsons, &current.version_jsons, &mut drift);
+    map_drift("Librería", &locked.libraries, &current.libraries, &mut drift);
+    map_drift("Mod", &locked.mods, &current.mods, &mut drift);
+    // An unreadable runtime says nothing about which build runs
+    if let (Some(locked), Some(current)) = (&locked.java, &current.java) {
+        if locked != current {
+            drift.push(format!("Java {} → {}", locked.runtime_id(), current.runtime_id()));
+        }
+    }
+    drift
+}
+
+pub fn drift_message(drift: &[String]) -> String {
+    let shown = drift.iter().take(SHOWN_DIFFERENCES).cloned().collect::<Vec<_>>().join("; ");
+    match drift.len().saturating_sub(SHOWN_DIFFERENCES) {
+        0 => format!("La instancia no coincide con instance.lock: {}", shown),
+        more => format!("La instancia no coincide con instance.lock: {} y {} más", shown, more),
+    }
+}
+
+pub fn load(instance_path: &Path) -> Option<InstanceLock> {
+    fs::read_to_string(lock_path(instance_path)).ok()
+        .and_then(|json| serde_json::from_str::<InstanceLock>(&json).ok())
+        .filter(|lock| lock.v == LOCK_VERSION)
+}
+
+/// Compares `current` with the instance's lock and returns the drift. Without a (readable) lock
+/// `current` becomes the lock; an existing one is never rewritten here.
+pub fn check(instance_path: &Path, current: &InstanceLock) -> Result<Vec<String>, String> {
+    if let Some(locked) = load(instance_path) {
+        return Ok(drift(&locked, current));
+    }
+    let json = serde_json::to_string_pretty(current).map_err(|e| e.to_string())?;
+    fs::write(lock_path(instance_path), json).map_err(|e| e.to_string())?;
+    Ok(Vec::new())
+}
+
+fn instance_path(instance_id: &str) -> Result<PathBuf, String> {
+    crate::load_instances().into_iter().find(|i| i.id == instance_id)
+        .map(|i| PathBuf::from(i.path))
+        .ok_or_else(|| "Instance not found".to_string())
+}
+
+#[tauri::command]
+pub fn get_instance_lock(instance_id: String) -> Result<Option<InstanceLock>, String> {
+    Ok(load(&instance_path(&instance_id)?))
+}
+
+/// Accepts the instance as it is now: the next successful prepare writes a new lock.
+#[tauri::command]
+pub fn clear_instance_lock(password: String, instance_id: String) -> Result<(), String> {
+    crate::ensure_admin(password)?;
+    match fs::remove_file(lock_path(&instance_path(&instance_id)?)) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.to_string()),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lock(mods: &[(&str, &str)]) -> InstanceLock {
+        InstanceLock {
+            v: LOCK_VERSION,
+            locked_at: 1,
+            mc_version: "1.20.1".to_string(),
+            loader: "forge".to_string(),
+            loader_version: Some("47.2.0".to_string()),
+            version_jsons: BTreeMap::from([("1.20.1".to_string(), "aa".to_string())]),
+            libraries: BTreeMap::from([("org.ow2.asm:asm:9.5".to_string(), "bb".to_string())]),
+            mods: mods.iter().map(|(name, sha1)| (name.to_string(), sha1.to_string())).collect(),
+            java: None,
+        }
+    }
+
+    #[test]
+    fn loader_builds_come_from_the_profile_id() {
+        assert_eq!(loader_version("fabric", "fabric-loader-0.15.0-1.20.1", "1.20.1").as_deref(), Some("0.15.0"));
+        assert_eq!(loader_version("forge", "1.20.1-forge-47.2.0", "1.20.1").as_deref(), Some("47.2.0"));
+        assert_eq!(loader_version("forge", "1.12.2-forge1.12.2-14.23.5.2859", "1.12.2").as_deref(), Some("14.23.5.2859"));
+        assert_eq!(loader_version("vanilla", "1.20.1", "1.20.1"), None);
+    }
+
+    #[test]
+    fn first_prepare_locks_and_later_ones_report_drift() {
+        let dir = tempfile::tempdir().unwrap();
+        let locked = lock(&[("create.jar", "11"), ("jei.jar", "22")]);
+        assert!(check(dir.path(), &locked).unwrap().is_empty());
+        assert!(check(dir.path(), &InstanceLock { locked_at: 99, ..locked.clone() }).unwrap().is_empty());
+
+        let mut current = lock(&[("create.jar", "33"), ("extra.jar", "44")]);
+        current.loader_version = Some("47.3.0".to_string());
+        let drift = check(dir.path(), &current).unwrap();
+        assert_eq!(drift, vec![
+            "Loader forge 47.2.0 → forge 47.3.0",
+            "Mod cambiado: create.jar",
+            "Mod quitado: jei.jar",
+            "Mod añadido: extra.jar",
+        ]);
+        assert_eq!(load(dir.path()), Some(locked));
+        assert!(drift_message(&drift).ends_with("Mod quitado: jei.jar y 1 más"));
+    }
+}
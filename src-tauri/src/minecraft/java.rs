@@ -1,5 +1,7 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
 use std::process::Command;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::fs;
 use tauri::{AppHandle, Emitter};
 
@@ -9,6 +11,11 @@ pub fn get_java_path(minecraft_version: &str) -> Result<PathBuf, String> {
 }
 
 pub fn get_java_path_for_major(required_version: u32) -> Result<PathBuf, String> {
+    get_java_path_in(Path::new(&crate::get_instances_dir()), required_version)
+}
+
+/// Java runtimes live in `<base_path>/java/<major>`; sandboxed instances have their own base.
+pub fn get_java_path_in(base_path: &Path, required_version: u32) -> Result<PathBuf, String> {
     if let Ok(version) = get_system_java_version("java") {
         // Use system Java ONLY if the major version matches exactly
         // Newer majors (e.g., 21/25) can break Forge 1.20.1 which expects Java 17
@@ -16,60 +23,172 @@ pub fn get_java_path_for_major(required_version: u32) -> Result<PathBuf, String>
             return Ok(PathBuf::from("java"));
         }
     }
-    let appdata = crate::get_instances_dir();
-    let embedded = PathBuf::from(&appdata)
-        .join("java")
-        .join(format!("{}", required_version))
-        .join("bin")
-        .join(if cfg!(target_os = "windows") { "java.exe" } else { "java" });
-    if embedded.exists() {
+    if let Some(embedded) = runtime_executable(&base_path.join("java").join(format!("{}", required_version))) {
         return Ok(embedded);
     }
-    
-    // Check for "javaw.exe" on Windows as alternative
-    if cfg!(target_os = "windows") {
-        let embedded_javaw = PathBuf::from(&appdata)
-            .join("java")
-            .join(format!("{}", required_version))
-            .join("bin")
-            .join("javaw.exe");
-        if embedded_javaw.exists() {
-             return Ok(embedded_javaw);
+
+    Err(format!("No suitable Java {} found", required_version))
+}
+
+/// The Java binary of a runtime folder: `bin/java`, or on Windows `bin/javaw.exe` as alternative.
+pub fn runtime_executable(dir: &Path) -> Option<PathBuf> {
+    let java = dir.join("bin").join(if cfg!(target_os = "windows") { "java.exe" } else { "java" });
+    if java.exists() {
+        return Some(java);
+    }
+    let javaw = dir.join("bin").join("javaw.exe");
+    (cfg!(target_os = "windows") && javaw.exists()).then_some(javaw)
+}
+
+/// Runtime folder `instance` is pinned to (see `Instance::java_runtime`), under `base_path`.
+pub fn pinned_runtime(base_path: &Path, instance: &crate::Instance) -> Option<PathBuf> {
+    let relative = instance.java_runtime.as_deref().filter(|r| !r.is_empty())?;
+    crate::path_policy::join_inside(base_path, relative).ok()
+}
+
+/// Pin a saved instance keeps: the stored one while its version is unchanged, none once the
+/// version changes. Whatever the webview sends is ignored; pins only come from offline bundles.
+pub fn kept_runtime(existing: Option<&crate::Instance>, version: &str) -> Option<String> {
+    existing.filter(|e| e.version == version).and_then(|e| e.java_runtime.clone())
+}
+
+/// `get_java_path_in` for a launch: a pinned runtime is used even when a system Java of the same
+/// major is around, so the game runs on the exact build it was exported with.
+pub fn get_instance_java_path(base_path: &Path, required_version: u32, pinned: Option<&Path>) -> Result<PathBuf, String> {
+    if let Some(dir) = pinned {
+        match runtime_executable(dir) {
+            Some(java) => return Ok(java),
+            None => eprintln!("[java] Pinned Java runtime {} not found; using Java {}", dir.display(), required_version),
         }
     }
+    get_java_path_in(base_path, required_version)
+}
 
-    Err(format!("No suitable Java {} found", required_version))
+/// A runtime's `release` file: the exact build a runtime folder holds.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct JavaRelease {
+    /// `JAVA_VERSION`, e.g. `17.0.9` or `1.8.0_392`
+    pub version: String,
+    /// `JAVA_RUNTIME_VERSION`, e.g. `17.0.9+9`
+    #[serde(rename = "runtimeVersion", alias = "runtime_version", default)]
+    pub runtime_version: Option<String>,
+    /// `IMPLEMENTOR`
+    #[serde(default)]
+    pub vendor: Option<String>,
+    #[serde(default)]
+    pub os: Option<String>,
+    #[serde(default)]
+    pub arch: Option<String>,
+}
+
+impl JavaRelease {
+    /// Folder name for this build under `java/runtimes`.
+    pub fn runtime_id(&self) -> String {
+        let version = self.runtime_version.as_deref().unwrap_or(&self.version);
+        let id = match &self.arch {
+            Some(arch) => format!("{}-{}", version, arch),
+            None => version.to_string(),
+        };
+        id.chars().map(|c| if c.is_ascii_alphanumeric() || "._+-".contains(c) { c } else { '_' }).collect()
+    }
+}
+
+pub fn parse_release(content: &str) -> Option<JavaRelease> {
+    let value = |key: &str| content.lines()
+        .filter_map(|line| line.split_once('='))
+        .find(|(k, _)| k.trim() == key)
+        .map(|(_, v)| v.trim().trim_matches('"').to_string())
+        .filter(|v| !v.is_empty());
+    Some(JavaRelease {
+        version: value("JAVA_VERSION")?,
+        runtime_version: value("JAVA_RUNTIME_VERSION"),
+        vendor: value("IMPLEMENTOR"),
+        os: value("OS_NAME"),
+        arch: value("OS_ARCH"),
+    })
+}
+
+/// `release` of the runtime in `dir`; Java 8 JDKs keep it beside their `jre` folder.
+pub fn read_release(dir: &Path) -> Option<JavaRelease> {
+    [Some(dir), dir.parent().filter(|_| dir.ends_with("jre"))].into_iter().flatten()
+        .find_map(|d| fs::read_to_string(d.join("release")).ok())
+        .and_then(|content| parse_release(&content))
+}
+
+/// Folder of the runtime `java_path` belongs to; for the system `java`, the `java.home` it reports.
+pub fn runtime_home(java_path: &Path) -> Option<PathBuf> {
+    if java_path != Path::new("java") {
+        return java_path.parent()?.parent().map(Path::to_path_buf);
+    }
+    let output = Command::new("java").args(["-XshowSettings:properties", "-version"]).output().ok()?;
+    String::from_utf8_lossy(&output.stderr).lines()
+        .find_map(|line| line.trim().strip_prefix("java.home = ").map(PathBuf::from))
+}
+
+/// `java_download_progress` payload.
+#[derive(Serialize, Clone, JsonSchema)]
+pub struct JavaDownloadProgress {
+    pub major: u32,
+    pub percent: u8,
+    pub message: String,
+}
+
+/// Java download progress: part of `launch_progress` when it belongs to an instance launch,
+/// `java_download_progress` when downloaded on its own.
+fn emit_progress(app: Option<&AppHandle>, instance_id: Option<&str>, major: u32, percent: u8, message: &str) {
+    let Some(app) = app else { return; };
+    match instance_id {
+        Some(id) => crate::events::emit_progress(app, crate::events::LaunchProgress::new(id, "java", percent, message)),
+        None => {
+            let _ = app.emit("java_download_progress", JavaDownloadProgress { major, percent, message: message.to_string() });
+        }
+    }
+}
+
+/// Reads the whole body, reporting whole-percent steps when the size is known.
+fn read_with_progress(mut resp: crate::http::HostResponse, mut on_progress: impl FnMut(u8)) -> Result<Vec<u8>, std::io::Error> {
+    use std::io::Read;
+    let total = resp.content_length().unwrap_or(0);
+    let mut bytes = Vec::with_capacity(total as usize);
+    let mut buffer = [0u8; 64 * 1024];
+    let mut last_percent = 0;
+    loop {
+        let n = resp.read(&mut buffer)?;
+        if n == 0 {
+            return Ok(bytes);
+        }
+        bytes.extend_from_slice(&buffer[..n]);
+        if let Some(percent) = (bytes.len() as u64 * 100).checked_div(total) {
+            let percent = percent.min(100) as u8;
+            if percent != last_percent {
+                last_percent = percent;
+                on_progress(percent);
+            }
+        }
+    }
 }
 
 pub fn download_java(major: u32, app: Option<&AppHandle>, instance_id: Option<&str>) -> Result<String, String> {
-    let base_dir = PathBuf::from(crate::get_instances_dir()).join("java").join(format!("{}", major));
+    download_java_in(Path::new(&crate::get_instances_dir()), major, app, instance_id)
+}
+
+pub fn download_java_in(base_path: &Path, major: u32, app: Option<&AppHandle>, instance_id: Option<&str>) -> Result<String, String> {
+    let base_dir = base_path.join("java").join(format!("{}", major));
     let bin_java = base_dir.join("bin").join(if cfg!(target_os = "windows") { "java.exe" } else { "java" });
     
     if bin_java.exists() {
         return Ok(bin_java.to_string_lossy().to_string());
     }
 
-    if let Some(app) = app {
-        if let Some(id) = instance_id {
-            let _ = app.emit("launch_progress", serde_json::json!({
-                "instanceId": id,
-                "stage": "java",
-                "percent": 0,
-                "message": format!("Descargando Java {}", major)
-            }));
-        }
-    }
+    emit_progress(app, instance_id, major, 0, &format!("Descargando Java {}", major));
 
-    fs::create_dir_all(&base_dir).map_err(|e| e.to_string())?;
+    let java_dir = base_dir.parent().map(Path::to_path_buf).unwrap_or_default();
+    fs::create_dir_all(&java_dir).map_err(|e| e.to_string())?;
     
-    // Determine OS and Arch for Adoptium API
-    let os = if cfg!(target_os = "windows") { "windows" } else if cfg!(target_os = "macos") { "mac" } else { "linux" };
-    let arch = "x64"; // Assuming x64 for now, could use utils::get_arch() if available and mapped correctly
-    
-    let api = format!("https://api.adoptium.net/v3/assets/latest/{}/hotspot?architecture={}&os={}&image_type=jre", major, arch, os);
+    let api = adoptium_api_url(major);
     
     let client = reqwest::blocking::Client::builder()
-        .user_agent("DrkLauncher/1.0")
+        .user_agent(crate::http::user_agent())
         .build()
         .map_err(|e| format!("Failed to build http client: {}", e))?;
 
@@ -77,7 +196,7 @@ pub fn download_java(major: u32, app: Option<&AppHandle>, instance_id: Option<&s
     let mut last_error = String::new();
     let mut assets_opt: Option<serde_json::Value> = None;
     for attempt in 1..=max_retries {
-        match client.get(&api).send() {
+        match crate::http::send(&client, client.get(crate::mirror::rewrite(&api))) {
             Ok(resp) => {
                 match resp.json::<serde_json::Value>() {
                     Ok(v) => {
@@ -107,21 +226,29 @@ pub fn download_java(major: u32, app: Option<&AppHandle>, instance_id: Option<&s
     }
     let assets = assets_opt.unwrap();
         
-    let release = assets.as_array().and_then(|arr| arr.first()).ok_or("No Java assets found")?;
+    let release = match assets.as_array().and_then(|arr| arr.first()) {
+        Some(release) => release,
+        None if crate::platform::java_arch() == "x32" => return Err(format!("No hay Java {} de 32 bits; esta versión de Minecraft requiere un sistema de 64 bits", major)),
+        None => return Err("No Java assets found".to_string()),
+    };
     let pkg = release.get("binary").and_then(|b| b.get("package")).ok_or("No Java package info")?;
     let url = pkg.get("link").and_then(|l| l.as_str()).ok_or("No Java download link")?;
     let filename = pkg.get("name").and_then(|n| n.as_str()).unwrap_or("java.zip");
     
-    let zip_path = base_dir.join(filename);
+    // Downloaded and unpacked beside `base_dir`, which only appears once Java is complete
+    let zip_path = crate::temp_files::tmp_path(&java_dir.join(filename));
+    let staging = crate::temp_files::tmp_path(&base_dir);
     
     // Download
     let mut bytes_opt: Option<Vec<u8>> = None;
     for attempt in 1..=max_retries {
-        match client.get(url).send() {
+        match crate::http::send(&client, client.get(crate::mirror::rewrite(url))) {
             Ok(resp) => {
-                match resp.bytes() {
+                match read_with_progress(resp, |percent| {
+                    emit_progress(app, instance_id, major, percent / 2, &format!("Descargando Java {} ({}%)", major, percent));
+                }) {
                     Ok(b) => {
-                        bytes_opt = Some(b.to_vec());
+                        bytes_opt = Some(b);
                         break;
                     }
                     Err(e) => {
@@ -148,34 +275,54 @@ pub fn download_java(major: u32, app: Option<&AppHandle>, instance_id: Option<&s
         return Err(last_error);
     }
 
-    if let Some(app) = app {
-        if let Some(id) = instance_id {
-            let _ = app.emit("launch_progress", serde_json::json!({
-                "instanceId": id,
-                "stage": "java",
-                "percent": 50,
-                "message": "Extrayendo Java"
-            }));
+    emit_progress(app, instance_id, major, 50, "Extrayendo Java");
+
+    let _ = crate::remover::remove_all(&staging);
+    let extracted = extract_runtime(&zip_path, &staging);
+    let _ = fs::remove_file(&zip_path);
+    if let Err(e) = extracted.and_then(|_| crate::temp_files::commit(&staging, &base_dir).map_err(|e| e.to_string())) {
+        let _ = crate::remover::remove_all(&staging);
+        return Err(e);
+    }
+
+    if bin_java.exists() {
+        Ok(bin_java.to_string_lossy().to_string())
+    } else {
+        // Fallback: check if we extracted without stripping (if zip structure was flat?)
+        // Or maybe check subfolders?
+        // For now, assume stripping worked or failed.
+        // Try finding java.exe recursively?
+        // Let's assume the strip logic is correct for Adoptium.
+        if cfg!(target_os = "windows") {
+             let javaw = base_dir.join("bin").join("javaw.exe");
+             if javaw.exists() {
+                 return Ok(javaw.to_string_lossy().to_string());
+             }
         }
+        Err("Java downloaded but executable not found in expected path".to_string())
     }
+}
 
-    // Extract
-    let file = std::fs::File::open(&zip_path).map_err(|e| e.to_string())?;
+
+/// Unpacks an Adoptium zip into `dest`, dropping its top-level `jdk-xx` folder.
+fn extract_runtime(zip_path: &Path, dest: &Path) -> Result<(), String> {
+    let file = std::fs::File::open(zip_path).map_err(|e| e.to_string())?;
     let mut zip = zip::ZipArchive::new(file).map_err(|e| e.to_string())?;
-    
+    fs::create_dir_all(dest).map_err(|e| e.to_string())?;
+
     for i in 0..zip.len() {
         let mut zf = zip.by_index(i).map_err(|e| e.to_string())?;
         // Some zips have a top-level folder, we want to strip it or handle it?
-        // Usually adoptium has "jdk-17+..." folder. We want to extract contents into base_dir.
-        // But the previous code just extracted to base_dir/zf.name().
-        // If zf.name() includes the top folder, we end up with base_dir/jdk-17.../bin/java.exe
-        // But we expect base_dir/bin/java.exe
+        // Usually adoptium has "jdk-17+..." folder. We want to extract contents into dest.
+        // But the previous code just extracted to dest/zf.name().
+        // If zf.name() includes the top folder, we end up with dest/jdk-17.../bin/java.exe
+        // But we expect dest/bin/java.exe
         // So we need to strip the first component if possible.
         
         let path = PathBuf::from(zf.name());
         let components: Vec<_> = path.components().collect();
         if components.len() > 1 {
-            let mut out = base_dir.clone();
+            let mut out = dest.to_path_buf();
             for component in &components[1..] {
                 out.push(component);
             }
@@ -198,28 +345,15 @@ pub fn download_java(major: u32, app: Option<&AppHandle>, instance_id: Option<&s
             }
         }
     }
-    
-    // Cleanup zip
-    let _ = fs::remove_file(&zip_path);
-
-    if bin_java.exists() {
-        Ok(bin_java.to_string_lossy().to_string())
-    } else {
-        // Fallback: check if we extracted without stripping (if zip structure was flat?)
-        // Or maybe check subfolders?
-        // For now, assume stripping worked or failed.
-        // Try finding java.exe recursively?
-        // Let's assume the strip logic is correct for Adoptium.
-        if cfg!(target_os = "windows") {
-             let javaw = base_dir.join("bin").join("javaw.exe");
-             if javaw.exists() {
-                 return Ok(javaw.to_string_lossy().to_string());
-             }
-        }
-        Err("Java downloaded but executable not found in expected path".to_string())
-    }
+    Ok(())
 }
 
+/// Adoptium API query for the latest JRE `major` build for this OS and arch.
+pub fn adoptium_api_url(major: u32) -> String {
+    let os = if cfg!(target_os = "windows") { "windows" } else if cfg!(target_os = "macos") { "mac" } else { "linux" };
+    let arch = crate::platform::java_arch();
+    format!("https://api.adoptium.net/v3/assets/latest/{}/hotspot?architecture={}&os={}&image_type=jre", major, arch, os)
+}
 
 pub fn get_required_java_version(mc_version: &str) -> u32 {
     // Logic based on Minecraft version
@@ -285,3 +419,47 @@ pub fn get_system_java_version(binary: &str) -> Result<u32, String> {
 
     Err("Could not parse java version".to_string())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn release_files_name_the_exact_build() {
+        let release = parse_release("IMPLEMENTOR=\"Eclipse Adoptium\"\nJAVA_RUNTIME_VERSION=\"17.0.9+9\"\nJAVA_VERSION=\"17.0.9\"\nOS_ARCH=\"x86_64\"\nOS_NAME=\"Linux\"\n").unwrap();
+        assert_eq!(release.vendor.as_deref(), Some("Eclipse Adoptium"));
+        assert_eq!(release.runtime_id(), "17.0.9+9-x86_64");
+
+        // Java 8 builds have no runtime version
+        let release = parse_release("JAVA_VERSION=\"1.8.0_392\"\nOS_ARCH=\"amd64 x86\"").unwrap();
+        assert_eq!(release.runtime_id(), "1.8.0_392-amd64_x86");
+        assert_eq!(parse_release("OS_NAME=\"Linux\""), None);
+    }
+
+    #[test]
+    fn saved_instances_never_take_the_pin_from_the_webview() {
+        let instance = |version: &str, runtime: Option<&str>| -> crate::Instance {
+            serde_json::from_value(serde_json::json!({
+                "id": "evento", "name": "Evento", "version": version, "icon": "", "path": "", "javaRuntime": runtime,
+            })).unwrap()
+        };
+        let stored = instance("1.20.1", Some("java/runtimes/17.0.9+9-x86_64"));
+        assert_eq!(kept_runtime(Some(&stored), "1.20.1").as_deref(), Some("java/runtimes/17.0.9+9-x86_64"));
+        // The old build may not even run the new version
+        assert_eq!(kept_runtime(Some(&stored), "1.21.1"), None);
+        // New instances start unpinned no matter what was sent
+        assert_eq!(kept_runtime(None, "1.20.1"), None);
+        assert_eq!(kept_runtime(Some(&instance("1.20.1", None)), "1.20.1"), None);
+    }
+
+    #[test]
+    fn pinned_runtimes_win_over_other_javas() {
+        let dir = tempfile::tempdir().unwrap();
+        let pinned = dir.path().join("java/runtimes/17.0.9+9-x86_64");
+        let java = pinned.join("bin").join(if cfg!(target_os = "windows") { "java.exe" } else { "java" });
+        fs::create_dir_all(java.parent().unwrap()).unwrap();
+        fs::write(&java, "").unwrap();
+        assert_eq!(get_instance_java_path(dir.path(), 17, Some(&pinned)).unwrap(), java);
+        assert_eq!(runtime_home(&java), Some(pinned));
+    }
+}
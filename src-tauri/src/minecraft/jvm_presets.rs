@@ -0,0 +1,170 @@
+use schemars::JsonSchema;
+use serde::Serialize;
+
+pub const DEFAULT_PRESET: &str = "balanced";
+// Aikar's larger-young-generation variant applies above this heap size
+const AIKAR_LARGE_HEAP_MB: u64 = 12 * 1024;
+
+/// A named set of GC/JIT flags an instance can pick instead of the launcher default.
+#[derive(Serialize, Clone, Debug, JsonSchema)]
+pub struct JvmPreset {
+    pub id: &'static str,
+    pub name: &'static str,
+    pub description: &'static str,
+}
+
+pub const PRESETS: [JvmPreset; 4] = [
+    JvmPreset {
+        id: DEFAULT_PRESET,
+        name: "Equilibrado",
+        description: "G1 afinado para la mayoría de modpacks. Es el predeterminado.",
+    },
+    JvmPreset {
+        id: "aikar",
+        name: "Aikar",
+        description: "Los flags de Aikar: pausas de GC cortas con montones grandes.",
+    },
+    JvmPreset {
+        id: "low-end",
+        name: "PC de bajos recursos",
+        description: "Menos memoria reservada y caché de código pequeña para equipos con poca RAM.",
+    },
+    JvmPreset {
+        id: "shaders",
+        name: "Shaders",
+        description: "ZGC en Java 17+ para evitar tirones con shaders; G1 con regiones grandes en Java antiguos.",
+    },
+];
+
+fn to_strings(flags: &[&str]) -> Vec<String> {
+    flags.iter().map(|f| f.to_string()).collect()
+}
+
+fn balanced() -> Vec<String> {
+    to_strings(&[
+        "-XX:+UseG1GC",
+        "-XX:MaxGCPauseMillis=120",
+        "-XX:G1HeapRegionSize=8M",
+        "-XX:G1NewSizePercent=30",
+        "-XX:G1MaxNewSizePercent=40",
+        "-XX:G1ReservePercent=20",
+        "-XX:G1HeapWastePercent=5",
+        "-XX:G1MixedGCCountTarget=4",
+        "-XX:InitiatingHeapOccupancyPercent=15",
+        "-XX:G1MixedGCLiveThresholdPercent=90",
+        "-XX:G1RSetUpdatingPauseTimePercent=5",
+        "-XX:ReservedCodeCacheSize=768M",
+        "-XX:InitialCodeCacheSize=128M",
+        "-XX:+ParallelRefProcEnabled",
+        "-XX:+DisableExplicitGC",
+        "-XX:+AlwaysPreTouch",
+        "-XX:+UseStringDeduplication",
+        "-XX:+UseCompressedOops",
+        "-XX:+UseCompressedClassPointers",
+        "-XX:+PerfDisableSharedMem",
+    ])
+}
+
+fn aikar(ram_mb: u64) -> Vec<String> {
+    let large = ram_mb > AIKAR_LARGE_HEAP_MB;
+    let mut flags = to_strings(&[
+        "-XX:+UseG1GC",
+        "-XX:+ParallelRefProcEnabled",
+        "-XX:MaxGCPauseMillis=200",
+        "-XX:+DisableExplicitGC",
+        "-XX:+AlwaysPreTouch",
+        "-XX:G1HeapWastePercent=5",
+        "-XX:G1MixedGCCountTarget=4",
+        "-XX:G1MixedGCLiveThresholdPercent=90",
+        "-XX:G1RSetUpdatingPauseTimePercent=5",
+        "-XX:SurvivorRatio=32",
+        "-XX:+PerfDisableSharedMem",
+        "-XX:MaxTenuringThreshold=1",
+    ]);
+    flags.extend(to_strings(if large {
+        &["-XX:G1NewSizePercent=40", "-XX:G1MaxNewSizePercent=50", "-XX:G1HeapRegionSize=16M", "-XX:G1ReservePercent=15", "-XX:InitiatingHeapOccupancyPercent=20"]
+    } else {
+        &["-XX:G1NewSizePercent=30", "-XX:G1MaxNewSizePercent=40", "-XX:G1HeapRegionSize=8M", "-XX:G1ReservePercent=20", "-XX:InitiatingHeapOccupancyPercent=15"]
+    }));
+    flags
+}
+
+fn low_end() -> Vec<String> {
+    // No AlwaysPreTouch: committing the whole heap up front starves a machine with little RAM
+    to_strings(&[
+        "-XX:+UseG1GC",
+        "-XX:MaxGCPauseMillis=50",
+        "-XX:G1HeapRegionSize=4M",
+        "-XX:+DisableExplicitGC",
+        "-XX:ReservedCodeCacheSize=256M",
+        "-XX:+UseStringDeduplication",
+        "-XX:+UseCompressedOops",
+        "-XX:+PerfDisableSharedMem",
+    ])
+}
+
+fn shaders(java_major: u32) -> Vec<String> {
+    let mut flags = if java_major >= 17 {
+        to_strings(&["-XX:+UseZGC"])
+    } else {
+        to_strings(&["-XX:+UseG1GC", "-XX:MaxGCPauseMillis=100", "-XX:G1HeapRegionSize=16M", "-XX:G1NewSizePercent=40", "-XX:G1ReservePercent=20"])
+    };
+    // Generational ZGC (JDK 21) halves the CPU cost of plain ZGC
+    if java_major >= 21 {
+        flags.push("-XX:+ZGenerational".to_string());
+    }
+    flags.extend(to_strings(&[
+        "-XX:ReservedCodeCacheSize=512M",
+        "-XX:+DisableExplicitGC",
+        "-XX:+AlwaysPreTouch",
+        "-XX:+PerfDisableSharedMem",
+    ]));
+    flags
+}
+
+/// GC and JIT flags for `preset` on the Java that will run the game; unknown or missing presets
+/// fall back to the default. Heap size (`-Xms`/`-Xmx`) stays with the command builders.
+pub fn preset_flags(preset: Option<&str>, java_major: u32, ram_mb: u64) -> Vec<String> {
+    let mut flags = vec!["-XX:+UnlockExperimentalVMOptions".to_string()];
+    flags.extend(match preset.map(str::trim).unwrap_or(DEFAULT_PRESET) {
+        "aikar" => aikar(ram_mb),
+        "low-end" => low_end(),
+        "shaders" => shaders(java_major),
+        _ => balanced(),
+    });
+    flags
+}
+
+#[tauri::command]
+pub fn get_jvm_presets() -> Vec<JvmPreset> {
+    PRESETS.to_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn zgc_needs_java_17() {
+        let java8 = preset_flags(Some("shaders"), 8, 6144);
+        assert!(java8.contains(&"-XX:+UseG1GC".to_string()));
+        assert!(!java8.iter().any(|f| f.contains("ZGC")));
+        let java17 = preset_flags(Some("shaders"), 17, 6144);
+        assert!(java17.contains(&"-XX:+UseZGC".to_string()));
+        assert!(!java17.contains(&"-XX:+ZGenerational".to_string()));
+        assert!(preset_flags(Some("shaders"), 21, 6144).contains(&"-XX:+ZGenerational".to_string()));
+    }
+
+    #[test]
+    fn unknown_presets_use_the_default() {
+        assert_eq!(preset_flags(Some("turbo"), 17, 4096), preset_flags(None, 17, 4096));
+        assert!(preset_flags(None, 17, 4096).contains(&"-XX:MaxGCPauseMillis=120".to_string()));
+        assert_eq!(preset_flags(None, 17, 4096)[0], "-XX:+UnlockExperimentalVMOptions");
+    }
+
+    #[test]
+    fn aikar_switches_to_large_heap_values() {
+        assert!(preset_flags(Some("aikar"), 17, 8192).contains(&"-XX:G1HeapRegionSize=8M".to_string()));
+        assert!(preset_flags(Some("aikar"), 17, 16384).contains(&"-XX:G1HeapRegionSize=16M".to_string()));
+    }
+}
@@ -1,39 +1,283 @@
 use std::process::Command;
-use std::path::{Path, PathBuf};
+use std::path::{Component, Path, PathBuf};
 use std::fs;
-use std::collections::VecDeque;
+use std::collections::{HashMap, VecDeque};
 use std::sync::{Arc, Mutex};
-use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use crate::auth::MinecraftProfile;
-use tauri::Emitter;
+use crate::events::{ExtractionPhase, ExtractionProgress};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
 use tauri::AppHandle;
 use super::models::*;
-use super::downloader::download_file;
-use super::utils::{get_os_name, get_arch, extract_natives, replace_vars, check_rules};
-use super::java::{get_java_path_for_major, get_required_java_version, download_java, get_system_java_version};
+use super::downloader::download_game_file;
+use super::utils::{check_rules, get_os_name, get_arch, parse_maven_name, maven_path, ensure_trailing_slash, extract_natives_if_changed, jvm_path_arg};
+use super::url_resolver::mod_file_name;
+use super::content_source::{self, ContentKind, Target};
+use super::java::{get_java_path_in, get_required_java_version, download_java_in};
+use super::prefetch::MetaCache;
+use super::prepare_checkpoint::{Checkpoint, CONTENT, MODPACK, TRANSLATION};
 
  
 const RESOURCES_URL: &str = "https://resources.download.minecraft.net";
+pub const ASSET_MIRRORS: [&str; 2] = [RESOURCES_URL, "https://bmclapi2.bangbang93.com/assets"];
+pub const MANIFEST_MIRRORS: [&str; 4] = [
+    "https://piston-meta.mojang.com/mc/game/version_manifest.json",
+    "https://piston-meta.mojang.com/mc/game/version_manifest_v2.json",
+    "https://launchermeta.mojang.com/mc/game/version_manifest.json",
+    "https://bmclapi2.bangbang93.com/mc/game/version_manifest.json",
+];
+// Speedtest probes stay small so a run takes seconds even on a slow connection
+const ASSET_PROBE_SIZE: std::ops::RangeInclusive<u64> = 64 * 1024..=1024 * 1024;
+
+/// Per-launch knobs taken from the instance configuration and shared by every command builder.
+#[derive(Clone, Default)]
+pub struct LaunchOptions {
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub debug: bool,
+    pub log_level: Option<String>,
+    /// World folder under `saves` to open directly (Quick Play, 1.20+)
+    pub quick_play_world: Option<String>,
+    /// `host[:port]` to join on startup; takes precedence over `quick_play_world`
+    pub quick_play_server: Option<String>,
+    /// `--fullscreen`, for kiosk stations
+    pub fullscreen: bool,
+    pub linux: LinuxTweaks,
+    /// Shown in the macOS Dock instead of "java"
+    pub instance_name: String,
+    /// Language written to `options.txt` when it has none (`es_es`)
+    pub game_language: Option<String>,
+    /// Resource pack installed on every sync and enabled above all others
+    pub translation_pack_url: Option<String>,
+    /// JVM flag preset id from `jvm_presets`; `None` uses the default
+    pub jvm_preset: Option<String>,
+    /// `--disableMultiplayer --disableChat`
+    pub singleplayer_only: bool,
+    /// Runtime folder the instance is pinned to (see `java::get_instance_java_path`)
+    pub java_runtime: Option<PathBuf>,
+}
+
+/// Per-instance Linux workarounds. Ignored on other platforms.
+#[derive(Serialize, Deserialize, Clone, Default, Debug, PartialEq, JsonSchema)]
+pub struct LinuxTweaks {
+    /// Run through XWayland even when a Wayland session is available (GLFW 3.4+ prefers Wayland)
+    #[serde(rename = "forceX11", alias = "force_x11", default)]
+    pub force_x11: bool,
+    /// System GLFW to load instead of LWJGL's bundled one, e.g. a Wayland-patched libglfw.so
+    #[serde(rename = "glfwLibname", alias = "glfw_libname", default)]
+    pub glfw_libname: Option<String>,
+    #[serde(rename = "nvidiaThreadedOptimizations", alias = "nvidia_threaded_optimizations", default)]
+    pub nvidia_threaded_optimizations: bool,
+    #[serde(rename = "mesaGlthread", alias = "mesa_glthread", default)]
+    pub mesa_glthread: bool,
+    /// Render on the discrete GPU of hybrid laptops (PRIME render offload)
+    #[serde(rename = "primeOffload", alias = "prime_offload", default)]
+    pub prime_offload: bool,
+}
+
+impl LinuxTweaks {
+    pub fn jvm_flags(&self) -> Vec<String> {
+        self.glfw_libname.iter()
+            .filter(|lib| !lib.trim().is_empty())
+            .map(|lib| format!("-Dorg.lwjgl.glfw.libname={}", lib.trim()))
+            .collect()
+    }
+
+    /// Variables to set, and variables to remove, in the game environment.
+    pub fn env(&self) -> (Vec<(&'static str, &'static str)>, Vec<&'static str>) {
+        let mut set = Vec::new();
+        let mut remove = Vec::new();
+        if self.force_x11 {
+            remove.push("WAYLAND_DISPLAY");
+            set.push(("XDG_SESSION_TYPE", "x11"));
+        }
+        if self.nvidia_threaded_optimizations {
+            set.push(("__GL_THREADED_OPTIMIZATIONS", "1"));
+        }
+        if self.mesa_glthread {
+            set.push(("mesa_glthread", "true"));
+        }
+        if self.prime_offload {
+            set.push(("__NV_PRIME_RENDER_OFFLOAD", "1"));
+            set.push(("__GLX_VENDOR_LIBRARY_NAME", "nvidia"));
+            set.push(("DRI_PRIME", "1"));
+        }
+        (set, remove)
+    }
+}
+
+/// `-Ddrk.pack.fingerprint=<sha256 hex>` for a server-side companion plugin to compare on join.
+/// The value is `crate::attestation::pack_fingerprint` of the instance's mods: it only changes
+/// when a jar is added, removed, renamed or modified. No flag without mods.
+pub fn pack_fingerprint_flags(minecraft_dir: &Path) -> Vec<String> {
+    match crate::attestation::pack_fingerprint(minecraft_dir) {
+        Ok(Some(fingerprint)) => vec![format!("-Ddrk.pack.fingerprint={}", fingerprint)],
+        Ok(None) => Vec::new(),
+        Err(e) => {
+            eprintln!("[launch_logic] Failed to compute pack fingerprint: {}", e);
+            Vec::new()
+        }
+    }
+}
+
+/// Linux tweak JVM flags for the current platform (none outside Linux).
+pub fn linux_jvm_flags(options: &LaunchOptions) -> Vec<String> {
+    if cfg!(target_os = "linux") { options.linux.jvm_flags() } else { Vec::new() }
+}
+
+/// JVM flags macOS needs: GLFW (LWJGL 3, 1.13+) must run on the main thread, and the Dock shows
+/// the instance instead of a bare "java". LWJGL 2 hangs with `-XstartOnFirstThread`.
+pub fn macos_flags(mc_version: &str, instance_name: &str, dock_icon: Option<&Path>) -> Vec<String> {
+    let mut flags = Vec::new();
+    if parse_mc_version(mc_version).map(|(minor, _)| minor >= 13).unwrap_or(true) {
+        flags.push("-XstartOnFirstThread".to_string());
+    }
+    let name = if instance_name.trim().is_empty() { "Minecraft" } else { instance_name.trim() };
+    flags.push(format!("-Xdock:name={}", name));
+    if let Some(icon) = dock_icon {
+        flags.push(format!("-Xdock:icon={}", icon.to_string_lossy()));
+    }
+    flags
+}
+
+/// `icons/minecraft.icns` from any downloaded asset index.
+fn dock_icon(assets_dir: &Path) -> Option<PathBuf> {
+    fs::read_dir(assets_dir.join("indexes")).ok()?.flatten().find_map(|entry| {
+        let mut hash = None;
+        super::asset_stream::for_each_object_in(&entry.path(), |name, object| {
+            if name == "icons/minecraft.icns" {
+                hash = Some(object.hash);
+            }
+            hash.is_none()
+        }).ok()?;
+        let hash = hash?;
+        let path = assets_dir.join("objects").join(hash.get(..2)?).join(&hash);
+        path.is_file().then_some(path)
+    })
+}
+
+/// macOS JVM flags for the current platform (none elsewhere).
+pub fn macos_jvm_flags(options: &LaunchOptions, mc_version: &str, assets_dir: &Path) -> Vec<String> {
+    if cfg!(target_os = "macos") {
+        macos_flags(mc_version, &options.instance_name, dock_icon(assets_dir).as_deref())
+    } else {
+        Vec::new()
+    }
+}
+
+/// Natives classifier of a legacy library for `os`/`arch`. Apple Silicon takes the `-arm64`
+/// build when the version json ships one; otherwise the x64 natives need an x64 Java (Rosetta).
+pub fn native_classifier(natives: &std::collections::HashMap<String, String>, os: &str, arch: &str, available: impl Fn(&str) -> bool) -> Option<String> {
+    let classifier = natives.get(os)?.replace("${arch}", if arch == "x86" { "32" } else { "64" });
+    let arm64 = format!("{}-arm64", classifier);
+    if arch == "arm64" && available(&arm64) {
+        return Some(arm64);
+    }
+    Some(classifier)
+}
+
+fn apply_linux_env(cmd: &mut Command, options: &LaunchOptions) {
+    if !cfg!(target_os = "linux") {
+        return;
+    }
+    let (set, remove) = options.linux.env();
+    for key in remove {
+        cmd.env_remove(key);
+    }
+    cmd.envs(set);
+}
+
+/// Effective log4j level: explicit instance level, DEBUG for debug launches, otherwise none.
+pub fn effective_log_level(options: &LaunchOptions) -> Option<String> {
+    options.log_level.as_ref()
+        .map(|l| l.trim().to_uppercase())
+        .filter(|l| !l.is_empty())
+        .or_else(|| if options.debug { Some("DEBUG".to_string()) } else { None })
+}
+
+/// Extra JVM flags for verbose logging; empty for a normal launch.
+pub fn debug_jvm_flags(options: &LaunchOptions) -> Vec<String> {
+    let mut flags = Vec::new();
+    if let Some(level) = effective_log_level(options) {
+        flags.push(format!("-Dlog4j2.level={}", level));
+        flags.push(format!("-Dforge.logging.console.level={}", level.to_lowercase()));
+    }
+    if options.debug {
+        flags.push("-Dfml.debugClassLoading=true".to_string());
+        flags.push("-Dmixin.debug.verbose=true".to_string());
+    }
+    flags
+}
+
+// Metadata is re-read on every launch; instance prefetch warms these so "Jugar" skips the round trips
+static MANIFEST_CACHE: MetaCache<VersionManifest> = MetaCache::new(std::time::Duration::from_secs(600));
+static FABRIC_PROFILE_CACHE: MetaCache<VersionInfo> = MetaCache::new(std::time::Duration::from_secs(600));
+static FORGE_RECOMMENDED_CACHE: MetaCache<String> = MetaCache::new(std::time::Duration::from_secs(600));
+
+/// `--quickPlayMultiplayer` / `--quickPlaySingleplayer` for 1.20+. Older versions have no Quick
+/// Play: servers go through the old `--server`/`--port` pair and worlds are ignored, so the game
+/// opens on the title screen.
+pub fn quick_play_args(options: &LaunchOptions, mc_version: &str) -> Vec<String> {
+    let quick_play = parse_mc_version(mc_version).is_some_and(|(minor, _)| minor >= 20);
+    if let Some(server) = options.quick_play_server.as_deref().map(str::trim).filter(|s| !s.is_empty()) {
+        if quick_play {
+            return vec!["--quickPlayMultiplayer".to_string(), server.to_string()];
+        }
+        // `[::1]:25565` keeps its brackets out of the host; a bare IPv6 address has no port
+        let (host, port) = match server.rsplit_once(':') {
+            Some((host, port)) if port.parse::<u16>().is_ok() && (!host.contains(':') || host.starts_with('[')) => (host, Some(port)),
+            _ => (server, None),
+        };
+        let mut args = vec!["--server".to_string(), host.trim_start_matches('[').trim_end_matches(']').to_string()];
+        if let Some(port) = port {
+            args.extend(["--port".to_string(), port.to_string()]);
+        }
+        return args;
+    }
+    match &options.quick_play_world {
+        Some(world) if quick_play => vec!["--quickPlaySingleplayer".to_string(), world.clone()],
+        _ => Vec::new(),
+    }
+}
+
+pub fn window_args(options: &LaunchOptions) -> Vec<String> {
+    if options.fullscreen { vec!["--fullscreen".to_string()] } else { Vec::new() }
+}
+
+/// Game flags that grey out the multiplayer menu and chat. Versions that predate them ignore
+/// unknown arguments, so they are passed regardless of version.
+pub fn isolation_args(options: &LaunchOptions) -> Vec<String> {
+    if !options.singleplayer_only {
+        return Vec::new();
+    }
+    vec!["--disableMultiplayer".to_string(), "--disableChat".to_string()]
+}
 
 pub fn fetch_manifest_with_fallback() -> Result<VersionManifest, String> {
+    MANIFEST_CACHE.get_or_fetch("manifest", fetch_manifest_uncached)
+}
+
+fn fetch_manifest_uncached() -> Result<VersionManifest, String> {
     let client = reqwest::blocking::Client::builder()
-        .user_agent("DrkLauncher/1.0")
+        .user_agent(crate::http::user_agent())
         .build()
         .map_err(|e| format!("Failed to build http client: {}", e))?;
     
-    let urls = [
-        "https://piston-meta.mojang.com/mc/game/version_manifest.json",
-        "https://piston-meta.mojang.com/mc/game/version_manifest_v2.json",
-        "https://launchermeta.mojang.com/mc/game/version_manifest.json",
-        "https://bmclapi2.bangbang93.com/mc/game/version_manifest.json",
-    ];
+    let urls = crate::download_stats::rank_mirrors(&MANIFEST_MIRRORS);
     let mut last_err = String::new();
-    for url in urls {
-        match client.get(url).send() {
+    for url in &urls {
+        let started = std::time::Instant::now();
+        match client.get(crate::mirror::rewrite(url)).send() {
             Ok(resp) => {
                 if resp.status().is_success() {
-                    match resp.json::<VersionManifest>() {
-                        Ok(m) => return Ok(m),
+                    match resp.text().map_err(|e| e.to_string()).and_then(|body| {
+                        serde_json::from_str::<VersionManifest>(&body).map(|m| (m, body.len())).map_err(|e| e.to_string())
+                    }) {
+                        Ok((m, bytes)) => {
+                            crate::download_stats::record_success(url, bytes as u64, started.elapsed());
+                            return Ok(m);
+                        }
                         Err(e) => last_err = format!("Failed to parse manifest: {}", e),
                     }
                 } else {
@@ -44,26 +288,66 @@ pub fn fetch_manifest_with_fallback() -> Result<VersionManifest, String> {
                 last_err = format!("Failed to fetch manifest: {}", e);
             }
         }
+        crate::download_stats::record_failure(url);
     }
     Err(last_err)
 }
 
 fn emit(app: &Option<AppHandle>, instance_id: &str, stage: &str, percent: u8, message: &str) {
     if let Some(app) = app {
-        let _ = app.emit("launch_progress", serde_json::json!({
-            "instanceId": instance_id,
-            "stage": stage,
-            "percent": percent,
-            "message": message,
-        }));
+        crate::events::emit_progress(app, crate::events::LaunchProgress::new(instance_id, stage, percent, message));
+    }
+}
+
+/// Extracts `(minor, patch)` from ids like "1.16.5", "1.16.5-forge-36.2.39" or "1.20".
+pub fn parse_mc_version(version: &str) -> Option<(u32, u32)> {
+    let base = version.split('-').next()?;
+    let mut parts = base.split('.');
+    if parts.next()? != "1" {
+        return None;
     }
+    let minor = parts.next()?.parse::<u32>().ok()?;
+    let patch = parts.next().and_then(|p| p.parse::<u32>().ok()).unwrap_or(0);
+    Some((minor, patch))
 }
 
+/// Log4Shell (CVE-2021-44228) mitigation following Mojang's guidance:
+/// 1.17-1.18 get `formatMsgNoLookups`, 1.7-1.16 additionally get Mojang's patched log4j config.
+/// 1.18.1+ ships a fixed log4j and needs nothing.
+pub fn log4shell_jvm_flags(mc_version: &str, assets_dir: &Path) -> Vec<String> {
+    let (minor, patch) = match parse_mc_version(mc_version) {
+        Some(v) => v,
+        None => return Vec::new(),
+    };
+    if !(7..=18).contains(&minor) || (minor == 18 && patch >= 1) {
+        return Vec::new();
+    }
+    let mut flags = vec!["-Dlog4j2.formatMsgNoLookups=true".to_string()];
+    let config = if minor >= 17 {
+        None
+    } else if minor >= 12 {
+        Some(("https://launcher.mojang.com/v1/objects/02937d122c86ce73319ef9975b58896fc1b491d1/log4j2_112-116.xml", "02937d122c86ce73319ef9975b58896fc1b491d1", "log4j2_112-116.xml"))
+    } else {
+        Some(("https://launcher.mojang.com/v1/objects/4bb89a97a66f350bc9f73b3ca8509632682aea2e/log4j2_17-111.xml", "4bb89a97a66f350bc9f73b3ca8509632682aea2e", "log4j2_17-111.xml"))
+    };
+    if let Some((url, sha1, name)) = config {
+        let path = assets_dir.join("log_configs").join(name);
+        if download_game_file(url, &path, Some(sha1)).is_ok() {
+            flags.push(format!("-Dlog4j.configurationFile={}", path.to_string_lossy()));
+        }
+    }
+    flags
+}
+
+/// `revalidate` (explicit syncs) also asks each source whether its file changed; launches only
+/// compare the jars against what was recorded when they were downloaded.
 fn download_mods_parallel(
     urls: &[String],
     mods_dir: &Path,
+    revalidate: bool,
     app: &Option<AppHandle>,
-    instance_id: &str
+    instance_id: &str,
+    checkpoint: Option<Checkpoint>
 ) -> Result<(), String> {
     if urls.is_empty() {
         return Ok(());
@@ -71,18 +355,25 @@ fn download_mods_parallel(
 
     let total = urls.len() as u64;
     let tasks = Arc::new(Mutex::new(VecDeque::new()));
+    let mut skipped = 0;
     
     for url in urls {
-        if let Some(fname) = url.split('/').last() {
-            let target = mods_dir.join(fname);
-            tasks.lock().map_err(|_| "Failed to lock tasks".to_string())?
-                .push_back((url.clone(), target));
+        let target = mods_dir.join(mod_file_name(url));
+        // Finished by an earlier attempt of this prepare
+        if target.exists() && checkpoint.as_ref().is_some_and(|c| c.mod_done(url)) {
+            skipped += 1;
+            continue;
         }
+        tasks.lock().map_err(|_| "Failed to lock tasks".to_string())?
+            .push_back((url.clone(), target));
     }
 
-    let done = Arc::new(AtomicU64::new(0));
+    let done = Arc::new(AtomicU64::new(skipped));
     let error = Arc::new(Mutex::new(None::<String>));
-    let workers = 10usize;
+    let workers = download_workers(|t| t.mod_workers);
+    let target = Arc::new(Target::for_instance(instance_id));
+    // Set by the first source that can't be reached; the rest keep their local copy without waiting
+    let offline = Arc::new(AtomicBool::new(false));
     let mut handles = Vec::new();
 
     for _ in 0..workers {
@@ -91,6 +382,9 @@ fn download_mods_parallel(
         let error = Arc::clone(&error);
         let app = app.clone();
         let instance_id = instance_id.to_string();
+        let checkpoint = checkpoint.clone();
+        let target = Arc::clone(&target);
+        let offline = Arc::clone(&offline);
         
         handles.push(std::thread::spawn(move || {
             loop {
@@ -109,13 +403,37 @@ fn download_mods_parallel(
                     None => break,
                 };
                 
+                // A jar that changed since it was downloaded, or whose source changed, is fetched again
+                if path.exists() {
+                    let stale = if revalidate && !offline.load(Ordering::Relaxed) {
+                        match content_source::matches_source(&url, &target, &path) {
+                            Some(matches) => !matches,
+                            None => {
+                                offline.store(true, Ordering::Relaxed);
+                                !crate::content_index::unchanged(&path)
+                            }
+                        }
+                    } else {
+                        !crate::content_index::unchanged(&path)
+                    };
+                    if stale {
+                        let _ = fs::remove_file(&path);
+                    }
+                }
+                // Only jars fetched now get scanned; existing ones were already checked or approved
+                let fresh = !path.exists();
                 // Retry logic (3 attempts)
                 let mut attempts = 0;
                 let mut success = false;
                 let mut last_err = String::new();
                 
                 while attempts < 3 {
-                    match download_file(&url, &path, None) {
+                    let attempt = if path.exists() {
+                        Ok(())
+                    } else {
+                        content_source::fetch(&url, &target, &path, ContentKind::Mod).map(|_| ())
+                    };
+                    match attempt {
                         Ok(_) => {
                             success = true;
                             break;
@@ -136,12 +454,24 @@ fn download_mods_parallel(
                     }
                     break;
                 }
+
+                if fresh {
+                    if let Err(e) = super::mod_scan::check_downloaded_mod(&path, &url, &instance_id) {
+                        if let Ok(mut guard) = error.lock() {
+                            guard.get_or_insert(e);
+                        }
+                        break;
+                    }
+                }
+                if let Some(checkpoint) = &checkpoint {
+                    checkpoint.complete_mod(&url);
+                }
                 
                 let current = done.fetch_add(1, Ordering::SeqCst) + 1;
                 // Update progress every 5 items or at the end
-                if current % 5 == 0 || current == total {
+                if current.is_multiple_of(5) || current == total {
                     let percent = 80 + ((current * 10) / total) as u8;
-                    let msg = format!("Descargando mods {}/{}", current, total);
+                    let msg = format!("Descargando mods {}/{}{}", current, total, crate::http::backoff_note());
                     emit(&app, &instance_id, "mods", percent, &msg);
                 }
             }
@@ -160,38 +490,139 @@ fn download_mods_parallel(
     Ok(())
 }
 
-fn escape_arg(arg: &str) -> String {
-    let mut escaped = String::new();
-    let needs_quotes = arg.contains(' ') || arg.contains('\t');
-    
-    if needs_quotes {
-        escaped.push('"');
+/// Quotes an argument for a Java `@argfile`. Outside quotes the launcher splits on whitespace and
+/// treats a leading `#` as a comment; inside double quotes a backslash escapes the next char
+/// (`\n`, `\r`, `\t`, `\f` are control chars). Plain backslashes outside quotes are literal,
+/// so Windows paths without spaces are written untouched.
+pub fn escape_arg(arg: &str) -> String {
+    let needs_quotes = arg.is_empty()
+        || arg.chars().any(|c| matches!(c, ' ' | '\t' | '\n' | '\r' | '\x0c' | '"' | '\'' | '#'));
+    if !needs_quotes {
+        return arg.to_string();
     }
-    
+
+    let mut escaped = String::with_capacity(arg.len() + 2);
+    escaped.push('"');
     for c in arg.chars() {
-        if c == '"' {
-            escaped.push_str("\\\"");
-        } else {
-            escaped.push(c);
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '"' => escaped.push_str("\\\""),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            '\x0c' => escaped.push_str("\\f"),
+            _ => escaped.push(c),
         }
     }
-    
-    if needs_quotes {
-        escaped.push('"');
-    }
-    
+    escaped.push('"');
     escaped
 }
 
-fn normalize_path_for_comparison(p: &Path) -> String {
-    let s = p.to_string_lossy().replace('\\', "/");
-    if get_os_name() == "windows" {
-        s.to_lowercase()
-    } else {
-        s
+/// Downloads and extracts the platform natives of a legacy (pre LWJGL 3.3) library, declared
+/// either through `downloads.classifiers` or a maven name plus `natives` map.
+pub fn extract_library_natives(lib: &Library, libraries_dir: &Path, natives_dir: &Path, default_repo: &str) -> Result<(), String> {
+    if !check_rules(&lib.rules) {
+        return Ok(());
+    }
+    let classifiers = lib.downloads.as_ref().and_then(|d| d.classifiers.as_ref());
+    let classifier = match lib.natives.as_ref().and_then(|n| {
+        native_classifier(n, get_os_name(), get_arch(), |c| classifiers.map(|all| all.contains_key(c)).unwrap_or(false))
+    }) {
+        Some(classifier) => classifier,
+        None => return Ok(()),
+    };
+    if let Some(downloads) = &lib.downloads {
+        if let Some(artifact) = downloads.classifiers.as_ref().and_then(|c| c.get(&classifier)) {
+            if let Some(path_str) = &artifact.path {
+                let target = libraries_dir.join(path_str);
+                download_game_file(&artifact.url, &target, Some(&artifact.sha1))?;
+                extract_natives_if_changed(&target, natives_dir)?;
+            }
+        }
+    } else if let Some(mut maven) = parse_maven_name(&lib.name) {
+        maven.classifier = Some(classifier);
+        if let Some(native_path) = maven_path(&maven) {
+            let base_url = lib.url.clone().unwrap_or_else(|| default_repo.to_string());
+            let target = libraries_dir.join(&native_path);
+            download_game_file(&format!("{}{}", ensure_trailing_slash(&base_url), native_path), &target, None)?;
+            extract_natives_if_changed(&target, natives_dir)?;
+        }
+    }
+    Ok(())
+}
+
+// Leaves room for the JVM flags and game args inside Windows' 32767 char command line limit
+const CLASSPATH_INLINE_LIMIT: usize = 24000;
+
+/// Builds the JVM arguments that carry the classpath without overflowing the command line.
+/// Java 9+ reads it from an `@args.txt` file; Java 8 has no argfile support, so long classpaths
+/// go through a pathing jar whose manifest `Class-Path` lists every entry.
+pub fn classpath_jvm_args(jars: &[PathBuf], java_major: u32, work_dir: &Path) -> Result<Vec<String>, String> {
+    let sep = if get_os_name() == "windows" { ";" } else { ":" };
+    // Inline or in args.txt, the JVM decodes it with the platform charset either way
+    let classpath = jars.iter()
+        .map(|p| jvm_path_arg(p, work_dir).to_string_lossy().to_string())
+        .collect::<Vec<_>>()
+        .join(sep);
+
+    if java_major >= 9 {
+        let args_file_path = work_dir.join("args.txt");
+        let args_content = format!("-cp\n{}\n", escape_arg(&classpath));
+        fs::write(&args_file_path, args_content).map_err(|e| format!("Failed to write args file: {}", e))?;
+        return Ok(vec![format!("@{}", args_file_path.to_string_lossy())]);
+    }
+
+    if classpath.len() <= CLASSPATH_INLINE_LIMIT {
+        return Ok(vec!["-cp".to_string(), classpath]);
+    }
+
+    let pathing_jar = work_dir.join("classpath.jar");
+    write_pathing_jar(jars, &pathing_jar)?;
+    Ok(vec!["-cp".to_string(), jvm_path_arg(&pathing_jar, work_dir).to_string_lossy().to_string()])
+}
+
+fn write_pathing_jar(jars: &[PathBuf], target: &Path) -> Result<(), String> {
+    use std::io::Write;
+
+    let mut entries = Vec::new();
+    for jar in jars {
+        let url = url::Url::from_file_path(jar)
+            .map_err(|_| format!("Invalid classpath entry: {}", jar.to_string_lossy()))?;
+        entries.push(url.to_string());
+    }
+
+    // Manifest lines are limited to 72 bytes; continuation lines start with a single space
+    let header = format!("Class-Path: {}", entries.join(" "));
+    let mut manifest = String::from("Manifest-Version: 1.0\r\n");
+    let bytes = header.as_bytes();
+    let mut start = 0;
+    let mut first = true;
+    while start < bytes.len() {
+        let max = if first { 72 } else { 71 };
+        let mut end = std::cmp::min(start + max, bytes.len());
+        while !header.is_char_boundary(end) {
+            end -= 1;
+        }
+        if !first {
+            manifest.push(' ');
+        }
+        manifest.push_str(&header[start..end]);
+        manifest.push_str("\r\n");
+        start = end;
+        first = false;
     }
+    manifest.push_str("\r\n");
+
+    let file = fs::File::create(target).map_err(|e| format!("Failed to create pathing jar: {}", e))?;
+    let mut zip = zip::ZipWriter::new(file);
+    zip.start_file("META-INF/MANIFEST.MF", zip::write::FileOptions::default())
+        .map_err(|e| e.to_string())?;
+    zip.write_all(manifest.as_bytes()).map_err(|e| e.to_string())?;
+    zip.finish().map_err(|e| e.to_string())?;
+    Ok(())
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn prepare_and_launch(
     base_path: &Path, // Common root (e.g., AppData/Roaming/Eventos DRK)
     instance_path: &Path, // Specific instance path
@@ -201,1026 +632,545 @@ pub fn prepare_and_launch(
     mods_urls: Option<Vec<String>>,
     modpack_url: Option<String>,
     loader: Option<String>,
-    width: Option<u32>,
-    height: Option<u32>,
     app: Option<AppHandle>,
     instance_id: &str,
-    force_update: bool
+    force_update: bool,
+    options: &LaunchOptions
 ) -> Result<Command, String> {
-    let assets_dir = base_path.join("assets");
-    let libraries_dir = base_path.join("libraries");
-    let versions_dir = base_path.join("versions");
-    let natives_dir = instance_path.join("natives");
+    crate::platform::ensure_supported(version_id)?;
     let minecraft_dir = instance_path.join("minecraft");
-
-    // 1. Fetch Manifest
     emit(&app, instance_id, "iniciando", 0, "Iniciando lanzamiento");
-    let manifest: VersionManifest = fetch_manifest_with_fallback()
-        .map_err(|e| format!("Failed to fetch manifest (with fallback): {}", e))?;
-    emit(&app, instance_id, "manifest", 10, "Manifest descargado");
-
-    let mut effective_version_id = version_id.to_string();
-    if matches!(loader.as_deref(), Some("forge")) {
-        effective_version_id = ensure_forge_installed(base_path, version_id, &app, instance_id)?;
-    }
-
-    // 2. Resolve Complete Version Info (Handling Inheritance / Loader)
-    let version_info = if matches!(loader.as_deref(), Some("fabric")) {
-        let mut child = load_fabric_profile_info(version_id)?;
-        if let Some(parent_id) = &child.inherits_from {
-            let parent = resolve_complete_version_info(parent_id, &versions_dir, &manifest)?;
-            // Merge parts from parent into child
-            if child.arguments.is_none() { child.arguments = parent.arguments; }
-            if child.minecraft_arguments.is_none() { child.minecraft_arguments = parent.minecraft_arguments; }
-            if child.asset_index.is_none() { child.asset_index = parent.asset_index; }
-            if child.assets.is_none() { child.assets = parent.assets; }
-            if child.downloads.is_none() { child.downloads = parent.downloads; }
-            if child.java_version.is_none() { child.java_version = parent.java_version; }
-            if child.logging.is_none() { child.logging = parent.logging; }
-            let mut libs = parent.libraries.clone();
-            libs.extend(child.libraries.clone());
-            child.libraries = deduplicate_libraries(libs);
+
+    // 1. Loader specific download (version json, client, libraries, assets)
+    let loader = loader.unwrap_or_else(|| "vanilla".to_string());
+    // A retry after a failure picks up after the last stage that completed
+    let plan = format!("{}|{}|{}", loader, version_id, modpack_url.as_deref().unwrap_or_default());
+    let checkpoint = Checkpoint::open(&minecraft_dir, &plan, force_update);
+    let info = match checkpoint.version_info() {
+        Some(info) => {
+            emit(&app, instance_id, "iniciando", 75, "Reanudando la preparación anterior");
+            info
+        }
+        None => {
+            let info = match loader.as_str() {
+                "vanilla" => super::vanilla_loader::download_vanilla(base_path, &minecraft_dir, version_id, &app, instance_id, &checkpoint)?,
+                "fabric" => super::fabric_loader::download_fabric(base_path, &minecraft_dir, version_id, &app, instance_id, &checkpoint)?,
+                "forge" => super::forge_loader::download_forge(base_path, &minecraft_dir, version_id, &app, instance_id, &checkpoint)?,
+                other => return Err(format!("Unsupported loader: {}", other)),
+            };
+            checkpoint.complete_loader(&info);
+            info
         }
-        child
-    } else {
-        resolve_complete_version_info(&effective_version_id, &versions_dir, &manifest)?
     };
-    emit(&app, instance_id, "version", 20, "Versión resuelta");
-    if loader.as_deref().map(|l| l == "vanilla").unwrap_or(true) {
-        let info = super::vanilla_loader::download_vanilla(base_path, &minecraft_dir, version_id, &app, instance_id)?;
-        let cmd = super::vanilla_loader::build_vanilla_command(base_path, &minecraft_dir, &info, auth, ram_mb)?;
-        return Ok(cmd);
-    }
-    if matches!(loader.as_deref(), Some("fabric")) {
-        let info = super::fabric_loader::download_fabric(base_path, &minecraft_dir, version_id, &app, instance_id)?;
-        let cmd = super::fabric_loader::build_fabric_command(base_path, &minecraft_dir, &info, auth, ram_mb)?;
-        return Ok(cmd);
-    }
-    if matches!(loader.as_deref(), Some("forge")) {
-        let info = super::forge_loader::download_forge(base_path, &minecraft_dir, version_id, &app, instance_id)?;
-        let cmd = super::forge_loader::build_forge_command(base_path, &minecraft_dir, &info, auth, ram_mb)?;
-        return Ok(cmd);
-    }
-
-    // 3. Download & Verify Libraries
-    let mut classpath_entries = download_libraries_parallel(
-        &version_info,
-        &libraries_dir,
-        &natives_dir,
-        &app,
-        instance_id
-    )?;
-
-    // 4. Download Client JAR
-    if let Some(downloads) = &version_info.downloads {
-        let client_id = &effective_version_id;
-        let client_dir = versions_dir.join(client_id);
-        let client_path = client_dir.join(format!("{}.jar", client_id));
-        download_file(
-            &downloads.client.url, 
-            &client_path, 
-            Some(&downloads.client.sha1)
-        )?;
-        if loader.as_deref().map(|l| l == "vanilla").unwrap_or(true) {
-            if let Ok(file) = std::fs::File::open(&client_path) {
-                if let Ok(mut archive) = zip::ZipArchive::new(file) {
-                    if archive.by_name("net/minecraft/client/main/Main.class").is_err() {
-                        emit(&app, instance_id, "cliente", 61, "Advertencia: client.jar sin Main.class; reintentar descarga");
-                        // Forzar una redescarga limpia
-                        let _ = std::fs::remove_file(&client_path);
-                        download_file(
-                            &downloads.client.url, 
-                            &client_path, 
-                            Some(&downloads.client.sha1)
-                        )?;
-                    }
-                }
-            }
+
+    // 2. Shared content pipeline (modpack + individual mods)
+    if !checkpoint.is_done(CONTENT) {
+        sync_instance_content(base_path, &minecraft_dir, mods_urls, modpack_url, options.translation_pack_url.as_deref(), force_update, &app, instance_id, Some(&checkpoint))?;
+        checkpoint.complete(CONTENT);
+    }
+    if let Some(lang) = options.game_language.as_deref().filter(|l| !l.trim().is_empty()) {
+        super::game_options::apply_game_language(&minecraft_dir, lang, version_id)?;
+    }
+    // The OS accessibility settings as defaults; whatever the player set in game stays
+    let accessibility = crate::accessibility::option_defaults(crate::accessibility::current(), version_id);
+    super::game_options::apply_missing_option_values(&minecraft_dir, &accessibility)?;
+
+    // 3. Loader specific command
+    emit(&app, instance_id, "comando", 92, "Preparando Java y el comando de inicio");
+    let ram_mb = crate::platform::clamp_ram_mb(ram_mb);
+    let mut cmd = match loader.as_str() {
+        "fabric" => super::fabric_loader::build_fabric_command(base_path, &minecraft_dir, &info, auth, ram_mb, options)?,
+        "forge" => super::forge_loader::build_forge_command(base_path, &minecraft_dir, &info, auth, ram_mb, options)?,
+        _ => super::vanilla_loader::build_vanilla_command(base_path, &minecraft_dir, &info, auth, ram_mb, options)?,
+    };
+
+    apply_linux_env(&mut cmd, options);
+
+    // Reported, not enforced: staff decide whether a drifted station still plays
+    let lock = super::instance_lock::capture(base_path, &minecraft_dir, &loader, version_id, &info, Path::new(cmd.get_program()))
+        .and_then(|current| super::instance_lock::check(instance_path, &current));
+    match lock {
+        Ok(drift) if !drift.is_empty() => {
+            let message = super::instance_lock::drift_message(&drift);
+            eprintln!("[launch_logic] {} drifted from its lock: {}", instance_id, drift.join("; "));
+            emit(&app, instance_id, "listo", 94, &message);
         }
-        classpath_entries.push(client_path);
-    } else {
-         return Err("No client download information found (missing downloads section)".to_string());
+        Ok(_) => {}
+        Err(e) => eprintln!("[launch_logic] Failed to check instance.lock of {}: {}", instance_id, e),
     }
-    emit(&app, instance_id, "cliente", 60, "Cliente descargado");
 
-    // 5. Download Assets
-    if let Some(asset_index_ref) = &version_info.asset_index {
-        let asset_index_path = assets_dir.join("indexes").join(format!("{}.json", asset_index_ref.id));
-        download_file(
-            &asset_index_ref.url, 
-            &asset_index_path, 
-            Some(&asset_index_ref.sha1)
-        )?;
-
-        let asset_index: AssetIndex = serde_json::from_str(
-            &fs::read_to_string(&asset_index_path).map_err(|e| e.to_string())?
-        ).map_err(|e| e.to_string())?;
+    checkpoint.finish();
+    emit(&app, instance_id, "listo", 95, "Preparación completa");
+    Ok(cmd)
+}
 
-        emit(&app, instance_id, "assets", 60, "Descargando assets");
-        download_assets_parallel(&assets_dir, &asset_index, &app, instance_id)?;
-    } else {
-        return Err("No asset index found".to_string());
+/// `work` under `stage` of the checkpoint, when there is one.
+fn run_stage(checkpoint: Option<&Checkpoint>, stage: &str, work: impl FnOnce() -> Result<(), String>) -> Result<(), String> {
+    match checkpoint {
+        Some(checkpoint) => checkpoint.run(stage, work),
+        None => work(),
     }
-    emit(&app, instance_id, "assets", 75, "Assets descargados");
+}
 
-    // 6. Mods Management (download/extract)
+/// Downloads/extracts the instance modpack and the individual mod list. Shared by every loader.
+/// `checkpoint` lets a retried prepare skip what an earlier attempt finished.
+#[allow(clippy::too_many_arguments)]
+pub fn sync_instance_content(
+    base_path: &Path,
+    minecraft_dir: &Path,
+    mods_urls: Option<Vec<String>>,
+    modpack_url: Option<String>,
+    translation_pack_url: Option<&str>,
+    force_update: bool,
+    app: &Option<AppHandle>,
+    instance_id: &str,
+    checkpoint: Option<&Checkpoint>
+) -> Result<(), String> {
     let mods_dir = minecraft_dir.join("mods");
     let _ = fs::create_dir_all(&mods_dir);
 
-    // Check if mods exist (optional check for future use)
-    let mut _has_mods = false;
-    if let Ok(entries) = fs::read_dir(&mods_dir) {
-        for entry in entries.flatten() {
-            if entry.path().extension().and_then(|e| e.to_str()) == Some("jar") {
-                _has_mods = true;
-                break;
-            }
-        }
+    let hooks = super::sync_hooks::for_instance(instance_id);
+    for line in super::sync_hooks::run_stage(&hooks, "preSync", minecraft_dir, instance_id)? {
+        emit(app, instance_id, "mods", 79, &format!("Script preSync: {}", line));
     }
 
-    if let Some(url) = &modpack_url {
-        if !url.is_empty() {
-            // Modpack logic
-            let zip_path = minecraft_dir.join("modpack.zip");
-            let mods_dir = minecraft_dir.join("mods");
-            
-            // Check if we already have mods installed
-            let has_installed_mods = if let Ok(entries) = fs::read_dir(&mods_dir) {
-                entries.count() > 0
-            } else {
-                false
-            };
-
-            let mut should_download = !zip_path.exists();
-            let mut should_extract = false;
-
-            // Update Logic: Check for size changes if file exists
-            if zip_path.exists() {
-                // Check remote size (Optimized HEAD request)
-                if let Ok(client) = reqwest::blocking::Client::builder().user_agent("DrkLauncher/1.0").timeout(std::time::Duration::from_secs(5)).build() {
-                     if let Ok(resp) = client.head(url).send() {
-                         if let Some(remote_len) = resp.content_length() {
-                             if let Ok(meta) = fs::metadata(&zip_path) {
-                                 if meta.len() != remote_len {
-                                     emit(&app, instance_id, "mods", 5, "Actualización de modpack detectada...");
-                                     should_download = true;
-                                     // Remove old zip to ensure clean download
-                                     let _ = fs::remove_file(&zip_path);
-                                 }
-                             }
-                         }
-                     }
-                }
-            }
-
-            if should_download {
-                 emit(&app, instance_id, "mods", 80, "Descargando modpack...");
-                 
-                 // Fix common URL issues (Dropbox)
-                 let fixed_url = if url.contains("dropbox.com") && url.contains("?dl=0") {
-                     url.replace("?dl=0", "?dl=1")
-                 } else if url.contains("dropbox.com") && !url.contains("?dl=") {
-                     format!("{}?dl=1", url)
-                 } else {
-                     url.clone()
-                 };
-                 
-                 download_file(&fixed_url, &zip_path, None)?;
-                 should_extract = true;
-            }
-            
-            // Extract if:
-            // 1. We just downloaded it (should_extract = true)
-            // 2. Or user requested Force Update
-            // 3. Or we have the zip but NO mods installed (first run or deleted mods)
-            if force_update || (!has_installed_mods && zip_path.exists()) {
-                should_extract = true;
-            }
-            
-            if should_extract && zip_path.exists() {
-                 emit(&app, instance_id, "mods", 81, "Sincronizando archivos del modpack...");
-                 let folders_to_clean = ["mods", "config", "scripts", "kubejs", "defaultconfigs"];
-                 for folder in folders_to_clean {
-                     // Clean inside minecraft_dir
-                     let target_path = minecraft_dir.join(folder);
-                     if target_path.exists() {
-                         let _ = fs::remove_dir_all(&target_path);
-                     }
-                 }
-
-                 emit(&app, instance_id, "mods", 82, "Extrayendo modpack...");
-                 let file = std::fs::File::open(&zip_path).map_err(|e| e.to_string())?;
-                 let mut archive = zip::ZipArchive::new(file).map_err(|e| e.to_string())?;
-                 
-                 for i in 0..archive.len() {
-                    let mut f = archive.by_index(i).map_err(|e| e.to_string())?;
-                    let name = f.name().to_string();
-                    
-                    // Exclude metadata files
-                    if name == "manifest.json" || name == "modlist.html" || name == "instance.cfg" || name.ends_with("/") {
-                        continue;
-                    }
-
-                    // Determine target path relative to MINECRAFT_DIR
-                    let target_path = if name.starts_with("overrides/") {
-                        minecraft_dir.join(name.strip_prefix("overrides/").unwrap())
-                    } else {
-                        if name.starts_with("mods/") || name.ends_with(".jar") {
-                            if !name.contains('/') || name.starts_with("mods/") {
-                                minecraft_dir.join(&name)
-                            } else {
-                                minecraft_dir.join(&name)
-                            }
-                        } else {
-                            minecraft_dir.join(&name)
-                        }
-                    };
-
-                    // Special case: Flat jars at root of zip -> go to mods/
-                    let final_path = if !name.contains('/') && name.ends_with(".jar") && !name.starts_with("overrides/") {
-                         minecraft_dir.join("mods").join(&name)
-                    } else {
-                         target_path
-                    };
-
-                    if let Some(p) = final_path.parent() { let _ = fs::create_dir_all(p); }
-                    
-                    let mut out_file = std::fs::File::create(&final_path).map_err(|e| e.to_string())?;
-                    std::io::copy(&mut f, &mut out_file).map_err(|e| e.to_string())?;
-                }
-            } else if zip_path.exists() {
-                // Si ya existe el zip y NO estamos forzando update ni extrayendo, asumimos que está listo
-                emit(&app, instance_id, "mods", 90, "Modpack verificado");
-            }
-        }
+    if let Some(url) = modpack_url.as_deref().filter(|u| !u.is_empty()) {
+        // Mods from a half-done extraction would otherwise count as an installed pack
+        let interrupted = checkpoint.is_some_and(|c| c.was_interrupted(MODPACK));
+        run_stage(checkpoint, MODPACK, || sync_modpack(minecraft_dir, url, force_update || interrupted, app, instance_id))?;
     }
 
     if let Some(urls) = &mods_urls {
-        emit(&app, instance_id, "mods", 80, "Iniciando descarga de mods...");
-        download_mods_parallel(urls, &mods_dir, &app, instance_id)?;
+        emit(app, instance_id, "mods", 80, "Iniciando descarga de mods...");
+        download_mods_parallel(urls, &mods_dir, force_update, app, instance_id, checkpoint.cloned())?;
     }
-    emit(&app, instance_id, "mods", 90, "Mods listos");
 
-    // 7. Build Arguments
-    let required_java = version_info
-        .java_version
-        .as_ref()
-        .map(|v| v.major_version)
-        .unwrap_or_else(|| get_required_java_version(version_id));
-    
-    let java_path = match get_java_path_for_major(required_java) {
-        Ok(path) => path,
-        Err(_) => {
-            // Try to download
-            emit(&app, instance_id, "java", 0, &format!("Descargando Java {}", required_java));
-            let path_str = download_java(required_java, app.as_ref(), Some(instance_id))?;
-            PathBuf::from(path_str)
-        }
-    };
-    
-    let mut cmd = Command::new(java_path.clone());
-    
-    // JVM Args - Optimized based on Plan_Nuevo (JavaConfigService.ts)
-    let min_mem = std::cmp::max(512, ram_mb / 4);
-    
-    // CRÍTICO: Desbloquear opciones experimentales PRIMERO
-    cmd.arg("-XX:+UnlockExperimentalVMOptions");
-    
-    // Memoria
-    cmd.arg(format!("-Xms{}M", min_mem));
-    cmd.arg(format!("-Xmx{}M", ram_mb));
-    
-    // Garbage Collector (G1GC)
-    cmd.arg("-XX:+UseG1GC");
-    cmd.arg("-XX:MaxGCPauseMillis=120");
-    cmd.arg("-XX:G1HeapRegionSize=8M");
-    cmd.arg("-XX:G1NewSizePercent=30");
-    cmd.arg("-XX:G1MaxNewSizePercent=40");
-    cmd.arg("-XX:G1ReservePercent=20");
-    cmd.arg("-XX:G1HeapWastePercent=5");
-    cmd.arg("-XX:G1MixedGCCountTarget=4");
-    cmd.arg("-XX:InitiatingHeapOccupancyPercent=15");
-    cmd.arg("-XX:G1MixedGCLiveThresholdPercent=90");
-    cmd.arg("-XX:G1RSetUpdatingPauseTimePercent=5");
-    
-    // CodeCache aumentado (OBLIGATORIO para mods)
-    cmd.arg("-XX:ReservedCodeCacheSize=768M");
-    cmd.arg("-XX:InitialCodeCacheSize=128M");
-    
-    // Optimizaciones de memoria y rendimiento
-    cmd.arg("-XX:+ParallelRefProcEnabled");
-    cmd.arg("-XX:+DisableExplicitGC");
-    cmd.arg("-XX:+AlwaysPreTouch");
-    cmd.arg("-XX:+UseStringDeduplication");
-    cmd.arg("-XX:+UseCompressedOops");
-    cmd.arg("-XX:+UseCompressedClassPointers");
-    cmd.arg("-XX:+PerfDisableSharedMem");
-    
-    // Optimizaciones de red y I/O
-    cmd.arg("-Djava.net.preferIPv4Stack=true");
-    cmd.arg("-Dfile.encoding=UTF-8");
-    
-    // Optimizaciones de renderizado y Forge
-    cmd.arg("-Dfml.ignoreInvalidMinecraftCertificates=true");
-    cmd.arg("-Dfml.ignorePatchDiscrepancies=true");
-    cmd.arg("-Dfml.earlyprogresswindow=false");
-    cmd.arg("-Dfml.earlyWindowControl=false");
-    cmd.arg("-Dforge.logging.console.level=info");
-    cmd.arg("-Djava.awt.headless=false");
-
-    // Expanded ignoreList to resolve common module conflicts in Forge 1.17+
-    // Only apply for Forge to avoid potential side effects on other loaders
-    if matches!(loader.as_deref(), Some("forge")) {
-        // No incluir módulos críticos de Forge en ignoreList (bootstraplauncher, modlauncher, securejarhandler)
-        // Mantener solo bibliotecas de terceros conocidas que causan conflictos cuando están duplicadas
-        cmd.arg("-DignoreList=asm-commons,asm-util,asm-analysis,asm-tree,asm,javassist,commons-compress,commons-io,httpclient,httpcore,netty-handler,netty-buffer,netty-common,netty-codec,netty-transport,netty-resolver,fastutil,jna,oshi-core,gson,guava,slf4j-api,log4j-api,log4j-core,org.jetbrains.annotations,annotations,kotlin-stdlib,kotlin-stdlib-jdk8,kotlin-stdlib-jdk7,mixin,sponge-mixin,commons-lang3,commons-logging,jakarta.activation,jakarta.xml.bind");
-    }
-    
-    // Fix for Java 16+ reflection restrictions (Forge/Fabric)
-    cmd.arg("--add-opens"); cmd.arg("java.base/java.util=ALL-UNNAMED");
-    cmd.arg("--add-opens"); cmd.arg("java.base/java.lang=ALL-UNNAMED");
-    cmd.arg("--add-opens"); cmd.arg("java.base/java.lang.reflect=ALL-UNNAMED");
-    cmd.arg("--add-opens"); cmd.arg("java.base/java.lang.invoke=ALL-UNNAMED");
-    cmd.arg("--add-opens"); cmd.arg("java.base/java.text=ALL-UNNAMED");
-    cmd.arg("--add-opens"); cmd.arg("java.desktop/java.awt.font=ALL-UNNAMED");
-    cmd.arg("--add-opens"); cmd.arg("java.base/java.nio=ALL-UNNAMED");
-    cmd.arg("--add-opens"); cmd.arg("java.base/sun.nio.ch=ALL-UNNAMED");
-    cmd.arg("--add-opens"); cmd.arg("java.base/java.util.jar=ALL-UNNAMED");
-    cmd.arg("--add-exports"); cmd.arg("java.base/sun.security.util=ALL-UNNAMED");
-    cmd.arg("--add-exports"); cmd.arg("jdk.naming.dns/com.sun.jndi.dns=java.naming");
-
-    cmd.arg(format!("-Djava.library.path={}", natives_dir.to_string_lossy()));
-    cmd.arg("-Dminecraft.launcher.brand=drklauncher");
-    cmd.arg("-Dminecraft.launcher.version=1.0");
-
-    let java_major = get_system_java_version(&java_path.to_string_lossy()).unwrap_or(required_java);
-    if java_major >= 21 && matches!(loader.as_deref(), Some("forge") | Some("fabric")) {
-        cmd.arg("--enable-native-access=ALL-UNNAMED");
-    }
-
-    // Log Config (if available)
-    if let Some(logging) = &version_info.logging {
-        if let Some(client_logging) = &logging.client {
-             if let Some(file) = &client_logging.file {
-                 let log_config_path = assets_dir.join("log_configs").join(&file.id);
-                 if !log_config_path.exists() {
-                     // Try to download log config
-                     if let Some(url) = &file.url {
-                         if let Some(parent) = log_config_path.parent() {
-                             let _ = fs::create_dir_all(parent);
-                         }
-                         let _ = download_file(url, &log_config_path, Some(&file.sha1));
-                     }
-                 }
-                 if log_config_path.exists() {
-                     if let Some(arg) = &client_logging.argument {
-                         cmd.arg(arg.replace("${path}", &log_config_path.to_string_lossy()));
-                     }
-                 }
-             }
-        }
-    }
-    
-    let asset_index_id = version_info.asset_index.as_ref().map(|a| a.id.as_str()).unwrap_or("legacy");
-
-    // Write arguments to a file to avoid command line length limits and character escaping issues
-    let args_file_path = minecraft_dir.join("args.txt");
-    let mut args_content = String::new();
-
-    // Force disable early window in args.txt as requested
-    if matches!(loader.as_deref(), Some("forge")) {
-        args_content.push_str("-Dfml.earlyprogresswindow=false\n");
-        args_content.push_str("-Dfml.earlyWindowControl=false\n");
-        args_content.push_str("-Dforge.logging.console.level=info\n");
-    }
-
-    // JVM Args from version info
-    let mut module_path_libs = std::collections::HashSet::new();
-    let mut module_path_seen_artifacts: std::collections::HashSet<(String, String)> = std::collections::HashSet::new();
-    let mut module_path_seen_paths: std::collections::HashSet<String> = std::collections::HashSet::new();
-    let mut is_module_path_val = false;
-    let mut module_path_values: Vec<String> = Vec::new();
-
-    // Build library map for smart filtering
-    let mut library_map: std::collections::HashMap<String, MavenName> = std::collections::HashMap::new();
-    for lib in &version_info.libraries {
-        if let Some(maven) = parse_maven_name(&lib.name) {
-            // Calculate potential paths for this library
-            if let Some(path_str) = maven_path(&maven) {
-                let full_path = libraries_dir.join(path_str);
-                library_map.insert(normalize_path_for_comparison(&full_path), maven.clone());
-            }
-            if let Some(downloads) = &lib.downloads {
-                if let Some(artifact) = &downloads.artifact {
-                    if let Some(path_str) = &artifact.path {
-                        let full_path = libraries_dir.join(path_str);
-                        library_map.insert(normalize_path_for_comparison(&full_path), maven.clone());
-                    }
-                }
-            }
-        }
+    // After the modpack, so an options.txt shipped in the pack can't drop the translation
+    if let Some(url) = translation_pack_url.filter(|u| !u.trim().is_empty()) {
+        run_stage(checkpoint, TRANSLATION, || sync_translation_pack(minecraft_dir, url, force_update, app, instance_id))?;
     }
 
-    if let Some(args) = &version_info.arguments {
-        if let Some(jvm_args) = &args.jvm {
-            let mut skip_next = false;
-            for arg in jvm_args {
-                match arg {
-                    Argument::Simple(s) => {
-                        if skip_next { skip_next = false; continue; }
-                        if s == "-cp" { skip_next = true; continue; }
-                        if s == "${classpath}" { continue; }
-                        if s.starts_with("-DignoreList=") { continue; }
-                        
-                        let val = replace_vars(s, auth, &effective_version_id, &assets_dir, &minecraft_dir, asset_index_id, &natives_dir, &libraries_dir);
-                        if val.starts_with("-DignoreList=") { continue; }
-                        
-                        if is_module_path_val {
-                            let sep = if get_os_name() == "windows" { ";" } else { ":" };
-                            let mut filtered = Vec::new();
-                            for p in val.split(sep) {
-                                let norm = normalize_path_for_comparison(&std::path::PathBuf::from(p));
-                                if let Some(maven) = library_map.get(&norm) {
-                                    let key = (maven.group.clone(), maven.artifact.clone());
-                                    if module_path_seen_artifacts.insert(key) {
-                                        filtered.push(p.to_string());
-                                        module_path_libs.insert(norm);
-                                    }
-                                } else {
-                                    if module_path_seen_paths.insert(norm.clone()) {
-                                        filtered.push(p.to_string());
-                                        module_path_libs.insert(norm);
-                                    }
-                                }
-                            }
-                            let filtered_val = filtered.join(sep);
-                            args_content.push_str(&format!("{}\n", escape_arg(&filtered_val)));
-                            module_path_values.extend(filtered);
-                            is_module_path_val = false;
-                            continue;
-                        } else if s == "-p" || s == "--module-path" {
-                            is_module_path_val = true;
-                        }
-
-                        args_content.push_str(&format!("{}\n", escape_arg(&val)));
-                    }
-                    Argument::Complex(c) => {
-                        if check_rules(&Some(c.rules.clone())) {
-                            match &c.value {
-                                ArgumentValue::Single(s) => {
-                                    if skip_next { skip_next = false; continue; }
-                                    if s == "-cp" { skip_next = true; continue; }
-                                    if s == "${classpath}" { continue; }
-                                    if s.starts_with("-DignoreList=") { continue; }
-                                    
-                                    let val = replace_vars(s, auth, &effective_version_id, &assets_dir, &minecraft_dir, asset_index_id, &natives_dir, &libraries_dir);
-                                    if val.starts_with("-DignoreList=") { continue; }
-                                    
-                                    if is_module_path_val {
-                                        let sep = if get_os_name() == "windows" { ";" } else { ":" };
-                                        let mut filtered = Vec::new();
-                                        for p in val.split(sep) {
-                                            let norm = normalize_path_for_comparison(&std::path::PathBuf::from(p));
-                                            if let Some(maven) = library_map.get(&norm) {
-                                                let key = (maven.group.clone(), maven.artifact.clone());
-                                                if module_path_seen_artifacts.insert(key) {
-                                                    filtered.push(p.to_string());
-                                                    module_path_libs.insert(norm);
-                                                }
-                                            } else {
-                                                if module_path_seen_paths.insert(norm.clone()) {
-                                                    filtered.push(p.to_string());
-                                                    module_path_libs.insert(norm);
-                                                }
-                                            }
-                                        }
-                                        let filtered_val = filtered.join(sep);
-                                        args_content.push_str(&format!("{}\n", escape_arg(&filtered_val)));
-                                        module_path_values.extend(filtered);
-                                        is_module_path_val = false;
-                                        continue;
-                                    } else if s == "-p" || s == "--module-path" {
-                                        is_module_path_val = true;
-                                    }
-
-                                    args_content.push_str(&format!("{}\n", escape_arg(&val)));
-                                }
-                                ArgumentValue::Multiple(vec) => {
-                                    for s in vec {
-                                        if skip_next { skip_next = false; continue; }
-                                        if s == "-cp" { skip_next = true; continue; }
-                                        if s == "${classpath}" { continue; }
-                                        if s.starts_with("-DignoreList=") { continue; }
-                                        
-                                        let val = replace_vars(s, auth, &effective_version_id, &assets_dir, &minecraft_dir, asset_index_id, &natives_dir, &libraries_dir);
-                                        if val.starts_with("-DignoreList=") { continue; }
-                                        
-                                        if is_module_path_val {
-                                            let sep = if get_os_name() == "windows" { ";" } else { ":" };
-                                            let mut filtered = Vec::new();
-                                            for p in val.split(sep) {
-                                                let norm = normalize_path_for_comparison(&std::path::PathBuf::from(p));
-                                                if let Some(maven) = library_map.get(&norm) {
-                                                    let key = (maven.group.clone(), maven.artifact.clone());
-                                                    if module_path_seen_artifacts.insert(key) {
-                                                        filtered.push(p.to_string());
-                                                        module_path_libs.insert(norm);
-                                                    }
-                                                } else {
-                                                    if module_path_seen_paths.insert(norm.clone()) {
-                                                        filtered.push(p.to_string());
-                                                        module_path_libs.insert(norm);
-                                                    }
-                                                }
-                                            }
-                                            let filtered_val = filtered.join(sep);
-                                            args_content.push_str(&format!("{}\n", escape_arg(&filtered_val)));
-                                            module_path_values.extend(filtered);
-                                            is_module_path_val = false;
-                                            continue;
-                                        } else if s == "-p" || s == "--module-path" {
-                                            is_module_path_val = true;
-                                        }
-
-                                        args_content.push_str(&format!("{}\n", escape_arg(&val)));
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
-            }
+    // Remote-managed packs only run what staff shipped or approved
+    if modpack_url.as_deref().is_some_and(|u| !u.is_empty()) {
+        let listed: Vec<String> = mods_urls.iter().flatten().map(|url| mod_file_name(url)).collect();
+        let held = super::mod_scan::hold_player_mods(minecraft_dir, &listed, super::mod_scan::approved_hashes)?;
+        if !held.is_empty() {
+            let msg = format!("{} mods añadidos a mano quedan pendientes de aprobación: {}", held.len(), held.join(", "));
+            emit(app, instance_id, "mods", 89, &msg);
         }
     }
 
-    // Ensure essential Forge modules are present on module-path
-    let mp_sep = if get_os_name() == "windows" { ";" } else { ":" };
-    let mut module_path_set: std::collections::HashSet<String> = std::collections::HashSet::new();
-    for v in &module_path_values {
-        module_path_set.insert(v.clone());
+    // Disk savings only; an instance with plain copies still launches fine
+    if let Err(e) = super::mod_store::link_mods(&super::mod_store::store_dir(base_path), minecraft_dir) {
+        eprintln!("[launch_logic] Failed to link mods into mods-store: {}", e);
     }
-    for (_, maven) in &library_map {
-        if maven.group == "cpw.mods" && (maven.artifact == "securejarhandler" || maven.artifact == "modlauncher" || maven.artifact == "bootstraplauncher") {
-            if let Some(rel) = maven_path(maven) {
-                let full = libraries_dir.join(rel).to_string_lossy().to_string();
-                if !module_path_set.contains(&full) {
-                    module_path_set.insert(full);
-                }
-            }
-        }
+    let patched = super::config_patch::apply(minecraft_dir, &super::config_patch::for_instance(instance_id))?;
+    if patched > 0 {
+        emit(app, instance_id, "mods", 89, &format!("Configuración del evento aplicada en {} archivo(s)", patched));
     }
-    // Validate presence on disk; attempt download from Forge Maven if missing
-    for v in module_path_set.clone() {
-        let full_path = std::path::PathBuf::from(&v);
-        if !full_path.exists() {
-            // Forge core modules are hosted on Forge Maven
-            let rel = if let Ok(stripped) = full_path.strip_prefix(&libraries_dir) {
-                stripped.to_string_lossy().replace('\\', "/")
-            } else {
-                continue;
-            };
-            let url = format!("https://maven.minecraftforge.net/{}", rel);
-            let _ = download_file(&url, &full_path, None);
-        }
-    }
-    if !module_path_set.is_empty() {
-        let final_mp = module_path_set.into_iter().collect::<Vec<_>>().join(mp_sep);
-        args_content.push_str("-p\n");
-        args_content.push_str(&format!("{}\n", escape_arg(&final_mp)));
+    for line in super::sync_hooks::run_stage(&hooks, "postSync", minecraft_dir, instance_id)? {
+        emit(app, instance_id, "mods", 89, &format!("Script postSync: {}", line));
     }
+    crate::info_card::refresh(app.as_ref(), instance_id, minecraft_dir);
+    emit(app, instance_id, "mods", 90, "Mods listos");
+    Ok(())
+}
 
-    // Classpath
-    let cp_sep = if get_os_name() == "windows" { ";" } else { ":" };
-    let mut unique_entries = std::collections::HashSet::new();
-    let mut final_classpath = Vec::new();
-    
-    // Identify artifacts on module path
-    let mut module_path_artifacts = std::collections::HashSet::new();
-    for path in &module_path_libs {
-        if let Some(maven) = library_map.get(path) {
-            module_path_artifacts.insert((maven.group.clone(), maven.artifact.clone()));
-        }
+fn sync_translation_pack(
+    minecraft_dir: &Path,
+    url: &str,
+    force_update: bool,
+    app: &Option<AppHandle>,
+    instance_id: &str
+) -> Result<(), String> {
+    emit(app, instance_id, "mods", 88, "Instalando traducción...");
+    let pack_file = super::url_resolver::pack_file_name(url);
+    let pack_path = minecraft_dir.join("resourcepacks").join(&pack_file);
+    if force_update {
+        let _ = fs::remove_file(&pack_path);
     }
-
-    // HARD BLACKLIST for Forge 1.17+ Classpath
-    // These libraries MUST NOT be in the classpath because they are handled by ModulePath
-    let forge_classpath_blacklist = [
-        "asm", "asm-commons", "asm-tree", "asm-util", "asm-analysis", 
-        "java-objc-bridge", "jna", "oshi-core",
-        "sponge-mixin", "mixin", "jakarta.activation", "jakarta.xml.bind"
-    ];
-
-    for entry in classpath_entries {
-        if unique_entries.insert(entry.clone()) {
-            let normalized_entry = normalize_path_for_comparison(&entry);
-            
-            // Smart Filter: Check if library is on module path (by exact path OR by artifact identity)
-            let is_on_module_path = module_path_libs.contains(&normalized_entry);
-            
-            let is_artifact_conflict = if let Some(maven) = library_map.get(&normalized_entry) {
-                module_path_artifacts.contains(&(maven.group.clone(), maven.artifact.clone()))
-            } else {
-                false
-            };
-
-            // Hard Blacklist Check for Forge
-            let is_blacklisted = if matches!(loader.as_deref(), Some("forge")) {
-                let file_name = entry.file_name().and_then(|n| n.to_str()).unwrap_or("").to_lowercase();
-                forge_classpath_blacklist.iter().any(|&b| file_name.contains(b))
-            } else {
-                false
-            };
-
-            // Always include bootstraplauncher in classpath to ensure main class is resolvable
-            let force_include_bootstrap = {
-                let file_name = entry.file_name().and_then(|n| n.to_str()).unwrap_or("").to_lowercase();
-                if file_name.contains("bootstraplauncher") {
-                    true
-                } else {
-                    if let Some(maven) = library_map.get(&normalized_entry) {
-                        maven.group == "cpw.mods" && maven.artifact == "bootstraplauncher"
-                    } else {
-                        false
-                    }
-                }
-            };
-
-            if force_include_bootstrap || (!is_on_module_path && !is_artifact_conflict && !is_blacklisted) {
-                final_classpath.push(entry);
-            }
-        }
+    if let Some(warning) = content_source::fetch(url, &Target::for_instance(instance_id), &pack_path, ContentKind::ResourcePack)? {
+        emit(app, instance_id, "mods", 88, &warning);
     }
+    super::game_options::enable_resource_pack_last(minecraft_dir, &pack_file)
+}
 
-    // Ensure bootstraplauncher jar is present on classpath even if not in classpath_entries
-    if matches!(loader.as_deref(), Some("forge")) {
-        for (_, maven) in &library_map {
-            if maven.group == "cpw.mods" && maven.artifact == "bootstraplauncher" {
-                if let Some(rel) = maven_path(maven) {
-                    let full = libraries_dir.join(rel);
-                    let full_norm = normalize_path_for_comparison(&full);
-                    let exists_in_cp = final_classpath.iter().any(|p| normalize_path_for_comparison(p) == full_norm);
-                    if !exists_in_cp {
-                        final_classpath.push(full);
-                    }
-                }
-            }
-        }
+fn sync_modpack(
+    minecraft_dir: &Path,
+    url: &str,
+    force_update: bool,
+    app: &Option<AppHandle>,
+    instance_id: &str
+) -> Result<(), String> {
+    let zip_path = minecraft_dir.join("modpack.zip");
+    let mods_dir = minecraft_dir.join("mods");
 
-        let mut fmlloader_target: Option<std::path::PathBuf> = None;
-        for (_, maven) in &library_map {
-            if maven.group == "net.minecraftforge" && maven.artifact == "fmlloader" {
-                if let Some(rel) = maven_path(maven) {
-                    fmlloader_target = Some(libraries_dir.join(rel));
-                    break;
-                }
-            }
-        }
-        if let Some(target) = fmlloader_target {
-            if !target.exists() {
-                if let Ok(stripped) = target.strip_prefix(&libraries_dir) {
-                    let rel = stripped.to_string_lossy().replace('\\', "/");
-                    let url = format!("https://maven.minecraftforge.net/{}", rel);
-                    let _ = download_file(&url, &target, None);
-                }
-            }
-            let target_norm = normalize_path_for_comparison(&target);
-            let exists_in_cp = final_classpath.iter().any(|p| normalize_path_for_comparison(p) == target_norm);
-            if !exists_in_cp {
-                final_classpath.push(target);
+    // Check if we already have mods installed
+    let has_installed_mods = if let Ok(entries) = fs::read_dir(&mods_dir) {
+        entries.count() > 0
+    } else {
+        false
+    };
+
+    let mut should_download = !zip_path.exists();
+    let mut should_extract = false;
+    let resolved = content_source::resolve(url, &Target::for_instance(instance_id));
+
+    // Update Logic: Check for size changes if file exists
+    if let (true, Ok(resolved)) = (zip_path.exists(), resolved.as_ref()) {
+        if let (Ok(Some(remote_len)), Ok(meta)) = (content_source::size(resolved), fs::metadata(&zip_path)) {
+            if meta.len() != remote_len {
+                emit(app, instance_id, "mods", 5, "Actualización de modpack detectada...");
+                should_download = true;
+                // Remove old zip to ensure clean download
+                let _ = fs::remove_file(&zip_path);
             }
         }
     }
-    if loader.as_deref().map(|l| l == "vanilla").unwrap_or(true) {
-        // Prefer com.mojang:minecraft jar if present in libraries
-        let mut mojang_minecraft: Option<std::path::PathBuf> = None;
-        for (_, maven) in &library_map {
-            if maven.group == "com.mojang" && maven.artifact == "minecraft" {
-                if let Some(rel) = maven_path(maven) {
-                    mojang_minecraft = Some(libraries_dir.join(rel));
-                    break;
-                }
-            }
-        }
-        if let Some(pref) = mojang_minecraft {
-            let pref_norm = normalize_path_for_comparison(&pref);
-            if let Some(pos) = final_classpath.iter().position(|p| normalize_path_for_comparison(p) == pref_norm) {
-                if pos != 0 {
-                    let p = final_classpath.remove(pos);
-                    final_classpath.insert(0, p);
-                }
-            } else {
-                final_classpath.insert(0, pref);
-            }
-        } else {
-            // Fallback: ensure client.jar is first
-            let client_first = versions_dir.join(&effective_version_id).join(format!("{}.jar", &effective_version_id));
-            let client_norm = normalize_path_for_comparison(&client_first);
-            if let Some(pos) = final_classpath.iter().position(|p| normalize_path_for_comparison(p) == client_norm) {
-                if pos != 0 {
-                    let p = final_classpath.remove(pos);
-                    final_classpath.insert(0, p);
-                }
-            }
-        }
+
+    if should_download {
+         emit(app, instance_id, "mods", 80, "Descargando modpack...");
+         
+         if let Some(warning) = content_source::download(&resolved?, &zip_path, ContentKind::Modpack)? {
+             emit(app, instance_id, "mods", 80, &warning);
+         }
+         should_extract = true;
     }
     
-    let cp_str = final_classpath.iter()
-        .map(|p| p.to_string_lossy().to_string())
-        .collect::<Vec<_>>()
-        .join(cp_sep);
-    
-    args_content.push_str("-cp\n");
-    args_content.push_str(&format!("{}\n", escape_arg(&cp_str)));
+    // Extract if:
+    // 1. We just downloaded it (should_extract = true)
+    // 2. Or user requested Force Update
+    // 3. Or we have the zip but NO mods installed (first run or deleted mods)
+    // 4. Or an earlier extraction was cancelled or cut short
+    if force_update || (!has_installed_mods && zip_path.exists()) || minecraft_dir.join(EXTRACT_MARKER).exists() {
+        should_extract = true;
+    }
     
-    // For Forge, prefer BootstrapLauncher as main class to avoid fmlloader resolution issues
-    let effective_main_class = if matches!(loader.as_deref(), Some("forge")) {
-        "cpw.mods.bootstraplauncher.BootstrapLauncher".to_string()
-    } else {
-        version_info.main_class.clone()
-    };
-    args_content.push_str(&format!("{}\n", escape_arg(&effective_main_class)));
+    if should_extract && zip_path.exists() {
+         // Opened (or unpacked) before cleaning: an unreadable pack must not leave the instance without mods
+         let staging = crate::temp_files::tmp_path(&minecraft_dir.join(".modpack-extract"));
+         let mut zip_archive = None;
+         match super::archive::detect_format(&zip_path) {
+             Some(super::archive::ArchiveFormat::Zip) => {
+                 let file = std::fs::File::open(&zip_path).map_err(|e| e.to_string())?;
+                 let mut archive = super::modpack::open_archive(file)?;
+                 super::modpack::ensure_not_encrypted(&mut archive, usize::MAX)?;
+                 zip_archive = Some(archive);
+             }
+             Some(format) => {
+                 let _ = crate::remover::remove_all(&staging);
+                 if let Err(e) = super::archive::extract_to(&zip_path, format, &staging) {
+                     let _ = crate::remover::remove_all(&staging);
+                     return Err(e);
+                 }
+             }
+             None => return Err(format!("{} El modpack descargado no es un zip, 7z ni rar. Vuelve a descargarlo.", super::modpack::CORRUPT_MARKER)),
+         }
+
+         emit(app, instance_id, "mods", 81, "Sincronizando archivos del modpack...");
+         let extraction = Extraction::start(instance_id);
+         let marker = minecraft_dir.join(EXTRACT_MARKER);
+         let _ = fs::write(&marker, b"");
+         let report = |progress: &ExtractionProgress| emit_extraction(app, instance_id, progress);
+         let result = extract_modpack(minecraft_dir, zip_archive, &staging, &extraction.cancel, &report);
+         let _ = crate::remover::remove_all(&staging);
+         result?;
+         let _ = fs::remove_file(&marker);
+         super::mod_scan::record_pack_mods(minecraft_dir)?;
+    } else if zip_path.exists() {
+        // Si ya existe el zip y NO estamos forzando update ni extrayendo, asumimos que está listo
+        emit(app, instance_id, "mods", 90, "Modpack verificado");
+    }
+    Ok(())
+}
 
-    // Game Args
-    if let Some(w) = width {
-        args_content.push_str(&format!("--width\n{}\n", w));
-    } else {
-        args_content.push_str("--width\n854\n");
+/// Left in the instance while a pack is half cleaned or extracted, so the next sync extracts again.
+const EXTRACT_MARKER: &str = ".modpack-incomplete";
+pub const EXTRACTION_CANCELLED: &str = "Extracción del modpack cancelada";
+
+/// Modpack extractions in progress by instance, stopped by `cancel_modpack_extraction`.
+static EXTRACTIONS: Mutex<Option<HashMap<String, Arc<AtomicBool>>>> = Mutex::new(None);
+
+struct Extraction {
+    instance_id: String,
+    cancel: Arc<AtomicBool>,
+}
+
+impl Extraction {
+    fn start(instance_id: &str) -> Self {
+        let cancel = Arc::new(AtomicBool::new(false));
+        EXTRACTIONS.lock().unwrap_or_else(|e| e.into_inner()).get_or_insert_with(HashMap::new).insert(instance_id.to_string(), cancel.clone());
+        Extraction { instance_id: instance_id.to_string(), cancel }
     }
+}
 
-    if let Some(h) = height {
-        args_content.push_str(&format!("--height\n{}\n", h));
-    } else {
-        args_content.push_str("--height\n480\n");
-    }
-
-    if let Some(args) = &version_info.arguments {
-        if let Some(game_args) = &args.game {
-            let mut skip_next = false;
-            for arg in game_args {
-                match arg {
-                    Argument::Simple(s) => {
-                        if skip_next { skip_next = false; continue; }
-                        if s == "--demo" { continue; }
-                        if s == "--width" || s == "--height" { skip_next = true; continue; }
-                        if s == "${resolution_width}" || s == "${resolution_height}" { continue; }
-
-                        let val = replace_vars(s, auth, &effective_version_id, &assets_dir, &minecraft_dir, asset_index_id, &natives_dir, &libraries_dir);
-                        args_content.push_str(&format!("{}\n", escape_arg(&val)));
-                    }
-                    Argument::Complex(c) => {
-                        if check_rules(&Some(c.rules.clone())) {
-                            match &c.value {
-                                ArgumentValue::Single(s) => {
-                                    if skip_next { skip_next = false; continue; }
-                                    if s == "--demo" { continue; }
-                                    if s == "--width" || s == "--height" { skip_next = true; continue; }
-                                    if s == "${resolution_width}" || s == "${resolution_height}" { continue; }
-
-                                    let val = replace_vars(s, auth, &effective_version_id, &assets_dir, &minecraft_dir, asset_index_id, &natives_dir, &libraries_dir);
-                                    args_content.push_str(&format!("{}\n", escape_arg(&val)));
-                                }
-                                ArgumentValue::Multiple(vec) => {
-                                    for s in vec {
-                                        if skip_next { skip_next = false; continue; }
-                                        if s == "--demo" { continue; }
-                                        if s == "--width" || s == "--height" { skip_next = true; continue; }
-                                        if s == "${resolution_width}" || s == "${resolution_height}" { continue; }
-
-                                        let val = replace_vars(s, auth, &effective_version_id, &assets_dir, &minecraft_dir, asset_index_id, &natives_dir, &libraries_dir);
-                                        args_content.push_str(&format!("{}\n", escape_arg(&val)));
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-        }
-    } else if let Some(legacy_args) = &version_info.minecraft_arguments {
-        let mut skip_next = false;
-        for part in legacy_args.split_whitespace() {
-            if skip_next { skip_next = false; continue; }
-            if part == "--demo" { continue; }
-            if part == "--width" || part == "--height" { skip_next = true; continue; }
-            if part == "${resolution_width}" || part == "${resolution_height}" { continue; }
-
-            let val = replace_vars(part, auth, &effective_version_id, &assets_dir, &minecraft_dir, asset_index_id, &natives_dir, &libraries_dir);
-            args_content.push_str(&format!("{}\n", escape_arg(&val)));
+impl Drop for Extraction {
+    fn drop(&mut self) {
+        if let Some(running) = EXTRACTIONS.lock().unwrap_or_else(|e| e.into_inner()).as_mut() {
+            running.remove(&self.instance_id);
         }
     }
+}
 
-    // Write args file
-    let _ = fs::write(&args_file_path, args_content);
-    
-    // Add args file to command
-    cmd.arg(format!("@{}", args_file_path.to_string_lossy()));
-
-    cmd.current_dir(&minecraft_dir);
-    
-    // Debug: Print command to stdout
-    println!("Launching command: {:?}", cmd);
-
-    emit(&app, instance_id, "listo", 95, "Preparación completa");
-    Ok(cmd)
+/// Stops the instance's modpack extraction before its next file. False when none is running.
+#[tauri::command]
+pub fn cancel_modpack_extraction(instance_id: String) -> bool {
+    let cancel = EXTRACTIONS.lock().ok().and_then(|running| running.as_ref().and_then(|r| r.get(&instance_id).cloned()));
+    if let Some(cancel) = &cancel {
+        cancel.store(true, Ordering::Relaxed);
+    }
+    cancel.is_some()
 }
 
+pub fn extraction_message(progress: &ExtractionProgress) -> String {
+    const MB: u64 = 1024 * 1024;
+    match progress.phase {
+        ExtractionPhase::Cleaning => format!(
+            "Limpiando {}: {}/{} archivos",
+            progress.current.as_deref().unwrap_or("el modpack anterior"), progress.done_files, progress.total_files
+        ),
+        ExtractionPhase::Extracting => format!(
+            "Extrayendo modpack: {}/{} archivos ({}/{} MB)",
+            progress.done_files, progress.total_files, progress.done_bytes / MB, progress.total_bytes.div_ceil(MB)
+        ),
+    }
+}
 
+/// Cleaning sits at 81%, extraction fills 82-89% by bytes.
+pub fn extraction_percent(progress: &ExtractionProgress) -> u8 {
+    match progress.phase {
+        ExtractionPhase::Cleaning => 81,
+        ExtractionPhase::Extracting => 82 + (progress.done_bytes.min(progress.total_bytes) * 7 / progress.total_bytes.max(1)) as u8,
+    }
+}
 
-fn download_libraries_parallel(
-    version_info: &VersionInfo,
-    libraries_dir: &Path,
-    natives_dir: &Path,
-    app: &Option<AppHandle>,
-    instance_id: &str
-) -> Result<Vec<PathBuf>, String> {
-    let tasks: Arc<Mutex<VecDeque<(String, PathBuf, Option<String>, bool)>>> = Arc::new(Mutex::new(VecDeque::new()));
-    let mut classpath_entries = Vec::new();
-    let mut total_libs = 0u64;
+fn emit_extraction(app: &Option<AppHandle>, instance_id: &str, progress: &ExtractionProgress) {
+    if let Some(app) = app {
+        crate::events::emit_progress(app, crate::events::LaunchProgress {
+            extraction: Some(progress.clone()),
+            ..crate::events::LaunchProgress::new(instance_id, "mods", extraction_percent(progress), &extraction_message(progress))
+        });
+    }
+}
 
-    for lib in &version_info.libraries {
-        if !check_rules(&lib.rules) {
+/// Empties the pack-managed folders, then writes the pack from `zip_archive`, or from `staging`
+/// for 7z/rar packs unpacked beforehand. Checks `cancel` between files.
+fn extract_modpack(
+    minecraft_dir: &Path,
+    zip_archive: Option<zip::ZipArchive<fs::File>>,
+    staging: &Path,
+    cancel: &AtomicBool,
+    report: &(dyn Fn(&ExtractionProgress) + Sync),
+) -> Result<(), String> {
+    for folder in PACK_MANAGED_FOLDERS {
+        let target_path = minecraft_dir.join(folder);
+        if !target_path.exists() {
             continue;
         }
-        if let Some(downloads) = &lib.downloads {
-            if let Some(artifact) = &downloads.artifact {
-                if let Some(path_str) = &artifact.path {
-                    total_libs += 1;
-                    classpath_entries.push(libraries_dir.join(path_str));
-                    tasks.lock().map_err(|_| "Failed to lock tasks".to_string())?
-                        .push_back((artifact.url.clone(), libraries_dir.join(path_str), Some(artifact.sha1.clone()), false));
-                }
-            }
-            if let Some(classifiers) = &downloads.classifiers {
-                let os = get_os_name();
-                if let Some(natives_map) = &lib.natives {
-                    if let Some(classifier_key) = natives_map.get(os) {
-                        let arch = get_arch();
-                        let key = classifier_key.replace("${arch}", if arch == "x86" { "32" } else { "64" });
-                        if let Some(artifact) = classifiers.get(&key) {
-                            if let Some(path_str) = &artifact.path {
-                                total_libs += 1;
-                                tasks.lock().map_err(|_| "Failed to lock tasks".to_string())?
-                                    .push_back((artifact.url.clone(), libraries_dir.join(path_str), Some(artifact.sha1.clone()), true));
-                            }
-                        }
-                    }
-                }
-            }
-        } else if let Some(maven) = parse_maven_name(&lib.name) {
-            let base_url = lib.url.clone().unwrap_or_else(|| "https://libraries.minecraft.net/".to_string());
-            if let Some(path_str) = maven_path(&maven) {
-                let url = format!("{}{}", ensure_trailing_slash(&base_url), path_str);
-                let is_native = maven.classifier.as_deref().map(|c| c.contains("natives")).unwrap_or(false);
-                total_libs += 1;
-                if !is_native {
-                    classpath_entries.push(libraries_dir.join(&path_str));
+        let cleaned = crate::remover::remove_tree(&target_path, cancel, &|removal| report(&ExtractionProgress {
+            phase: ExtractionPhase::Cleaning,
+            done_files: removal.done_files,
+            total_files: removal.total_files,
+            done_bytes: removal.done_bytes,
+            total_bytes: removal.total_bytes,
+            current: Some(folder.to_string()),
+        }));
+        // A file that can't be deleted gets overwritten by the pack, or lingers as it did before
+        if cleaned == Ok(false) {
+            return Err(EXTRACTION_CANCELLED.to_string());
+        }
+    }
+
+    let mut progress = ExtractionProgress { phase: ExtractionPhase::Extracting, ..Default::default() };
+    let advance = |progress: &mut ExtractionProgress, name: &str, size: u64| {
+        progress.done_files += 1;
+        progress.done_bytes += size;
+        progress.current = Some(name.to_string());
+        report(progress);
+    };
+    if let Some(mut archive) = zip_archive {
+        let entries: Vec<(usize, PathBuf, u64)> = (0..archive.len())
+            .filter_map(|i| {
+                let entry = archive.by_index_raw(i).ok()?;
+                // `enclosed_name` drops `..` and absolute names, which would write outside the instance
+                let name = entry.enclosed_name()?.to_string_lossy().replace('\\', "/");
+                if entry.is_dir() {
+                    return None;
                 }
-                tasks.lock().map_err(|_| "Failed to lock tasks".to_string())?
-                    .push_back((url, libraries_dir.join(path_str), None, is_native));
+                Some((i, pack_entry_target(minecraft_dir, &name)?, entry.size()))
+            })
+            .collect();
+        progress.total_files = entries.len() as u64;
+        progress.total_bytes = entries.iter().map(|(_, _, size)| size).sum();
+        for (i, final_path, size) in entries {
+            if cancel.load(Ordering::Relaxed) {
+                return Err(EXTRACTION_CANCELLED.to_string());
             }
-            if let Some(natives_map) = &lib.natives {
-                let os = get_os_name();
-                if let Some(classifier_key) = natives_map.get(os) {
-                    let arch = get_arch();
-                    let classifier = classifier_key.replace("${arch}", if arch == "x86" { "32" } else { "64" });
-                    let mut native_maven = maven.clone();
-                    native_maven.classifier = Some(classifier);
-                    if let Some(native_path) = maven_path(&native_maven) {
-                        let url = format!("{}{}", ensure_trailing_slash(&base_url), native_path);
-                        total_libs += 1;
-                        tasks.lock().map_err(|_| "Failed to lock tasks".to_string())?
-                            .push_back((url, libraries_dir.join(native_path), None, true));
-                    }
-                }
+            let mut f = archive.by_index(i).map_err(super::modpack::archive_error)?;
+            if let Some(p) = final_path.parent() { let _ = fs::create_dir_all(p); }
+            crate::temp_files::write_atomic(&final_path, |out_file| {
+                std::io::copy(&mut f, out_file).map(|_| ()).map_err(|e| super::modpack::archive_error(e.into()))
+            })?;
+            advance(&mut progress, f.name(), size);
+        }
+    } else {
+        let files: Vec<(String, PathBuf, PathBuf, u64)> = super::archive::walk_files(staging).into_iter()
+            .filter_map(|(name, path)| {
+                let final_path = pack_entry_target(minecraft_dir, &name)?;
+                let size = fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+                Some((name, path, final_path, size))
+            })
+            .collect();
+        progress.total_files = files.len() as u64;
+        progress.total_bytes = files.iter().map(|(_, _, _, size)| size).sum();
+        for (name, path, final_path, size) in files {
+            if cancel.load(Ordering::Relaxed) {
+                return Err(EXTRACTION_CANCELLED.to_string());
             }
+            if let Some(p) = final_path.parent() { let _ = fs::create_dir_all(p); }
+            let _ = fs::remove_file(&final_path);
+            fs::rename(&path, &final_path).map_err(|e| format!("No se pudo mover {}: {}", name, e))?;
+            advance(&mut progress, &name, size);
         }
     }
+    Ok(())
+}
 
-    if total_libs == 0 {
-        return Ok(classpath_entries);
-    }
-
-    let done = Arc::new(AtomicU64::new(0));
-    let error = Arc::new(Mutex::new(None::<String>));
-    let workers = 10usize;
-    let mut handles = Vec::new();
+/// Emptied before a pack is extracted, so files a new version drops don't linger.
+pub const PACK_MANAGED_FOLDERS: [&str; 5] = ["mods", "config", "scripts", "kubejs", "defaultconfigs"];
 
-    for _ in 0..workers {
-        let tasks = Arc::clone(&tasks);
-        let done = Arc::clone(&done);
-        let error = Arc::clone(&error);
-        let app = app.clone();
-        let instance_id = instance_id.to_string();
-        let natives_dir = natives_dir.to_path_buf();
-        handles.push(std::thread::spawn(move || {
-            loop {
-                if error.lock().ok().and_then(|e| e.clone()).is_some() {
-                    break;
-                }
-                let task = {
-                    let mut guard = match tasks.lock() {
-                        Ok(g) => g,
-                        Err(_) => break,
-                    };
-                    guard.pop_front()
-                };
-                let (url, path, hash, extract) = match task {
-                    Some(t) => t,
-                    None => break,
-                };
-                let download_res = if let Some(hash) = &hash {
-                    download_file(&url, &path, Some(hash))
-                } else {
-                    download_file(&url, &path, None)
-                };
-                if let Err(e) = download_res {
-                    if let Ok(mut guard) = error.lock() {
-                        if guard.is_none() {
-                            *guard = Some(e);
-                        }
-                    }
-                    break;
-                }
-                if extract {
-                    if let Err(e) = extract_natives(&path, &natives_dir) {
-                        if let Ok(mut guard) = error.lock() {
-                            if guard.is_none() {
-                                *guard = Some(e);
-                            }
-                        }
-                        break;
-                    }
-                }
-                let current = done.fetch_add(1, Ordering::SeqCst) + 1;
-                if current % 10 == 0 || current == total_libs {
-                    let percent = 20 + ((current * 30) / total_libs) as u8;
-                    let msg = format!("Verificando librerías {}/{}", current, total_libs);
-                    emit(&app, &instance_id, "librerias", percent, &msg);
-                }
-            }
-        }));
+/// Where a pack entry goes in the instance, `None` for metadata, folders and names that would
+/// leave it (`..`, absolute paths). `overrides/` is stripped and jars at the root of the pack go
+/// to `mods/`.
+pub fn pack_entry_target(minecraft_dir: &Path, name: &str) -> Option<PathBuf> {
+    if name == "manifest.json" || name == "modlist.html" || name == "instance.cfg" || name.ends_with('/') {
+        return None;
     }
-
-    for handle in handles {
-        let _ = handle.join();
+    if name.contains(':') || !Path::new(&name.replace('\\', "/")).components().all(|c| matches!(c, Component::Normal(_))) {
+        return None;
     }
+    Some(if let Some(stripped) = name.strip_prefix("overrides/") {
+        minecraft_dir.join(stripped)
+    } else if !name.contains('/') && name.ends_with(".jar") {
+        minecraft_dir.join("mods").join(name)
+    } else {
+        minecraft_dir.join(name)
+    })
+}
 
-    if let Ok(guard) = error.lock() {
-        if let Some(err) = guard.clone() {
-            return Err(err);
-        }
-    }
+/// Parallel download workers; data saver mode keeps a quarter so the connection stays usable.
+pub fn worker_count(default: usize, data_saver: bool) -> usize {
+    if data_saver { (default / 4).max(2) } else { default }
+}
 
-    Ok(classpath_entries)
+/// Pool size from `crate::tuning` (settings or auto-tuned), reduced in data saver mode.
+fn download_workers(pick: fn(&crate::tuning::Tuning) -> usize) -> usize {
+    worker_count(pick(&crate::tuning::current()), crate::settings::load_settings().data_saver)
 }
 
 pub fn download_assets_parallel(
     assets_dir: &Path,
-    asset_index: &AssetIndex,
+    index_path: &Path,
     app: &Option<AppHandle>,
     instance_id: &str
 ) -> Result<(), String> {
-    let total = asset_index.objects.len() as u64;
+    let app = app.clone();
+    let instance_id = instance_id.to_string();
+    download_assets_with_progress(assets_dir, index_path, Arc::new(move |current, total| {
+        let percent = 60 + ((current * 15) / total) as u8;
+        let msg = format!("Verificando assets {}/{}{}", current, total, crate::http::backoff_note());
+        emit(&app, &instance_id, "assets", percent, &msg);
+    }))
+}
+
+// Objects parsed ahead of the workers; the parser waits when they fall behind
+const ASSET_QUEUE_DEPTH: usize = 1024;
+
+/// The same mid-sized object on every asset mirror, taken from an index already on disk, for
+/// the mirror speedtest. Empty when no index has been downloaded yet.
+pub fn asset_probe_urls(assets_dir: &Path) -> Vec<String> {
+    let mut indexes: Vec<_> = fs::read_dir(assets_dir.join("indexes"))
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| p.extension().is_some_and(|ext| ext == "json"))
+        .collect();
+    indexes.sort();
+    let mut probe = None;
+    for index in indexes {
+        let _ = super::asset_stream::for_each_object_in(&index, |_, object| {
+            if ASSET_PROBE_SIZE.contains(&object.size) {
+                probe = Some(object.hash);
+            }
+            probe.is_none()
+        });
+        if probe.is_some() {
+            break;
+        }
+    }
+    let Some(hash) = probe else { return Vec::new() };
+    ASSET_MIRRORS.iter().map(|mirror| format!("{}/{}/{}", mirror, &hash[..2.min(hash.len())], hash)).collect()
+}
+
+/// Verifies and downloads every object of the asset index at `index_path` with the download
+/// worker pool. The index is streamed into a bounded queue instead of being loaded whole.
+/// `on_progress(done, total)` is called from the workers every 50 objects and at the end.
+pub fn download_assets_with_progress(
+    assets_dir: &Path,
+    index_path: &Path,
+    on_progress: Arc<dyn Fn(u64, u64) + Send + Sync>
+) -> Result<(), String> {
+    let total = super::asset_stream::count_objects(index_path)?;
     if total == 0 {
         return Ok(());
     }
-    let tasks = Arc::new(Mutex::new(VecDeque::new()));
-    for (_name, object) in &asset_index.objects {
-        let hash_head = &object.hash[0..2];
-        let object_path = assets_dir.join("objects").join(hash_head).join(&object.hash);
-        let url = format!("{}/{}/{}", RESOURCES_URL, hash_head, object.hash);
-        tasks.lock().map_err(|_| "Failed to lock tasks".to_string())?
-            .push_back((url, object_path, object.hash.clone()));
-    }
+    // A self-hosted mirror is the only source: `download_game_file` moves the official URL onto it
+    let mirrors: Arc<[String]> = if crate::mirror::active() {
+        vec![RESOURCES_URL.to_string()].into()
+    } else {
+        // Fastest mirror from earlier runs first; the others are only tried for objects it fails on
+        crate::download_stats::rank_mirrors(&ASSET_MIRRORS).into()
+    };
+    let objects_dir: Arc<Path> = assets_dir.join("objects").into();
+    // Quick mode (HDDs by default) trusts objects of the right size instead of hashing them all
+    let quick = crate::tuning::current().verify_mode == crate::tuning::VerifyMode::Quick;
+    let (sender, receiver) = std::sync::mpsc::sync_channel::<String>(ASSET_QUEUE_DEPTH);
+    let receiver = Arc::new(Mutex::new(receiver));
 
     let done = Arc::new(AtomicU64::new(0));
     let error = Arc::new(Mutex::new(None::<String>));
-    let workers = 24usize;
+    let workers = download_workers(|t| t.asset_workers);
     let mut handles = Vec::new();
 
     for _ in 0..workers {
-        let tasks = Arc::clone(&tasks);
+        let receiver = Arc::clone(&receiver);
         let done = Arc::clone(&done);
         let error = Arc::clone(&error);
-        let app = app.clone();
-        let instance_id = instance_id.to_string();
+        let mirrors = Arc::clone(&mirrors);
+        let objects_dir = Arc::clone(&objects_dir);
+        let on_progress = Arc::clone(&on_progress);
         handles.push(std::thread::spawn(move || {
             loop {
                 if error.lock().ok().and_then(|e| e.clone()).is_some() {
                     break;
                 }
-                let task = {
-                    let mut guard = match tasks.lock() {
-                        Ok(g) => g,
-                        Err(_) => break,
-                    };
-                    guard.pop_front()
-                };
-                let (url, path, hash) = match task {
-                    Some(t) => t,
-                    None => break,
+                let task = match receiver.lock() {
+                    Ok(guard) => guard.recv(),
+                    Err(_) => break,
                 };
-                if let Err(e) = download_file(&url, &path, Some(&hash)) {
+                let Ok(hash) = task else { break };
+                let hash_head = &hash[0..2];
+                let path = objects_dir.join(hash_head).join(&hash);
+                // First mirror that serves it; the last error otherwise
+                let mut result = Err(String::new());
+                for mirror in mirrors.iter() {
+                    result = download_game_file(&format!("{}/{}/{}", mirror, hash_head, hash), &path, Some(&hash));
+                    if result.is_ok() {
+                        break;
+                    }
+                }
+                if let Err(e) = result {
                     if let Ok(mut guard) = error.lock() {
                         if guard.is_none() {
                             *guard = Some(e);
@@ -1229,14 +1179,30 @@ pub fn download_assets_parallel(
                     break;
                 }
                 let current = done.fetch_add(1, Ordering::SeqCst) + 1;
-                if current % 50 == 0 || current == total {
-                    let percent = 60 + ((current * 15) / total) as u8;
-                    let msg = format!("Verificando assets {}/{}", current, total);
-                    emit(&app, &instance_id, "assets", percent, &msg);
+                if current.is_multiple_of(50) || current == total {
+                    on_progress(current, total);
                 }
             }
         }));
     }
+    drop(receiver);
+
+    // Stops once every worker is gone (an error), since sends then fail
+    let streamed = super::asset_stream::for_each_object_in(index_path, |_name, object| {
+        if object.hash.len() < 2 {
+            return true;
+        }
+        let path = objects_dir.join(&object.hash[0..2]).join(&object.hash);
+        if quick && fs::metadata(&path).map(|m| m.len() == object.size).unwrap_or(false) {
+            let current = done.fetch_add(1, Ordering::SeqCst) + 1;
+            if current.is_multiple_of(50) || current == total {
+                on_progress(current, total);
+            }
+            return true;
+        }
+        sender.send(object.hash).is_ok()
+    });
+    drop(sender);
 
     for handle in handles {
         let _ = handle.join();
@@ -1247,13 +1213,17 @@ pub fn download_assets_parallel(
             return Err(err);
         }
     }
-    Ok(())
+    streamed.map(|_| ())
 }
 
 pub fn load_fabric_profile_info(mc_version: &str) -> Result<VersionInfo, String> {
+    FABRIC_PROFILE_CACHE.get_or_fetch(mc_version, || fetch_fabric_profile_info(mc_version))
+}
+
+fn fetch_fabric_profile_info(mc_version: &str) -> Result<VersionInfo, String> {
     // Get latest stable loader for this MC version
     let list: serde_json::Value = reqwest::blocking::get(
-        &format!("https://meta.fabricmc.net/v2/versions/loader/{}", mc_version)
+        crate::mirror::rewrite(&format!("https://meta.fabricmc.net/v2/versions/loader/{}", mc_version))
     )
         .map_err(|e| format!("Failed to fetch fabric loader list: {}", e))?
         .json()
@@ -1270,7 +1240,7 @@ pub fn load_fabric_profile_info(mc_version: &str) -> Result<VersionInfo, String>
         mc_version, loader_version
     );
 
-    let json_text = reqwest::blocking::get(&url)
+    let json_text = reqwest::blocking::get(crate::mirror::rewrite(&url))
         .map_err(|e| format!("Failed to fetch fabric profile: {}", e))?
         .text()
         .map_err(|e| e.to_string())?;
@@ -1288,14 +1258,12 @@ fn deduplicate_libraries(libs: Vec<Library>) -> Vec<Library> {
         if let Some(maven) = parse_maven_name(&lib.name) {
             // Key includes classifier to distinguish natives
             let key = format!("{}:{}:{}", maven.group, maven.artifact, maven.classifier.unwrap_or_default());
-            if !seen.contains_key(&key) {
-                seen.insert(key, true);
+            if seen.insert(key, true).is_none() {
                 result.push(lib);
             }
         } else {
              // Fallback for non-standard names
-             if !seen.contains_key(&lib.name) {
-                 seen.insert(lib.name.clone(), true);
+             if seen.insert(lib.name.clone(), true).is_none() {
                  result.push(lib);
              }
         }
@@ -1305,17 +1273,20 @@ fn deduplicate_libraries(libs: Vec<Library>) -> Vec<Library> {
     result
 }
 
-pub fn resolve_complete_version_info(
-    version_id: &str,
-    versions_dir: &Path,
-    manifest: &VersionManifest
-) -> Result<VersionInfo, String> {
+/// `version_id` merged with its parents, each read through the sources configured in the
+/// settings (see `version_store::configured_sources`).
+pub fn resolve_complete_version_info(version_id: &str, versions_dir: &Path) -> Result<VersionInfo, String> {
+    resolve_with(version_id, &super::version_store::configured_sources(versions_dir))
+}
+
+pub fn resolve_with(version_id: &str, store: &dyn super::version_store::VersionStore) -> Result<VersionInfo, String> {
     // 1. Load current version info (local or remote)
-    let mut current_info = load_version_info(version_id, versions_dir, manifest)?;
+    let mut current_info = store.load(version_id)?
+        .ok_or_else(|| format!("Version {} not found locally or in any metadata source", version_id))?;
 
     // 2. Check inheritance
     if let Some(parent_id) = &current_info.inherits_from {
-        let parent_info = resolve_complete_version_info(parent_id, versions_dir, manifest)?;
+        let parent_info = resolve_with(parent_id, store)?;
         
         // 3. Merge Libraries (Append child libs to parent libs)
         let mut merged_libs = parent_info.libraries.clone();
@@ -1371,34 +1342,34 @@ pub fn resolve_complete_version_info(
     Ok(current_info)
 }
 
-fn load_version_info(version_id: &str, versions_dir: &Path, manifest: &VersionManifest) -> Result<VersionInfo, String> {
-    let version_json_path = versions_dir.join(version_id).join(format!("{}.json", version_id));
+pub fn forge_installer_url(mc_version: &str, forge_version: &str) -> String {
+    format!(
+        "https://maven.minecraftforge.net/net/minecraftforge/forge/{0}-{1}/forge-{0}-{1}-installer.jar",
+        mc_version, forge_version
+    )
+}
+
+/// Fresh copy of the official Forge installer under `forge/installers`.
+pub fn download_forge_installer(base_path: &Path, mc_version: &str, forge_version: &str) -> Result<PathBuf, String> {
+    let installer_dir = base_path.join("forge").join("installers");
+    let installer_name = format!("forge-{}-{}-installer.jar", mc_version, forge_version);
+    let installer_path = installer_dir.join(&installer_name);
+    let installer_url = forge_installer_url(mc_version, forge_version);
     
-    if version_json_path.exists() {
-        let content = fs::read_to_string(&version_json_path).map_err(|e| e.to_string())?;
-        serde_json::from_str(&content).map_err(|e| e.to_string())
-    } else {
-        let version_entry = manifest.versions.iter().find(|v| v.id == version_id)
-            .ok_or(format!("Version {} not found in manifest or locally", version_id))?;
-            
-        let client = reqwest::blocking::Client::builder()
-            .user_agent("DrkLauncher/1.0")
-            .build()
-            .map_err(|e| format!("Failed to build http client: {}", e))?;
-
-        let info: VersionInfo = client.get(&version_entry.url)
-            .send()
-            .map_err(|e| format!("Failed to fetch version info: {}", e))?
-            .json()
-            .map_err(|e| format!("Failed to parse version info: {}", e))?;
-        
-        if let Some(p) = version_json_path.parent() {
-            fs::create_dir_all(p).map_err(|e| e.to_string())?;
-        }
-        let json = serde_json::to_string_pretty(&info).unwrap();
-        fs::write(&version_json_path, json).map_err(|e| e.to_string())?;
-        Ok(info)
+    // Force clean download to avoid corrupt/html files from previous 404s
+    if installer_path.exists() {
+        let _ = fs::remove_file(&installer_path);
+    }
+    
+    download_game_file(&installer_url, &installer_path, None)?;
+    if !installer_path.exists() {
+        let installer_url_alt = format!(
+            "https://maven.creeperhost.net/net/minecraftforge/forge/{}-{}/{}",
+            mc_version, forge_version, installer_name
+        );
+        let _ = download_game_file(&installer_url_alt, &installer_path, None);
     }
+    Ok(installer_path)
 }
 
 pub fn ensure_forge_installed(
@@ -1407,53 +1378,32 @@ pub fn ensure_forge_installed(
     app: &Option<AppHandle>,
     instance_id: &str
 ) -> Result<String, String> {
-    let forge_version = get_forge_recommended_version(mc_version)?;
+    // Pinned by the catalog or locked on first install; never silently upgraded
+    let forge_version = super::loader_pin::forge_version(instance_id, mc_version)?;
     let versions_dir = base_path.join("versions");
+    let store = super::version_store::LocalVersions::new(&versions_dir);
     let candidates = [
         format!("{}-forge-{}", mc_version, forge_version),
         format!("forge-{}-{}", mc_version, forge_version),
     ];
-    for candidate in &candidates {
-        let json_path = versions_dir.join(candidate).join(format!("{}.json", candidate));
-        if json_path.exists() {
-            return Ok(candidate.to_string());
-        }
+    if let Some(candidate) = candidates.iter().find(|c| store.contains(c)) {
+        return Ok(candidate.to_string());
     }
     if let Some(found) = find_existing_forge_version(&versions_dir, mc_version, &forge_version) {
         return Ok(found);
     }
 
     emit(app, instance_id, "forge", 22, "Descargando Forge");
-    let installer_dir = base_path.join("forge").join("installers");
-    let installer_name = format!("forge-{}-{}-installer.jar", mc_version, forge_version);
-    let installer_path = installer_dir.join(&installer_name);
-    let installer_url = format!(
-        "https://maven.minecraftforge.net/net/minecraftforge/forge/{}-{}/{}",
-        mc_version, forge_version, installer_name
-    );
-    
-    // Force clean download to avoid corrupt/html files from previous 404s
-    if installer_path.exists() {
-        let _ = fs::remove_file(&installer_path);
-    }
-    
-    download_file(&installer_url, &installer_path, None)?;
-    if !installer_path.exists() {
-        let installer_url_alt = format!(
-            "https://maven.creeperhost.net/net/minecraftforge/forge/{}-{}/{}",
-            mc_version, forge_version, installer_name
-        );
-        let _ = download_file(&installer_url_alt, &installer_path, None);
-    }
+    let installer_path = download_forge_installer(base_path, mc_version, &forge_version)?;
 
     emit(app, instance_id, "forge", 24, "Instalando Forge (Ejecutable)");
     
     // Ejecutar el installer oficial
     let java_ver = get_required_java_version(mc_version);
-    let java_path = match get_java_path_for_major(java_ver) {
+    let java_path = match get_java_path_in(base_path, java_ver) {
         Ok(p) => p,
         Err(_) => {
-            let path_str = download_java(java_ver, app.as_ref(), Some(instance_id))?;
+            let path_str = download_java_in(base_path, java_ver, app.as_ref(), Some(instance_id))?;
             PathBuf::from(path_str)
         }
     };
@@ -1469,46 +1419,55 @@ pub fn ensure_forge_installed(
     }
     
     let mut success = false;
+    let mut existing = None;
     let mut last_stdout = String::new();
     let mut last_stderr = String::new();
+    // Every attempt goes to logs/loader-install, not just the one in the error message
+    let mut install_log = super::loader_log::InstallLog::new(instance_id, "forge", &format!("{}-{}", mc_version, forge_version));
+    // The installer runs from base_path, so relative args keep non-ASCII profile folders off the command line
+    let installer_arg = jvm_path_arg(&installer_path, base_path).to_string_lossy().to_string();
+    let target_arg = jvm_path_arg(base_path, base_path).to_string_lossy().to_string();
     for attempt in [
-        vec!["-jar", installer_path.to_string_lossy().as_ref(), "--installClient"],
-        vec!["-jar", installer_path.to_string_lossy().as_ref(), "--installClient", base_path.to_string_lossy().as_ref()],
-        vec!["-jar", installer_path.to_string_lossy().as_ref(), "--installClient", "--target", base_path.to_string_lossy().as_ref()],
+        vec!["-jar", installer_arg.as_str(), "--installClient"],
+        vec!["-jar", installer_arg.as_str(), "--installClient", target_arg.as_str()],
+        vec!["-jar", installer_arg.as_str(), "--installClient", "--target", target_arg.as_str()],
     ] {
         let mut cmd = Command::new(&java_path);
-        for a in attempt {
-            cmd.arg(a);
-        }
+        cmd.args(&attempt);
         let output = cmd.current_dir(base_path).output().map_err(|e| format!("Failed to run forge installer: {}", e))?;
+        install_log.attempt(&attempt, &output);
         last_stdout = String::from_utf8_lossy(&output.stdout).to_string();
         last_stderr = String::from_utf8_lossy(&output.stderr).to_string();
         if output.status.success() {
             success = true;
             break;
         }
-        for candidate in &candidates {
-            let json_path = versions_dir.join(candidate).join(format!("{}.json", candidate));
-            if json_path.exists() {
-                success = true;
-                break;
-            }
+        if candidates.iter().any(|c| store.contains(c)) {
+            success = true;
+            break;
         }
-        if success { break; }
-        if let Some(found) = find_existing_forge_version(&versions_dir, mc_version, &forge_version) {
-            return Ok(found);
+        existing = find_existing_forge_version(&versions_dir, mc_version, &forge_version);
+        if existing.is_some() {
+            break;
         }
     }
+    // Newer installers write installer.log in the working dir, older ones `<jar>.log` beside the jar
+    install_log.attach(&base_path.join("installer.log"));
+    if let Some(name) = installer_path.file_name() {
+        install_log.attach(&installer_path.with_file_name(format!("{}.log", name.to_string_lossy())));
+    }
+    let log_path = install_log.save(!success && existing.is_none());
+    if let Some(found) = existing {
+        return Ok(found);
+    }
     if !success {
-        return Err(format!("Forge installer failed. Stdout: {}, Stderr: {}", last_stdout, last_stderr));
+        let log_note = log_path.map(|p| format!(" Registro completo: {}", p.display())).unwrap_or_default();
+        return Err(format!("Forge installer failed. Stdout: {}, Stderr: {}.{}", last_stdout, last_stderr, log_note));
     }
 
     // Verificar resultado
-    for candidate in &candidates {
-        let json_path = versions_dir.join(candidate).join(format!("{}.json", candidate));
-        if json_path.exists() {
-            return Ok(candidate.to_string());
-        }
+    if let Some(candidate) = candidates.iter().find(|c| store.contains(c)) {
+        return Ok(candidate.to_string());
     }
 
     // Si no encontramos el json en las rutas esperadas, intentamos buscar en la carpeta versions
@@ -1518,11 +1477,8 @@ pub fn ensure_forge_installed(
             let path = entry.path();
             if path.is_dir() {
                 if let Some(name) = path.file_name().and_then(|n| n.to_str()) {
-                    if name.starts_with(mc_version) && name.contains("forge") {
-                        let json = path.join(format!("{}.json", name));
-                        if json.exists() {
-                            return Ok(name.to_string());
-                        }
+                    if name.starts_with(mc_version) && name.contains("forge") && store.contains(name) {
+                        return Ok(name.to_string());
                     }
                 }
             }
@@ -1533,8 +1489,12 @@ pub fn ensure_forge_installed(
 }
 
 pub fn get_forge_recommended_version(mc_version: &str) -> Result<String, String> {
+    FORGE_RECOMMENDED_CACHE.get_or_fetch(mc_version, || fetch_forge_recommended_version(mc_version))
+}
+
+fn fetch_forge_recommended_version(mc_version: &str) -> Result<String, String> {
     let client = reqwest::blocking::Client::builder()
-        .user_agent("Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36")
+        .user_agent(crate::http::download_user_agent())
         .build()
         .map_err(|e| e.to_string())?;
 
@@ -1548,7 +1508,7 @@ pub fn get_forge_recommended_version(mc_version: &str) -> Result<String, String>
     let mut last_err = String::new();
     
     for url in urls {
-        let response = client.get(url)
+        let response = client.get(crate::mirror::rewrite(url))
             .send();
             
         match response {
@@ -1596,7 +1556,7 @@ pub fn get_forge_recommended_version(mc_version: &str) -> Result<String, String>
         "https://maven.creeperhost.net/net/minecraftforge/forge/maven-metadata.xml"
     ];
     for url in urls_xml {
-        let resp = client.get(url).send();
+        let resp = client.get(crate::mirror::rewrite(url)).send();
         if let Ok(r) = resp {
             if r.status().is_success() {
                 if let Ok(text) = r.text() {
@@ -1644,7 +1604,7 @@ pub fn get_forge_recommended_version(mc_version: &str) -> Result<String, String>
         "https://maven.creeperhost.net/net/minecraftforge/forge/",
     ];
     for url in listing_urls {
-        if let Ok(r) = client.get(url).send() {
+        if let Ok(r) = client.get(crate::mirror::rewrite(url)).send() {
             if r.status().is_success() {
                 if let Ok(text) = r.text() {
                     let mut best: Option<String> = None;
@@ -1652,8 +1612,7 @@ pub fn get_forge_recommended_version(mc_version: &str) -> Result<String, String>
                         if let Some(hpos) = line.find("href=") {
                             let rest = &line[hpos+5..];
                             // href may use quotes or not; handle common patterns
-                            let candidate = if rest.starts_with('"') {
-                                let rest2 = &rest[1..];
+                            let candidate = if let Some(rest2) = rest.strip_prefix('"') {
                                 if let Some(endq) = rest2.find('"') {
                                     &rest2[..endq]
                                 } else { continue }
@@ -1713,64 +1672,276 @@ pub fn find_existing_forge_version(versions_dir: &Path, mc_version: &str, forge_
             }
         }
         let name = entry.file_name().to_string_lossy().to_string();
-        if name.contains("forge") && name.contains(mc_version) && name.contains(forge_version) {
-            let json_path = versions_dir.join(&name).join(format!("{}.json", name));
-            if json_path.exists() {
-                return Some(name);
-            }
+        if name.contains("forge") && name.contains(mc_version) && name.contains(forge_version)
+            && super::version_store::LocalVersions::new(versions_dir).contains(&name)
+        {
+            return Some(name);
         }
     }
     None
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    #[test]
+    fn pack_entries_map_into_the_instance() {
+        let dir = Path::new("mc");
+        assert_eq!(pack_entry_target(dir, "overrides/config/a.toml"), Some(dir.join("config/a.toml")));
+        assert_eq!(pack_entry_target(dir, "extra.jar"), Some(dir.join("mods").join("extra.jar")));
+        assert_eq!(pack_entry_target(dir, "manifest.json"), None);
+        assert_eq!(pack_entry_target(dir, "overrides/"), None);
+        for name in ["overrides/../../x", "../mods/x.jar", "/etc/x", "..\\..\\x.jar", "C:/x.jar"] {
+            assert_eq!(pack_entry_target(dir, name), None, "{}", name);
+        }
+    }
 
+    #[test]
+    fn extraction_reports_each_file_and_stops_when_cancelled() {
+        let dir = tempfile::tempdir().unwrap();
+        let pack = dir.path().join("modpack.zip");
+        let mut zip = zip::ZipWriter::new(fs::File::create(&pack).unwrap());
+        for (name, body) in [("manifest.json", "{}"), ("overrides/mods/a.jar", "aaaa"), ("overrides/config/b.toml", "bb"), ("overrides/../../escaped.txt", "x")] {
+            zip.start_file(name, zip::write::FileOptions::default()).unwrap();
+            std::io::Write::write_all(&mut zip, body.as_bytes()).unwrap();
+        }
+        zip.finish().unwrap();
+        let minecraft_dir = dir.path().join("minecraft");
+        fs::create_dir_all(minecraft_dir.join("mods")).unwrap();
+        fs::write(minecraft_dir.join("mods").join("old.jar"), "old").unwrap();
+        let open = || zip::ZipArchive::new(fs::File::open(&pack).unwrap()).unwrap();
+
+        let reports = Mutex::new(Vec::new());
+        let report = |p: &ExtractionProgress| reports.lock().unwrap().push(p.clone());
+        extract_modpack(&minecraft_dir, Some(open()), &dir.path().join("staging"), &AtomicBool::new(false), &report).unwrap();
+        assert!(!minecraft_dir.join("mods").join("old.jar").exists());
+        assert_eq!(fs::read_to_string(minecraft_dir.join("config").join("b.toml")).unwrap(), "bb");
+        assert!(!dir.path().join("escaped.txt").exists() && !minecraft_dir.join("escaped.txt").exists());
+        let last = reports.lock().unwrap().last().cloned().unwrap();
+        assert_eq!((last.phase, last.done_files, last.total_files, last.done_bytes, last.total_bytes), (ExtractionPhase::Extracting, 2, 2, 6, 6));
+        assert_eq!(extraction_percent(&last), 89);
+        assert_eq!(extraction_message(&last), "Extrayendo modpack: 2/2 archivos (0/1 MB)");
+
+        let cancelled = extract_modpack(&minecraft_dir, Some(open()), &dir.path().join("staging"), &AtomicBool::new(true), &|_| {});
+        assert_eq!(cancelled, Err(EXTRACTION_CANCELLED.to_string()));
+    }
+
+    #[test]
+    fn quick_play_only_targets_1_20_and_newer() {
+        let options = LaunchOptions { quick_play_world: Some("Mundo Evento".to_string()), ..Default::default() };
+        assert_eq!(quick_play_args(&options, "1.20.1"), vec!["--quickPlaySingleplayer", "Mundo Evento"]);
+        assert_eq!(quick_play_args(&options, "1.20.1-forge-47.2.0"), vec!["--quickPlaySingleplayer", "Mundo Evento"]);
+        assert!(quick_play_args(&options, "1.19.4").is_empty());
+        assert!(quick_play_args(&LaunchOptions::default(), "1.21").is_empty());
+
+        let server = |address: &str| LaunchOptions { quick_play_server: Some(address.to_string()), ..options.clone() };
+        assert_eq!(quick_play_args(&server("mc.drk.gg:25566"), "1.20.4"), vec!["--quickPlayMultiplayer", "mc.drk.gg:25566"]);
+        assert_eq!(quick_play_args(&server("mc.drk.gg:25566"), "1.16.5"), vec!["--server", "mc.drk.gg", "--port", "25566"]);
+        assert_eq!(quick_play_args(&server("mc.drk.gg"), "1.12.2"), vec!["--server", "mc.drk.gg"]);
+        assert_eq!(quick_play_args(&server("[::1]:25565"), "1.19.2"), vec!["--server", "::1", "--port", "25565"]);
+        assert_eq!(window_args(&LaunchOptions { fullscreen: true, ..Default::default() }), vec!["--fullscreen"]);
+    }
+
+    #[test]
+    fn singleplayer_only_disables_multiplayer_and_chat() {
+        let options = LaunchOptions { singleplayer_only: true, ..Default::default() };
+        assert_eq!(isolation_args(&options), vec!["--disableMultiplayer", "--disableChat"]);
+        assert!(isolation_args(&LaunchOptions::default()).is_empty());
+    }
+
+    #[test]
+    fn data_saver_reduces_workers() {
+        assert_eq!(worker_count(24, false), 24);
+        assert_eq!(worker_count(24, true), 6);
+        assert_eq!(worker_count(4, true), 2);
+    }
+
+    #[test]
+    fn macos_flags_skip_first_thread_for_lwjgl2() {
+        let icon = Path::new("/assets/objects/99/991b");
+        assert_eq!(
+            macos_flags("1.20.1", "Evento", Some(icon)),
+            vec!["-XstartOnFirstThread", "-Xdock:name=Evento", "-Xdock:icon=/assets/objects/99/991b"]
+        );
+        assert_eq!(macos_flags("1.12.2", " ", None), vec!["-Xdock:name=Minecraft"]);
+    }
+
+    #[test]
+    fn apple_silicon_prefers_arm64_natives() {
+        let natives = std::collections::HashMap::from([("osx".to_string(), "natives-osx".to_string())]);
+        assert_eq!(native_classifier(&natives, "osx", "arm64", |c| c == "natives-osx-arm64").as_deref(), Some("natives-osx-arm64"));
+        assert_eq!(native_classifier(&natives, "osx", "arm64", |_| false).as_deref(), Some("natives-osx"));
+        assert_eq!(native_classifier(&natives, "osx", "x64", |_| true).as_deref(), Some("natives-osx"));
+        assert_eq!(native_classifier(&natives, "windows", "x64", |_| true), None);
+    }
+
+    #[test]
+    fn linux_tweaks_map_to_flags_and_env() {
+        let tweaks = LinuxTweaks {
+            force_x11: true,
+            glfw_libname: Some(" /usr/lib/libglfw.so.3 ".to_string()),
+            nvidia_threaded_optimizations: true,
+            ..Default::default()
+        };
+        assert_eq!(tweaks.jvm_flags(), vec!["-Dorg.lwjgl.glfw.libname=/usr/lib/libglfw.so.3"]);
+        let (set, remove) = tweaks.env();
+        assert_eq!(remove, vec!["WAYLAND_DISPLAY"]);
+        assert_eq!(set, vec![("XDG_SESSION_TYPE", "x11"), ("__GL_THREADED_OPTIMIZATIONS", "1")]);
+        assert_eq!(LinuxTweaks::default().env(), (Vec::new(), Vec::new()));
+    }
+
+    /// Tokenizer following the JDK launcher's argfile rules (src/java.base/share/native/libjli/args.c).
+    fn parse_argfile(content: &str) -> Vec<String> {
+        let mut tokens = Vec::new();
+        let mut chars = content.chars().peekable();
+        loop {
+            // Skip whitespace and comments between tokens
+            while let Some(&c) = chars.peek() {
+                if matches!(c, ' ' | '\t' | '\n' | '\r' | '\x0c') {
+                    chars.next();
+                } else if c == '#' {
+                    for c in chars.by_ref() {
+                        if c == '\n' || c == '\r' { break; }
+                    }
+                } else {
+                    break;
+                }
+            }
+            if chars.peek().is_none() {
+                return tokens;
+            }
 
-#[derive(Clone)]
-struct MavenName {
-    group: String,
-    artifact: String,
-    version: String,
-    classifier: Option<String>,
-    ext: String,
-}
+            let mut token = String::new();
+            let mut quote: Option<char> = None;
+            while let Some(c) = chars.next() {
+                match quote {
+                    Some(q) if c == q => quote = None,
+                    Some(_) if c == '\\' => match chars.next() {
+                        Some('n') => token.push('\n'),
+                        Some('r') => token.push('\r'),
+                        Some('t') => token.push('\t'),
+                        Some('f') => token.push('\x0c'),
+                        Some(other) => token.push(other),
+                        None => break,
+                    },
+                    Some(_) => token.push(c),
+                    None if matches!(c, ' ' | '\t' | '\n' | '\r' | '\x0c') => break,
+                    None if c == '"' || c == '\'' => quote = Some(c),
+                    None => token.push(c),
+                }
+            }
+            tokens.push(token);
+        }
+    }
 
-fn parse_maven_name(name: &str) -> Option<MavenName> {
-    let mut parts = name.split('@');
-    let main = parts.next()?;
-    let ext = parts.next().unwrap_or("jar").to_string();
-    let items: Vec<&str> = main.split(':').collect();
-    if items.len() < 3 {
-        return None;
+    fn write_argfile(args: &[String]) -> String {
+        args.iter().map(|a| format!("{}\n", escape_arg(a))).collect()
     }
-    let classifier = if items.len() > 3 { Some(items[3].to_string()) } else { None };
-    Some(MavenName {
-        group: items[0].to_string(),
-        artifact: items[1].to_string(),
-        version: items[2].to_string(),
-        classifier,
-        ext,
-    })
-}
 
-fn maven_path(maven: &MavenName) -> Option<String> {
-    let group_path = maven.group.replace('.', "/");
-    let mut filename = format!("{}-{}", maven.artifact, maven.version);
-    if let Some(classifier) = &maven.classifier {
-        if !classifier.is_empty() {
-            filename = format!("{}-{}", filename, classifier);
+    #[test]
+    fn plain_args_are_left_unquoted() {
+        assert_eq!(escape_arg("-Xmx4096M"), "-Xmx4096M");
+        assert_eq!(escape_arg(r"C:\Users\Jose\AppData"), r"C:\Users\Jose\AppData");
+        assert_eq!(escape_arg("C:/Users/Peña/.minecraft"), "C:/Users/Peña/.minecraft");
+    }
+
+    #[test]
+    fn spanish_windows_paths_round_trip() {
+        let args = vec![
+            r"C:\Users\José María\AppData\Roaming\Eventos DRK\libraries\a.jar;C:\Users\José María\b.jar".to_string(),
+            r#"C:\Users\O'Brien "Toño"\natives"#.to_string(),
+            "#not-a-comment".to_string(),
+            String::new(),
+            r"C:\ruta con espacio\".to_string(),
+        ];
+        assert_eq!(parse_argfile(&write_argfile(&args)), args);
+    }
+
+    proptest! {
+        #[test]
+        fn escaped_args_round_trip(args in prop::collection::vec("[ -~ñÑáéíóúÁÉÍÓÚüç\t\n\r\x0c]{0,48}", 1..8)) {
+            prop_assert_eq!(parse_argfile(&write_argfile(&args)), args);
+        }
+
+        #[test]
+        fn escaped_paths_round_trip(
+            user in "[A-Za-z ñáéíóú'\"]{1,16}",
+            dirs in prop::collection::vec("[A-Za-z0-9 ._#-]{1,12}", 1..5)
+        ) {
+            let path = format!(r"C:\Users\{}\AppData\Roaming\{}", user, dirs.join(r"\"));
+            let args = vec!["-cp".to_string(), path];
+            prop_assert_eq!(parse_argfile(&write_argfile(&args)), args);
         }
     }
-    filename = format!("{}.{}", filename, maven.ext);
-    Some(format!(
-        "{}/{}/{}/{}",
-        group_path, maven.artifact, maven.version, filename
-    ))
-}
 
-fn ensure_trailing_slash(value: &str) -> String {
-    if value.ends_with('/') {
-        value.to_string()
-    } else {
-        format!("{}/", value)
+    #[test]
+    fn argfile_classpath_survives_non_ascii_and_spaces() {
+        let home = tempfile::tempdir().unwrap();
+        let root = home.path().join("José María").join("Eventos DRK");
+        let work_dir = root.join("instances").join("Pack Ñu").join("minecraft");
+        fs::create_dir_all(&work_dir).unwrap();
+        let jars = ["client.jar", "libraries/com/mojang/brigadier 1.0.jar", "libraries/net/fabric/año.jar"]
+            .iter()
+            .map(|rel| {
+                let jar = work_dir.join(rel);
+                fs::create_dir_all(jar.parent().unwrap()).unwrap();
+                fs::write(&jar, rel.as_bytes()).unwrap();
+                jar
+            })
+            .collect::<Vec<_>>();
+
+        let args = classpath_jvm_args(&jars, 17, &work_dir).unwrap();
+        assert_eq!(args.len(), 1);
+        let content = fs::read_to_string(work_dir.join("args.txt")).unwrap();
+        let tokens = parse_argfile(&content);
+        assert_eq!(tokens[0], "-cp");
+        let sep = if get_os_name() == "windows" { ";" } else { ":" };
+        let entries = tokens[1].split(sep).collect::<Vec<_>>();
+        assert_eq!(entries.len(), jars.len());
+        for (entry, jar) in entries.iter().zip(&jars) {
+            assert!(!entry.contains("José María"));
+            assert_eq!(fs::read(work_dir.join(entry)).unwrap(), fs::read(jar).unwrap());
+        }
+    }
+
+    #[test]
+    fn java_8_inline_classpath_hides_non_ascii_profile_folder() {
+        let home = tempfile::tempdir().unwrap();
+        let work_dir = home.path().join("José María").join("minecraft");
+        let jar = work_dir.join("libraries").join("año.jar");
+        fs::create_dir_all(jar.parent().unwrap()).unwrap();
+        fs::write(&jar, b"jar").unwrap();
+
+        let args = classpath_jvm_args(std::slice::from_ref(&jar), 8, &work_dir).unwrap();
+        assert_eq!(args[0], "-cp");
+        assert!(!args[1].contains("José María"));
+        assert_eq!(fs::read(work_dir.join(&args[1])).unwrap(), b"jar");
+    }
+
+    #[test]
+    fn pathing_jar_percent_encodes_non_ascii_entries() {
+        use std::io::Read;
+
+        let home = tempfile::tempdir().unwrap();
+        let work_dir = home.path().join("José María").join("minecraft");
+        fs::create_dir_all(&work_dir).unwrap();
+        let jars = (0..400)
+            .map(|i| work_dir.join("libraries").join(format!("librería con espacios {:03}-{}.jar", i, "x".repeat(40))))
+            .collect::<Vec<_>>();
+
+        let args = classpath_jvm_args(&jars, 8, &work_dir).unwrap();
+        assert_eq!(args[0], "-cp");
+        assert_eq!(args[1], "classpath.jar");
+
+        let mut archive = zip::ZipArchive::new(fs::File::open(work_dir.join("classpath.jar")).unwrap()).unwrap();
+        let mut manifest = String::new();
+        archive.by_name("META-INF/MANIFEST.MF").unwrap().read_to_string(&mut manifest).unwrap();
+        assert!(manifest.is_ascii());
+        assert!(manifest.lines().all(|l| l.len() <= 72));
+        let unfolded = manifest.replace("\r\n ", "");
+        assert!(unfolded.contains("Jos%C3%A9%20Mar%C3%ADa"));
+        assert!(unfolded.contains("librer%C3%ADa%20con%20espacios%20399"));
     }
 }
@@ -0,0 +1,119 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Output;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const LOG_DIR: &str = "loader-install";
+const FAILED_SUFFIX: &str = "-failed.log";
+// Per instance; older installs are of no help anymore
+const KEEP_LOGS: usize = 5;
+
+/// Everything a loader installer printed over all its attempts, plus the log files it wrote,
+/// saved under `logs/loader-install/` for crash analysis and the diagnostics bundle.
+pub struct InstallLog {
+    instance_id: String,
+    name: String,
+    text: String,
+}
+
+pub fn log_dir() -> PathBuf {
+    PathBuf::from(crate::get_instances_dir()).join("logs").join(LOG_DIR)
+}
+
+impl InstallLog {
+    pub fn new(instance_id: &str, loader: &str, version: &str) -> InstallLog {
+        let secs = crate::unix_now();
+        let name = format!("{}-{}-{}-{}", safe_name(instance_id), loader, safe_name(version), secs);
+        InstallLog { instance_id: instance_id.to_string(), name, text: String::new() }
+    }
+
+    pub fn attempt(&mut self, args: &[&str], output: &Output) {
+        self.text.push_str(&format!("=== {} ({})\n", args.join(" "), output.status));
+        self.text.push_str("--- stdout\n");
+        self.text.push_str(&String::from_utf8_lossy(&output.stdout));
+        self.text.push_str("\n--- stderr\n");
+        self.text.push_str(&String::from_utf8_lossy(&output.stderr));
+        self.text.push('\n');
+    }
+
+    /// Appends a log file the installer wrote itself, if it exists.
+    pub fn attach(&mut self, path: &Path) {
+        if let Ok(content) = fs::read(path) {
+            self.text.push_str(&format!("=== {}\n", path.display()));
+            self.text.push_str(&String::from_utf8_lossy(&content));
+            self.text.push('\n');
+        }
+    }
+
+    /// Writes the log (failed installs are marked in the name) and returns its path.
+    pub fn save(self, failed: bool) -> Option<PathBuf> {
+        let dir = log_dir();
+        fs::create_dir_all(&dir).ok()?;
+        let path = dir.join(format!("{}{}", self.name, if failed { FAILED_SUFFIX } else { ".log" }));
+        fs::write(&path, &self.text).ok()?;
+        prune(&dir, &self.instance_id);
+        Some(path)
+    }
+}
+
+fn safe_name(value: &str) -> String {
+    value.chars().map(|c| if c.is_ascii_alphanumeric() || c == '.' { c } else { '_' }).collect()
+}
+
+fn modified(path: &Path) -> SystemTime {
+    fs::metadata(path).and_then(|m| m.modified()).unwrap_or(UNIX_EPOCH)
+}
+
+/// Install logs of `instance_id`, newest first. Ids are stored with `-` replaced, so the
+/// prefix never matches another instance.
+pub fn logs_for(dir: &Path, instance_id: &str) -> Vec<PathBuf> {
+    let prefix = format!("{}-", safe_name(instance_id));
+    let mut logs: Vec<PathBuf> = fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| p.file_name().is_some_and(|n| n.to_string_lossy().starts_with(&prefix)))
+        .collect();
+    logs.sort_by_key(|p| std::cmp::Reverse(modified(p)));
+    logs
+}
+
+fn prune(dir: &Path, instance_id: &str) {
+    for old in logs_for(dir, instance_id).into_iter().skip(KEEP_LOGS) {
+        let _ = fs::remove_file(old);
+    }
+}
+
+/// The newest install log of the instance when that install failed and the game hasn't
+/// started since (no game log newer than it): the launch died installing the loader.
+pub fn failed_install(dir: &Path, instance_id: &str, game_log: &Path) -> Option<PathBuf> {
+    let latest = logs_for(dir, instance_id).into_iter().next()?;
+    let failed = latest.file_name()?.to_string_lossy().ends_with(FAILED_SUFFIX);
+    (failed && modified(&latest) >= modified(game_log)).then_some(latest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn newest_failed_install_is_reported_until_the_game_runs() {
+        let dir = tempfile::tempdir().unwrap();
+        let game_log = dir.path().join("latest.log");
+        fs::write(&game_log, "old session").unwrap();
+        let failed = dir.path().join(format!("evento_1-forge-47.2.0-100{}", FAILED_SUFFIX));
+        fs::write(&failed, "installer output").unwrap();
+        let later = SystemTime::now() + Duration::from_secs(60);
+        fs::File::options().write(true).open(&failed).unwrap().set_modified(later).unwrap();
+        fs::write(dir.path().join("otra-forge-47.2.0-100.log"), "").unwrap();
+
+        assert_eq!(failed_install(dir.path(), "evento 1", &game_log), Some(failed.clone()));
+        assert_eq!(logs_for(dir.path(), "evento 1"), vec![failed]);
+
+        fs::write(&game_log, "new session").unwrap();
+        fs::File::options().write(true).open(&game_log).unwrap().set_modified(later + Duration::from_secs(1)).unwrap();
+        assert_eq!(failed_install(dir.path(), "evento 1", &game_log), None);
+    }
+}
@@ -0,0 +1,162 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::PathBuf;
+use super::launch_logic::get_forge_recommended_version;
+
+const LOCKS_FILE: &str = "loader-locks.json";
+
+/// Exact loader build staff fix for an instance in the remote catalog:
+/// `{"loaders": {"<instanceId>": {"loader": "forge", "version": "47.2.0"}}}`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct LoaderPin {
+    pub loader: String,
+    pub version: String,
+}
+
+#[derive(Deserialize)]
+struct PinCatalog {
+    #[serde(default)]
+    loaders: HashMap<String, LoaderPin>,
+}
+
+/// Loader build an instance resolved to. It stays until the catalog pins another build or an
+/// admin clears it, so a change in Forge's promotions mid-event never upgrades anyone.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LoaderLock {
+    pub loader: String,
+    #[serde(rename = "mcVersion", alias = "mc_version")]
+    pub mc_version: String,
+    pub version: String,
+    /// `catalog` when staff pinned it, `promotions` when it was Forge's recommended build
+    pub source: String,
+    #[serde(rename = "lockedAt", alias = "locked_at")]
+    pub locked_at: u64,
+}
+
+pub type Locks = BTreeMap<String, LoaderLock>;
+
+pub fn parse_pins(body: &str) -> HashMap<String, LoaderPin> {
+    serde_json::from_str::<PinCatalog>(body).map(|c| c.loaders).unwrap_or_default()
+}
+
+/// `47.2.0` from either `47.2.0` or `1.20.1-47.2.0`.
+fn build_number(version: &str, mc_version: &str) -> String {
+    let version = version.trim();
+    version.strip_prefix(&format!("{}-", mc_version)).unwrap_or(version).to_string()
+}
+
+/// The pin wins; without one an existing lock for the same game version is kept as is, and
+/// only an instance with neither asks `promotions`.
+pub fn resolve(
+    locked: Option<&LoaderLock>,
+    pin: Option<&LoaderPin>,
+    loader: &str,
+    mc_version: &str,
+    promotions: impl FnOnce() -> Result<String, String>,
+    now: u64,
+) -> Result<LoaderLock, String> {
+    let locked = locked.filter(|l| l.loader == loader && l.mc_version == mc_version);
+    let (version, source) = match pin {
+        Some(pin) if !pin.loader.eq_ignore_ascii_case(loader) => {
+            return Err(format!("El catálogo fija {} {} para esta instancia, pero la instancia usa {}", pin.loader, pin.version, loader));
+        }
+        Some(pin) => (build_number(&pin.version, mc_version), "catalog"),
+        None => match locked {
+            Some(lock) => return Ok(lock.clone()),
+            None => (promotions()?, "promotions"),
+        },
+    };
+    if let Some(lock) = locked.filter(|l| l.version == version && l.source == source) {
+        return Ok(lock.clone());
+    }
+    Ok(LoaderLock { loader: loader.to_string(), mc_version: mc_version.to_string(), version, source: source.to_string(), locked_at: now })
+}
+
+fn locks_path() -> PathBuf {
+    PathBuf::from(crate::get_instances_dir()).join(LOCKS_FILE)
+}
+
+fn load_locks() -> Locks {
+    fs::read_to_string(locks_path()).ok()
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+
+fn save_locks(locks: &Locks) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(locks).map_err(|e| e.to_string())?;
+    fs::write(locks_path(), json).map_err(|e| e.to_string())
+}
+
+/// Forge build for `instance_id`: the catalog pin, else the build it already locked, else the
+/// recommended one, which then gets locked.
+pub fn forge_version(instance_id: &str, mc_version: &str) -> Result<String, String> {
+    let pin = super::mod_scan::catalog_body().and_then(|body| parse_pins(&body).remove(instance_id));
+    let mut locks = load_locks();
+    let lock = resolve(locks.get(instance_id), pin.as_ref(), "forge", mc_version, || get_forge_recommended_version(mc_version), crate::unix_now())?;
+    if locks.get(instance_id) != Some(&lock) {
+        if let Some(old) = locks.get(instance_id) {
+            eprintln!("[loader_pin] Loader of {} changed from {} {} to {} ({})", instance_id, old.loader, old.version, lock.version, lock.source);
+        }
+        locks.insert(instance_id.to_string(), lock.clone());
+        save_locks(&locks)?;
+    }
+    Ok(lock.version)
+}
+
+/// Gives `to` the loader build `from` is locked to, so a copy starts on the same build.
+pub fn copy_lock(from: &str, to: &str) -> Result<(), String> {
+    let mut locks = load_locks();
+    let Some(lock) = locks.get(from).cloned() else { return Ok(()) };
+    locks.insert(to.to_string(), lock);
+    save_locks(&locks)
+}
+
+#[tauri::command]
+pub fn get_loader_locks(password: String) -> Result<Locks, String> {
+    crate::ensure_admin(password)?;
+    Ok(load_locks())
+}
+
+/// Lets the next launch of the instance take the current recommended build (or the pin).
+#[tauri::command]
+pub fn clear_loader_lock(password: String, instance_id: String) -> Result<(), String> {
+    crate::ensure_admin(password)?;
+    let mut locks = load_locks();
+    if locks.remove(&instance_id).is_some() {
+        save_locks(&locks)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lock(version: &str, source: &str) -> LoaderLock {
+        LoaderLock { loader: "forge".into(), mc_version: "1.20.1".into(), version: version.into(), source: source.into(), locked_at: 1 }
+    }
+
+    #[test]
+    fn locked_build_survives_new_promotions() {
+        let promoted = || Ok("47.3.0".to_string());
+        assert_eq!(resolve(None, None, "forge", "1.20.1", promoted, 5).unwrap(), LoaderLock { locked_at: 5, ..lock("47.3.0", "promotions") });
+        // Promotions aren't even asked once locked
+        let unreachable = || -> Result<String, String> { panic!("promotions queried") };
+        assert_eq!(resolve(Some(&lock("47.2.0", "promotions")), None, "forge", "1.20.1", unreachable, 5).unwrap(), lock("47.2.0", "promotions"));
+
+        let pin = LoaderPin { loader: "forge".into(), version: "1.20.1-47.1.3".into() };
+        let pinned = resolve(Some(&lock("47.2.0", "promotions")), Some(&pin), "forge", "1.20.1", promoted, 5).unwrap();
+        assert_eq!(pinned, LoaderLock { locked_at: 5, ..lock("47.1.3", "catalog") });
+        assert_eq!(resolve(Some(&pinned), Some(&pin), "forge", "1.20.1", promoted, 9).unwrap(), pinned);
+
+        let other_version = resolve(Some(&lock("47.2.0", "promotions")), None, "forge", "1.21.1", || Ok("52.0.1".to_string()), 5).unwrap();
+        assert_eq!(other_version.version, "52.0.1");
+
+        let neoforge = LoaderPin { loader: "neoforge".into(), version: "21.1.77".into() };
+        assert!(resolve(None, Some(&neoforge), "forge", "1.21.1", promoted, 5).is_err());
+        assert_eq!(parse_pins(r#"{"approved": [], "loaders": {"evento": {"loader": "forge", "version": "47.2.0"}}}"#)["evento"].version, "47.2.0");
+        assert!(parse_pins(r#"["abc"]"#).is_empty());
+    }
+}
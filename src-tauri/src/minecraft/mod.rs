@@ -2,8 +2,34 @@ pub mod models;
 pub mod downloader;
 pub mod launch_logic;
 pub mod java;
+pub mod jvm_presets;
 pub mod utils;
 pub mod versions;
 pub mod vanilla_loader;
 pub mod fabric_loader;
 pub mod forge_loader;
+pub mod assets;
+pub mod mod_store;
+pub mod modpack;
+pub mod url_resolver;
+pub mod prefetch;
+pub mod nbt;
+pub mod worlds;
+pub mod modrinth;
+pub mod game_options;
+pub mod readiness;
+pub mod server;
+pub mod archive;
+pub mod ping;
+pub mod asset_stream;
+pub mod mod_scan;
+pub mod version_store;
+pub mod prepare_checkpoint;
+pub mod loader_pin;
+pub mod loader_log;
+pub mod classpath_policy;
+pub mod curseforge;
+pub mod sync_hooks;
+pub mod config_patch;
+pub mod content_source;
+pub mod instance_lock;
@@ -0,0 +1,480 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+const QUARANTINE_DIR: &str = "quarantine";
+const RECORD_FILE: &str = "record.json";
+const APPROVED_FILE: &str = "approved.json";
+// Last catalog fetched, used when the catalog server can't be reached
+const CATALOG_CACHE_FILE: &str = "catalog.json";
+/// Jars the pack brought, written on every extraction (relative to the instance's `minecraft/`)
+const PACK_MODS_FILE: &str = ".pack-mods.json";
+/// Player-added jars held back on remote-managed instances
+const PENDING_DIR: &str = "pending";
+/// Native executables and scripts have no business inside a mod jar.
+const SUSPICIOUS_EXTENSIONS: [&str; 10] = ["exe", "dll", "scr", "msi", "bat", "cmd", "ps1", "vbs", "sh", "jar"];
+/// Package paths of known Minecraft stealers and loaders (fractureiser, Skyrage and friends).
+const KNOWN_MALWARE_PATHS: [&str; 6] = [
+    "dev/neko/nekoinjector/",
+    "dev/neko/nekoclient/",
+    "skyrage/",
+    "net/libcurl/",
+    "me/ratsiel/",
+    "com/rat/",
+];
+
+#[derive(Serialize, Deserialize, Clone, JsonSchema)]
+pub struct ModScanSettings {
+    #[serde(default = "crate::settings::default_true")]
+    pub enabled: bool,
+    /// Jars Modrinth knows by hash are trusted even with unusual entries
+    #[serde(rename = "modrinthLookup", alias = "modrinth_lookup", default = "crate::settings::default_true")]
+    pub modrinth_lookup: bool,
+    /// JSON list of SHA-1 hashes staff approve for every instance, or
+    /// `{"approved": [...], "loaders": {...}}` (see `loader_pin`)
+    #[serde(rename = "catalogUrl", alias = "catalog_url", default)]
+    pub catalog_url: Option<String>,
+}
+
+impl Default for ModScanSettings {
+    fn default() -> Self {
+        ModScanSettings { enabled: true, modrinth_lookup: true, catalog_url: None }
+    }
+}
+
+/// Why a jar looks wrong; empty means clean.
+pub fn inspect_jar(path: &Path) -> Vec<String> {
+    let file = match fs::File::open(path) {
+        Ok(file) => file,
+        Err(e) => return vec![format!("No se pudo abrir: {}", e)],
+    };
+    let mut archive = match zip::ZipArchive::new(file) {
+        Ok(archive) => archive,
+        Err(e) => return vec![format!("No es un jar válido: {}", e)],
+    };
+    let mut findings = Vec::new();
+    for i in 0..archive.len() {
+        let Ok(entry) = archive.by_index(i) else {
+            findings.push("Entrada dañada en el jar".to_string());
+            continue;
+        };
+        let name = entry.name().replace('\\', "/");
+        let lower = name.to_lowercase();
+        // Nested jars under META-INF/jars are how Fabric and Forge bundle libraries (jar-in-jar)
+        let extension = lower.rsplit_once('.').map(|(_, ext)| ext).unwrap_or_default();
+        let bundled_library = extension == "jar" && lower.starts_with("meta-inf/jars/");
+        if SUSPICIOUS_EXTENSIONS.contains(&extension) && !bundled_library && !entry.is_dir() {
+            findings.push(format!("Contiene un ejecutable: {}", name));
+        }
+        if let Some(path) = KNOWN_MALWARE_PATHS.iter().find(|p| lower.starts_with(*p)) {
+            findings.push(format!("Contiene clases de malware conocido ({})", path.trim_end_matches('/')));
+        }
+    }
+    findings.dedup();
+    findings
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, JsonSchema)]
+pub struct QuarantinedMod {
+    pub id: String,
+    #[serde(rename = "instanceId", alias = "instance_id")]
+    pub instance_id: String,
+    #[serde(rename = "fileName", alias = "file_name")]
+    pub file_name: String,
+    pub url: String,
+    pub sha1: String,
+    pub findings: Vec<String>,
+    /// Unix seconds
+    #[serde(rename = "quarantinedAt", alias = "quarantined_at")]
+    pub quarantined_at: u64,
+    /// Where the jar goes back on approval
+    #[serde(rename = "targetPath", alias = "target_path")]
+    pub target_path: PathBuf,
+}
+
+fn quarantine_root() -> PathBuf {
+    PathBuf::from(crate::get_instances_dir()).join(QUARANTINE_DIR)
+}
+
+fn approved(root: &Path) -> HashSet<String> {
+    fs::read_to_string(root.join(APPROVED_FILE)).ok()
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+
+fn approve_hash(root: &Path, sha1: &str) -> Result<(), String> {
+    let mut hashes = approved(root);
+    hashes.insert(sha1.to_string());
+    fs::create_dir_all(root).map_err(|e| e.to_string())?;
+    let json = serde_json::to_string_pretty(&hashes).map_err(|e| e.to_string())?;
+    fs::write(root.join(APPROVED_FILE), json).map_err(|e| e.to_string())
+}
+
+/// Moves a flagged jar out of the mods folder with a record for the admin.
+pub fn quarantine(root: &Path, path: &Path, url: &str, instance_id: &str, sha1: &str, findings: Vec<String>) -> Result<QuarantinedMod, String> {
+    let record = QuarantinedMod {
+        id: uuid::Uuid::new_v4().to_string(),
+        instance_id: instance_id.to_string(),
+        file_name: path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default(),
+        url: url.to_string(),
+        sha1: sha1.to_string(),
+        findings,
+        quarantined_at: crate::unix_now(),
+        target_path: path.to_path_buf(),
+    };
+    let dir = root.join(&record.id);
+    fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    fs::rename(path, dir.join(&record.file_name))
+        .or_else(|_| fs::copy(path, dir.join(&record.file_name)).and_then(|_| fs::remove_file(path)))
+        .map_err(|e| format!("No se pudo poner en cuarentena {}: {}", record.file_name, e))?;
+    let json = serde_json::to_string_pretty(&record).map_err(|e| e.to_string())?;
+    fs::write(dir.join(RECORD_FILE), json).map_err(|e| e.to_string())?;
+    Ok(record)
+}
+
+/// Checks a mod jar that was just downloaded from `url`. Flagged jars are quarantined and the
+/// launch stops until an admin approves or rejects them.
+pub fn check_downloaded_mod(path: &Path, url: &str, instance_id: &str) -> Result<(), String> {
+    let settings = crate::settings::load_settings().mod_scan;
+    if !settings.enabled {
+        return Ok(());
+    }
+    let findings = inspect_jar(path);
+    if findings.is_empty() {
+        return Ok(());
+    }
+    let sha1 = super::downloader::file_sha1(path)?;
+    let root = quarantine_root();
+    if approved(&root).contains(&sha1) {
+        return Ok(());
+    }
+    if settings.modrinth_lookup && super::modrinth::is_known_file(&sha1).unwrap_or(false) {
+        return Ok(());
+    }
+    let record = quarantine(&root, path, url, instance_id, &sha1, findings)?;
+    Err(format!(
+        "El mod {} quedó en cuarentena: {}. Un administrador debe revisarlo antes de jugar.",
+        record.file_name,
+        record.findings.join("; ")
+    ))
+}
+
+pub fn list(root: &Path) -> Vec<QuarantinedMod> {
+    let mut records: Vec<QuarantinedMod> = fs::read_dir(root)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|dir| fs::read_to_string(dir.path().join(RECORD_FILE)).ok())
+        .filter_map(|json| serde_json::from_str(&json).ok())
+        .collect();
+    records.sort_by_key(|r| std::cmp::Reverse(r.quarantined_at));
+    records
+}
+
+fn record_dir(root: &Path, id: &str) -> Result<(PathBuf, QuarantinedMod), String> {
+    if uuid::Uuid::parse_str(id).is_err() {
+        return Err("Elemento de cuarentena no válido".to_string());
+    }
+    let dir = root.join(id);
+    let json = fs::read_to_string(dir.join(RECORD_FILE)).map_err(|_| "El mod ya no está en cuarentena".to_string())?;
+    let record = serde_json::from_str(&json).map_err(|e| e.to_string())?;
+    Ok((dir, record))
+}
+
+/// Puts the jar back where it was downloaded to and trusts its hash from now on.
+pub fn approve(root: &Path, id: &str) -> Result<QuarantinedMod, String> {
+    let (dir, record) = record_dir(root, id)?;
+    if let Some(parent) = record.target_path.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    fs::rename(dir.join(&record.file_name), &record.target_path).map_err(|e| format!("No se pudo restaurar el mod: {}", e))?;
+    approve_hash(root, &record.sha1)?;
+    let _ = fs::remove_dir_all(&dir);
+    Ok(record)
+}
+
+pub fn reject(root: &Path, id: &str) -> Result<(), String> {
+    let (dir, _) = record_dir(root, id)?;
+    fs::remove_dir_all(&dir).map_err(|e| e.to_string())
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Catalog {
+    List(HashSet<String>),
+    Object {
+        #[serde(default)]
+        approved: HashSet<String>,
+    },
+}
+
+pub fn parse_catalog(body: &str) -> Result<HashSet<String>, String> {
+    let hashes = match serde_json::from_str(body).map_err(|e| format!("Catálogo de mods no válido: {}", e))? {
+        Catalog::List(hashes) | Catalog::Object { approved: hashes } => hashes,
+    };
+    Ok(hashes.into_iter().map(|h| h.trim().to_lowercase()).collect())
+}
+
+fn fetch_catalog(url: &str) -> Result<String, String> {
+    let client = reqwest::blocking::Client::builder()
+        .user_agent(crate::http::user_agent())
+        .timeout(Duration::from_secs(10))
+        .build()
+        .map_err(|e| e.to_string())?;
+    let resp = crate::http::send(&client, client.get(url))?;
+    if !resp.status().is_success() {
+        return Err(format!("El catálogo de mods respondió {}", resp.status()));
+    }
+    resp.text().map_err(|e| e.to_string())
+}
+
+/// The remote catalog, or its cached copy when the server can't be reached. `None` without a
+/// catalog URL or any usable copy.
+pub fn catalog_body() -> Option<String> {
+    let url = crate::settings::load_settings().mod_scan.catalog_url.filter(|u| !u.trim().is_empty())?;
+    let root = quarantine_root();
+    let cache = root.join(CATALOG_CACHE_FILE);
+    match fetch_catalog(&url).and_then(|body| parse_catalog(&body).map(|_| body)) {
+        Ok(body) => {
+            let _ = fs::create_dir_all(&root).and_then(|_| fs::write(&cache, &body));
+            Some(body)
+        }
+        Err(e) => {
+            eprintln!("[mod_scan] Mod catalog unavailable, using cached copy: {}", e);
+            fs::read_to_string(&cache).ok().filter(|body| parse_catalog(body).is_ok())
+        }
+    }
+}
+
+/// Age of the copy `catalog_body` last returned: about zero right after a fetch, the cached
+/// copy's age when the server couldn't be reached. `None` without any copy.
+pub fn catalog_age() -> Option<Duration> {
+    let modified = fs::metadata(quarantine_root().join(CATALOG_CACHE_FILE)).and_then(|m| m.modified()).ok()?;
+    Some(modified.elapsed().unwrap_or(Duration::ZERO))
+}
+
+/// Hashes approved locally plus the remote catalog (or its cached copy when offline).
+pub fn approved_hashes() -> HashSet<String> {
+    let mut hashes = approved(&quarantine_root());
+    if let Some(catalog) = catalog_body().and_then(|body| parse_catalog(&body).ok()) {
+        hashes.extend(catalog);
+    }
+    hashes
+}
+
+pub fn jar_names(dir: &Path) -> Vec<String> {
+    let mut names: Vec<String> = fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter(|e| e.path().is_file())
+        .map(|e| e.file_name().to_string_lossy().to_string())
+        .filter(|name| name.to_lowercase().ends_with(".jar"))
+        .collect();
+    names.sort();
+    names
+}
+
+/// Remembers which jars came with the pack; called right after extracting it.
+pub fn record_pack_mods(minecraft_dir: &Path) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(&jar_names(&minecraft_dir.join("mods"))).map_err(|e| e.to_string())?;
+    fs::write(minecraft_dir.join(PACK_MODS_FILE), json).map_err(|e| e.to_string())
+}
+
+/// Jars that came with the pack, `None` for packs extracted before the list existed.
+pub fn pack_mods(minecraft_dir: &Path) -> Option<HashSet<String>> {
+    fs::read_to_string(minecraft_dir.join(PACK_MODS_FILE)).ok()
+        .and_then(|json| serde_json::from_str(&json).ok())
+}
+
+/// Moves jars that neither the pack nor `extra` (the instance's own mod list) brought into
+/// `pending/`, unless their hash is approved. `approved` is only called when there is something
+/// to check. Instances extracted before the pack list existed are left alone.
+pub fn hold_player_mods(minecraft_dir: &Path, extra: &[String], approved: impl FnOnce() -> HashSet<String>) -> Result<Vec<String>, String> {
+    let Some(mut known) = pack_mods(minecraft_dir) else {
+        return Ok(Vec::new());
+    };
+    known.extend(extra.iter().cloned());
+    let mods_dir = minecraft_dir.join("mods");
+    let unknown: Vec<String> = jar_names(&mods_dir).into_iter().filter(|name| !known.contains(name)).collect();
+    if unknown.is_empty() {
+        return Ok(Vec::new());
+    }
+    let approved = approved();
+    let pending_dir = minecraft_dir.join(PENDING_DIR);
+    let mut held = Vec::new();
+    for name in unknown {
+        let path = mods_dir.join(&name);
+        if approved.contains(&super::downloader::file_sha1(&path)?) {
+            continue;
+        }
+        fs::create_dir_all(&pending_dir).map_err(|e| e.to_string())?;
+        let _ = fs::remove_file(pending_dir.join(&name));
+        fs::rename(&path, pending_dir.join(&name)).map_err(|e| format!("No se pudo apartar {}: {}", name, e))?;
+        held.push(name);
+    }
+    Ok(held)
+}
+
+/// Jars waiting for review in an instance.
+pub fn pending_mods(minecraft_dir: &Path) -> Vec<String> {
+    jar_names(&minecraft_dir.join(PENDING_DIR))
+}
+
+/// Path of a pending jar; `file_name` must be a bare name from `pending_mods`.
+fn pending_path(minecraft_dir: &Path, file_name: &str) -> Result<PathBuf, String> {
+    let bare = Path::new(file_name).file_name().map(|n| n == file_name).unwrap_or(false);
+    let path = minecraft_dir.join(PENDING_DIR).join(file_name);
+    if !bare || !path.is_file() {
+        return Err("El mod ya no está pendiente de revisión".to_string());
+    }
+    Ok(path)
+}
+
+/// Trusts the jar's hash on every instance and puts it back in `mods/`.
+pub fn approve_pending(root: &Path, minecraft_dir: &Path, file_name: &str) -> Result<(), String> {
+    let path = pending_path(minecraft_dir, file_name)?;
+    approve_hash(root, &super::downloader::file_sha1(&path)?)?;
+    fs::create_dir_all(minecraft_dir.join("mods")).map_err(|e| e.to_string())?;
+    fs::rename(&path, minecraft_dir.join("mods").join(file_name)).map_err(|e| format!("No se pudo restaurar el mod: {}", e))
+}
+
+fn instance_minecraft_dir(instance_id: &str) -> Result<PathBuf, String> {
+    let instance = crate::load_instances().into_iter().find(|i| i.id == instance_id).ok_or("Instance not found")?;
+    Ok(PathBuf::from(instance.path).join("minecraft"))
+}
+
+#[tauri::command]
+pub fn list_quarantined_mods(password: String) -> Result<Vec<QuarantinedMod>, String> {
+    crate::ensure_admin(password)?;
+    Ok(list(&quarantine_root()))
+}
+
+#[tauri::command]
+pub fn approve_quarantined_mod(password: String, id: String) -> Result<QuarantinedMod, String> {
+    crate::ensure_admin(password)?;
+    approve(&quarantine_root(), &id)
+}
+
+#[tauri::command]
+pub fn reject_quarantined_mod(password: String, id: String) -> Result<(), String> {
+    crate::ensure_admin(password)?;
+    reject(&quarantine_root(), &id)
+}
+
+#[tauri::command]
+pub fn list_pending_mods(instance_id: String) -> Result<Vec<String>, String> {
+    Ok(pending_mods(&instance_minecraft_dir(&instance_id)?))
+}
+
+#[tauri::command]
+pub fn approve_pending_mod(password: String, instance_id: String, file_name: String) -> Result<(), String> {
+    crate::ensure_admin(password)?;
+    approve_pending(&quarantine_root(), &instance_minecraft_dir(&instance_id)?, &file_name)
+}
+
+#[tauri::command]
+pub fn reject_pending_mod(password: String, instance_id: String, file_name: String) -> Result<(), String> {
+    crate::ensure_admin(password)?;
+    let path = pending_path(&instance_minecraft_dir(&instance_id)?, &file_name)?;
+    fs::remove_file(path).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn get_mod_scan_settings() -> ModScanSettings {
+    crate::settings::load_settings().mod_scan
+}
+
+#[tauri::command]
+pub fn set_mod_scan_settings(password: String, mod_scan: ModScanSettings) -> Result<(), String> {
+    crate::ensure_admin(password)?;
+    let mut settings = crate::settings::load_settings();
+    settings.mod_scan = mod_scan;
+    crate::settings::save_settings(&settings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn jar(path: &Path, entries: &[&str]) {
+        let mut zip = zip::ZipWriter::new(fs::File::create(path).unwrap());
+        for name in entries {
+            zip.start_file(*name, zip::write::FileOptions::default()).unwrap();
+            zip.write_all(b"x").unwrap();
+        }
+        zip.finish().unwrap();
+    }
+
+    #[test]
+    fn suspicious_entries_are_flagged() {
+        let dir = tempfile::tempdir().unwrap();
+        let clean = dir.path().join("clean.jar");
+        jar(&clean, &["fabric.mod.json", "com/example/Mod.class", "META-INF/jars/lib.jar"]);
+        assert!(inspect_jar(&clean).is_empty());
+
+        let bad = dir.path().join("bad.jar");
+        jar(&bad, &["fabric.mod.json", "dev/neko/nekoclient/Client.class", "payload.exe"]);
+        assert_eq!(inspect_jar(&bad).len(), 2);
+
+        let broken = dir.path().join("broken.jar");
+        fs::write(&broken, b"<html>not a jar</html>").unwrap();
+        assert_eq!(inspect_jar(&broken).len(), 1);
+    }
+
+    #[test]
+    fn quarantined_mods_can_be_approved() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join(QUARANTINE_DIR);
+        let mods = dir.path().join("mods");
+        fs::create_dir_all(&mods).unwrap();
+        let path = mods.join("bad.jar");
+        jar(&path, &["payload.exe"]);
+
+        let record = quarantine(&root, &path, "https://drk.example/bad.jar", "evento", "abc", inspect_jar(&path)).unwrap();
+        assert!(!path.exists());
+        assert_eq!(list(&root).len(), 1);
+
+        approve(&root, &record.id).unwrap();
+        assert!(path.exists());
+        assert!(approved(&root).contains("abc"));
+        assert!(list(&root).is_empty());
+        assert!(reject(&root, "../mods").is_err());
+    }
+
+    #[test]
+    fn player_mods_wait_for_approval() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join(QUARANTINE_DIR);
+        let minecraft = dir.path().join("minecraft");
+        let mods = minecraft.join("mods");
+        fs::create_dir_all(&mods).unwrap();
+        jar(&mods.join("pack.jar"), &["fabric.mod.json"]);
+        // Nothing to compare against until the pack has been extracted once
+        assert!(hold_player_mods(&minecraft, &[], HashSet::new).unwrap().is_empty());
+        record_pack_mods(&minecraft).unwrap();
+
+        jar(&mods.join("listed.jar"), &["a.class"]);
+        jar(&mods.join("xray.jar"), &["b.class"]);
+        let held = hold_player_mods(&minecraft, &["listed.jar".to_string()], HashSet::new).unwrap();
+        assert_eq!(held, vec!["xray.jar".to_string()]);
+        assert_eq!(pending_mods(&minecraft), held);
+        assert!(approve_pending(&root, &minecraft, "../mods/pack.jar").is_err());
+
+        approve_pending(&root, &minecraft, "xray.jar").unwrap();
+        assert!(mods.join("xray.jar").exists());
+        assert!(hold_player_mods(&minecraft, &["listed.jar".to_string()], || approved(&root)).unwrap().is_empty());
+    }
+
+    #[test]
+    fn catalogs_are_lists_or_objects() {
+        let expected: HashSet<String> = ["abc".to_string()].into_iter().collect();
+        assert_eq!(parse_catalog(r#"["ABC "]"#).unwrap(), expected);
+        assert_eq!(parse_catalog(r#"{"approved": ["abc"]}"#).unwrap(), expected);
+        assert!(parse_catalog("<html>").is_err());
+    }
+}
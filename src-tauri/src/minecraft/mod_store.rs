@@ -0,0 +1,151 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+use serde::{Deserialize, Serialize};
+use super::downloader::file_sha1;
+
+const STORE_DIR: &str = "mods-store";
+// Per-instance cache so unchanged jars are not re-hashed on every launch
+const LINKS_FILE: &str = ".mods-store.json";
+
+#[derive(Serialize, Deserialize, Clone, PartialEq)]
+struct LinkedMod {
+    size: u64,
+    modified: u64,
+    sha1: String,
+}
+
+#[derive(Serialize, Clone, Debug, Default, PartialEq)]
+pub struct LinkStats {
+    /// Jars now sharing an existing store entry
+    pub linked: usize,
+    /// Jars added to the store
+    pub stored: usize,
+    /// Jars kept as plain copies because hard links are not possible (e.g. another drive)
+    pub copied: usize,
+}
+
+/// Hash-addressed jar store shared by every instance, `<base>/mods-store/<aa>/<sha1>.jar`.
+pub fn store_dir(base_path: &Path) -> PathBuf {
+    base_path.join(STORE_DIR)
+}
+
+fn store_path(store_dir: &Path, sha1: &str) -> PathBuf {
+    store_dir.join(&sha1[..2]).join(format!("{}.jar", sha1))
+}
+
+fn file_stamp(meta: &fs::Metadata) -> (u64, u64) {
+    let modified = meta.modified().ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    (meta.len(), modified)
+}
+
+/// Moves every jar in `<minecraft_dir>/mods` into the store and replaces it with a hard link,
+/// so identical mods across instances take disk space once. When linking fails the instance
+/// keeps its own copy and the store gets a copy for the next instance.
+pub fn link_mods(store_dir: &Path, minecraft_dir: &Path) -> Result<LinkStats, String> {
+    let mods_dir = minecraft_dir.join("mods");
+    let cache_path = minecraft_dir.join(LINKS_FILE);
+    let old_cache: HashMap<String, LinkedMod> = fs::read_to_string(&cache_path)
+        .ok()
+        .and_then(|c| serde_json::from_str(&c).ok())
+        .unwrap_or_default();
+    let mut cache = HashMap::new();
+    let mut stats = LinkStats::default();
+
+    let entries = match fs::read_dir(&mods_dir) {
+        Ok(entries) => entries,
+        Err(_) => return Ok(stats),
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let name = entry.file_name().to_string_lossy().to_string();
+        let meta = match entry.metadata() {
+            Ok(meta) if meta.is_file() && name.to_lowercase().ends_with(".jar") => meta,
+            _ => continue,
+        };
+        let (size, modified) = file_stamp(&meta);
+        if let Some(known) = old_cache.get(&name) {
+            if known.size == size && known.modified == modified && store_path(store_dir, &known.sha1).exists() {
+                cache.insert(name, known.clone());
+                continue;
+            }
+        }
+
+        let sha1 = file_sha1(&path).map_err(|e| format!("Failed to hash mod {}: {}", name, e))?;
+        let target = store_path(store_dir, &sha1);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+
+        if !target.exists() {
+            if fs::hard_link(&path, &target).is_ok() {
+                stats.stored += 1;
+            } else {
+                fs::copy(&path, &target).map_err(|e| format!("Failed to store mod {}: {}", name, e))?;
+                stats.copied += 1;
+            }
+        } else {
+            // Link next to the jar first so a failure never leaves the instance without it
+            let staging = crate::temp_files::tmp_path(&path);
+            let _ = fs::remove_file(&staging);
+            if fs::hard_link(&target, &staging).is_ok() && fs::rename(&staging, &path).is_ok() {
+                stats.linked += 1;
+            } else {
+                let _ = fs::remove_file(&staging);
+                stats.copied += 1;
+            }
+        }
+
+        let (size, modified) = fs::metadata(&path).map(|m| file_stamp(&m)).unwrap_or((size, modified));
+        cache.insert(name, LinkedMod { size, modified, sha1 });
+    }
+
+    if cache != old_cache {
+        let json = serde_json::to_string_pretty(&cache).map_err(|e| e.to_string())?;
+        fs::write(&cache_path, json).map_err(|e| e.to_string())?;
+    }
+    Ok(stats)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn identical_mods_share_one_store_entry() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = store_dir(dir.path());
+        let instances = ["pack-a", "pack-b"].map(|name| dir.path().join(name).join("minecraft"));
+        for instance in &instances {
+            fs::create_dir_all(instance.join("mods")).unwrap();
+            fs::write(instance.join("mods").join("jei.jar"), b"same bytes").unwrap();
+            fs::write(instance.join("mods").join("notes.txt"), b"not a mod").unwrap();
+        }
+        fs::write(instances[1].join("mods").join("only-b.jar"), b"different").unwrap();
+
+        assert_eq!(link_mods(&store, &instances[0]).unwrap(), LinkStats { linked: 0, stored: 1, copied: 0 });
+        assert_eq!(link_mods(&store, &instances[1]).unwrap(), LinkStats { linked: 1, stored: 1, copied: 0 });
+        // Second pass hits the cache
+        assert_eq!(link_mods(&store, &instances[1]).unwrap(), LinkStats::default());
+
+        let stored = fs::read_dir(&store).unwrap().flatten()
+            .flat_map(|bucket| fs::read_dir(bucket.path()).unwrap().flatten())
+            .count();
+        assert_eq!(stored, 2);
+        for instance in &instances {
+            assert_eq!(fs::read(instance.join("mods").join("jei.jar")).unwrap(), b"same bytes");
+            assert!(instance.join("mods").join("notes.txt").exists());
+        }
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::MetadataExt;
+            let links = fs::metadata(instances[0].join("mods").join("jei.jar")).unwrap().nlink();
+            assert_eq!(links, 3);
+        }
+    }
+}
@@ -0,0 +1,665 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{self, File};
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::Path;
+use schemars::JsonSchema;
+use serde::Serialize;
+use zip::result::ZipError;
+use zip::ZipArchive;
+use super::downloader::download_file;
+use super::content_source::{self, Target};
+
+// Central directories of big packs are a few hundred KB; 64KB blocks keep the request count low
+const RANGE_BLOCK: u64 = 64 * 1024;
+// Detection needs a few agreeing mods, not the whole pack (remote packs download every scanned jar)
+const MAX_SCANNED_JARS: usize = 24;
+const MAX_SCANNED_JAR_SIZE: u64 = 32 * 1024 * 1024;
+// Tools encrypt whole archives, so a few entries tell; each costs a Range request on remote packs
+const ENCRYPTION_PROBE_ENTRIES: usize = 8;
+
+/// Codes at the start of modpack archive errors, for the UI to explain them.
+pub const ENCRYPTED_MARKER: &str = "[modpack:encrypted]";
+pub const CORRUPT_MARKER: &str = "[modpack:corrupt]";
+pub const UNSUPPORTED_MARKER: &str = "[modpack:unsupported]";
+
+/// What an admin needs to validate a modpack before assigning it to an instance.
+#[derive(Serialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct ModpackInfo {
+    pub name: Option<String>,
+    /// "curseforge", "modrinth", "multimc" or "zip" when there is no manifest; "7z" and "rar"
+    /// packs are not read beyond their format
+    pub format: String,
+    #[serde(rename = "mcVersion")]
+    pub mc_version: Option<String>,
+    pub loader: Option<String>,
+    #[serde(rename = "loaderVersion")]
+    pub loader_version: Option<String>,
+    /// Jars bundled in the zip plus mods the manifest downloads separately
+    #[serde(rename = "modCount")]
+    pub mod_count: usize,
+    #[serde(rename = "totalSize")]
+    pub total_size: u64,
+    /// Loader or version were guessed from the bundled mods because the pack doesn't declare them
+    #[serde(rename = "detectedFromMods")]
+    pub detected_from_mods: bool,
+}
+
+/// A file a modpack update writes into the game folder.
+#[derive(Serialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PackFile {
+    /// Relative to the game folder, `/` separated
+    pub path: String,
+    pub size: u64,
+}
+
+/// What extracting a pack over an instance would do, so it can be reviewed before applying.
+#[derive(Serialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct ModpackDiff {
+    pub added: Vec<PackFile>,
+    pub changed: Vec<PackFile>,
+    /// Files in the folders the sync replaces wholesale that the pack no longer ships
+    pub removed: Vec<String>,
+    pub unchanged: usize,
+    /// Size of the pack itself, i.e. what applying the update downloads
+    #[serde(rename = "downloadSize")]
+    pub download_size: u64,
+}
+
+trait ReadSeek: Read + Seek {}
+
+impl<T: Read + Seek> ReadSeek for T {}
+
+/// `Read + Seek` over an HTTP resource using Range requests, caching whole blocks.
+struct HttpRangeReader {
+    client: reqwest::blocking::Client,
+    url: String,
+    len: u64,
+    pos: u64,
+    blocks: HashMap<u64, Vec<u8>>,
+}
+
+impl HttpRangeReader {
+    /// Returns `None` when the server ignores Range requests.
+    fn open(url: &str) -> Result<Option<Self>, String> {
+        let client = reqwest::blocking::Client::builder()
+            .user_agent(crate::http::user_agent())
+            .timeout(std::time::Duration::from_secs(30))
+            .build()
+            .map_err(|e| e.to_string())?;
+        let resp = client.get(url)
+            .header(reqwest::header::RANGE, "bytes=0-0")
+            .send()
+            .map_err(|e| format!("Network error: {}", e))?;
+        if resp.status() != reqwest::StatusCode::PARTIAL_CONTENT {
+            if !resp.status().is_success() {
+                return Err(format!("Modpack request failed with status: {}", resp.status()));
+            }
+            return Ok(None);
+        }
+        // Content-Range: bytes 0-0/123456
+        let len = resp.headers().get(reqwest::header::CONTENT_RANGE)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.rsplit('/').next())
+            .and_then(|total| total.parse::<u64>().ok())
+            .filter(|len| *len > 0);
+        Ok(len.map(|len| HttpRangeReader { client, url: url.to_string(), len, pos: 0, blocks: HashMap::new() }))
+    }
+
+    fn block(&mut self, index: u64) -> io::Result<&Vec<u8>> {
+        if !self.blocks.contains_key(&index) {
+            let start = index * RANGE_BLOCK;
+            let end = std::cmp::min(start + RANGE_BLOCK, self.len) - 1;
+            let bytes = self.client.get(&self.url)
+                .header(reqwest::header::RANGE, format!("bytes={}-{}", start, end))
+                .send()
+                .and_then(|r| r.error_for_status())
+                .and_then(|r| r.bytes())
+                .map_err(|e| io::Error::other(e.to_string()))?;
+            self.blocks.insert(index, bytes.to_vec());
+        }
+        Ok(&self.blocks[&index])
+    }
+}
+
+impl Read for HttpRangeReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.pos >= self.len || buf.is_empty() {
+            return Ok(0);
+        }
+        let pos = self.pos;
+        let block = self.block(pos / RANGE_BLOCK)?;
+        let offset = (pos % RANGE_BLOCK) as usize;
+        if offset >= block.len() {
+            return Ok(0);
+        }
+        let n = std::cmp::min(buf.len(), block.len() - offset);
+        buf[..n].copy_from_slice(&block[offset..offset + n]);
+        self.pos += n as u64;
+        Ok(n)
+    }
+}
+
+impl Seek for HttpRangeReader {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let target = match pos {
+            SeekFrom::Start(p) => p as i64,
+            SeekFrom::End(p) => self.len as i64 + p,
+            SeekFrom::Current(p) => self.pos as i64 + p,
+        };
+        if target < 0 {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "seek before start"));
+        }
+        self.pos = target as u64;
+        Ok(self.pos)
+    }
+}
+
+/// Modpack archive error with the code the UI explains.
+pub fn archive_error(e: ZipError) -> String {
+    match e {
+        ZipError::UnsupportedArchive(msg) if msg == ZipError::PASSWORD_REQUIRED || msg.to_lowercase().contains("encrypt") => format!(
+            "{} El modpack está protegido con contraseña. Pide al autor una versión sin cifrar.",
+            ENCRYPTED_MARKER
+        ),
+        ZipError::UnsupportedArchive(msg) => format!("{} El modpack usa un formato de zip no soportado: {}", UNSUPPORTED_MARKER, msg),
+        ZipError::InvalidArchive(_) | ZipError::Io(_) => format!(
+            "{} El archivo del modpack está incompleto o dañado ({}). Vuelve a descargarlo.",
+            CORRUPT_MARKER, e
+        ),
+        ZipError::FileNotFound => e.to_string(),
+    }
+}
+
+/// Opens a modpack zip. ZIP64 archives (event packs above 4 GB) read like any other.
+pub fn open_archive<R: Read + Seek>(reader: R) -> Result<ZipArchive<R>, String> {
+    ZipArchive::new(reader).map_err(archive_error)
+}
+
+/// Fails on the first password-protected entry among the first `limit` ones. The flag comes from
+/// the central directory, so nothing is decrypted; other errors are left to the actual read.
+pub fn ensure_not_encrypted<R: Read + Seek>(archive: &mut ZipArchive<R>, limit: usize) -> Result<(), String> {
+    for i in 0..archive.len().min(limit) {
+        if let Err(e @ ZipError::UnsupportedArchive(_)) = archive.by_index(i).map(|_| ()) {
+            return Err(archive_error(e));
+        }
+    }
+    Ok(())
+}
+
+/// Runs `read` over a modpack by URL or local path, with its total size. Remote packs only fetch
+/// what `read` touches through Range requests; servers without Range support get a full download.
+fn with_pack<T>(url_or_path: &str, read: impl FnOnce(&mut dyn ReadSeek, u64) -> Result<T, String>) -> Result<T, String> {
+    let local = Path::new(url_or_path);
+    if local.exists() {
+        let total_size = local.metadata().map(|m| m.len()).unwrap_or(0);
+        let mut file = File::open(local).map_err(|e| e.to_string())?;
+        return read(&mut file, total_size);
+    }
+
+    let url = content_source::resolve(url_or_path, &Target::default())?.url;
+    if let Some(mut reader) = HttpRangeReader::open(&url)? {
+        let total_size = reader.len;
+        return read(&mut reader, total_size);
+    }
+
+    let temp = std::env::temp_dir().join(format!("drk-inspect-{}.zip", uuid::Uuid::new_v4()));
+    let result = download_file(&url, &temp, None).and_then(|_| {
+        let total_size = temp.metadata().map(|m| m.len()).unwrap_or(0);
+        let mut file = File::open(&temp).map_err(|e| e.to_string())?;
+        read(&mut file, total_size)
+    });
+    let _ = std::fs::remove_file(&temp);
+    result
+}
+
+/// Inspects a modpack zip by URL or local path; remote packs only fetch the central directory
+/// and the manifest.
+pub fn inspect_modpack(url_or_path: &str) -> Result<ModpackInfo, String> {
+    with_pack(url_or_path, |reader, total_size| inspect_archive(reader, total_size))
+}
+
+/// What installing the pack at `url_or_path` over `minecraft_dir` would change. Remote packs
+/// only fetch their central directory. `kept` holds game-folder paths the sync restores on its
+/// own, such as the instance's individual mods.
+pub fn diff_modpack(url_or_path: &str, minecraft_dir: &Path, kept: &[String]) -> Result<ModpackDiff, String> {
+    with_pack(url_or_path, |reader, total_size| diff_archive(reader, total_size, minecraft_dir, kept))
+}
+
+fn read_text<R: Read + Seek>(archive: &mut ZipArchive<R>, name: &str) -> Option<String> {
+    let mut entry = archive.by_name(name).ok()?;
+    let mut content = String::new();
+    entry.read_to_string(&mut content).ok()?;
+    Some(content)
+}
+
+fn read_json<R: Read + Seek>(archive: &mut ZipArchive<R>, name: &str) -> Option<serde_json::Value> {
+    serde_json::from_str(&read_text(archive, name)?).ok()
+}
+
+/// Version the pack declares: `version` in a CurseForge `manifest.json`, `versionId` in a
+/// Modrinth `modrinth.index.json`.
+pub fn pack_version(zip_path: &Path) -> Option<String> {
+    let mut archive = ZipArchive::new(File::open(zip_path).ok()?).ok()?;
+    let version = match read_json(&mut archive, "manifest.json") {
+        Some(manifest) => manifest["version"].as_str().map(str::to_string),
+        None => read_json(&mut archive, "modrinth.index.json")?["versionId"].as_str().map(str::to_string),
+    };
+    version.filter(|v| !v.trim().is_empty())
+}
+
+/// Jars the launcher would place in `mods/`: root-level jars and anything under a `mods/` folder.
+fn is_bundled_mod(name: &str) -> bool {
+    name.to_lowercase().ends_with(".jar") && (!name.contains('/') || name.starts_with("mods/") || name.contains("/mods/"))
+}
+
+fn count_bundled_mods<'a>(names: impl Iterator<Item = &'a str>) -> usize {
+    names.filter(|n| is_bundled_mod(n)).count()
+}
+
+/// Splits a CurseForge loader id like `forge-47.2.0` / `fabric-0.15.3`.
+fn split_loader_id(id: &str) -> (String, Option<String>) {
+    match id.split_once('-') {
+        Some((loader, version)) => (loader.to_string(), Some(version.to_string())),
+        None => (id.to_string(), None),
+    }
+}
+
+/// First `x.y[.z]` in a version requirement like `~1.20.1`, `>=1.19` or `[1.20.1,1.21)`.
+pub fn first_mc_version(requirement: &str) -> Option<String> {
+    let start = requirement.find(|c: char| c.is_ascii_digit())?;
+    let version = requirement[start..].split(|c: char| !(c.is_ascii_digit() || c == '.')).next()?.trim_end_matches('.');
+    version.contains('.').then(|| version.to_string())
+}
+
+/// `versionRange` of the `minecraft` dependency in a Forge/NeoForge `mods.toml`.
+fn toml_minecraft_range(toml: &str) -> Option<String> {
+    let mut current_mod = String::new();
+    for line in toml.lines().map(str::trim) {
+        if line.starts_with('[') {
+            current_mod.clear();
+        }
+        let Some((key, value)) = line.split_once('=') else { continue; };
+        let value = value.trim().trim_matches('"').to_string();
+        match key.trim() {
+            "modId" => current_mod = value,
+            "versionRange" if current_mod == "minecraft" => return Some(value),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Loader and Minecraft version a single mod jar targets, from its metadata files.
+pub fn detect_mod_target<R: Read + Seek>(jar: &mut ZipArchive<R>) -> Option<(String, Option<String>)> {
+    if let Some(json) = read_json(jar, "fabric.mod.json") {
+        let requirement = match &json["depends"]["minecraft"] {
+            serde_json::Value::Array(list) => list.first().and_then(|v| v.as_str()).map(str::to_string),
+            value => value.as_str().map(str::to_string),
+        };
+        return Some(("fabric".to_string(), requirement.as_deref().and_then(first_mc_version)));
+    }
+    if let Some(json) = read_json(jar, "quilt.mod.json") {
+        let version = json["quilt_loader"]["depends"].as_array()
+            .and_then(|deps| deps.iter().find(|d| d["id"] == "minecraft"))
+            .and_then(|d| d["versions"].as_str())
+            .and_then(first_mc_version);
+        return Some(("quilt".to_string(), version));
+    }
+    if let Some(toml) = read_text(jar, "META-INF/neoforge.mods.toml") {
+        return Some(("neoforge".to_string(), toml_minecraft_range(&toml).as_deref().and_then(first_mc_version)));
+    }
+    if let Some(toml) = read_text(jar, "META-INF/mods.toml") {
+        let loader = if toml.contains("modId=\"neoforge\"") || toml.contains("modId = \"neoforge\"") { "neoforge" } else { "forge" };
+        return Some((loader.to_string(), toml_minecraft_range(&toml).as_deref().and_then(first_mc_version)));
+    }
+    if let Some(json) = read_json(jar, "mcmod.info") {
+        // Either a bare list or `{"modList": [...]}`
+        let list = json.as_array().cloned().or_else(|| json["modList"].as_array().cloned()).unwrap_or_default();
+        let version = list.iter().find_map(|m| m["mcversion"].as_str()).and_then(first_mc_version);
+        return Some(("forge".to_string(), version));
+    }
+    // Legacy coremods only declare a tweaker or FML plugin in the manifest
+    let manifest = read_text(jar, "META-INF/MANIFEST.MF")?;
+    (manifest.contains("FMLCorePlugin") || manifest.contains("TweakClass")).then(|| ("forge".to_string(), None))
+}
+
+fn json_license(value: &serde_json::Value) -> Option<String> {
+    match value {
+        serde_json::Value::String(license) => Some(license.clone()),
+        serde_json::Value::Array(list) => {
+            let names: Vec<String> = list.iter().filter_map(|v| v.as_str().or_else(|| v["id"].as_str())).map(str::to_string).collect();
+            (!names.is_empty()).then(|| names.join(" / "))
+        }
+        serde_json::Value::Object(_) => value["id"].as_str().or_else(|| value["name"].as_str()).map(str::to_string),
+        _ => None,
+    }
+}
+
+/// License the mod declares in its own metadata (`license` in `fabric.mod.json`, `quilt.mod.json`
+/// or the top of `mods.toml`). Only what the author wrote; nothing checks it.
+pub fn declared_license<R: Read + Seek>(jar: &mut ZipArchive<R>) -> Option<String> {
+    let license = if let Some(json) = read_json(jar, "fabric.mod.json") {
+        json_license(&json["license"])
+    } else if let Some(json) = read_json(jar, "quilt.mod.json") {
+        json_license(&json["quilt_loader"]["metadata"]["license"])
+    } else {
+        let toml = read_text(jar, "META-INF/neoforge.mods.toml").or_else(|| read_text(jar, "META-INF/mods.toml"))?;
+        toml.lines()
+            .map(str::trim)
+            .take_while(|line| !line.starts_with('['))
+            .filter_map(|line| line.split_once('='))
+            .find(|(key, _)| key.trim() == "license")
+            .map(|(_, value)| value.trim().trim_matches('"').to_string())
+    };
+    license.filter(|l| !l.trim().is_empty())
+}
+
+fn most_common(values: impl Iterator<Item = String>) -> Option<String> {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for value in values {
+        *counts.entry(value).or_default() += 1;
+    }
+    counts.into_iter().max_by(|a, b| a.1.cmp(&b.1).then_with(|| b.0.cmp(&a.0))).map(|(value, _)| value)
+}
+
+/// Majority loader and Minecraft version among the bundled mod jars.
+fn detect_from_mods<R: Read + Seek>(archive: &mut ZipArchive<R>, names: &[String]) -> (Option<String>, Option<String>) {
+    let jars = names.iter()
+        .filter(|n| is_bundled_mod(n))
+        .take(MAX_SCANNED_JARS)
+        .collect::<Vec<_>>();
+    let mut targets = Vec::new();
+    for name in jars {
+        let Ok(mut entry) = archive.by_name(name) else { continue; };
+        if entry.size() > MAX_SCANNED_JAR_SIZE {
+            continue;
+        }
+        let mut bytes = Vec::with_capacity(entry.size() as usize);
+        if entry.read_to_end(&mut bytes).is_err() {
+            continue;
+        }
+        let Ok(mut jar) = ZipArchive::new(io::Cursor::new(bytes)) else { continue; };
+        targets.extend(detect_mod_target(&mut jar));
+    }
+    let loader = most_common(targets.iter().map(|(loader, _)| loader.clone()));
+    let version = most_common(targets.into_iter().filter_map(|(_, version)| version));
+    (loader, version)
+}
+
+/// Format from the first bytes, leaving `reader` at the start.
+fn sniff_format<R: Read + Seek>(reader: &mut R) -> Result<Option<super::archive::ArchiveFormat>, String> {
+    let mut head = [0u8; 8];
+    let read = reader.read(&mut head).map_err(|e| e.to_string())?;
+    reader.seek(SeekFrom::Start(0)).map_err(|e| e.to_string())?;
+    Ok(super::archive::format_of(&head[..read]))
+}
+
+fn inspect_archive<R: Read + Seek>(mut reader: R, total_size: u64) -> Result<ModpackInfo, String> {
+    // 7z and rar packs have no central directory to peek at; only the sync can unpack them
+    match sniff_format(&mut reader)? {
+        Some(super::archive::ArchiveFormat::SevenZip) => return Ok(ModpackInfo { format: "7z".to_string(), total_size, ..Default::default() }),
+        Some(super::archive::ArchiveFormat::Rar) => return Ok(ModpackInfo { format: "rar".to_string(), total_size, ..Default::default() }),
+        _ => {}
+    }
+    let mut archive = open_archive(reader)?;
+    ensure_not_encrypted(&mut archive, ENCRYPTION_PROBE_ENTRIES)?;
+    let names = archive.file_names().map(|n| n.to_string()).collect::<Vec<_>>();
+    let mut info = ModpackInfo {
+        format: "zip".to_string(),
+        mod_count: count_bundled_mods(names.iter().map(|n| n.as_str())),
+        total_size,
+        ..Default::default()
+    };
+
+    if let Some(manifest) = read_json(&mut archive, "manifest.json") {
+        info.format = "curseforge".to_string();
+        info.name = manifest["name"].as_str().map(|s| s.to_string());
+        info.mc_version = manifest["minecraft"]["version"].as_str().map(|s| s.to_string());
+        let loaders = manifest["minecraft"]["modLoaders"].as_array().cloned().unwrap_or_default();
+        let primary = loaders.iter().find(|l| l["primary"].as_bool().unwrap_or(false)).or(loaders.first());
+        if let Some(id) = primary.and_then(|l| l["id"].as_str()) {
+            let (loader, version) = split_loader_id(id);
+            info.loader = Some(loader);
+            info.loader_version = version;
+        }
+        info.mod_count += manifest["files"].as_array().map(|f| f.len()).unwrap_or(0);
+    } else if let Some(index) = read_json(&mut archive, "modrinth.index.json") {
+        info.format = "modrinth".to_string();
+        info.name = index["name"].as_str().map(|s| s.to_string());
+        let deps = &index["dependencies"];
+        info.mc_version = deps["minecraft"].as_str().map(|s| s.to_string());
+        for (key, loader) in [("forge", "forge"), ("neoforge", "neoforge"), ("fabric-loader", "fabric"), ("quilt-loader", "quilt")] {
+            if let Some(version) = deps[key].as_str() {
+                info.loader = Some(loader.to_string());
+                info.loader_version = Some(version.to_string());
+                break;
+            }
+        }
+        info.mod_count += index["files"].as_array()
+            .map(|files| files.iter().filter(|f| f["path"].as_str().map(|p| p.starts_with("mods/")).unwrap_or(false)).count())
+            .unwrap_or(0);
+    } else if let Some(pack) = read_json(&mut archive, "mmc-pack.json") {
+        info.format = "multimc".to_string();
+        for component in pack["components"].as_array().cloned().unwrap_or_default() {
+            let version = component["version"].as_str().map(|s| s.to_string());
+            match component["uid"].as_str().unwrap_or("") {
+                "net.minecraft" => info.mc_version = version,
+                "net.minecraftforge" => { info.loader = Some("forge".to_string()); info.loader_version = version; }
+                "net.neoforged" => { info.loader = Some("neoforge".to_string()); info.loader_version = version; }
+                "net.fabricmc.fabric-loader" => { info.loader = Some("fabric".to_string()); info.loader_version = version; }
+                "org.quiltmc.quilt-loader" => { info.loader = Some("quilt".to_string()); info.loader_version = version; }
+                _ => {}
+            }
+        }
+        info.name = read_text(&mut archive, "instance.cfg")
+            .and_then(|cfg| cfg.lines().find_map(|l| l.strip_prefix("name=").map(|n| n.trim().to_string())));
+    }
+
+    if info.loader.is_none() || info.mc_version.is_none() {
+        let (loader, mc_version) = detect_from_mods(&mut archive, &names);
+        info.detected_from_mods = (info.loader.is_none() && loader.is_some()) || (info.mc_version.is_none() && mc_version.is_some());
+        info.loader = info.loader.or(loader);
+        info.mc_version = info.mc_version.or(mc_version);
+    }
+
+    Ok(info)
+}
+
+fn file_crc(path: &Path) -> Option<u32> {
+    let mut file = File::open(path).ok()?;
+    let mut crc = flate2::Crc::new();
+    let mut buf = vec![0u8; 64 * 1024];
+    loop {
+        let n = file.read(&mut buf).ok()?;
+        if n == 0 {
+            return Some(crc.sum());
+        }
+        crc.update(&buf[..n]);
+    }
+}
+
+/// Compares a zip pack against `minecraft_dir` the way `sync_modpack` extracts it: entries land
+/// where `pack_entry_target` puts them and the managed folders are emptied first. Sizes and
+/// CRCs come from the central directory, so nothing is decompressed.
+fn diff_archive<R: Read + Seek>(mut reader: R, total_size: u64, minecraft_dir: &Path, kept: &[String]) -> Result<ModpackDiff, String> {
+    if sniff_format(&mut reader)? != Some(super::archive::ArchiveFormat::Zip) {
+        return Err("La vista previa de cambios solo está disponible para modpacks zip".to_string());
+    }
+    let mut archive = open_archive(reader)?;
+    // Later duplicates overwrite earlier ones on extraction
+    let mut entries = BTreeMap::new();
+    for i in 0..archive.len() {
+        let entry = archive.by_index_raw(i).map_err(archive_error)?;
+        let Some(target) = super::launch_logic::pack_entry_target(Path::new(""), entry.name()) else { continue };
+        let path = target.components().map(|c| c.as_os_str().to_string_lossy()).collect::<Vec<_>>().join("/");
+        entries.insert(path, (entry.size(), entry.crc32()));
+    }
+
+    let mut diff = ModpackDiff { download_size: total_size, ..Default::default() };
+    for (path, (size, crc)) in &entries {
+        let local = minecraft_dir.join(path);
+        let file = PackFile { path: path.clone(), size: *size };
+        match fs::metadata(&local) {
+            Ok(meta) if meta.is_file() && meta.len() == *size && file_crc(&local) == Some(*crc) => diff.unchanged += 1,
+            Ok(meta) if meta.is_file() => diff.changed.push(file),
+            _ => diff.added.push(file),
+        }
+    }
+    for folder in super::launch_logic::PACK_MANAGED_FOLDERS {
+        for (name, _) in super::archive::walk_files(&minecraft_dir.join(folder)) {
+            let path = format!("{}/{}", folder, name);
+            if !entries.contains_key(&path) && !kept.contains(&path) {
+                diff.removed.push(path);
+            }
+        }
+    }
+    Ok(diff)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Cursor, Write};
+
+    fn build_zip(entries: &[(&str, &str)]) -> Vec<u8> {
+        let mut zip = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        for (name, content) in entries {
+            zip.start_file(*name, zip::write::FileOptions::default()).unwrap();
+            zip.write_all(content.as_bytes()).unwrap();
+        }
+        zip.finish().unwrap().into_inner()
+    }
+
+    #[test]
+    fn zip64_packs_open_like_any_other() {
+        let mut zip = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        let options = zip::write::FileOptions::default().large_file(true);
+        zip.start_file("mods/big.jar", options).unwrap();
+        zip.write_all(b"jar").unwrap();
+        let bytes = zip.finish().unwrap().into_inner();
+        let mut archive = open_archive(Cursor::new(bytes)).unwrap();
+        ensure_not_encrypted(&mut archive, usize::MAX).unwrap();
+        let mut content = String::new();
+        archive.by_name("mods/big.jar").unwrap().read_to_string(&mut content).unwrap();
+        assert_eq!(content, "jar");
+    }
+
+    #[test]
+    fn encrypted_and_truncated_packs_get_codes() {
+        let mut bytes = build_zip(&[("manifest.json", "{}")]);
+        // Set the "encrypted" flag bit in the local and central directory headers
+        for (signature, flags_offset) in [(b"PK\x03\x04", 6), (b"PK\x01\x02", 8)] {
+            let at = bytes.windows(4).position(|w| w == signature).unwrap();
+            bytes[at + flags_offset] |= 1;
+        }
+        let mut archive = open_archive(Cursor::new(bytes.clone())).unwrap();
+        assert!(ensure_not_encrypted(&mut archive, usize::MAX).unwrap_err().starts_with(ENCRYPTED_MARKER));
+        assert!(inspect_archive(Cursor::new(bytes.clone()), 0).unwrap_err().starts_with(ENCRYPTED_MARKER));
+
+        let truncated = build_zip(&[("manifest.json", "{}")])[..20].to_vec();
+        assert!(open_archive(Cursor::new(truncated)).unwrap_err().starts_with(CORRUPT_MARKER));
+    }
+
+    #[test]
+    fn curseforge_manifest_is_read() {
+        let manifest = serde_json::json!({
+            "name": "Evento DRK",
+            "minecraft": {"version": "1.20.1", "modLoaders": [{"id": "forge-47.2.0", "primary": true}]},
+            "files": [{"projectID": 1}, {"projectID": 2}]
+        }).to_string();
+        let bytes = build_zip(&[("manifest.json", &manifest), ("overrides/mods/extra.jar", "jar"), ("overrides/config/a.toml", "")]);
+        let len = bytes.len() as u64;
+        let info = inspect_archive(Cursor::new(bytes), len).unwrap();
+        assert_eq!(info.format, "curseforge");
+        assert_eq!(info.name.as_deref(), Some("Evento DRK"));
+        assert_eq!(info.mc_version.as_deref(), Some("1.20.1"));
+        assert_eq!(info.loader.as_deref(), Some("forge"));
+        assert_eq!(info.loader_version.as_deref(), Some("47.2.0"));
+        assert_eq!(info.mod_count, 3);
+        assert_eq!(info.total_size, len);
+    }
+
+    #[test]
+    fn diff_follows_how_the_sync_extracts() {
+        let dir = tempfile::tempdir().unwrap();
+        let minecraft = dir.path();
+        for (path, content) in [("config/a.toml", "igual"), ("mods/b.jar", "viejo"), ("mods/quitado.jar", "x"), ("mods/suelto.jar", "x"), ("saves/Mundo/level.dat", "x")] {
+            fs::create_dir_all(minecraft.join(path).parent().unwrap()).unwrap();
+            fs::write(minecraft.join(path), content).unwrap();
+        }
+        let bytes = build_zip(&[
+            ("manifest.json", "{}"),
+            ("overrides/config/a.toml", "igual"),
+            ("mods/b.jar", "nuevo"),
+            ("c.jar", "x"),
+            ("overrides/options.txt", "x"),
+        ]);
+        let size = bytes.len() as u64;
+        let diff = diff_archive(Cursor::new(bytes), size, minecraft, &["mods/suelto.jar".to_string()]).unwrap();
+        let paths = |files: &[PackFile]| files.iter().map(|f| f.path.clone()).collect::<Vec<_>>();
+        assert_eq!(paths(&diff.added), vec!["mods/c.jar", "options.txt"]);
+        assert_eq!(paths(&diff.changed), vec!["mods/b.jar"]);
+        assert_eq!(diff.removed, vec!["mods/quitado.jar"]);
+        assert_eq!(diff.unchanged, 1);
+        assert_eq!(diff.download_size, size);
+        assert!(diff_archive(Cursor::new(b"7z\xBC\xAF\x27\x1C".to_vec()), 6, minecraft, &[]).is_err());
+    }
+
+    #[test]
+    fn plain_zip_counts_root_and_mods_jars() {
+        let bytes = build_zip(&[("a.jar", ""), ("mods/b.jar", ""), ("config/c.jar.disabled", ""), ("libs/d.jar", "")]);
+        let info = inspect_archive(Cursor::new(bytes), 0).unwrap();
+        assert_eq!(info.format, "zip");
+        assert_eq!(info.mod_count, 2);
+        assert_eq!(info.mc_version, None);
+    }
+
+    #[test]
+    fn declared_licenses_are_read_from_mod_metadata() {
+        let fabric = build_zip(&[("fabric.mod.json", r#"{"license": ["MIT", "CC0-1.0"]}"#)]);
+        let forge = build_zip(&[("META-INF/mods.toml", "modLoader=\"javafml\"\nlicense=\"All Rights Reserved\"\n[[mods]]\nmodId=\"x\"\n")]);
+        let bare = build_zip(&[("META-INF/mods.toml", "modLoader=\"javafml\"\n[[mods]]\nlicense=\"MIT\"\n")]);
+        assert_eq!(declared_license(&mut ZipArchive::new(Cursor::new(fabric)).unwrap()).as_deref(), Some("MIT / CC0-1.0"));
+        assert_eq!(declared_license(&mut ZipArchive::new(Cursor::new(forge)).unwrap()).as_deref(), Some("All Rights Reserved"));
+        assert_eq!(declared_license(&mut ZipArchive::new(Cursor::new(bare)).unwrap()), None);
+    }
+
+    #[test]
+    fn plain_zip_detects_loader_and_version_from_mods() {
+        let fabric = build_zip(&[("fabric.mod.json", r#"{"depends": {"minecraft": "~1.20.1"}}"#)]);
+        let forge = build_zip(&[("META-INF/mods.toml", "modLoader=\"javafml\"\n[[dependencies.x]]\nmodId=\"minecraft\"\nversionRange=\"[1.19.2,1.20)\"\n")]);
+        let mut zip = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        for (name, bytes) in [("mods/a.jar", &fabric), ("mods/b.jar", &fabric), ("mods/c.jar", &forge)] {
+            zip.start_file(name, zip::write::FileOptions::default()).unwrap();
+            zip.write_all(bytes).unwrap();
+        }
+        let bytes = zip.finish().unwrap().into_inner();
+        let info = inspect_archive(Cursor::new(bytes), 0).unwrap();
+        assert_eq!(info.loader.as_deref(), Some("fabric"));
+        assert_eq!(info.mc_version.as_deref(), Some("1.20.1"));
+        assert!(info.detected_from_mods);
+    }
+
+    #[test]
+    fn version_requirements_yield_a_concrete_version() {
+        assert_eq!(first_mc_version("~1.20.1").as_deref(), Some("1.20.1"));
+        assert_eq!(first_mc_version(">=1.19-").as_deref(), Some("1.19"));
+        assert_eq!(first_mc_version("[1.20.1,1.21)").as_deref(), Some("1.20.1"));
+        assert_eq!(first_mc_version("*"), None);
+        assert_eq!(toml_minecraft_range("[[dependencies.a]]\nmodId=\"forge\"\nversionRange=\"[47,)\"\n[[dependencies.a]]\nmodId=\"minecraft\"\nversionRange=\"[1.20.1]\"").as_deref(), Some("[1.20.1]"));
+    }
+
+    #[test]
+    fn multimc_pack_reads_components_and_name() {
+        let pack = serde_json::json!({"components": [
+            {"uid": "net.minecraft", "version": "1.19.2"},
+            {"uid": "net.fabricmc.fabric-loader", "version": "0.14.21"}
+        ]}).to_string();
+        let bytes = build_zip(&[("mmc-pack.json", &pack), ("instance.cfg", "InstanceType=OneSix\nname=Pack Ñu\n")]);
+        let info = inspect_archive(Cursor::new(bytes), 0).unwrap();
+        assert_eq!(info.format, "multimc");
+        assert_eq!(info.name.as_deref(), Some("Pack Ñu"));
+        assert_eq!(info.loader.as_deref(), Some("fabric"));
+        assert_eq!(info.mc_version.as_deref(), Some("1.19.2"));
+    }
+}
@@ -0,0 +1,200 @@
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::time::Duration;
+
+const API_BASE: &str = "https://api.modrinth.com/v2";
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct ModrinthFile {
+    pub url: String,
+    pub filename: String,
+    #[serde(default)]
+    pub primary: bool,
+    /// `sha1` and `sha512`, in hex
+    #[serde(default)]
+    pub hashes: HashMap<String, String>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct ModrinthVersion {
+    pub id: String,
+    pub version_number: String,
+    #[serde(default)]
+    pub files: Vec<ModrinthFile>,
+}
+
+impl ModrinthVersion {
+    /// The primary file, or the first one for versions that don't flag any.
+    pub fn primary_file(&self) -> Option<&ModrinthFile> {
+        self.files.iter().find(|f| f.primary).or_else(|| self.files.first())
+    }
+}
+
+fn client() -> Result<reqwest::blocking::Client, String> {
+    reqwest::blocking::Client::builder()
+        .user_agent(crate::http::user_agent())
+        .timeout(Duration::from_secs(20))
+        .build()
+        .map_err(|e| e.to_string())
+}
+
+/// Versions of `project` (slug or id) for a loader/game version, newest first. An empty loader
+/// or version doesn't filter.
+pub fn project_versions(project: &str, loader: &str, mc_version: &str) -> Result<Vec<ModrinthVersion>, String> {
+    let client = client()?;
+    let url = format!("{}/project/{}/version", API_BASE, project);
+    let filters = [("loaders", loader), ("game_versions", mc_version)];
+    let query: Vec<_> = filters.iter()
+        .filter(|(_, value)| !value.is_empty())
+        .map(|(key, value)| (*key, format!("[\"{}\"]", value)))
+        .collect();
+    let request = client.get(&url).query(&query);
+    let resp = crate::http::send(&client, request)?;
+    if !resp.status().is_success() {
+        return Err(format!("Modrinth returned status {} for {}", resp.status(), project));
+    }
+    resp.json().map_err(|e| format!("Invalid Modrinth response: {}", e))
+}
+
+/// Download URL of the newest build of `project` for a loader/game version.
+pub fn latest_file_url(project: &str, loader: &str, mc_version: &str) -> Result<String, String> {
+    project_versions(project, loader, mc_version)?
+        .first()
+        .and_then(|v| v.primary_file())
+        .map(|f| f.url.clone())
+        .ok_or_else(|| format!("{} no tiene versión para {} {}", project, loader, mc_version))
+}
+
+/// Whether Modrinth hosts a file with this SHA-1.
+pub fn is_known_file(sha1: &str) -> Result<bool, String> {
+    let client = client()?;
+    let url = format!("{}/version_file/{}", API_BASE, sha1);
+    let resp = crate::http::send(&client, client.get(&url).query(&[("algorithm", "sha1")]))?;
+    match resp.status() {
+        s if s.is_success() => Ok(true),
+        reqwest::StatusCode::NOT_FOUND => Ok(false),
+        s => Err(format!("Modrinth returned status {} for {}", s, sha1)),
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct ModrinthFileVersion {
+    pub project_id: String,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct ModrinthLicense {
+    /// SPDX id, or `LicenseRef-All-Rights-Reserved` / `LicenseRef-Custom` / `LicenseRef-Unknown`
+    pub id: String,
+    #[serde(default)]
+    pub name: String,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct ModrinthProject {
+    pub id: String,
+    pub slug: String,
+    pub title: String,
+    #[serde(default)]
+    pub license: Option<ModrinthLicense>,
+    #[serde(default)]
+    pub source_url: Option<String>,
+}
+
+/// Versions Modrinth hosts, keyed by the SHA-1 of their file.
+pub fn versions_by_hash(hashes: &[String]) -> Result<HashMap<String, ModrinthFileVersion>, String> {
+    if hashes.is_empty() {
+        return Ok(HashMap::new());
+    }
+    let client = client()?;
+    let url = format!("{}/version_files", API_BASE);
+    let body = serde_json::json!({ "hashes": hashes, "algorithm": "sha1" });
+    let resp = crate::http::send(&client, client.post(&url).json(&body))?;
+    if !resp.status().is_success() {
+        return Err(format!("Modrinth returned status {} for version_files", resp.status()));
+    }
+    resp.json().map_err(|e| format!("Invalid Modrinth response: {}", e))
+}
+
+pub fn projects(ids: &[String]) -> Result<Vec<ModrinthProject>, String> {
+    if ids.is_empty() {
+        return Ok(Vec::new());
+    }
+    let client = client()?;
+    let url = format!("{}/projects", API_BASE);
+    let ids = serde_json::to_string(ids).map_err(|e| e.to_string())?;
+    let resp = crate::http::send(&client, client.get(&url).query(&[("ids", ids)]))?;
+    if !resp.status().is_success() {
+        return Err(format!("Modrinth returned status {} for projects", resp.status()));
+    }
+    resp.json().map_err(|e| format!("Invalid Modrinth response: {}", e))
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct ModrinthSearchHit {
+    pub slug: String,
+    pub title: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub icon_url: Option<String>,
+    #[serde(default)]
+    pub downloads: u64,
+}
+
+#[derive(Deserialize)]
+struct SearchResults {
+    hits: Vec<ModrinthSearchHit>,
+}
+
+/// `facets` for a search: the project type, plus loader and game version when given.
+pub fn search_facets(project_type: &str, loader: &str, mc_version: &str) -> String {
+    let mut facets = vec![format!("[\"project_type:{}\"]", project_type)];
+    if !loader.is_empty() {
+        facets.push(format!("[\"categories:{}\"]", loader));
+    }
+    if !mc_version.is_empty() {
+        facets.push(format!("[\"versions:{}\"]", mc_version));
+    }
+    format!("[{}]", facets.join(","))
+}
+
+/// Projects matching `query`; `project_type` is `mod`, `modpack` or `resourcepack`.
+pub fn search(query: &str, project_type: &str, loader: &str, mc_version: &str, limit: usize) -> Result<Vec<ModrinthSearchHit>, String> {
+    let client = client()?;
+    let url = format!("{}/search", API_BASE);
+    let request = client.get(&url).query(&[
+        ("query", query.to_string()),
+        ("facets", search_facets(project_type, loader, mc_version)),
+        ("limit", limit.to_string()),
+    ]);
+    let resp = crate::http::send(&client, request)?;
+    if !resp.status().is_success() {
+        return Err(format!("Modrinth returned status {} for search", resp.status()));
+    }
+    resp.json::<SearchResults>().map(|r| r.hits).map_err(|e| format!("Invalid Modrinth response: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn primary_file_prefers_flagged_entry() {
+        let version: ModrinthVersion = serde_json::from_value(serde_json::json!({
+            "id": "abc",
+            "version_number": "2.0.0",
+            "files": [
+                {"url": "https://cdn.modrinth.com/sources.jar", "filename": "sources.jar", "primary": false},
+                {"url": "https://cdn.modrinth.com/mod.jar", "filename": "mod.jar", "primary": true}
+            ]
+        })).unwrap();
+        assert_eq!(version.primary_file().map(|f| f.filename.as_str()), Some("mod.jar"));
+    }
+
+    #[test]
+    fn search_facets_skip_missing_filters() {
+        assert_eq!(search_facets("mod", "fabric", "1.20.1"), r#"[["project_type:mod"],["categories:fabric"],["versions:1.20.1"]]"#);
+        assert_eq!(search_facets("modpack", "", ""), r#"[["project_type:modpack"]]"#);
+    }
+}
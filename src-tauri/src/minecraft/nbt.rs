@@ -0,0 +1,229 @@
+use std::io::Read;
+use std::path::Path;
+use flate2::read::GzDecoder;
+
+/// Named Binary Tag value, as stored in `level.dat` and friends (big-endian, Java edition).
+#[derive(Debug, Clone, PartialEq)]
+pub enum Tag {
+    Byte(i8),
+    Short(i16),
+    Int(i32),
+    Long(i64),
+    Float(f32),
+    Double(f64),
+    ByteArray(Vec<i8>),
+    String(String),
+    List(Vec<Tag>),
+    Compound(Vec<(String, Tag)>),
+    IntArray(Vec<i32>),
+    LongArray(Vec<i64>),
+}
+
+impl Tag {
+    /// Child of a compound by name.
+    pub fn get(&self, name: &str) -> Option<&Tag> {
+        match self {
+            Tag::Compound(entries) => entries.iter().find(|(n, _)| n == name).map(|(_, t)| t),
+            _ => None,
+        }
+    }
+
+    /// Walks nested compounds, e.g. `["Data", "Version", "Name"]`.
+    pub fn path(&self, names: &[&str]) -> Option<&Tag> {
+        names.iter().try_fold(self, |tag, name| tag.get(name))
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Tag::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_int(&self) -> Option<i64> {
+        match self {
+            Tag::Byte(v) => Some(*v as i64),
+            Tag::Short(v) => Some(*v as i64),
+            Tag::Int(v) => Some(*v as i64),
+            Tag::Long(v) => Some(*v),
+            _ => None,
+        }
+    }
+}
+
+// Guards against corrupt files announcing absurd lengths
+const MAX_LEN: usize = 64 * 1024 * 1024;
+const MAX_DEPTH: usize = 512;
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], String> {
+        let end = self.pos.checked_add(n).filter(|end| *end <= self.data.len()).ok_or("Unexpected end of NBT data")?;
+        let bytes = &self.data[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], String> {
+        Ok(self.take(N)?.try_into().expect("length checked"))
+    }
+
+    fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn len(&mut self) -> Result<usize, String> {
+        let len = i32::from_be_bytes(self.array()?);
+        if len < 0 || len as usize > MAX_LEN {
+            return Err(format!("Invalid NBT length {}", len));
+        }
+        Ok(len as usize)
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        let len = u16::from_be_bytes(self.array()?) as usize;
+        // Java's modified UTF-8 only differs for NUL and supplementary characters
+        Ok(String::from_utf8_lossy(self.take(len)?).into_owned())
+    }
+
+    fn payload(&mut self, kind: u8, depth: usize) -> Result<Tag, String> {
+        if depth > MAX_DEPTH {
+            return Err("NBT nesting too deep".to_string());
+        }
+        Ok(match kind {
+            1 => Tag::Byte(self.u8()? as i8),
+            2 => Tag::Short(i16::from_be_bytes(self.array()?)),
+            3 => Tag::Int(i32::from_be_bytes(self.array()?)),
+            4 => Tag::Long(i64::from_be_bytes(self.array()?)),
+            5 => Tag::Float(f32::from_be_bytes(self.array()?)),
+            6 => Tag::Double(f64::from_be_bytes(self.array()?)),
+            7 => {
+                let len = self.len()?;
+                Tag::ByteArray(self.take(len)?.iter().map(|b| *b as i8).collect())
+            }
+            8 => Tag::String(self.string()?),
+            9 => {
+                let item_kind = self.u8()?;
+                let len = self.len()?;
+                let mut items = Vec::new();
+                for _ in 0..len {
+                    items.push(self.payload(item_kind, depth + 1)?);
+                }
+                Tag::List(items)
+            }
+            10 => {
+                let mut entries = Vec::new();
+                loop {
+                    let child_kind = self.u8()?;
+                    if child_kind == 0 {
+                        break;
+                    }
+                    let name = self.string()?;
+                    entries.push((name, self.payload(child_kind, depth + 1)?));
+                }
+                Tag::Compound(entries)
+            }
+            11 => {
+                let len = self.len()?;
+                let mut values = Vec::with_capacity(len.min(4096));
+                for _ in 0..len {
+                    values.push(i32::from_be_bytes(self.array()?));
+                }
+                Tag::IntArray(values)
+            }
+            12 => {
+                let len = self.len()?;
+                let mut values = Vec::with_capacity(len.min(4096));
+                for _ in 0..len {
+                    values.push(i64::from_be_bytes(self.array()?));
+                }
+                Tag::LongArray(values)
+            }
+            other => return Err(format!("Unknown NBT tag type {}", other)),
+        })
+    }
+}
+
+/// Parses an uncompressed NBT document; the root is a named compound whose name is dropped.
+pub fn parse(data: &[u8]) -> Result<Tag, String> {
+    let mut reader = Reader { data, pos: 0 };
+    if reader.u8()? != 10 {
+        return Err("NBT root is not a compound".to_string());
+    }
+    reader.string()?;
+    reader.payload(10, 0)
+}
+
+/// Reads a gzip-compressed NBT file such as `level.dat`.
+pub fn read_gzip_file(path: &Path) -> Result<Tag, String> {
+    let file = std::fs::File::open(path).map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    let mut data = Vec::new();
+    GzDecoder::new(file)
+        .take(MAX_LEN as u64)
+        .read_to_end(&mut data)
+        .map_err(|e| format!("Failed to decompress {}: {}", path.display(), e))?;
+    parse(&data)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    fn named(kind: u8, name: &str, payload: &[u8]) -> Vec<u8> {
+        let mut out = vec![kind];
+        out.extend((name.len() as u16).to_be_bytes());
+        out.extend(name.as_bytes());
+        out.extend(payload);
+        out
+    }
+
+    fn string_payload(value: &str) -> Vec<u8> {
+        let mut out = (value.len() as u16).to_be_bytes().to_vec();
+        out.extend(value.as_bytes());
+        out
+    }
+
+    /// Uncompressed `level.dat` body with `Data.Version.Name`, `Data.Version.Id` and `Data.DataVersion`.
+    pub(crate) fn level_dat(version_name: &str, data_version: i32) -> Vec<u8> {
+        let mut version = named(8, "Name", &string_payload(version_name));
+        version.extend(named(3, "Id", &data_version.to_be_bytes()));
+        version.push(0);
+        let mut data = named(10, "Version", &version);
+        data.extend(named(3, "DataVersion", &data_version.to_be_bytes()));
+        data.extend(named(8, "LevelName", &string_payload("Mundo")));
+        data.push(0);
+        let mut root = named(10, "Data", &data);
+        root.push(0);
+        named(10, "", &root)
+    }
+
+    #[test]
+    fn reads_level_dat_version_fields() {
+        let tag = parse(&level_dat("1.20.1", 3465)).unwrap();
+        assert_eq!(tag.path(&["Data", "Version", "Name"]).and_then(Tag::as_str), Some("1.20.1"));
+        assert_eq!(tag.path(&["Data", "DataVersion"]).and_then(Tag::as_int), Some(3465));
+        assert_eq!(tag.path(&["Data", "LevelName"]).and_then(Tag::as_str), Some("Mundo"));
+    }
+
+    #[test]
+    fn lists_arrays_and_truncation() {
+        let mut list = vec![3u8];
+        list.extend(2i32.to_be_bytes());
+        list.extend(7i32.to_be_bytes());
+        list.extend(9i32.to_be_bytes());
+        let mut body = named(9, "nums", &list);
+        let mut longs = 1i32.to_be_bytes().to_vec();
+        longs.extend((-5i64).to_be_bytes());
+        body.extend(named(12, "longs", &longs));
+        body.push(0);
+        let doc = named(10, "", &body);
+        let tag = parse(&doc).unwrap();
+        assert_eq!(tag.get("nums"), Some(&Tag::List(vec![Tag::Int(7), Tag::Int(9)])));
+        assert_eq!(tag.get("longs"), Some(&Tag::LongArray(vec![-5])));
+        assert!(parse(&doc[..doc.len() - 3]).is_err());
+    }
+}
@@ -0,0 +1,189 @@
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::io::{Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+const DEFAULT_PORT: u16 = 25565;
+// The status JSON carries the favicon as base64; anything bigger than this isn't a status reply
+const MAX_PACKET: usize = 1 << 21;
+
+/// What the event server answers to a server list ping.
+#[derive(Serialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ServerStatus {
+    pub online: u32,
+    pub max: u32,
+    pub version: Option<String>,
+    pub motd: Option<String>,
+}
+
+impl ServerStatus {
+    pub fn is_full(&self) -> bool {
+        self.max > 0 && self.online >= self.max
+    }
+}
+
+/// `host`, `host:port` or `[v6]:port`; the port defaults to 25565 (no SRV lookup).
+pub fn parse_address(address: &str) -> Result<(String, u16), String> {
+    let address = address.trim();
+    let (host, port) = if let Some(rest) = address.strip_prefix('[') {
+        let (host, rest) = rest.split_once(']').ok_or("Dirección de servidor no válida")?;
+        (host, rest.strip_prefix(':'))
+    } else {
+        match address.rsplit_once(':') {
+            Some((host, port)) if !host.contains(':') => (host, Some(port)),
+            _ => (address, None),
+        }
+    };
+    if host.is_empty() {
+        return Err("Dirección de servidor no válida".to_string());
+    }
+    let port = match port {
+        Some(port) => port.parse().map_err(|_| "Puerto de servidor no válido")?,
+        None => DEFAULT_PORT,
+    };
+    Ok((host.to_string(), port))
+}
+
+fn write_varint(buf: &mut Vec<u8>, value: i32) {
+    let mut value = value as u32;
+    loop {
+        if value & !0x7F == 0 {
+            buf.push(value as u8);
+            return;
+        }
+        buf.push((value & 0x7F) as u8 | 0x80);
+        value >>= 7;
+    }
+}
+
+fn read_varint(reader: &mut impl Read) -> Result<i32, String> {
+    let mut value = 0u32;
+    for i in 0..5 {
+        let mut byte = [0u8];
+        reader.read_exact(&mut byte).map_err(|e| e.to_string())?;
+        value |= ((byte[0] & 0x7F) as u32) << (7 * i);
+        if byte[0] & 0x80 == 0 {
+            return Ok(value as i32);
+        }
+    }
+    Err("VarInt demasiado largo".to_string())
+}
+
+fn packet(id: i32, body: &[u8]) -> Vec<u8> {
+    let mut payload = Vec::new();
+    write_varint(&mut payload, id);
+    payload.extend_from_slice(body);
+    let mut framed = Vec::new();
+    write_varint(&mut framed, payload.len() as i32);
+    framed.extend(payload);
+    framed
+}
+
+fn handshake(host: &str, port: u16) -> Vec<u8> {
+    let mut body = Vec::new();
+    // -1: "whatever you speak", the usual value for status requests
+    write_varint(&mut body, -1);
+    write_varint(&mut body, host.len() as i32);
+    body.extend_from_slice(host.as_bytes());
+    body.extend_from_slice(&port.to_be_bytes());
+    write_varint(&mut body, 1);
+    packet(0x00, &body)
+}
+
+/// Reads the status response packet and returns its JSON.
+fn read_status(reader: &mut impl Read) -> Result<String, String> {
+    let length = read_varint(reader)? as usize;
+    if length > MAX_PACKET {
+        return Err("Respuesta de estado demasiado grande".to_string());
+    }
+    let mut packet = vec![0u8; length];
+    reader.read_exact(&mut packet).map_err(|e| e.to_string())?;
+    let mut cursor = packet.as_slice();
+    if read_varint(&mut cursor)? != 0x00 {
+        return Err("Respuesta de estado inesperada".to_string());
+    }
+    let len = read_varint(&mut cursor)? as usize;
+    let json = cursor.get(..len).ok_or("Respuesta de estado incompleta")?;
+    String::from_utf8(json.to_vec()).map_err(|e| e.to_string())
+}
+
+fn motd_text(description: &serde_json::Value) -> Option<String> {
+    match description {
+        serde_json::Value::String(text) => Some(text.clone()),
+        serde_json::Value::Object(object) => {
+            let mut text = object.get("text").and_then(|t| t.as_str()).unwrap_or_default().to_string();
+            for extra in object.get("extra").and_then(|e| e.as_array()).into_iter().flatten() {
+                text.push_str(&motd_text(extra).unwrap_or_default());
+            }
+            Some(text)
+        }
+        _ => None,
+    }
+}
+
+pub fn parse_status(json: &str) -> Result<ServerStatus, String> {
+    let value: serde_json::Value = serde_json::from_str(json).map_err(|e| format!("Estado del servidor no válido: {}", e))?;
+    let players = &value["players"];
+    Ok(ServerStatus {
+        online: players["online"].as_u64().unwrap_or(0) as u32,
+        max: players["max"].as_u64().unwrap_or(0) as u32,
+        version: value["version"]["name"].as_str().map(str::to_string),
+        motd: motd_text(&value["description"]),
+    })
+}
+
+/// Server list ping: the same request the multiplayer menu sends.
+pub fn ping(address: &str, timeout: Duration) -> Result<ServerStatus, String> {
+    let (host, port) = parse_address(address)?;
+    let addr = (host.as_str(), port).to_socket_addrs().map_err(|e| e.to_string())?
+        .next()
+        .ok_or_else(|| format!("No se pudo resolver {}", host))?;
+    let mut stream = TcpStream::connect_timeout(&addr, timeout).map_err(|e| format!("El servidor no responde: {}", e))?;
+    let _ = stream.set_read_timeout(Some(timeout));
+    let _ = stream.set_write_timeout(Some(timeout));
+    stream.write_all(&handshake(&host, port)).map_err(|e| e.to_string())?;
+    stream.write_all(&packet(0x00, &[])).map_err(|e| e.to_string())?;
+    parse_status(&read_status(&mut stream)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn addresses_default_to_the_minecraft_port() {
+        assert_eq!(parse_address("play.drk.example"), Ok(("play.drk.example".to_string(), 25565)));
+        assert_eq!(parse_address(" play.drk.example:25570 "), Ok(("play.drk.example".to_string(), 25570)));
+        assert_eq!(parse_address("[::1]:25566"), Ok(("::1".to_string(), 25566)));
+        assert_eq!(parse_address("::1"), Ok(("::1".to_string(), 25565)));
+        assert!(parse_address("host:abc").is_err());
+        assert!(parse_address("").is_err());
+    }
+
+    #[test]
+    fn varints_round_trip() {
+        for value in [0, 1, 127, 128, 25565, i32::MAX, -1] {
+            let mut buf = Vec::new();
+            write_varint(&mut buf, value);
+            assert_eq!(read_varint(&mut buf.as_slice()), Ok(value));
+        }
+        let mut buf = Vec::new();
+        write_varint(&mut buf, -1);
+        assert_eq!(buf.len(), 5);
+    }
+
+    #[test]
+    fn status_replies_are_unframed_and_parsed() {
+        let json = r#"{"version":{"name":"1.20.1"},"players":{"online":100,"max":100},"description":{"text":"DRK ","extra":[{"text":"Evento"}]}}"#;
+        let mut body = Vec::new();
+        write_varint(&mut body, json.len() as i32);
+        body.extend_from_slice(json.as_bytes());
+        let framed = packet(0x00, &body);
+        let status = parse_status(&read_status(&mut framed.as_slice()).unwrap()).unwrap();
+        assert!(status.is_full());
+        assert_eq!(status.motd.as_deref(), Some("DRK Evento"));
+        assert_eq!(status.version.as_deref(), Some("1.20.1"));
+        assert!(!parse_status(r#"{"players":{"online":3,"max":0}}"#).unwrap().is_full());
+    }
+}
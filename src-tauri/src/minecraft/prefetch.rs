@@ -0,0 +1,130 @@
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager, State};
+use super::launch_logic::{fetch_manifest_with_fallback, load_fabric_profile_info};
+use super::content_source::{self, Target};
+
+/// In-memory metadata cache with a fixed TTL. Errors are never cached.
+pub struct MetaCache<T> {
+    entries: OnceLock<Mutex<HashMap<String, (Instant, T)>>>,
+    ttl: Duration,
+}
+
+impl<T: Clone> MetaCache<T> {
+    pub const fn new(ttl: Duration) -> Self {
+        MetaCache { entries: OnceLock::new(), ttl }
+    }
+
+    pub fn get_or_fetch(&self, key: &str, fetch: impl FnOnce() -> Result<T, String>) -> Result<T, String> {
+        let entries = self.entries.get_or_init(|| Mutex::new(HashMap::new()));
+        if let Some((at, value)) = entries.lock().unwrap_or_else(|e| e.into_inner()).get(key) {
+            if at.elapsed() < self.ttl {
+                return Ok(value.clone());
+            }
+        }
+        // Fetch outside the lock; two concurrent misses just fetch twice
+        let value = fetch()?;
+        entries.lock().unwrap_or_else(|e| e.into_inner()).insert(key.to_string(), (Instant::now(), value.clone()));
+        Ok(value)
+    }
+}
+
+/// Instances with a prefetch running, so reopening the detail page doesn't stack requests.
+#[derive(Default)]
+pub struct PrefetchState {
+    running: Mutex<HashSet<String>>,
+}
+
+/// `prefetch_status` payload: one step of warming the caches for an instance.
+#[derive(Serialize, Clone, JsonSchema)]
+pub struct PrefetchStatus {
+    #[serde(rename = "instanceId")]
+    pub instance_id: String,
+    pub step: String,
+    /// `running`, `done` or `error`
+    pub status: String,
+    pub message: String,
+}
+
+fn emit_status(app: &AppHandle, instance_id: &str, step: &str, status: &str, message: &str) {
+    let _ = app.emit("prefetch_status", PrefetchStatus {
+        instance_id: instance_id.to_string(),
+        step: step.to_string(),
+        status: status.to_string(),
+        message: message.to_string(),
+    });
+}
+
+fn run_step(app: &AppHandle, instance_id: &str, step: &str, f: impl FnOnce() -> Result<String, String>) {
+    emit_status(app, instance_id, step, "running", "");
+    match f() {
+        Ok(message) => emit_status(app, instance_id, step, "done", &message),
+        Err(e) => emit_status(app, instance_id, step, "error", &e),
+    }
+}
+
+fn modpack_size(url: &str, target: &Target) -> Result<String, String> {
+    let size = content_source::size(&content_source::resolve(url, target)?)?;
+    Ok(size.map(|len| len.to_string()).unwrap_or_default())
+}
+
+fn prefetch(app: &AppHandle, instance: &crate::Instance) {
+    let id = instance.id.as_str();
+    run_step(app, id, "manifest", || fetch_manifest_with_fallback().map(|m| m.latest.release));
+    match instance.modloader.as_deref().unwrap_or("vanilla") {
+        "fabric" => run_step(app, id, "loader", || load_fabric_profile_info(&instance.version).map(|info| info.id)),
+        "forge" => run_step(app, id, "loader", || super::loader_pin::forge_version(id, &instance.version)),
+        _ => {}
+    }
+    if let Some(url) = instance.modpack_url.as_deref().filter(|u| !u.is_empty()) {
+        run_step(app, id, "modpack", || modpack_size(url, &Target::of(instance)));
+    }
+    emit_status(app, id, "complete", "done", "");
+}
+
+/// Warms manifest/loader metadata and checks the modpack while the user looks at an instance.
+/// Returns immediately; progress arrives as `prefetch_status` events.
+#[tauri::command]
+pub fn prefetch_instance(app: AppHandle, instance_id: String, state: State<'_, PrefetchState>) -> Result<(), String> {
+    let instance = crate::load_instances().into_iter().find(|i| i.id == instance_id)
+        .ok_or("Instance not found")?;
+    if !state.running.lock().map_err(|_| "Failed to lock prefetch state")?.insert(instance_id.clone()) {
+        return Ok(());
+    }
+    std::thread::spawn(move || {
+        prefetch(&app, &instance);
+        if let Ok(mut running) = app.state::<PrefetchState>().running.lock() {
+            running.remove(&instance.id);
+        }
+    });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    #[test]
+    fn cache_reuses_values_and_skips_errors() {
+        let cache: MetaCache<u32> = MetaCache::new(Duration::from_secs(60));
+        let calls = Cell::new(0);
+        let fetch = || { calls.set(calls.get() + 1); Ok(7) };
+        assert_eq!(cache.get_or_fetch("a", fetch), Ok(7));
+        assert_eq!(cache.get_or_fetch("a", fetch), Ok(7));
+        assert_eq!(calls.get(), 1);
+
+        assert!(cache.get_or_fetch("b", || Err("offline".to_string())).is_err());
+        assert_eq!(cache.get_or_fetch("b", || Ok(9)), Ok(9));
+    }
+
+    #[test]
+    fn expired_entries_are_refetched() {
+        let cache: MetaCache<u32> = MetaCache::new(Duration::ZERO);
+        assert_eq!(cache.get_or_fetch("a", || Ok(1)), Ok(1));
+        assert_eq!(cache.get_or_fetch("a", || Ok(2)), Ok(2));
+    }
+}
@@ -0,0 +1,162 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use super::models::VersionInfo;
+
+const CHECKPOINT_FILE: &str = ".prepare-checkpoint.json";
+// Past this the files on disk may have changed under us; start over
+const MAX_AGE_SECS: u64 = 12 * 60 * 60;
+
+pub const ASSETS: &str = "assets";
+pub const LIBRARIES: &str = "libraries";
+/// Fabric libraries on top of the vanilla ones
+pub const LOADER_LIBRARIES: &str = "loaderLibraries";
+pub const MODPACK: &str = "modpack";
+pub const TRANSLATION: &str = "translation";
+pub const CONTENT: &str = "content";
+
+#[derive(Serialize, Deserialize, Default)]
+struct State {
+    /// What was being prepared; a checkpoint of another plan is thrown away
+    plan: String,
+    #[serde(rename = "updatedAt")]
+    updated_at: u64,
+    /// `false` while a stage runs, `true` once it completed
+    stages: BTreeMap<String, bool>,
+    /// Profile the loader stage produced, so a retry can skip the whole stage
+    #[serde(default)]
+    info: Option<VersionInfo>,
+    /// Mod URLs already downloaded and checked
+    #[serde(default)]
+    mods: BTreeSet<String>,
+}
+
+/// What an interrupted prepare of an instance already finished, in
+/// `minecraft/.prepare-checkpoint.json`. A retry skips those stages and items; a prepare that
+/// completes removes the file. Cheap to clone for the download workers.
+#[derive(Clone)]
+pub struct Checkpoint {
+    path: Arc<PathBuf>,
+    state: Arc<Mutex<State>>,
+}
+
+impl Checkpoint {
+    /// Resumes the checkpoint left for `plan`, or starts an empty one when there is none, it is
+    /// for another plan or too old, or `fresh` (forced update) is set.
+    pub fn open(minecraft_dir: &Path, plan: &str, fresh: bool) -> Checkpoint {
+        Checkpoint::open_at(minecraft_dir, plan, fresh, crate::unix_now())
+    }
+
+    fn open_at(minecraft_dir: &Path, plan: &str, fresh: bool, now: u64) -> Checkpoint {
+        let path = minecraft_dir.join(CHECKPOINT_FILE);
+        let state = fs::read_to_string(&path).ok()
+            .and_then(|json| serde_json::from_str::<State>(&json).ok())
+            .filter(|s| !fresh && s.plan == plan && now.saturating_sub(s.updated_at) < MAX_AGE_SECS)
+            .unwrap_or_else(|| State { plan: plan.to_string(), ..Default::default() });
+        Checkpoint { path: Arc::new(path), state: Arc::new(Mutex::new(state)) }
+    }
+
+    fn update(&self, change: impl FnOnce(&mut State)) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        change(&mut state);
+        state.updated_at = crate::unix_now();
+        if let Some(parent) = self.path.parent() {
+            let _ = fs::create_dir_all(parent);
+        }
+        if let Ok(json) = serde_json::to_string(&*state) {
+            let _ = fs::write(&*self.path, json);
+        }
+    }
+
+    fn stage(&self, stage: &str) -> Option<bool> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner()).stages.get(stage).copied()
+    }
+
+    pub fn is_done(&self, stage: &str) -> bool {
+        self.stage(stage) == Some(true)
+    }
+
+    /// The stage started in an earlier run and never completed.
+    pub fn was_interrupted(&self, stage: &str) -> bool {
+        self.stage(stage) == Some(false)
+    }
+
+    pub fn begin(&self, stage: &str) {
+        if !self.is_done(stage) {
+            self.update(|s| { s.stages.insert(stage.to_string(), false); });
+        }
+    }
+
+    pub fn complete(&self, stage: &str) {
+        self.update(|s| { s.stages.insert(stage.to_string(), true); });
+    }
+
+    /// Runs `work` unless `stage` already completed, and records it when it succeeds.
+    pub fn run(&self, stage: &str, work: impl FnOnce() -> Result<(), String>) -> Result<(), String> {
+        if self.is_done(stage) {
+            return Ok(());
+        }
+        self.begin(stage);
+        work()?;
+        self.complete(stage);
+        Ok(())
+    }
+
+    pub fn version_info(&self) -> Option<VersionInfo> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner()).info.clone()
+    }
+
+    /// Marks the whole loader stage done with the profile it resolved.
+    pub fn complete_loader(&self, info: &VersionInfo) {
+        self.update(|s| s.info = Some(info.clone()));
+    }
+
+    pub fn mod_done(&self, url: &str) -> bool {
+        self.state.lock().unwrap_or_else(|e| e.into_inner()).mods.contains(url)
+    }
+
+    pub fn complete_mod(&self, url: &str) {
+        self.update(|s| { s.mods.insert(url.to_string()); });
+    }
+
+    /// Everything is prepared; the next prepare verifies from scratch again.
+    pub fn finish(self) {
+        let _ = fs::remove_file(&*self.path);
+    }
+}
+
+/// Drops whatever checkpoint `minecraft_dir` holds, e.g. in a copy prepared under another root.
+pub fn clear(minecraft_dir: &Path) {
+    let _ = fs::remove_file(minecraft_dir.join(CHECKPOINT_FILE));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn retry_resumes_only_the_same_recent_plan() {
+        let dir = tempfile::tempdir().unwrap();
+        let checkpoint = Checkpoint::open(dir.path(), "forge|1.20.1|pack", false);
+        checkpoint.run(ASSETS, || Ok(())).unwrap();
+        assert!(checkpoint.run(LIBRARIES, || Err("timeout".to_string())).is_err());
+        checkpoint.complete_mod("https://cdn.example.com/a.jar");
+
+        let retry = Checkpoint::open(dir.path(), "forge|1.20.1|pack", false);
+        assert!(retry.is_done(ASSETS));
+        assert!(retry.was_interrupted(LIBRARIES) && !retry.is_done(LIBRARIES));
+        assert!(retry.mod_done("https://cdn.example.com/a.jar"));
+        let mut ran = false;
+        retry.run(ASSETS, || { ran = true; Ok(()) }).unwrap();
+        assert!(!ran);
+
+        assert!(!Checkpoint::open(dir.path(), "forge|1.20.2|pack", false).is_done(ASSETS));
+        assert!(!Checkpoint::open(dir.path(), "forge|1.20.1|pack", true).is_done(ASSETS));
+        assert!(!Checkpoint::open_at(dir.path(), "forge|1.20.1|pack", false, crate::unix_now() + MAX_AGE_SECS).is_done(ASSETS));
+
+        retry.finish();
+        assert!(!dir.path().join(CHECKPOINT_FILE).exists());
+    }
+}
@@ -0,0 +1,159 @@
+use std::fs;
+use std::path::Path;
+use schemars::JsonSchema;
+use serde::Serialize;
+use super::models::AssetIndex;
+use super::url_resolver::mod_file_name;
+use super::version_store::LocalVersions;
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ComponentState {
+    Ready,
+    Missing,
+    /// Some files are on disk; `percent` says how many
+    Partial,
+    /// Present, but not what the instance currently asks for
+    Outdated,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ComponentStatus {
+    pub state: ComponentState,
+    pub percent: u8,
+}
+
+impl ComponentStatus {
+    pub fn ready() -> Self {
+        ComponentStatus { state: ComponentState::Ready, percent: 100 }
+    }
+
+    pub fn missing() -> Self {
+        ComponentStatus { state: ComponentState::Missing, percent: 0 }
+    }
+
+    fn from_counts(present: usize, total: usize, partial: ComponentState) -> Self {
+        match (present, total) {
+            (_, 0) => Self::ready(),
+            (0, _) => Self::missing(),
+            (p, t) if p >= t => Self::ready(),
+            (p, t) => ComponentStatus { state: partial, percent: (p * 100 / t) as u8 },
+        }
+    }
+}
+
+/// What is already on disk for an instance, checked without touching the network.
+#[derive(Serialize, Clone, Debug, JsonSchema)]
+pub struct InstanceReadiness {
+    pub ready: bool,
+    pub java: ComponentStatus,
+    pub client: ComponentStatus,
+    pub assets: ComponentStatus,
+    pub mods: ComponentStatus,
+    /// Player-added jars held back on a remote-managed instance (see `mod_scan::hold_player_mods`)
+    #[serde(rename = "pendingMods")]
+    pub pending_mods: Vec<String>,
+}
+
+/// Vanilla needs the instance's `client.jar`; loaders need their resolved profile json.
+pub fn client_status(minecraft_dir: &Path, versions_dir: &Path, version: &str, is_vanilla: bool) -> ComponentStatus {
+    let present = if is_vanilla {
+        minecraft_dir.join("client.jar").is_file()
+    } else {
+        LocalVersions::new(versions_dir).contains(version)
+    };
+    if present { ComponentStatus::ready() } else { ComponentStatus::missing() }
+}
+
+/// The local asset index of a version, found through the Mojang `<version>.json` every loader
+/// caches for its base game version.
+fn local_asset_index(assets_dir: &Path, versions_dir: &Path, version: &str) -> Option<AssetIndex> {
+    let index_id = LocalVersions::new(versions_dir).load(version).ok()??.asset_index?.id;
+    let json = fs::read_to_string(assets_dir.join("indexes").join(format!("{}.json", index_id))).ok()?;
+    serde_json::from_str(&json).ok()
+}
+
+fn has_object(assets_dir: &Path, hash: &str) -> bool {
+    hash.len() > 2 && assets_dir.join("objects").join(&hash[..2]).join(hash).is_file()
+}
+
+/// Share of the version's asset index objects already in `assets/objects`.
+pub fn asset_status(assets_dir: &Path, versions_dir: &Path, version: &str) -> ComponentStatus {
+    let Some(index) = local_asset_index(assets_dir, versions_dir, version) else { return ComponentStatus::missing(); };
+    let present = index.objects.values().filter(|o| has_object(assets_dir, &o.hash)).count();
+    ComponentStatus::from_counts(present, index.objects.len(), ComponentState::Partial)
+}
+
+/// Bytes of assets still to download; `None` until the asset index itself is on disk.
+pub fn missing_asset_bytes(assets_dir: &Path, versions_dir: &Path, version: &str) -> Option<u64> {
+    let index = local_asset_index(assets_dir, versions_dir, version)?;
+    Some(index.objects.values().filter(|o| !has_object(assets_dir, &o.hash)).map(|o| o.size).sum())
+}
+
+/// Rough size of a pending download, shown before starting it in data saver mode.
+#[derive(Serialize, Clone, Debug, Default, JsonSchema)]
+pub struct DownloadEstimate {
+    #[serde(rename = "modpackBytes")]
+    pub modpack_bytes: u64,
+    #[serde(rename = "javaBytes")]
+    pub java_bytes: u64,
+    #[serde(rename = "assetsBytes")]
+    pub assets_bytes: u64,
+    #[serde(rename = "totalBytes")]
+    pub total_bytes: u64,
+    /// Parts whose size is not known yet (e.g. "assets" before the index is downloaded)
+    pub unknown: Vec<String>,
+}
+
+/// Modpack zip plus every listed mod jar. Missing jars next to others mean the mod list changed
+/// since the last sync.
+pub fn mods_status(minecraft_dir: &Path, modpack_url: Option<&str>, mods: &[String]) -> ComponentStatus {
+    if modpack_url.map(|u| !u.is_empty()).unwrap_or(false) && !minecraft_dir.join("modpack.zip").is_file() {
+        return ComponentStatus::missing();
+    }
+    let mods_dir = minecraft_dir.join("mods");
+    let present = mods.iter().filter(|url| mods_dir.join(mod_file_name(url)).is_file()).count();
+    ComponentStatus::from_counts(present, mods.len(), ComponentState::Outdated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn asset_progress_counts_downloaded_objects() {
+        let dir = tempfile::tempdir().unwrap();
+        let (assets, versions) = (dir.path().join("assets"), dir.path().join("versions"));
+        assert_eq!(asset_status(&assets, &versions, "1.20.1").state, ComponentState::Missing);
+
+        fs::create_dir_all(versions.join("1.20.1")).unwrap();
+        let version = serde_json::json!({
+            "id": "1.20.1", "type": "release", "mainClass": "net.minecraft.client.main.Main", "libraries": [],
+            "assetIndex": {"id": "5", "sha1": "x", "size": 1, "totalSize": 5, "url": "https://example.com/5.json"}
+        });
+        fs::write(versions.join("1.20.1/1.20.1.json"), version.to_string()).unwrap();
+        fs::create_dir_all(assets.join("indexes")).unwrap();
+        let objects = (0..5).map(|i| (format!("file{}", i), serde_json::json!({"hash": format!("ab{:038}", i), "size": 1})));
+        let index = serde_json::json!({"objects": objects.collect::<serde_json::Map<_, _>>()});
+        fs::write(assets.join("indexes/5.json"), index.to_string()).unwrap();
+        fs::create_dir_all(assets.join("objects/ab")).unwrap();
+        for i in 0..4 {
+            fs::write(assets.join("objects/ab").join(format!("ab{:038}", i)), b"x").unwrap();
+        }
+
+        assert_eq!(asset_status(&assets, &versions, "1.20.1"), ComponentStatus { state: ComponentState::Partial, percent: 80 });
+        assert_eq!(missing_asset_bytes(&assets, &versions, "1.20.1"), Some(1));
+    }
+
+    #[test]
+    fn missing_mod_jars_are_reported_as_outdated() {
+        let dir = tempfile::tempdir().unwrap();
+        let mods = vec!["https://cdn.modrinth.com/a.jar".to_string(), "https://cdn.modrinth.com/b.jar".to_string()];
+        assert_eq!(mods_status(dir.path(), None, &mods).state, ComponentState::Missing);
+        fs::create_dir_all(dir.path().join("mods")).unwrap();
+        fs::write(dir.path().join("mods/a.jar"), b"x").unwrap();
+        assert_eq!(mods_status(dir.path(), None, &mods), ComponentStatus { state: ComponentState::Outdated, percent: 50 });
+        assert_eq!(mods_status(dir.path(), Some("https://example.com/pack.zip"), &[]).state, ComponentState::Missing);
+        assert_eq!(mods_status(dir.path(), None, &[]).state, ComponentState::Ready);
+    }
+}
@@ -0,0 +1,218 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use schemars::JsonSchema;
+use serde::Serialize;
+use super::downloader::download_game_file;
+use super::java::{get_java_path_for_major, get_required_java_version, download_java};
+use super::launch_logic::{resolve_complete_version_info, get_forge_recommended_version, download_forge_installer};
+
+const SERVER_MEMORY: &str = "-Xmx2G";
+const FABRIC_META: &str = "https://meta.fabricmc.net/v2/versions";
+
+/// What `provision_server` left in the target folder.
+#[derive(Serialize, Clone, Debug, JsonSchema)]
+pub struct ServerInstall {
+    pub path: String,
+    pub loader: String,
+    /// Script that starts the server with the Java the launcher picked for this version
+    #[serde(rename = "startScript")]
+    pub start_script: String,
+}
+
+pub fn start_script(java: &Path, args: &[String], windows: bool) -> String {
+    let quoted = std::iter::once(java.to_string_lossy().to_string())
+        .chain(args.iter().cloned())
+        .map(|a| if a.contains(' ') { format!("\"{}\"", a) } else { a })
+        .collect::<Vec<_>>()
+        .join(" ");
+    if windows {
+        format!("@echo off\r\ncd /d \"%~dp0\"\r\n{}\r\npause\r\n", quoted)
+    } else {
+        format!("#!/bin/sh\ncd \"$(dirname \"$0\")\"\nexec {} \"$@\"\n", quoted)
+    }
+}
+
+fn write_start_script(target_dir: &Path, java: &Path, args: &[String]) -> Result<PathBuf, String> {
+    let windows = cfg!(windows);
+    let path = target_dir.join(if windows { "start.bat" } else { "start.sh" });
+    fs::write(&path, start_script(java, args, windows)).map_err(|e| format!("No se pudo escribir {}: {}", path.display(), e))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let _ = fs::set_permissions(&path, fs::Permissions::from_mode(0o755));
+    }
+    Ok(path)
+}
+
+fn server_java(mc_version: &str) -> Result<PathBuf, String> {
+    let major = get_required_java_version(mc_version);
+    match get_java_path_for_major(major) {
+        Ok(p) => Ok(p),
+        Err(_) => download_java(major, None, None).map(PathBuf::from),
+    }
+}
+
+fn download_vanilla_server(base_path: &Path, mc_version: &str, target_dir: &Path) -> Result<(), String> {
+    let info = resolve_complete_version_info(mc_version, &base_path.join("versions"))?;
+    let server = info.downloads.and_then(|d| d.server)
+        .ok_or_else(|| format!("La versión {} no tiene servidor oficial", mc_version))?;
+    download_game_file(&server.url, &target_dir.join("server.jar"), Some(&server.sha1))
+}
+
+fn first_stable_version(list: &serde_json::Value, pointer: &str) -> Option<String> {
+    let items = list.as_array()?;
+    items.iter()
+        .find(|item| item.pointer(pointer).and_then(|v| v["stable"].as_bool()).unwrap_or(false))
+        .or_else(|| items.first())
+        .and_then(|item| item.pointer(pointer))
+        .and_then(|v| v["version"].as_str())
+        .map(str::to_string)
+}
+
+fn fetch_json(url: &str) -> Result<serde_json::Value, String> {
+    let client = reqwest::blocking::Client::builder()
+        .user_agent(crate::http::user_agent())
+        .build()
+        .map_err(|e| e.to_string())?;
+    crate::http::send(&client, client.get(crate::mirror::rewrite(url)))?
+        .json()
+        .map_err(|e| format!("Respuesta no válida de {}: {}", url, e))
+}
+
+/// Fabric's server launcher; it runs the vanilla `server.jar` downloaded next to it.
+fn install_fabric_server(mc_version: &str, target_dir: &Path) -> Result<String, String> {
+    let loader = first_stable_version(&fetch_json(&format!("{}/loader/{}", FABRIC_META, mc_version))?, "/loader")
+        .ok_or_else(|| format!("Fabric no está disponible para {}", mc_version))?;
+    let installer = first_stable_version(&fetch_json(&format!("{}/installer", FABRIC_META))?, "")
+        .ok_or("No se encontró el instalador de Fabric")?;
+    let jar = "fabric-server-launch.jar";
+    let url = format!("{}/loader/{}/{}/{}/server/jar", FABRIC_META, mc_version, loader, installer);
+    download_game_file(&url, &target_dir.join(jar), None)?;
+    Ok(jar.to_string())
+}
+
+/// Arguments for 1.17+ Forge servers, which start from `@`-files instead of a jar.
+fn forge_args_file(target_dir: &Path) -> Option<String> {
+    let name = if cfg!(windows) { "win_args.txt" } else { "unix_args.txt" };
+    let forge_dir = target_dir.join("libraries").join("net").join("minecraftforge").join("forge");
+    fs::read_dir(forge_dir).ok()?.flatten()
+        .map(|e| e.path().join(name))
+        .find(|p| p.is_file())
+        .and_then(|p| p.strip_prefix(target_dir).ok().map(|rel| rel.to_string_lossy().to_string()))
+}
+
+/// Legacy Forge servers ship a runnable `forge-<version>[-universal].jar`.
+fn forge_server_jar(target_dir: &Path) -> Option<String> {
+    fs::read_dir(target_dir).ok()?.flatten()
+        .map(|e| e.file_name().to_string_lossy().to_string())
+        .find(|name| name.starts_with("forge-") && name.ends_with(".jar") && !name.contains("installer"))
+}
+
+fn install_forge_server(base_path: &Path, mc_version: &str, target_dir: &Path, java: &Path) -> Result<Vec<String>, String> {
+    let forge_version = get_forge_recommended_version(mc_version)?;
+    let installer = download_forge_installer(base_path, mc_version, &forge_version)?;
+    let output = Command::new(java)
+        .arg("-jar")
+        .arg(&installer)
+        .arg("--installServer")
+        .arg(target_dir)
+        .current_dir(target_dir)
+        .output()
+        .map_err(|e| format!("Failed to run forge installer: {}", e))?;
+    if !output.status.success() {
+        return Err(format!("El instalador de Forge falló: {}", String::from_utf8_lossy(&output.stderr)));
+    }
+    if let Some(args_file) = forge_args_file(target_dir) {
+        let jvm_args = target_dir.join("user_jvm_args.txt");
+        if !fs::read_to_string(&jvm_args).unwrap_or_default().lines().any(|l| l.trim_start().starts_with("-Xmx")) {
+            let _ = fs::OpenOptions::new().create(true).append(true).open(&jvm_args)
+                .and_then(|mut f| std::io::Write::write_all(&mut f, format!("\n{}\n", SERVER_MEMORY).as_bytes()));
+        }
+        return Ok(vec!["@user_jvm_args.txt".to_string(), format!("@{}", args_file), "nogui".to_string()]);
+    }
+    let jar = forge_server_jar(target_dir).ok_or("El instalador de Forge no generó el jar del servidor")?;
+    Ok(jar_args(&jar))
+}
+
+fn jar_args(jar: &str) -> Vec<String> {
+    vec![SERVER_MEMORY.to_string(), "-jar".to_string(), jar.to_string(), "nogui".to_string()]
+}
+
+/// Sets up a ready-to-run server for `mc_version` in `target_dir`: server jar (vanilla, Fabric or
+/// Forge), accepted `eula.txt` and a start script.
+pub fn provision_server(base_path: &Path, mc_version: &str, target_dir: &Path, loader: &str) -> Result<ServerInstall, String> {
+    fs::create_dir_all(target_dir).map_err(|e| format!("No se pudo crear {}: {}", target_dir.display(), e))?;
+    let java = server_java(mc_version)?;
+    let loader = loader.trim().to_lowercase();
+    let args = match loader.as_str() {
+        "" | "vanilla" => {
+            download_vanilla_server(base_path, mc_version, target_dir)?;
+            jar_args("server.jar")
+        }
+        "fabric" => {
+            download_vanilla_server(base_path, mc_version, target_dir)?;
+            jar_args(&install_fabric_server(mc_version, target_dir)?)
+        }
+        "forge" => install_forge_server(base_path, mc_version, target_dir, &java)?,
+        other => return Err(format!("Loader de servidor no soportado: {}", other)),
+    };
+    // The admin provisioning the server accepts the EULA on the operator's behalf
+    fs::write(target_dir.join("eula.txt"), "eula=true\n").map_err(|e| e.to_string())?;
+    let script = write_start_script(target_dir, &java, &args)?;
+    Ok(ServerInstall {
+        path: target_dir.to_string_lossy().to_string(),
+        loader: if loader.is_empty() { "vanilla".to_string() } else { loader },
+        start_script: script.to_string_lossy().to_string(),
+    })
+}
+
+#[tauri::command]
+pub async fn download_server(password: String, version: String, target_dir: String, loader: Option<String>) -> Result<ServerInstall, String> {
+    crate::ensure_admin(password)?;
+    let base_path = PathBuf::from(crate::get_instances_dir());
+    tauri::async_runtime::spawn_blocking(move || {
+        provision_server(&base_path, version.trim(), Path::new(&target_dir), loader.as_deref().unwrap_or("vanilla"))
+    })
+    .await
+    .map_err(|e| format!("Task panicked: {}", e))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn start_scripts_quote_paths_with_spaces() {
+        let java = Path::new("/opt/java 17/bin/java");
+        let args = jar_args("server.jar");
+        assert_eq!(
+            start_script(java, &args, false),
+            "#!/bin/sh\ncd \"$(dirname \"$0\")\"\nexec \"/opt/java 17/bin/java\" -Xmx2G -jar server.jar nogui \"$@\"\n"
+        );
+        assert!(start_script(java, &args, true).ends_with("-jar server.jar nogui\r\npause\r\n"));
+    }
+
+    #[test]
+    fn fabric_versions_prefer_stable_entries() {
+        let loaders = serde_json::json!([
+            {"loader": {"version": "0.16.0-beta", "stable": false}},
+            {"loader": {"version": "0.15.11", "stable": true}}
+        ]);
+        assert_eq!(first_stable_version(&loaders, "/loader").as_deref(), Some("0.15.11"));
+        let installers = serde_json::json!([{"version": "1.0.1", "stable": true}]);
+        assert_eq!(first_stable_version(&installers, "").as_deref(), Some("1.0.1"));
+    }
+
+    #[test]
+    fn modern_forge_servers_start_from_args_files() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(forge_args_file(dir.path()), None);
+        let version_dir = dir.path().join("libraries/net/minecraftforge/forge/1.20.1-47.2.0");
+        fs::create_dir_all(&version_dir).unwrap();
+        let name = if cfg!(windows) { "win_args.txt" } else { "unix_args.txt" };
+        fs::write(version_dir.join(name), "").unwrap();
+        let expected = Path::new("libraries/net/minecraftforge/forge/1.20.1-47.2.0").join(name);
+        assert_eq!(forge_args_file(dir.path()), Some(expected.to_string_lossy().to_string()));
+    }
+}
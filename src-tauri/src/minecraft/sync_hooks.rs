@@ -0,0 +1,214 @@
+use rhai::{Engine, EvalAltResult, Scope};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// Scripts may run this many Rhai operations; a stray loop fails instead of hanging the sync.
+const MAX_OPERATIONS: u64 = 2_000_000;
+/// Largest file a script may read or patch, and largest string it may build.
+const MAX_FILE_BYTES: usize = 4 * 1024 * 1024;
+
+/// Rhai steps staff run around the content sync of an instance, from the remote catalog:
+/// `{"syncHooks": {"<instanceId>": {"preSync": "...", "postSync": "..."}}}`.
+#[derive(Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct SyncHooks {
+    /// Before the modpack and mods are synced
+    #[serde(rename = "preSync", alias = "pre_sync", default)]
+    pub pre_sync: Option<String>,
+    /// Once the modpack, mods and translation pack are in place
+    #[serde(rename = "postSync", alias = "post_sync", default)]
+    pub post_sync: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct HookCatalog {
+    #[serde(rename = "syncHooks", alias = "sync_hooks", default)]
+    sync_hooks: HashMap<String, SyncHooks>,
+}
+
+pub fn parse_hooks(body: &str) -> HashMap<String, SyncHooks> {
+    serde_json::from_str::<HookCatalog>(body).map(|c| c.sync_hooks).unwrap_or_default()
+}
+
+/// Hooks of `instance_id` in the catalog (or its cached copy). Fetches, so off the async runtime.
+pub fn for_instance(instance_id: &str) -> SyncHooks {
+    super::mod_scan::catalog_body()
+        .and_then(|body| parse_hooks(&body).remove(instance_id))
+        .unwrap_or_default()
+}
+
+/// A script path, relative to the game folder, as long as it stays inside it (symlinks included).
+fn sandboxed(root: &Path, path: &str) -> Result<PathBuf, String> {
+    Ok(crate::path_policy::join_inside(root, path)?)
+}
+
+fn read_limited(path: &Path) -> Result<String, String> {
+    let size = fs::metadata(path).map_err(|e| format!("{}: {}", path.display(), e))?.len();
+    if size > MAX_FILE_BYTES as u64 {
+        return Err(format!("{} es demasiado grande para un script", path.display()));
+    }
+    fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+/// Replaces the file instead of writing into it: mods jars and client.jar may be hard links
+/// into the shared stores, and an in-place write would change every instance using them.
+fn write_through_temp(path: &Path, text: &str) -> Result<(), String> {
+    crate::temp_files::write_atomic(path, |file| file.write_all(text.as_bytes()).map_err(|e| e.to_string()))
+}
+
+/// Rhai has no file, network or process access of its own (modules are compiled out); these
+/// are the only ways a script reaches the disk, and every path goes through `sandboxed`.
+fn engine(root: &Path, log: Arc<Mutex<Vec<String>>>) -> Engine {
+    let mut engine = Engine::new();
+    engine
+        .set_max_operations(MAX_OPERATIONS)
+        .set_max_call_levels(32)
+        .set_max_expr_depths(64, 32)
+        .set_max_string_size(MAX_FILE_BYTES)
+        .set_max_array_size(10_000)
+        .set_max_map_size(10_000);
+    engine.disable_symbol("eval");
+    engine.on_print(move |line| {
+        if let Ok(mut log) = log.lock() {
+            log.push(line.to_string());
+        }
+    });
+
+    let dir = root.to_path_buf();
+    engine.register_fn("exists", move |path: &str| -> Result<bool, Box<EvalAltResult>> {
+        Ok(sandboxed(&dir, path)?.exists())
+    });
+    let dir = root.to_path_buf();
+    engine.register_fn("read_file", move |path: &str| -> Result<String, Box<EvalAltResult>> {
+        Ok(read_limited(&sandboxed(&dir, path)?)?)
+    });
+    let dir = root.to_path_buf();
+    engine.register_fn("write_file", move |path: &str, text: &str| -> Result<(), Box<EvalAltResult>> {
+        let path = sandboxed(&dir, path)?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        Ok(write_through_temp(&path, text)?)
+    });
+    let dir = root.to_path_buf();
+    engine.register_fn("replace_in_file", move |path: &str, from: &str, to: &str| -> Result<i64, Box<EvalAltResult>> {
+        let path = sandboxed(&dir, path)?;
+        let text = read_limited(&path)?;
+        let count = if from.is_empty() { 0 } else { text.matches(from).count() };
+        if count > 0 {
+            write_through_temp(&path, &text.replace(from, to))?;
+        }
+        Ok(count as i64)
+    });
+    let dir = root.to_path_buf();
+    engine.register_fn("rename", move |from: &str, to: &str| -> Result<(), Box<EvalAltResult>> {
+        let (from, to) = (sandboxed(&dir, from)?, sandboxed(&dir, to)?);
+        if let Some(parent) = to.parent() {
+            fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        Ok(fs::rename(&from, &to).map_err(|e| format!("{}: {}", from.display(), e))?)
+    });
+    let dir = root.to_path_buf();
+    engine.register_fn("remove", move |path: &str| -> Result<bool, Box<EvalAltResult>> {
+        let path = sandboxed(&dir, path)?;
+        // Files only; a script never wipes a folder such as saves
+        if !path.is_file() {
+            return Ok(false);
+        }
+        fs::remove_file(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        Ok(true)
+    });
+    engine
+}
+
+/// Runs `script` against the game folder of `instance_id`. Returns what it printed.
+pub fn run(script: &str, minecraft_dir: &Path, instance_id: &str, stage: &str) -> Result<Vec<String>, String> {
+    let log = Arc::new(Mutex::new(Vec::new()));
+    let engine = engine(minecraft_dir, log.clone());
+    let mut scope = Scope::new();
+    scope.push_constant("instance_id", instance_id.to_string());
+    scope.push_constant("stage", stage.to_string());
+    engine.run_with_scope(&mut scope, script)
+        .map_err(|e| format!("El script {} de la instancia falló: {}", stage, e))?;
+    let lines = log.lock().map(|log| log.clone()).unwrap_or_default();
+    Ok(lines)
+}
+
+/// Runs the `stage` hook, if the instance has one. Returns what it printed.
+pub fn run_stage(hooks: &SyncHooks, stage: &str, minecraft_dir: &Path, instance_id: &str) -> Result<Vec<String>, String> {
+    let script = match stage {
+        "preSync" => hooks.pre_sync.as_deref(),
+        "postSync" => hooks.post_sync.as_deref(),
+        _ => None,
+    };
+    let Some(script) = script.filter(|s| !s.trim().is_empty()) else { return Ok(Vec::new()) };
+    run(script, minecraft_dir, instance_id, stage)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hooks_come_from_the_catalog_per_instance() {
+        let hooks = parse_hooks(r#"{"approved": [], "syncHooks": {"evento": {"postSync": "print(1)"}}}"#);
+        assert_eq!(hooks["evento"], SyncHooks { pre_sync: None, post_sync: Some("print(1)".to_string()) });
+        assert!(parse_hooks(r#"{"approved": []}"#).is_empty());
+        assert!(parse_hooks("[]").is_empty());
+    }
+
+    #[test]
+    fn scripts_stay_inside_the_game_folder() {
+        let dir = tempfile::tempdir().unwrap();
+        let minecraft = dir.path().join("minecraft");
+        fs::create_dir_all(minecraft.join("config")).unwrap();
+        fs::write(dir.path().join("secreto.txt"), "x").unwrap();
+
+        assert!(sandboxed(&minecraft, "config/nuevo/mod.toml").is_ok());
+        for escape in ["../secreto.txt", "config/../../secreto.txt", "/etc/passwd", "", "config/nuevo/../../../x"] {
+            assert!(sandboxed(&minecraft, escape).is_err(), "{} escaped", escape);
+        }
+        assert!(run(r#"read_file("../secreto.txt")"#, &minecraft, "evento", "preSync").is_err());
+        assert!(run("loop {}", &minecraft, "evento", "preSync").is_err());
+    }
+
+    #[test]
+    fn scripts_patch_configs() {
+        let dir = tempfile::tempdir().unwrap();
+        let minecraft = dir.path();
+        fs::create_dir_all(minecraft.join("config")).unwrap();
+        fs::write(minecraft.join("config").join("menu.toml"), "server = \"viejo.example:25565\"\n").unwrap();
+        fs::write(minecraft.join("options.txt.drk"), "lang:es_es\n").unwrap();
+
+        let script = r#"
+            let n = replace_in_file("config/menu.toml", "viejo.example", "nuevo.example");
+            if exists("options.txt.drk") { rename("options.txt.drk", "options.txt"); }
+            write_file("config/evento/id.txt", instance_id);
+            print(`${stage}: ${n}`);
+        "#;
+        let log = run(script, minecraft, "evento", "postSync").unwrap();
+        assert_eq!(log, vec!["postSync: 1".to_string()]);
+        assert_eq!(fs::read_to_string(minecraft.join("config").join("menu.toml")).unwrap(), "server = \"nuevo.example:25565\"\n");
+        assert!(minecraft.join("options.txt").is_file() && !minecraft.join("options.txt.drk").exists());
+        assert_eq!(fs::read_to_string(minecraft.join("config").join("evento").join("id.txt")).unwrap(), "evento");
+    }
+
+    #[test]
+    fn writes_leave_store_links_alone() {
+        let dir = tempfile::tempdir().unwrap();
+        let minecraft = dir.path().join("minecraft");
+        fs::create_dir_all(minecraft.join("mods")).unwrap();
+        let stored = dir.path().join("store.jar");
+        fs::write(&stored, "jar original").unwrap();
+        fs::hard_link(&stored, minecraft.join("mods").join("a.jar")).unwrap();
+        fs::hard_link(&stored, minecraft.join("mods").join("b.jar")).unwrap();
+
+        run(r#"replace_in_file("mods/a.jar", "original", "parcheado"); write_file("mods/b.jar", "otro");"#, &minecraft, "evento", "postSync").unwrap();
+        assert_eq!(fs::read_to_string(&stored).unwrap(), "jar original");
+        assert_eq!(fs::read_to_string(minecraft.join("mods").join("a.jar")).unwrap(), "jar parcheado");
+        assert_eq!(fs::read_to_string(minecraft.join("mods").join("b.jar")).unwrap(), "otro");
+    }
+}
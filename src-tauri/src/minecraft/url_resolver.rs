@@ -0,0 +1,293 @@
+use base64::Engine;
+use reqwest::blocking::Client;
+use sha1::{Digest, Sha1};
+use url::Url;
+
+/// Turns a file host share link into a URL that serves the raw file. Add new hosts by
+/// implementing this and listing the type in `resolvers()`.
+pub trait UrlResolver {
+    fn handles(&self, url: &Url) -> bool;
+    fn resolve(&self, url: &Url, client: &Client) -> Result<String, String>;
+}
+
+fn host_is(url: &Url, domain: &str) -> bool {
+    url.host_str()
+        .map(|h| h == domain || h.ends_with(&format!(".{}", domain)))
+        .unwrap_or(false)
+}
+
+/// Dropbox serves an HTML preview unless `dl=1`.
+struct Dropbox;
+
+impl UrlResolver for Dropbox {
+    fn handles(&self, url: &Url) -> bool {
+        host_is(url, "dropbox.com")
+    }
+
+    fn resolve(&self, url: &Url, _client: &Client) -> Result<String, String> {
+        let mut direct = url.clone();
+        let pairs = url.query_pairs()
+            .filter(|(k, _)| k != "dl")
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect::<Vec<_>>();
+        direct.query_pairs_mut().clear().extend_pairs(pairs).append_pair("dl", "1");
+        Ok(direct.to_string())
+    }
+}
+
+/// Google Drive `file/d/<id>/view`, `open?id=` and `uc?id=` links. Files over ~100MB answer
+/// with a "can't scan for viruses" page whose form carries the confirm token.
+struct GoogleDrive;
+
+fn drive_file_id(url: &Url) -> Option<String> {
+    let segments = url.path_segments()?.collect::<Vec<_>>();
+    if let Some(pos) = segments.iter().position(|s| *s == "d") {
+        return segments.get(pos + 1).filter(|id| !id.is_empty()).map(|id| id.to_string());
+    }
+    url.query_pairs().find(|(k, _)| k == "id").map(|(_, v)| v.to_string())
+}
+
+/// `<input type="hidden" name=".." value="..">` pairs of an HTML page.
+fn hidden_inputs(html: &str) -> Vec<(String, String)> {
+    let attr = |tag: &str, name: &str| {
+        let start = tag.find(&format!("{}=\"", name))? + name.len() + 2;
+        let end = tag[start..].find('"')? + start;
+        Some(tag[start..end].to_string())
+    };
+    html.split("<input")
+        .skip(1)
+        .filter_map(|rest| rest.split('>').next())
+        .filter(|tag| tag.contains("type=\"hidden\""))
+        .filter_map(|tag| Some((attr(tag, "name")?, attr(tag, "value").unwrap_or_default())))
+        .collect()
+}
+
+fn drive_confirm_url(id: &str, html: &str) -> String {
+    let mut url = Url::parse("https://drive.usercontent.google.com/download").expect("static url");
+    let inputs = hidden_inputs(html);
+    if inputs.iter().any(|(k, _)| k == "confirm") {
+        url.query_pairs_mut().extend_pairs(inputs);
+    } else {
+        url.query_pairs_mut().append_pair("id", id).append_pair("export", "download").append_pair("confirm", "t");
+    }
+    url.to_string()
+}
+
+impl UrlResolver for GoogleDrive {
+    fn handles(&self, url: &Url) -> bool {
+        host_is(url, "drive.google.com") || host_is(url, "docs.google.com")
+    }
+
+    fn resolve(&self, url: &Url, client: &Client) -> Result<String, String> {
+        let id = drive_file_id(url).ok_or("Google Drive link without a file id")?;
+        let direct = format!("https://drive.google.com/uc?export=download&id={}", id);
+        let resp = client.get(&direct).send().map_err(|e| format!("Network error: {}", e))?;
+        let is_html = resp.headers().get(reqwest::header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .map(|v| v.starts_with("text/html"))
+            .unwrap_or(false);
+        if !is_html {
+            return Ok(direct);
+        }
+        let html = resp.text().map_err(|e| e.to_string())?;
+        if html.contains("ServiceLogin") {
+            return Err("El archivo de Google Drive no es público".to_string());
+        }
+        Ok(drive_confirm_url(&id, &html))
+    }
+}
+
+/// OneDrive personal links go through the shares API (`u!` + base64url of the link);
+/// SharePoint/business links accept `download=1`.
+struct OneDrive;
+
+fn onedrive_share_url(link: &str) -> String {
+    let encoded = base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(link.as_bytes());
+    format!("https://api.onedrive.com/v1.0/shares/u!{}/root/content", encoded)
+}
+
+impl UrlResolver for OneDrive {
+    fn handles(&self, url: &Url) -> bool {
+        host_is(url, "1drv.ms") || host_is(url, "onedrive.live.com") || host_is(url, "sharepoint.com")
+    }
+
+    fn resolve(&self, url: &Url, _client: &Client) -> Result<String, String> {
+        if host_is(url, "sharepoint.com") {
+            let mut direct = url.clone();
+            direct.query_pairs_mut().append_pair("download", "1");
+            return Ok(direct.to_string());
+        }
+        Ok(onedrive_share_url(url.as_str()))
+    }
+}
+
+/// Mediafire file pages link the real file from the "Download" button.
+struct Mediafire;
+
+fn mediafire_download_link(html: &str) -> Option<String> {
+    let button = html.find("id=\"downloadButton\"")?;
+    let tag_start = html[..button].rfind('<')?;
+    let tag = &html[tag_start..];
+    let start = tag.find("href=\"")? + 6;
+    let end = tag[start..].find('"')? + start;
+    Some(tag[start..end].replace("&amp;", "&"))
+}
+
+impl UrlResolver for Mediafire {
+    fn handles(&self, url: &Url) -> bool {
+        host_is(url, "mediafire.com") && !url.host_str().unwrap_or("").starts_with("download")
+    }
+
+    fn resolve(&self, url: &Url, client: &Client) -> Result<String, String> {
+        let html = client.get(url.as_str()).send()
+            .and_then(|r| r.text())
+            .map_err(|e| format!("Network error: {}", e))?;
+        mediafire_download_link(&html).ok_or_else(|| "No se encontró el enlace de descarga de Mediafire".to_string())
+    }
+}
+
+/// MEGA encrypts files client-side with the key in the URL fragment, so there is no plain
+/// download URL to hand to the downloader.
+struct Mega;
+
+impl UrlResolver for Mega {
+    fn handles(&self, url: &Url) -> bool {
+        host_is(url, "mega.nz") || host_is(url, "mega.co.nz")
+    }
+
+    fn resolve(&self, _url: &Url, _client: &Client) -> Result<String, String> {
+        Err("Los enlaces de MEGA están cifrados y no se pueden descargar directamente; usa Drive, Dropbox u OneDrive".to_string())
+    }
+}
+
+fn resolvers() -> Vec<Box<dyn UrlResolver>> {
+    vec![Box::new(Dropbox), Box::new(GoogleDrive), Box::new(OneDrive), Box::new(Mediafire), Box::new(Mega)]
+}
+
+/// Direct-download URL for modpack/mod links. Unknown hosts are returned unchanged.
+pub fn resolve_download_url(raw: &str) -> Result<String, String> {
+    let url = match Url::parse(raw.trim()) {
+        Ok(url) => url,
+        Err(_) => return Ok(raw.to_string()),
+    };
+    match resolvers().into_iter().find(|r| r.handles(&url)) {
+        Some(resolver) => {
+            let client = Client::builder()
+                .user_agent(crate::http::user_agent())
+                .timeout(std::time::Duration::from_secs(30))
+                .build()
+                .map_err(|e| e.to_string())?;
+            resolver.resolve(&url, &client)
+        }
+        None => Ok(raw.to_string()),
+    }
+}
+
+/// File name for a downloaded mod. Share links rarely end in the jar name, so those get a
+/// stable name derived from the link instead of `view?usp=sharing`.
+pub fn mod_file_name(raw: &str) -> String {
+    let last = Url::parse(raw)
+        .ok()
+        .and_then(|u| u.path_segments().and_then(|mut s| s.next_back().map(|l| l.to_string())))
+        .unwrap_or_else(|| raw.rsplit(['/', '\\']).next().unwrap_or("").to_string());
+    if last.to_lowercase().ends_with(".jar") {
+        return last;
+    }
+    let hash = hex::encode(Sha1::digest(raw.as_bytes()));
+    format!("mod-{}.jar", &hash[..12])
+}
+
+/// Same as `mod_file_name`, for resource pack zips.
+pub fn pack_file_name(raw: &str) -> String {
+    let last = Url::parse(raw)
+        .ok()
+        .and_then(|u| u.path_segments().and_then(|mut s| s.next_back().map(|l| l.to_string())))
+        .unwrap_or_else(|| raw.rsplit(['/', '\\']).next().unwrap_or("").to_string());
+    if last.to_lowercase().ends_with(".zip") {
+        return last;
+    }
+    let hash = hex::encode(Sha1::digest(raw.as_bytes()));
+    format!("pack-{}.zip", &hash[..12])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn url(s: &str) -> Url {
+        Url::parse(s).unwrap()
+    }
+
+    #[test]
+    fn dropbox_forces_dl_1() {
+        let client = Client::new();
+        assert_eq!(
+            Dropbox.resolve(&url("https://www.dropbox.com/s/abc/pack.zip?dl=0"), &client).unwrap(),
+            "https://www.dropbox.com/s/abc/pack.zip?dl=1"
+        );
+        assert_eq!(
+            Dropbox.resolve(&url("https://www.dropbox.com/scl/fi/x/pack.zip?rlkey=k"), &client).unwrap(),
+            "https://www.dropbox.com/scl/fi/x/pack.zip?rlkey=k&dl=1"
+        );
+    }
+
+    #[test]
+    fn drive_ids_are_extracted_from_all_link_shapes() {
+        for link in [
+            "https://drive.google.com/file/d/1AbC_dE-f/view?usp=sharing",
+            "https://drive.google.com/open?id=1AbC_dE-f",
+            "https://drive.google.com/uc?export=download&id=1AbC_dE-f",
+        ] {
+            assert_eq!(drive_file_id(&url(link)).as_deref(), Some("1AbC_dE-f"), "{}", link);
+        }
+    }
+
+    #[test]
+    fn drive_virus_scan_page_yields_confirm_url() {
+        let html = r#"<form id="download-form" action="https://drive.usercontent.google.com/download" method="get">
+            <input type="submit" id="uc-download-link" value="Download anyway">
+            <input type="hidden" name="id" value="1AbC">
+            <input type="hidden" name="export" value="download">
+            <input type="hidden" name="confirm" value="t">
+            <input type="hidden" name="uuid" value="1234-abcd"></form>"#;
+        assert_eq!(
+            drive_confirm_url("1AbC", html),
+            "https://drive.usercontent.google.com/download?id=1AbC&export=download&confirm=t&uuid=1234-abcd"
+        );
+        assert_eq!(
+            drive_confirm_url("1AbC", "<html></html>"),
+            "https://drive.usercontent.google.com/download?id=1AbC&export=download&confirm=t"
+        );
+    }
+
+    #[test]
+    fn onedrive_links_use_shares_api() {
+        assert_eq!(
+            onedrive_share_url("https://1drv.ms/u/s!AqXyz"),
+            "https://api.onedrive.com/v1.0/shares/u!aHR0cHM6Ly8xZHJ2Lm1zL3UvcyFBcVh5eg/root/content"
+        );
+    }
+
+    #[test]
+    fn mediafire_button_link_is_found() {
+        let html = r#"<a class="input popsok" aria-label="Download file" href="https://download2390.mediafire.com/abc/pack.zip?a=1&amp;b=2" id="downloadButton">"#;
+        assert_eq!(
+            mediafire_download_link(html).as_deref(),
+            Some("https://download2390.mediafire.com/abc/pack.zip?a=1&b=2")
+        );
+    }
+
+    #[test]
+    fn mega_and_unknown_hosts() {
+        assert!(resolve_download_url("https://mega.nz/file/abc#key").is_err());
+        assert_eq!(resolve_download_url("https://cdn.example.com/pack.zip").unwrap(), "https://cdn.example.com/pack.zip");
+    }
+
+    #[test]
+    fn mod_names_fall_back_to_stable_hash() {
+        assert_eq!(mod_file_name("https://cdn.modrinth.com/data/x/sodium-0.5.jar"), "sodium-0.5.jar");
+        let name = mod_file_name("https://drive.google.com/file/d/1AbC/view?usp=sharing");
+        assert!(name.starts_with("mod-") && name.ends_with(".jar"));
+        assert_eq!(name, mod_file_name("https://drive.google.com/file/d/1AbC/view?usp=sharing"));
+    }
+}
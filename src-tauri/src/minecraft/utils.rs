@@ -1,6 +1,8 @@
 use std::env;
-use std::path::Path;
+use std::path::{Component, Path, PathBuf};
+use std::collections::HashMap;
 use std::fs::{self, File};
+use serde::{Deserialize, Serialize};
 use zip::ZipArchive;
 use crate::minecraft::models::Rule;
 use crate::auth::MinecraftProfile;
@@ -23,6 +25,108 @@ pub fn get_arch() -> &'static str {
     }
 }
 
+/// Maven coordinate `group:artifact:version[:classifier][@ext]`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MavenName {
+    pub group: String,
+    pub artifact: String,
+    pub version: String,
+    pub classifier: Option<String>,
+    pub ext: String,
+}
+
+pub fn parse_maven_name(name: &str) -> Option<MavenName> {
+    let mut parts = name.split('@');
+    let main = parts.next()?;
+    let ext = parts.next().unwrap_or("jar").to_string();
+    let items: Vec<&str> = main.split(':').collect();
+    if items.len() < 3 {
+        return None;
+    }
+    let classifier = if items.len() > 3 { Some(items[3].to_string()) } else { None };
+    Some(MavenName {
+        group: items[0].to_string(),
+        artifact: items[1].to_string(),
+        version: items[2].to_string(),
+        classifier,
+        ext,
+    })
+}
+
+/// Relative repository path, e.g. `org/ow2/asm/asm/9.5/asm-9.5.jar`.
+pub fn maven_path(maven: &MavenName) -> Option<String> {
+    let group_path = maven.group.replace('.', "/");
+    let mut filename = format!("{}-{}", maven.artifact, maven.version);
+    if let Some(classifier) = &maven.classifier {
+        if !classifier.is_empty() {
+            filename = format!("{}-{}", filename, classifier);
+        }
+    }
+    filename = format!("{}.{}", filename, maven.ext);
+    Some(format!(
+        "{}/{}/{}/{}",
+        group_path, maven.artifact, maven.version, filename
+    ))
+}
+
+/// `(year, month, day)` in UTC of a unix time, without pulling in a date crate.
+pub fn utc_date(secs: u64) -> (i64, u32, u32) {
+    // Days to civil date (Howard Hinnant's algorithm)
+    let days = (secs / 86_400) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let doe = days.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = (if mp < 10 { mp + 3 } else { mp - 9 }) as u32;
+    (yoe + era * 400 + i64::from(month <= 2), month, day)
+}
+
+pub fn ensure_trailing_slash(value: &str) -> String {
+    if value.ends_with('/') {
+        value.to_string()
+    } else {
+        format!("{}/", value)
+    }
+}
+
+/// Path as it should be handed to the JVM. The java launcher decodes argfiles and (on Windows)
+/// its command line with the platform charset rather than UTF-8, so an absolute path under a
+/// profile like `C:\Users\José María` can reach the JVM mangled. Non-ASCII paths are rewritten
+/// relative to `work_dir` (the process working directory), which keeps the user folder out of
+/// the argument entirely; ASCII paths are left absolute.
+pub fn jvm_path_arg(path: &Path, work_dir: &Path) -> PathBuf {
+    if path.to_string_lossy().is_ascii() {
+        return path.to_path_buf();
+    }
+    relative_path(path, work_dir).unwrap_or_else(|| path.to_path_buf())
+}
+
+/// `path` relative to `base`, or `None` when they don't share a root (e.g. different drives).
+pub fn relative_path(path: &Path, base: &Path) -> Option<PathBuf> {
+    let path_parts = path.components().filter(|c| *c != Component::CurDir).collect::<Vec<_>>();
+    let base_parts = base.components().filter(|c| *c != Component::CurDir).collect::<Vec<_>>();
+    if path.is_absolute() != base.is_absolute() || path_parts.first() != base_parts.first() {
+        return None;
+    }
+    let common = path_parts.iter().zip(&base_parts).take_while(|(a, b)| a == b).count();
+    if base_parts[common..].contains(&Component::ParentDir) {
+        return None;
+    }
+    let mut relative = PathBuf::new();
+    for _ in common..base_parts.len() {
+        relative.push("..");
+    }
+    for part in &path_parts[common..] {
+        relative.push(part.as_os_str());
+    }
+    if relative.as_os_str().is_empty() {
+        relative.push(".");
+    }
+    Some(relative)
+}
+
 pub fn check_rules(rules: &Option<Vec<Rule>>) -> bool {
     if let Some(rules) = rules {
         if rules.is_empty() {
@@ -58,13 +162,15 @@ pub fn check_rules(rules: &Option<Vec<Rule>>) -> bool {
     }
 }
 
-pub fn extract_natives(jar_path: &Path, target_dir: &Path) -> Result<(), String> {
+/// Extracts a natives jar flat into `target_dir`, returning the written file names.
+pub fn extract_natives(jar_path: &Path, target_dir: &Path) -> Result<Vec<String>, String> {
     if !target_dir.exists() {
         fs::create_dir_all(target_dir).map_err(|e| e.to_string())?;
     }
 
     let file = File::open(jar_path).map_err(|e| format!("Failed to open natives jar: {}", e))?;
     let mut archive = ZipArchive::new(file).map_err(|e| format!("Failed to open zip: {}", e))?;
+    let mut extracted = Vec::new();
 
     for i in 0..archive.len() {
         let mut file = archive.by_index(i).map_err(|e| e.to_string())?;
@@ -80,14 +186,90 @@ pub fn extract_natives(jar_path: &Path, target_dir: &Path) -> Result<(), String>
             let out_path = target_dir.join(fname);
             
             // Overwrite existing
-            let mut out_file = File::create(&out_path).map_err(|e| e.to_string())?;
-            std::io::copy(&mut file, &mut out_file).map_err(|e| e.to_string())?;
+            let mut out_file = File::create(&out_path).map_err(|e| natives_write_error(&out_path, e))?;
+            std::io::copy(&mut file, &mut out_file).map_err(|e| natives_write_error(&out_path, e))?;
+            extracted.push(fname.to_string_lossy().to_string());
+        }
+    }
+
+    Ok(extracted)
+}
+
+const NATIVES_STAMP_FILE: &str = ".natives-sha1.json";
+
+#[derive(Serialize, Deserialize, Default)]
+struct NativesStamp {
+    sha1: String,
+    files: Vec<String>,
+}
+
+/// Like `extract_natives`, but skips the jar when the same SHA1 was already extracted into
+/// `target_dir` and its files are still there. Returns whether anything was written.
+pub fn extract_natives_if_changed(jar_path: &Path, target_dir: &Path) -> Result<bool, String> {
+    let jar_sha1 = super::downloader::file_sha1(jar_path)
+        .map_err(|e| format!("Failed to hash natives jar {}: {}", jar_path.to_string_lossy(), e))?;
+    let jar_name = jar_path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    let stamp_path = target_dir.join(NATIVES_STAMP_FILE);
+    let mut stamps: HashMap<String, NativesStamp> = fs::read_to_string(&stamp_path)
+        .ok()
+        .and_then(|c| serde_json::from_str(&c).ok())
+        .unwrap_or_default();
+
+    if let Some(stamp) = stamps.get(&jar_name) {
+        if stamp.sha1 == jar_sha1 && stamp.files.iter().all(|f| target_dir.join(f).exists()) {
+            return Ok(false);
         }
     }
 
-    Ok(())
+    let files = extract_natives(jar_path, target_dir)?;
+    stamps.insert(jar_name, NativesStamp { sha1: jar_sha1, files });
+    let json = serde_json::to_string_pretty(&stamps).map_err(|e| e.to_string())?;
+    fs::write(&stamp_path, json).map_err(|e| e.to_string())?;
+    Ok(true)
+}
+
+/// The game keeps its dlls open while running, so a failed write there almost always means
+/// another Minecraft process is still alive. Say so instead of a bare OS error.
+fn natives_write_error(path: &Path, e: std::io::Error) -> String {
+    // ERROR_SHARING_VIOLATION / ERROR_LOCK_VIOLATION on Windows
+    let locked = (get_os_name() == "windows" && matches!(e.raw_os_error(), Some(32) | Some(33)))
+        || e.kind() == std::io::ErrorKind::PermissionDenied;
+    if locked {
+        format!(
+            "Native library {} is locked by another process (is Minecraft still running?). Close the game and try again.",
+            path.to_string_lossy()
+        )
+    } else {
+        format!("Failed to write native library {}: {}", path.to_string_lossy(), e)
+    }
+}
+
+/// `natives/<version_id>/` inside the instance. Each version gets its own folder so dlls from
+/// different versions/loaders never mix.
+pub fn natives_dir_for(instance_minecraft_dir: &Path, version_id: &str) -> PathBuf {
+    instance_minecraft_dir.join("natives").join(version_id)
+}
+
+/// Creates the natives folder for `version_id` and removes whatever other versions (or the old
+/// flat `natives/` layout) left behind.
+pub fn reset_natives_dir(instance_minecraft_dir: &Path, version_id: &str) -> Result<PathBuf, String> {
+    let root = instance_minecraft_dir.join("natives");
+    let target = natives_dir_for(instance_minecraft_dir, version_id);
+    if let Ok(entries) = fs::read_dir(&root) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path == target {
+                continue;
+            }
+            let removed = if path.is_dir() { fs::remove_dir_all(&path) } else { fs::remove_file(&path) };
+            removed.map_err(|e| natives_write_error(&path, e))?;
+        }
+    }
+    fs::create_dir_all(&target).map_err(|e| e.to_string())?;
+    Ok(target)
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn replace_vars(
     arg: &str,
     auth: &MinecraftProfile,
@@ -128,3 +310,224 @@ pub fn replace_vars(
     
     result
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn utc_dates_follow_the_calendar() {
+        assert_eq!(utc_date(0), (1970, 1, 1));
+        assert_eq!(utc_date(1_709_210_096), (2024, 2, 29));
+        assert_eq!(utc_date(1_735_689_599), (2024, 12, 31));
+    }
+
+    fn rules(json: serde_json::Value) -> Option<Vec<Rule>> {
+        Some(serde_json::from_value(json).unwrap())
+    }
+
+    fn other_os() -> &'static str {
+        if get_os_name() == "windows" { "linux" } else { "windows" }
+    }
+
+    fn profile(access_token: &str) -> MinecraftProfile {
+        MinecraftProfile {
+            id: "uuid-123".into(),
+            name: "Steve".into(),
+            access_token: access_token.into(),
+            has_entitlement: true,
+        }
+    }
+
+    #[test]
+    fn rules_absent_or_empty_allow() {
+        assert!(check_rules(&None));
+        assert!(check_rules(&Some(Vec::new())));
+    }
+
+    #[test]
+    fn rules_last_matching_rule_wins() {
+        let allow_then_disallow_here = rules(serde_json::json!([
+            {"action": "allow"},
+            {"action": "disallow", "os": {"name": get_os_name()}}
+        ]));
+        assert!(!check_rules(&allow_then_disallow_here));
+
+        let allow_then_disallow_elsewhere = rules(serde_json::json!([
+            {"action": "allow"},
+            {"action": "disallow", "os": {"name": other_os()}}
+        ]));
+        assert!(check_rules(&allow_then_disallow_elsewhere));
+    }
+
+    #[test]
+    fn rules_only_allowing_other_os_disallow() {
+        let only_other = rules(serde_json::json!([{"action": "allow", "os": {"name": other_os()}}]));
+        assert!(!check_rules(&only_other));
+        let only_here = rules(serde_json::json!([{"action": "allow", "os": {"name": get_os_name()}}]));
+        assert!(check_rules(&only_here));
+    }
+
+    #[test]
+    fn rules_respect_arch() {
+        let same_arch = rules(serde_json::json!([{"action": "allow", "os": {"name": get_os_name(), "arch": get_arch()}}]));
+        assert!(check_rules(&same_arch));
+        let other_arch = rules(serde_json::json!([{"action": "allow", "os": {"arch": "not-an-arch"}}]));
+        assert!(!check_rules(&other_arch));
+    }
+
+    #[test]
+    fn replace_vars_fills_every_placeholder() {
+        let template = [
+            "${auth_player_name}", "${version_name}", "${game_directory}", "${assets_root}",
+            "${assets_index_name}", "${auth_uuid}", "${auth_access_token}", "${user_type}",
+            "${version_type}", "${natives_directory}", "${launcher_name}", "${launcher_version}",
+            "${library_directory}", "${classpath_separator}", "${resolution_width}", "${resolution_height}",
+        ].join("|");
+        let result = replace_vars(
+            &template,
+            &profile("token-abc"),
+            "1.20.1",
+            &PathBuf::from("/mc/assets"),
+            &PathBuf::from("/mc/game"),
+            "5",
+            &PathBuf::from("/mc/natives"),
+            &PathBuf::from("/mc/libraries"),
+        );
+        let sep = if get_os_name() == "windows" { ";" } else { ":" };
+        let expected = [
+            "Steve", "1.20.1", "/mc/game", "/mc/assets", "5", "uuid-123", "token-abc", "msa",
+            "release", "/mc/natives", "DrkLauncher", "1.0", "/mc/libraries", sep, "854", "480",
+        ].join("|");
+        assert_eq!(result, expected);
+        assert!(!result.contains("${"));
+    }
+
+    #[test]
+    fn replace_vars_offline_user_type_is_legacy() {
+        let dir = PathBuf::from("/mc");
+        let result = replace_vars("${user_type}", &profile("offline"), "1.8.9", &dir, &dir, "1.8", &dir, &dir);
+        assert_eq!(result, "legacy");
+    }
+
+    #[test]
+    fn maven_parsing_handles_classifier_and_extension() {
+        assert_eq!(parse_maven_name("net.fabricmc:fabric-loader"), None);
+
+        let plain = parse_maven_name("org.ow2.asm:asm:9.5").unwrap();
+        assert_eq!(plain.classifier, None);
+        assert_eq!(plain.ext, "jar");
+        assert_eq!(maven_path(&plain).unwrap(), "org/ow2/asm/asm/9.5/asm-9.5.jar");
+
+        let native = parse_maven_name("org.lwjgl:lwjgl:3.3.1:natives-windows").unwrap();
+        assert_eq!(native.classifier.as_deref(), Some("natives-windows"));
+        assert_eq!(maven_path(&native).unwrap(), "org/lwjgl/lwjgl/3.3.1/lwjgl-3.3.1-natives-windows.jar");
+
+        let zip = parse_maven_name("net.minecraftforge:forge:1.20.1-47.2.0:installer@zip").unwrap();
+        assert_eq!(zip.ext, "zip");
+        assert_eq!(
+            maven_path(&zip).unwrap(),
+            "net/minecraftforge/forge/1.20.1-47.2.0/forge-1.20.1-47.2.0-installer.zip"
+        );
+    }
+
+    #[test]
+    fn trailing_slash_is_added_once() {
+        assert_eq!(ensure_trailing_slash("https://maven.minecraftforge.net"), "https://maven.minecraftforge.net/");
+        assert_eq!(ensure_trailing_slash("https://libraries.minecraft.net/"), "https://libraries.minecraft.net/");
+    }
+
+    #[test]
+    fn relative_path_walks_up_and_down() {
+        let base = Path::new("/data/Eventos DRK/instances/pack/minecraft");
+        assert_eq!(
+            relative_path(Path::new("/data/Eventos DRK/instances/pack/libraries/a.jar"), base).unwrap(),
+            PathBuf::from("../libraries/a.jar")
+        );
+        assert_eq!(relative_path(base, base).unwrap(), PathBuf::from("."));
+        assert_eq!(relative_path(Path::new("relative/a.jar"), base), None);
+    }
+
+    #[test]
+    fn jvm_path_arg_keeps_ascii_paths_absolute() {
+        let path = Path::new("/data/Eventos DRK/libraries/a.jar");
+        assert_eq!(jvm_path_arg(path, Path::new("/data/Eventos DRK/instances/x")), path);
+    }
+
+    #[test]
+    fn jvm_path_arg_hides_non_ascii_profile_folder() {
+        let home = tempfile::tempdir().unwrap();
+        let root = home.path().join("José María").join("Eventos DRK");
+        let work_dir = root.join("instances").join("pack").join("minecraft");
+        let jar = root.join("instances").join("pack").join("libraries").join("org").join("lwjgl 3.jar");
+        fs::create_dir_all(&work_dir).unwrap();
+        fs::create_dir_all(jar.parent().unwrap()).unwrap();
+        fs::write(&jar, b"jar").unwrap();
+
+        let arg = jvm_path_arg(&jar, &work_dir);
+        assert!(arg.is_relative());
+        assert!(arg.to_string_lossy().is_ascii());
+        assert_eq!(fs::read(work_dir.join(&arg)).unwrap(), b"jar");
+    }
+
+    fn write_natives_jar(jar: &Path, dll: &[u8]) {
+        use std::io::Write;
+
+        let mut zip = zip::ZipWriter::new(File::create(jar).unwrap());
+        zip.start_file("windows/x64/org/lwjgl/lwjgl.dll", zip::write::FileOptions::default()).unwrap();
+        zip.write_all(dll).unwrap();
+        zip.start_file("META-INF/MANIFEST.MF", zip::write::FileOptions::default()).unwrap();
+        zip.write_all(b"Manifest-Version: 1.0").unwrap();
+        zip.finish().unwrap();
+    }
+
+    #[test]
+    fn natives_extract_into_non_ascii_dir() {
+        let home = tempfile::tempdir().unwrap();
+        let root = home.path().join("Peña Ñandú");
+        fs::create_dir_all(&root).unwrap();
+        let jar = root.join("lwjgl-natives-windows.jar");
+        write_natives_jar(&jar, b"dll");
+
+        let natives_dir = root.join("instances").join("José María").join("natives");
+        assert_eq!(extract_natives(&jar, &natives_dir).unwrap(), vec!["lwjgl.dll".to_string()]);
+        assert_eq!(fs::read(natives_dir.join("lwjgl.dll")).unwrap(), b"dll");
+        assert!(!natives_dir.join("MANIFEST.MF").exists());
+    }
+
+    #[test]
+    fn natives_extraction_skips_unchanged_jars() {
+        let dir = tempfile::tempdir().unwrap();
+        let jar = dir.path().join("lwjgl-natives-windows.jar");
+        let natives_dir = dir.path().join("natives").join("1.12.2");
+        write_natives_jar(&jar, b"v1");
+
+        assert!(extract_natives_if_changed(&jar, &natives_dir).unwrap());
+        assert!(!extract_natives_if_changed(&jar, &natives_dir).unwrap());
+
+        // A deleted dll or a different jar forces a fresh extraction
+        fs::remove_file(natives_dir.join("lwjgl.dll")).unwrap();
+        assert!(extract_natives_if_changed(&jar, &natives_dir).unwrap());
+        write_natives_jar(&jar, b"v2");
+        assert!(extract_natives_if_changed(&jar, &natives_dir).unwrap());
+        assert_eq!(fs::read(natives_dir.join("lwjgl.dll")).unwrap(), b"v2");
+    }
+
+    #[test]
+    fn natives_reset_keeps_only_current_version() {
+        let dir = tempfile::tempdir().unwrap();
+        let instance = dir.path().join("minecraft");
+        let old = natives_dir_for(&instance, "1.12.2");
+        fs::create_dir_all(&old).unwrap();
+        fs::write(old.join("lwjgl64.dll"), b"old").unwrap();
+        fs::write(instance.join("natives").join("OpenAL64.dll"), b"flat layout").unwrap();
+        let current = natives_dir_for(&instance, "1.16.5");
+        fs::create_dir_all(&current).unwrap();
+        fs::write(current.join("lwjgl64.dll"), b"current").unwrap();
+
+        assert_eq!(reset_natives_dir(&instance, "1.16.5").unwrap(), current);
+        let remaining = fs::read_dir(instance.join("natives")).unwrap().flatten().map(|e| e.path()).collect::<Vec<_>>();
+        assert_eq!(remaining, vec![current.clone()]);
+        assert_eq!(fs::read(current.join("lwjgl64.dll")).unwrap(), b"current");
+    }
+}
@@ -2,22 +2,19 @@ use std::path::{Path, PathBuf};
 use std::fs;
 use std::process::Command;
 use tauri::AppHandle;
-use tauri::Emitter;
 use crate::auth::MinecraftProfile;
-use super::models::{VersionManifest, VersionInfo, AssetIndex};
-use super::downloader::download_file;
-use super::java::{get_java_path_for_major, get_required_java_version, download_java};
-use super::utils::{check_rules};
-use super::launch_logic::{resolve_complete_version_info};
+use super::models::VersionInfo;
+use super::downloader::{download_game_file, link_client_jar};
+use super::java::{get_instance_java_path, get_required_java_version, download_java_in};
+use super::utils::{check_rules, natives_dir_for, reset_natives_dir, jvm_path_arg};
+use super::jvm_presets;
+use super::version_store::LocalVersions;
+use super::prepare_checkpoint::{Checkpoint, ASSETS, LIBRARIES};
+use super::launch_logic::{LaunchOptions, classpath_jvm_args, quick_play_args, window_args, isolation_args, debug_jvm_flags, linux_jvm_flags, macos_jvm_flags, extract_library_natives, log4shell_jvm_flags, resolve_complete_version_info};
 
 fn emit(app: &Option<AppHandle>, instance_id: &str, stage: &str, percent: u8, message: &str) {
     if let Some(app) = app {
-        let _ = app.emit("launch_progress", serde_json::json!({
-            "instanceId": instance_id,
-            "stage": stage,
-            "percent": percent,
-            "message": message,
-        }));
+        crate::events::emit_progress(app, crate::events::LaunchProgress::new(instance_id, stage, percent, message));
     }
 }
 
@@ -26,48 +23,44 @@ pub fn download_vanilla(
     instance_minecraft_dir: &Path,
     mc_version: &str,
     app: &Option<AppHandle>,
-    instance_id: &str
+    instance_id: &str,
+    checkpoint: &Checkpoint
 ) -> Result<VersionInfo, String> {
     let assets_dir = base_path.join("assets");
     let libraries_dir = base_path.join("libraries");
     let versions_dir = base_path.join("versions");
     fs::create_dir_all(instance_minecraft_dir).map_err(|e| e.to_string())?;
-    let manifest: VersionManifest = super::launch_logic::fetch_manifest_with_fallback()
-        .map_err(|e| format!("Failed to fetch manifest (with fallback): {}", e))?;
-    let info = resolve_complete_version_info(mc_version, &versions_dir, &manifest)?;
-    let version_dir = versions_dir.join(&info.id);
-    fs::create_dir_all(&version_dir).map_err(|e| e.to_string())?;
-    let json_path = version_dir.join("version.json");
-    let json = serde_json::to_string_pretty(&info).map_err(|e| e.to_string())?;
-    fs::write(&json_path, json).map_err(|e| e.to_string())?;
+    // Saves `<id>.json` locally when it isn't cached yet
+    let info = resolve_complete_version_info(mc_version, &versions_dir)?;
     emit(app, instance_id, "cliente", 55, "Descargando cliente");
     if let Some(downloads) = &info.downloads {
-        let client_path = instance_minecraft_dir.join("client.jar");
-        download_file(&downloads.client.url, &client_path, Some(&downloads.client.sha1))?;
+        link_client_jar(&versions_dir, &info.id, instance_minecraft_dir, &downloads.client)?;
     }
     if let Some(asset_index_ref) = &info.asset_index {
-        let idx_path = assets_dir.join("indexes").join(format!("{}.json", asset_index_ref.id));
-        download_file(&asset_index_ref.url, &idx_path, Some(&asset_index_ref.sha1))?;
-        let asset_index: AssetIndex = serde_json::from_str(
-            &fs::read_to_string(&idx_path).map_err(|e| e.to_string())?
-        ).map_err(|e| e.to_string())?;
-        super::launch_logic::download_assets_parallel(&assets_dir, &asset_index, app, instance_id)?;
+        checkpoint.run(ASSETS, || {
+            let idx_path = assets_dir.join("indexes").join(format!("{}.json", asset_index_ref.id));
+            download_game_file(&asset_index_ref.url, &idx_path, Some(&asset_index_ref.sha1))?;
+            super::launch_logic::download_assets_parallel(&assets_dir, &idx_path, app, instance_id)
+        })?;
     }
-    emit(app, instance_id, "librerias", 65, "Descargando librerías");
-    let mut libs = Vec::new();
-    for lib in &info.libraries {
-        if !check_rules(&lib.rules) { continue; }
-        if let Some(downloads) = &lib.downloads {
-            if let Some(artifact) = &downloads.artifact {
-                if let Some(path_str) = &artifact.path {
-                    let target = libraries_dir.join(path_str);
-                    let _ = fs::create_dir_all(target.parent().unwrap());
-                    download_file(&artifact.url, &target, Some(&artifact.sha1))?;
-                    libs.push(target);
+    checkpoint.run(LIBRARIES, || {
+        emit(app, instance_id, "librerias", 65, "Descargando librerías");
+        let natives_dir = reset_natives_dir(instance_minecraft_dir, &info.id)?;
+        for lib in &info.libraries {
+            if !check_rules(&lib.rules) { continue; }
+            if let Some(downloads) = &lib.downloads {
+                if let Some(artifact) = &downloads.artifact {
+                    if let Some(path_str) = &artifact.path {
+                        let target = libraries_dir.join(path_str);
+                        let _ = fs::create_dir_all(target.parent().unwrap());
+                        download_game_file(&artifact.url, &target, Some(&artifact.sha1))?;
+                    }
                 }
             }
+            extract_library_natives(lib, &libraries_dir, &natives_dir, "https://libraries.minecraft.net/")?;
         }
-    }
+        Ok(())
+    })?;
     Ok(info)
 }
 
@@ -76,30 +69,29 @@ pub fn build_vanilla_command(
     instance_minecraft_dir: &Path,
     info: &VersionInfo,
     auth: &MinecraftProfile,
-    ram_mb: u64
+    ram_mb: u64,
+    options: &LaunchOptions
 ) -> Result<Command, String> {
     let assets_dir = base_path.join("assets");
     let libraries_dir = base_path.join("libraries");
     let versions_dir = base_path.join("versions");
     let required_java = info.java_version.as_ref().map(|v| v.major_version).unwrap_or_else(|| get_required_java_version(&info.id));
-    let java_path = match get_java_path_for_major(required_java) {
+    let java_path = match get_instance_java_path(base_path, required_java, options.java_runtime.as_deref()) {
         Ok(p) => p,
         Err(_) => {
-            let path_str = download_java(required_java, None, None)?;
+            let path_str = download_java_in(base_path, required_java, None, None)?;
             PathBuf::from(path_str)
         }
     };
     let mut jars: Vec<PathBuf> = Vec::new();
-    if let Ok(content) = fs::read_to_string(versions_dir.join(&info.id).join("version.json")) {
-        if let Ok(meta) = serde_json::from_str::<VersionInfo>(&content) {
-            for lib in meta.libraries {
-                if !check_rules(&lib.rules) { continue; }
-                if let Some(downloads) = &lib.downloads {
-                    if let Some(artifact) = &downloads.artifact {
-                        if let Some(path_str) = &artifact.path {
-                            let p = libraries_dir.join(path_str);
-                            if p.exists() { jars.push(p); }
-                        }
+    if let Ok(Some(meta)) = LocalVersions::new(&versions_dir).load(&info.id) {
+        for lib in meta.libraries {
+            if !check_rules(&lib.rules) { continue; }
+            if let Some(downloads) = &lib.downloads {
+                if let Some(artifact) = &downloads.artifact {
+                    if let Some(path_str) = &artifact.path {
+                        let p = libraries_dir.join(path_str);
+                        if p.exists() { jars.push(p); }
                     }
                 }
             }
@@ -111,39 +103,27 @@ pub fn build_vanilla_command(
     } else {
         return Err("client.jar missing in instance".to_string());
     }
-    let sep = if cfg!(target_os = "windows") { ";" } else { ":" };
-    let classpath = jars.into_iter().map(|p| p.to_string_lossy().to_string()).collect::<Vec<_>>().join(sep);
+    let classpath_args = classpath_jvm_args(&jars, required_java, instance_minecraft_dir)?;
     let mut cmd = Command::new(java_path);
+    // Relative argfile paths, and the JVM's own crash logs, are relative to the game folder
+    cmd.current_dir(instance_minecraft_dir);
     let min_mem = std::cmp::max(512, ram_mb / 4);
     cmd.arg(format!("-Xms{}M", min_mem));
     cmd.arg(format!("-Xmx{}M", ram_mb));
-    cmd.arg("-XX:+UnlockExperimentalVMOptions");
-    cmd.arg("-XX:+UseG1GC");
-    cmd.arg("-XX:MaxGCPauseMillis=120");
-    cmd.arg("-XX:G1HeapRegionSize=8M");
-    cmd.arg("-XX:G1NewSizePercent=30");
-    cmd.arg("-XX:G1MaxNewSizePercent=40");
-    cmd.arg("-XX:G1ReservePercent=20");
-    cmd.arg("-XX:G1HeapWastePercent=5");
-    cmd.arg("-XX:G1MixedGCCountTarget=4");
-    cmd.arg("-XX:InitiatingHeapOccupancyPercent=15");
-    cmd.arg("-XX:G1MixedGCLiveThresholdPercent=90");
-    cmd.arg("-XX:G1RSetUpdatingPauseTimePercent=5");
-    cmd.arg("-XX:ReservedCodeCacheSize=768M");
-    cmd.arg("-XX:InitialCodeCacheSize=128M");
-    cmd.arg("-XX:+ParallelRefProcEnabled");
-    cmd.arg("-XX:+DisableExplicitGC");
-    cmd.arg("-XX:+AlwaysPreTouch");
-    cmd.arg("-XX:+UseStringDeduplication");
-    cmd.arg("-XX:+UseCompressedOops");
-    cmd.arg("-XX:+UseCompressedClassPointers");
-    cmd.arg("-XX:+PerfDisableSharedMem");
+    cmd.args(jvm_presets::preset_flags(options.jvm_preset.as_deref(), required_java, ram_mb));
     cmd.arg("-Djava.net.preferIPv4Stack=true");
     cmd.arg("-Dfile.encoding=UTF-8");
     cmd.arg("-Djava.awt.headless=false");
-    cmd.arg("-cp").arg(classpath);
-    cmd.arg(format!("-Dorg.lwjgl.librarypath={}", instance_minecraft_dir.join("natives").to_string_lossy()));
-    cmd.arg(format!("-Djava.library.path={}", instance_minecraft_dir.join("natives").to_string_lossy()));
+    cmd.args(debug_jvm_flags(options));
+    cmd.args(linux_jvm_flags(options));
+    cmd.args(macos_jvm_flags(options, &info.id, &assets_dir));
+    cmd.args(log4shell_jvm_flags(&info.id, &assets_dir));
+    cmd.args(classpath_args);
+    let natives_dir = natives_dir_for(instance_minecraft_dir, &info.id);
+    // Same encoding concern as the classpath: a non-ASCII profile folder reaches the JVM mangled
+    let arg_natives_dir = jvm_path_arg(&natives_dir, instance_minecraft_dir);
+    cmd.arg(format!("-Dorg.lwjgl.librarypath={}", arg_natives_dir.to_string_lossy()));
+    cmd.arg(format!("-Djava.library.path={}", arg_natives_dir.to_string_lossy()));
     cmd.arg(&info.main_class);
     let asset_index_id = info.asset_index.as_ref().map(|a| a.id.as_str()).unwrap_or("legacy").to_string();
     cmd.arg("--version").arg(&info.id);
@@ -154,7 +134,10 @@ pub fn build_vanilla_command(
     cmd.arg("--accessToken").arg(&auth.access_token);
     cmd.arg("--userType").arg("mojang");
     cmd.arg("--versionType").arg("launcher");
-    cmd.arg("--width").arg("854");
-    cmd.arg("--height").arg("480");
+    cmd.arg("--width").arg(options.width.unwrap_or(854).to_string());
+    cmd.arg("--height").arg(options.height.unwrap_or(480).to_string());
+    cmd.args(quick_play_args(options, &info.id));
+    cmd.args(window_args(options));
+    cmd.args(isolation_args(options));
     Ok(cmd)
 }
@@ -0,0 +1,386 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use super::models::VersionInfo;
+use crate::settings::{load_settings, save_settings};
+
+// What earlier builds wrote next to (or instead of) `<id>.json`
+const LEGACY_FILE: &str = "version.json";
+const FABRIC_PREFIX: &str = "fabric-loader-";
+
+/// Somewhere version profiles can be read from. `resolve_complete_version_info` asks a
+/// `Sources` chain, which tries the local folder first and then each remote source in turn.
+pub trait VersionStore {
+    /// Short name for errors
+    fn name(&self) -> &'static str;
+    /// Profile `id` as published (unmerged), `None` when this source doesn't know it.
+    fn load(&self, id: &str) -> Result<Option<VersionInfo>, String>;
+}
+
+/// Version jsons under `versions/`, in the layout the official launcher and the Forge installer
+/// use: `<id>/<id>.json`, holding the profile as published (Mojang, Fabric meta, Forge). Profiles
+/// are stored unmerged; `launch_logic::resolve_complete_version_info` merges parents on load.
+///
+/// Earlier builds also wrote a `<id>/version.json` (the merged profile for vanilla and Forge, the
+/// only copy for Fabric). Every lookup moves it into place first, see `migrate`.
+pub struct LocalVersions {
+    dir: PathBuf,
+}
+
+impl LocalVersions {
+    pub fn new(versions_dir: &Path) -> Self {
+        LocalVersions { dir: versions_dir.to_path_buf() }
+    }
+
+    pub fn json_path(&self, id: &str) -> PathBuf {
+        self.dir.join(id).join(format!("{}.json", id))
+    }
+
+    pub fn contains(&self, id: &str) -> bool {
+        self.migrate(id);
+        self.json_path(id).is_file()
+    }
+
+    /// The stored profile, `None` when the version isn't on disk.
+    pub fn load(&self, id: &str) -> Result<Option<VersionInfo>, String> {
+        self.migrate(id);
+        let content = match fs::read_to_string(self.json_path(id)) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.to_string()),
+        };
+        serde_json::from_str(&content).map(Some).map_err(|e| format!("Invalid version json for {}: {}", id, e))
+    }
+
+    pub fn save(&self, id: &str, info: &VersionInfo) -> Result<(), String> {
+        let path = self.json_path(id);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        let json = serde_json::to_string_pretty(info).map_err(|e| e.to_string())?;
+        fs::write(&path, json).map_err(|e| e.to_string())
+    }
+
+    /// Moves a legacy `version.json` to `<id>.json`, or drops it when `<id>.json` already exists
+    /// (it was then a merged copy). Returns whether anything changed.
+    pub fn migrate(&self, id: &str) -> bool {
+        let legacy = self.dir.join(id).join(LEGACY_FILE);
+        if !legacy.is_file() {
+            return false;
+        }
+        let result = if self.json_path(id).is_file() {
+            fs::remove_file(&legacy)
+        } else {
+            fs::rename(&legacy, self.json_path(id))
+        };
+        if let Err(e) = &result {
+            eprintln!("[version_store] Failed to migrate {}: {}", legacy.display(), e);
+        }
+        result.is_ok()
+    }
+
+    /// Migrates every version folder at once; returns how many changed.
+    pub fn migrate_all(&self) -> usize {
+        fs::read_dir(&self.dir)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .filter(|entry| entry.path().is_dir())
+            .filter(|entry| self.migrate(&entry.file_name().to_string_lossy()))
+            .count()
+    }
+}
+
+impl VersionStore for LocalVersions {
+    fn name(&self) -> &'static str {
+        "local"
+    }
+
+    fn load(&self, id: &str) -> Result<Option<VersionInfo>, String> {
+        LocalVersions::load(self, id)
+    }
+}
+
+fn client() -> Result<reqwest::blocking::Client, String> {
+    reqwest::blocking::Client::builder()
+        .user_agent(crate::http::user_agent())
+        .timeout(Duration::from_secs(30))
+        .build()
+        .map_err(|e| format!("Failed to build http client: {}", e))
+}
+
+/// GETs a profile; a 404 means the source doesn't have it.
+fn fetch_profile(url: &str) -> Result<Option<VersionInfo>, String> {
+    let client = client()?;
+    let resp = crate::http::send(&client, client.get(crate::mirror::rewrite(url)))?;
+    if resp.status() == reqwest::StatusCode::NOT_FOUND {
+        return Ok(None);
+    }
+    if !resp.status().is_success() {
+        return Err(format!("{} respondió {}", url, resp.status()));
+    }
+    resp.json().map(Some).map_err(|e| format!("Failed to parse version info: {}", e))
+}
+
+/// Vanilla profiles listed in Mojang's version manifest.
+pub struct MojangMeta;
+
+impl VersionStore for MojangMeta {
+    fn name(&self) -> &'static str {
+        "Mojang"
+    }
+
+    fn load(&self, id: &str) -> Result<Option<VersionInfo>, String> {
+        let manifest = super::launch_logic::fetch_manifest_with_fallback()?;
+        match manifest.versions.iter().find(|v| v.id == id) {
+            Some(entry) => fetch_profile(&entry.url),
+            None => Ok(None),
+        }
+    }
+}
+
+/// `(loader, game version)` of a Fabric profile id (`fabric-loader-<loader>-<game>`).
+pub fn parse_fabric_id(id: &str) -> Option<(&str, &str)> {
+    id.strip_prefix(FABRIC_PREFIX)?.split_once('-').filter(|(loader, mc)| !loader.is_empty() && !mc.is_empty())
+}
+
+/// Fabric loader profiles from meta.fabricmc.net.
+pub struct FabricMeta;
+
+impl VersionStore for FabricMeta {
+    fn name(&self) -> &'static str {
+        "Fabric"
+    }
+
+    fn load(&self, id: &str) -> Result<Option<VersionInfo>, String> {
+        let Some((loader, mc_version)) = parse_fabric_id(id) else { return Ok(None) };
+        fetch_profile(&format!("https://meta.fabricmc.net/v2/versions/loader/{}/{}/profile/json", mc_version, loader))
+    }
+}
+
+/// `(game version, forge version)` of the ids the Forge installer creates
+/// (`<game>-forge-<forge>`, older ones `forge-<game>-<forge>`).
+pub fn parse_forge_id(id: &str) -> Option<(&str, &str)> {
+    let parts = match id.strip_prefix("forge-") {
+        Some(rest) => rest.split_once('-'),
+        None => id.split_once("-forge-"),
+    };
+    parts.filter(|(mc, forge)| !mc.is_empty() && !forge.is_empty())
+}
+
+/// Forge profiles read out of the installer on the Forge maven. Running the installer is still
+/// what makes the version launchable (`launch_logic::ensure_forge_installed`).
+pub struct ForgeMaven {
+    pub base_path: PathBuf,
+}
+
+impl VersionStore for ForgeMaven {
+    fn name(&self) -> &'static str {
+        "Forge"
+    }
+
+    fn load(&self, id: &str) -> Result<Option<VersionInfo>, String> {
+        let Some((mc_version, forge_version)) = parse_forge_id(id) else { return Ok(None) };
+        let installer = super::launch_logic::download_forge_installer(&self.base_path, mc_version, forge_version)?;
+        let file = fs::File::open(&installer).map_err(|e| e.to_string())?;
+        let mut archive = zip::ZipArchive::new(file).map_err(|e| format!("Invalid Forge installer: {}", e))?;
+        let mut json = String::new();
+        match archive.by_name(LEGACY_FILE) {
+            Ok(mut entry) => entry.read_to_string(&mut json).map_err(|e| e.to_string())?,
+            Err(_) => return Ok(None),
+        };
+        serde_json::from_str(&json).map(Some).map_err(|e| format!("Invalid version json for {}: {}", id, e))
+    }
+}
+
+/// Meta server run by the event staff, mirroring the `versions/` layout:
+/// `<url>/<id>/<id>.json`. A copy of a launcher's `versions` folder served statically works.
+pub struct AdminMeta {
+    pub url: String,
+}
+
+impl VersionStore for AdminMeta {
+    fn name(&self) -> &'static str {
+        "servidor de metadatos"
+    }
+
+    fn load(&self, id: &str) -> Result<Option<VersionInfo>, String> {
+        fetch_profile(&format!("{}/{}/{}.json", self.url.trim_end_matches('/'), id, id))
+    }
+}
+
+/// The local folder first, then each remote source until one has the profile; remote hits are
+/// saved locally so the next launch needs no network.
+pub struct Sources {
+    pub local: LocalVersions,
+    pub remotes: Vec<Box<dyn VersionStore>>,
+}
+
+impl VersionStore for Sources {
+    fn name(&self) -> &'static str {
+        "sources"
+    }
+
+    fn load(&self, id: &str) -> Result<Option<VersionInfo>, String> {
+        if let Some(info) = self.local.load(id)? {
+            return Ok(Some(info));
+        }
+        let mut errors = Vec::new();
+        for source in &self.remotes {
+            match source.load(id) {
+                Ok(Some(info)) => {
+                    self.local.save(id, &info)?;
+                    return Ok(Some(info));
+                }
+                Ok(None) => {}
+                // An unreachable source shouldn't hide one further down that has it
+                Err(e) => errors.push(format!("{}: {}", source.name(), e)),
+            }
+        }
+        if errors.is_empty() { Ok(None) } else { Err(errors.join("; ")) }
+    }
+}
+
+/// Where version metadata comes from. Admin only.
+#[derive(Serialize, Deserialize, Clone, Default, PartialEq, Debug, JsonSchema)]
+pub struct VersionMetaSettings {
+    /// Base URL of the staff meta server, asked before the public sources
+    #[serde(rename = "serverUrl", alias = "server_url", default)]
+    pub server_url: Option<String>,
+    /// Never contact Mojang, Fabric or Forge (closed-network events)
+    #[serde(rename = "selfHostedOnly", alias = "self_hosted_only", default)]
+    pub self_hosted_only: bool,
+}
+
+pub fn remote_sources(settings: &VersionMetaSettings, base_path: &Path) -> Vec<Box<dyn VersionStore>> {
+    let mut remotes: Vec<Box<dyn VersionStore>> = Vec::new();
+    if let Some(url) = settings.server_url.as_deref().map(str::trim).filter(|u| !u.is_empty()) {
+        remotes.push(Box::new(AdminMeta { url: url.to_string() }));
+    }
+    if !settings.self_hosted_only {
+        remotes.push(Box::new(MojangMeta));
+        remotes.push(Box::new(FabricMeta));
+        remotes.push(Box::new(ForgeMaven { base_path: base_path.to_path_buf() }));
+    }
+    remotes
+}
+
+/// The chain configured in the settings, over `versions_dir`.
+pub fn configured_sources(versions_dir: &Path) -> Sources {
+    let base_path = versions_dir.parent().unwrap_or(versions_dir);
+    Sources {
+        local: LocalVersions::new(versions_dir),
+        remotes: remote_sources(&load_settings().version_meta, base_path),
+    }
+}
+
+#[tauri::command]
+pub fn get_version_meta_settings(password: String) -> Result<VersionMetaSettings, String> {
+    crate::ensure_admin(password)?;
+    Ok(load_settings().version_meta)
+}
+
+#[tauri::command]
+pub fn set_version_meta_settings(password: String, version_meta: VersionMetaSettings) -> Result<(), String> {
+    crate::ensure_admin(password)?;
+    let mut settings = load_settings();
+    settings.version_meta = version_meta;
+    save_settings(&settings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn profile(id: &str, inherits_from: Option<&str>) -> VersionInfo {
+        serde_json::from_value(serde_json::json!({
+            "id": id,
+            "inheritsFrom": inherits_from,
+            "mainClass": "net.minecraft.client.main.Main",
+            "type": "release",
+            "libraries": [],
+        }))
+        .unwrap()
+    }
+
+    struct Fake(Result<HashMap<String, VersionInfo>, String>);
+
+    impl VersionStore for Fake {
+        fn name(&self) -> &'static str {
+            "fake"
+        }
+
+        fn load(&self, id: &str) -> Result<Option<VersionInfo>, String> {
+            self.0.clone().map(|profiles| profiles.get(id).cloned())
+        }
+    }
+
+    #[test]
+    fn profiles_round_trip_in_the_official_layout() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = LocalVersions::new(dir.path());
+        assert!(!store.contains("1.20.1"));
+        assert!(store.load("1.20.1").unwrap().is_none());
+
+        store.save("1.20.1", &profile("1.20.1", None)).unwrap();
+        assert!(dir.path().join("1.20.1").join("1.20.1.json").is_file());
+        assert_eq!(store.load("1.20.1").unwrap().unwrap().id, "1.20.1");
+    }
+
+    #[test]
+    fn legacy_version_json_is_migrated() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = LocalVersions::new(dir.path());
+        let fabric = "fabric-loader-0.15.11-1.20.1";
+        fs::create_dir_all(dir.path().join(fabric)).unwrap();
+        fs::write(dir.path().join(fabric).join(LEGACY_FILE), serde_json::to_string(&profile(fabric, Some("1.20.1"))).unwrap()).unwrap();
+        // Vanilla had both files; the legacy one is a duplicate
+        store.save("1.20.1", &profile("1.20.1", None)).unwrap();
+        fs::write(dir.path().join("1.20.1").join(LEGACY_FILE), "{}").unwrap();
+
+        assert_eq!(store.migrate_all(), 2);
+        assert!(!dir.path().join(fabric).join(LEGACY_FILE).exists());
+        assert_eq!(store.load(fabric).unwrap().unwrap().inherits_from.as_deref(), Some("1.20.1"));
+        assert_eq!(store.load("1.20.1").unwrap().unwrap().id, "1.20.1");
+        assert_eq!(store.migrate_all(), 0);
+    }
+
+    #[test]
+    fn sources_fall_through_and_cache_hits() {
+        let dir = tempfile::tempdir().unwrap();
+        let sources = Sources {
+            local: LocalVersions::new(dir.path()),
+            remotes: vec![
+                Box::new(Fake(Err("sin red".to_string()))),
+                Box::new(Fake(Ok([("1.20.1".to_string(), profile("1.20.1", None))].into_iter().collect()))),
+            ],
+        };
+        assert_eq!(sources.load("1.20.1").unwrap().unwrap().id, "1.20.1");
+        assert!(LocalVersions::new(dir.path()).contains("1.20.1"));
+        // Unknown everywhere, but one source failed: that's worth reporting
+        assert!(sources.load("1.7.10").unwrap_err().contains("sin red"));
+    }
+
+    #[test]
+    fn self_hosted_only_keeps_to_the_staff_server() {
+        let names = |settings: &VersionMetaSettings| {
+            remote_sources(settings, Path::new("/data")).iter().map(|s| s.name()).collect::<Vec<_>>()
+        };
+        assert_eq!(names(&VersionMetaSettings::default()), vec!["Mojang", "Fabric", "Forge"]);
+        let closed = VersionMetaSettings { server_url: Some("http://meta.lan".to_string()), self_hosted_only: true };
+        assert_eq!(names(&closed), vec!["servidor de metadatos"]);
+    }
+
+    #[test]
+    fn loader_ids_are_parsed() {
+        assert_eq!(parse_fabric_id("fabric-loader-0.15.11-1.20.1"), Some(("0.15.11", "1.20.1")));
+        assert_eq!(parse_fabric_id("1.20.1"), None);
+        assert_eq!(parse_forge_id("1.20.1-forge-47.2.0"), Some(("1.20.1", "47.2.0")));
+        assert_eq!(parse_forge_id("forge-1.12.2-14.23.5.2859"), Some(("1.12.2", "14.23.5.2859")));
+        assert_eq!(parse_forge_id("1.20.1"), None);
+    }
+}
@@ -1,17 +1,13 @@
+use schemars::JsonSchema;
+use serde::Serialize;
 use crate::minecraft::models::{VersionManifest, VersionEntry};
-
-const MANIFEST_URLS: [&str; 4] = [
-    "https://piston-meta.mojang.com/mc/game/version_manifest.json",
-    "https://piston-meta.mojang.com/mc/game/version_manifest_v2.json",
-    "https://launchermeta.mojang.com/mc/game/version_manifest.json",
-    "https://bmclapi2.bangbang93.com/mc/game/version_manifest.json",
-];
+use crate::minecraft::launch_logic::{fetch_manifest_with_fallback, get_forge_recommended_version, load_fabric_profile_info, parse_mc_version, MANIFEST_MIRRORS};
 
 pub fn get_release_versions(limit: usize) -> Result<Vec<String>, String> {
     let mut last_err = String::new();
     let mut manifest_opt: Option<VersionManifest> = None;
-    for url in MANIFEST_URLS {
-        match reqwest::blocking::get(url) {
+    for url in crate::download_stats::rank_mirrors(&MANIFEST_MIRRORS) {
+        match reqwest::blocking::get(crate::mirror::rewrite(&url)) {
             Ok(resp) => {
                 match resp.json::<VersionManifest>() {
                     Ok(m) => { manifest_opt = Some(m); break; }
@@ -33,3 +29,341 @@ pub fn get_release_versions(limit: usize) -> Result<Vec<String>, String> {
 
     Ok(releases.into_iter().take(limit).map(|v| v.id).collect())
 }
+
+/// `latest` block of the manifest with release dates, for defaults and "new version" badges.
+#[derive(Serialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LatestVersions {
+    pub release: String,
+    #[serde(rename = "releaseDate")]
+    pub release_date: Option<String>,
+    pub snapshot: String,
+    #[serde(rename = "snapshotDate")]
+    pub snapshot_date: Option<String>,
+}
+
+pub fn latest_versions(manifest: &VersionManifest) -> LatestVersions {
+    let date_of = |id: &str| manifest.versions.iter().find(|v| v.id == id).map(|v| v.release_time.clone());
+    LatestVersions {
+        release: manifest.latest.release.clone(),
+        release_date: date_of(&manifest.latest.release),
+        snapshot: manifest.latest.snapshot.clone(),
+        snapshot_date: date_of(&manifest.latest.snapshot),
+    }
+}
+
+/// Newest release in the same `1.<minor>` line that is newer than `current`.
+pub fn latest_patch_for(current: &str, manifest: &VersionManifest) -> Option<String> {
+    let (minor, patch) = parse_mc_version(current)?;
+    manifest.versions.iter()
+        .filter(|v| v.version_type == "release")
+        .filter_map(|v| parse_mc_version(&v.id).map(|(m, p)| (m, p, &v.id)))
+        .filter(|(m, p, _)| *m == minor && *p > patch)
+        .max_by_key(|(_, p, _)| *p)
+        .map(|(_, _, id)| id.clone())
+}
+
+/// Offered to instances with `trackLatestPatch` before re-preparing on the new patch.
+#[derive(Serialize, Clone, Debug, JsonSchema)]
+pub struct PatchUpdate {
+    pub current: String,
+    pub latest: String,
+    /// Loader version the new patch would use (Forge/Fabric), when there is one
+    #[serde(rename = "loaderVersion")]
+    pub loader_version: Option<String>,
+    /// False when the instance loader has no build for the new patch yet
+    pub compatible: bool,
+}
+
+pub fn check_patch_update(current: &str, loader: Option<&str>) -> Result<Option<PatchUpdate>, String> {
+    let manifest = fetch_manifest_with_fallback()?;
+    let Some(latest) = latest_patch_for(current, &manifest) else { return Ok(None); };
+    let loader_version = match loader.unwrap_or("vanilla") {
+        "fabric" => load_fabric_profile_info(&latest).map(|info| Some(info.id)),
+        "forge" => get_forge_recommended_version(&latest).map(Some),
+        _ => Ok(None),
+    };
+    Ok(Some(PatchUpdate {
+        current: current.to_string(),
+        latest,
+        compatible: loader_version.is_ok(),
+        loader_version: loader_version.ok().flatten(),
+    }))
+}
+
+/// Result of validating a version/loader pair before an instance is saved.
+#[derive(Serialize, Clone, Debug, Default, JsonSchema)]
+pub struct CompatibilityReport {
+    /// The instance would not prepare/launch with this combination
+    pub errors: Vec<String>,
+    pub warnings: Vec<String>,
+}
+
+impl CompatibilityReport {
+    pub fn is_ok(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
+
+// Fabric Loader targets 1.14 (snapshot 18w43b) and newer
+const FABRIC_MIN_MINOR: u32 = 14;
+
+/// Checks that only need the manifest; loader availability is added by `check_compatibility`.
+pub fn manifest_compatibility(version: &str, loader: &str, manifest: &VersionManifest) -> CompatibilityReport {
+    let mut report = CompatibilityReport::default();
+    match manifest.versions.iter().find(|v| v.id == version) {
+        None => report.errors.push(format!("La versión {} no existe en el manifest de Mojang", version)),
+        Some(entry) if entry.version_type != "release" => {
+            report.warnings.push(format!("{} es una versión {}; los mods suelen no estar disponibles", version, entry.version_type))
+        }
+        Some(_) => {}
+    }
+    if loader == "fabric" {
+        if let Some((minor, _)) = parse_mc_version(version) {
+            if minor < FABRIC_MIN_MINOR {
+                report.errors.push(format!("Fabric requiere Minecraft 1.{} o superior", FABRIC_MIN_MINOR));
+            }
+        }
+    }
+    if !matches!(loader, "vanilla" | "fabric" | "forge") {
+        report.errors.push(format!("Modloader no soportado: {}", loader));
+    }
+    report
+}
+
+/// Validates a version/modloader pair against the cached Mojang, Fabric and Forge metadata.
+pub fn check_compatibility(version: &str, loader: Option<&str>) -> Result<CompatibilityReport, String> {
+    let loader = loader.unwrap_or("vanilla");
+    let manifest = fetch_manifest_with_fallback()?;
+    let mut report = manifest_compatibility(version, loader, &manifest);
+    if !report.is_ok() {
+        return Ok(report);
+    }
+    let (name, lookup) = match loader {
+        "fabric" => ("Fabric Loader", load_fabric_profile_info(version).map(|_| ())),
+        "forge" => ("Forge", get_forge_recommended_version(version).map(|_| ())),
+        _ => return Ok(report),
+    };
+    if let Err(e) = lookup {
+        add_loader_lookup_failure(&mut report, name, version, &e);
+    }
+    Ok(report)
+}
+
+/// A loader server that can't be reached says nothing about the version; only an answer
+/// without a build for it is an error.
+fn add_loader_lookup_failure(report: &mut CompatibilityReport, loader: &str, version: &str, error: &str) {
+    use crate::launch_history::{classify_failure, FailureKind, Outcome};
+    if classify_failure(Outcome::Failed, error) == Some(FailureKind::Network) {
+        report.warnings.push(format!("No se pudo comprobar si hay {} para {}: sin conexión con sus servidores", loader, version));
+    } else {
+        report.errors.push(format!("No hay {} disponible para {}", loader, version));
+    }
+}
+
+/// One build of a mod loader for a given Minecraft version.
+#[derive(Serialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LoaderVersion {
+    pub version: String,
+    /// False for builds the loader marks (or names) beta/alpha/pre-release
+    pub stable: bool,
+}
+
+/// Every known build of a loader for one Minecraft version, newest first.
+#[derive(Serialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LoaderVersions {
+    pub loader: String,
+    pub recommended: Option<String>,
+    pub latest: Option<String>,
+    pub versions: Vec<LoaderVersion>,
+}
+
+fn named_unstable(version: &str) -> bool {
+    let version = version.to_lowercase();
+    ["beta", "alpha", "pre", "rc"].iter().any(|tag| version.contains(tag))
+}
+
+/// Fabric and Quilt meta share the `[{"loader": {"version", "stable"?}}]` shape; Quilt has no
+/// stability flag, only beta names.
+pub fn fabric_like_versions(list: &serde_json::Value) -> Vec<LoaderVersion> {
+    list.as_array().into_iter().flatten()
+        .filter_map(|item| {
+            let version = item["loader"]["version"].as_str()?;
+            let stable = item["loader"]["stable"].as_bool().unwrap_or(true) && !named_unstable(version);
+            Some(LoaderVersion { version: version.to_string(), stable })
+        })
+        .collect()
+}
+
+/// Forge `maven-metadata.json` maps each Minecraft version to `<mc>-<forge>` ids, oldest first.
+pub fn forge_versions(metadata: &serde_json::Value, mc_version: &str) -> Vec<LoaderVersion> {
+    let prefix = format!("{}-", mc_version);
+    metadata[mc_version].as_array().into_iter().flatten().rev()
+        .filter_map(|v| v.as_str())
+        .map(|id| {
+            let version = id.strip_prefix(&prefix).unwrap_or(id).to_string();
+            LoaderVersion { stable: !named_unstable(&version), version }
+        })
+        .collect()
+}
+
+/// NeoForge numbers builds after the game version: 1.20.4 -> `20.4.x`, 1.21 -> `21.0.x`. Its own
+/// line starts at 1.20.2; 1.20.1 builds were published as Forge.
+pub fn neoforge_prefix(mc_version: &str) -> Option<String> {
+    let (minor, patch) = parse_mc_version(mc_version)?;
+    ((minor, patch) >= (20, 2)).then(|| format!("{}.{}.", minor, patch))
+}
+
+pub fn neoforge_versions(list: &serde_json::Value, mc_version: &str) -> Vec<LoaderVersion> {
+    let Some(prefix) = neoforge_prefix(mc_version) else { return Vec::new(); };
+    list["versions"].as_array().into_iter().flatten().rev()
+        .filter_map(|v| v.as_str())
+        .filter(|v| v.starts_with(&prefix))
+        .map(|v| LoaderVersion { version: v.to_string(), stable: !named_unstable(v) })
+        .collect()
+}
+
+fn summarize(loader: &str, versions: Vec<LoaderVersion>, recommended: Option<String>) -> LoaderVersions {
+    LoaderVersions {
+        loader: loader.to_string(),
+        recommended: recommended.or_else(|| versions.iter().find(|v| v.stable).map(|v| v.version.clone())),
+        latest: versions.first().map(|v| v.version.clone()),
+        versions,
+    }
+}
+
+fn fetch_json(url: &str) -> Result<serde_json::Value, String> {
+    let client = reqwest::blocking::Client::builder()
+        .user_agent(crate::http::user_agent())
+        .build()
+        .map_err(|e| e.to_string())?;
+    let resp = crate::http::send(&client, client.get(crate::mirror::rewrite(url)))?;
+    if !resp.status().is_success() {
+        return Err(format!("{} returned status: {}", url, resp.status()));
+    }
+    resp.json().map_err(|e| format!("Invalid response from {}: {}", url, e))
+}
+
+pub fn loader_versions(loader: &str, mc_version: &str) -> Result<LoaderVersions, String> {
+    let result = match loader {
+        "fabric" => {
+            let list = fetch_json(&format!("https://meta.fabricmc.net/v2/versions/loader/{}", mc_version))?;
+            summarize(loader, fabric_like_versions(&list), None)
+        }
+        "quilt" => {
+            let list = fetch_json(&format!("https://meta.quiltmc.org/v3/versions/loader/{}", mc_version))?;
+            summarize(loader, fabric_like_versions(&list), None)
+        }
+        "forge" => {
+            let metadata = fetch_json("https://files.minecraftforge.net/net/minecraftforge/forge/maven-metadata.json")?;
+            // The launcher installs the promoted build, so that is the one to recommend
+            summarize(loader, forge_versions(&metadata, mc_version), get_forge_recommended_version(mc_version).ok())
+        }
+        "neoforge" => {
+            let list = fetch_json("https://maven.neoforged.net/api/maven/versions/releases/net/neoforged/neoforge")?;
+            summarize(loader, neoforge_versions(&list, mc_version), None)
+        }
+        other => return Err(format!("Unsupported loader: {}", other)),
+    };
+    if result.versions.is_empty() {
+        return Err(format!("No hay versiones de {} para {}", loader, mc_version));
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn loader_lists_are_newest_first_with_stability() {
+        let fabric = serde_json::json!([
+            {"loader": {"version": "0.16.0", "stable": false}},
+            {"loader": {"version": "0.15.11", "stable": true}}
+        ]);
+        let summary = summarize("fabric", fabric_like_versions(&fabric), None);
+        assert_eq!(summary.latest.as_deref(), Some("0.16.0"));
+        assert_eq!(summary.recommended.as_deref(), Some("0.15.11"));
+
+        let forge = serde_json::json!({"1.20.1": ["1.20.1-46.0.14", "1.20.1-47.2.0"]});
+        assert_eq!(forge_versions(&forge, "1.20.1"), vec![
+            LoaderVersion { version: "47.2.0".into(), stable: true },
+            LoaderVersion { version: "46.0.14".into(), stable: true },
+        ]);
+
+        let neoforge = serde_json::json!({"versions": ["20.4.80-beta", "20.4.237", "21.0.167"]});
+        let versions = neoforge_versions(&neoforge, "1.20.4");
+        assert_eq!(versions, vec![
+            LoaderVersion { version: "20.4.237".into(), stable: true },
+            LoaderVersion { version: "20.4.80-beta".into(), stable: false },
+        ]);
+        assert_eq!(neoforge_prefix("1.21"), Some("21.0.".to_string()));
+        assert_eq!(neoforge_prefix("1.20.1"), None);
+    }
+
+    #[test]
+    fn latest_versions_carry_release_dates() {
+        let manifest: VersionManifest = serde_json::from_value(serde_json::json!({
+            "latest": {"release": "1.21.1", "snapshot": "24w33a"},
+            "versions": [
+                {"id": "24w33a", "type": "snapshot", "url": "u", "time": "t", "releaseTime": "2024-08-15T12:00:00+00:00"},
+                {"id": "1.21.1", "type": "release", "url": "u", "time": "t", "releaseTime": "2024-08-08T12:00:00+00:00"}
+            ]
+        })).unwrap();
+        let latest = latest_versions(&manifest);
+        assert_eq!(latest.release, "1.21.1");
+        assert_eq!(latest.release_date.as_deref(), Some("2024-08-08T12:00:00+00:00"));
+        assert_eq!(latest.snapshot_date.as_deref(), Some("2024-08-15T12:00:00+00:00"));
+    }
+
+    fn manifest_with(entries: &[(&str, &str)]) -> VersionManifest {
+        let versions = entries.iter()
+            .map(|(id, kind)| serde_json::json!({"id": id, "type": kind, "url": "u", "time": "t", "releaseTime": "r"}))
+            .collect::<Vec<_>>();
+        serde_json::from_value(serde_json::json!({
+            "latest": {"release": "1.21.1", "snapshot": "24w33a"},
+            "versions": versions
+        })).unwrap()
+    }
+
+    #[test]
+    fn compatibility_flags_unknown_versions_snapshots_and_old_fabric() {
+        let manifest = manifest_with(&[("1.21.1", "release"), ("24w33a", "snapshot"), ("1.12.2", "release")]);
+        assert!(manifest_compatibility("1.21.1", "fabric", &manifest).is_ok());
+        assert!(!manifest_compatibility("1.99", "vanilla", &manifest).is_ok());
+        assert!(!manifest_compatibility("1.12.2", "fabric", &manifest).is_ok());
+        assert!(manifest_compatibility("1.12.2", "forge", &manifest).is_ok());
+        let snapshot = manifest_compatibility("24w33a", "vanilla", &manifest);
+        assert!(snapshot.is_ok() && snapshot.warnings.len() == 1);
+    }
+
+    #[test]
+    fn unreachable_loader_servers_only_warn() {
+        let mut offline = CompatibilityReport::default();
+        add_loader_lookup_failure(&mut offline, "Fabric Loader", "1.21.1", "Failed to fetch fabric loader list: error sending request for url (https://meta.fabricmc.net/v2/versions/loader/1.21.1)");
+        assert!(offline.is_ok() && offline.warnings.len() == 1);
+
+        let mut missing = CompatibilityReport::default();
+        add_loader_lookup_failure(&mut missing, "Forge", "1.21.9", "No forge version found for Minecraft 1.21.9. Last error: Forge promotions API did not yield a version");
+        assert!(!missing.is_ok());
+    }
+
+    #[test]
+    fn latest_patch_stays_within_the_minor_line() {
+        let versions = ["1.21.1", "1.21", "1.20.6", "1.20.4", "1.20.1", "24w33a"]
+            .iter()
+            .map(|id| serde_json::json!({
+                "id": id,
+                "type": if id.contains('w') { "snapshot" } else { "release" },
+                "url": "u", "time": "t", "releaseTime": "r"
+            }))
+            .collect::<Vec<_>>();
+        let manifest: VersionManifest = serde_json::from_value(serde_json::json!({
+            "latest": {"release": "1.21.1", "snapshot": "24w33a"},
+            "versions": versions
+        })).unwrap();
+        assert_eq!(latest_patch_for("1.20.1", &manifest).as_deref(), Some("1.20.6"));
+        assert_eq!(latest_patch_for("1.21", &manifest).as_deref(), Some("1.21.1"));
+        assert_eq!(latest_patch_for("1.21.1", &manifest), None);
+        assert_eq!(latest_patch_for("1.19.2", &manifest), None);
+    }
+}
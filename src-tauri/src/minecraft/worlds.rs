@@ -0,0 +1,172 @@
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use schemars::JsonSchema;
+use serde::Serialize;
+use super::launch_logic::parse_mc_version;
+use super::nbt::{read_gzip_file, Tag};
+
+/// A world under `minecraft/saves`, with the version that last wrote its `level.dat`.
+#[derive(Serialize, Clone, Debug, JsonSchema)]
+pub struct WorldInfo {
+    pub folder: String,
+    pub name: String,
+    /// `Data.Version.Name`; missing on worlds last opened before 1.9
+    #[serde(rename = "versionName")]
+    pub version_name: Option<String>,
+    #[serde(rename = "dataVersion")]
+    pub data_version: Option<i64>,
+}
+
+#[derive(Serialize, Clone, Debug, JsonSchema)]
+pub struct WorldMigrationPlan {
+    #[serde(rename = "fromVersion")]
+    pub from_version: String,
+    #[serde(rename = "toVersion")]
+    pub to_version: String,
+    pub upgrade: bool,
+    pub worlds: Vec<WorldInfo>,
+    /// Worlds saved by a newer version than the target; opening them would corrupt them
+    #[serde(rename = "blockedWorlds")]
+    pub blocked_worlds: Vec<String>,
+}
+
+pub fn read_world_info(world_dir: &Path) -> Result<WorldInfo, String> {
+    let level = read_gzip_file(&world_dir.join("level.dat"))?;
+    let folder = world_dir.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    Ok(WorldInfo {
+        name: level.path(&["Data", "LevelName"]).and_then(Tag::as_str).map(str::to_string).unwrap_or_else(|| folder.clone()),
+        version_name: level.path(&["Data", "Version", "Name"]).and_then(Tag::as_str).map(str::to_string),
+        data_version: level.path(&["Data", "DataVersion"]).and_then(Tag::as_int),
+        folder,
+    })
+}
+
+/// Worlds with a readable `level.dat`, sorted by folder name.
+pub fn list_worlds(minecraft_dir: &Path) -> Vec<WorldInfo> {
+    let mut worlds = fs::read_dir(minecraft_dir.join("saves"))
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter(|e| e.path().join("level.dat").is_file())
+        .filter_map(|e| match read_world_info(&e.path()) {
+            Ok(info) => Some(info),
+            Err(err) => {
+                eprintln!("[worlds] Skipping world {}: {}", e.path().display(), err);
+                None
+            }
+        })
+        .collect::<Vec<_>>();
+    worlds.sort_by(|a, b| a.folder.cmp(&b.folder));
+    worlds
+}
+
+/// True when `world_version` is a later release than `target`. Snapshots and unknown
+/// versions are not compared.
+fn is_newer(world_version: &str, target: &str) -> bool {
+    match (parse_mc_version(world_version), parse_mc_version(target)) {
+        (Some(world), Some(target)) => world > target,
+        _ => false,
+    }
+}
+
+pub fn plan_migration(minecraft_dir: &Path, from_version: &str, to_version: &str) -> WorldMigrationPlan {
+    let worlds = list_worlds(minecraft_dir);
+    let blocked_worlds = worlds.iter()
+        .filter(|w| w.version_name.as_deref().map(|v| is_newer(v, to_version)).unwrap_or(false))
+        .map(|w| w.name.clone())
+        .collect();
+    WorldMigrationPlan {
+        from_version: from_version.to_string(),
+        to_version: to_version.to_string(),
+        upgrade: is_newer(to_version, from_version),
+        worlds,
+        blocked_worlds,
+    }
+}
+
+fn add_dir_to_zip(zip: &mut zip::ZipWriter<fs::File>, dir: &Path, prefix: &str) -> Result<(), String> {
+    for entry in fs::read_dir(dir).map_err(|e| e.to_string())?.flatten() {
+        let path = entry.path();
+        let name = format!("{}/{}", prefix, entry.file_name().to_string_lossy());
+        if path.is_dir() {
+            add_dir_to_zip(zip, &path, &name)?;
+        } else if entry.file_name() != "session.lock" {
+            // The game holds session.lock open while the world is loaded
+            zip.start_file(name, zip::write::FileOptions::default()).map_err(|e| e.to_string())?;
+            zip.write_all(&fs::read(&path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?)
+                .map_err(|e| e.to_string())?;
+        }
+    }
+    Ok(())
+}
+
+/// Zips one world folder into `target`, entries under the folder's name.
+pub fn zip_world(world_dir: &Path, target: &Path) -> Result<(), String> {
+    let folder = world_dir.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    let file = fs::File::create(target).map_err(|e| format!("Failed to create {}: {}", target.display(), e))?;
+    let mut zip = zip::ZipWriter::new(file);
+    add_dir_to_zip(&mut zip, world_dir, &folder)?;
+    zip.finish().map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// Zips every world into `backup_dir` as `<folder>-<version>-<timestamp>.zip`, tagging each backup
+/// with the version that last saved the world (or `fallback_version` when `level.dat` has none).
+pub fn backup_worlds(minecraft_dir: &Path, backup_dir: &Path, fallback_version: &str, timestamp: u64) -> Result<Vec<PathBuf>, String> {
+    fs::create_dir_all(backup_dir).map_err(|e| e.to_string())?;
+    let mut backups = Vec::new();
+    for world in list_worlds(minecraft_dir) {
+        let version = world.version_name.as_deref().unwrap_or(fallback_version);
+        let target = backup_dir.join(format!("{}-{}-{}.zip", world.folder, version, timestamp));
+        zip_world(&minecraft_dir.join("saves").join(&world.folder), &target)?;
+        backups.push(target);
+    }
+    Ok(backups)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+
+    fn write_world(minecraft_dir: &Path, folder: &str, version: &str) {
+        let dir = minecraft_dir.join("saves").join(folder);
+        fs::create_dir_all(dir.join("region")).unwrap();
+        let mut gz = GzEncoder::new(Vec::new(), Compression::default());
+        gz.write_all(&super::super::nbt::tests::level_dat(version, 3465)).unwrap();
+        fs::write(dir.join("level.dat"), gz.finish().unwrap()).unwrap();
+        fs::write(dir.join("region").join("r.0.0.mca"), b"region").unwrap();
+        fs::write(dir.join("session.lock"), b"").unwrap();
+    }
+
+    #[test]
+    fn downgrades_of_newer_worlds_are_blocked() {
+        let dir = tempfile::tempdir().unwrap();
+        write_world(dir.path(), "old", "1.19.4");
+        write_world(dir.path(), "new", "1.20.4");
+
+        let up = plan_migration(dir.path(), "1.20.1", "1.20.6");
+        assert!(up.upgrade);
+        assert_eq!(up.worlds.len(), 2);
+        assert!(up.blocked_worlds.is_empty());
+
+        let down = plan_migration(dir.path(), "1.20.4", "1.20.1");
+        assert!(!down.upgrade);
+        assert_eq!(down.blocked_worlds, vec!["Mundo".to_string()]);
+    }
+
+    #[test]
+    fn backups_are_tagged_with_the_world_version() {
+        let dir = tempfile::tempdir().unwrap();
+        write_world(dir.path(), "survival", "1.20.1");
+        let backups = backup_worlds(dir.path(), &dir.path().join("backups"), "1.20.1", 42).unwrap();
+        assert_eq!(backups, vec![dir.path().join("backups").join("survival-1.20.1-42.zip")]);
+        let archive = zip::ZipArchive::new(fs::File::open(&backups[0]).unwrap()).unwrap();
+        let names = archive.file_names().collect::<Vec<_>>();
+        assert!(names.contains(&"survival/level.dat"));
+        assert!(names.contains(&"survival/region/r.0.0.mca"));
+        assert!(!names.contains(&"survival/session.lock"));
+    }
+}
@@ -0,0 +1,349 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use url::Url;
+use crate::minecraft::models::VersionInfo;
+use crate::minecraft::launch_logic::native_classifier;
+use crate::minecraft::utils::{check_rules, ensure_trailing_slash, get_arch, get_os_name, maven_path, parse_maven_name};
+use crate::settings::{load_settings, save_settings};
+
+const RESOURCES_URL: &str = "https://resources.download.minecraft.net";
+const CHECK_WORKERS: usize = 16;
+// The report lists this many missing files; the count covers all of them
+const MAX_REPORTED: usize = 500;
+
+/// Air-gapped events: every metadata and download URL (Mojang manifest and profiles, libraries,
+/// assets, Java, Fabric meta, Forge maven) is fetched from one staff server instead. The server
+/// mirrors upstream by host and path, `https://piston-meta.mojang.com/mc/game/x.json` becoming
+/// `<base>/piston-meta.mojang.com/mc/game/x.json`: the layout `wget -x` produces. Query strings
+/// are kept, static servers ignore them. URLs already on the mirror are left alone.
+///
+/// The Forge installer downloads its own libraries and can't be redirected; install Forge once
+/// online, or copy its `libraries/` folder, before going offline.
+#[derive(Serialize, Deserialize, Clone, Default, PartialEq, Debug, JsonSchema)]
+pub struct MirrorSettings {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(rename = "baseUrl", alias = "base_url", default)]
+    pub base_url: Option<String>,
+}
+
+impl MirrorSettings {
+    fn active_base(&self) -> Option<String> {
+        self.base_url.as_deref().map(str::trim).filter(|u| self.enabled && !u.is_empty()).map(str::to_string)
+    }
+}
+
+// Read on every download, so kept in memory; `None` until the settings are first read
+static BASE: Mutex<Option<Option<String>>> = Mutex::new(None);
+
+fn base() -> Option<String> {
+    let mut cached = BASE.lock().unwrap_or_else(|e| e.into_inner());
+    cached.get_or_insert_with(|| load_settings().mirror.active_base()).clone()
+}
+
+pub fn active() -> bool {
+    base().is_some()
+}
+
+fn same_server(a: &Url, b: &Url) -> bool {
+    a.host_str() == b.host_str() && a.port_or_known_default() == b.port_or_known_default()
+}
+
+/// Whether `url` is served by the mirror at `base`.
+pub fn on_mirror_with(base: &str, url: &str) -> bool {
+    matches!((Url::parse(url), Url::parse(base)), (Ok(parsed), Ok(base_url)) if same_server(&parsed, &base_url))
+}
+
+/// Whether `url` points at the self-hosted mirror; it never gets API keys or tokens.
+pub fn on_mirror(url: &str) -> bool {
+    base().is_some_and(|base| on_mirror_with(&base, url))
+}
+
+/// `url` on the mirror at `base`.
+pub fn rewrite_with(base: &str, url: &str) -> String {
+    let (Ok(parsed), Ok(base_url)) = (Url::parse(url), Url::parse(base)) else { return url.to_string() };
+    let Some(host) = parsed.host_str() else { return url.to_string() };
+    if same_server(&parsed, &base_url) {
+        return url.to_string();
+    }
+    let host = match parsed.port() {
+        Some(port) => format!("{}:{}", host, port),
+        None => host.to_string(),
+    };
+    let mut rewritten = format!("{}/{}{}", base.trim_end_matches('/'), host, parsed.path());
+    if let Some(query) = parsed.query() {
+        rewritten.push('?');
+        rewritten.push_str(query);
+    }
+    rewritten
+}
+
+/// `url` as it should be fetched: on the mirror when self-hosted mode is on, unchanged otherwise.
+pub fn rewrite(url: &str) -> String {
+    match base() {
+        Some(base) => rewrite_with(&base, url),
+        None => url.to_string(),
+    }
+}
+
+/// Download URLs of a resolved profile: client, asset index and every library (natives included)
+/// that applies to this platform.
+pub fn profile_urls(info: &VersionInfo) -> Vec<String> {
+    let mut urls = Vec::new();
+    if let Some(downloads) = &info.downloads {
+        urls.push(downloads.client.url.clone());
+    }
+    if let Some(index) = &info.asset_index {
+        urls.push(index.url.clone());
+    }
+    for lib in info.libraries.iter().filter(|lib| check_rules(&lib.rules)) {
+        match &lib.downloads {
+            Some(downloads) => {
+                urls.extend(downloads.artifact.iter().map(|a| a.url.clone()));
+                if let (Some(natives), Some(classifiers)) = (&lib.natives, &downloads.classifiers) {
+                    let classifier = native_classifier(natives, get_os_name(), get_arch(), |c| classifiers.contains_key(c));
+                    urls.extend(classifier.and_then(|c| classifiers.get(&c)).map(|a| a.url.clone()));
+                }
+            }
+            None => {
+                let path = parse_maven_name(&lib.name).and_then(|maven| maven_path(&maven));
+                let base = lib.url.clone().unwrap_or_else(|| "https://libraries.minecraft.net/".to_string());
+                urls.extend(path.map(|path| format!("{}{}", ensure_trailing_slash(&base), path)));
+            }
+        }
+    }
+    urls.retain(|url| !url.is_empty());
+    urls
+}
+
+fn asset_object_urls(base_path: &Path, info: &VersionInfo) -> Result<Vec<String>, String> {
+    let Some(index) = &info.asset_index else { return Ok(Vec::new()) };
+    let index_path = base_path.join("assets").join("indexes").join(format!("{}.json", index.id));
+    crate::minecraft::downloader::download_game_file(&index.url, &index_path, Some(&index.sha1))?;
+    let mut urls = Vec::new();
+    crate::minecraft::asset_stream::for_each_object_in(&index_path, |_, object| {
+        urls.push(format!("{}/{}/{}", RESOURCES_URL, &object.hash[..2.min(object.hash.len())], object.hash));
+        true
+    })?;
+    Ok(urls)
+}
+
+/// Everything the instance downloads before its first launch, as upstream URLs. Profiles are
+/// resolved through the mirror, so a missing profile is reported as a problem.
+fn instance_urls(base_path: &Path, instance: &crate::Instance) -> (Vec<String>, Vec<(String, String)>) {
+    let mut urls = Vec::new();
+    let mut problems = Vec::new();
+    let versions_dir = base_path.join("versions");
+    let mc_version = instance.version.as_str();
+    let java_major = crate::minecraft::java::get_required_java_version(mc_version);
+    urls.push(crate::minecraft::java::adoptium_api_url(java_major));
+
+    match crate::minecraft::launch_logic::resolve_complete_version_info(mc_version, &versions_dir) {
+        Ok(info) => {
+            urls.extend(profile_urls(&info));
+            match asset_object_urls(base_path, &info) {
+                Ok(objects) => urls.extend(objects),
+                Err(e) => problems.push((format!("assets {}", mc_version), e)),
+            }
+        }
+        Err(e) => problems.push((format!("perfil {}", mc_version), e)),
+    }
+    match instance.modloader.as_deref().unwrap_or("vanilla") {
+        "fabric" => match crate::minecraft::launch_logic::load_fabric_profile_info(mc_version) {
+            Ok(profile) => urls.extend(profile_urls(&profile)),
+            Err(e) => problems.push((format!("perfil Fabric {}", mc_version), e)),
+        },
+        "forge" => match crate::minecraft::loader_pin::forge_version(&instance.id, mc_version) {
+            Ok(forge) => urls.push(crate::minecraft::launch_logic::forge_installer_url(mc_version, &forge)),
+            Err(e) => problems.push((format!("versión de Forge {}", mc_version), e)),
+        },
+        _ => {}
+    }
+    urls.extend(instance.modpack_url.iter().chain(&instance.translation_pack_url).cloned());
+    urls.extend(instance.mods.iter().flatten().cloned());
+    // Local paths in `mods` never go through the network
+    urls.retain(|url| url.starts_with("http://") || url.starts_with("https://"));
+    (urls, problems)
+}
+
+#[derive(Serialize, Debug, PartialEq, JsonSchema)]
+pub struct MissingFile {
+    /// Upstream URL (or what failed to resolve)
+    pub item: String,
+    #[serde(rename = "instanceId")]
+    pub instance_id: String,
+    pub reason: String,
+}
+
+#[derive(Serialize, Debug, JsonSchema)]
+pub struct MirrorReport {
+    /// Files checked on the mirror
+    pub checked: usize,
+    #[serde(rename = "missingCount")]
+    pub missing_count: usize,
+    /// The first `MAX_REPORTED` problems
+    pub missing: Vec<MissingFile>,
+}
+
+fn head(client: &reqwest::blocking::Client, url: &str) -> Result<(), String> {
+    let resp = crate::http::send(client, client.head(url))?;
+    match resp.status() {
+        s if s.is_success() => Ok(()),
+        // Servers without HEAD: one byte is enough to know the file is there
+        reqwest::StatusCode::METHOD_NOT_ALLOWED => {
+            let resp = crate::http::send(client, client.get(url).header(reqwest::header::RANGE, "bytes=0-0"))?;
+            if resp.status().is_success() { Ok(()) } else { Err(format!("HTTP {}", resp.status())) }
+        }
+        s => Err(format!("HTTP {}", s)),
+    }
+}
+
+/// HEADs every URL (rewritten onto the mirror) with a small worker pool.
+fn check_urls(urls: &BTreeMap<String, String>) -> Vec<MissingFile> {
+    let Ok(client) = reqwest::blocking::Client::builder()
+        .user_agent(crate::http::user_agent())
+        .timeout(Duration::from_secs(20))
+        .build()
+    else {
+        return Vec::new();
+    };
+    let entries: Vec<(&String, &String)> = urls.iter().collect();
+    let next = AtomicUsize::new(0);
+    let missing = Mutex::new(Vec::new());
+    std::thread::scope(|scope| {
+        for _ in 0..CHECK_WORKERS.min(entries.len()) {
+            scope.spawn(|| {
+                while let Some((url, instance_id)) = entries.get(next.fetch_add(1, Ordering::Relaxed)) {
+                    if let Err(reason) = head(&client, &rewrite(url)) {
+                        let item = MissingFile { item: url.to_string(), instance_id: instance_id.to_string(), reason };
+                        missing.lock().unwrap_or_else(|e| e.into_inner()).push(item);
+                    }
+                }
+            });
+        }
+    });
+    let mut missing = missing.into_inner().unwrap_or_else(|e| e.into_inner());
+    missing.sort_by(|a, b| a.item.cmp(&b.item));
+    missing
+}
+
+#[tauri::command]
+pub fn get_mirror_settings() -> MirrorSettings {
+    load_settings().mirror
+}
+
+#[tauri::command]
+pub fn set_mirror_settings(password: String, mirror: MirrorSettings) -> Result<(), String> {
+    crate::ensure_admin(password)?;
+    if let Some(base) = mirror.base_url.as_deref().filter(|u| !u.trim().is_empty()) {
+        Url::parse(base.trim()).map_err(|e| format!("URL del mirror no válida: {}", e))?;
+    }
+    let mut settings = load_settings();
+    settings.mirror = mirror;
+    save_settings(&settings)?;
+    *BASE.lock().unwrap_or_else(|e| e.into_inner()) = Some(settings.mirror.active_base());
+    Ok(())
+}
+
+/// Checks that the mirror has everything `instance_ids` (all instances when empty) need. Only
+/// meaningful with self-hosted mode on.
+#[tauri::command]
+pub async fn validate_mirror(password: String, instance_ids: Vec<String>) -> Result<MirrorReport, String> {
+    crate::ensure_admin(password)?;
+    if !active() {
+        return Err("El modo de mirror propio no está activado".to_string());
+    }
+    crate::run_blocking(move || {
+        let base_path = std::path::PathBuf::from(crate::get_instances_dir());
+        let instances: Vec<crate::Instance> = crate::load_instances()
+            .into_iter()
+            .filter(|i| instance_ids.is_empty() || instance_ids.contains(&i.id))
+            .collect();
+        // First instance needing a URL is the one reported for it
+        let mut urls = BTreeMap::new();
+        let mut missing = Vec::new();
+        for instance in &instances {
+            let (needed, problems) = instance_urls(&base_path, instance);
+            for url in needed {
+                urls.entry(url).or_insert_with(|| instance.id.clone());
+            }
+            missing.extend(problems.into_iter().map(|(item, reason)| MissingFile { item, instance_id: instance.id.clone(), reason }));
+        }
+        missing.extend(check_urls(&urls));
+        let missing_count = missing.len();
+        missing.truncate(MAX_REPORTED);
+        Ok(MirrorReport { checked: urls.len(), missing_count, missing })
+    }).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn urls_move_onto_the_mirror_by_host_and_path() {
+        let base = "http://mirror.lan:8080/drk/";
+        assert_eq!(
+            rewrite_with(base, "https://piston-meta.mojang.com/mc/game/version_manifest_v2.json"),
+            "http://mirror.lan:8080/drk/piston-meta.mojang.com/mc/game/version_manifest_v2.json"
+        );
+        assert_eq!(
+            rewrite_with(base, "https://api.adoptium.net/v3/assets/latest/17/hotspot?architecture=x64&os=windows"),
+            "http://mirror.lan:8080/drk/api.adoptium.net/v3/assets/latest/17/hotspot?architecture=x64&os=windows"
+        );
+        assert_eq!(rewrite_with(base, "http://local.lan:81/pack.zip"), "http://mirror.lan:8080/drk/local.lan:81/pack.zip");
+        // Already on the mirror, or not a URL at all
+        assert_eq!(rewrite_with(base, "http://mirror.lan:8080/packs/evento.zip"), "http://mirror.lan:8080/packs/evento.zip");
+        assert_eq!(rewrite_with(base, "mods/local.jar"), "mods/local.jar");
+        assert!(on_mirror_with(base, &rewrite_with(base, "https://api.github.com/repos/x/releases")));
+        assert!(!on_mirror_with(base, "https://api.github.com/repos/x/releases"));
+    }
+
+    #[test]
+    fn only_game_downloads_move_onto_the_mirror() {
+        use std::io::{Read, Write};
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/status.json", listener.local_addr().unwrap());
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = [0u8; 1024];
+            let read = stream.read(&mut request).unwrap();
+            stream.write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\nConnection: close\r\n\r\nok").unwrap();
+            String::from_utf8_lossy(&request[..read]).into_owned()
+        });
+        *BASE.lock().unwrap() = Some(Some("http://mirror.invalid/drk".to_string()));
+        assert_ne!(rewrite(&url), url);
+        // Status feeds, reports and APIs go where they were addressed, API keys included
+        let client = reqwest::blocking::Client::new();
+        let body = crate::http::send(&client, client.get(&url)).and_then(|r| r.text().map_err(|e| e.to_string()));
+        *BASE.lock().unwrap() = None;
+        assert_eq!(body.unwrap(), "ok");
+        assert!(server.join().unwrap().starts_with("GET /status.json "));
+    }
+
+    #[test]
+    fn profile_urls_cover_client_index_and_libraries() {
+        let info: VersionInfo = serde_json::from_value(serde_json::json!({
+            "id": "1.20.1",
+            "mainClass": "net.minecraft.client.main.Main",
+            "type": "release",
+            "downloads": {"client": {"url": "https://piston-data.mojang.com/client.jar", "sha1": "a", "size": 1}},
+            "assetIndex": {"id": "5", "url": "https://piston-meta.mojang.com/5.json", "sha1": "b", "size": 1, "totalSize": 1},
+            "libraries": [
+                {"name": "com.mojang:brigadier:1.1.8", "downloads": {"artifact": {"url": "https://libraries.minecraft.net/brigadier.jar", "sha1": "c", "size": 1}}},
+                {"name": "net.fabricmc:fabric-loader:0.15.11", "url": "https://maven.fabricmc.net"}
+            ]
+        })).unwrap();
+        assert_eq!(profile_urls(&info), vec![
+            "https://piston-data.mojang.com/client.jar",
+            "https://piston-meta.mojang.com/5.json",
+            "https://libraries.minecraft.net/brigadier.jar",
+            "https://maven.fabricmc.net/net/fabricmc/fabric-loader/0.15.11/fabric-loader-0.15.11.jar",
+        ]);
+    }
+}
@@ -0,0 +1,283 @@
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use url::Url;
+use crate::minecraft::content_source::{self, Target};
+use crate::minecraft::url_resolver::mod_file_name;
+
+const CHECK_WORKERS: usize = 8;
+// Smaller than any real mod jar; error pages and empty placeholders usually are
+const MIN_JAR_BYTES: u64 = 1024;
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum LinkStatus {
+    Ok,
+    /// Probably downloads, but worth a look
+    Warning,
+    /// Will fail (or install garbage) on event night
+    Broken,
+}
+
+#[derive(Serialize, Clone, Debug, JsonSchema)]
+pub struct ModUrlCheck {
+    pub url: String,
+    /// Direct download the launcher will use, when it differs from `url` (CurseForge, Modrinth...)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resolved: Option<String>,
+    pub status: LinkStatus,
+    #[serde(rename = "httpStatus", skip_serializing_if = "Option::is_none")]
+    pub http_status: Option<u16>,
+    #[serde(rename = "contentType", skip_serializing_if = "Option::is_none")]
+    pub content_type: Option<String>,
+    /// Bytes, when the server says
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub size: Option<u64>,
+    /// Name the jar gets in `mods/`
+    #[serde(rename = "fileName")]
+    pub file_name: String,
+    pub problems: Vec<String>,
+}
+
+#[derive(Serialize, Clone, Debug, JsonSchema)]
+pub struct ModUrlReport {
+    /// Same order as the URLs given
+    pub checks: Vec<ModUrlCheck>,
+    pub ok: usize,
+    pub warnings: usize,
+    pub broken: usize,
+}
+
+/// What the response headers say about a mod download.
+fn classify(http_status: u16, content_type: Option<&str>, size: Option<u64>) -> (LinkStatus, Vec<String>) {
+    if !(200..300).contains(&http_status) {
+        let problem = match http_status {
+            404 | 410 => "El archivo ya no existe (enlace muerto)".to_string(),
+            401 | 403 => "El servidor no permite la descarga (requiere sesión o permisos)".to_string(),
+            _ => format!("El servidor respondió HTTP {}", http_status),
+        };
+        return (LinkStatus::Broken, vec![problem]);
+    }
+    let mut status = LinkStatus::Ok;
+    let mut problems = Vec::new();
+    let mime = content_type
+        .map(|c| c.split(';').next().unwrap_or("").trim().to_ascii_lowercase())
+        .filter(|c| !c.is_empty());
+    match mime.as_deref() {
+        Some("text/html") | Some("application/xhtml+xml") => {
+            status = LinkStatus::Broken;
+            problems.push("Devuelve una página web, no un archivo .jar (¿enlace de vista previa o de inicio de sesión?)".to_string());
+        }
+        Some(m) if m.starts_with("text/") || m.ends_with("json") || m.ends_with("xml") => {
+            status = LinkStatus::Warning;
+            problems.push(format!("Tipo de contenido inesperado para un mod: {}", m));
+        }
+        Some(_) => {}
+        None => {
+            status = LinkStatus::Warning;
+            problems.push("El servidor no indica el tipo de contenido".to_string());
+        }
+    }
+    match size {
+        Some(0) => {
+            status = LinkStatus::Broken;
+            problems.push("El archivo está vacío".to_string());
+        }
+        Some(bytes) if bytes < MIN_JAR_BYTES => {
+            status = status.max(LinkStatus::Warning);
+            problems.push(format!("Demasiado pequeño para ser un mod ({} bytes)", bytes));
+        }
+        Some(_) => {}
+        None => {
+            status = status.max(LinkStatus::Warning);
+            problems.push("El servidor no indica el tamaño del archivo".to_string());
+        }
+    }
+    (status, problems)
+}
+
+/// Total size from `Content-Range: bytes 0-0/12345`.
+fn range_total(value: &str) -> Option<u64> {
+    value.rsplit('/').next()?.trim().parse().ok()
+}
+
+/// Status, content type and size, from a HEAD or, for servers without it, a one-byte GET.
+fn probe(client: &reqwest::blocking::Client, url: &str) -> Result<(u16, Option<String>, Option<u64>), String> {
+    let header = |resp: &reqwest::blocking::Response, name: reqwest::header::HeaderName| {
+        resp.headers().get(name).and_then(|v| v.to_str().ok()).map(str::to_string)
+    };
+    let resp = crate::http::send(client, client.head(url))?;
+    if !matches!(resp.status(), reqwest::StatusCode::METHOD_NOT_ALLOWED | reqwest::StatusCode::NOT_IMPLEMENTED) {
+        // `content_length()` is the (empty) body's length on a HEAD, the header is the file's
+        let size = header(&resp, reqwest::header::CONTENT_LENGTH).and_then(|v| v.trim().parse().ok());
+        return Ok((resp.status().as_u16(), header(&resp, reqwest::header::CONTENT_TYPE), size));
+    }
+    let resp = crate::http::send(client, client.get(url).header(reqwest::header::RANGE, "bytes=0-0"))?;
+    let size = match resp.status() {
+        reqwest::StatusCode::PARTIAL_CONTENT => header(&resp, reqwest::header::CONTENT_RANGE).and_then(|v| range_total(&v)),
+        _ => header(&resp, reqwest::header::CONTENT_LENGTH).and_then(|v| v.trim().parse().ok()),
+    };
+    Ok((resp.status().as_u16(), header(&resp, reqwest::header::CONTENT_TYPE), size))
+}
+
+fn check(client: &reqwest::blocking::Client, raw: &str) -> ModUrlCheck {
+    let url = raw.trim().to_string();
+    let mut result = ModUrlCheck {
+        file_name: mod_file_name(&url),
+        url,
+        resolved: None,
+        status: LinkStatus::Broken,
+        http_status: None,
+        content_type: None,
+        size: None,
+        problems: Vec::new(),
+    };
+    // References (`modrinth:`, `admin:`...) are checked where they resolve to
+    let direct = match content_source::resolve(&result.url, &Target::default()) {
+        Ok(resolved) => resolved.url,
+        Err(e) => {
+            result.problems.push(format!("No se pudo obtener el enlace de descarga: {}", e));
+            return result;
+        }
+    };
+    if !Url::parse(&direct).is_ok_and(|u| matches!(u.scheme(), "http" | "https")) {
+        result.problems.push("No es una URL http(s) válida".to_string());
+        return result;
+    }
+    match probe(client, &direct) {
+        Ok((http_status, content_type, size)) => {
+            let (status, problems) = classify(http_status, content_type.as_deref(), size);
+            result.status = status;
+            result.problems = problems;
+            result.http_status = Some(http_status);
+            result.content_type = content_type;
+            result.size = size;
+        }
+        Err(e) => result.problems.push(format!("Sin respuesta del servidor: {}", e)),
+    }
+    if direct != result.url {
+        result.resolved = Some(direct);
+    }
+    result
+}
+
+/// Problems only visible across the whole list: the same link twice, or two links that would
+/// be saved under the same jar name and overwrite each other.
+fn flag_collisions(checks: &mut [ModUrlCheck]) {
+    let mut by_url: HashMap<String, usize> = HashMap::new();
+    let mut by_name: HashMap<String, usize> = HashMap::new();
+    for (i, check) in checks.iter_mut().enumerate() {
+        let name = check.file_name.to_lowercase();
+        let problem = if let Some(&first) = by_url.get(&check.url) {
+            Some(format!("URL repetida (también en la posición {})", first + 1))
+        } else if let Some(&first) = by_name.get(&name) {
+            Some(format!("Se guardaría como {}, igual que la URL de la posición {}", check.file_name, first + 1))
+        } else {
+            None
+        };
+        by_url.entry(check.url.clone()).or_insert(i);
+        by_name.entry(name).or_insert(i);
+        if let Some(problem) = problem {
+            check.status = check.status.max(LinkStatus::Warning);
+            check.problems.push(problem);
+        }
+    }
+}
+
+fn check_all(urls: &[String]) -> Result<Vec<ModUrlCheck>, String> {
+    let client = reqwest::blocking::Client::builder()
+        .user_agent(crate::http::user_agent())
+        .timeout(Duration::from_secs(20))
+        .build()
+        .map_err(|e| e.to_string())?;
+    let next = AtomicUsize::new(0);
+    let results = Mutex::new(Vec::with_capacity(urls.len()));
+    std::thread::scope(|scope| {
+        for _ in 0..CHECK_WORKERS.min(urls.len()) {
+            scope.spawn(|| {
+                loop {
+                    let index = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(url) = urls.get(index) else { break };
+                    let result = check(&client, url);
+                    results.lock().unwrap_or_else(|e| e.into_inner()).push((index, result));
+                }
+            });
+        }
+    });
+    let mut results = results.into_inner().unwrap_or_else(|e| e.into_inner());
+    results.sort_by_key(|(index, _)| *index);
+    let mut checks: Vec<ModUrlCheck> = results.into_iter().map(|(_, c)| c).collect();
+    flag_collisions(&mut checks);
+    Ok(checks)
+}
+
+/// Checks an instance's mod URLs before saving it: every link is HEADed in parallel and
+/// dead links, web pages served instead of jars and suspicious sizes are reported per URL.
+#[tauri::command]
+pub async fn validate_mod_urls(password: String, urls: Vec<String>) -> Result<ModUrlReport, String> {
+    crate::ensure_admin(password)?;
+    let urls: Vec<String> = urls.into_iter().filter(|u| !u.trim().is_empty()).collect();
+    crate::run_blocking(move || {
+        let checks = check_all(&urls)?;
+        let count = |status| checks.iter().filter(|c| c.status == status).count();
+        Ok(ModUrlReport {
+            ok: count(LinkStatus::Ok),
+            warnings: count(LinkStatus::Warning),
+            broken: count(LinkStatus::Broken),
+            checks,
+        })
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn headers_are_classified() {
+        let jar = Some("application/java-archive");
+        assert_eq!(classify(200, jar, Some(250_000)), (LinkStatus::Ok, vec![]));
+        assert_eq!(classify(200, Some("application/octet-stream"), Some(250_000)).0, LinkStatus::Ok);
+        assert_eq!(classify(404, jar, Some(250_000)).0, LinkStatus::Broken);
+        assert_eq!(classify(200, Some("text/html; charset=utf-8"), Some(250_000)).0, LinkStatus::Broken);
+        assert_eq!(classify(200, Some("application/json"), Some(250_000)).0, LinkStatus::Warning);
+        assert_eq!(classify(200, None, Some(250_000)).0, LinkStatus::Warning);
+        assert_eq!(classify(200, jar, Some(0)).0, LinkStatus::Broken);
+        assert_eq!(classify(200, jar, Some(300)).0, LinkStatus::Warning);
+        assert_eq!(classify(200, jar, None).0, LinkStatus::Warning);
+        // A web page stays broken whatever its size
+        assert_eq!(classify(200, Some("text/html"), Some(300)).1.len(), 2);
+        assert_eq!(classify(200, Some("text/html"), Some(300)).0, LinkStatus::Broken);
+
+        assert_eq!(range_total("bytes 0-0/123456"), Some(123456));
+        assert_eq!(range_total("bytes 0-0/*"), None);
+    }
+
+    #[test]
+    fn repeated_links_and_names_are_flagged() {
+        let ok = |url: &str| ModUrlCheck {
+            url: url.to_string(),
+            resolved: None,
+            status: LinkStatus::Ok,
+            http_status: Some(200),
+            content_type: None,
+            size: None,
+            file_name: mod_file_name(url),
+            problems: vec![],
+        };
+        let mut checks = vec![
+            ok("https://a.example/mods/sodium.jar"),
+            ok("https://b.example/mods/lithium.jar"),
+            ok("https://a.example/mods/sodium.jar"),
+            ok("https://c.example/other/Sodium.jar"),
+        ];
+        flag_collisions(&mut checks);
+        let statuses: Vec<LinkStatus> = checks.iter().map(|c| c.status).collect();
+        assert_eq!(statuses, [LinkStatus::Ok, LinkStatus::Ok, LinkStatus::Warning, LinkStatus::Warning]);
+        assert!(checks[2].problems[0].contains("posición 1"));
+    }
+}
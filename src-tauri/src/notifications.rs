@@ -0,0 +1,26 @@
+use tauri::{AppHandle, Manager};
+use tauri_plugin_notification::NotificationExt;
+use crate::settings::load_settings;
+
+fn window_hidden(app: &AppHandle) -> bool {
+    match app.get_webview_window("main") {
+        Some(window) => window.is_minimized().unwrap_or(false) || !window.is_visible().unwrap_or(true),
+        None => true,
+    }
+}
+
+/// Native notification for a background task (`what`, e.g. "Verificación") that finished for
+/// `instance_name`. Respects the notification settings; failures are only logged.
+pub fn notify_completion(app: &AppHandle, instance_name: &str, what: &str, result: &Result<(), String>) {
+    let settings = load_settings().notifications;
+    if !settings.enabled || (settings.only_when_minimized && !window_hidden(app)) {
+        return;
+    }
+    let (title, body) = match result {
+        Ok(()) => (format!("{} completada", what), format!("{} está lista", instance_name)),
+        Err(e) => (format!("{} fallida", what), format!("{}: {}", instance_name, e)),
+    };
+    if let Err(e) = app.notification().builder().title(title).body(body).show() {
+        eprintln!("[notifications] Failed to show notification: {}", e);
+    }
+}
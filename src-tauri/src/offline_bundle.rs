@@ -0,0 +1,378 @@
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use crate::minecraft::archive::walk_files;
+use crate::minecraft::java::JavaRelease;
+use crate::minecraft::launch_logic::native_classifier;
+use crate::minecraft::models::VersionInfo;
+use crate::minecraft::utils::{check_rules, get_arch, get_os_name, maven_path, parse_maven_name};
+use crate::minecraft::version_store::LocalVersions;
+
+/// 2: Java moved from `java/<major>` to `java/runtimes/<build>`, pinned in the instance
+const BUNDLE_VERSION: u32 = 2;
+const MANIFEST_NAME: &str = "manifest.json";
+/// What the instance folder contributes; saves, logs and screenshots stay behind
+const INSTANCE_PARTS: [&str; 6] = ["client.jar", "mods", "config", "resourcepacks", "modpack.zip", ".pack-mods.json"];
+
+/// A file to pack: where it goes in the archive (relative to the data folder, instance files
+/// under `instance/`) and which part of the report it counts towards.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BundleFile {
+    pub name: String,
+    pub source: PathBuf,
+    pub section: &'static str,
+}
+
+#[derive(Serialize, Debug)]
+struct ManifestFile {
+    path: String,
+    size: u64,
+    /// Left out for asset objects, whose name is already their hash
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sha1: Option<String>,
+}
+
+/// The runtime the instance launches with on this machine.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BundledJava {
+    pub major: u32,
+    /// `None` when there is no Java for `major` here
+    pub dir: Option<PathBuf>,
+    pub release: Option<JavaRelease>,
+}
+
+impl BundledJava {
+    /// Same lookup as a launch: the pinned runtime, then the system or embedded Java.
+    pub fn resolve(root: &Path, instance: &crate::Instance) -> Self {
+        use crate::minecraft::java;
+        let major = java::get_required_java_version(&instance.version);
+        let pinned = java::pinned_runtime(root, instance);
+        let dir = java::get_instance_java_path(root, major, pinned.as_deref()).ok()
+            .and_then(|path| java::runtime_home(&path))
+            .filter(|dir| dir.is_dir());
+        let release = dir.as_deref().and_then(java::read_release);
+        BundledJava { major, dir, release }
+    }
+
+    /// Where the runtime goes in the bundle and, after import, under the data folder.
+    pub fn bundle_dir(&self) -> String {
+        let id = self.release.as_ref().map(JavaRelease::runtime_id).unwrap_or_else(|| self.major.to_string());
+        format!("java/runtimes/{}", id)
+    }
+}
+
+#[derive(Serialize, Debug)]
+struct JavaManifest {
+    /// Folder of the runtime in the bundle, also set as the instance's `javaRuntime`
+    path: String,
+    /// Contents of its `release` file; `None` when it has none
+    release: Option<JavaRelease>,
+}
+
+#[derive(Serialize, Debug)]
+struct BundleManifest {
+    v: u32,
+    #[serde(rename = "createdAt")]
+    created_at: u64,
+    #[serde(rename = "launcherVersion")]
+    launcher_version: String,
+    #[serde(rename = "javaMajor")]
+    java_major: u32,
+    java: Option<JavaManifest>,
+    /// Instance definition, as in `instances.json`
+    instance: serde_json::Value,
+    files: Vec<ManifestFile>,
+}
+
+#[derive(Serialize, Debug, Default, Clone, PartialEq, JsonSchema)]
+pub struct SectionSize {
+    pub files: usize,
+    pub bytes: u64,
+}
+
+#[derive(Serialize, Debug, JsonSchema)]
+pub struct BundleReport {
+    pub file: String,
+    pub files: usize,
+    /// Uncompressed size of everything packed
+    #[serde(rename = "totalBytes")]
+    pub total_bytes: u64,
+    #[serde(rename = "archiveBytes")]
+    pub archive_bytes: u64,
+    /// client, libraries, assets, java and instance
+    pub sections: BTreeMap<String, SectionSize>,
+    /// Needed but not on this machine; launch the instance once online and export again
+    pub missing: Vec<String>,
+}
+
+/// The instance's own profile plus, for loaders, the installed loader profiles built on it.
+fn installed_profiles(versions_dir: &Path, version: &str, loader: Option<&str>) -> Vec<VersionInfo> {
+    let store = LocalVersions::new(versions_dir);
+    let mut profiles: Vec<VersionInfo> = store.load(version).ok().flatten().into_iter().collect();
+    let Some(loader) = loader.filter(|l| *l != "vanilla") else { return profiles };
+    let Ok(entries) = fs::read_dir(versions_dir) else { return profiles };
+    let mut ids: Vec<String> = entries.flatten()
+        .filter_map(|e| e.file_name().to_str().map(str::to_string))
+        .filter(|id| id != version && id.to_lowercase().contains(&loader.to_lowercase()))
+        .collect();
+    ids.sort();
+    profiles.extend(ids.iter()
+        .filter_map(|id| store.load(id).ok().flatten())
+        .filter(|info| info.inherits_from.as_deref() == Some(version)));
+    profiles
+}
+
+/// `libraries/` paths of a profile's jars (natives for this platform included).
+fn library_paths(info: &VersionInfo) -> Vec<String> {
+    let mut paths = Vec::new();
+    for lib in info.libraries.iter().filter(|lib| check_rules(&lib.rules)) {
+        match &lib.downloads {
+            Some(downloads) => {
+                paths.extend(downloads.artifact.iter().filter_map(|a| a.path.clone()));
+                if let (Some(natives), Some(classifiers)) = (&lib.natives, &downloads.classifiers) {
+                    let classifier = native_classifier(natives, get_os_name(), get_arch(), |c| classifiers.contains_key(c));
+                    paths.extend(classifier.and_then(|c| classifiers.get(&c)).and_then(|a| a.path.clone()));
+                }
+            }
+            None => paths.extend(parse_maven_name(&lib.name).and_then(|maven| maven_path(&maven))),
+        }
+    }
+    paths
+}
+
+/// Adds `root/name` when it exists, records it as missing otherwise.
+fn add_file(files: &mut Vec<BundleFile>, missing: &mut Vec<String>, root: &Path, name: String, section: &'static str) {
+    let source = root.join(&name);
+    if source.is_file() {
+        files.push(BundleFile { name, source, section });
+    } else {
+        missing.push(name);
+    }
+}
+
+/// Every file launching `instance` offline needs, read from what is installed here. Nothing is
+/// downloaded: what was never installed is listed in the second value.
+pub fn bundle_files(root: &Path, instance: &crate::Instance, java: &BundledJava) -> (Vec<BundleFile>, Vec<String>) {
+    let mut files = Vec::new();
+    let mut missing = Vec::new();
+    let versions_dir = root.join("versions");
+    let profiles = installed_profiles(&versions_dir, &instance.version, instance.modloader.as_deref());
+    if profiles.is_empty() {
+        missing.push(format!("versions/{0}/{0}.json", instance.version));
+    }
+
+    for info in &profiles {
+        let store = LocalVersions::new(&versions_dir);
+        let json = store.json_path(&info.id);
+        let name = json.strip_prefix(root).unwrap_or(&json).to_string_lossy().replace('\\', "/");
+        add_file(&mut files, &mut missing, root, name, "client");
+        if info.downloads.is_some() {
+            add_file(&mut files, &mut missing, root, format!("versions/{0}/{0}.jar", info.id), "client");
+        }
+        for path in library_paths(info) {
+            add_file(&mut files, &mut missing, root, format!("libraries/{}", path), "libraries");
+        }
+        if let Some(index) = &info.asset_index {
+            let index_name = format!("assets/indexes/{}.json", index.id);
+            let index_path = root.join(&index_name);
+            add_file(&mut files, &mut missing, root, index_name, "assets");
+            // Only the objects this version's index lists, not the whole shared folder
+            let _ = crate::minecraft::asset_stream::for_each_object_in(&index_path, |_, object| {
+                if object.hash.len() > 2 {
+                    let name = format!("assets/objects/{}/{}", &object.hash[..2], object.hash);
+                    add_file(&mut files, &mut missing, root, name, "assets");
+                }
+                true
+            });
+        }
+    }
+
+    // The whole runtime folder, `release` included, wherever it is installed here
+    match &java.dir {
+        Some(java_dir) => files.extend(walk_files(java_dir).into_iter().map(|(name, source)| BundleFile {
+            name: format!("{}/{}", java.bundle_dir(), name),
+            source,
+            section: "java",
+        })),
+        None => missing.push(format!("java/{}", java.major)),
+    }
+
+    let minecraft_dir = Path::new(&instance.path).join("minecraft");
+    for part in INSTANCE_PARTS {
+        let path = minecraft_dir.join(part);
+        if path.is_file() {
+            files.push(BundleFile { name: format!("instance/minecraft/{}", part), source: path, section: "instance" });
+        } else if path.is_dir() {
+            files.extend(walk_files(&path).into_iter().map(|(name, source)| BundleFile {
+                name: format!("instance/minecraft/{}/{}", part, name),
+                source,
+                section: "instance",
+            }));
+        }
+    }
+
+    // Loader profiles share most libraries with the vanilla one
+    files.sort_by(|a, b| a.name.cmp(&b.name));
+    files.dedup_by(|a, b| a.name == b.name);
+    missing.sort();
+    missing.dedup();
+    (files, missing)
+}
+
+fn write_bundle(target: &Path, files: &[BundleFile], manifest_for: impl FnOnce(Vec<ManifestFile>) -> BundleManifest) -> Result<(), String> {
+    let partial = crate::temp_files::tmp_path(target);
+    let mut zip = zip::ZipWriter::new(File::create(&partial).map_err(|e| format!("No se pudo crear {}: {}", partial.display(), e))?);
+    let mut entries = Vec::with_capacity(files.len());
+    for file in files {
+        let metadata = fs::metadata(&file.source).map_err(|e| format!("No se pudo leer {}: {}", file.source.display(), e))?;
+        let size = metadata.len();
+        let options = zip::write::FileOptions::default().large_file(true);
+        // Java's binaries must stay executable
+        #[cfg(unix)]
+        let options = {
+            use std::os::unix::fs::PermissionsExt;
+            options.unix_permissions(metadata.permissions().mode())
+        };
+        let sha1 = match file.name.starts_with("assets/objects/") {
+            true => None,
+            false => Some(crate::minecraft::downloader::file_sha1(&file.source)?),
+        };
+        zip.start_file(file.name.as_str(), options).map_err(|e| e.to_string())?;
+        let mut source = File::open(&file.source).map_err(|e| format!("No se pudo leer {}: {}", file.source.display(), e))?;
+        io::copy(&mut source, &mut zip).map_err(|e| e.to_string())?;
+        entries.push(ManifestFile { path: file.name.clone(), size, sha1 });
+    }
+    let manifest = serde_json::to_vec_pretty(&manifest_for(entries)).map_err(|e| e.to_string())?;
+    zip.start_file(MANIFEST_NAME, zip::write::FileOptions::default()).map_err(|e| e.to_string())?;
+    zip.write_all(&manifest).map_err(|e| e.to_string())?;
+    zip.finish().map_err(|e| e.to_string())?;
+    fs::rename(&partial, target).map_err(|e| format!("No se pudo guardar {}: {}", target.display(), e))
+}
+
+fn section_sizes(files: &[BundleFile]) -> BTreeMap<String, SectionSize> {
+    let mut sections: BTreeMap<String, SectionSize> = BTreeMap::new();
+    for file in files {
+        let size = sections.entry(file.section.to_string()).or_default();
+        size.files += 1;
+        size.bytes += fs::metadata(&file.source).map(|m| m.len()).unwrap_or(0);
+    }
+    sections
+}
+
+/// Packs everything `instance_id` needs to launch without Internet into one zip at `file`, with
+/// a `manifest.json` listing each file for a later import.
+#[tauri::command]
+pub async fn export_offline_bundle(password: String, instance_id: String, file: String) -> Result<BundleReport, String> {
+    crate::ensure_admin(password)?;
+    crate::run_blocking(move || {
+        let instance = crate::load_instances().into_iter().find(|i| i.id == instance_id)
+            .ok_or("Instance not found")?;
+        let root = PathBuf::from(crate::get_instances_dir());
+        let java = BundledJava::resolve(&crate::sandbox::base_path(&instance), &instance);
+        let (files, missing) = bundle_files(&root, &instance, &java);
+        let target = PathBuf::from(&file);
+        let mut definition = serde_json::to_value(&instance).map_err(|e| e.to_string())?;
+        // Launches on the importing machine use the packed runtime, not whatever Java it has
+        if java.dir.is_some() {
+            definition["javaRuntime"] = java.bundle_dir().into();
+        }
+        write_bundle(&target, &files, |entries| BundleManifest {
+            v: BUNDLE_VERSION,
+            created_at: crate::unix_now(),
+            launcher_version: env!("CARGO_PKG_VERSION").to_string(),
+            java_major: java.major,
+            java: java.dir.as_ref().map(|_| JavaManifest { path: java.bundle_dir(), release: java.release.clone() }),
+            instance: definition,
+            files: entries,
+        })?;
+        let sections = section_sizes(&files);
+        Ok(BundleReport {
+            file,
+            files: files.len(),
+            total_bytes: sections.values().map(|s| s.bytes).sum(),
+            archive_bytes: fs::metadata(&target).map(|m| m.len()).unwrap_or(0),
+            sections,
+            missing,
+        })
+    }).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write(root: &Path, name: &str, contents: &str) {
+        let path = root.join(name);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+    }
+
+    fn instance(path: &Path, version: &str, modloader: &str) -> crate::Instance {
+        serde_json::from_value(serde_json::json!({
+            "id": "evento", "name": "Evento", "version": version, "icon": "", "path": path.to_string_lossy(),
+            "modloader": modloader,
+        })).unwrap()
+    }
+
+    #[test]
+    fn bundle_takes_the_loader_profile_and_only_indexed_assets() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        write(root, "versions/1.20.1/1.20.1.json", &serde_json::json!({
+            "id": "1.20.1", "mainClass": "net.minecraft.client.main.Main", "type": "release",
+            "downloads": {"client": {"url": "https://x/client.jar", "sha1": "a", "size": 1}},
+            "assetIndex": {"id": "5", "url": "https://x/5.json", "sha1": "b", "size": 1, "totalSize": 1},
+            "libraries": [{"name": "com.mojang:brigadier:1.1.8", "downloads": {"artifact": {"path": "com/mojang/brigadier/1.1.8/brigadier-1.1.8.jar", "url": "https://x/b.jar", "sha1": "c", "size": 1}}}]
+        }).to_string());
+        write(root, "versions/1.20.1/1.20.1.jar", "client");
+        write(root, "versions/fabric-loader-0.15.11-1.20.1/fabric-loader-0.15.11-1.20.1.json", &serde_json::json!({
+            "id": "fabric-loader-0.15.11-1.20.1", "inheritsFrom": "1.20.1", "mainClass": "net.fabricmc.loader.impl.launch.knot.KnotClient", "type": "release",
+            "libraries": [{"name": "net.fabricmc:fabric-loader:0.15.11", "url": "https://maven.fabricmc.net/"}]
+        }).to_string());
+        write(root, "versions/1.19.4/1.19.4.json", "{}");
+        write(root, "libraries/com/mojang/brigadier/1.1.8/brigadier-1.1.8.jar", "lib");
+        write(root, "assets/indexes/5.json", r#"{"objects": {"a": {"hash": "ab00", "size": 1}, "b": {"hash": "cd00", "size": 1}}}"#);
+        write(root, "assets/objects/ab/ab00", "x");
+        write(root, "assets/objects/ef/ef00", "not in the index");
+        write(root, "java/17/bin/java", "java");
+        write(root, "java/17/release", "JAVA_VERSION=\"17.0.9\"\nJAVA_RUNTIME_VERSION=\"17.0.9+9\"\nOS_ARCH=\"x86_64\"\n");
+        let instance_dir = root.join("instancias/evento");
+        write(&instance_dir, "minecraft/mods/a.jar", "mod");
+        write(&instance_dir, "minecraft/saves/World/level.dat", "save");
+
+        let java_dir = root.join("java/17");
+        let java = BundledJava { major: 17, release: crate::minecraft::java::read_release(&java_dir), dir: Some(java_dir) };
+        assert_eq!(java.bundle_dir(), "java/runtimes/17.0.9+9-x86_64");
+        let (files, missing) = bundle_files(root, &instance(&instance_dir, "1.20.1", "fabric"), &java);
+        let names: Vec<&str> = files.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(names, vec![
+            "assets/indexes/5.json",
+            "assets/objects/ab/ab00",
+            "instance/minecraft/mods/a.jar",
+            "java/runtimes/17.0.9+9-x86_64/bin/java",
+            "java/runtimes/17.0.9+9-x86_64/release",
+            "libraries/com/mojang/brigadier/1.1.8/brigadier-1.1.8.jar",
+            "versions/1.20.1/1.20.1.jar",
+            "versions/1.20.1/1.20.1.json",
+            "versions/fabric-loader-0.15.11-1.20.1/fabric-loader-0.15.11-1.20.1.json",
+        ]);
+        assert_eq!(missing, vec![
+            "assets/objects/cd/cd00",
+            "libraries/net/fabricmc/fabric-loader/0.15.11/fabric-loader-0.15.11.jar",
+        ]);
+        assert_eq!(section_sizes(&files)["assets"], SectionSize { files: 2, bytes: 82 });
+
+        let target = root.join("bundle.zip");
+        write_bundle(&target, &files, |entries| BundleManifest {
+            v: BUNDLE_VERSION, created_at: 0, launcher_version: "test".into(), java_major: 17, java: None,
+            instance: serde_json::Value::Null, files: entries,
+        }).unwrap();
+        let mut zip = zip::ZipArchive::new(File::open(&target).unwrap()).unwrap();
+        assert_eq!(zip.len(), files.len() + 1);
+        let manifest: serde_json::Value = serde_json::from_reader(zip.by_name(MANIFEST_NAME).unwrap()).unwrap();
+        assert_eq!(manifest["files"][1], serde_json::json!({"path": "assets/objects/ab/ab00", "size": 1}));
+    }
+}
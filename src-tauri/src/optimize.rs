@@ -0,0 +1,115 @@
+use std::path::Path;
+use schemars::JsonSchema;
+use serde::Serialize;
+use crate::minecraft::game_options::{apply_option_values, low_end_options};
+use crate::minecraft::modrinth::latest_file_url;
+
+pub const LOW_END_PROFILE: &str = "low-end";
+const LOW_END_MIN_RAM_MB: u64 = 1024;
+const LOW_END_MAX_RAM_MB: u64 = 3072;
+
+/// Performance mods per loader, as Modrinth project slugs. Quilt runs the Fabric builds.
+const PERFORMANCE_MODS: [(&str, &[&str]); 4] = [
+    ("fabric", &["sodium", "lithium", "ferrite-core"]),
+    ("quilt", &["sodium", "lithium", "ferrite-core"]),
+    ("forge", &["embeddium", "ferrite-core"]),
+    ("neoforge", &["embeddium", "ferrite-core"]),
+];
+
+pub fn performance_mods_for(loader: &str) -> &'static [&'static str] {
+    PERFORMANCE_MODS.iter().find(|(l, _)| *l == loader).map(|(_, mods)| *mods).unwrap_or(&[])
+}
+
+/// A quarter of the machine's RAM in 512 MB steps, within what modded low-end setups need.
+pub fn low_end_ram_mb(total_ram_mb: u64) -> u64 {
+    (total_ram_mb / 4 / 512 * 512).clamp(LOW_END_MIN_RAM_MB, LOW_END_MAX_RAM_MB)
+}
+
+/// Adds the newest build of each Modrinth project for the instance's loader and version to its
+/// mod list (installed on the next sync). Returns the added URLs and the projects that failed.
+pub fn add_modrinth_mods(instance: &mut crate::Instance, slugs: &[&str]) -> (Vec<String>, Vec<String>) {
+    let loader = instance.modloader.clone().unwrap_or_else(|| "vanilla".to_string());
+    if slugs.is_empty() || loader == "vanilla" {
+        return (Vec::new(), vec!["Vanilla no admite mods de rendimiento; usa Fabric o Forge".to_string()]);
+    }
+    let modrinth_loader = if loader == "quilt" { "fabric" } else { loader.as_str() };
+    let mut urls = Vec::new();
+    let mut warnings = Vec::new();
+    for slug in slugs {
+        match latest_file_url(slug, modrinth_loader, &instance.version) {
+            Ok(url) => {
+                let list = instance.mods.get_or_insert_with(Vec::new);
+                if !list.contains(&url) {
+                    list.push(url.clone());
+                }
+                urls.push(url);
+            }
+            Err(e) => warnings.push(e),
+        }
+    }
+    (urls, warnings)
+}
+
+#[derive(Serialize, Clone, Debug, Default, JsonSchema)]
+pub struct OptimizeResult {
+    #[serde(rename = "ramMb")]
+    pub ram_mb: u64,
+    #[serde(rename = "modUrls")]
+    pub mod_urls: Vec<String>,
+    #[serde(rename = "jvmPreset")]
+    pub jvm_preset: String,
+    /// Parts of the profile that could not be applied; the rest still is
+    pub warnings: Vec<String>,
+}
+
+/// Applies a performance profile to an instance in one go. `low-end`: less RAM, the loader's
+/// performance mods, fast graphics in `options.txt` and the low-memory JVM preset.
+#[tauri::command]
+pub async fn optimize_instance(instance_id: String, profile: String) -> Result<OptimizeResult, String> {
+    crate::settings::ensure_not_guest()?;
+    if profile != LOW_END_PROFILE {
+        return Err(format!("Perfil de optimización desconocido: {}", profile));
+    }
+    crate::run_blocking(move || {
+        let mut instances = crate::load_instances();
+        let instance = instances.iter_mut().find(|i| i.id == instance_id)
+            .ok_or("Instance not found")?;
+        let total_ram_mb = crate::platform::get_platform_info().max_ram_mb;
+        let ram_mb = low_end_ram_mb(total_ram_mb).min(instance.ram.unwrap_or(u64::MAX));
+        let mut result = OptimizeResult { ram_mb, jvm_preset: LOW_END_PROFILE.to_string(), ..Default::default() };
+        instance.ram = Some(ram_mb);
+        instance.jvm_preset = Some(LOW_END_PROFILE.to_string());
+
+        let loader = instance.modloader.clone().unwrap_or_else(|| "vanilla".to_string());
+        let (mod_urls, warnings) = add_modrinth_mods(instance, performance_mods_for(&loader));
+        result.mod_urls = mod_urls;
+        result.warnings.extend(warnings);
+
+        let minecraft_dir = Path::new(&instance.path).join("minecraft");
+        if let Err(e) = apply_option_values(&minecraft_dir, &low_end_options(&instance.version)) {
+            result.warnings.push(e);
+        }
+        crate::save_instances(&instances);
+        Ok(result)
+    }).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn low_end_ram_stays_in_range() {
+        assert_eq!(low_end_ram_mb(2048), LOW_END_MIN_RAM_MB);
+        assert_eq!(low_end_ram_mb(8192), 2048);
+        assert_eq!(low_end_ram_mb(10000), 2048);
+        assert_eq!(low_end_ram_mb(65536), LOW_END_MAX_RAM_MB);
+    }
+
+    #[test]
+    fn performance_mods_match_the_loader() {
+        assert_eq!(performance_mods_for("fabric")[0], "sodium");
+        assert_eq!(performance_mods_for("forge")[0], "embeddium");
+        assert!(performance_mods_for("vanilla").is_empty());
+    }
+}
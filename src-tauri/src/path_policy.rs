@@ -0,0 +1,132 @@
+use std::fmt;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+/// Why a path coming from the webview was refused.
+#[derive(Debug, PartialEq)]
+pub enum PathError {
+    Empty,
+    /// `..` left over after resolving what exists of the path
+    Traversal(PathBuf),
+    /// Resolves (symlinks included) outside the allowed root
+    Outside(PathBuf),
+    Io(String),
+}
+
+impl fmt::Display for PathError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PathError::Empty => write!(f, "Ruta vacía"),
+            PathError::Traversal(path) => write!(f, "Ruta no permitida: {}", path.display()),
+            PathError::Outside(path) => write!(f, "La ruta {} está fuera de la carpeta del launcher", path.display()),
+            PathError::Io(e) => write!(f, "Ruta no válida: {}", e),
+        }
+    }
+}
+
+impl From<PathError> for String {
+    fn from(error: PathError) -> String {
+        error.to_string()
+    }
+}
+
+/// Absolute form of `path` with every existing part canonicalized; the part that doesn't exist
+/// yet may only hold plain names.
+pub fn resolve(path: &Path) -> Result<PathBuf, PathError> {
+    if path.as_os_str().is_empty() {
+        return Err(PathError::Empty);
+    }
+    let mut existing = path.to_path_buf();
+    let mut missing = Vec::new();
+    let base = loop {
+        match fs::canonicalize(&existing) {
+            Ok(base) => break base,
+            Err(e) => {
+                let (Some(parent), Some(name)) = (existing.parent(), existing.file_name()) else {
+                    return Err(PathError::Io(e.to_string()));
+                };
+                missing.push(name.to_os_string());
+                existing = if parent.as_os_str().is_empty() { PathBuf::from(".") } else { parent.to_path_buf() };
+            }
+        }
+    };
+    // A trailing `..` has no file name and already failed above; this guards the rest
+    if missing.iter().any(|name| Path::new(name).components().any(|c| !matches!(c, Component::Normal(_)))) {
+        return Err(PathError::Traversal(path.to_path_buf()));
+    }
+    Ok(missing.into_iter().rev().fold(base, |path, name| path.join(name)))
+}
+
+/// `path` resolved, as long as it lands inside `root`.
+pub fn inside(path: &Path, root: &Path) -> Result<PathBuf, PathError> {
+    let resolved = resolve(path)?;
+    let root = resolve(root)?;
+    if !resolved.starts_with(&root) {
+        return Err(PathError::Outside(resolved));
+    }
+    Ok(resolved)
+}
+
+/// `relative` (as written in a catalog or instance, e.g. `config/menu.toml`) under `root`, as
+/// long as it lands inside it.
+pub fn join_inside(root: &Path, relative: &str) -> Result<PathBuf, PathError> {
+    let path = Path::new(relative.trim());
+    if path.has_root() || path.is_absolute() {
+        return Err(PathError::Traversal(path.to_path_buf()));
+    }
+    if path.as_os_str().is_empty() {
+        return Err(PathError::Empty);
+    }
+    inside(&root.join(path), root)
+}
+
+/// Inside the launcher's data root.
+pub fn inside_data_root(path: &Path) -> Result<PathBuf, PathError> {
+    inside(path, Path::new(&crate::get_instances_dir()))
+}
+
+/// Data-root paths for everyone; anywhere on disk with the admin password (staff opening a
+/// server folder elsewhere, for instance).
+pub fn checked(path: &Path, admin_password: Option<String>) -> Result<PathBuf, String> {
+    match admin_password {
+        Some(password) => {
+            crate::ensure_admin(password)?;
+            Ok(resolve(path)?)
+        }
+        None => Ok(inside_data_root(path)?),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn paths_must_stay_inside_the_root() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("Eventos DRK");
+        fs::create_dir_all(root.join("instancias").join("Evento")).unwrap();
+        let root_canonical = fs::canonicalize(&root).unwrap();
+
+        assert_eq!(inside(&root.join("instancias").join("Evento"), &root).unwrap(), root_canonical.join("instancias").join("Evento"));
+        assert_eq!(inside(&root.join("instancias").join("Nueva").join("mods"), &root).unwrap(), root_canonical.join("instancias").join("Nueva").join("mods"));
+        assert!(matches!(inside(&root.join("instancias").join("..").join(".."), &root), Err(PathError::Outside(_))));
+        assert!(inside(&root.join("no-existe").join(".."), &root).is_err());
+        assert!(matches!(inside(dir.path(), &root), Err(PathError::Outside(_))));
+        assert_eq!(inside(Path::new(""), &root), Err(PathError::Empty));
+        assert!(join_inside(&root, "instancias/Evento/config/menu.toml").is_ok());
+        assert!(matches!(join_inside(&root, "/etc/passwd"), Err(PathError::Traversal(_))));
+        assert!(join_inside(&root, "../fuera.txt").is_err());
+        assert_eq!(join_inside(&root, " "), Err(PathError::Empty));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinks_out_of_the_root_are_refused() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("root");
+        fs::create_dir_all(&root).unwrap();
+        std::os::unix::fs::symlink(dir.path(), root.join("escape")).unwrap();
+        assert!(matches!(inside(&root.join("escape"), &root), Err(PathError::Outside(_))));
+    }
+}
@@ -0,0 +1,429 @@
+use schemars::JsonSchema;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+use sysinfo::{CpuRefreshKind, DiskExt, RefreshKind, System, SystemExt};
+use crate::minecraft::launch_logic::parse_mc_version;
+use crate::minecraft::utils::{get_arch, get_os_name};
+
+/// A 32-bit JVM can't reserve a larger contiguous heap.
+pub const MAX_RAM_32_BIT_MB: u64 = 1536;
+const BYTES_PER_MB: u64 = 1024 * 1024;
+/// The machine id as fixed on first run (see `machine_id`)
+const MACHINE_ID_FILE: &str = "machine-id";
+const MIN_DEFAULT_RAM_MB: u64 = 2048;
+
+/// What the UI needs to hide options the machine can't run and word instructions for it.
+#[derive(Serialize, Clone, Debug, JsonSchema)]
+pub struct PlatformInfo {
+    /// Mojang's names: `windows`, `osx`, `linux`
+    pub os: String,
+    /// `x64`, `arm64`, `x86`
+    pub arch: String,
+    /// Whether the OS is 64-bit, even when the launcher itself runs as a 32-bit process
+    #[serde(rename = "is64Bit")]
+    pub is_64_bit: bool,
+    #[serde(rename = "totalRamMb")]
+    pub total_ram_mb: u64,
+    /// Largest heap worth offering for an instance on this machine
+    #[serde(rename = "maxRamMb")]
+    pub max_ram_mb: u64,
+    /// Free space on the drive holding the launcher data; `None` when no mount matches
+    #[serde(rename = "availableDiskBytes")]
+    pub available_disk_bytes: Option<u64>,
+    #[serde(rename = "dataDir")]
+    pub data_dir: String,
+}
+
+/// Memory and CPU of this machine. Sizes are always MiB, whatever unit sysinfo works in.
+#[derive(Serialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct HardwareProfile {
+    #[serde(rename = "totalRamMb")]
+    pub total_ram_mb: u64,
+    #[serde(rename = "availableRamMb")]
+    pub available_ram_mb: u64,
+    /// Physical cores; `None` when the OS doesn't say
+    #[serde(rename = "cpuCores")]
+    pub cpu_cores: Option<usize>,
+    /// Logical processors (hardware threads)
+    #[serde(rename = "cpuThreads")]
+    pub cpu_threads: usize,
+    #[serde(rename = "totalSwapMb")]
+    pub total_swap_mb: u64,
+    #[serde(rename = "freeSwapMb")]
+    pub free_swap_mb: u64,
+}
+
+/// sysinfo reports memory in bytes since 0.26 (it was KiB before); the version is pinned in
+/// Cargo.toml and `sysinfo_reports_bytes` fails if that ever changes.
+pub fn bytes_to_mb(bytes: u64) -> u64 {
+    bytes / BYTES_PER_MB
+}
+
+fn memory_system() -> System {
+    let mut sys = System::new_with_specifics(RefreshKind::new().with_memory());
+    sys.refresh_memory();
+    sys
+}
+
+pub fn total_ram_mb() -> u64 {
+    bytes_to_mb(memory_system().total_memory())
+}
+
+pub fn hardware_profile() -> HardwareProfile {
+    let sys = System::new_with_specifics(RefreshKind::new().with_memory().with_cpu(CpuRefreshKind::new()));
+    let cpu_threads = match sys.cpus().len() {
+        0 => std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1),
+        n => n,
+    };
+    HardwareProfile {
+        total_ram_mb: bytes_to_mb(sys.total_memory()),
+        available_ram_mb: bytes_to_mb(sys.available_memory()),
+        cpu_cores: sys.physical_core_count(),
+        cpu_threads,
+        total_swap_mb: bytes_to_mb(sys.total_swap()),
+        free_swap_mb: bytes_to_mb(sys.free_swap()),
+    }
+}
+
+#[tauri::command]
+pub fn get_hardware_profile() -> Result<HardwareProfile, String> {
+    let profile = hardware_profile();
+    if profile.total_ram_mb == 0 {
+        return Err("No se pudo leer la memoria del sistema".to_string());
+    }
+    Ok(profile)
+}
+
+/// `PROCESSOR_ARCHITECTURE` is the process' view; WOW64 adds `PROCESSOR_ARCHITEW6432` with the
+/// real one.
+pub fn windows_is_64_bit(architecture: Option<&str>, wow64_architecture: Option<&str>) -> bool {
+    [architecture, wow64_architecture].iter().flatten().any(|a| a.contains("64"))
+}
+
+pub fn os_is_64_bit() -> bool {
+    if cfg!(target_pointer_width = "64") {
+        return true;
+    }
+    if cfg!(target_os = "windows") {
+        let architecture = std::env::var("PROCESSOR_ARCHITECTURE").ok();
+        let wow64_architecture = std::env::var("PROCESSOR_ARCHITEW6432").ok();
+        return windows_is_64_bit(architecture.as_deref(), wow64_architecture.as_deref());
+    }
+    std::process::Command::new("uname")
+        .arg("-m")
+        .output()
+        .map(|o| String::from_utf8_lossy(&o.stdout).contains("64"))
+        .unwrap_or(false)
+}
+
+/// Architecture name Adoptium uses for the JRE matching this OS.
+pub fn java_arch() -> &'static str {
+    match (os_is_64_bit(), get_arch()) {
+        (false, _) => "x32",
+        (true, "arm64") => "aarch64",
+        _ => "x64",
+    }
+}
+
+/// Minecraft 1.20.5 (snapshot 24w14a) dropped 32-bit Java.
+pub fn requires_64_bit(mc_version: &str) -> bool {
+    if let Some((minor, patch)) = parse_mc_version(mc_version) {
+        return (minor, patch) >= (20, 5);
+    }
+    // Snapshots: `<yy>w<ww><letter>`
+    let Some((year, rest)) = mc_version.split_once('w') else { return false; };
+    let week = rest.get(..2).and_then(|w| w.parse::<u32>().ok());
+    match (year.parse::<u32>().ok(), week) {
+        (Some(year), Some(week)) => (year, week) >= (24, 14),
+        _ => false,
+    }
+}
+
+pub fn ensure_supported(mc_version: &str) -> Result<(), String> {
+    if requires_64_bit(mc_version) && !os_is_64_bit() {
+        return Err(format!("Minecraft {} requiere un sistema operativo de 64 bits", mc_version));
+    }
+    Ok(())
+}
+
+pub fn max_ram_mb(total_ram_mb: u64, is_64_bit: bool) -> u64 {
+    if is_64_bit { total_ram_mb } else { total_ram_mb.min(MAX_RAM_32_BIT_MB) }
+}
+
+/// Heap worth giving a loader by default: vanilla gains nothing past 4 GB, modded packs do.
+fn loader_ram_cap_mb(loader: Option<&str>) -> u64 {
+    match loader {
+        Some("forge") | Some("neoforge") => 8192,
+        Some("fabric") | Some("quilt") => 6144,
+        _ => 4096,
+    }
+}
+
+/// RAM a new instance gets: half the machine in 512 MB steps, up to the loader's cap and never
+/// more than the machine can start.
+pub fn default_ram_mb(total_ram_mb: u64, is_64_bit: bool, loader: Option<&str>) -> u64 {
+    let half = total_ram_mb / 2 / 512 * 512;
+    half.min(loader_ram_cap_mb(loader)).max(MIN_DEFAULT_RAM_MB).min(max_ram_mb(total_ram_mb, is_64_bit))
+}
+
+/// Default RAM for `loader` on this machine; also used for instances saved without one.
+pub fn default_ram_for(loader: Option<&str>) -> u64 {
+    default_ram_mb(total_ram_mb(), os_is_64_bit(), loader)
+}
+
+#[tauri::command]
+pub fn get_default_ram(modloader: Option<String>) -> u64 {
+    default_ram_for(modloader.as_deref())
+}
+
+/// After the machine's RAM changed from `previous_mb` to `total_mb`: instances still on the old
+/// default get the new one, and those asking for more than the machine can start are brought
+/// down to it. Returns how many changed.
+pub fn revalidate_ram(instances: &mut [crate::Instance], previous_mb: u64, total_mb: u64, is_64_bit: bool) -> usize {
+    let mut changed = 0;
+    for instance in instances.iter_mut() {
+        let loader = instance.modloader.as_deref();
+        let Some(ram) = instance.ram else { continue };
+        let new_default = default_ram_mb(total_mb, is_64_bit, loader);
+        let stale = ram == default_ram_mb(previous_mb, is_64_bit, loader) || ram > max_ram_mb(total_mb, is_64_bit);
+        if stale && ram != new_default {
+            instance.ram = Some(new_default);
+            changed += 1;
+        }
+    }
+    changed
+}
+
+/// Compares the RAM seen on the last run with the current one and revalidates the instances'
+/// RAM when it changed (new PC, module removed, data folder copied to another machine).
+pub fn check_hardware_change() {
+    let total_mb = total_ram_mb();
+    if total_mb == 0 {
+        return;
+    }
+    let mut settings = crate::settings::load_settings();
+    if settings.last_total_ram_mb == Some(total_mb) {
+        return;
+    }
+    if let Some(previous_mb) = settings.last_total_ram_mb {
+        let mut instances = crate::load_instances();
+        let changed = revalidate_ram(&mut instances, previous_mb, total_mb, os_is_64_bit());
+        if changed > 0 {
+            eprintln!("[platform] RAM changed from {} MB to {} MB; adjusted {} instance(s)", previous_mb, total_mb, changed);
+            crate::save_instances(&instances);
+        }
+    }
+    settings.last_total_ram_mb = Some(total_mb);
+    if let Err(e) = crate::settings::save_settings(&settings) {
+        eprintln!("[platform] Could not remember the system RAM: {}", e);
+    }
+}
+
+/// Caps a configured heap to what this machine can start; instances are often shared
+/// between 64-bit and 32-bit PCs.
+pub fn clamp_ram_mb(ram_mb: u64) -> u64 {
+    if os_is_64_bit() { ram_mb } else { ram_mb.min(MAX_RAM_32_BIT_MB) }
+}
+
+/// Free space of the mount containing `path`: the deepest mount point that prefixes it.
+pub fn available_space_for(path: &Path, mounts: &[(PathBuf, u64)]) -> Option<u64> {
+    mounts.iter()
+        .filter(|(mount, _)| path.starts_with(mount))
+        .max_by_key(|(mount, _)| mount.components().count())
+        .map(|(_, available)| *available)
+}
+
+/// Storage type of the drive holding `path` as the OS reports it; `None` when it doesn't know
+/// (network drives, some virtual disks).
+pub fn disk_kind_of(path: &Path) -> Option<crate::tuning::DiskKind> {
+    let mut sys = System::new();
+    sys.refresh_disks_list();
+    let path = std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    sys.disks().iter()
+        .filter(|d| path.starts_with(d.mount_point()))
+        .max_by_key(|d| d.mount_point().components().count())
+        .and_then(|d| match d.kind() {
+            sysinfo::DiskKind::SSD => Some(crate::tuning::DiskKind::Ssd),
+            sysinfo::DiskKind::HDD => Some(crate::tuning::DiskKind::Hdd),
+            sysinfo::DiskKind::Unknown(_) => None,
+        })
+}
+
+/// What the OS calls this machine: `/etc/machine-id`, the Windows `MachineGuid` or the Mac's
+/// `IOPlatformUUID`.
+fn os_machine_id() -> Option<String> {
+    let raw = if cfg!(target_os = "windows") {
+        let mut cmd = std::process::Command::new("reg");
+        cmd.args(["query", r"HKLM\SOFTWARE\Microsoft\Cryptography", "/v", "MachineGuid"]);
+        #[cfg(target_os = "windows")]
+        {
+            use std::os::windows::process::CommandExt;
+            cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
+        }
+        let out = cmd.output().ok()?;
+        String::from_utf8_lossy(&out.stdout).lines().find(|l| l.contains("MachineGuid"))?.split_whitespace().last()?.to_string()
+    } else if cfg!(target_os = "macos") {
+        let out = std::process::Command::new("ioreg").args(["-rd1", "-c", "IOPlatformExpertDevice"]).output().ok()?;
+        // `  "IOPlatformUUID" = "XXXXXXXX-..."`
+        String::from_utf8_lossy(&out.stdout).lines().find(|l| l.contains("IOPlatformUUID"))?.split('"').nth(3)?.to_string()
+    } else {
+        ["/etc/machine-id", "/var/lib/dbus/machine-id"].iter().find_map(|p| fs::read_to_string(p).ok())?
+    };
+    Some(raw.trim().to_string()).filter(|id| !id.is_empty())
+}
+
+/// `A1B2-C3D4-E5F6-0718`: a hash of `raw`, so the OS id itself never leaves the machine.
+pub fn format_machine_id(raw: &str) -> String {
+    let hex = hex::encode(Sha256::digest(format!("drk-launcher:{}", raw.trim()))).to_uppercase();
+    format!("{}-{}-{}-{}", &hex[..4], &hex[4..8], &hex[8..12], &hex[12..16])
+}
+
+/// Stable id staff use to tell event machines apart, e.g. when issuing a password reset or in
+/// the fleet view. Derived from the OS id (random when there is none) on first run and kept in
+/// the data root from then on, so a later failure to read the OS id can't change it.
+pub fn machine_id() -> String {
+    let path = crate::data_dir::root().join(MACHINE_ID_FILE);
+    if let Some(id) = fs::read_to_string(&path).ok().map(|id| id.trim().to_string()).filter(|id| !id.is_empty()) {
+        return id;
+    }
+    let id = format_machine_id(&os_machine_id().unwrap_or_else(|| uuid::Uuid::new_v4().to_string()));
+    if let Err(e) = fs::write(&path, &id) {
+        eprintln!("[platform] Failed to save the machine id: {}", e);
+    }
+    id
+}
+
+#[tauri::command]
+pub fn get_machine_id() -> String {
+    machine_id()
+}
+
+/// Free space on the drive holding the launcher data.
+pub fn data_dir_available_space() -> Option<u64> {
+    let mut sys = System::new();
+    sys.refresh_disks_list();
+    let mounts = sys.disks().iter()
+        .map(|d| (d.mount_point().to_path_buf(), d.available_space()))
+        .collect::<Vec<_>>();
+    available_space_for(&crate::data_dir::root(), &mounts)
+}
+
+#[tauri::command]
+pub fn get_platform_info() -> PlatformInfo {
+    let data_dir = crate::data_dir::root();
+    let is_64_bit = os_is_64_bit();
+    let total_ram_mb = total_ram_mb();
+    PlatformInfo {
+        os: get_os_name().to_string(),
+        arch: get_arch().to_string(),
+        is_64_bit,
+        total_ram_mb,
+        max_ram_mb: max_ram_mb(total_ram_mb, is_64_bit),
+        available_disk_bytes: data_dir_available_space(),
+        data_dir: data_dir.to_string_lossy().to_string(),
+    }
+}
+
+/// Why `version` can't run here, if it can't.
+#[tauri::command]
+pub fn check_version_support(version: String) -> Option<String> {
+    ensure_supported(version.trim()).err()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn machine_ids_are_short_stable_hashes() {
+        let id = format_machine_id("0123456789abcdef0123456789abcdef\n");
+        assert_eq!(id, format_machine_id("0123456789abcdef0123456789abcdef"));
+        assert_eq!(id.len(), 19);
+        assert!(id.chars().all(|c| c == '-' || c.is_ascii_digit() || c.is_ascii_uppercase()));
+        assert_ne!(id, format_machine_id("otro"));
+    }
+
+    #[test]
+    fn deepest_mount_wins() {
+        let mounts = vec![(PathBuf::from("/"), 10), (PathBuf::from("/home"), 20), (PathBuf::from("/media/usb"), 30)];
+        assert_eq!(available_space_for(Path::new("/home/u/.local/share/DrkLauncher"), &mounts), Some(20));
+        assert_eq!(available_space_for(Path::new("/opt/drk"), &mounts), Some(10));
+        assert_eq!(available_space_for(Path::new("/opt/drk"), &[]), None);
+    }
+
+    #[test]
+    fn wow64_reports_the_real_architecture() {
+        assert!(!windows_is_64_bit(Some("x86"), None));
+        assert!(windows_is_64_bit(Some("x86"), Some("AMD64")));
+        assert!(windows_is_64_bit(Some("ARM64"), None));
+        assert_eq!(max_ram_mb(8192, false), MAX_RAM_32_BIT_MB);
+        assert_eq!(max_ram_mb(8192, true), 8192);
+    }
+
+    #[test]
+    fn memory_is_reported_in_mb() {
+        assert_eq!(bytes_to_mb(8 * 1024 * 1024 * 1024), 8192);
+        assert_eq!(bytes_to_mb(BYTES_PER_MB - 1), 0);
+        let profile = hardware_profile();
+        assert!(profile.total_ram_mb > 0);
+        assert!(profile.available_ram_mb <= profile.total_ram_mb);
+        assert!(profile.free_swap_mb <= profile.total_swap_mb);
+        assert!(profile.cpu_threads >= profile.cpu_cores.unwrap_or(1));
+    }
+
+    /// Pins the unit of the sysinfo version in use against the kernel's own figure.
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn sysinfo_reports_bytes() {
+        let meminfo = std::fs::read_to_string("/proc/meminfo").unwrap();
+        let kib: u64 = meminfo.lines()
+            .find_map(|l| l.strip_prefix("MemTotal:"))
+            .and_then(|v| v.trim().trim_end_matches("kB").trim().parse().ok())
+            .unwrap();
+        assert_eq!(memory_system().total_memory(), kib * 1024);
+        assert_eq!(total_ram_mb(), kib / 1024);
+    }
+
+    #[test]
+    fn default_ram_follows_the_machine_and_the_loader() {
+        assert_eq!(default_ram_mb(16384, true, Some("forge")), 8192);
+        assert_eq!(default_ram_mb(16384, true, Some("fabric")), 6144);
+        assert_eq!(default_ram_mb(16384, true, None), 4096);
+        assert_eq!(default_ram_mb(32768, true, Some("neoforge")), 8192);
+        // Half of an odd total, in 512 MB steps
+        assert_eq!(default_ram_mb(6013, true, Some("forge")), 2560);
+        assert_eq!(default_ram_mb(3000, true, Some("forge")), MIN_DEFAULT_RAM_MB);
+        assert_eq!(default_ram_mb(1900, true, None), 1900);
+        assert_eq!(default_ram_mb(16384, false, Some("forge")), MAX_RAM_32_BIT_MB);
+    }
+
+    #[test]
+    fn ram_is_revalidated_when_the_machine_changes() {
+        let instance = |ram: Option<u64>| -> crate::Instance {
+            serde_json::from_value(serde_json::json!({
+                "id": "evento", "name": "Evento", "version": "1.20.1", "icon": "default",
+                "path": "", "modloader": "forge", "ram": ram
+            })).unwrap()
+        };
+        // 16 GB to 8 GB: the old default and an impossible heap move, a custom one stays
+        let mut instances = vec![instance(Some(8192)), instance(Some(12288)), instance(Some(3072)), instance(None)];
+        assert_eq!(revalidate_ram(&mut instances, 16384, 8192, true), 2);
+        let rams: Vec<Option<u64>> = instances.iter().map(|i| i.ram).collect();
+        assert_eq!(rams, vec![Some(4096), Some(4096), Some(3072), None]);
+        assert_eq!(revalidate_ram(&mut instances, 8192, 8192, true), 0);
+    }
+
+    #[test]
+    fn modern_versions_need_64_bit() {
+        assert!(!requires_64_bit("1.20.4"));
+        assert!(requires_64_bit("1.20.5"));
+        assert!(requires_64_bit("1.21.1"));
+        assert!(!requires_64_bit("1.8.9"));
+        assert!(!requires_64_bit("24w13a"));
+        assert!(requires_64_bit("24w14a"));
+        assert!(requires_64_bit("25w02a"));
+        assert!(!requires_64_bit("b1.7.3"));
+    }
+}
@@ -0,0 +1,100 @@
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Listener, Manager, State};
+
+/// Stages after which nothing more is coming for that launch.
+const FINAL_STAGES: [&str; 4] = ["cerrado", "crasheado", "error", "descarga_completa"];
+
+/// Last `launch_progress` event of an instance, kept so a reloaded webview can pick up where
+/// the previous one left off.
+#[derive(Serialize, Clone, Debug, JsonSchema)]
+pub struct ProgressSnapshot {
+    /// The event payload as it was emitted
+    #[schemars(with = "crate::events::LaunchProgress")]
+    pub event: serde_json::Value,
+    /// Unix millis
+    #[serde(rename = "updatedAt")]
+    pub updated_at: u64,
+    /// Preparation or game still running
+    pub active: bool,
+}
+
+#[derive(Default)]
+pub struct ProgressSnapshots(Mutex<HashMap<String, ProgressSnapshot>>);
+
+impl ProgressSnapshots {
+    pub fn record(&self, event: serde_json::Value, now_ms: u64) {
+        let Some(instance_id) = event["instanceId"].as_str().map(str::to_string) else { return };
+        let stage = event["stage"].as_str().unwrap_or_default();
+        let active = !FINAL_STAGES.contains(&stage);
+        if let Ok(mut snapshots) = self.0.lock() {
+            snapshots.insert(instance_id, ProgressSnapshot { event, updated_at: now_ms, active });
+        }
+    }
+
+    pub fn get(&self, instance_id: &str) -> Option<ProgressSnapshot> {
+        self.0.lock().ok()?.get(instance_id).cloned()
+    }
+
+    pub fn active(&self) -> Vec<ProgressSnapshot> {
+        self.0.lock().map(|s| s.values().filter(|s| s.active).cloned().collect()).unwrap_or_default()
+    }
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis() as u64).unwrap_or(0)
+}
+
+/// Mirrors every `launch_progress` event into the managed snapshots.
+pub fn install(app: &AppHandle) {
+    let handle = app.clone();
+    app.listen_any("launch_progress", move |event| {
+        let Ok(payload) = serde_json::from_str::<serde_json::Value>(event.payload()) else { return };
+        handle.state::<ProgressSnapshots>().record(payload, now_ms());
+    });
+}
+
+#[tauri::command]
+pub fn get_progress_snapshot(instance_id: String, snapshots: State<'_, ProgressSnapshots>) -> Option<ProgressSnapshot> {
+    snapshots.get(&instance_id)
+}
+
+/// Launches still preparing or running, for a webview that just (re)loaded.
+#[tauri::command]
+pub fn get_active_progress(snapshots: State<'_, ProgressSnapshots>) -> Vec<ProgressSnapshot> {
+    snapshots.active()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn latest_event_per_instance_is_kept() {
+        let snapshots = ProgressSnapshots::default();
+        snapshots.record(json!({"instanceId": "evento", "stage": "assets", "percent": 62, "message": "Verificando assets"}), 1);
+        snapshots.record(json!({"instanceId": "evento", "stage": "librerias", "percent": 65, "message": "Descargando librerías"}), 2);
+        snapshots.record(json!({"instanceId": "otro", "stage": "java", "percent": 10}), 3);
+
+        let snapshot = snapshots.get("evento").unwrap();
+        assert_eq!(snapshot.event["stage"], "librerias");
+        assert_eq!(snapshot.updated_at, 2);
+        assert_eq!(snapshots.active().len(), 2);
+        assert!(snapshots.get("nada").is_none());
+    }
+
+    #[test]
+    fn finished_launches_are_not_active() {
+        let snapshots = ProgressSnapshots::default();
+        snapshots.record(json!({"instanceId": "evento", "stage": "iniciado", "percent": 100}), 1);
+        assert!(snapshots.get("evento").unwrap().active);
+        snapshots.record(json!({"instanceId": "evento", "stage": "recompensa", "percent": 100}), 2);
+        snapshots.record(json!({"instanceId": "evento", "stage": "cerrado", "percent": 100}), 3);
+        assert_eq!(snapshots.get("evento").unwrap().event["stage"], "cerrado");
+        assert!(snapshots.active().is_empty());
+    }
+}
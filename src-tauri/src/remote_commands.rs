@@ -0,0 +1,217 @@
+use hmac::{Hmac, Mac};
+use serde::Deserialize;
+use sha2::Sha256;
+use std::collections::VecDeque;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tauri::AppHandle;
+use crate::maintenance::Maintenance;
+use crate::settings::load_settings;
+
+const AUDIT_FILE: &str = "remote-commands.log";
+/// Everything staff can trigger remotely; anything else is rejected before the allowlist
+pub const KNOWN_ACTIONS: [&str; 3] = ["resync", "invalidate_pack", "maintenance"];
+// Older commands are replays (or a clock far off); either way they don't run
+const MAX_AGE_SECS: u64 = 300;
+// Ids already run, so a replayed message inside the age window is ignored
+const SEEN_IDS: usize = 256;
+
+static SEEN: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+
+/// What travels over the announcement socket: `payload` is the command JSON exactly as signed,
+/// `signature` its HMAC-SHA256 in hex with the shared command secret.
+#[derive(Deserialize)]
+struct Envelope {
+    #[serde(rename = "type")]
+    kind: String,
+    payload: String,
+    signature: String,
+}
+
+#[derive(Deserialize, Debug, PartialEq)]
+pub struct RemoteCommand {
+    pub id: String,
+    pub action: String,
+    /// Unix seconds
+    #[serde(rename = "issuedAt", alias = "issued_at")]
+    pub issued_at: u64,
+    #[serde(rename = "instanceId", alias = "instance_id", default)]
+    pub instance_id: Option<String>,
+    /// Maintenance banner text; empty or missing turns maintenance off
+    #[serde(default)]
+    pub message: Option<String>,
+    /// End of the maintenance window, unix seconds
+    #[serde(default)]
+    pub until: Option<u64>,
+    /// Instances under maintenance; empty means all
+    #[serde(rename = "instanceIds", alias = "instance_ids", default)]
+    pub instance_ids: Vec<String>,
+}
+
+pub fn sign(secret: &str, payload: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key length");
+    mac.update(payload.as_bytes());
+    hex::encode(mac.finalize().into_bytes())
+}
+
+pub(crate) fn signature_valid(secret: &str, payload: &str, signature: &str) -> bool {
+    let Ok(signature) = hex::decode(signature.trim()) else { return false; };
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key length");
+    mac.update(payload.as_bytes());
+    mac.verify_slice(&signature).is_ok()
+}
+
+/// `None` when `text` isn't a command envelope; otherwise the command if its signature, action
+/// and age check out. Replays are caught separately by id.
+pub fn verify(text: &str, secret: Option<&str>, allowed: &[String], now: u64) -> Option<Result<RemoteCommand, String>> {
+    let envelope = serde_json::from_str::<Envelope>(text).ok().filter(|e| e.kind == "command")?;
+    Some((|| {
+        let secret = secret.filter(|s| !s.is_empty()).ok_or("No hay secreto de comandos configurado")?;
+        if !signature_valid(secret, &envelope.payload, &envelope.signature) {
+            return Err("Firma no válida".to_string());
+        }
+        let command: RemoteCommand = serde_json::from_str(&envelope.payload).map_err(|e| format!("Comando no válido: {}", e))?;
+        if !KNOWN_ACTIONS.contains(&command.action.as_str()) {
+            return Err(format!("Acción desconocida: {}", command.action));
+        }
+        if !allowed.iter().any(|a| a == &command.action) {
+            return Err(format!("Acción no permitida: {}", command.action));
+        }
+        if command.issued_at + MAX_AGE_SECS < now || command.issued_at > now + MAX_AGE_SECS {
+            return Err("Comando caducado".to_string());
+        }
+        Ok(command)
+    })())
+}
+
+/// True the first time an id is seen.
+fn first_seen(id: &str) -> bool {
+    let mut seen = SEEN.lock().unwrap_or_else(|e| e.into_inner());
+    if seen.iter().any(|s| s == id) {
+        return false;
+    }
+    if seen.len() >= SEEN_IDS {
+        seen.pop_front();
+    }
+    seen.push_back(id.to_string());
+    true
+}
+
+fn audit_path() -> PathBuf {
+    crate::data_dir::root().join(AUDIT_FILE)
+}
+
+/// One JSON line per command received, run or not.
+fn audit(id: Option<&str>, action: Option<&str>, result: &Result<String, String>) {
+    let line = serde_json::json!({
+        "at": crate::unix_now(),
+        "id": id,
+        "action": action,
+        "ok": result.is_ok(),
+        "detail": match result { Ok(detail) | Err(detail) => detail },
+    });
+    if let Ok(mut file) = OpenOptions::new().create(true).append(true).open(audit_path()) {
+        let _ = writeln!(file, "{}", line);
+    }
+}
+
+fn run(app: &AppHandle, command: &RemoteCommand) -> Result<String, String> {
+    match command.action.as_str() {
+        "resync" => {
+            let count = crate::updater::force_resync(app, command.instance_id.as_deref());
+            Ok(format!("{} instancias sincronizadas", count))
+        }
+        "invalidate_pack" => {
+            let instance_id = command.instance_id.as_deref().ok_or("Falta instanceId")?;
+            let instance = crate::load_instances().into_iter().find(|i| i.id == instance_id).ok_or("Instance not found")?;
+            // Without the cached zip the next launch or update downloads the pack again
+            let zip_path = PathBuf::from(&instance.path).join("minecraft").join("modpack.zip");
+            match fs::remove_file(&zip_path) {
+                Ok(()) => Ok(format!("Pack de {} invalidado", instance.name)),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(format!("{} no tenía pack descargado", instance.name)),
+                Err(e) => Err(e.to_string()),
+            }
+        }
+        "maintenance" => {
+            let maintenance = command.message.as_deref().map(str::trim).filter(|m| !m.is_empty()).map(|message| Maintenance {
+                message: message.to_string(),
+                until: command.until,
+                instance_ids: command.instance_ids.clone(),
+            });
+            let enabled = maintenance.is_some();
+            crate::maintenance::set_local(app, maintenance)?;
+            Ok(if enabled { "Mantenimiento activado" } else { "Mantenimiento desactivado" }.to_string())
+        }
+        other => Err(format!("Acción desconocida: {}", other)),
+    }
+}
+
+/// Runs `text` if it's a signed command envelope. Returns false for anything else so the caller
+/// can treat it as an announcement.
+pub fn handle(app: &AppHandle, text: &str) -> bool {
+    let settings = load_settings().announcements;
+    let Some(verified) = verify(text, settings.command_secret.as_deref(), &settings.allowed_commands, crate::unix_now()) else {
+        return false;
+    };
+    match verified {
+        Ok(command) if first_seen(&command.id) => {
+            // A resync can take minutes; the socket keeps reading meanwhile
+            let app = app.clone();
+            std::thread::spawn(move || {
+                let result = run(&app, &command);
+                audit(Some(&command.id), Some(&command.action), &result);
+            });
+        }
+        Ok(command) => audit(Some(&command.id), Some(&command.action), &Err("Comando repetido".to_string())),
+        Err(e) => audit(None, None, &Err(e)),
+    }
+    true
+}
+
+/// Last lines of the audit log, newest last.
+#[tauri::command]
+pub fn get_remote_command_log(password: String) -> Result<Vec<serde_json::Value>, String> {
+    crate::ensure_admin(password)?;
+    let content = fs::read_to_string(audit_path()).unwrap_or_default();
+    let lines: Vec<_> = content.lines().filter_map(|l| serde_json::from_str(l).ok()).collect();
+    Ok(lines[lines.len().saturating_sub(200)..].to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn envelope(secret: &str, payload: &str) -> String {
+        serde_json::json!({ "type": "command", "payload": payload, "signature": sign(secret, payload) }).to_string()
+    }
+
+    #[test]
+    fn only_signed_fresh_allowed_commands_pass() {
+        let allowed = vec!["resync".to_string()];
+        let payload = r#"{"id":"1","action":"resync","issuedAt":1000}"#;
+        let command = verify(&envelope("s3cret", payload), Some("s3cret"), &allowed, 1100).unwrap().unwrap();
+        assert_eq!(command.action, "resync");
+
+        assert!(verify(&envelope("other", payload), Some("s3cret"), &allowed, 1100).unwrap().is_err());
+        assert!(verify(&envelope("s3cret", payload), None, &allowed, 1100).unwrap().is_err());
+        assert!(verify(&envelope("s3cret", payload), Some("s3cret"), &allowed, 1000 + MAX_AGE_SECS + 1).unwrap().is_err());
+        let maintenance = r#"{"id":"2","action":"maintenance","issuedAt":1000}"#;
+        assert!(verify(&envelope("s3cret", maintenance), Some("s3cret"), &allowed, 1000).unwrap().is_err());
+        let unknown = r#"{"id":"3","action":"delete_everything","issuedAt":1000}"#;
+        assert!(verify(&envelope("s3cret", unknown), Some("s3cret"), &["delete_everything".to_string()], 1000).unwrap().is_err());
+    }
+
+    #[test]
+    fn announcements_are_not_commands() {
+        assert!(verify(r#"{"type":"pack_update","message":"hola"}"#, Some("s"), &[], 0).is_none());
+        assert!(verify("texto libre", Some("s"), &[], 0).is_none());
+    }
+
+    #[test]
+    fn replays_are_ignored() {
+        assert!(first_seen("replay-test"));
+        assert!(!first_seen("replay-test"));
+    }
+}
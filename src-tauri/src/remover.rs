@@ -0,0 +1,281 @@
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
+
+/// Folders renamed to this prefix are being deleted; whatever a crash or cancel leaves behind is
+/// swept on the next start.
+const TOMBSTONE_PREFIX: &str = ".deleting-";
+const WORKERS: usize = 4;
+const REPORT_INTERVAL: Duration = Duration::from_millis(250);
+
+static JOBS: Mutex<Option<HashMap<String, Arc<AtomicBool>>>> = Mutex::new(None);
+
+#[derive(Serialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct RemovalProgress {
+    #[serde(rename = "doneFiles")]
+    pub done_files: u64,
+    #[serde(rename = "totalFiles")]
+    pub total_files: u64,
+    #[serde(rename = "doneBytes")]
+    pub done_bytes: u64,
+    #[serde(rename = "totalBytes")]
+    pub total_bytes: u64,
+}
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum RemovalState {
+    Running,
+    Done,
+    Cancelled,
+    Error,
+}
+
+/// `removal_progress` payload, reported under the job id `spawn` returned.
+#[derive(Serialize, Clone, Debug, JsonSchema)]
+pub struct RemovalStatus {
+    #[serde(rename = "jobId")]
+    pub job_id: String,
+    pub label: String,
+    pub progress: RemovalProgress,
+    pub state: RemovalState,
+    pub error: Option<String>,
+}
+
+/// Top-level entries of a folder (or the path itself if it's a file).
+fn children(path: &Path) -> Vec<PathBuf> {
+    match fs::symlink_metadata(path) {
+        Ok(meta) if meta.is_dir() => fs::read_dir(path).map(|e| e.flatten().map(|e| e.path()).collect()).unwrap_or_default(),
+        Ok(_) => vec![path.to_path_buf()],
+        Err(_) => Vec::new(),
+    }
+}
+
+/// Runs `work` over the top-level entries on a few threads; the big subfolders of an instance
+/// (mods, saves, natives) then proceed side by side.
+fn for_each_parallel(entries: &[PathBuf], work: impl Fn(&Path) + Sync) {
+    let next = AtomicUsize::new(0);
+    std::thread::scope(|scope| {
+        for _ in 0..WORKERS.min(entries.len()) {
+            scope.spawn(|| {
+                while let Some(entry) = entries.get(next.fetch_add(1, Ordering::Relaxed)) {
+                    work(entry);
+                }
+            });
+        }
+    });
+}
+
+fn count(path: &Path, files: &AtomicU64, bytes: &AtomicU64) {
+    let Ok(meta) = fs::symlink_metadata(path) else { return; };
+    if meta.is_dir() {
+        for entry in fs::read_dir(path).into_iter().flatten().flatten() {
+            count(&entry.path(), files, bytes);
+        }
+    } else {
+        files.fetch_add(1, Ordering::Relaxed);
+        bytes.fetch_add(meta.len(), Ordering::Relaxed);
+    }
+}
+
+/// Files and bytes under `path`, walked in parallel. Symlinks count as themselves.
+pub fn measure(path: &Path) -> (u64, u64) {
+    let (files, bytes) = (AtomicU64::new(0), AtomicU64::new(0));
+    for_each_parallel(&children(path), |entry| count(entry, &files, &bytes));
+    (files.into_inner(), bytes.into_inner())
+}
+
+struct Remover<'a> {
+    cancel: &'a AtomicBool,
+    done_files: AtomicU64,
+    done_bytes: AtomicU64,
+    error: Mutex<Option<String>>,
+}
+
+impl Remover<'_> {
+    fn remove(&self, path: &Path) {
+        if self.cancel.load(Ordering::Relaxed) {
+            return;
+        }
+        let Ok(meta) = fs::symlink_metadata(path) else { return; };
+        // Directory symlinks/junctions are removed as links, never followed
+        let result = if meta.is_dir() {
+            for entry in fs::read_dir(path).into_iter().flatten().flatten() {
+                self.remove(&entry.path());
+            }
+            if self.cancel.load(Ordering::Relaxed) {
+                return;
+            }
+            fs::remove_dir(path)
+        } else {
+            fs::remove_file(path).or_else(|_| fs::remove_dir(path)).map(|_| {
+                self.done_files.fetch_add(1, Ordering::Relaxed);
+                self.done_bytes.fetch_add(meta.len(), Ordering::Relaxed);
+            })
+        };
+        if let Err(e) = result {
+            self.error.lock().unwrap_or_else(|e| e.into_inner()).get_or_insert_with(|| format!("No se pudo borrar {}: {}", path.display(), e));
+        }
+    }
+}
+
+/// Deletes `path` with progress reports, stopping early when `cancel` is set. Returns false if
+/// cancelled; the first file that couldn't be deleted is the error.
+pub fn remove_tree(path: &Path, cancel: &AtomicBool, on_progress: &(dyn Fn(&RemovalProgress) + Sync)) -> Result<bool, String> {
+    if !path.exists() && fs::symlink_metadata(path).is_err() {
+        return Ok(true);
+    }
+    let (total_files, total_bytes) = measure(path);
+    let remover = Remover { cancel, done_files: AtomicU64::new(0), done_bytes: AtomicU64::new(0), error: Mutex::new(None) };
+    let progress = || RemovalProgress {
+        done_files: remover.done_files.load(Ordering::Relaxed),
+        total_files,
+        done_bytes: remover.done_bytes.load(Ordering::Relaxed),
+        total_bytes,
+    };
+    let finished = AtomicBool::new(false);
+    std::thread::scope(|scope| {
+        scope.spawn(|| {
+            while !finished.load(Ordering::Relaxed) {
+                on_progress(&progress());
+                std::thread::sleep(REPORT_INTERVAL);
+            }
+        });
+        for_each_parallel(&children(path), |entry| remover.remove(entry));
+        if !cancel.load(Ordering::Relaxed) && fs::symlink_metadata(path).map(|m| m.is_dir()).unwrap_or(false) {
+            let _ = fs::remove_dir(path);
+        }
+        finished.store(true, Ordering::Relaxed);
+    });
+    on_progress(&progress());
+    if cancel.load(Ordering::Relaxed) {
+        return Ok(false);
+    }
+    match remover.error.into_inner().unwrap_or_else(|e| e.into_inner()) {
+        Some(error) => Err(error),
+        None => Ok(true),
+    }
+}
+
+/// `remove_tree` without progress or cancel, for staging folders and caches.
+pub fn remove_all(path: &Path) -> Result<(), String> {
+    remove_tree(path, &AtomicBool::new(false), &|_| {}).map(|_| ())
+}
+
+/// Moves `path` aside so it disappears from its folder at once; the slow delete happens on the
+/// tombstone. Falls back to the original path if it can't be renamed (file in use).
+pub fn retire(path: &Path) -> PathBuf {
+    let Some(parent) = path.parent() else { return path.to_path_buf(); };
+    let tombstone = parent.join(format!("{}{}", TOMBSTONE_PREFIX, uuid::Uuid::new_v4()));
+    match fs::rename(path, &tombstone) {
+        Ok(()) => tombstone,
+        Err(_) => path.to_path_buf(),
+    }
+}
+
+/// Deletes `path` on a background thread, reporting `removal_progress` events under the returned
+/// job id until it finishes, fails or is cancelled with `cancel_removal`.
+pub fn spawn(app: &AppHandle, label: &str, path: PathBuf) -> String {
+    let job_id = uuid::Uuid::new_v4().to_string();
+    let cancel = Arc::new(AtomicBool::new(false));
+    JOBS.lock().unwrap_or_else(|e| e.into_inner()).get_or_insert_with(HashMap::new).insert(job_id.clone(), cancel.clone());
+    let (app, label, id) = (app.clone(), label.to_string(), job_id.clone());
+    std::thread::spawn(move || {
+        let started = Instant::now();
+        let emit = |progress: &RemovalProgress, state: RemovalState, error: Option<&str>| {
+            let _ = app.emit("removal_progress", RemovalStatus {
+                job_id: id.clone(),
+                label: label.clone(),
+                progress: progress.clone(),
+                state,
+                error: error.map(str::to_string),
+            });
+        };
+        let last = Mutex::new(RemovalProgress::default());
+        let result = remove_tree(&path, &cancel, &|progress| {
+            *last.lock().unwrap_or_else(|e| e.into_inner()) = progress.clone();
+            emit(progress, RemovalState::Running, None);
+        });
+        let last = last.into_inner().unwrap_or_else(|e| e.into_inner());
+        match result {
+            Ok(true) => emit(&last, RemovalState::Done, None),
+            Ok(false) => emit(&last, RemovalState::Cancelled, None),
+            Err(e) => emit(&last, RemovalState::Error, Some(e.as_str())),
+        }
+        eprintln!("[remover] Removal of {} finished in {:?}", label, started.elapsed());
+        if let Some(jobs) = JOBS.lock().unwrap_or_else(|e| e.into_inner()).as_mut() {
+            jobs.remove(&id);
+        }
+    });
+    job_id
+}
+
+/// Finishes deletions a crash or a cancel left behind in `dir`.
+pub fn sweep_leftovers(dir: PathBuf) {
+    std::thread::spawn(move || {
+        for entry in fs::read_dir(&dir).into_iter().flatten().flatten() {
+            if entry.file_name().to_string_lossy().starts_with(TOMBSTONE_PREFIX) {
+                let _ = remove_all(&entry.path());
+            }
+        }
+    });
+}
+
+#[tauri::command]
+pub fn cancel_removal(job_id: String) -> bool {
+    let cancel = JOBS.lock().ok().and_then(|jobs| jobs.as_ref().and_then(|jobs| jobs.get(&job_id).cloned()));
+    if let Some(cancel) = &cancel {
+        cancel.store(true, Ordering::Relaxed);
+    }
+    cancel.is_some()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn populate(dir: &Path) {
+        for sub in ["mods", "saves/world/region", "logs"] {
+            fs::create_dir_all(dir.join(sub)).unwrap();
+            for i in 0..5 {
+                fs::write(dir.join(sub).join(format!("f{}", i)), vec![1u8; 10]).unwrap();
+            }
+        }
+    }
+
+    #[test]
+    fn trees_are_measured_and_removed_with_progress() {
+        let dir = tempfile::tempdir().unwrap();
+        let target = dir.path().join("Evento");
+        populate(&target);
+        assert_eq!(measure(&target), (15, 150));
+
+        let reports = Mutex::new(Vec::new());
+        let done = remove_tree(&target, &AtomicBool::new(false), &|p| reports.lock().unwrap().push(p.clone())).unwrap();
+        assert!(done);
+        assert!(!target.exists());
+        let last = reports.into_inner().unwrap().pop().unwrap();
+        assert_eq!(last, RemovalProgress { done_files: 15, total_files: 15, done_bytes: 150, total_bytes: 150 });
+    }
+
+    #[test]
+    fn cancelled_removals_stop_and_tombstones_are_swept() {
+        let dir = tempfile::tempdir().unwrap();
+        let target = dir.path().join("Evento");
+        populate(&target);
+        assert_eq!(remove_tree(&target, &AtomicBool::new(true), &|_| {}), Ok(false));
+        assert!(target.exists());
+
+        let tombstone = retire(&target);
+        assert!(!target.exists());
+        assert!(tombstone.file_name().unwrap().to_string_lossy().starts_with(TOMBSTONE_PREFIX));
+        remove_all(&tombstone).unwrap();
+        assert!(!tombstone.exists());
+    }
+}
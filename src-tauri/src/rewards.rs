@@ -0,0 +1,167 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use crate::settings::{load_settings, save_settings};
+
+// The game already closed; don't keep the player waiting on a slow event server
+const SUBMIT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// What the launcher reports once an event session ends.
+#[derive(Serialize, Clone, Debug)]
+pub struct SessionSummary {
+    #[serde(rename = "playerUuid")]
+    pub player_uuid: String,
+    #[serde(rename = "playerName")]
+    pub player_name: String,
+    #[serde(rename = "instanceId")]
+    pub instance_id: String,
+    #[serde(rename = "instanceName")]
+    pub instance_name: String,
+    #[serde(rename = "playtimeSeconds")]
+    pub playtime_seconds: u64,
+    /// Unix seconds
+    #[serde(rename = "startedAt")]
+    pub started_at: u64,
+    #[serde(rename = "endedAt")]
+    pub ended_at: u64,
+    /// Process exit code; `None` when the launcher lost track of the game
+    #[serde(rename = "exitCode")]
+    pub exit_code: Option<i32>,
+}
+
+impl SessionSummary {
+    pub fn new(profile: &crate::auth::MinecraftProfile, instance_id: &str, instance_name: &str, playtime: Duration, exit_code: Option<i32>) -> Self {
+        let ended_at = crate::unix_now();
+        SessionSummary {
+            player_uuid: profile.id.clone(),
+            player_name: profile.name.clone(),
+            instance_id: instance_id.to_string(),
+            instance_name: instance_name.to_string(),
+            playtime_seconds: playtime.as_secs(),
+            started_at: ended_at.saturating_sub(playtime.as_secs()),
+            ended_at,
+            exit_code,
+        }
+    }
+}
+
+/// The endpoint's answer, shown to the player when the session ends. Every field is optional so
+/// event servers can answer with as little as `{}`.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct RewardResult {
+    #[serde(default)]
+    pub points: Option<i64>,
+    #[serde(rename = "totalPoints", alias = "total_points", default)]
+    pub total_points: Option<i64>,
+    #[serde(default)]
+    pub message: Option<String>,
+}
+
+impl RewardResult {
+    /// One line for the progress bar, e.g. "+120 puntos (total 940)".
+    pub fn summary(&self) -> String {
+        let points = match (self.points, self.total_points) {
+            (Some(points), Some(total)) => Some(format!("{:+} puntos (total {})", points, total)),
+            (Some(points), None) => Some(format!("{:+} puntos", points)),
+            (None, Some(total)) => Some(format!("Total: {} puntos", total)),
+            (None, None) => None,
+        };
+        match (points, self.message.as_deref().map(str::trim).filter(|m| !m.is_empty())) {
+            (Some(points), Some(message)) => format!("{} · {}", points, message),
+            (Some(points), None) => points,
+            (None, Some(message)) => message.to_string(),
+            (None, None) => "Resultados del evento enviados".to_string(),
+        }
+    }
+}
+
+/// What the final `launch_progress` payload carries as `reward`.
+#[derive(Serialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RewardReport {
+    pub submitted: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub points: Option<i64>,
+    #[serde(rename = "totalPoints", skip_serializing_if = "Option::is_none")]
+    pub total_points: Option<i64>,
+    /// `RewardResult::summary`, or why the submission failed
+    pub message: String,
+}
+
+/// Endpoint the session summary goes to, if staff configured one.
+pub fn endpoint() -> Option<String> {
+    load_settings().rewards_endpoint.filter(|e| !e.trim().is_empty())
+}
+
+/// POSTs the summary; a non-JSON success body still counts as submitted.
+pub fn submit(endpoint: &str, summary: &SessionSummary) -> Result<RewardResult, String> {
+    let client = reqwest::blocking::Client::builder()
+        .user_agent(crate::http::user_agent())
+        .timeout(SUBMIT_TIMEOUT)
+        .build()
+        .map_err(|e| e.to_string())?;
+    let resp = crate::http::send(&client, client.post(endpoint).json(summary))?;
+    if !resp.status().is_success() {
+        return Err(format!("El servidor de recompensas respondió {}", resp.status()));
+    }
+    let body = resp.text().map_err(|e| e.to_string())?;
+    Ok(serde_json::from_str(&body).unwrap_or_default())
+}
+
+/// Submits the session when an endpoint is configured, showing the wait on the progress bar.
+/// The result rides on the final `launch_progress` payload as `reward`; failures are reported
+/// there too but never turn a clean exit into an error.
+pub fn report_session(app: &tauri::AppHandle, summary: &SessionSummary) -> Option<RewardReport> {
+    let endpoint = endpoint()?;
+    crate::events::emit_progress(app, crate::events::LaunchProgress::new(&summary.instance_id, "recompensa", 100, "Enviando resultados del evento..."));
+    Some(match submit(&endpoint, summary) {
+        Ok(result) => RewardReport {
+            submitted: true,
+            points: result.points,
+            total_points: result.total_points,
+            message: result.summary(),
+        },
+        Err(e) => {
+            eprintln!("[rewards] {}", e);
+            RewardReport { submitted: false, points: None, total_points: None, message: format!("No se pudieron enviar los resultados: {}", e) }
+        }
+    })
+}
+
+#[tauri::command]
+pub fn get_rewards_endpoint() -> Option<String> {
+    load_settings().rewards_endpoint
+}
+
+#[tauri::command]
+pub fn set_rewards_endpoint(password: String, endpoint: Option<String>) -> Result<(), String> {
+    crate::ensure_admin(password)?;
+    let mut settings = load_settings();
+    settings.rewards_endpoint = endpoint.map(|e| e.trim().to_string()).filter(|e| !e.is_empty());
+    save_settings(&settings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn responses_parse_leniently_and_summarize() {
+        let result: RewardResult = serde_json::from_str(r#"{"points":120,"total_points":940,"rank":3}"#).unwrap();
+        assert_eq!(result.summary(), "+120 puntos (total 940)");
+        let result: RewardResult = serde_json::from_str(r#"{"message":" ¡Gracias por jugar! "}"#).unwrap();
+        assert_eq!(result.summary(), "¡Gracias por jugar!");
+        assert_eq!(RewardResult::default().summary(), "Resultados del evento enviados");
+        let result = RewardResult { points: Some(-5), total_points: None, message: Some("Penalización".into()) };
+        assert_eq!(result.summary(), "-5 puntos · Penalización");
+    }
+
+    #[test]
+    fn summaries_use_the_session_window() {
+        let profile = crate::auth::MinecraftProfile { id: "uuid".into(), name: "Steve".into(), access_token: String::new(), has_entitlement: true };
+        let summary = SessionSummary::new(&profile, "evento", "Evento", Duration::from_secs(3600), Some(0));
+        assert_eq!(summary.ended_at - summary.started_at, 3600);
+        let json = serde_json::to_value(&summary).unwrap();
+        assert_eq!(json["playerUuid"], "uuid");
+        assert_eq!(json["playtimeSeconds"], 3600);
+    }
+}
@@ -0,0 +1,308 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use crate::launch_history::Outcome;
+use crate::minecraft::game_options::{apply_option_values, low_end_options};
+use crate::minecraft::mod_scan::{jar_names, pack_mods};
+use crate::minecraft::url_resolver::mod_file_name;
+
+const STATE_FILE: &str = "safe-mode.json";
+// Crashes in a row before safe mode is offered
+const CRASH_LOOP: usize = 3;
+const DISABLED_SUFFIX: &str = ".disabled";
+// Next to options.txt while the settings are lowered
+const OPTIONS_BACKUP: &str = "options.txt.safe-mode";
+
+/// Safe mode goes one step further each time the game still crashes; every step keeps the
+/// changes of the ones before it, so the step that got a clean session is what fixed it.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum SafeModeStep {
+    /// Low video settings, as in the low-end optimization
+    LowerSettings,
+    /// Jars added since the last session that closed cleanly are disabled
+    DisableRecentMods,
+    /// Jars neither the mod list nor the modpack brought are disabled
+    DisableOptionalMods,
+}
+
+const STEPS: [SafeModeStep; 3] = [SafeModeStep::LowerSettings, SafeModeStep::DisableRecentMods, SafeModeStep::DisableOptionalMods];
+
+impl SafeModeStep {
+    fn description(self) -> &'static str {
+        match self {
+            SafeModeStep::LowerSettings => "Gráficos rápidos, distancia de renderizado 8, pocas partículas y sin sombras.",
+            SafeModeStep::DisableRecentMods => "Además de bajar los gráficos, desactiva los mods añadidos desde la última partida que se cerró bien.",
+            SafeModeStep::DisableOptionalMods => "Además de bajar los gráficos, desactiva los mods que no vienen en la lista de la instancia ni en el modpack.",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct SafeModeState {
+    /// Step the next launches use; `None` while the instance runs normally
+    #[serde(default)]
+    pub step: Option<SafeModeStep>,
+    /// Steps whose sessions still crashed
+    #[serde(default)]
+    pub failed: Vec<SafeModeStep>,
+    /// Step of the last safe mode session that closed cleanly
+    #[serde(rename = "fixedBy", alias = "fixed_by", default)]
+    pub fixed_by: Option<SafeModeStep>,
+    /// Jars renamed to `<name>.disabled`, put back when safe mode ends
+    #[serde(default)]
+    pub disabled: Vec<String>,
+    #[serde(rename = "settingsLowered", alias = "settings_lowered", default)]
+    pub settings_lowered: bool,
+    /// Jars in `mods/` after the last session outside safe mode that closed cleanly
+    #[serde(rename = "knownGood", alias = "known_good", default)]
+    pub known_good: Option<Vec<String>>,
+}
+
+/// Safe mode launch proposed after a crash, for the crash dialog.
+#[derive(Serialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SafeModeOffer {
+    pub step: SafeModeStep,
+    /// Crashes in a row so far
+    pub crashes: usize,
+    pub description: String,
+    /// Jars this step disables
+    pub mods: Vec<String>,
+}
+
+static LOCK: Mutex<()> = Mutex::new(());
+
+fn state_path() -> PathBuf {
+    PathBuf::from(crate::get_instances_dir()).join(STATE_FILE)
+}
+
+fn load() -> HashMap<String, SafeModeState> {
+    fs::read_to_string(state_path())
+        .ok()
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+
+fn save(states: &HashMap<String, SafeModeState>) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(states).map_err(|e| e.to_string())?;
+    fs::write(state_path(), json).map_err(|e| e.to_string())
+}
+
+fn state_of(instance_id: &str) -> SafeModeState {
+    let _guard = LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    load().remove(instance_id).unwrap_or_default()
+}
+
+/// Steps still worth trying: all of them once the crashes loop, then the ones after a step
+/// that crashed too.
+fn remaining_steps(state: &SafeModeState, crashes: usize) -> &'static [SafeModeStep] {
+    match state.step {
+        None if crashes >= CRASH_LOOP => &STEPS,
+        Some(step) if crashes > 0 && state.failed.contains(&step) => {
+            &STEPS[STEPS.iter().position(|s| *s == step).map_or(STEPS.len(), |i| i + 1)..]
+        }
+        _ => &[],
+    }
+}
+
+/// Jars `step` disables. `listed` is what the mod list and the pack brought, `None` when the
+/// pack's own list is unknown and no jar can be called optional.
+fn mods_to_disable(step: SafeModeStep, jars: &[String], known_good: Option<&[String]>, listed: Option<&HashSet<String>>) -> Vec<String> {
+    jars.iter()
+        .filter(|jar| {
+            let recent = step >= SafeModeStep::DisableRecentMods && known_good.is_some_and(|good| !good.contains(jar));
+            let optional = step >= SafeModeStep::DisableOptionalMods && listed.is_some_and(|listed| !listed.contains(*jar));
+            recent || optional
+        })
+        .cloned()
+        .collect()
+}
+
+fn listed_mods(instance: &crate::Instance, minecraft_dir: &Path) -> Option<HashSet<String>> {
+    let mut listed: HashSet<String> = instance.mods.iter().flatten().map(|url| mod_file_name(url)).collect();
+    if instance.modpack_url.as_deref().is_some_and(|u| !u.is_empty()) {
+        listed.extend(pack_mods(minecraft_dir)?);
+    }
+    Some(listed)
+}
+
+/// Safe mode offer for an instance whose last session crashed. Steps that would change
+/// nothing over the previous one (no recent or optional jars) are skipped.
+pub fn offer(instance: &crate::Instance) -> Option<SafeModeOffer> {
+    let crashes = crate::launch_history::consecutive_crashes(&instance.id);
+    let state = state_of(&instance.id);
+    let minecraft_dir = Path::new(&instance.path).join("minecraft");
+    let jars = jar_names(&minecraft_dir.join("mods"));
+    let listed = listed_mods(instance, &minecraft_dir);
+    remaining_steps(&state, crashes).iter().find_map(|&step| {
+        let mods = mods_to_disable(step, &jars, state.known_good.as_deref(), listed.as_ref());
+        (step == SafeModeStep::LowerSettings || !mods.is_empty()).then(|| SafeModeOffer {
+            step,
+            crashes,
+            description: step.description().to_string(),
+            mods,
+        })
+    })
+}
+
+fn disable(mods_dir: &Path, jar: &str) -> Result<(), String> {
+    let target = mods_dir.join(format!("{}{}", jar, DISABLED_SUFFIX));
+    let _ = fs::remove_file(&target);
+    fs::rename(mods_dir.join(jar), &target).map_err(|e| format!("No se pudo desactivar {}: {}", jar, e))
+}
+
+/// Applies the instance's safe mode step once its content is synced, right before the game
+/// starts: the sync puts back what an earlier safe launch disabled. Returns the step used.
+pub fn before_launch(instance: &crate::Instance, minecraft_dir: &Path) -> Result<Option<SafeModeStep>, String> {
+    let _guard = LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let mut states = load();
+    let Some(state) = states.get_mut(&instance.id) else { return Ok(None) };
+    let Some(step) = state.step else { return Ok(None) };
+    if !state.settings_lowered {
+        // An empty backup puts the game's defaults back if there was no options.txt yet
+        let options = fs::read_to_string(minecraft_dir.join("options.txt")).unwrap_or_default();
+        fs::write(minecraft_dir.join(OPTIONS_BACKUP), options).map_err(|e| e.to_string())?;
+        state.settings_lowered = true;
+    }
+    apply_option_values(minecraft_dir, &low_end_options(&instance.version))?;
+    let mods_dir = minecraft_dir.join("mods");
+    let listed = listed_mods(instance, minecraft_dir);
+    for jar in mods_to_disable(step, &jar_names(&mods_dir), state.known_good.as_deref(), listed.as_ref()) {
+        disable(&mods_dir, &jar)?;
+        if !state.disabled.contains(&jar) {
+            state.disabled.push(jar);
+        }
+    }
+    save(&states)?;
+    Ok(Some(step))
+}
+
+/// Records how a session that got to run ended: which safe mode step fixed the crashes, or
+/// which one didn't, and the jars of a clean session outside safe mode.
+pub fn after_session(instance_id: &str, minecraft_dir: &Path, outcome: Outcome) {
+    let _guard = LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let mut states = load();
+    let state = states.entry(instance_id.to_string()).or_default();
+    match (state.step, outcome) {
+        (Some(step), Outcome::Closed) => state.fixed_by = Some(step),
+        (Some(step), _) => {
+            state.fixed_by = None;
+            if !state.failed.contains(&step) {
+                state.failed.push(step);
+            }
+        }
+        (None, Outcome::Closed) => state.known_good = Some(jar_names(&minecraft_dir.join("mods"))),
+        (None, _) => return,
+    }
+    if let Err(e) = save(&states) {
+        eprintln!("[safe_mode] Failed to save safe mode state: {}", e);
+    }
+}
+
+/// Safe mode state for crash log uploads, `None` for instances that never used it.
+pub fn report(instance_id: &str) -> Option<serde_json::Value> {
+    let state = state_of(instance_id);
+    if state.step.is_none() && state.fixed_by.is_none() && state.failed.is_empty() {
+        return None;
+    }
+    serde_json::to_value(SafeModeState { known_good: None, ..state }).ok()
+}
+
+#[tauri::command]
+pub fn get_safe_mode(instance_id: String) -> SafeModeState {
+    state_of(&instance_id)
+}
+
+/// Next launches of the instance use `step`; launch it normally afterwards.
+#[tauri::command]
+pub fn start_safe_mode(instance_id: String, step: SafeModeStep) -> Result<(), String> {
+    crate::settings::ensure_not_guest()?;
+    if !crate::load_instances().iter().any(|i| i.id == instance_id) {
+        return Err("Instance not found".to_string());
+    }
+    let _guard = LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let mut states = load();
+    let state = states.entry(instance_id).or_default();
+    // A new round starts clean; going one step further keeps the steps that failed
+    if state.step.is_none() {
+        state.failed.clear();
+    }
+    state.step = Some(step);
+    state.fixed_by = None;
+    save(&states)
+}
+
+/// Puts the disabled jars and the player's settings back. What fixed the crashes stays on
+/// record for later crash reports. Returns warnings for jars that couldn't be restored.
+#[tauri::command]
+pub async fn exit_safe_mode(instance_id: String) -> Result<Vec<String>, String> {
+    crate::settings::ensure_not_guest()?;
+    crate::run_blocking(move || {
+        let instance = crate::load_instances().into_iter().find(|i| i.id == instance_id)
+            .ok_or("Instance not found")?;
+        let minecraft_dir = Path::new(&instance.path).join("minecraft");
+        let mods_dir = minecraft_dir.join("mods");
+        let _guard = LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let mut states = load();
+        let Some(state) = states.get_mut(&instance_id) else { return Ok(Vec::new()) };
+        let mut warnings = Vec::new();
+        for jar in state.disabled.drain(..) {
+            let disabled = mods_dir.join(format!("{}{}", jar, DISABLED_SUFFIX));
+            if !disabled.exists() {
+                continue;
+            }
+            // The sync already downloaded it again
+            let restored = if mods_dir.join(&jar).exists() { fs::remove_file(&disabled) } else { fs::rename(&disabled, mods_dir.join(&jar)) };
+            if let Err(e) = restored {
+                warnings.push(format!("No se pudo reactivar {}: {}", jar, e));
+            }
+        }
+        let backup = minecraft_dir.join(OPTIONS_BACKUP);
+        if state.settings_lowered && backup.exists() {
+            fs::rename(&backup, minecraft_dir.join("options.txt")).map_err(|e| format!("No se pudo restaurar options.txt: {}", e))?;
+        }
+        state.step = None;
+        state.settings_lowered = false;
+        save(&states)?;
+        Ok(warnings)
+    }).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn steps_escalate_after_each_crash() {
+        let mut state = SafeModeState::default();
+        assert!(remaining_steps(&state, CRASH_LOOP - 1).is_empty());
+        assert_eq!(remaining_steps(&state, CRASH_LOOP), &STEPS);
+        state.step = Some(SafeModeStep::LowerSettings);
+        // Not launched in safe mode yet
+        assert!(remaining_steps(&state, CRASH_LOOP).is_empty());
+        state.failed.push(SafeModeStep::LowerSettings);
+        assert_eq!(remaining_steps(&state, CRASH_LOOP + 1)[0], SafeModeStep::DisableRecentMods);
+        state.step = Some(SafeModeStep::DisableOptionalMods);
+        state.failed.push(SafeModeStep::DisableOptionalMods);
+        assert!(remaining_steps(&state, CRASH_LOOP + 2).is_empty());
+    }
+
+    #[test]
+    fn steps_disable_recent_then_optional_mods() {
+        let jars = names(&["base.jar", "new.jar", "extra.jar"]);
+        let good = names(&["base.jar", "extra.jar"]);
+        let listed: HashSet<String> = names(&["base.jar", "new.jar"]).into_iter().collect();
+        assert!(mods_to_disable(SafeModeStep::LowerSettings, &jars, Some(&good), Some(&listed)).is_empty());
+        assert_eq!(mods_to_disable(SafeModeStep::DisableRecentMods, &jars, Some(&good), Some(&listed)), names(&["new.jar"]));
+        assert_eq!(mods_to_disable(SafeModeStep::DisableOptionalMods, &jars, Some(&good), Some(&listed)), names(&["new.jar", "extra.jar"]));
+        // Nothing known about earlier sessions or the pack's jars
+        assert!(mods_to_disable(SafeModeStep::DisableOptionalMods, &jars, None, None).is_empty());
+    }
+}
@@ -0,0 +1,96 @@
+use std::path::{Path, PathBuf};
+use crate::Instance;
+
+/// Sandboxes live in `<data>/sandbox/<id>`, each a launcher root of its own: libraries, assets,
+/// versions, the mod store and Java are downloaded again inside it, and the instance copy sits
+/// in `instance/`.
+const SANDBOX_DIR: &str = "sandbox";
+const INSTANCE_DIR: &str = "instance";
+
+pub fn root() -> PathBuf {
+    PathBuf::from(crate::get_instances_dir()).join(SANDBOX_DIR)
+}
+
+/// True for anything inside a sandbox; shared caches must not index or link these files.
+pub fn contains(path: &Path) -> bool {
+    path.starts_with(root())
+}
+
+/// Root the launch of `instance` downloads into and runs from.
+pub fn base_path(instance: &Instance) -> PathBuf {
+    match &instance.sandbox_root {
+        Some(root) => PathBuf::from(root),
+        None => PathBuf::from(crate::get_instances_dir()),
+    }
+}
+
+/// Copies `source` into a new sandbox under `sandboxes`. The copy prepares from scratch under
+/// its own root, starting from the loader build the original is locked to.
+pub fn clone_into(source: &Instance, sandboxes: &Path, id: &str) -> Result<Instance, String> {
+    let sandbox_root = sandboxes.join(id);
+    let path = sandbox_root.join(INSTANCE_DIR);
+    crate::data_dir::copy_tree(Path::new(&source.path), &path)?;
+    crate::minecraft::prepare_checkpoint::clear(&path.join("minecraft"));
+    let mut clone = source.clone();
+    clone.id = id.to_string();
+    clone.name = format!("{} (prueba)", source.name);
+    clone.path = path.to_string_lossy().to_string();
+    clone.sandbox_root = Some(sandbox_root.to_string_lossy().to_string());
+    Ok(clone)
+}
+
+/// Clone of an instance that never touches the shared libraries, assets or Java, so staff can
+/// try loader upgrades or pack changes on a machine players are using.
+#[tauri::command]
+pub async fn create_sandbox_clone(password: String, instance_id: String) -> Result<Instance, String> {
+    crate::ensure_admin(password)?;
+    crate::run_blocking(move || {
+        let mut instances = crate::load_instances();
+        let source = instances.iter().find(|i| i.id == instance_id).ok_or("Instance not found")?;
+        if source.sandbox_root.is_some() {
+            return Err("La instancia ya es una copia de prueba".to_string());
+        }
+        let clone = clone_into(source, &root(), &uuid::Uuid::new_v4().to_string())?;
+        crate::minecraft::loader_pin::copy_lock(&instance_id, &clone.id)?;
+        instances.push(clone.clone());
+        crate::save_instances(&instances);
+        Ok(clone)
+    }).await
+}
+
+/// Sandbox folder to delete with `instance`: the whole root, as long as it really is one.
+pub fn removable_root(instance: &Instance) -> Option<PathBuf> {
+    let root = crate::path_policy::inside(Path::new(instance.sandbox_root.as_ref()?), &root()).ok()?;
+    crate::path_policy::inside(Path::new(&instance.path), &root).ok()?;
+    (root != crate::path_policy::resolve(&self::root()).ok()?).then_some(root)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clones_start_unprepared_under_their_own_root() {
+        let dir = tempfile::tempdir().unwrap();
+        let original = dir.path().join("instancias").join("Evento");
+        let minecraft = original.join("minecraft");
+        std::fs::create_dir_all(minecraft.join("mods")).unwrap();
+        std::fs::write(minecraft.join("mods").join("a.jar"), "jar").unwrap();
+        std::fs::write(minecraft.join(".prepare-checkpoint.json"), "{}").unwrap();
+        let source: Instance = serde_json::from_value(serde_json::json!({
+            "id": "evento", "name": "Evento", "version": "1.20.1", "icon": "default",
+            "path": original.to_string_lossy(), "modloader": "forge"
+        })).unwrap();
+
+        let clone = clone_into(&source, &dir.path().join("sandbox"), "prueba-1").unwrap();
+        let sandbox_root = dir.path().join("sandbox").join("prueba-1");
+        assert_eq!(clone.id, "prueba-1");
+        assert_eq!(base_path(&clone), sandbox_root);
+        assert_eq!(Path::new(&clone.path), sandbox_root.join(INSTANCE_DIR));
+        let copied = sandbox_root.join(INSTANCE_DIR).join("minecraft");
+        assert_eq!(std::fs::read_to_string(copied.join("mods").join("a.jar")).unwrap(), "jar");
+        assert!(!copied.join(".prepare-checkpoint.json").exists());
+        assert!(minecraft.join(".prepare-checkpoint.json").exists());
+        assert_eq!(source.sandbox_root, None);
+    }
+}
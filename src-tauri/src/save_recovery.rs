@@ -0,0 +1,369 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter};
+use crate::minecraft::archive::walk_files;
+use crate::minecraft::nbt::read_gzip_file;
+
+const SECTOR: u64 = 4096;
+// Location table and timestamp table
+const HEADER_BYTES: u64 = 2 * SECTOR;
+// Chunk compression ids: gzip, zlib, none, lz4 (1.20.5+), custom; +128 when kept in a .mcc file
+const COMPRESSIONS: [u8; 5] = [1, 2, 3, 4, 127];
+const EXTERNAL_CHUNK: u8 = 128;
+const STAGING_DIR: &str = "save-restore";
+// Marks the copies taken of a damaged world before restoring it; never offered as backups
+const DAMAGED_TAG: &str = "damaged";
+
+#[derive(Serialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SaveProblem {
+    /// Path inside the world folder, `/` separated
+    pub file: String,
+    pub detail: String,
+}
+
+#[derive(Serialize, Clone, Debug, JsonSchema)]
+pub struct DamagedWorld {
+    pub folder: String,
+    pub problems: Vec<SaveProblem>,
+    /// `level.dat` is damaged and `level.dat_old`, the game's copy of the previous save, reads fine
+    #[serde(rename = "levelDatOld")]
+    pub level_dat_old: bool,
+    /// Zips of this world in `backups/worlds`, newest first
+    pub backups: Vec<String>,
+}
+
+/// `save_corruption` payload, emitted when a session that ended badly left damaged worlds.
+#[derive(Serialize, Clone, Debug, JsonSchema)]
+pub struct SaveCorruption {
+    #[serde(rename = "instanceId")]
+    pub instance_id: String,
+    pub worlds: Vec<DamagedWorld>,
+}
+
+#[derive(Deserialize, Clone, Debug, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum RestoreSource {
+    /// Puts `level.dat_old` back as `level.dat`; the regions are kept
+    LevelDatOld,
+    /// Replaces the whole world with a zip from `backups/worlds`
+    Backup { file: String },
+}
+
+fn read_u32(file: &mut impl Read) -> std::io::Result<u32> {
+    let mut bytes = [0u8; 4];
+    file.read_exact(&mut bytes)?;
+    Ok(u32::from_be_bytes(bytes))
+}
+
+/// Problems in a region file (`.mca`, also used for `entities/` and `poi/`): a cut header,
+/// chunks pointing into the header, past the end of the file or over each other, and with
+/// `deep`, chunks whose own header is zeroed or unreadable (what a power cut usually leaves).
+fn check_region<R: Read + Seek>(file: &mut R, len: u64, deep: bool) -> Vec<String> {
+    // The game creates empty region files and fills them later
+    if len == 0 {
+        return Vec::new();
+    }
+    if len < HEADER_BYTES {
+        return vec![format!("Cabecera incompleta ({} bytes)", len)];
+    }
+    let sectors = len.div_ceil(SECTOR);
+    let mut locations = Vec::new();
+    for index in 0..1024u64 {
+        match read_u32(file) {
+            Ok(0) => {}
+            Ok(entry) => locations.push((index, (entry >> 8) as u64, (entry & 0xff) as u64)),
+            Err(e) => return vec![format!("No se pudo leer la cabecera: {}", e)],
+        }
+    }
+    let chunk = |index: u64| format!("chunk {},{}", index % 32, index / 32);
+    let mut problems = Vec::new();
+    let mut used: Vec<(u64, u64, u64)> = Vec::new();
+    for &(index, offset, count) in &locations {
+        if offset < 2 {
+            problems.push(format!("El {} apunta a la cabecera", chunk(index)));
+        } else if count == 0 {
+            problems.push(format!("El {} no ocupa ningún sector", chunk(index)));
+        } else if offset + count > sectors {
+            problems.push(format!("El {} queda fuera del archivo (archivo cortado)", chunk(index)));
+        } else {
+            used.push((offset, offset + count, index));
+        }
+    }
+    used.sort();
+    for pair in used.windows(2) {
+        if pair[1].0 < pair[0].1 {
+            problems.push(format!("El {} se solapa con el {}", chunk(pair[1].2), chunk(pair[0].2)));
+        }
+    }
+    if deep {
+        for &(offset, end, index) in &used {
+            let header = file.seek(SeekFrom::Start(offset * SECTOR)).and_then(|_| {
+                let length = read_u32(file)?;
+                let mut compression = [0u8; 1];
+                file.read_exact(&mut compression)?;
+                Ok((length as u64, compression[0]))
+            });
+            match header {
+                // `length` counts the compression byte and the data, after its own 4 bytes
+                Ok((length, compression)) if compression & EXTERNAL_CHUNK == 0 && (length == 0 || 4 + length > (end - offset) * SECTOR) => {
+                    problems.push(format!("El {} tiene datos vacíos o cortados", chunk(index)));
+                }
+                Ok((_, compression)) if !COMPRESSIONS.contains(&(compression & !EXTERNAL_CHUNK)) => {
+                    problems.push(format!("El {} tiene datos dañados", chunk(index)));
+                }
+                Ok(_) => {}
+                Err(_) => problems.push(format!("El {} no se pudo leer", chunk(index))),
+            }
+        }
+    }
+    problems
+}
+
+/// What is wrong with `level.dat` (or `level.dat_old`), if anything.
+fn level_dat_problem(path: &Path) -> Option<String> {
+    let name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    if !path.is_file() {
+        return Some(format!("Falta {}", name));
+    }
+    match read_gzip_file(path) {
+        Ok(level) if level.get("Data").is_some() => None,
+        Ok(_) => Some(format!("{} no tiene datos del mundo", name)),
+        Err(e) => Some(format!("{} no se puede leer: {}", name, e)),
+    }
+}
+
+fn modified(path: &Path) -> SystemTime {
+    fs::metadata(path).and_then(|m| m.modified()).unwrap_or(UNIX_EPOCH)
+}
+
+/// Damage in one world. With `since`, only region files written after it get the full chunk check.
+fn scan_world(world_dir: &Path, since: Option<SystemTime>) -> Vec<SaveProblem> {
+    let mut problems = Vec::new();
+    if let Some(detail) = level_dat_problem(&world_dir.join("level.dat")) {
+        problems.push(SaveProblem { file: "level.dat".to_string(), detail });
+    }
+    for (name, path) in walk_files(world_dir).into_iter().filter(|(name, _)| name.ends_with(".mca")) {
+        let Ok(mut file) = File::open(&path) else { continue };
+        let len = file.metadata().map(|m| m.len()).unwrap_or(0);
+        let deep = since.is_none_or(|since| modified(&path) >= since);
+        let found = check_region(&mut file, len, deep);
+        if let Some(first) = found.first() {
+            let detail = match found.len() {
+                1 => first.clone(),
+                n => format!("{} (y {} problemas más)", first, n - 1),
+            };
+            problems.push(SaveProblem { file: name, detail });
+        }
+    }
+    problems
+}
+
+/// Zip backups of `folder`, newest first. The name prefix is ambiguous (`a-b-...` could be world
+/// `a` or `a-b`), so the zip must hold the folder itself.
+fn world_backups(backups_dir: &Path, folder: &str) -> Vec<String> {
+    let prefix = format!("{}-", folder);
+    let inside = format!("{}/", folder);
+    let mut backups: Vec<(SystemTime, String)> = fs::read_dir(backups_dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|e| e.file_name().to_string_lossy().to_string())
+        .filter(|name| name.starts_with(&prefix) && name.ends_with(".zip") && !name[prefix.len()..].starts_with(DAMAGED_TAG))
+        .filter(|name| {
+            File::open(backups_dir.join(name)).ok()
+                .and_then(|file| zip::ZipArchive::new(file).ok())
+                .is_some_and(|zip| zip.file_names().any(|entry| entry.starts_with(&inside)))
+        })
+        .map(|name| (modified(&backups_dir.join(&name)), name))
+        .collect();
+    backups.sort_by(|a, b| b.cmp(a));
+    backups.into_iter().map(|(_, name)| name).collect()
+}
+
+fn saves_dir(instance_path: &Path) -> PathBuf {
+    instance_path.join("minecraft").join("saves")
+}
+
+fn backups_dir(instance_path: &Path) -> PathBuf {
+    instance_path.join("backups").join("worlds")
+}
+
+/// Worlds with damage, with what they can be restored from. With `since`, worlds the session
+/// didn't touch are skipped.
+pub fn scan_saves(instance_path: &Path, since: Option<SystemTime>) -> Vec<DamagedWorld> {
+    let mut folders: Vec<PathBuf> = fs::read_dir(saves_dir(instance_path))
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|e| e.path())
+        .filter(|path| path.is_dir())
+        .collect();
+    folders.sort();
+    folders.into_iter()
+        .filter(|world| since.is_none_or(|since| walk_files(world).iter().any(|(_, path)| modified(path) >= since)))
+        .filter_map(|world| {
+            let problems = scan_world(&world, since);
+            if problems.is_empty() {
+                return None;
+            }
+            let folder = world.file_name()?.to_string_lossy().to_string();
+            let level_dat_old = problems.iter().any(|p| p.file == "level.dat") && level_dat_problem(&world.join("level.dat_old")).is_none();
+            Some(DamagedWorld { backups: world_backups(&backups_dir(instance_path), &folder), folder, problems, level_dat_old })
+        })
+        .collect()
+}
+
+/// Checks the worlds a session that ended badly was writing and warns the UI before anything
+/// else touches them. Returns the damaged worlds, so the caller can hold back the cloud upload.
+pub fn check_after_crash(app: &AppHandle, instance_id: &str, instance_path: &Path, since: SystemTime) -> Vec<DamagedWorld> {
+    let worlds = scan_saves(instance_path, Some(since));
+    if !worlds.is_empty() {
+        let _ = app.emit("save_corruption", SaveCorruption { instance_id: instance_id.to_string(), worlds: worlds.clone() });
+    }
+    worlds
+}
+
+fn instance_path(instance_id: &str) -> Result<PathBuf, String> {
+    crate::load_instances().into_iter().find(|i| i.id == instance_id)
+        .map(|i| PathBuf::from(i.path))
+        .ok_or_else(|| "Instance not found".to_string())
+}
+
+/// Full check of every world in the instance, for the player or staff to run by hand.
+#[tauri::command]
+pub async fn check_instance_saves(instance_id: String) -> Result<Vec<DamagedWorld>, String> {
+    crate::run_blocking(move || Ok(scan_saves(&instance_path(&instance_id)?, None))).await
+}
+
+/// Restores a damaged world. The damaged copy is zipped into `backups/worlds` first, so a
+/// restore never loses anything.
+#[tauri::command]
+pub async fn restore_world(instance_id: String, folder: String, source: RestoreSource) -> Result<(), String> {
+    crate::settings::ensure_not_guest()?;
+    crate::run_blocking(move || {
+        let instance_path = instance_path(&instance_id)?;
+        if crate::launch_history::running().iter().any(|(id, _)| *id == instance_id) {
+            return Err("Cierra el juego antes de restaurar el mundo".to_string());
+        }
+        let world_dir = saves_dir(&instance_path).join(&folder);
+        if folder.is_empty() || folder.contains(['/', '\\']) || folder == ".." || !world_dir.is_dir() {
+            return Err(format!("El mundo {} no existe", folder));
+        }
+        let backups = backups_dir(&instance_path);
+        // Checked before the damaged copy lands next to it
+        if let RestoreSource::Backup { file } = &source {
+            if !world_backups(&backups, &folder).contains(file) {
+                return Err(format!("{} no es una copia de seguridad de {}", file, folder));
+            }
+        }
+        fs::create_dir_all(&backups).map_err(|e| e.to_string())?;
+        let now = crate::unix_now();
+        crate::minecraft::worlds::zip_world(&world_dir, &backups.join(format!("{}-{}-{}.zip", folder, DAMAGED_TAG, now)))?;
+        match source {
+            RestoreSource::LevelDatOld => {
+                let old = world_dir.join("level.dat_old");
+                if let Some(problem) = level_dat_problem(&old) {
+                    return Err(format!("La copia automática tampoco sirve: {}", problem));
+                }
+                fs::copy(&old, world_dir.join("level.dat")).map_err(|e| format!("No se pudo restaurar level.dat: {}", e))?;
+            }
+            RestoreSource::Backup { file } => {
+                let incoming = instance_path.join(STAGING_DIR);
+                let _ = fs::remove_dir_all(&incoming);
+                fs::create_dir_all(&incoming).map_err(|e| e.to_string())?;
+                let extracted = File::open(backups.join(&file)).map_err(|e| e.to_string())
+                    .and_then(|file| zip::ZipArchive::new(file).map_err(|e| e.to_string()))
+                    .and_then(|mut zip| zip.extract(&incoming).map_err(|e| e.to_string()));
+                if let Err(e) = extracted.and_then(|_| if incoming.join(&folder).is_dir() { Ok(()) } else { Err("el mundo no está dentro".to_string()) }) {
+                    let _ = fs::remove_dir_all(&incoming);
+                    return Err(format!("No se pudo abrir la copia {}: {}", file, e));
+                }
+                fs::remove_dir_all(&world_dir).map_err(|e| e.to_string())?;
+                let moved = fs::rename(incoming.join(&folder), &world_dir).map_err(|e| e.to_string());
+                let _ = fs::remove_dir_all(&incoming);
+                moved?;
+            }
+        }
+        Ok(())
+    }).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    /// Region file with one chunk per `(index, offset, sectors, chunk header)`.
+    fn region(chunks: &[(usize, u32, u32, [u8; 5])], total_sectors: u64) -> Vec<u8> {
+        let mut data = vec![0u8; (total_sectors * SECTOR) as usize];
+        for &(index, offset, count, header) in chunks {
+            data[index * 4..index * 4 + 4].copy_from_slice(&((offset << 8) | count).to_be_bytes());
+            let start = offset as usize * SECTOR as usize;
+            if start + 5 <= data.len() {
+                data[start..start + 5].copy_from_slice(&header);
+            }
+        }
+        data
+    }
+
+    fn check(data: &[u8], deep: bool) -> Vec<String> {
+        check_region(&mut Cursor::new(data), data.len() as u64, deep)
+    }
+
+    const ZLIB: [u8; 5] = [0, 0, 0x0f, 0, 2];
+
+    #[test]
+    fn healthy_regions_pass() {
+        assert!(check(&[], true).is_empty());
+        assert!(check(&region(&[(0, 2, 1, ZLIB), (33, 3, 2, ZLIB)], 5), true).is_empty());
+    }
+
+    #[test]
+    fn damaged_regions_are_reported() {
+        assert_eq!(check(&[0u8; 100], false), vec!["Cabecera incompleta (100 bytes)".to_string()]);
+        // Cut at sector 4 while chunk 1,0 needs sectors 3-5
+        let cut = region(&[(0, 2, 1, ZLIB), (1, 3, 3, ZLIB)], 4);
+        assert_eq!(check(&cut, false), vec!["El chunk 1,0 queda fuera del archivo (archivo cortado)".to_string()]);
+        let overlap = region(&[(0, 2, 2, ZLIB), (1, 3, 1, ZLIB)], 4);
+        assert_eq!(check(&overlap, false).len(), 1);
+        let header = region(&[(5, 1, 1, ZLIB)], 3);
+        assert_eq!(check(&header, false), vec!["El chunk 5,0 apunta a la cabecera".to_string()]);
+        // Zeroed by a power cut: only the deep check reads the chunk itself
+        let zeroed = region(&[(0, 2, 1, [0; 5])], 3);
+        assert!(check(&zeroed, false).is_empty());
+        assert_eq!(check(&zeroed, true), vec!["El chunk 0,0 tiene datos vacíos o cortados".to_string()]);
+        let garbage = region(&[(0, 2, 1, [0, 0, 0x0f, 0, 9])], 3);
+        assert_eq!(check(&garbage, true), vec!["El chunk 0,0 tiene datos dañados".to_string()]);
+        // Stored in a separate .mcc file: the sector only holds the header
+        let external = region(&[(0, 2, 1, [0, 0, 0, 1, 2 | EXTERNAL_CHUNK])], 3);
+        assert!(check(&external, true).is_empty());
+    }
+
+    #[test]
+    fn damaged_worlds_list_their_backups() {
+        let dir = tempfile::tempdir().unwrap();
+        let world = saves_dir(dir.path()).join("survival");
+        fs::create_dir_all(world.join("region")).unwrap();
+        fs::write(world.join("level.dat"), b"not gzip").unwrap();
+        fs::write(world.join("region").join("r.0.0.mca"), region(&[(0, 2, 1, ZLIB)], 3)).unwrap();
+        let backups = backups_dir(dir.path());
+        fs::create_dir_all(&backups).unwrap();
+        crate::minecraft::worlds::zip_world(&world, &backups.join("survival-1.20.1-1.zip")).unwrap();
+        crate::minecraft::worlds::zip_world(&world, &backups.join("survival-damaged-2.zip")).unwrap();
+        fs::copy(backups.join("survival-1.20.1-1.zip"), backups.join("survival-old-3.zip")).unwrap();
+        fs::write(backups.join("survival-broken-4.zip"), b"").unwrap();
+
+        let damaged = scan_saves(dir.path(), None);
+        assert_eq!(damaged.len(), 1);
+        assert_eq!(damaged[0].problems.len(), 1);
+        assert_eq!(damaged[0].problems[0].file, "level.dat");
+        assert!(!damaged[0].level_dat_old);
+        let mut listed = damaged[0].backups.clone();
+        listed.sort();
+        assert_eq!(listed, vec!["survival-1.20.1-1.zip", "survival-old-3.zip"]);
+    }
+}
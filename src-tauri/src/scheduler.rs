@@ -0,0 +1,244 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::{Condvar, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager, State};
+use crate::settings::{load_settings, save_settings};
+
+// Upper bound between checks so clock changes (sleep, manual adjustments) are picked up
+const MAX_IDLE: Duration = Duration::from_secs(60);
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug, Default, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum JobAction {
+    /// Same as "Verificar/Reparar": loader files, libraries, assets, modpack and mods
+    #[default]
+    Verify,
+    /// Only the modpack and the mod list
+    Sync,
+}
+
+/// A one-shot job, typically "verify everything 2 hours before the event". Persisted in settings.
+#[derive(Serialize, Deserialize, Clone, Debug, JsonSchema)]
+pub struct ScheduledJob {
+    pub id: String,
+    #[serde(rename = "instanceIds", alias = "instance_ids", default)]
+    pub instance_ids: Vec<String>,
+    /// Unix seconds
+    #[serde(rename = "runAt", alias = "run_at")]
+    pub run_at: u64,
+    #[serde(default)]
+    pub action: JobAction,
+    #[serde(rename = "lastRun", alias = "last_run", default)]
+    pub last_run: Option<u64>,
+    #[serde(rename = "lastResult", alias = "last_result", default)]
+    pub last_result: Option<String>,
+}
+
+/// Wakes the scheduler thread when jobs change instead of waiting for the next tick.
+#[derive(Default)]
+pub struct SchedulerState {
+    changed: Mutex<bool>,
+    wake: Condvar,
+}
+
+impl SchedulerState {
+    fn notify(&self) {
+        if let Ok(mut changed) = self.changed.lock() {
+            *changed = true;
+            self.wake.notify_all();
+        }
+    }
+
+    fn wait(&self, timeout: Duration) {
+        let Ok(guard) = self.changed.lock() else { return; };
+        if let Ok((mut changed, _)) = self.wake.wait_timeout_while(guard, timeout, |changed| !*changed) {
+            *changed = false;
+        }
+    }
+}
+
+/// Jobs whose time has come and that haven't run yet.
+pub fn due_jobs(jobs: &[ScheduledJob], now: u64) -> Vec<ScheduledJob> {
+    jobs.iter().filter(|j| j.last_run.is_none() && j.run_at <= now).cloned().collect()
+}
+
+/// How long the scheduler can sleep before the next pending job.
+pub fn next_wake(jobs: &[ScheduledJob], now: u64) -> Duration {
+    jobs.iter()
+        .filter(|j| j.last_run.is_none())
+        .map(|j| Duration::from_secs(j.run_at.saturating_sub(now)))
+        .min()
+        .unwrap_or(MAX_IDLE)
+        .min(MAX_IDLE)
+}
+
+fn run_action(app: &AppHandle, instance: &crate::Instance, action: JobAction) -> Result<(), String> {
+    match action {
+        JobAction::Verify => {
+            // Only the downloads matter here; the launch command built at the end is discarded
+            let profile = app.state::<crate::AppState>().auth.lock()
+                .ok()
+                .and_then(|auth| auth.profile.clone())
+                .unwrap_or_default();
+            crate::verify_instance_files(app, instance, &profile)
+        }
+        JobAction::Sync => crate::minecraft::launch_logic::sync_instance_content(
+            &PathBuf::from(crate::get_instances_dir()),
+            &PathBuf::from(&instance.path).join("minecraft"),
+            instance.mods.clone(),
+            instance.modpack_url.clone(),
+            instance.translation_pack_url.as_deref(),
+            true,
+            &Some(app.clone()),
+            &instance.id,
+            None,
+        ),
+    }
+}
+
+fn run_job(app: &AppHandle, job: &ScheduledJob) -> Result<(), String> {
+    let instances = crate::load_instances();
+    let mut failures = Vec::new();
+    for id in &job.instance_ids {
+        let result = match instances.iter().find(|i| &i.id == id) {
+            Some(instance) => {
+                let result = run_action(app, instance, job.action);
+                let what = match job.action {
+                    JobAction::Verify => "Verificación programada",
+                    JobAction::Sync => "Actualización programada",
+                };
+                crate::notifications::notify_completion(app, &instance.name, what, &result);
+                result.map_err(|e| format!("{}: {}", instance.name, e))
+            }
+            None => Err(format!("{}: instancia no encontrada", id)),
+        };
+        if let Err(e) = result {
+            failures.push(e);
+        }
+    }
+    if failures.is_empty() { Ok(()) } else { Err(failures.join("; ")) }
+}
+
+fn record_result(job_id: &str, ran_at: u64, result: &Result<(), String>) {
+    let mut settings = load_settings();
+    if let Some(job) = settings.scheduled_jobs.iter_mut().find(|j| j.id == job_id) {
+        job.last_run = Some(ran_at);
+        job.last_result = Some(match result {
+            Ok(()) => "ok".to_string(),
+            Err(e) => e.clone(),
+        });
+        if let Err(e) = save_settings(&settings) {
+            eprintln!("[scheduler] Failed to save scheduled job result: {}", e);
+        }
+    }
+}
+
+/// `scheduled_job` payload: `running` when a job starts, then `ok` or `error`.
+#[derive(Serialize, Clone, JsonSchema)]
+pub struct ScheduledJobStatus {
+    #[serde(rename = "jobId")]
+    pub job_id: String,
+    pub status: String,
+    pub message: String,
+}
+
+/// Runs due jobs one at a time for the lifetime of the app.
+pub fn start(app: AppHandle) {
+    std::thread::spawn(move || loop {
+        let now = crate::unix_now();
+        let jobs = load_settings().scheduled_jobs;
+        for job in due_jobs(&jobs, now) {
+            let _ = app.emit("scheduled_job", ScheduledJobStatus {
+                job_id: job.id.clone(),
+                status: "running".to_string(),
+                message: "Tarea programada iniciada".to_string(),
+            });
+            let result = run_job(&app, &job);
+            record_result(&job.id, now, &result);
+            let _ = app.emit("scheduled_job", ScheduledJobStatus {
+                job_id: job.id.clone(),
+                status: if result.is_ok() { "ok" } else { "error" }.to_string(),
+                message: match &result {
+                    Ok(()) => "Tarea programada completada".to_string(),
+                    Err(e) => e.clone(),
+                },
+            });
+        }
+        let jobs = load_settings().scheduled_jobs;
+        app.state::<SchedulerState>().wait(next_wake(&jobs, crate::unix_now()));
+    });
+}
+
+#[tauri::command]
+pub fn get_scheduled_jobs() -> Vec<ScheduledJob> {
+    load_settings().scheduled_jobs
+}
+
+#[tauri::command]
+pub fn schedule_job(
+    password: String,
+    instance_ids: Vec<String>,
+    run_at: u64,
+    action: JobAction,
+    state: State<'_, SchedulerState>,
+) -> Result<ScheduledJob, String> {
+    crate::ensure_admin(password)?;
+    if instance_ids.is_empty() {
+        return Err("Selecciona al menos una instancia".to_string());
+    }
+    let job = ScheduledJob {
+        id: uuid::Uuid::new_v4().to_string(),
+        instance_ids,
+        run_at,
+        action,
+        last_run: None,
+        last_result: None,
+    };
+    let mut settings = load_settings();
+    settings.scheduled_jobs.push(job.clone());
+    save_settings(&settings)?;
+    state.notify();
+    Ok(job)
+}
+
+#[tauri::command]
+pub fn cancel_scheduled_job(password: String, job_id: String, state: State<'_, SchedulerState>) -> Result<(), String> {
+    crate::ensure_admin(password)?;
+    let mut settings = load_settings();
+    settings.scheduled_jobs.retain(|j| j.id != job_id);
+    save_settings(&settings)?;
+    state.notify();
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn job(id: &str, run_at: u64, last_run: Option<u64>) -> ScheduledJob {
+        ScheduledJob { id: id.to_string(), instance_ids: vec!["a".to_string()], run_at, action: JobAction::Verify, last_run, last_result: None }
+    }
+
+    #[test]
+    fn only_pending_jobs_past_their_time_are_due() {
+        let jobs = vec![job("past", 100, None), job("done", 100, Some(100)), job("future", 500, None)];
+        let due = due_jobs(&jobs, 200);
+        assert_eq!(due.iter().map(|j| j.id.as_str()).collect::<Vec<_>>(), vec!["past"]);
+    }
+
+    #[test]
+    fn wake_is_capped_and_tracks_the_nearest_job() {
+        assert_eq!(next_wake(&[], 0), MAX_IDLE);
+        assert_eq!(next_wake(&[job("soon", 30, None), job("later", 45, None)], 10), Duration::from_secs(20));
+        assert_eq!(next_wake(&[job("far", 100_000, None)], 0), MAX_IDLE);
+    }
+
+    #[test]
+    fn jobs_round_trip_with_camel_case_fields() {
+        let parsed: ScheduledJob = serde_json::from_str(r#"{"id":"x","instanceIds":["a"],"runAt":5,"action":"sync"}"#).unwrap();
+        assert_eq!(parsed.action, JobAction::Sync);
+        assert!(parsed.last_run.is_none());
+    }
+}
@@ -0,0 +1,441 @@
+use schemars::gen::{SchemaGenerator, SchemaSettings};
+use schemars::schema::Schema;
+use schemars::{JsonSchema, Map};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// Bumped when a command or event changes in a way the generated types can't absorb.
+pub const API_SCHEMA_VERSION: u32 = 1;
+
+#[derive(Serialize, Clone, Debug)]
+pub struct ArgSchema {
+    /// As the frontend passes it to `invoke`
+    pub name: String,
+    pub schema: Schema,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct CommandSchema {
+    pub args: Vec<ArgSchema>,
+    /// What the command resolves to; errors are always a message string
+    pub output: Schema,
+}
+
+/// Every command and event payload the frontend sees, as JSON Schema (draft 7). Types shared
+/// between entries live in `definitions` and are referenced from there.
+#[derive(Serialize, Clone, Debug)]
+pub struct ApiSchema {
+    pub version: u32,
+    pub commands: BTreeMap<String, CommandSchema>,
+    pub events: BTreeMap<String, Schema>,
+    pub definitions: Map<String, Schema>,
+}
+
+/// Schemas for the lines of `command_table!`.
+macro_rules! commands {
+    ($gen:expr; $($name:ident($($arg:literal: $ty:ty),*) -> $out:ty;)*) => {{
+        let mut commands = BTreeMap::new();
+        $(commands.insert(stringify!($name).to_string(), CommandSchema {
+            args: vec![$(ArgSchema { name: $arg.to_string(), schema: $gen.subschema_for::<$ty>() }),*],
+            output: $gen.subschema_for::<$out>(),
+        });)*
+        commands
+    }};
+}
+
+fn event<T: JsonSchema>(gen: &mut SchemaGenerator, events: &mut BTreeMap<String, Schema>, name: &str) {
+    events.insert(name.to_string(), gen.subschema_for::<T>());
+}
+
+/// One line per command in `generate_handler!`: the camelCase argument names `invoke` takes
+/// and the `Ok` type. Arguments Tauri injects (app handle, state, window) are left out. The
+/// lines are passed on to `$callback` so the tests can check them against the signatures.
+macro_rules! command_table {
+    ($callback:ident!($($prefix:tt)*)) => {
+        $callback! { $($prefix)*
+            get_instances() -> Vec<crate::Instance>;
+            save_instance("instance": crate::Instance) -> crate::Instance;
+            delete_instance("instanceId": String, "permanent": Option<bool>) -> crate::DeletedInstance;
+            launch_instance("instanceId": String) -> ();
+            launch_world("instanceId": String, "worldName": String) -> ();
+            prepare_instance("instanceId": String) -> ();
+            check_instance_ready("instanceId": String) -> crate::minecraft::readiness::InstanceReadiness;
+            estimate_instance_download("instanceId": String) -> crate::minecraft::readiness::DownloadEstimate;
+            get_platform_info() -> crate::platform::PlatformInfo;
+            get_hardware_profile() -> crate::platform::HardwareProfile;
+            get_default_ram("modloader": Option<String>) -> u64;
+            check_version_support("version": String) -> Option<String>;
+            get_machine_id() -> String;
+            get_fleet_registration() -> crate::fleet::FleetSettings;
+            register_machine("password": String, "fleetUrl": String, "label": Option<String>) -> crate::fleet::FleetSettings;
+            get_jvm_presets() -> Vec<crate::minecraft::jvm_presets::JvmPreset>;
+            optimize_instance("instanceId": String, "profile": String) -> crate::optimize::OptimizeResult;
+            get_launch_history("instanceId": String) -> Vec<crate::launch_history::LaunchAttempt>;
+            set_instance_notes("password": String, "instanceId": String, "notes": Option<String>) -> ();
+            get_instance_admin_notes("password": String, "instanceId": String) -> Option<String>;
+            set_instance_admin_notes("password": String, "instanceId": String, "notes": Option<String>) -> ();
+            get_instance_players("password": String, "instanceId": String) -> Vec<crate::instance_players::InstancePlayer>;
+            analyze_crash("instanceId": String) -> crate::crash_analysis::CrashAnalysis;
+            apply_crash_suggestion("instanceId": String, "suggestion": crate::crash_analysis::Suggestion) -> Vec<String>;
+            export_diagnostics("instanceId": String, "file": String, "password": Option<String>) -> Vec<String>;
+            get_crash_upload() -> serde_json::Value;
+            set_crash_upload("enabled": bool) -> ();
+            set_crash_upload_endpoint("password": String, "endpoint": Option<String>) -> ();
+            import_instance_from_code("code": String) -> crate::Instance;
+            export_instance_code("password": String, "instanceId": String, "upload": Option<bool>) -> crate::share_code::ShareCode;
+            get_share_code_endpoint() -> Option<String>;
+            set_share_code_endpoint("password": String, "endpoint": Option<String>) -> ();
+            get_rewards_endpoint() -> Option<String>;
+            set_rewards_endpoint("password": String, "endpoint": Option<String>) -> ();
+            get_announcement_settings("password": String) -> crate::announcements::AnnouncementSettings;
+            set_announcement_settings("password": String, "announcements": crate::announcements::AnnouncementSettings) -> ();
+            get_maintenance() -> Vec<crate::maintenance::Maintenance>;
+            set_maintenance("password": String, "maintenance": Option<crate::maintenance::Maintenance>) -> ();
+            get_maintenance_url() -> Option<String>;
+            set_maintenance_url("password": String, "url": Option<String>) -> ();
+            get_service_status() -> crate::service_status::StatusReport;
+            get_status_url() -> Option<String>;
+            set_status_url("password": String, "url": Option<String>) -> ();
+            get_accessibility_prefs() -> crate::accessibility::AccessibilityPrefs;
+            check_server_capacity("instanceId": String) -> Option<crate::minecraft::ping::ServerStatus>;
+            wait_for_server_slot("instanceId": String) -> bool;
+            cancel_server_queue("instanceId": String) -> ();
+            get_server_history("host": String) -> Vec<crate::server_history::Sample>;
+            get_server_history_settings() -> crate::server_history::ServerHistorySettings;
+            set_server_history_settings("serverHistory": crate::server_history::ServerHistorySettings) -> ();
+            get_remote_command_log("password": String) -> Vec<serde_json::Value>;
+            set_drop_target("instanceId": Option<String>) -> ();
+            get_mc_versions("limit": usize) -> Vec<String>;
+            get_latest_versions() -> crate::minecraft::versions::LatestVersions;
+            check_instance_update("instanceId": String) -> Option<crate::minecraft::versions::PatchUpdate>;
+            apply_instance_update("instanceId": String, "version": String) -> ();
+            check_instance_compatibility("version": String, "modloader": Option<String>) -> crate::minecraft::versions::CompatibilityReport;
+            get_game_languages("version": String) -> Vec<String>;
+            get_world_migration("instanceId": String, "newVersion": String) -> crate::minecraft::worlds::WorldMigrationPlan;
+            create_instance_shortcut("instanceId": String, "steam": bool) -> Vec<String>;
+            take_pending_launch() -> Option<String>;
+            take_pending_kiosk() -> Option<String>;
+            kiosk_start("instanceId": String) -> ();
+            get_kiosk_settings() -> crate::kiosk::KioskSettings;
+            set_kiosk_settings("password": String, "kiosk": crate::kiosk::KioskSettings) -> ();
+            create_kiosk_shortcut("password": String, "instanceId": String) -> String;
+            get_metrics_settings() -> crate::metrics::MetricsSettings;
+            set_metrics_settings("password": String, "metrics": crate::metrics::MetricsSettings) -> ();
+            take_pending_open() -> Option<crate::file_associations::OpenRequest>;
+            get_file_associations() -> crate::file_associations::FileAssociationSettings;
+            set_file_associations("enabled": bool, "deepLinks": bool) -> ();
+            apply_gamepad_profile("instanceId": String, "steamShortcut": bool) -> crate::gamepad::GamepadProfileResult;
+            check_graphics("instanceId": String) -> crate::graphics::GraphicsCheck;
+            get_data_dir_info() -> crate::data_dir::DataDirInfo;
+            migrate_data_dir("password": String, "toPortable": bool) -> String;
+            backup_instance_worlds("instanceId": String) -> Vec<String>;
+            get_loader_recommendation("loader": String, "mcVersion": String) -> crate::minecraft::versions::LoaderVersions;
+            get_java_info("mcVersion": String) -> serde_json::Value;
+            download_java("major": u32) -> String;
+            get_asset_stats() -> crate::minecraft::assets::AssetStats;
+            prune_assets("password": String) -> crate::minecraft::assets::PruneResult;
+            inspect_modpack("urlOrPath": String) -> crate::minecraft::modpack::ModpackInfo;
+            prefetch_instance("instanceId": String) -> ();
+            download_server("password": String, "version": String, "targetDir": String, "loader": Option<String>) -> crate::minecraft::server::ServerInstall;
+            open_folder("path": String, "password": Option<String>) -> ();
+            open_instance_folder("instanceId": String, "which": String) -> ();
+            reveal_file("path": String) -> ();
+            attach_instance_image("instanceId": String, "source": String) -> String;
+            prefetch_images("urls": Vec<String>) -> ();
+            get_storage_summary("refresh": Option<bool>) -> crate::storage::StorageSummary;
+            cancel_removal("jobId": String) -> bool;
+            cancel_modpack_extraction("instanceId": String) -> bool;
+            clear_caches("password": String) -> crate::undo::Operation;
+            get_trash_settings() -> crate::trash::TrashSettings;
+            get_janitor_settings() -> crate::janitor::JanitorSettings;
+            set_janitor_settings("janitor": crate::janitor::JanitorSettings) -> ();
+            set_trash_settings("trash": crate::trash::TrashSettings) -> ();
+            list_trash() -> Vec<crate::trash::TrashEntry>;
+            restore_instance("trashId": String) -> crate::Instance;
+            purge_trash("trashId": Option<String>) -> Vec<String>;
+            undo_last_operation() -> crate::undo::Operation;
+            get_performance_settings() -> crate::tuning::PerformanceInfo;
+            set_performance_settings("performance": crate::tuning::PerformanceSettings) -> crate::tuning::PerformanceSettings;
+            get_version_meta_settings("password": String) -> crate::minecraft::version_store::VersionMetaSettings;
+            set_version_meta_settings("password": String, "versionMeta": crate::minecraft::version_store::VersionMetaSettings) -> ();
+            get_mirror_settings() -> crate::mirror::MirrorSettings;
+            set_mirror_settings("password": String, "mirror": crate::mirror::MirrorSettings) -> ();
+            validate_mirror("password": String, "instanceIds": Vec<String>) -> crate::mirror::MirrorReport;
+            export_offline_bundle("password": String, "instanceId": String, "file": String) -> crate::offline_bundle::BundleReport;
+            get_save_sync("instanceId": String) -> crate::save_sync::SaveSyncStatus;
+            set_synced_worlds("instanceId": String, "worlds": Vec<String>) -> ();
+            get_save_sync_target("password": String) -> Option<crate::save_sync::SyncTarget>;
+            set_save_sync_target("password": String, "target": Option<crate::save_sync::SyncTarget>) -> ();
+            resolve_save_conflict("instanceId": String, "world": String, "keep": String) -> crate::save_sync::SyncReport;
+            get_attestation_settings("password": String) -> crate::attestation::AttestationSettings;
+            set_attestation_settings("password": String, "attestation": crate::attestation::AttestationSettings) -> ();
+            search_content("instanceId": Option<String>, "query": String, "kind": crate::minecraft::content_source::ContentKind) -> Vec<crate::minecraft::content_source::ContentHit>;
+            list_quarantined_mods("password": String) -> Vec<crate::minecraft::mod_scan::QuarantinedMod>;
+            approve_quarantined_mod("password": String, "id": String) -> crate::minecraft::mod_scan::QuarantinedMod;
+            reject_quarantined_mod("password": String, "id": String) -> ();
+            list_pending_mods("instanceId": String) -> Vec<String>;
+            approve_pending_mod("password": String, "instanceId": String, "fileName": String) -> ();
+            reject_pending_mod("password": String, "instanceId": String, "fileName": String) -> ();
+            get_mod_scan_settings() -> crate::minecraft::mod_scan::ModScanSettings;
+            set_mod_scan_settings("password": String, "modScan": crate::minecraft::mod_scan::ModScanSettings) -> ();
+            get_loader_locks("password": String) -> crate::minecraft::loader_pin::Locks;
+            clear_loader_lock("password": String, "instanceId": String) -> ();
+            get_instance_lock("instanceId": String) -> Option<crate::minecraft::instance_lock::InstanceLock>;
+            clear_instance_lock("password": String, "instanceId": String) -> ();
+            get_instance_config_patches("password": String, "instanceId": String) -> Vec<crate::minecraft::config_patch::ConfigPatch>;
+            set_instance_config_patches("password": String, "instanceId": String, "patches": Vec<crate::minecraft::config_patch::ConfigPatch>) -> ();
+            get_progress_snapshot("instanceId": String) -> Option<crate::progress_snapshot::ProgressSnapshot>;
+            get_active_progress() -> Vec<crate::progress_snapshot::ProgressSnapshot>;
+            start_microsoft_login() -> String;
+            get_auth_profile() -> Option<crate::auth::MinecraftProfile>;
+            start_offline_login("username": String) -> String;
+            get_account_avatar("uuid": String) -> String;
+            get_startup_auth_state() -> crate::auth::StartupAuthState;
+            check_admin_password("password": String) -> bool;
+            set_admin_password("currentPassword": String, "password": String) -> ();
+            get_admin_setup_status() -> crate::admin_setup::AdminSetupStatus;
+            complete_admin_setup("code": String, "password": String) -> ();
+            reset_admin_password("token": String, "password": String) -> ();
+            get_admin_reset_log("password": String) -> Vec<serde_json::Value>;
+            is_admin_configured() -> bool;
+            get_guest_mode() -> crate::settings::GuestMode;
+            set_guest_mode("password": String, "enabled": bool, "allowedInstances": Vec<String>) -> ();
+            get_credentials("password": String) -> crate::settings::Credentials;
+            set_credentials("password": String, "credentials": crate::settings::Credentials) -> ();
+            get_scheduled_jobs() -> Vec<crate::scheduler::ScheduledJob>;
+            schedule_job("password": String, "instanceIds": Vec<String>, "runAt": u64, "action": crate::scheduler::JobAction) -> crate::scheduler::ScheduledJob;
+            cancel_scheduled_job("password": String, "jobId": String) -> ();
+            get_notification_settings() -> crate::settings::NotificationSettings;
+            set_notification_settings("notifications": crate::settings::NotificationSettings) -> ();
+            get_launch_hotkey() -> Option<String>;
+            set_launch_hotkey("hotkey": Option<String>) -> ();
+            get_updater_settings() -> crate::updater::UpdaterSettings;
+            get_download_stats() -> crate::download_stats::DownloadStats;
+            run_mirror_speedtest() -> crate::download_stats::Speedtest;
+            get_antivirus_guidance() -> crate::antivirus::AntivirusGuidance;
+            reverify_blocked_files() -> Vec<String>;
+            audit_instance_content("password": String, "instanceId": String) -> crate::content_audit::ContentAudit;
+            create_sandbox_clone("password": String, "instanceId": String) -> crate::Instance;
+            smoke_test_instance("password": String, "instanceId": String, "timeoutSecs": Option<u64>) -> crate::smoke_test::SmokeTestResult;
+            export_content_index("password": String, "file": String) -> usize;
+            verify_against_index("password": String, "file": String) -> crate::content_index::IndexDrift;
+            set_updater_settings("updater": crate::updater::UpdaterSettings) -> ();
+            preview_modpack_update("instanceId": String) -> crate::minecraft::modpack::ModpackDiff;
+            apply_modpack_update("instanceId": String) -> ();
+            get_host_pins("password": String) -> crate::host_pins::Pins;
+            forget_host_pin("password": String, "host": String) -> ();
+            get_host_pin_mode() -> crate::host_pins::PinMode;
+            set_host_pin_mode("password": String, "mode": crate::host_pins::PinMode) -> ();
+            get_data_saver() -> bool;
+            set_data_saver("enabled": bool) -> ();
+            get_api_schema() -> serde_json::Value;
+            format_last_played("instanceId": String, "locale": Option<String>) -> crate::last_played::LastPlayed;
+            validate_mod_urls("password": String, "urls": Vec<String>) -> crate::mod_links::ModUrlReport;
+            get_safe_mode("instanceId": String) -> crate::safe_mode::SafeModeState;
+            start_safe_mode("instanceId": String, "step": crate::safe_mode::SafeModeStep) -> ();
+            exit_safe_mode("instanceId": String) -> Vec<String>;
+            check_instance_saves("instanceId": String) -> Vec<crate::save_recovery::DamagedWorld>;
+            restore_world("instanceId": String, "folder": String, "source": crate::save_recovery::RestoreSource) -> ();
+        }
+    };
+}
+
+pub fn build() -> ApiSchema {
+    let mut gen = SchemaSettings::draft07().into_generator();
+    let commands = command_table!(commands!(gen;));
+
+    let mut events = BTreeMap::new();
+    event::<crate::events::LaunchProgress>(&mut gen, &mut events, "launch_progress");
+    event::<crate::minecraft::java::JavaDownloadProgress>(&mut gen, &mut events, "java_download_progress");
+    event::<crate::minecraft::prefetch::PrefetchStatus>(&mut gen, &mut events, "prefetch_status");
+    event::<crate::accessibility::AccessibilityPrefs>(&mut gen, &mut events, "accessibility_changed");
+    event::<crate::announcements::Announcement>(&mut gen, &mut events, "announcement");
+    event::<crate::announcements::AnnouncementStatus>(&mut gen, &mut events, "announcement_status");
+    event::<crate::drop_install::DropInstallProgress>(&mut gen, &mut events, "drop_install_progress");
+    event::<crate::hotkey::HotkeyLaunch>(&mut gen, &mut events, "hotkey_launch");
+    event::<crate::info_card::InstanceCardUpdated>(&mut gen, &mut events, "instance_card_updated");
+    event::<Vec<crate::maintenance::Maintenance>>(&mut gen, &mut events, "maintenance");
+    event::<crate::remover::RemovalStatus>(&mut gen, &mut events, "removal_progress");
+    event::<crate::save_recovery::SaveCorruption>(&mut gen, &mut events, "save_corruption");
+    event::<crate::save_sync::SaveSyncResult>(&mut gen, &mut events, "save_sync");
+    event::<crate::scheduler::ScheduledJobStatus>(&mut gen, &mut events, "scheduled_job");
+    event::<crate::server_queue::QueueUpdate>(&mut gen, &mut events, "server_queue");
+    event::<crate::auth::StartupAuthState>(&mut gen, &mut events, "session_restored");
+    event::<crate::service_status::StatusReport>(&mut gen, &mut events, "status_changed");
+    event::<crate::undo::UndoAvailable>(&mut gen, &mut events, "undo_available");
+    event::<crate::updater::UpdateAvailable>(&mut gen, &mut events, "instance_update_available");
+    event::<crate::updater::UpdateApplied>(&mut gen, &mut events, "instance_update_applied");
+
+    ApiSchema { version: API_SCHEMA_VERSION, commands, events, definitions: gen.take_definitions() }
+}
+
+/// For `npm run typegen`, which turns it into `src/api/types.generated.ts`.
+#[tauri::command]
+pub fn get_api_schema() -> serde_json::Value {
+    serde_json::to_value(build()).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::path::Path;
+
+    fn sources(dir: &Path, out: &mut Vec<String>) {
+        for entry in fs::read_dir(dir).unwrap().flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                sources(&path, out);
+            } else if path.extension().is_some_and(|e| e == "rs") {
+                out.push(fs::read_to_string(path).unwrap());
+            }
+        }
+    }
+
+    /// Arguments (`invoke` name and type) and `Ok` type, with the paths left off the types.
+    type Signature = (Vec<(String, String)>, String);
+
+    /// Signature of every `#[tauri::command]`, by command name.
+    fn declared_signatures(files: &[String]) -> BTreeMap<String, Signature> {
+        let mut declared = BTreeMap::new();
+        for file in files {
+            for command in file.split("#[tauri::command]").skip(1) {
+                let signature = &command[command.find("fn ").unwrap() + 3..];
+                let name = signature.split(['(', '<']).next().unwrap().trim();
+                let params = &signature[signature.find('(').unwrap() + 1..];
+                let (mut depth, mut end) = (0, params.len());
+                for (i, c) in params.char_indices() {
+                    match c {
+                        '(' | '<' => depth += 1,
+                        ')' if depth == 0 => { end = i; break; }
+                        ')' | '>' => depth -= 1,
+                        _ => {}
+                    }
+                }
+                let mut args = Vec::new();
+                for param in split_top_level(&params[..end]) {
+                    if let Some((arg, ty)) = param.split_once(':') {
+                        // App handle and managed state are injected by Tauri
+                        if !ty.contains("AppHandle") && !ty.trim_start().starts_with("State<") {
+                            args.push((camel_case(arg.trim().trim_start_matches("mut ")), normalize(ty)));
+                        }
+                    }
+                }
+                let rest = &params[end + 1..];
+                let output = rest[..rest.find('{').unwrap()].trim().strip_prefix("->").map(normalize);
+                let output = match output {
+                    Some(ty) if ty.starts_with("Result<") => {
+                        split_top_level(&ty["Result<".len()..ty.len() - 1]).remove(0)
+                    }
+                    Some(ty) => ty,
+                    None => "()".to_string(),
+                };
+                declared.insert(name.to_string(), (args, output));
+            }
+        }
+        declared
+    }
+
+    /// Splits on the commas that aren't inside `<>` or `()`.
+    fn split_top_level(list: &str) -> Vec<String> {
+        let mut parts = Vec::new();
+        let mut part = String::new();
+        let mut depth = 0;
+        for c in list.chars().chain([',']) {
+            match c {
+                '<' | '(' => depth += 1,
+                '>' | ')' => depth -= 1,
+                ',' if depth == 0 => {
+                    if !part.trim().is_empty() {
+                        parts.push(part.trim().to_string());
+                    }
+                    part.clear();
+                    continue;
+                }
+                _ => {}
+            }
+            part.push(c);
+        }
+        parts
+    }
+
+    /// `Vec < crate :: Instance >` and `Vec<Instance>` both become `Vec<Instance>`.
+    fn normalize(ty: &str) -> String {
+        let ty: String = ty.chars().filter(|c| !c.is_whitespace()).collect();
+        let mut out = String::new();
+        let mut rest = ty.as_str();
+        while let Some(c) = rest.chars().next() {
+            if rest.starts_with("::") {
+                while out.ends_with(|c: char| c.is_alphanumeric() || c == '_') {
+                    out.pop();
+                }
+                rest = &rest[2..];
+            } else {
+                out.push(c);
+                rest = &rest[c.len_utf8()..];
+            }
+        }
+        out
+    }
+
+    /// What `command_table!` says about each command, in the same form as the signatures.
+    macro_rules! described {
+        ($($name:ident($($arg:literal: $ty:ty),*) -> $out:ty;)*) => {{
+            let mut described: BTreeMap<String, Signature> = BTreeMap::new();
+            $(described.insert(stringify!($name).to_string(), (
+                vec![$(($arg.to_string(), normalize(stringify!($ty)))),*],
+                normalize(stringify!($out)),
+            ));)*
+            described
+        }};
+    }
+
+    fn camel_case(name: &str) -> String {
+        let mut parts = name.split('_');
+        let mut out = parts.next().unwrap_or_default().to_string();
+        for part in parts {
+            let mut chars = part.chars();
+            out.extend(chars.next().map(|c| c.to_ascii_uppercase()));
+            out.push_str(chars.as_str());
+        }
+        out
+    }
+
+    #[test]
+    fn every_command_and_event_is_described() {
+        let schema = build();
+        let src_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("src");
+        let lib = fs::read_to_string(src_dir.join("lib.rs")).unwrap();
+        let handler = lib.split("generate_handler![").nth(1).unwrap().split("])").next().unwrap();
+        let registered: Vec<&str> = handler.split(',')
+            .map(|path| path.trim().rsplit("::").next().unwrap())
+            .filter(|name| !name.is_empty())
+            .collect();
+        for name in &registered {
+            assert!(schema.commands.contains_key(*name), "falta el comando {} en el esquema", name);
+        }
+        assert_eq!(schema.commands.len(), registered.len(), "el esquema describe comandos no registrados");
+
+        let mut files = Vec::new();
+        sources(&src_dir, &mut files);
+        let declared = declared_signatures(&files);
+        for (name, signature) in command_table!(described!()) {
+            let found = declared.get(&name).unwrap_or_else(|| panic!("no se encontró la función del comando {}", name));
+            assert_eq!(signature, *found, "la firma de {} no coincide con el esquema", name);
+        }
+
+        for file in &files {
+            for emitted in file.split(".emit(\"").skip(1) {
+                let name = emitted.split('"').next().unwrap();
+                assert!(schema.events.contains_key(name), "falta el evento {} en el esquema", name);
+            }
+        }
+    }
+
+    /// `DRK_API_SCHEMA_OUT=<file> cargo test export_api_schema` writes the schema for typegen.
+    #[test]
+    fn export_api_schema() {
+        let Ok(out) = std::env::var("DRK_API_SCHEMA_OUT") else { return; };
+        fs::write(out, serde_json::to_string_pretty(&build()).unwrap()).unwrap();
+    }
+}
@@ -0,0 +1,173 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+use crate::minecraft::ping::{parse_address, ping};
+use crate::settings::{ensure_not_guest, load_settings, save_settings};
+
+const HISTORY_FILE: &str = "server-history.json";
+const PING_TIMEOUT: Duration = Duration::from_secs(5);
+// Lets the window and any launch at startup go first
+const STARTUP_DELAY: Duration = Duration::from_secs(30);
+const MIN_INTERVAL_MINUTES: u64 = 1;
+/// Samples older than this are dropped
+const WINDOW_SECS: u64 = 24 * 60 * 60;
+const MAX_SAMPLES: usize = 24 * 60;
+
+/// Background sampling of the instances' event servers for the stats card sparkline.
+#[derive(Serialize, Deserialize, Clone, JsonSchema)]
+pub struct ServerHistorySettings {
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    #[serde(rename = "intervalMinutes", alias = "interval_minutes", default = "default_interval")]
+    pub interval_minutes: u64,
+}
+
+impl Default for ServerHistorySettings {
+    fn default() -> Self {
+        ServerHistorySettings { enabled: true, interval_minutes: default_interval() }
+    }
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_interval() -> u64 {
+    5
+}
+
+impl ServerHistorySettings {
+    pub fn interval(&self) -> Duration {
+        Duration::from_secs(self.interval_minutes.max(MIN_INTERVAL_MINUTES) * 60)
+    }
+}
+
+/// One ping; `online` and `max` are `None` when the server didn't answer.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Sample {
+    pub at: u64,
+    pub online: Option<u32>,
+    pub max: Option<u32>,
+}
+
+type History = BTreeMap<String, Vec<Sample>>;
+
+/// `host:port`, so `play.example.com` and `play.example.com:25565` share one series.
+pub fn host_key(address: &str) -> Result<String, String> {
+    let (host, port) = parse_address(address)?;
+    let host = host.to_lowercase();
+    Ok(if host.contains(':') { format!("[{}]:{}", host, port) } else { format!("{}:{}", host, port) })
+}
+
+fn history_path() -> PathBuf {
+    PathBuf::from(crate::get_instances_dir()).join(HISTORY_FILE)
+}
+
+fn load_history() -> History {
+    fs::read_to_string(history_path()).ok()
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+
+fn save_history(history: &History) {
+    if let Ok(json) = serde_json::to_string(history) {
+        let _ = fs::write(history_path(), json);
+    }
+}
+
+/// Appends `sample` and drops what fell out of the window, including servers no longer sampled.
+pub fn record(history: &mut History, key: &str, sample: Sample) {
+    let oldest = sample.at.saturating_sub(WINDOW_SECS);
+    history.entry(key.to_string()).or_default().push(sample);
+    for samples in history.values_mut() {
+        samples.retain(|s| s.at >= oldest);
+        let excess = samples.len().saturating_sub(MAX_SAMPLES);
+        samples.drain(..excess);
+    }
+    history.retain(|_, samples| !samples.is_empty());
+}
+
+/// Server addresses of all instances, deduplicated.
+fn configured_servers() -> BTreeSet<String> {
+    crate::load_instances().into_iter()
+        .filter_map(|i| i.server_ip)
+        .filter_map(|ip| host_key(&ip).ok())
+        .collect()
+}
+
+fn sample_all() {
+    let servers = configured_servers();
+    if servers.is_empty() {
+        return;
+    }
+    let samples: Vec<(String, Sample)> = servers.into_iter().map(|key| {
+        let status = ping(&key, PING_TIMEOUT).ok();
+        let sample = Sample { at: crate::unix_now(), online: status.as_ref().map(|s| s.online), max: status.as_ref().map(|s| s.max) };
+        (key, sample)
+    }).collect();
+    let mut history = load_history();
+    for (key, sample) in samples {
+        record(&mut history, &key, sample);
+    }
+    save_history(&history);
+}
+
+/// Samples every configured event server while the launcher is open.
+pub fn start() {
+    std::thread::spawn(|| {
+        std::thread::sleep(STARTUP_DELAY);
+        loop {
+            let settings = load_settings().server_history;
+            if settings.enabled {
+                sample_all();
+            }
+            std::thread::sleep(settings.interval());
+        }
+    });
+}
+
+/// Samples of the last 24 hours for `host` (as in an instance's `serverIp`), oldest first.
+#[tauri::command]
+pub fn get_server_history(host: String) -> Result<Vec<Sample>, String> {
+    let key = host_key(&host)?;
+    Ok(load_history().remove(&key).unwrap_or_default())
+}
+
+#[tauri::command]
+pub fn get_server_history_settings() -> ServerHistorySettings {
+    load_settings().server_history
+}
+
+#[tauri::command]
+pub fn set_server_history_settings(server_history: ServerHistorySettings) -> Result<(), String> {
+    ensure_not_guest()?;
+    let mut settings = load_settings();
+    settings.server_history = server_history;
+    save_settings(&settings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(at: u64, online: u32) -> Sample {
+        Sample { at, online: Some(online), max: Some(100) }
+    }
+
+    #[test]
+    fn window_keeps_the_last_day_per_server() {
+        let mut history = History::new();
+        record(&mut history, "old.example.com:25565", sample(1_000, 3));
+        record(&mut history, "play.example.com:25565", sample(1_000, 10));
+        record(&mut history, "play.example.com:25565", sample(1_000 + WINDOW_SECS / 2, 20));
+        record(&mut history, "play.example.com:25565", sample(2_000 + WINDOW_SECS, 30));
+
+        assert_eq!(history.len(), 1);
+        assert_eq!(history["play.example.com:25565"], vec![sample(1_000 + WINDOW_SECS / 2, 20), sample(2_000 + WINDOW_SECS, 30)]);
+        assert_eq!(host_key("Play.Example.com").unwrap(), "play.example.com:25565");
+        assert_eq!(host_key("[::1]:25570").unwrap(), "[::1]:25570");
+    }
+}
@@ -0,0 +1,113 @@
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager, State};
+use crate::minecraft::ping::{ping, ServerStatus};
+
+const PING_TIMEOUT: Duration = Duration::from_secs(5);
+const POLL_INTERVAL: Duration = Duration::from_secs(10);
+
+/// `server_queue` payload: either where the player stands or why the last ping failed.
+#[derive(Serialize, Clone, Default, JsonSchema)]
+pub struct QueueUpdate {
+    #[serde(rename = "instanceId")]
+    pub instance_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub online: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub position: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Instances whose queue the player left; the waiting loop checks it between pings.
+#[derive(Default)]
+pub struct ServerQueue(Mutex<HashSet<String>>);
+
+impl ServerQueue {
+    fn take_cancelled(&self, instance_id: &str) -> bool {
+        self.0.lock().map(|mut ids| ids.remove(instance_id)).unwrap_or(false)
+    }
+}
+
+/// Players that have to leave before there's room: 1 when the server is exactly full.
+pub fn queue_position(status: &ServerStatus) -> u32 {
+    if status.is_full() { status.online - status.max + 1 } else { 0 }
+}
+
+fn server_address(instance_id: &str) -> Result<Option<String>, String> {
+    let instance = crate::load_instances().into_iter().find(|i| i.id == instance_id).ok_or("Instance not found")?;
+    Ok(instance.server_ip.filter(|ip| !ip.trim().is_empty()))
+}
+
+/// Player count of the instance's event server; `None` when it has no server or doesn't answer,
+/// in which case launching goes ahead as before.
+#[tauri::command]
+pub async fn check_server_capacity(instance_id: String) -> Result<Option<ServerStatus>, String> {
+    crate::run_blocking(move || {
+        let Some(address) = server_address(&instance_id)? else { return Ok(None); };
+        Ok(ping(&address, PING_TIMEOUT).map_err(|e| eprintln!("[server_queue] Ping {} failed: {}", address, e)).ok())
+    }).await
+}
+
+/// Waits until the event server has a free slot, emitting `server_queue` with the position on
+/// every ping. Resolves to false if the player cancels with `cancel_server_queue`.
+#[tauri::command]
+pub async fn wait_for_server_slot(app: AppHandle, instance_id: String) -> Result<bool, String> {
+    crate::run_blocking(move || {
+        let Some(address) = server_address(&instance_id)? else { return Ok(true); };
+        let queue = app.state::<ServerQueue>();
+        queue.take_cancelled(&instance_id);
+        loop {
+            if queue.take_cancelled(&instance_id) {
+                return Ok(false);
+            }
+            let status = match ping(&address, PING_TIMEOUT) {
+                Ok(status) => status,
+                // A server that stopped answering may be restarting; keep the place and retry
+                Err(e) => {
+                    let _ = app.emit("server_queue", QueueUpdate { instance_id: instance_id.clone(), error: Some(e), ..QueueUpdate::default() });
+                    std::thread::sleep(POLL_INTERVAL);
+                    continue;
+                }
+            };
+            let position = queue_position(&status);
+            let _ = app.emit("server_queue", QueueUpdate {
+                instance_id: instance_id.clone(),
+                online: Some(status.online),
+                max: Some(status.max),
+                position: Some(position),
+                error: None,
+            });
+            if position == 0 {
+                return Ok(true);
+            }
+            std::thread::sleep(POLL_INTERVAL);
+        }
+    }).await
+}
+
+#[tauri::command]
+pub fn cancel_server_queue(instance_id: String, queue: State<'_, ServerQueue>) {
+    if let Ok(mut ids) = queue.0.lock() {
+        ids.insert(instance_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn positions_count_the_players_over_the_cap() {
+        let status = |online, max| ServerStatus { online, max, version: None, motd: None };
+        assert_eq!(queue_position(&status(99, 100)), 0);
+        assert_eq!(queue_position(&status(100, 100)), 1);
+        assert_eq!(queue_position(&status(103, 100)), 4);
+        assert_eq!(queue_position(&status(5, 0)), 0);
+    }
+}
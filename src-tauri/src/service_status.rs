@@ -0,0 +1,243 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+use crate::settings::{load_settings, save_settings};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(300);
+/// Starts launch errors that the UI answers with the update prompt instead of the plain error.
+pub const OUTDATED_MARKER: &str = "[launcher-outdated]";
+
+/// A line of the status page: a planned maintenance or a known issue.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct StatusNotice {
+    #[serde(default)]
+    pub title: Option<String>,
+    #[serde(default)]
+    pub message: String,
+    /// Page with the details, if any
+    #[serde(default)]
+    pub url: Option<String>,
+}
+
+/// Public status JSON published by staff, shown as banners across the launcher.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct ServiceStatus {
+    #[serde(default)]
+    pub maintenance: Vec<StatusNotice>,
+    #[serde(rename = "knownIssues", alias = "known_issues", default)]
+    pub known_issues: Vec<StatusNotice>,
+    /// Older launchers can't play until they update, e.g. `1.4.0`
+    #[serde(rename = "minLauncherVersion", alias = "min_launcher_version", default)]
+    pub min_launcher_version: Option<String>,
+    /// Where the update is downloaded from; shown next to the version warning
+    #[serde(rename = "downloadUrl", alias = "download_url", default)]
+    pub download_url: Option<String>,
+}
+
+/// What the UI gets from `get_service_status` and the `status_changed` event.
+#[derive(Serialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct StatusReport {
+    pub status: ServiceStatus,
+    #[serde(rename = "launcherVersion")]
+    pub launcher_version: String,
+    /// The installed launcher is below `minLauncherVersion`; launches are blocked
+    pub outdated: bool,
+    /// Unix seconds of the last successful poll; `None` until one succeeds
+    #[serde(rename = "fetchedAt")]
+    pub fetched_at: Option<u64>,
+}
+
+/// Last answer of the status URL and when it came.
+static LAST: Mutex<Option<(ServiceStatus, u64)>> = Mutex::new(None);
+
+/// `1.4.0`, `v1.4` or `1.4.0-beta.2` as numbers; suffixes are ignored.
+fn parse_version(version: &str) -> Option<Vec<u64>> {
+    let core = version.trim().trim_start_matches(['v', 'V']).split(['-', '+']).next()?;
+    let parts: Option<Vec<u64>> = core.split('.').map(|p| p.parse().ok()).collect();
+    parts.filter(|p| !p.is_empty())
+}
+
+/// True when `current` is an older release than `minimum`. A version that doesn't parse
+/// never blocks, so a typo in the status page can't lock every player out.
+pub fn is_below(current: &str, minimum: &str) -> bool {
+    match (parse_version(current), parse_version(minimum)) {
+        (Some(mut current), Some(mut minimum)) => {
+            let len = current.len().max(minimum.len());
+            current.resize(len, 0);
+            minimum.resize(len, 0);
+            current < minimum
+        }
+        _ => false,
+    }
+}
+
+pub fn parse_status(body: &str) -> Result<ServiceStatus, String> {
+    serde_json::from_str(body).map_err(|e| format!("Estado del servicio no válido: {}", e))
+}
+
+fn report_for(status: ServiceStatus, fetched_at: Option<u64>) -> StatusReport {
+    let launcher_version = env!("CARGO_PKG_VERSION").to_string();
+    let outdated = status.min_launcher_version.as_deref().map(|min| is_below(&launcher_version, min)).unwrap_or(false);
+    StatusReport { status, launcher_version, outdated, fetched_at }
+}
+
+pub fn current() -> StatusReport {
+    match LAST.lock().ok().and_then(|last| last.clone()) {
+        Some((status, fetched_at)) => report_for(status, Some(fetched_at)),
+        None => report_for(ServiceStatus::default(), None),
+    }
+}
+
+fn outdated_error(current: &str, minimum: &str) -> String {
+    format!("{} Actualiza el launcher: la versión {} ya no es compatible (mínima {})", OUTDATED_MARKER, current, minimum.trim())
+}
+
+/// Launch error while the installed launcher is older than the advertised minimum.
+pub fn launch_blocked() -> Option<String> {
+    let report = current();
+    if !report.outdated {
+        return None;
+    }
+    Some(outdated_error(&report.launcher_version, &report.status.min_launcher_version.unwrap_or_default()))
+}
+
+/// Minimum launcher version staff require per instance in the remote catalog:
+/// `{"minLauncherVersions": {"<instanceId>": "1.5.0"}}`.
+#[derive(Deserialize)]
+struct MinVersionCatalog {
+    #[serde(rename = "minLauncherVersions", alias = "min_launcher_versions", default)]
+    min_launcher_versions: HashMap<String, String>,
+}
+
+pub fn parse_instance_minimums(body: &str) -> HashMap<String, String> {
+    serde_json::from_str::<MinVersionCatalog>(body).map(|c| c.min_launcher_versions).unwrap_or_default()
+}
+
+/// Launch error when `current` is older than what the catalog requires for `instance_id`.
+pub fn instance_blocked(minimums: &HashMap<String, String>, instance_id: &str, current: &str) -> Option<String> {
+    let minimum = minimums.get(instance_id)?;
+    is_below(current, minimum).then(|| outdated_error(current, minimum))
+}
+
+/// Refuses launching `instance_id` on a launcher older than the catalog's minimum for it.
+/// Fetches the catalog (or its cached copy), so it must run off the async runtime.
+pub fn ensure_instance_supported(instance_id: &str) -> Result<(), String> {
+    let Some(body) = crate::minecraft::mod_scan::catalog_body() else { return Ok(()) };
+    match instance_blocked(&parse_instance_minimums(&body), instance_id, env!("CARGO_PKG_VERSION")) {
+        Some(error) => Err(error),
+        None => Ok(()),
+    }
+}
+
+fn fetch(url: &str) -> Result<ServiceStatus, String> {
+    let client = reqwest::blocking::Client::builder()
+        .user_agent(crate::http::user_agent())
+        .timeout(Duration::from_secs(10))
+        .build()
+        .map_err(|e| e.to_string())?;
+    let resp = crate::http::send(&client, client.get(url))?;
+    if !resp.status().is_success() {
+        return Err(format!("La página de estado respondió {}", resp.status()));
+    }
+    parse_status(&resp.text().map_err(|e| e.to_string())?)
+}
+
+/// Polls the status URL once and tells the UI when the answer changed.
+fn refresh(app: &AppHandle) {
+    let Some(url) = load_settings().status_url.filter(|u| !u.trim().is_empty()) else {
+        let cleared = LAST.lock().map(|mut last| last.take().is_some()).unwrap_or(false);
+        if cleared {
+            let _ = app.emit("status_changed", current());
+        }
+        return;
+    };
+    match fetch(&url) {
+        Ok(status) => {
+            let changed = LAST.lock()
+                .map(|mut last| {
+                    let changed = last.as_ref().map(|(old, _)| old != &status).unwrap_or(true);
+                    *last = Some((status, crate::unix_now()));
+                    changed
+                })
+                .unwrap_or(false);
+            if changed {
+                let _ = app.emit("status_changed", current());
+            }
+        }
+        // Keep the last known status; an unreachable page shouldn't lift a version block
+        Err(e) => eprintln!("[service_status] {}", e),
+    }
+}
+
+/// Polls the status URL on startup and then every few minutes.
+pub fn start(app: AppHandle) {
+    std::thread::spawn(move || loop {
+        refresh(&app);
+        std::thread::sleep(POLL_INTERVAL);
+    });
+}
+
+#[tauri::command]
+pub fn get_service_status() -> StatusReport {
+    current()
+}
+
+#[tauri::command]
+pub fn get_status_url() -> Option<String> {
+    load_settings().status_url
+}
+
+#[tauri::command]
+pub fn set_status_url(app: AppHandle, password: String, url: Option<String>) -> Result<(), String> {
+    crate::ensure_admin(password)?;
+    let mut settings = load_settings();
+    settings.status_url = url.map(|u| u.trim().to_string()).filter(|u| !u.is_empty());
+    save_settings(&settings)?;
+    std::thread::spawn(move || refresh(&app));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn older_launchers_are_below_the_minimum() {
+        assert!(is_below("1.3.9", "1.4.0"));
+        assert!(is_below("1.4", "1.4.1"));
+        assert!(!is_below("1.4.0", "1.4"));
+        assert!(!is_below("1.10.0", "1.9.5"));
+        assert!(!is_below("1.4.0-beta.1", "v1.4.0"));
+        // Garbage in the status page never blocks
+        assert!(!is_below("1.0.0", "pronto"));
+        assert!(!is_below("1.0.0", ""));
+    }
+
+    #[test]
+    fn status_fields_are_optional() {
+        assert_eq!(parse_status("{}").unwrap(), ServiceStatus::default());
+        let status = parse_status(r#"{
+            "maintenance": [{"title": "Reinicio", "message": "El sábado a las 18:00"}],
+            "knownIssues": [{"message": "Las skins tardan en cargar", "url": "https://estado.example/1"}],
+            "minLauncherVersion": "99.0.0"
+        }"#).unwrap();
+        assert_eq!(status.maintenance[0].title.as_deref(), Some("Reinicio"));
+        assert_eq!(status.known_issues[0].url.as_deref(), Some("https://estado.example/1"));
+        assert!(report_for(status, Some(1)).outdated);
+        assert!(parse_status("<html>").is_err());
+    }
+
+    #[test]
+    fn the_catalog_sets_minimums_per_instance() {
+        let minimums = parse_instance_minimums(r#"{"approved": [], "minLauncherVersions": {"evento": "1.5.0"}}"#);
+        let error = instance_blocked(&minimums, "evento", "1.4.2").unwrap();
+        assert!(error.starts_with(OUTDATED_MARKER));
+        assert!(error.contains("1.4.2") && error.contains("1.5.0"));
+        assert_eq!(instance_blocked(&minimums, "evento", "1.5.0"), None);
+        assert_eq!(instance_blocked(&minimums, "otro", "0.1.0"), None);
+        assert!(parse_instance_minimums(r#"["abc"]"#).is_empty());
+    }
+}
@@ -0,0 +1,243 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+const SETTINGS_FILE: &str = "settings.json";
+
+/// Launcher-wide settings persisted next to `instances.json`.
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct LauncherSettings {
+    #[serde(rename = "guestMode", alias = "guest_mode", default)]
+    pub guest_mode: bool,
+    #[serde(rename = "guestAllowedInstances", alias = "guest_allowed_instances", default)]
+    pub guest_allowed_instances: Vec<String>,
+    #[serde(default)]
+    pub credentials: Credentials,
+    #[serde(rename = "scheduledJobs", alias = "scheduled_jobs", default)]
+    pub scheduled_jobs: Vec<crate::scheduler::ScheduledJob>,
+    #[serde(default)]
+    pub notifications: NotificationSettings,
+    /// Global accelerator that launches the last played instance, e.g. `CommandOrControl+Shift+J`
+    #[serde(rename = "launchHotkey", alias = "launch_hotkey", default)]
+    pub launch_hotkey: Option<String>,
+    #[serde(default)]
+    pub updater: crate::updater::UpdaterSettings,
+    /// Metered connection: background updates only report, large downloads ask first and run
+    /// with fewer parallel connections
+    #[serde(rename = "dataSaver", alias = "data_saver", default)]
+    pub data_saver: bool,
+    /// Where short share codes are looked up; `{code}` is replaced, otherwise the code is appended
+    #[serde(rename = "shareCodeEndpoint", alias = "share_code_endpoint", default)]
+    pub share_code_endpoint: Option<String>,
+    /// Receives a summary of each finished game session (see `crate::rewards`)
+    #[serde(rename = "rewardsEndpoint", alias = "rewards_endpoint", default)]
+    pub rewards_endpoint: Option<String>,
+    #[serde(default)]
+    pub announcements: crate::announcements::AnnouncementSettings,
+    /// Local maintenance window (see `crate::maintenance`); launches are blocked while it lasts
+    #[serde(rename = "maintenanceMode", alias = "maintenance_mode", default)]
+    pub maintenance_mode: Option<crate::maintenance::Maintenance>,
+    /// Polled for maintenance windows published by staff
+    #[serde(rename = "maintenanceUrl", alias = "maintenance_url", default)]
+    pub maintenance_url: Option<String>,
+    /// Public status JSON (see `crate::service_status`): notices, known issues, minimum version
+    #[serde(rename = "statusUrl", alias = "status_url", default)]
+    pub status_url: Option<String>,
+    #[serde(default)]
+    pub trash: crate::trash::TrashSettings,
+    #[serde(default)]
+    pub performance: crate::tuning::PerformanceSettings,
+    #[serde(rename = "modScan", alias = "mod_scan", default)]
+    pub mod_scan: crate::minecraft::mod_scan::ModScanSettings,
+    #[serde(default)]
+    pub attestation: crate::attestation::AttestationSettings,
+    #[serde(rename = "versionMeta", alias = "version_meta", default)]
+    pub version_meta: crate::minecraft::version_store::VersionMetaSettings,
+    #[serde(default)]
+    pub mirror: crate::mirror::MirrorSettings,
+    #[serde(rename = "saveSync", alias = "save_sync", default)]
+    pub save_sync: crate::save_sync::SaveSyncSettings,
+    #[serde(rename = "serverHistory", alias = "server_history", default)]
+    pub server_history: crate::server_history::ServerHistorySettings,
+    #[serde(rename = "fileAssociations", alias = "file_associations", default)]
+    pub file_associations: crate::file_associations::FileAssociationSettings,
+    #[serde(rename = "crashUpload", alias = "crash_upload", default)]
+    pub crash_upload: crate::crash_upload::CrashUploadSettings,
+    #[serde(default)]
+    pub janitor: crate::janitor::JanitorSettings,
+    /// Periodic machine reports to the organizers' fleet view (see `crate::fleet`)
+    #[serde(default)]
+    pub fleet: crate::fleet::FleetSettings,
+    /// Event-night stations started with `--kiosk` (see `crate::kiosk`)
+    #[serde(default)]
+    pub kiosk: crate::kiosk::KioskSettings,
+    /// Localhost metrics endpoint for monitoring stations (see `crate::metrics`)
+    #[serde(default)]
+    pub metrics: crate::metrics::MetricsSettings,
+    /// Certificate checks on modpack hosts (see `host_pins`)
+    #[serde(rename = "hostPinning", alias = "host_pinning", default)]
+    pub host_pinning: crate::host_pins::PinMode,
+    /// System RAM seen on the last run (see `platform::check_hardware_change`)
+    #[serde(rename = "lastTotalRamMb", alias = "last_total_ram_mb", default)]
+    pub last_total_ram_mb: Option<u64>,
+}
+
+/// Native OS notifications for work that finishes in the background.
+#[derive(Serialize, Deserialize, Clone, JsonSchema)]
+pub struct NotificationSettings {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// Skip the notification while the launcher window is visible
+    #[serde(rename = "onlyWhenMinimized", alias = "only_when_minimized", default = "default_true")]
+    pub only_when_minimized: bool,
+}
+
+impl Default for NotificationSettings {
+    fn default() -> Self {
+        NotificationSettings { enabled: true, only_when_minimized: true }
+    }
+}
+
+/// Serde default for flags that start on.
+pub fn default_true() -> bool {
+    true
+}
+
+/// API keys and the HTTP user agent, injected by `crate::http`. Admin only.
+#[derive(Serialize, Deserialize, Clone, Default, JsonSchema)]
+pub struct Credentials {
+    #[serde(rename = "curseforgeApiKey", alias = "curseforge_api_key", default)]
+    pub curseforge_api_key: Option<String>,
+    #[serde(rename = "githubToken", alias = "github_token", default)]
+    pub github_token: Option<String>,
+    #[serde(rename = "userAgent", alias = "user_agent", default)]
+    pub user_agent: Option<String>,
+}
+
+fn get_settings_path() -> PathBuf {
+    PathBuf::from(crate::get_instances_dir()).join(SETTINGS_FILE)
+}
+
+/// Parsed settings with the path, modification time and length they were read at. Every HTTP
+/// request reads the user agent, credentials and mirror, so the file is only parsed again when
+/// it changes on disk.
+static CACHE: Mutex<Option<(PathBuf, SystemTime, u64, LauncherSettings)>> = Mutex::new(None);
+
+pub fn load_settings() -> LauncherSettings {
+    let path = get_settings_path();
+    let stamp = fs::metadata(&path).and_then(|m| Ok((m.modified()?, m.len()))).ok();
+    let mut cache = CACHE.lock().unwrap_or_else(|e| e.into_inner());
+    if let (Some((cached_path, modified, len, settings)), Some(stamp)) = (cache.as_ref(), stamp) {
+        if *cached_path == path && (*modified, *len) == stamp {
+            return settings.clone();
+        }
+    }
+    let settings: LauncherSettings = fs::read_to_string(&path)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default();
+    *cache = stamp.map(|(modified, len)| (path, modified, len, settings.clone()));
+    settings
+}
+
+pub fn save_settings(settings: &LauncherSettings) -> Result<(), String> {
+    let path = get_settings_path();
+    if let Some(parent) = path.parent() {
+        let _ = fs::create_dir_all(parent);
+    }
+    let json = serde_json::to_string_pretty(settings).map_err(|e| e.to_string())?;
+    fs::write(&path, json).map_err(|e| e.to_string())?;
+    // A save within the file system's timestamp granularity would look unchanged
+    *CACHE.lock().unwrap_or_else(|e| e.into_inner()) = None;
+    Ok(())
+}
+
+/// Guest mode only lets whitelisted instances launch; maintenance windows and an outdated
+/// launcher block them all.
+pub fn ensure_launch_allowed(instance_id: &str) -> Result<(), String> {
+    if let Some(error) = crate::maintenance::launch_blocked(instance_id) {
+        return Err(error);
+    }
+    if let Some(error) = crate::service_status::launch_blocked() {
+        return Err(error);
+    }
+    let settings = load_settings();
+    if settings.guest_mode && !settings.guest_allowed_instances.iter().any(|id| id == instance_id) {
+        return Err("Modo invitado: esta instancia no está habilitada".to_string());
+    }
+    Ok(())
+}
+
+/// Guest mode blocks any change to instances, mods or settings.
+pub fn ensure_not_guest() -> Result<(), String> {
+    if load_settings().guest_mode {
+        return Err("Modo invitado: no se permiten modificaciones".to_string());
+    }
+    Ok(())
+}
+
+/// Whether the launcher is locked to guest play and which instances guests may launch.
+#[derive(Serialize, Clone, JsonSchema)]
+pub struct GuestMode {
+    pub enabled: bool,
+    #[serde(rename = "allowedInstances")]
+    pub allowed_instances: Vec<String>,
+}
+
+#[tauri::command]
+pub fn get_guest_mode() -> GuestMode {
+    let settings = load_settings();
+    GuestMode { enabled: settings.guest_mode, allowed_instances: settings.guest_allowed_instances }
+}
+
+#[tauri::command]
+pub fn set_guest_mode(password: String, enabled: bool, allowed_instances: Vec<String>) -> Result<(), String> {
+    crate::ensure_admin(password)?;
+    let mut settings = load_settings();
+    settings.guest_mode = enabled;
+    settings.guest_allowed_instances = allowed_instances;
+    save_settings(&settings)
+}
+
+#[tauri::command]
+pub fn get_data_saver() -> bool {
+    load_settings().data_saver
+}
+
+#[tauri::command]
+pub fn set_data_saver(enabled: bool) -> Result<(), String> {
+    ensure_not_guest()?;
+    let mut settings = load_settings();
+    settings.data_saver = enabled;
+    save_settings(&settings)
+}
+
+#[tauri::command]
+pub fn get_credentials(password: String) -> Result<Credentials, String> {
+    crate::ensure_admin(password)?;
+    Ok(load_settings().credentials)
+}
+
+#[tauri::command]
+pub fn set_credentials(password: String, credentials: Credentials) -> Result<(), String> {
+    crate::ensure_admin(password)?;
+    let mut settings = load_settings();
+    settings.credentials = credentials;
+    save_settings(&settings)
+}
+
+#[tauri::command]
+pub fn get_notification_settings() -> NotificationSettings {
+    load_settings().notifications
+}
+
+#[tauri::command]
+pub fn set_notification_settings(notifications: NotificationSettings) -> Result<(), String> {
+    ensure_not_guest()?;
+    let mut settings = load_settings();
+    settings.notifications = notifications;
+    save_settings(&settings)
+}
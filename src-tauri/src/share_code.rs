@@ -0,0 +1,268 @@
+use base64::Engine;
+use flate2::read::DeflateDecoder;
+use flate2::write::DeflateEncoder;
+use flate2::Compression;
+use schemars::JsonSchema;
+use serde::Serialize;
+use sha1::{Digest, Sha1};
+use std::io::{Read, Write};
+use crate::settings::{load_settings, save_settings};
+
+// Machine-specific fields never travel in a share code; the importing launcher sets its own
+const LOCAL_FIELDS: [&str; 8] = [
+    "id", "path", "lastPlayed", "last_played", "sandboxRoot", "sandbox_root", "javaRuntime", "java_runtime",
+];
+// Set by staff through their own admin commands, never by whoever wrote a pasted code
+const STAFF_FIELDS: [&str; 4] = ["adminNotes", "admin_notes", "configPatches", "config_patches"];
+/// Compact codes: `drk1.<deflated JSON, base64url>.<checksum>`
+const CODE_PREFIX: &str = "drk1.";
+// Hex digits of the payload's SHA-1 kept as checksum; enough to reject a code cut off while pasting
+const CHECKSUM_LEN: usize = 8;
+
+/// What a pasted code refers to.
+#[derive(Debug, PartialEq)]
+pub enum ShareSource {
+    /// Instance JSON to download
+    Url(String),
+    /// Instance JSON carried in the code itself
+    Payload(String),
+}
+
+/// Decodes a base64 payload (standard or URL-safe, padded or not) into JSON text.
+fn decode_payload(code: &str) -> Option<String> {
+    let engines = [
+        base64::engine::general_purpose::URL_SAFE_NO_PAD,
+        base64::engine::general_purpose::URL_SAFE,
+        base64::engine::general_purpose::STANDARD_NO_PAD,
+        base64::engine::general_purpose::STANDARD,
+    ];
+    engines.iter()
+        .find_map(|engine| engine.decode(code).ok())
+        .and_then(|bytes| String::from_utf8(bytes).ok())
+        .filter(|text| text.trim_start().starts_with('{'))
+}
+
+fn checksum(payload: &str) -> String {
+    let mut hasher = Sha1::new();
+    hasher.update(payload.as_bytes());
+    hex::encode(hasher.finalize())[..CHECKSUM_LEN].to_string()
+}
+
+pub fn encode_compact(json: &str) -> Result<String, String> {
+    let mut encoder = DeflateEncoder::new(Vec::new(), Compression::best());
+    encoder.write_all(json.as_bytes()).map_err(|e| e.to_string())?;
+    let payload = base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(encoder.finish().map_err(|e| e.to_string())?);
+    Ok(format!("{}{}.{}", CODE_PREFIX, payload, checksum(&payload)))
+}
+
+pub fn decode_compact(code: &str) -> Result<String, String> {
+    let rest = code.strip_prefix(CODE_PREFIX).ok_or("Código no válido")?;
+    let (payload, sum) = rest.rsplit_once('.').ok_or("Código incompleto")?;
+    if !checksum(payload).eq_ignore_ascii_case(sum) {
+        return Err("El código está incompleto o dañado; cópialo de nuevo".to_string());
+    }
+    let bytes = base64::engine::general_purpose::URL_SAFE_NO_PAD.decode(payload).map_err(|_| "Código no válido")?;
+    let mut json = String::new();
+    DeflateDecoder::new(bytes.as_slice()).read_to_string(&mut json).map_err(|_| "Código no válido")?;
+    Ok(json)
+}
+
+/// `code` may be a full URL, a base64 instance payload, or a short code looked up at `endpoint`
+/// (`{code}` in the endpoint is replaced, otherwise the code is appended as a path segment).
+pub fn resolve_source(code: &str, endpoint: Option<&str>) -> Result<ShareSource, String> {
+    let code = code.trim().trim_start_matches("drk:");
+    if code.is_empty() {
+        return Err("El código está vacío".to_string());
+    }
+    if code.starts_with(CODE_PREFIX) {
+        return decode_compact(code).map(ShareSource::Payload);
+    }
+    if code.starts_with("http://") || code.starts_with("https://") {
+        return Ok(ShareSource::Url(code.to_string()));
+    }
+    if let Some(json) = decode_payload(code) {
+        return Ok(ShareSource::Payload(json));
+    }
+    let endpoint = endpoint.filter(|e| !e.trim().is_empty())
+        .ok_or("Código no válido y no hay un servidor de códigos configurado")?;
+    let code = url::form_urlencoded::byte_serialize(code.as_bytes()).collect::<String>();
+    Ok(ShareSource::Url(if endpoint.contains("{code}") {
+        endpoint.replace("{code}", &code)
+    } else {
+        format!("{}/{}", endpoint.trim_end_matches('/'), code)
+    }))
+}
+
+fn fetch_text(url: &str) -> Result<String, String> {
+    let client = reqwest::blocking::Client::builder()
+        .user_agent(crate::http::user_agent())
+        .timeout(std::time::Duration::from_secs(20))
+        .build()
+        .map_err(|e| e.to_string())?;
+    let resp = crate::http::send(&client, client.get(url))?;
+    if !resp.status().is_success() {
+        return Err(format!("El servidor de códigos respondió {}", resp.status()));
+    }
+    resp.text().map_err(|e| e.to_string())
+}
+
+/// Turns shared instance JSON into a new local instance: fresh id, no path yet (save_instance
+/// assigns it) and defaults for the fields a minimal payload may leave out.
+pub fn instance_from_json(json: &str, new_id: &str) -> Result<crate::Instance, String> {
+    let mut value: serde_json::Value = serde_json::from_str(json).map_err(|e| format!("El código no contiene una instancia válida: {}", e))?;
+    let object = value.as_object_mut().ok_or("El código no contiene una instancia válida")?;
+    for field in LOCAL_FIELDS.iter().chain(&STAFF_FIELDS) {
+        object.remove(*field);
+    }
+    object.insert("id".into(), new_id.into());
+    object.insert("path".into(), "".into());
+    object.entry("icon").or_insert_with(|| "default".into());
+    let instance: crate::Instance = serde_json::from_value(value).map_err(|e| format!("El código no contiene una instancia válida: {}", e))?;
+    if instance.name.trim().is_empty() || instance.version.trim().is_empty() {
+        return Err("La instancia del código no tiene nombre o versión".to_string());
+    }
+    Ok(instance)
+}
+
+/// Creates the instance a share code points to and returns it as saved.
+#[tauri::command]
+pub async fn import_instance_from_code(code: String) -> Result<crate::Instance, String> {
+    crate::run_blocking(move || {
+        let json = match resolve_source(&code, load_settings().share_code_endpoint.as_deref())? {
+            ShareSource::Payload(json) => json,
+            ShareSource::Url(url) => fetch_text(&url)?,
+        };
+        // save_instance gives a new instance its own id; this one only has to be unused
+        let id = crate::instance_input::new_id();
+        crate::save_instance(instance_from_json(&json, &id)?)
+    }).await
+}
+
+#[derive(Serialize, Clone, Debug, JsonSchema)]
+pub struct ShareCode {
+    /// Compact code that carries the whole definition
+    pub code: String,
+    /// Short link from the code endpoint, when the definition was uploaded
+    pub link: Option<String>,
+}
+
+/// Where definitions are uploaded: the endpoint up to the `{code}` placeholder or query.
+pub fn upload_url(endpoint: &str) -> String {
+    let base = endpoint.split("{code}").next().unwrap_or(endpoint);
+    base.split('?').next().unwrap_or(base).trim_end_matches('/').to_string()
+}
+
+/// POSTs the definition to the code endpoint, which answers `{"code": "..."}` or `{"url": "..."}`.
+fn upload_definition(endpoint: &str, definition: &serde_json::Value) -> Result<String, String> {
+    let client = reqwest::blocking::Client::builder()
+        .user_agent(crate::http::user_agent())
+        .timeout(std::time::Duration::from_secs(20))
+        .build()
+        .map_err(|e| e.to_string())?;
+    let resp = crate::http::send(&client, client.post(upload_url(endpoint)).json(definition))?;
+    if !resp.status().is_success() {
+        return Err(format!("El servidor de códigos respondió {}", resp.status()));
+    }
+    let body: serde_json::Value = resp.json().map_err(|e| format!("Respuesta no válida del servidor de códigos: {}", e))?;
+    if let Some(url) = body["url"].as_str() {
+        return Ok(url.to_string());
+    }
+    let code = body["code"].as_str().ok_or("El servidor de códigos no devolvió un código")?;
+    match resolve_source(code, Some(endpoint))? {
+        ShareSource::Url(url) => Ok(url),
+        ShareSource::Payload(_) => Ok(code.to_string()),
+    }
+}
+
+/// Share code for an instance, for staff to post where players can paste it. With `upload` and a
+/// configured endpoint the definition is also published there and a short link returned.
+#[tauri::command]
+pub async fn export_instance_code(password: String, instance_id: String, upload: Option<bool>) -> Result<ShareCode, String> {
+    crate::ensure_admin(password)?;
+    crate::run_blocking(move || {
+        let instance = crate::load_instances().into_iter().find(|i| i.id == instance_id)
+            .ok_or("Instance not found")?;
+        // Codes get posted publicly; staff remarks and config patches stay on this machine
+        let mut definition = serde_json::to_value(crate::instance_notes::for_display(instance)).map_err(|e| e.to_string())?;
+        if let Some(object) = definition.as_object_mut() {
+            for field in LOCAL_FIELDS {
+                object.remove(field);
+            }
+        }
+        let code = encode_compact(&definition.to_string())?;
+        let link = match load_settings().share_code_endpoint.filter(|_| upload.unwrap_or(false)) {
+            Some(endpoint) => Some(upload_definition(&endpoint, &definition)?),
+            None => None,
+        };
+        Ok(ShareCode { code, link })
+    }).await
+}
+
+#[tauri::command]
+pub fn get_share_code_endpoint() -> Option<String> {
+    load_settings().share_code_endpoint
+}
+
+#[tauri::command]
+pub fn set_share_code_endpoint(password: String, endpoint: Option<String>) -> Result<(), String> {
+    crate::ensure_admin(password)?;
+    let mut settings = load_settings();
+    settings.share_code_endpoint = endpoint.map(|e| e.trim().to_string()).filter(|e| !e.is_empty());
+    save_settings(&settings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn codes_resolve_to_urls_or_payloads() {
+        let payload = base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(r#"{"name":"Evento","version":"1.20.1"}"#);
+        assert_eq!(resolve_source(&payload, None), Ok(ShareSource::Payload(r#"{"name":"Evento","version":"1.20.1"}"#.to_string())));
+        assert_eq!(
+            resolve_source(" https://drk.example/pack.json ", None),
+            Ok(ShareSource::Url("https://drk.example/pack.json".to_string()))
+        );
+        assert_eq!(
+            resolve_source("EVENTO-24", Some("https://drk.example/codes/")),
+            Ok(ShareSource::Url("https://drk.example/codes/EVENTO-24".to_string()))
+        );
+        assert_eq!(
+            resolve_source("drk:EVENTO 24", Some("https://drk.example/c?id={code}")),
+            Ok(ShareSource::Url("https://drk.example/c?id=EVENTO+24".to_string()))
+        );
+        assert!(resolve_source("EVENTO-24", None).is_err());
+    }
+
+    #[test]
+    fn compact_codes_round_trip_and_reject_truncation() {
+        let json = r#"{"name":"Evento","version":"1.20.1","modpackUrl":"https://drk.example/pack.zip"}"#;
+        let code = encode_compact(json).unwrap();
+        assert!(code.starts_with(CODE_PREFIX));
+        assert_eq!(resolve_source(&code, None), Ok(ShareSource::Payload(json.to_string())));
+        let (payload, sum) = code.rsplit_once('.').unwrap();
+        assert!(decode_compact(&format!("{}.{}", &payload[..payload.len() - 3], sum)).is_err());
+    }
+
+    #[test]
+    fn uploads_go_to_the_endpoint_base() {
+        assert_eq!(upload_url("https://drk.example/codes/"), "https://drk.example/codes");
+        assert_eq!(upload_url("https://drk.example/c?id={code}"), "https://drk.example/c");
+        assert_eq!(upload_url("https://drk.example/c/{code}.json"), "https://drk.example/c");
+    }
+
+    #[test]
+    fn imported_instances_get_a_fresh_identity() {
+        let json = r#"{"id":"staff-id","path":"C:\\staff\\Evento","name":"Evento","version":"1.20.1","modpackUrl":"https://drk.example/pack.zip"}"#;
+        let instance = instance_from_json(json, "new-id").unwrap();
+        assert_eq!(instance.id, "new-id");
+        assert_eq!(instance.path, "");
+        assert_eq!(instance.icon, "default");
+        assert_eq!(instance.modpack_url.as_deref(), Some("https://drk.example/pack.zip"));
+        assert!(instance_from_json(r#"{"name":"","version":"1.20.1"}"#, "x").is_err());
+
+        let json = r#"{"name":"Evento","version":"1.20.1","adminNotes":"x","configPatches":[],"sandboxRoot":"C:\\x","javaRuntime":"../../x"}"#;
+        let instance = instance_from_json(json, "new-id").unwrap();
+        assert_eq!((instance.admin_notes, instance.config_patches, instance.sandbox_root, instance.java_runtime), (None, None, None, None));
+    }
+}
@@ -0,0 +1,348 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::State;
+
+/// Command line flag used by shortcuts to open the launcher straight into an instance.
+pub const LAUNCH_FLAG: &str = "--launch-instance";
+
+/// Instance requested on the command line, consumed by the frontend once it has loaded.
+#[derive(Default)]
+pub struct PendingLaunch(pub Mutex<Option<String>>);
+
+/// Value of `flag` on the command line, as `flag value` or `flag=value`.
+pub fn flag_value(args: &[String], flag: &str) -> Option<String> {
+    args.iter()
+        .position(|a| a == flag)
+        .and_then(|i| args.get(i + 1).cloned())
+        .or_else(|| args.iter().find_map(|a| a.strip_prefix(flag)?.strip_prefix('=').map(str::to_string)))
+        .filter(|id| !id.is_empty())
+}
+
+pub fn launch_arg(args: &[String]) -> Option<String> {
+    flag_value(args, LAUNCH_FLAG)
+}
+
+#[tauri::command]
+pub fn take_pending_launch(state: State<'_, PendingLaunch>) -> Option<String> {
+    state.0.lock().ok().and_then(|mut pending| pending.take())
+}
+
+/// The executable shortcuts should point at. AppImages run from a temporary mount, so the
+/// image itself is used when available.
+pub fn launcher_exe() -> Result<PathBuf, String> {
+    if let Some(appimage) = std::env::var_os("APPIMAGE") {
+        return Ok(PathBuf::from(appimage));
+    }
+    std::env::current_exe().map_err(|e| format!("No se pudo localizar el ejecutable: {}", e))
+}
+
+// ---- Steam non-Steam-game shortcuts (binary VDF) ----
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum VdfValue {
+    Map(Vec<(String, VdfValue)>),
+    Str(String),
+    Int(u32),
+}
+
+const VDF_MAP: u8 = 0x00;
+const VDF_STR: u8 = 0x01;
+const VDF_INT: u8 = 0x02;
+const VDF_END: u8 = 0x08;
+
+fn read_cstr(data: &[u8], pos: &mut usize) -> Result<String, String> {
+    let len = data[*pos..].iter().position(|b| *b == 0).ok_or("Unterminated VDF string")?;
+    let s = String::from_utf8_lossy(&data[*pos..*pos + len]).into_owned();
+    *pos += len + 1;
+    Ok(s)
+}
+
+fn read_map(data: &[u8], pos: &mut usize) -> Result<Vec<(String, VdfValue)>, String> {
+    let mut entries = Vec::new();
+    loop {
+        let kind = *data.get(*pos).ok_or("Unexpected end of VDF data")?;
+        *pos += 1;
+        if kind == VDF_END {
+            return Ok(entries);
+        }
+        let key = read_cstr(data, pos)?;
+        let value = match kind {
+            VDF_MAP => VdfValue::Map(read_map(data, pos)?),
+            VDF_STR => VdfValue::Str(read_cstr(data, pos)?),
+            VDF_INT => {
+                let bytes = data.get(*pos..*pos + 4).ok_or("Unexpected end of VDF data")?;
+                *pos += 4;
+                VdfValue::Int(u32::from_le_bytes(bytes.try_into().expect("4 bytes")))
+            }
+            other => return Err(format!("Unknown VDF type {:#x}", other)),
+        };
+        entries.push((key, value));
+    }
+}
+
+pub fn parse_vdf(data: &[u8]) -> Result<Vec<(String, VdfValue)>, String> {
+    let mut pos = 0;
+    read_map(data, &mut pos)
+}
+
+fn write_map(out: &mut Vec<u8>, entries: &[(String, VdfValue)]) {
+    for (key, value) in entries {
+        let kind = match value {
+            VdfValue::Map(_) => VDF_MAP,
+            VdfValue::Str(_) => VDF_STR,
+            VdfValue::Int(_) => VDF_INT,
+        };
+        out.push(kind);
+        out.extend(key.as_bytes());
+        out.push(0);
+        match value {
+            VdfValue::Map(children) => write_map(out, children),
+            VdfValue::Str(s) => {
+                out.extend(s.as_bytes());
+                out.push(0);
+            }
+            VdfValue::Int(v) => out.extend(v.to_le_bytes()),
+        }
+    }
+    out.push(VDF_END);
+}
+
+pub fn write_vdf(entries: &[(String, VdfValue)]) -> Vec<u8> {
+    let mut out = Vec::new();
+    write_map(&mut out, entries);
+    out
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+    for byte in data {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ 0xEDB8_8320 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+
+/// Same id Steam derives for non-Steam games, so custom artwork and controller configs stick.
+pub fn steam_app_id(exe: &str, name: &str) -> u32 {
+    crc32(format!("{}{}", exe, name).as_bytes()) | 0x8000_0000
+}
+
+fn str_entry(key: &str, value: &str) -> (String, VdfValue) {
+    (key.to_string(), VdfValue::Str(value.to_string()))
+}
+
+fn int_entry(key: &str, value: u32) -> (String, VdfValue) {
+    (key.to_string(), VdfValue::Int(value))
+}
+
+fn shortcut_entry(name: &str, exe: &Path, instance_id: &str) -> Vec<(String, VdfValue)> {
+    let exe_quoted = format!("\"{}\"", exe.display());
+    let start_dir = exe.parent().map(|p| format!("\"{}\"", p.display())).unwrap_or_default();
+    vec![
+        int_entry("appid", steam_app_id(&exe_quoted, name)),
+        str_entry("AppName", name),
+        str_entry("Exe", &exe_quoted),
+        str_entry("StartDir", &start_dir),
+        str_entry("icon", ""),
+        str_entry("ShortcutPath", ""),
+        str_entry("LaunchOptions", &format!("{} {}", LAUNCH_FLAG, instance_id)),
+        int_entry("IsHidden", 0),
+        int_entry("AllowDesktopConfig", 1),
+        int_entry("AllowOverlay", 1),
+        int_entry("OpenVR", 0),
+        int_entry("Devkit", 0),
+        str_entry("DevkitGameID", ""),
+        int_entry("DevkitOverrideAppID", 0),
+        int_entry("LastPlayTime", 0),
+        str_entry("FlatpakAppID", ""),
+        ("tags".to_string(), VdfValue::Map(vec![str_entry("0", "DrkLauncher")])),
+    ]
+}
+
+/// Adds (or replaces) the shortcut for `instance_id` in a `shortcuts.vdf` document.
+pub fn upsert_steam_shortcut(existing: Option<&[u8]>, name: &str, exe: &Path, instance_id: &str) -> Result<Vec<u8>, String> {
+    let mut root = match existing {
+        Some(data) if !data.is_empty() => parse_vdf(data)?,
+        _ => Vec::new(),
+    };
+    if !root.iter().any(|(k, _)| k.eq_ignore_ascii_case("shortcuts")) {
+        root.push(("shortcuts".to_string(), VdfValue::Map(Vec::new())));
+    }
+    let launch_options = format!("{} {}", LAUNCH_FLAG, instance_id);
+    let Some((_, VdfValue::Map(shortcuts))) = root.iter_mut().find(|(k, _)| k.eq_ignore_ascii_case("shortcuts")) else {
+        return Err("shortcuts.vdf has an unexpected layout".to_string());
+    };
+    shortcuts.retain(|(_, entry)| match entry {
+        VdfValue::Map(fields) => !fields.iter().any(|(k, v)| k.eq_ignore_ascii_case("LaunchOptions") && *v == VdfValue::Str(launch_options.clone())),
+        _ => true,
+    });
+    // Entries are keyed by their position
+    let mut renumbered = shortcuts.drain(..).enumerate().map(|(i, (_, v))| (i.to_string(), v)).collect::<Vec<_>>();
+    renumbered.push((renumbered.len().to_string(), VdfValue::Map(shortcut_entry(name, exe, instance_id))));
+    *shortcuts = renumbered;
+    Ok(write_vdf(&root))
+}
+
+fn steam_userdata_dirs() -> Vec<PathBuf> {
+    let mut roots = Vec::new();
+    #[cfg(target_os = "windows")]
+    {
+        if let Some(pf) = std::env::var_os("ProgramFiles(x86)") {
+            roots.push(PathBuf::from(pf).join("Steam"));
+        }
+    }
+    #[cfg(not(target_os = "windows"))]
+    {
+        if let Some(home) = std::env::var_os("HOME").map(PathBuf::from) {
+            roots.push(home.join(".steam").join("steam"));
+            roots.push(home.join(".local").join("share").join("Steam"));
+            roots.push(home.join(".var").join("app").join("com.valvesoftware.Steam").join(".local").join("share").join("Steam"));
+            roots.push(home.join("Library").join("Application Support").join("Steam"));
+        }
+    }
+    let mut dirs = Vec::new();
+    for root in roots {
+        for entry in fs::read_dir(root.join("userdata")).into_iter().flatten().flatten() {
+            let config = entry.path().join("config");
+            // ~/.steam/steam is usually a symlink to one of the other roots
+            let canonical = fs::canonicalize(&config).unwrap_or(config);
+            if entry.path().is_dir() && !dirs.contains(&canonical) {
+                dirs.push(canonical);
+            }
+        }
+    }
+    dirs
+}
+
+fn create_steam_shortcuts(name: &str, exe: &Path, instance_id: &str) -> Result<Vec<PathBuf>, String> {
+    let dirs = steam_userdata_dirs();
+    if dirs.is_empty() {
+        return Err("No se encontró una instalación de Steam".to_string());
+    }
+    let mut written = Vec::new();
+    for config in dirs {
+        let path = config.join("shortcuts.vdf");
+        let existing = fs::read(&path).ok();
+        let data = upsert_steam_shortcut(existing.as_deref(), name, exe, instance_id)?;
+        fs::create_dir_all(&config).map_err(|e| e.to_string())?;
+        fs::write(&path, data).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+        written.push(path);
+    }
+    Ok(written)
+}
+
+// ---- Desktop shortcuts ----
+
+pub fn desktop_entry(name: &str, exe: &Path, flag: &str, instance_id: &str) -> String {
+    format!(
+        "[Desktop Entry]\nType=Application\nName={}\nExec=\"{}\" {} {}\nTerminal=false\nCategories=Game;\n",
+        name,
+        exe.display(),
+        flag,
+        instance_id
+    )
+}
+
+fn safe_file_name(name: &str) -> String {
+    name.chars().map(|c| if c.is_alphanumeric() || c == ' ' || c == '-' || c == '_' { c } else { '_' }).collect()
+}
+
+/// Desktop shortcut running the launcher with `flag instance_id` (`LAUNCH_FLAG`, or another
+/// startup flag such as the kiosk one).
+#[cfg(target_os = "windows")]
+pub fn create_desktop_shortcut(name: &str, exe: &Path, flag: &str, instance_id: &str) -> Result<PathBuf, String> {
+    let desktop = std::env::var_os("USERPROFILE").map(|p| PathBuf::from(p).join("Desktop")).ok_or("USERPROFILE not set")?;
+    let link = desktop.join(format!("{}.lnk", safe_file_name(name)));
+    let quote = |s: &str| s.replace('\'', "''");
+    let script = format!(
+        "$s=(New-Object -ComObject WScript.Shell).CreateShortcut('{}');$s.TargetPath='{}';$s.Arguments='{} {}';$s.WorkingDirectory='{}';$s.Save()",
+        quote(&link.to_string_lossy()),
+        quote(&exe.to_string_lossy()),
+        flag,
+        quote(instance_id),
+        quote(&exe.parent().map(|p| p.to_string_lossy().to_string()).unwrap_or_default()),
+    );
+    let status = std::process::Command::new("powershell")
+        .args(["-NoProfile", "-NonInteractive", "-Command", &script])
+        .status()
+        .map_err(|e| e.to_string())?;
+    if !status.success() {
+        return Err("No se pudo crear el acceso directo".to_string());
+    }
+    Ok(link)
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn create_desktop_shortcut(name: &str, exe: &Path, flag: &str, instance_id: &str) -> Result<PathBuf, String> {
+    let home = std::env::var_os("HOME").map(PathBuf::from).ok_or("HOME not set")?;
+    let dir = home.join(".local").join("share").join("applications");
+    fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    // Play shortcuts keep their original file name, so recreating one still replaces it
+    let stem = match flag {
+        LAUNCH_FLAG => safe_file_name(instance_id),
+        _ => format!("{}-{}", flag.trim_start_matches('-'), safe_file_name(instance_id)),
+    };
+    let path = dir.join(format!("drklauncher-{}.desktop", stem));
+    fs::write(&path, desktop_entry(name, exe, flag, instance_id)).map_err(|e| e.to_string())?;
+    Ok(path)
+}
+
+/// Creates a desktop shortcut, or a Steam non-Steam-game entry (usable from Game Mode on the
+/// Steam Deck), that opens the launcher and plays the instance. Steam must be restarted to see it.
+#[tauri::command]
+pub fn create_instance_shortcut(instance_id: String, steam: bool) -> Result<Vec<String>, String> {
+    let instance = crate::load_instances().into_iter().find(|i| i.id == instance_id)
+        .ok_or("Instance not found")?;
+    let exe = launcher_exe()?;
+    let paths = if steam {
+        create_steam_shortcuts(&instance.name, &exe, &instance.id)?
+    } else {
+        vec![create_desktop_shortcut(&instance.name, &exe, LAUNCH_FLAG, &instance.id)?]
+    };
+    Ok(paths.iter().map(|p| p.to_string_lossy().to_string()).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn launch_arg_accepts_both_forms() {
+        let args = |v: &[&str]| v.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        assert_eq!(launch_arg(&args(&["drk", "--launch-instance", "abc"])).as_deref(), Some("abc"));
+        assert_eq!(launch_arg(&args(&["drk", "--launch-instance=abc"])).as_deref(), Some("abc"));
+        assert_eq!(launch_arg(&args(&["drk"])), None);
+    }
+
+    #[test]
+    fn steam_shortcuts_round_trip_and_replace_same_instance() {
+        let exe = Path::new("/home/deck/DrkLauncher.AppImage");
+        let first = upsert_steam_shortcut(None, "Evento", exe, "id-1").unwrap();
+        let second = upsert_steam_shortcut(Some(&first), "Otra", exe, "id-2").unwrap();
+        let third = upsert_steam_shortcut(Some(&second), "Evento v2", exe, "id-1").unwrap();
+
+        let root = parse_vdf(&third).unwrap();
+        let VdfValue::Map(shortcuts) = &root[0].1 else { panic!("shortcuts map") };
+        let names = shortcuts.iter()
+            .map(|(key, entry)| {
+                let VdfValue::Map(fields) = entry else { panic!("entry map") };
+                let name = fields.iter().find(|(k, _)| k == "AppName").map(|(_, v)| v.clone());
+                (key.clone(), name)
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec![
+            ("0".to_string(), Some(VdfValue::Str("Otra".to_string()))),
+            ("1".to_string(), Some(VdfValue::Str("Evento v2".to_string()))),
+        ]);
+        assert_eq!(write_vdf(&root), third);
+    }
+
+    #[test]
+    fn app_id_matches_steam_crc_scheme() {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+        assert!(steam_app_id("\"/a\"", "b") & 0x8000_0000 != 0);
+    }
+}
@@ -0,0 +1,312 @@
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, State};
+use crate::auth::{AppState, MinecraftProfile};
+use crate::minecraft::launch_logic::{prepare_and_launch, LaunchOptions};
+
+const DEFAULT_TIMEOUT_SECS: u64 = 300;
+const MIN_TIMEOUT_SECS: u64 = 30;
+const MAX_TIMEOUT_SECS: u64 = 1800;
+const LOG_FILE: &str = "smoke-test.log";
+// The crash report keeps printing for a moment after its first line
+const CRASH_GRACE: Duration = Duration::from_secs(10);
+/// Account used when the build machine has nobody logged in.
+const SMOKE_USERNAME: &str = "DrkSmokeTest";
+
+/// The client logs these once it reached the title screen; `Done (` is the server's.
+const READY_MARKERS: [&str; 3] = ["Sound engine started", "SoundSystem started", "Done ("];
+const FAILURE_MARKERS: [&str; 7] = [
+    "---- Minecraft Crash Report ----",
+    "incompatible mod set",
+    "Missing or unsupported mandatory dependencies",
+    "Failed to create mod instance",
+    "Mixin apply for mod",
+    "Exception in thread \"main\"",
+    "java.lang.OutOfMemoryError",
+];
+
+#[derive(Serialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SmokeTestResult {
+    pub passed: bool,
+    pub reason: String,
+    /// Mod the loader or the crash report blamed, when it named one
+    #[serde(rename = "problemMod")]
+    pub problem_mod: Option<String>,
+    pub seconds: u64,
+    /// Everything the game printed during the test
+    #[serde(rename = "logPath")]
+    pub log_path: String,
+}
+
+/// Mod named by a loader error or crash report line:
+/// - Fabric: `- Mod 'Sodium' (sodium) 0.5.3 requires ...`
+/// - Forge: `Failed to create mod instance. ModID: examplemod`, `-- MOD examplemod --`,
+///   `Mod File: /.../mods/examplemod-1.0.jar`
+/// - Mixin: `Mixin apply for mod examplemod failed ...`
+pub fn problem_mod(line: &str) -> Option<String> {
+    let line = line.trim();
+    let word = |rest: &str| rest.split_whitespace().next().map(|w| w.trim_end_matches([',', ':', '.']).to_string()).filter(|w| !w.is_empty());
+    if let Some((_, rest)) = line.split_once("Mixin apply for mod ") {
+        return word(rest);
+    }
+    if let Some((_, rest)) = line.split_once("ModID: ") {
+        return word(rest);
+    }
+    if let Some(rest) = line.strip_prefix("-- MOD ") {
+        return word(rest.trim_end_matches("--"));
+    }
+    if let Some(path) = line.strip_prefix("Mod File: ") {
+        return Path::new(path.trim()).file_name().map(|n| n.to_string_lossy().to_string());
+    }
+    if let Some((_, rest)) = line.split_once("- Mod '") {
+        let (name, after) = rest.split_once('\'')?;
+        let id = after.trim_start().strip_prefix('(').and_then(|a| a.split_once(')')).map(|(id, _)| id);
+        return Some(id.unwrap_or(name).to_string());
+    }
+    None
+}
+
+/// What the game's output has shown so far.
+#[derive(Default, Debug)]
+pub struct Watch {
+    pub ready: bool,
+    /// First line that marks the launch as failed
+    pub failure: Option<String>,
+    pub problem_mod: Option<String>,
+}
+
+impl Watch {
+    pub fn feed(&mut self, line: &str) {
+        if READY_MARKERS.iter().any(|m| line.contains(m)) {
+            self.ready = true;
+        }
+        if self.failure.is_none() && FAILURE_MARKERS.iter().any(|m| line.contains(m)) {
+            self.failure = Some(line.trim().chars().take(300).collect());
+        }
+        if self.problem_mod.is_none() {
+            self.problem_mod = problem_mod(line);
+        }
+    }
+}
+
+enum Outcome {
+    Ready,
+    Failed,
+    Exited,
+    TimedOut,
+}
+
+/// Forge's early loading window needs a real display; on a Linux box without one the client
+/// runs under `xvfb-run` with software GL.
+fn offscreen(mut cmd: Command) -> Command {
+    let mut tool_options = std::env::var("JAVA_TOOL_OPTIONS").unwrap_or_default();
+    tool_options.push_str(" -Dfml.earlyprogresswindow=false");
+    cmd.env("JAVA_TOOL_OPTIONS", tool_options.trim());
+    let has_display = std::env::var_os("DISPLAY").is_some() || std::env::var_os("WAYLAND_DISPLAY").is_some();
+    if !cfg!(target_os = "linux") || has_display {
+        return cmd;
+    }
+    let mut wrapped = Command::new("xvfb-run");
+    wrapped.arg("-a").arg(cmd.get_program()).args(cmd.get_args());
+    for (key, value) in cmd.get_envs() {
+        match value {
+            Some(value) => wrapped.env(key, value),
+            None => wrapped.env_remove(key),
+        };
+    }
+    if let Some(dir) = cmd.get_current_dir() {
+        wrapped.current_dir(dir);
+    }
+    wrapped.env("LIBGL_ALWAYS_SOFTWARE", "1");
+    wrapped
+}
+
+/// Kills the game and whatever it runs under (`xvfb-run` leaves Java behind otherwise). Returns
+/// the exit code when it had already exited by itself.
+fn kill_tree(child: &mut Child) -> Option<i32> {
+    let exited = child.try_wait().ok().flatten();
+    if cfg!(windows) {
+        let _ = Command::new("taskkill").args(["/T", "/F", "/PID", &child.id().to_string()]).output();
+    } else {
+        // The child leads its own process group (see `run`)
+        let _ = Command::new("kill").args(["-KILL", "--", &format!("-{}", child.id())]).output();
+    }
+    let _ = child.kill();
+    let status = child.wait().ok();
+    exited.or(status).and_then(|status| status.code())
+}
+
+fn forward(pipe: impl Read + Send + 'static, lines: Sender<String>) {
+    std::thread::spawn(move || {
+        for line in BufReader::new(pipe).lines().map_while(Result::ok) {
+            if lines.send(line).is_err() {
+                break;
+            }
+        }
+    });
+}
+
+/// Runs the prepared game until it reaches the title screen, fails or runs out of time.
+fn run(mut cmd: Command, log_path: &Path, timeout: Duration) -> Result<SmokeTestResult, String> {
+    let mut log = File::create(log_path).map_err(|e| format!("No se pudo crear {}: {}", log_path.display(), e))?;
+    cmd.stdout(Stdio::piped()).stderr(Stdio::piped());
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        cmd.process_group(0);
+    }
+    let started = Instant::now();
+    let mut child = cmd.spawn().map_err(|e| format!("No se pudo iniciar el juego: {}", e))?;
+    let (sender, lines) = mpsc::channel();
+    if let Some(stdout) = child.stdout.take() {
+        forward(stdout, sender.clone());
+    }
+    if let Some(stderr) = child.stderr.take() {
+        forward(stderr, sender.clone());
+    }
+    drop(sender);
+
+    let deadline = started + timeout;
+    let mut watch = Watch::default();
+    let mut failed_at: Option<Instant> = None;
+    let outcome = loop {
+        if watch.ready {
+            break if watch.failure.is_some() { Outcome::Failed } else { Outcome::Ready };
+        }
+        if failed_at.is_some_and(|at| at.elapsed() >= CRASH_GRACE) {
+            break Outcome::Failed;
+        }
+        let Some(left) = deadline.checked_duration_since(Instant::now()) else { break Outcome::TimedOut };
+        match lines.recv_timeout(left.min(Duration::from_secs(1))) {
+            Ok(line) => {
+                let _ = writeln!(log, "{}", line);
+                watch.feed(&line);
+                if watch.failure.is_some() && failed_at.is_none() {
+                    failed_at = Some(Instant::now());
+                }
+            }
+            Err(RecvTimeoutError::Timeout) => {}
+            // Both pipes closed: the game is gone
+            Err(RecvTimeoutError::Disconnected) => break if watch.failure.is_some() { Outcome::Failed } else { Outcome::Exited },
+        }
+    };
+    let exit_code = kill_tree(&mut child);
+
+    let reason = match outcome {
+        Outcome::Ready => "El juego terminó de cargar".to_string(),
+        Outcome::Failed => watch.failure.clone().unwrap_or_default(),
+        Outcome::Exited => match exit_code {
+            Some(code) => format!("El juego se cerró antes de terminar de cargar (código {})", code),
+            None => "El juego se cerró antes de terminar de cargar".to_string(),
+        },
+        Outcome::TimedOut => format!("El juego no terminó de cargar en {} s", timeout.as_secs()),
+    };
+    let passed = matches!(outcome, Outcome::Ready);
+    Ok(SmokeTestResult {
+        passed,
+        reason,
+        problem_mod: if passed { None } else { watch.problem_mod },
+        seconds: started.elapsed().as_secs(),
+        log_path: log_path.to_string_lossy().to_string(),
+    })
+}
+
+fn smoke_profile() -> MinecraftProfile {
+    MinecraftProfile {
+        id: crate::auth::offline_uuid(SMOKE_USERNAME),
+        name: SMOKE_USERNAME.to_string(),
+        access_token: "offline".to_string(),
+        has_entitlement: false,
+    }
+}
+
+/// Launches an instance as players would, waits until it reaches the title screen and closes
+/// it, so staff can validate a pack on a build machine without watching it.
+#[tauri::command]
+pub async fn smoke_test_instance(
+    app: AppHandle,
+    password: String,
+    instance_id: String,
+    timeout_secs: Option<u64>,
+    state: State<'_, AppState>,
+) -> Result<SmokeTestResult, String> {
+    crate::ensure_admin(password)?;
+    let instance = crate::load_instances().into_iter().find(|i| i.id == instance_id)
+        .ok_or("Instance not found")?;
+    let profile = {
+        let auth_state = state.auth.lock().map_err(|_| "Failed to lock auth state")?;
+        auth_state.profile.clone().unwrap_or_else(smoke_profile)
+    };
+    let timeout = Duration::from_secs(timeout_secs.unwrap_or(DEFAULT_TIMEOUT_SECS).clamp(MIN_TIMEOUT_SECS, MAX_TIMEOUT_SECS));
+    crate::run_blocking(move || {
+        let _active = crate::updater::mark_active(&app, &instance.id);
+        let instance_path = PathBuf::from(&instance.path);
+        let logs_dir = instance_path.join("logs");
+        fs::create_dir_all(&logs_dir).map_err(|e| e.to_string())?;
+        let options = LaunchOptions {
+            width: Some(854),
+            height: Some(480),
+            linux: instance.linux_tweaks.clone().unwrap_or_default(),
+            instance_name: instance.name.clone(),
+            game_language: instance.game_language.clone(),
+            translation_pack_url: instance.translation_pack_url.clone(),
+            jvm_preset: instance.jvm_preset.clone(),
+            java_runtime: crate::minecraft::java::pinned_runtime(&crate::sandbox::base_path(&instance), &instance),
+            ..LaunchOptions::default()
+        };
+        let ram = instance.ram.unwrap_or_else(|| crate::platform::default_ram_for(instance.modloader.as_deref()));
+        let cmd = prepare_and_launch(
+            &crate::sandbox::base_path(&instance),
+            &instance_path,
+            &instance.version,
+            &profile,
+            ram,
+            instance.mods.clone(),
+            instance.modpack_url.clone(),
+            instance.modloader.clone(),
+            Some(app.clone()),
+            &instance.id,
+            false,
+            &options,
+        )?;
+        let result = run(offscreen(cmd), &logs_dir.join(LOG_FILE), timeout);
+        crate::janitor::after_session(&instance_path);
+        result
+    }).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn loaders_name_the_mod_they_blame() {
+        assert_eq!(problem_mod("\t - Mod 'Sodium' (sodium) 0.5.3 requires version 0.14.21 or later of fabricloader"), Some("sodium".into()));
+        assert_eq!(problem_mod("Failed to create mod instance. ModID: examplemod, class com.example.ExampleMod"), Some("examplemod".into()));
+        assert_eq!(problem_mod("-- MOD create --"), Some("create".into()));
+        assert_eq!(problem_mod("\tMod File: /data/instancias/Evento/minecraft/mods/create-1.20.1-0.5.1.jar"), Some("create-1.20.1-0.5.1.jar".into()));
+        assert_eq!(problem_mod("Mixin apply for mod iris failed iris.mixins.json:MixinGameRenderer"), Some("iris".into()));
+        assert_eq!(problem_mod("[Render thread/INFO]: Sound engine started"), None);
+    }
+
+    #[test]
+    fn the_first_failure_wins_over_a_late_title_screen() {
+        let mut watch = Watch::default();
+        watch.feed("[main/INFO]: Loading Minecraft 1.20.1 with Fabric Loader 0.14.21");
+        assert!(!watch.ready && watch.failure.is_none());
+        watch.feed("[Render thread/INFO]: Sound engine started");
+        assert!(watch.ready && watch.failure.is_none());
+
+        let mut watch = Watch::default();
+        watch.feed("net.fabricmc.loader.impl.FormattedException: Mod resolution encountered an incompatible mod set!");
+        watch.feed("\t - Mod 'Iris' (iris) 1.6.4 is incompatible with any version of mod 'OptiFabric' (optifabric)");
+        assert!(watch.failure.as_deref().unwrap().contains("incompatible mod set"));
+        assert_eq!(watch.problem_mod.as_deref(), Some("iris"));
+    }
+}
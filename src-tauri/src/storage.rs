@@ -0,0 +1,179 @@
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+// Walking a data dir with a few instances takes seconds; the settings page re-renders often
+const CACHE_TTL: Duration = Duration::from_secs(60);
+/// Top-level folders that only hold re-downloadable content.
+const CACHE_DIRS: [&str; 2] = ["versions", "mods-store"];
+
+#[derive(Serialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct InstanceStorage {
+    pub id: String,
+    pub name: String,
+    pub mods: u64,
+    pub saves: u64,
+    /// Launcher logs next to `minecraft/` plus the game's own `minecraft/logs`
+    pub logs: u64,
+    pub other: u64,
+    pub total: u64,
+}
+
+/// Bytes per area of the data dir. Mods hard-linked from `mods-store` count both there and in
+/// each instance, so the parts can add up to more than the disk actually holds.
+#[derive(Serialize, Clone, Debug, Default, JsonSchema)]
+pub struct StorageSummary {
+    #[serde(rename = "totalBytes")]
+    pub total_bytes: u64,
+    pub assets: u64,
+    pub libraries: u64,
+    pub java: u64,
+    pub caches: u64,
+    pub instances: Vec<InstanceStorage>,
+    /// Settings, indexes and anything else in the data dir
+    pub other: u64,
+}
+
+static CACHE: Mutex<Option<(Instant, StorageSummary)>> = Mutex::new(None);
+
+/// Size of a file or folder; symlinks aren't followed.
+pub fn dir_size(path: &Path) -> u64 {
+    let Ok(meta) = fs::symlink_metadata(path) else { return 0; };
+    if !meta.is_dir() {
+        return meta.len();
+    }
+    fs::read_dir(path)
+        .map(|entries| entries.flatten().map(|entry| dir_size(&entry.path())).sum())
+        .unwrap_or(0)
+}
+
+/// Sizes every path on its own thread; the big folders (assets, libraries, each instance) dominate
+/// and walk in parallel.
+fn sizes_parallel(paths: &[PathBuf]) -> Vec<u64> {
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = paths.iter().map(|path| scope.spawn(move || dir_size(path))).collect();
+        handles.into_iter().map(|handle| handle.join().unwrap_or(0)).collect()
+    })
+}
+
+fn instance_storage(instance: &crate::Instance) -> InstanceStorage {
+    let root = PathBuf::from(&instance.path);
+    let minecraft = root.join("minecraft");
+    let parts = [minecraft.join("mods"), minecraft.join("saves"), root.join("logs"), minecraft.join("logs"), root.clone()];
+    let sizes = parts.iter().map(|p| dir_size(p)).collect::<Vec<_>>();
+    let (mods, saves, logs, total) = (sizes[0], sizes[1], sizes[2] + sizes[3], sizes[4]);
+    InstanceStorage {
+        id: instance.id.clone(),
+        name: instance.name.clone(),
+        mods,
+        saves,
+        logs,
+        other: total.saturating_sub(mods + saves + logs),
+        total,
+    }
+}
+
+pub fn summarize(root: &Path, instances: &[crate::Instance]) -> StorageSummary {
+    let named = [root.join("assets"), root.join("libraries"), root.join("java")];
+    let caches: Vec<PathBuf> = CACHE_DIRS.iter().map(|dir| root.join(dir)).collect();
+    let (named_sizes, cache_sizes, instance_sizes) = std::thread::scope(|scope| {
+        let named = scope.spawn(|| sizes_parallel(&named));
+        let caches = scope.spawn(|| sizes_parallel(&caches));
+        let instances = instances.iter()
+            .map(|instance| scope.spawn(move || instance_storage(instance)))
+            .collect::<Vec<_>>()
+            .into_iter()
+            .filter_map(|handle| handle.join().ok())
+            .collect::<Vec<_>>();
+        (named.join().unwrap_or_default(), caches.join().unwrap_or_default(), instances)
+    });
+    let total_bytes = dir_size(root);
+    let instances_dir = dir_size(&root.join(crate::INSTANCES_SUBDIR));
+    let accounted = named_sizes.iter().sum::<u64>() + cache_sizes.iter().sum::<u64>() + instances_dir;
+    StorageSummary {
+        total_bytes,
+        assets: named_sizes.first().copied().unwrap_or(0),
+        libraries: named_sizes.get(1).copied().unwrap_or(0),
+        java: named_sizes.get(2).copied().unwrap_or(0),
+        caches: cache_sizes.iter().sum(),
+        instances: instance_sizes,
+        other: total_bytes.saturating_sub(accounted),
+    }
+}
+
+/// Storage use of the launcher's data dir for the "Almacenamiento" page. Cached for a minute;
+/// `refresh` walks the disk again (after deleting an instance or pruning assets).
+#[tauri::command]
+pub async fn get_storage_summary(refresh: Option<bool>) -> Result<StorageSummary, String> {
+    crate::run_blocking(move || {
+        if !refresh.unwrap_or(false) {
+            if let Some((at, summary)) = CACHE.lock().ok().and_then(|cache| cache.clone()) {
+                if at.elapsed() < CACHE_TTL {
+                    return Ok(summary);
+                }
+            }
+        }
+        let summary = summarize(Path::new(&crate::get_instances_dir()), &crate::load_instances());
+        if let Ok(mut cache) = CACHE.lock() {
+            *cache = Some((Instant::now(), summary.clone()));
+        }
+        Ok(summary)
+    }).await
+}
+
+/// Moves the re-downloadable caches out of the way; they're erased once the undo window closes
+/// (see `crate::undo`).
+#[tauri::command]
+pub fn clear_caches(app: tauri::AppHandle, password: String) -> Result<crate::undo::Operation, String> {
+    crate::ensure_admin(password)?;
+    let root = PathBuf::from(crate::get_instances_dir());
+    let paths: Vec<PathBuf> = CACHE_DIRS.iter().map(|dir| root.join(dir)).collect();
+    let operation = crate::undo::stage_and_announce(&app, "clear_caches", "Cachés de descargas", &paths, None)?;
+    if let Ok(mut cache) = CACHE.lock() {
+        *cache = None;
+    }
+    Ok(operation)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write(path: &Path, len: usize) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, vec![0u8; len]).unwrap();
+    }
+
+    #[test]
+    fn areas_and_instances_are_split() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        write(&root.join("assets/objects/ab/abcd"), 100);
+        write(&root.join("libraries/a.jar"), 50);
+        write(&root.join("java/bin/java"), 30);
+        write(&root.join("versions/1.20.1/1.20.1.jar"), 20);
+        write(&root.join("mods-store/ab/ab.jar"), 10);
+        write(&root.join("settings.json"), 5);
+        let instance_dir = root.join(crate::INSTANCES_SUBDIR).join("Evento");
+        write(&instance_dir.join("minecraft/mods/a.jar"), 40);
+        write(&instance_dir.join("minecraft/saves/w/level.dat"), 8);
+        write(&instance_dir.join("logs/latest.log"), 4);
+        write(&instance_dir.join("minecraft/logs/latest.log"), 2);
+        write(&instance_dir.join("minecraft/options.txt"), 1);
+        let instance: crate::Instance = serde_json::from_value(serde_json::json!({
+            "id": "evento", "name": "Evento", "version": "1.20.1", "icon": "default",
+            "path": instance_dir.to_string_lossy(),
+        })).unwrap();
+
+        let summary = summarize(root, &[instance]);
+        assert_eq!((summary.assets, summary.libraries, summary.java, summary.caches), (100, 50, 30, 30));
+        assert_eq!(summary.instances[0], InstanceStorage {
+            id: "evento".into(), name: "Evento".into(), mods: 40, saves: 8, logs: 6, other: 1, total: 55,
+        });
+        assert_eq!(summary.other, 5);
+        assert_eq!(summary.total_bytes, 100 + 50 + 30 + 30 + 55 + 5);
+    }
+}
@@ -0,0 +1,107 @@
+use std::fs::{self, File};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// Suffix of every file or folder the launcher is still writing. Distinct from a bare `.tmp` so
+/// the sweep never touches temp files of the game or its mods.
+pub const TMP_SUFFIX: &str = ".drk.tmp";
+// Downloads keep touching their temp file; one this old belongs to no running job
+const STALE_AFTER: Duration = Duration::from_secs(60 * 60);
+
+/// `<path>.drk.tmp`, next to `path` so the final rename never crosses drives.
+pub fn tmp_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().map(|n| n.to_os_string()).unwrap_or_default();
+    name.push(TMP_SUFFIX);
+    path.with_file_name(name)
+}
+
+pub fn is_tmp(path: &Path) -> bool {
+    path.file_name().is_some_and(|n| n.to_string_lossy().ends_with(TMP_SUFFIX))
+}
+
+/// Moves a finished temp file or folder onto `path`, replacing what was there.
+pub fn commit(tmp: &Path, path: &Path) -> io::Result<()> {
+    if tmp.is_dir() && path.exists() {
+        crate::remover::remove_all(path).map_err(io::Error::other)?;
+    }
+    fs::rename(tmp, path)
+}
+
+/// Creates `path` through its temp file: `path` only appears once `write` returned Ok, and a
+/// failed or interrupted write never leaves a file that passes an existence check.
+pub fn write_atomic(path: &Path, write: impl FnOnce(&mut File) -> Result<(), String>) -> Result<(), String> {
+    let tmp = tmp_path(path);
+    let result = File::create(&tmp)
+        .map_err(|e| format!("No se pudo crear {}: {}", tmp.display(), e))
+        .and_then(|mut file| write(&mut file))
+        .and_then(|_| commit(&tmp, path).map_err(|e| format!("No se pudo guardar {}: {}", path.display(), e)));
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp);
+    }
+    result
+}
+
+fn is_stale(path: &Path, now: SystemTime) -> bool {
+    fs::symlink_metadata(path)
+        .and_then(|m| m.modified())
+        .map(|modified| now.duration_since(modified).unwrap_or_default() >= STALE_AFTER)
+        .unwrap_or(false)
+}
+
+/// Removes the temp files and folders under `dir` that a crash or a kill left behind. Returns
+/// how many were removed.
+pub fn sweep(dir: &Path, now: SystemTime) -> usize {
+    let mut removed = 0;
+    for entry in fs::read_dir(dir).into_iter().flatten().flatten() {
+        let path = entry.path();
+        let Ok(kind) = entry.file_type() else { continue };
+        if is_tmp(&path) {
+            if is_stale(&path, now) && crate::remover::remove_all(&path).is_ok() {
+                removed += 1;
+            }
+        } else if kind.is_dir() {
+            removed += sweep(&path, now);
+        }
+    }
+    removed
+}
+
+/// Sweeps the data folder in the background at startup.
+pub fn start_sweep(dir: PathBuf) {
+    std::thread::spawn(move || {
+        let removed = sweep(&dir, SystemTime::now());
+        if removed > 0 {
+            eprintln!("[temp_files] Removed {} stale temp files", removed);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_stale_launcher_temps_are_swept() {
+        let dir = tempfile::tempdir().unwrap();
+        let lib = dir.path().join("libraries").join("a.jar");
+        fs::create_dir_all(lib.parent().unwrap()).unwrap();
+        write_atomic(&lib, |file| io::Write::write_all(file, b"jar").map_err(|e| e.to_string())).unwrap();
+        assert_eq!(fs::read(&lib).unwrap(), b"jar");
+        assert!(!tmp_path(&lib).exists());
+
+        let failed = dir.path().join("libraries").join("b.jar");
+        assert!(write_atomic(&failed, |_| Err("cut".to_string())).is_err());
+        assert!(!failed.exists() && !tmp_path(&failed).exists());
+
+        fs::write(tmp_path(&failed), b"partial").unwrap();
+        fs::create_dir_all(tmp_path(&dir.path().join("java").join("17")).join("bin")).unwrap();
+        fs::write(dir.path().join("libraries").join("mod.tmp"), b"not ours").unwrap();
+
+        assert_eq!(sweep(dir.path(), SystemTime::now()), 0);
+        assert_eq!(sweep(dir.path(), SystemTime::now() + STALE_AFTER), 2);
+        assert!(!tmp_path(&failed).exists());
+        assert!(dir.path().join("libraries").join("mod.tmp").exists());
+        assert!(dir.path().join("java").exists() && lib.exists());
+    }
+}
@@ -0,0 +1,235 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tauri::AppHandle;
+use crate::settings::{ensure_not_guest, load_settings, save_settings};
+
+/// Deleted instances wait here (under the data root, so moving them is a rename) until restored
+/// or purged.
+const TRASH_DIR: &str = ".trash";
+const ENTRY_FILE: &str = "entry.json";
+const FILES_DIR: &str = "files";
+const PURGE_INTERVAL: Duration = Duration::from_secs(6 * 60 * 60);
+
+#[derive(Serialize, Deserialize, Clone, JsonSchema)]
+pub struct TrashSettings {
+    /// Deleting an instance moves it to the trash instead of erasing it
+    #[serde(default = "crate::settings::default_true")]
+    pub enabled: bool,
+    /// Days an instance stays in the trash before it's purged for good
+    #[serde(rename = "retentionDays", alias = "retention_days", default = "default_retention_days")]
+    pub retention_days: u64,
+}
+
+impl Default for TrashSettings {
+    fn default() -> Self {
+        TrashSettings { enabled: true, retention_days: default_retention_days() }
+    }
+}
+
+fn default_retention_days() -> u64 {
+    7
+}
+
+#[derive(Serialize, Deserialize, Clone, JsonSchema)]
+pub struct TrashEntry {
+    pub id: String,
+    /// Unix seconds
+    #[serde(rename = "deletedAt", alias = "deleted_at")]
+    pub deleted_at: u64,
+    pub instance: crate::Instance,
+}
+
+pub fn root() -> PathBuf {
+    PathBuf::from(crate::get_instances_dir()).join(TRASH_DIR)
+}
+
+/// Entry ids are generated here; anything else coming from the webview is refused.
+fn entry_dir(root: &Path, id: &str) -> Result<PathBuf, String> {
+    if uuid::Uuid::parse_str(id).is_err() {
+        return Err("Elemento de la papelera no válido".to_string());
+    }
+    Ok(root.join(id))
+}
+
+/// Moves `folder` (the instance's resolved folder) into the trash with the instance record, so
+/// it can be put back as it was.
+pub fn move_to_trash(root: &Path, instance: &crate::Instance, folder: &Path) -> Result<TrashEntry, String> {
+    let entry = TrashEntry { id: uuid::Uuid::new_v4().to_string(), deleted_at: crate::unix_now(), instance: instance.clone() };
+    let dir = root.join(&entry.id);
+    fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    let json = serde_json::to_string_pretty(&entry).map_err(|e| e.to_string())?;
+    let moved = fs::write(dir.join(ENTRY_FILE), json).and_then(|_| fs::rename(folder, dir.join(FILES_DIR)));
+    if let Err(e) = moved {
+        let _ = fs::remove_dir_all(&dir);
+        return Err(format!("No se pudo mover la instancia a la papelera (¿el juego sigue abierto?): {}", e));
+    }
+    Ok(entry)
+}
+
+/// Entries in the trash, newest first. Folders without a readable record are skipped.
+pub fn entries(root: &Path) -> Vec<TrashEntry> {
+    let mut entries: Vec<TrashEntry> = fs::read_dir(root)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|dir| fs::read_to_string(dir.path().join(ENTRY_FILE)).ok())
+        .filter_map(|json| serde_json::from_str(&json).ok())
+        .collect();
+    entries.sort_by_key(|entry| std::cmp::Reverse(entry.deleted_at));
+    entries
+}
+
+/// Free folder to restore into: the original one, or the same name with a numeric suffix.
+fn restore_target(original: &Path) -> PathBuf {
+    if !original.exists() {
+        return original.to_path_buf();
+    }
+    let name = original.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    (2..)
+        .map(|n| original.with_file_name(format!("{} ({})", name, n)))
+        .find(|candidate| !candidate.exists())
+        .unwrap_or_else(|| original.to_path_buf())
+}
+
+/// Puts the instance folder back and returns the instance record pointing at it. Ids already
+/// taken by another instance get a new one.
+pub fn restore(root: &Path, id: &str, instances: &[crate::Instance]) -> Result<crate::Instance, String> {
+    let dir = entry_dir(root, id)?;
+    let json = fs::read_to_string(dir.join(ENTRY_FILE)).map_err(|_| "El elemento ya no está en la papelera".to_string())?;
+    let entry: TrashEntry = serde_json::from_str(&json).map_err(|e| e.to_string())?;
+    let mut instance = entry.instance;
+    let target = restore_target(Path::new(&instance.path));
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    fs::rename(dir.join(FILES_DIR), &target).map_err(|e| format!("No se pudo restaurar la instancia: {}", e))?;
+    let _ = fs::remove_dir_all(&dir);
+    instance.path = target.to_string_lossy().to_string();
+    if instances.iter().any(|i| i.id == instance.id) {
+        instance.id = crate::instance_input::new_id();
+    }
+    Ok(instance)
+}
+
+/// Entry folders older than `retention_days` (and leftovers without a record).
+pub fn expired(root: &Path, retention_days: u64, now: u64) -> Vec<PathBuf> {
+    fs::read_dir(root)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter(|dir| {
+            let record = fs::read_to_string(dir.path().join(ENTRY_FILE)).ok().and_then(|json| serde_json::from_str::<TrashEntry>(&json).ok());
+            match record {
+                Some(entry) => now.saturating_sub(entry.deleted_at) >= retention_days * 24 * 60 * 60,
+                None => true,
+            }
+        })
+        .map(|dir| dir.path())
+        .collect()
+}
+
+/// Purges expired entries at startup and every few hours after.
+pub fn start() {
+    std::thread::spawn(|| loop {
+        let settings = load_settings().trash;
+        for dir in expired(&root(), settings.retention_days, crate::unix_now()) {
+            if let Err(e) = crate::remover::remove_all(&dir) {
+                eprintln!("[trash] Purge failed: {}", e);
+            }
+        }
+        std::thread::sleep(PURGE_INTERVAL);
+    });
+}
+
+#[tauri::command]
+pub fn get_trash_settings() -> TrashSettings {
+    load_settings().trash
+}
+
+#[tauri::command]
+pub fn set_trash_settings(trash: TrashSettings) -> Result<(), String> {
+    ensure_not_guest()?;
+    let mut settings = load_settings();
+    settings.trash = trash;
+    save_settings(&settings)
+}
+
+#[tauri::command]
+pub fn list_trash() -> Vec<TrashEntry> {
+    entries(&root())
+}
+
+#[tauri::command]
+pub async fn restore_instance(trash_id: String) -> Result<crate::Instance, String> {
+    ensure_not_guest()?;
+    crate::run_blocking(move || {
+        let mut instances = crate::load_instances();
+        let instance = restore(&root(), &trash_id, &instances)?;
+        instances.push(instance.clone());
+        crate::save_instances(&instances);
+        Ok(instance)
+    }).await
+}
+
+/// Empties one entry (or the whole trash) in the background; returns the removal job ids.
+#[tauri::command]
+pub fn purge_trash(app: AppHandle, trash_id: Option<String>) -> Result<Vec<String>, String> {
+    ensure_not_guest()?;
+    let root = root();
+    if let Some(id) = &trash_id {
+        entry_dir(&root, id)?;
+    }
+    Ok(entries(&root).into_iter()
+        .filter(|entry| trash_id.is_none() || trash_id.as_deref() == Some(entry.id.as_str()))
+        .map(|entry| crate::remover::spawn(&app, &entry.instance.name, crate::remover::retire(&root.join(&entry.id))))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn instance(id: &str, path: &Path) -> crate::Instance {
+        serde_json::from_value(serde_json::json!({
+            "id": id, "name": "Evento", "version": "1.20.1", "icon": "default",
+            "path": path.to_string_lossy(),
+        })).unwrap()
+    }
+
+    #[test]
+    fn trashed_instances_restore_to_a_free_folder() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join(TRASH_DIR);
+        let folder = dir.path().join("instancias").join("Evento");
+        fs::create_dir_all(folder.join("minecraft/saves/mundo")).unwrap();
+        fs::write(folder.join("minecraft/saves/mundo/level.dat"), b"x").unwrap();
+
+        let entry = move_to_trash(&root, &instance("evento", &folder), &folder).unwrap();
+        assert!(!folder.exists());
+        assert_eq!(entries(&root).len(), 1);
+
+        // A new instance took the folder and the id meanwhile
+        fs::create_dir_all(&folder).unwrap();
+        let restored = restore(&root, &entry.id, &[instance("evento", &folder)]).unwrap();
+        assert_ne!(restored.id, "evento");
+        assert_eq!(PathBuf::from(&restored.path), folder.with_file_name("Evento (2)"));
+        assert!(Path::new(&restored.path).join("minecraft/saves/mundo/level.dat").exists());
+        assert!(entries(&root).is_empty());
+        assert!(restore(&root, "../instancias", &[]).is_err());
+    }
+
+    #[test]
+    fn only_old_entries_expire() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join(TRASH_DIR);
+        let folder = dir.path().join("Evento");
+        fs::create_dir_all(&folder).unwrap();
+        let entry = move_to_trash(&root, &instance("evento", &folder), &folder).unwrap();
+
+        assert!(expired(&root, 7, entry.deleted_at + 60).is_empty());
+        assert_eq!(expired(&root, 7, entry.deleted_at + 8 * 24 * 60 * 60), vec![root.join(&entry.id)]);
+    }
+}
@@ -0,0 +1,226 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+use crate::settings::{ensure_not_guest, load_settings, save_settings};
+
+const MAX_WORKERS: usize = 64;
+const MIN_TIMEOUT_SECS: u64 = 60;
+const MAX_TIMEOUT_SECS: u64 = 4 * 60 * 60;
+// Large files (Java, modpacks) on a slow link need a while
+const DEFAULT_TIMEOUT_SECS: u64 = 60 * 60;
+const MIN_STALL_SECS: u64 = 5;
+const MAX_STALL_SECS: u64 = 10 * 60;
+// A healthy connection never goes this long without a byte, even on event-night Wi-Fi
+const DEFAULT_STALL_SECS: u64 = 30;
+// Synced small writes take well under a millisecond on SSDs and several on spinning disks
+const HDD_SYNC_WRITE_THRESHOLD: Duration = Duration::from_millis(4);
+const PROBE_FILES: usize = 16;
+
+/// Overrides for the download pools and asset verification; `None` means tuned automatically.
+#[derive(Serialize, Deserialize, Clone, Default, Debug, PartialEq, JsonSchema)]
+pub struct PerformanceSettings {
+    /// Storage type of the data dir when detection gets it wrong
+    #[serde(default)]
+    pub disk: Option<DiskKind>,
+    #[serde(rename = "verifyMode", alias = "verify_mode", default)]
+    pub verify_mode: Option<VerifyMode>,
+    #[serde(rename = "modWorkers", alias = "mod_workers", default)]
+    pub mod_workers: Option<usize>,
+    #[serde(rename = "assetWorkers", alias = "asset_workers", default)]
+    pub asset_workers: Option<usize>,
+    #[serde(rename = "downloadTimeoutSecs", alias = "download_timeout_secs", default)]
+    pub download_timeout_secs: Option<u64>,
+    /// Seconds without receiving a byte before a download is dropped and retried
+    #[serde(rename = "stallTimeoutSecs", alias = "stall_timeout_secs", default)]
+    pub stall_timeout_secs: Option<u64>,
+}
+
+impl PerformanceSettings {
+    /// Same settings with every override moved into its allowed range.
+    pub fn bounded(&self) -> PerformanceSettings {
+        PerformanceSettings {
+            disk: self.disk,
+            verify_mode: self.verify_mode,
+            mod_workers: self.mod_workers.map(|n| n.clamp(1, MAX_WORKERS)),
+            asset_workers: self.asset_workers.map(|n| n.clamp(1, MAX_WORKERS)),
+            download_timeout_secs: self.download_timeout_secs.map(|s| s.clamp(MIN_TIMEOUT_SECS, MAX_TIMEOUT_SECS)),
+            stall_timeout_secs: self.stall_timeout_secs.map(|s| s.clamp(MIN_STALL_SECS, MAX_STALL_SECS)),
+        }
+    }
+}
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+pub struct Tuning {
+    #[serde(rename = "modWorkers")]
+    pub mod_workers: usize,
+    #[serde(rename = "assetWorkers")]
+    pub asset_workers: usize,
+    #[serde(rename = "downloadTimeoutSecs")]
+    pub download_timeout_secs: u64,
+    #[serde(rename = "stallTimeoutSecs")]
+    pub stall_timeout_secs: u64,
+    #[serde(rename = "verifyMode")]
+    pub verify_mode: VerifyMode,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum DiskKind {
+    Ssd,
+    Hdd,
+}
+
+/// How existing asset objects are checked before launch.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum VerifyMode {
+    /// SHA-1 of every object; catches corrupted files
+    Full,
+    /// Existence and size only; reading tens of thousands of files is what makes HDDs slow
+    Quick,
+}
+
+/// Defaults for this machine. Mods are a few large files, assets thousands of tiny ones; on a
+/// spinning disk more readers and writers only add seeks.
+pub fn auto_tuning(cpus: usize, disk: DiskKind) -> Tuning {
+    let (mod_workers, asset_workers, verify_mode) = match disk {
+        DiskKind::Ssd => ((cpus * 2).clamp(4, 16), (cpus * 4).clamp(8, 32), VerifyMode::Full),
+        DiskKind::Hdd => (4, 8, VerifyMode::Quick),
+    };
+    Tuning { mod_workers, asset_workers, download_timeout_secs: DEFAULT_TIMEOUT_SECS, stall_timeout_secs: DEFAULT_STALL_SECS, verify_mode }
+}
+
+/// Times a few synced small writes in `dir`; slow ones mean a spinning disk (or a network drive,
+/// which wants the same treatment).
+pub fn probe_disk(dir: &Path) -> DiskKind {
+    let probe_dir = dir.join(".disk-probe");
+    if fs::create_dir_all(&probe_dir).is_err() {
+        return DiskKind::Ssd;
+    }
+    let started = Instant::now();
+    let mut written: u32 = 0;
+    for i in 0..PROBE_FILES {
+        let ok = fs::File::create(probe_dir.join(i.to_string()))
+            .and_then(|mut file| std::io::Write::write_all(&mut file, &[0u8; 4096]).and_then(|_| file.sync_all()));
+        if ok.is_ok() {
+            written += 1;
+        }
+    }
+    let elapsed = started.elapsed();
+    let _ = fs::remove_dir_all(&probe_dir);
+    if written > 0 && elapsed / written > HDD_SYNC_WRITE_THRESHOLD {
+        DiskKind::Hdd
+    } else {
+        DiskKind::Ssd
+    }
+}
+
+/// Detected once per run, on first use: what the OS reports for the data dir's drive, else the
+/// write probe.
+fn detected_disk() -> DiskKind {
+    static DISK: OnceLock<DiskKind> = OnceLock::new();
+    *DISK.get_or_init(|| {
+        let dir = crate::data_dir::root();
+        crate::platform::disk_kind_of(&dir).unwrap_or_else(|| probe_disk(&dir))
+    })
+}
+
+fn disk_kind(settings: &PerformanceSettings) -> DiskKind {
+    settings.disk.unwrap_or_else(detected_disk)
+}
+
+fn cpus() -> usize {
+    std::thread::available_parallelism().map(|n| n.get()).unwrap_or(4)
+}
+
+/// Overrides on top of the automatic values.
+pub fn effective(settings: &PerformanceSettings, auto: Tuning) -> Tuning {
+    let settings = settings.bounded();
+    Tuning {
+        mod_workers: settings.mod_workers.unwrap_or(auto.mod_workers),
+        asset_workers: settings.asset_workers.unwrap_or(auto.asset_workers),
+        download_timeout_secs: settings.download_timeout_secs.unwrap_or(auto.download_timeout_secs),
+        stall_timeout_secs: settings.stall_timeout_secs.unwrap_or(auto.stall_timeout_secs),
+        verify_mode: settings.verify_mode.unwrap_or(auto.verify_mode),
+    }
+}
+
+pub fn current() -> Tuning {
+    let settings = load_settings().performance;
+    effective(&settings, auto_tuning(cpus(), disk_kind(&settings)))
+}
+
+/// Timeout for a whole file download.
+pub fn download_timeout() -> Duration {
+    Duration::from_secs(current().download_timeout_secs)
+}
+
+/// Longest wait for the next chunk of a download.
+pub fn stall_timeout() -> Duration {
+    Duration::from_secs(current().stall_timeout_secs)
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct PerformanceInfo {
+    pub settings: PerformanceSettings,
+    /// What "automatic" resolves to on this machine
+    pub auto: Tuning,
+    pub cpus: usize,
+    /// Storage type in use (the override, if set)
+    pub disk: DiskKind,
+    #[serde(rename = "detectedDisk")]
+    pub detected_disk: DiskKind,
+}
+
+#[tauri::command]
+pub async fn get_performance_settings() -> Result<PerformanceInfo, String> {
+    crate::run_blocking(|| {
+        let settings = load_settings().performance;
+        let (cpus, disk) = (cpus(), disk_kind(&settings));
+        Ok(PerformanceInfo { settings, auto: auto_tuning(cpus, disk), cpus, disk, detected_disk: detected_disk() })
+    }).await
+}
+
+/// Out-of-range values are clamped rather than refused.
+#[tauri::command]
+pub fn set_performance_settings(performance: PerformanceSettings) -> Result<PerformanceSettings, String> {
+    ensure_not_guest()?;
+    let mut settings = load_settings();
+    settings.performance = performance.bounded();
+    save_settings(&settings)?;
+    Ok(settings.performance)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn defaults_follow_cpus_and_disk() {
+        assert_eq!(auto_tuning(2, DiskKind::Ssd), Tuning {
+            mod_workers: 4, asset_workers: 8, download_timeout_secs: 3600, stall_timeout_secs: 30, verify_mode: VerifyMode::Full,
+        });
+        assert_eq!(auto_tuning(8, DiskKind::Ssd), Tuning {
+            mod_workers: 16, asset_workers: 32, download_timeout_secs: 3600, stall_timeout_secs: 30, verify_mode: VerifyMode::Full,
+        });
+        let hdd = auto_tuning(32, DiskKind::Hdd);
+        assert_eq!((hdd.asset_workers, hdd.verify_mode), (8, VerifyMode::Quick));
+    }
+
+    #[test]
+    fn overrides_are_bounded() {
+        let auto = auto_tuning(8, DiskKind::Ssd);
+        let settings = PerformanceSettings {
+            mod_workers: Some(0), asset_workers: Some(500), download_timeout_secs: Some(5), stall_timeout_secs: Some(1),
+            verify_mode: Some(VerifyMode::Quick), ..Default::default()
+        };
+        assert_eq!(effective(&settings, auto), Tuning {
+            mod_workers: 1, asset_workers: MAX_WORKERS, download_timeout_secs: MIN_TIMEOUT_SECS, stall_timeout_secs: MIN_STALL_SECS,
+            verify_mode: VerifyMode::Quick,
+        });
+        assert_eq!(effective(&PerformanceSettings::default(), auto), auto);
+    }
+}
@@ -0,0 +1,261 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+/// Data removed by a destructive operation waits here until the undo window closes.
+const UNDO_DIR: &str = ".undo";
+const OPERATION_FILE: &str = "operation.json";
+const ITEMS_DIR: &str = "items";
+const UNDO_WINDOW_SECS: u64 = 10 * 60;
+const PURGE_TICK: Duration = Duration::from_secs(30);
+
+// Staging and undoing both rename inside the staging area; one at a time
+static LOCK: Mutex<()> = Mutex::new(());
+
+/// One path moved out of the way: `staged` is relative to the operation's folder.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct StagedItem {
+    pub original: PathBuf,
+    pub staged: PathBuf,
+}
+
+#[derive(Serialize, Deserialize, Clone, JsonSchema)]
+pub struct Operation {
+    pub id: String,
+    /// `delete_instance`, `clear_caches`, `prune_assets`
+    pub kind: String,
+    pub label: String,
+    /// Unix seconds
+    #[serde(rename = "createdAt", alias = "created_at")]
+    pub created_at: u64,
+    #[serde(rename = "expiresAt", alias = "expires_at")]
+    pub expires_at: u64,
+    #[serde(default)]
+    pub items: Vec<StagedItem>,
+    /// Instance record to put back in the list on undo
+    #[serde(default)]
+    pub instance: Option<crate::Instance>,
+    /// Deleted instance sitting in the trash; undo restores it from there
+    #[serde(rename = "trashId", alias = "trash_id", default)]
+    pub trash_id: Option<String>,
+}
+
+fn root() -> PathBuf {
+    PathBuf::from(crate::get_instances_dir()).join(UNDO_DIR)
+}
+
+fn write_operation(dir: &Path, operation: &Operation) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(operation).map_err(|e| e.to_string())?;
+    fs::write(dir.join(OPERATION_FILE), json).map_err(|e| e.to_string())
+}
+
+/// Moves `paths` into a new operation folder. Paths that don't exist are skipped; if one can't
+/// be moved, the ones already moved go back and nothing is staged.
+pub fn stage(root: &Path, kind: &str, label: &str, paths: &[PathBuf], instance: Option<crate::Instance>, now: u64) -> Result<Operation, String> {
+    let _guard = LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let mut operation = Operation {
+        id: uuid::Uuid::new_v4().to_string(),
+        kind: kind.to_string(),
+        label: label.to_string(),
+        created_at: now,
+        expires_at: now + UNDO_WINDOW_SECS,
+        items: Vec::new(),
+        instance,
+        trash_id: None,
+    };
+    let dir = root.join(&operation.id);
+    fs::create_dir_all(dir.join(ITEMS_DIR)).map_err(|e| e.to_string())?;
+    for path in paths.iter().filter(|p| fs::symlink_metadata(p).is_ok()) {
+        let staged = Path::new(ITEMS_DIR).join(operation.items.len().to_string());
+        if let Err(e) = fs::rename(path, dir.join(&staged)) {
+            for item in &operation.items {
+                let _ = fs::rename(dir.join(&item.staged), &item.original);
+            }
+            let _ = fs::remove_dir_all(&dir);
+            return Err(format!("No se pudo mover {}: {}", path.display(), e));
+        }
+        operation.items.push(StagedItem { original: path.clone(), staged });
+    }
+    write_operation(&dir, &operation)?;
+    Ok(operation)
+}
+
+/// Records and announces a deletion whose data is kept in the instance trash.
+pub fn record_trashed(app: &AppHandle, label: &str, trash_id: &str) -> Result<Operation, String> {
+    let root = root();
+    let mut operation = stage(&root, "delete_instance", label, &[], None, crate::unix_now())?;
+    operation.trash_id = Some(trash_id.to_string());
+    write_operation(&root.join(&operation.id), &operation)?;
+    announce(app, &operation);
+    Ok(operation)
+}
+
+fn operations(root: &Path) -> Vec<Operation> {
+    let mut operations: Vec<Operation> = fs::read_dir(root)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|dir| fs::read_to_string(dir.path().join(OPERATION_FILE)).ok())
+        .filter_map(|json| serde_json::from_str(&json).ok())
+        .collect();
+    operations.sort_by_key(|op| op.created_at);
+    operations
+}
+
+/// Newest operation still inside its window.
+pub fn last(root: &Path, now: u64) -> Option<Operation> {
+    operations(root).into_iter().rev().find(|op| op.expires_at > now)
+}
+
+/// Moves the staged paths of the newest operation back. Originals that were recreated in the
+/// meantime are left alone and reported.
+pub fn undo_last(root: &Path, now: u64) -> Result<Operation, String> {
+    let _guard = LOCK.lock().unwrap_or_else(|e| e.into_inner());
+    let operation = last(root, now).ok_or("No hay ninguna operación que deshacer")?;
+    let dir = root.join(&operation.id);
+    let mut conflicts = Vec::new();
+    for item in &operation.items {
+        if item.original.exists() {
+            conflicts.push(item.clone());
+            continue;
+        }
+        if let Some(parent) = item.original.parent() {
+            let _ = fs::create_dir_all(parent);
+        }
+        fs::rename(dir.join(&item.staged), &item.original).map_err(|e| format!("No se pudo restaurar {}: {}", item.original.display(), e))?;
+    }
+    if !conflicts.is_empty() {
+        // What couldn't go back stays staged until it expires
+        let names = conflicts.iter().map(|item| item.original.display().to_string()).collect::<Vec<_>>().join(", ");
+        write_operation(&dir, &Operation { items: conflicts, ..operation })?;
+        return Err(format!("Ya existe: {}", names));
+    }
+    let _ = fs::remove_dir_all(&dir);
+    Ok(operation)
+}
+
+/// Operation folders past their window (and leftovers without a record).
+pub fn expired(root: &Path, now: u64) -> Vec<PathBuf> {
+    fs::read_dir(root)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter(|dir| {
+            let record = fs::read_to_string(dir.path().join(OPERATION_FILE)).ok().and_then(|json| serde_json::from_str::<Operation>(&json).ok());
+            match record {
+                Some(op) => op.expires_at <= now,
+                None => true,
+            }
+        })
+        .map(|dir| dir.path())
+        .collect()
+}
+
+/// `undo_available` payload: the operation without its staged paths.
+#[derive(Serialize, Clone, JsonSchema)]
+pub struct UndoAvailable {
+    pub id: String,
+    pub kind: String,
+    pub label: String,
+    #[serde(rename = "expiresAt")]
+    pub expires_at: u64,
+}
+
+/// Tells the UI an operation can be undone until `expiresAt`.
+fn announce(app: &AppHandle, operation: &Operation) {
+    let _ = app.emit("undo_available", UndoAvailable {
+        id: operation.id.clone(),
+        kind: operation.kind.clone(),
+        label: operation.label.clone(),
+        expires_at: operation.expires_at,
+    });
+}
+
+/// Stages `paths` and announces the operation; the data is erased once the window closes.
+pub fn stage_and_announce(app: &AppHandle, kind: &str, label: &str, paths: &[PathBuf], instance: Option<crate::Instance>) -> Result<Operation, String> {
+    let operation = stage(&root(), kind, label, paths, instance, crate::unix_now())?;
+    announce(app, &operation);
+    Ok(operation)
+}
+
+/// Erases staged data whose undo window closed, at startup and every few seconds after.
+pub fn start() {
+    std::thread::spawn(|| loop {
+        // Checked and moved aside under the lock so a folder being staged is never taken for a leftover
+        let retired = {
+            let _guard = LOCK.lock().unwrap_or_else(|e| e.into_inner());
+            expired(&root(), crate::unix_now()).iter().map(|dir| crate::remover::retire(dir)).collect::<Vec<_>>()
+        };
+        for dir in retired {
+            let _ = crate::remover::remove_all(&dir);
+        }
+        std::thread::sleep(PURGE_TICK);
+    });
+}
+
+/// Reverts the newest destructive operation still inside its window and returns it.
+#[tauri::command]
+pub async fn undo_last_operation() -> Result<Operation, String> {
+    crate::settings::ensure_not_guest()?;
+    crate::run_blocking(move || {
+        let operation = undo_last(&root(), crate::unix_now())?;
+        let mut instances = crate::load_instances();
+        let instance = match (&operation.trash_id, &operation.instance) {
+            (Some(trash_id), _) => Some(crate::trash::restore(&crate::trash::root(), trash_id, &instances)?),
+            (None, Some(instance)) => Some(instance.clone()),
+            (None, None) => None,
+        };
+        if let Some(mut instance) = instance {
+            if instances.iter().any(|i| i.id == instance.id) {
+                instance.id = crate::instance_input::new_id();
+            }
+            instances.push(instance);
+            crate::save_instances(&instances);
+        }
+        Ok(operation)
+    }).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn staged_paths_come_back_on_undo() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join(UNDO_DIR);
+        let mods = dir.path().join("mods-store");
+        fs::create_dir_all(mods.join("ab")).unwrap();
+        fs::write(mods.join("ab/ab.jar"), b"jar").unwrap();
+        let asset = dir.path().join("assets/objects/cd/cdef");
+        fs::create_dir_all(asset.parent().unwrap()).unwrap();
+        fs::write(&asset, b"ogg").unwrap();
+
+        let operation = stage(&root, "clear_caches", "Cachés", &[mods.clone(), asset.clone(), dir.path().join("missing")], None, 1_000).unwrap();
+        assert_eq!(operation.items.len(), 2);
+        assert!(!mods.exists() && !asset.exists());
+
+        let undone = undo_last(&root, 1_060).unwrap();
+        assert_eq!(undone.id, operation.id);
+        assert_eq!(fs::read(mods.join("ab/ab.jar")).unwrap(), b"jar");
+        assert_eq!(fs::read(&asset).unwrap(), b"ogg");
+        assert!(undo_last(&root, 1_060).is_err());
+    }
+
+    #[test]
+    fn operations_expire_after_the_window() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join(UNDO_DIR);
+        let file = dir.path().join("latest.log");
+        fs::write(&file, b"log").unwrap();
+        let operation = stage(&root, "clear_caches", "Logs", std::slice::from_ref(&file), None, 1_000).unwrap();
+
+        assert!(expired(&root, 1_000 + UNDO_WINDOW_SECS - 1).is_empty());
+        assert!(last(&root, operation.expires_at).is_none());
+        assert_eq!(expired(&root, operation.expires_at), vec![root.join(&operation.id)]);
+    }
+}
@@ -0,0 +1,322 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+use crate::minecraft::modpack::ModpackDiff;
+use crate::minecraft::content_source::{self, Target};
+use crate::minecraft::url_resolver::mod_file_name;
+use crate::settings::{ensure_not_guest, load_settings, save_settings};
+
+// Gives the window and the session restore time before the first round of HEAD requests
+const STARTUP_DELAY: Duration = Duration::from_secs(120);
+const MIN_INTERVAL_MINUTES: u64 = 15;
+
+/// Background modpack updates for remote-managed instances (those with a modpack URL).
+#[derive(Serialize, Deserialize, Clone, JsonSchema)]
+pub struct UpdaterSettings {
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    #[serde(rename = "intervalMinutes", alias = "interval_minutes", default = "default_interval")]
+    pub interval_minutes: u64,
+}
+
+impl Default for UpdaterSettings {
+    fn default() -> Self {
+        UpdaterSettings { enabled: true, interval_minutes: default_interval() }
+    }
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_interval() -> u64 {
+    60
+}
+
+impl UpdaterSettings {
+    pub fn interval(&self) -> Duration {
+        Duration::from_secs(self.interval_minutes.max(MIN_INTERVAL_MINUTES) * 60)
+    }
+}
+
+/// Instances being prepared or played right now; the updater never touches their files.
+/// Counted per instance, since a scheduled verify can overlap a running game.
+#[derive(Default)]
+pub struct ActiveInstances(Mutex<HashMap<String, Holds>>);
+
+/// Guards alive for one instance; `updates` of them held by the updater.
+#[derive(Default, Clone, Copy, Debug, PartialEq)]
+pub struct Holds {
+    pub total: usize,
+    pub updates: usize,
+}
+
+impl ActiveInstances {
+    /// Adds a hold when `allowed` accepts the current ones, atomically with the check.
+    pub fn hold(&self, instance_id: &str, update: bool, allowed: impl FnOnce(Holds) -> bool) -> bool {
+        let Ok(mut ids) = self.0.lock() else { return false };
+        let holds = ids.get(instance_id).copied().unwrap_or_default();
+        if !allowed(holds) {
+            return false;
+        }
+        let holds = ids.entry(instance_id.to_string()).or_default();
+        holds.total += 1;
+        holds.updates += update as usize;
+        true
+    }
+
+    pub fn release(&self, instance_id: &str, update: bool) {
+        let Ok(mut ids) = self.0.lock() else { return };
+        let Some(holds) = ids.get_mut(instance_id) else { return };
+        holds.total = holds.total.saturating_sub(1);
+        holds.updates = holds.updates.saturating_sub(update as usize);
+        if holds.total == 0 {
+            ids.remove(instance_id);
+        }
+    }
+}
+
+/// `instance_update_available` payload. `deferred` when the data saver holds the download back.
+#[derive(Serialize, Clone, JsonSchema)]
+pub struct UpdateAvailable {
+    #[serde(rename = "instanceId")]
+    pub instance_id: String,
+    pub name: String,
+    pub deferred: bool,
+}
+
+/// `instance_update_applied` payload; `error` is set when the update failed.
+#[derive(Serialize, Clone, JsonSchema)]
+pub struct UpdateApplied {
+    #[serde(rename = "instanceId")]
+    pub instance_id: String,
+    pub name: String,
+    pub error: Option<String>,
+}
+
+/// Keeps an instance marked active until dropped.
+pub struct ActiveGuard {
+    app: AppHandle,
+    instance_id: String,
+    update: bool,
+}
+
+pub fn mark_active(app: &AppHandle, instance_id: &str) -> ActiveGuard {
+    app.state::<ActiveInstances>().hold(instance_id, false, |_| true);
+    ActiveGuard { app: app.clone(), instance_id: instance_id.to_string(), update: false }
+}
+
+/// Marks a background update, unless the instance is already being prepared or played.
+fn try_mark_updating(app: &AppHandle, instance_id: &str) -> Option<ActiveGuard> {
+    app.state::<ActiveInstances>().hold(instance_id, true, |holds| holds.total == 0)
+        .then(|| ActiveGuard { app: app.clone(), instance_id: instance_id.to_string(), update: true })
+}
+
+/// `mark_active` for a launch, refused while the updater is rewriting the instance's files.
+pub fn mark_launching(app: &AppHandle, instance_id: &str) -> Result<ActiveGuard, String> {
+    if !app.state::<ActiveInstances>().hold(instance_id, false, |holds| holds.updates == 0) {
+        return Err("La instancia se está actualizando; espera a que termine para jugar".to_string());
+    }
+    Ok(ActiveGuard { app: app.clone(), instance_id: instance_id.to_string(), update: false })
+}
+
+/// Instances being prepared, updated or played right now.
+pub fn active_count(app: &AppHandle) -> usize {
+    app.state::<ActiveInstances>().0.lock().map(|ids| ids.len()).unwrap_or(0)
+}
+
+impl Drop for ActiveGuard {
+    fn drop(&mut self) {
+        self.app.state::<ActiveInstances>().release(&self.instance_id, self.update);
+    }
+}
+
+/// Same size comparison the launch-time sync does: a different remote length means a new pack.
+pub fn size_changed(local_len: u64, remote_len: Option<u64>) -> bool {
+    remote_len.map(|remote| remote != local_len).unwrap_or(false)
+}
+
+/// Remote size of a modpack; `None` when the server doesn't send one.
+pub fn remote_len(url: &str, target: &Target) -> Result<Option<u64>, String> {
+    content_source::size(&content_source::resolve(url, target)?)
+}
+
+/// Only packs already installed are updated; a never-downloaded instance stays that way.
+fn modpack_update_available(url: &str, target: &Target, zip_path: &Path) -> Result<bool, String> {
+    let Ok(meta) = fs::metadata(zip_path) else { return Ok(false); };
+    Ok(size_changed(meta.len(), remote_len(url, target)?))
+}
+
+fn update_instance(app: &AppHandle, instance: &crate::Instance, force: bool) -> Result<(), String> {
+    crate::minecraft::launch_logic::sync_instance_content(
+        &PathBuf::from(crate::get_instances_dir()),
+        &PathBuf::from(&instance.path).join("minecraft"),
+        instance.mods.clone(),
+        instance.modpack_url.clone(),
+        instance.translation_pack_url.as_deref(),
+        force,
+        &Some(app.clone()),
+        &instance.id,
+        None,
+    )
+}
+
+fn check_all(app: &AppHandle, data_saver: bool) {
+    for instance in crate::load_instances() {
+        let Some(url) = instance.modpack_url.as_deref().filter(|u| !u.is_empty()) else { continue; };
+        let zip_path = PathBuf::from(&instance.path).join("minecraft").join("modpack.zip");
+        match modpack_update_available(url, &Target::of(&instance), &zip_path) {
+            Ok(true) => {}
+            Ok(false) => continue,
+            Err(e) => {
+                eprintln!("[updater] {}: {}", instance.name, e);
+                continue;
+            }
+        }
+        let _ = app.emit("instance_update_available", UpdateAvailable {
+            instance_id: instance.id.clone(),
+            name: instance.name.clone(),
+            deferred: data_saver,
+        });
+        // On a metered connection the update waits for the next launch or a manual verify
+        if data_saver {
+            continue;
+        }
+        let Some(active) = try_mark_updating(app, &instance.id) else { continue };
+        let result = update_instance(app, &instance, false);
+        drop(active);
+        crate::notifications::notify_completion(app, &instance.name, "Actualización", &result);
+        let _ = app.emit("instance_update_applied", UpdateApplied {
+            instance_id: instance.id.clone(),
+            name: instance.name.clone(),
+            error: result.err(),
+        });
+    }
+}
+
+/// Re-downloads the pack of every remote-managed instance (or just `only`) right away, skipping
+/// instances being played. Returns how many were synced.
+pub fn force_resync(app: &AppHandle, only: Option<&str>) -> usize {
+    let mut synced = 0;
+    for instance in crate::load_instances() {
+        if only.map(|id| id != instance.id).unwrap_or(false) || instance.modpack_url.as_deref().unwrap_or_default().is_empty() {
+            continue;
+        }
+        let Some(active) = try_mark_updating(app, &instance.id) else { continue };
+        let result = update_instance(app, &instance, true);
+        drop(active);
+        if result.is_ok() {
+            synced += 1;
+        }
+        let _ = app.emit("instance_update_applied", UpdateApplied {
+            instance_id: instance.id.clone(),
+            name: instance.name.clone(),
+            error: result.err(),
+        });
+    }
+    synced
+}
+
+fn remote_instance(instance_id: &str) -> Result<(crate::Instance, String), String> {
+    let instance = crate::load_instances().into_iter().find(|i| i.id == instance_id)
+        .ok_or("Instance not found")?;
+    let url = instance.modpack_url.clone().filter(|u| !u.is_empty())
+        .ok_or("La instancia no tiene un modpack remoto")?;
+    Ok((instance, url))
+}
+
+/// What updating `instance_id` to the pack currently online would change, without touching it.
+#[tauri::command]
+pub async fn preview_modpack_update(instance_id: String) -> Result<ModpackDiff, String> {
+    crate::run_blocking(move || {
+        let (instance, url) = remote_instance(&instance_id)?;
+        // Individual mods are downloaded again after the extraction
+        let kept: Vec<String> = instance.mods.iter().flatten().map(|url| format!("mods/{}", mod_file_name(url))).collect();
+        crate::minecraft::modpack::diff_modpack(&url, &PathBuf::from(&instance.path).join("minecraft"), &kept)
+    }).await
+}
+
+/// Installs the pack reviewed with `preview_modpack_update`.
+#[tauri::command]
+pub async fn apply_modpack_update(app: AppHandle, instance_id: String) -> Result<(), String> {
+    crate::run_blocking(move || {
+        let (instance, _) = remote_instance(&instance_id)?;
+        let _active = try_mark_updating(&app, &instance.id)
+            .ok_or("La instancia está en uso; ciérrala antes de actualizar")?;
+        // The preview read the online pack; a cached zip of the same size would be extracted instead
+        let _ = fs::remove_file(PathBuf::from(&instance.path).join("minecraft").join("modpack.zip"));
+        let result = update_instance(&app, &instance, true);
+        let _ = app.emit("instance_update_applied", UpdateApplied {
+            instance_id: instance.id.clone(),
+            name: instance.name.clone(),
+            error: result.as_ref().err().cloned(),
+        });
+        result
+    }).await
+}
+
+/// Checks every remote-managed instance on the configured interval for the lifetime of the app.
+pub fn start(app: AppHandle) {
+    std::thread::spawn(move || {
+        std::thread::sleep(STARTUP_DELAY);
+        loop {
+            let settings = load_settings();
+            if settings.updater.enabled {
+                check_all(&app, settings.data_saver);
+            }
+            std::thread::sleep(settings.updater.interval());
+        }
+    });
+}
+
+#[tauri::command]
+pub fn get_updater_settings() -> UpdaterSettings {
+    load_settings().updater
+}
+
+#[tauri::command]
+pub fn set_updater_settings(updater: UpdaterSettings) -> Result<(), String> {
+    ensure_not_guest()?;
+    let mut settings = load_settings();
+    settings.updater = updater;
+    save_settings(&settings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn overlapping_holds_keep_the_instance_active() {
+        let active = ActiveInstances::default();
+        assert!(active.hold("evento", false, |_| true));
+        assert!(active.hold("evento", false, |_| true));
+        active.release("evento", false);
+        // The game still runs after a scheduled verify ended
+        assert!(!active.hold("evento", true, |holds| holds.total == 0));
+        active.release("evento", false);
+        assert!(active.hold("evento", true, |holds| holds.total == 0));
+        assert!(!active.hold("evento", false, |holds| holds.updates == 0));
+        active.release("evento", true);
+        assert!(active.0.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn unknown_remote_size_is_not_an_update() {
+        assert!(size_changed(1024, Some(2048)));
+        assert!(!size_changed(1024, Some(1024)));
+        assert!(!size_changed(1024, None));
+    }
+
+    #[test]
+    fn interval_has_a_floor() {
+        let settings = UpdaterSettings { enabled: true, interval_minutes: 1 };
+        assert_eq!(settings.interval(), Duration::from_secs(15 * 60));
+        assert_eq!(UpdaterSettings::default().interval(), Duration::from_secs(3600));
+    }
+}
@@ -1,80 +1,170 @@
 import { useState, useEffect } from "react";
-import { invoke } from "@tauri-apps/api/core";
-import { listen } from "@tauri-apps/api/event";
 import Login from "./pages/Login/Login";
 import OfflineLogin from "./pages/OfflineLogin/OfflineLogin";
-import Home from "./pages/Home/Home";
+import Home, { LaunchProgressState } from "./pages/Home/Home";
 import Sidebar from "./components/Sidebar/Sidebar";
 import CreateInstance, { InstanceData } from "./pages/CreateInstance/CreateInstance";
 import AdminLogin from "./components/AdminLogin/AdminLogin";
 import ConfirmModal from "./components/ConfirmModal/ConfirmModal";
-import CrashModal from "./components/CrashModal/CrashModal";
+import CrashModal, { CrashSuggestion, ExitCodeInfo, SafeModeOffer } from "./components/CrashModal/CrashModal";
+import { instanceImageSrc } from "./utils/instanceImages";
+import {
+  invokeCommand,
+  listenEvent,
+  type AccessibilityPrefs,
+  type Announcement,
+  type Instance as SavedInstance,
+  type JvmPreset,
+  type LinuxTweaks,
+  type Maintenance,
+  type ModpackDiff,
+  type PlatformInfo,
+  type RemovalStatus,
+  type StartupAuthState,
+  type StatusReport,
+  type UndoAvailable,
+} from "./api/types.generated";
 import "./App.css";
 
-interface Instance {
-  id: string;
-  name: string;
-  version: string;
+type Card = { image?: string; title?: string; content?: string };
+
+// Lo que el backend deja sin valor llega como null; en la interfaz se trata como ausente
+type Defined<T> = { [K in keyof T]: Exclude<T[K], null> };
+
+// La instancia de `get_instances` como la muestran las páginas: imagen resuelta y tarjetas en camelCase
+type Instance = Defined<Omit<SavedInstance, "lastPlayed" | "eventCard" | "statsCard" | "infoCard" | "resolution_width" | "resolution_height">> & {
   lastPlayed: string;
-  icon: string;
-  path: string;
   image?: string;
-  images?: string[];
-  description?: string;
-  ram?: number;
-  serverIp?: string;
-  serverName?: string;
-  modpackUrl?: string;
-  mods?: any[];
-  launcher?: string;
-  // Recuadros de Noticias (Nuevo diseño)
-  newsLeft?: {
-    image?: string;
-    title?: string;
-    content?: string;
-  };
-  newsCenter?: {
-    image?: string;
-    title?: string;
-    content?: string;
-  };
-  newsRight?: {
-    image?: string;
-    title?: string;
-    content?: string;
-  };
-  // Legacy fields (kept for backward compatibility but might be unused in new layout)
-  eventCard?: { // Izquierda
-    image?: string;
-    title?: string;
-    content?: string;
-    date?: string;
-    // Legacy fields
-    eventName?: string;
-    rewards?: string;
-  };
-  statsCard?: { // Centro
-    image?: string;
-    title?: string;
-    content?: string;
-    // Server specific fields
-    playersOnline?: number;
-    latency?: number;
-    status?: string;
-  };
-  infoCard?: { // Derecha
-    image?: string;
-    title?: string;
-    content?: string;
-    // Info specific fields
-    modsInstalled?: number;
-    lastUpdate?: string;
-  };
-  modloader?: string;
   resolutionWidth?: number;
   resolutionHeight?: number;
+  eventCard?: Card & { date?: string; eventName?: string; rewards?: string };
+  statsCard?: Card & { playersOnline?: number; latency?: number; status?: string };
+  infoCard?: Card & { modsInstalled?: number; lastUpdate?: string; packVersion?: string };
+};
+
+function defined<T>(value: T | null | undefined): T | undefined {
+  return value ?? undefined;
 }
 
+function toInstance(saved: SavedInstance): Instance {
+  const { eventCard, statsCard, infoCard } = saved;
+  return {
+    id: saved.id,
+    name: saved.name,
+    version: saved.version,
+    lastPlayed: saved.lastPlayed ?? new Date().toISOString(),
+    icon: saved.icon || "default",
+    path: saved.path,
+    image: (saved.images && saved.images.length > 0 ? instanceImageSrc(saved.id, saved.images[0], true) : undefined) || `https://api.dicebear.com/7.x/shapes/svg?seed=${saved.name}`,
+    images: defined(saved.images),
+    description: defined(saved.description),
+    ram: defined(saved.ram),
+    serverIp: defined(saved.serverIp),
+    serverName: defined(saved.serverName),
+    modpackUrl: defined(saved.modpackUrl),
+    mods: defined(saved.mods),
+    launcher: defined(saved.launcher),
+    modloader: defined(saved.modloader),
+    eventCard: eventCard ? {
+      image: defined(eventCard.image),
+      title: defined(eventCard.title),
+      eventName: defined(eventCard.event_name),
+      date: defined(eventCard.date),
+      rewards: defined(eventCard.rewards),
+    } : undefined,
+    statsCard: statsCard ? {
+      image: defined(statsCard.image),
+      playersOnline: defined(statsCard.players_online),
+      latency: defined(statsCard.latency),
+      status: defined(statsCard.status),
+    } : undefined,
+    infoCard: infoCard ? {
+      image: defined(infoCard.image),
+      modsInstalled: defined(infoCard.mods_installed),
+      lastUpdate: defined(infoCard.last_update),
+      packVersion: defined(infoCard.pack_version),
+    } : undefined,
+    resolutionWidth: defined(saved.resolution_width),
+    resolutionHeight: defined(saved.resolution_height),
+    trackLatestPatch: defined(saved.trackLatestPatch),
+    gamepadProfile: defined(saved.gamepadProfile),
+    linuxTweaks: defined(saved.linuxTweaks),
+    gameLanguage: defined(saved.gameLanguage),
+    translationPackUrl: defined(saved.translationPackUrl),
+    singleplayerOnly: defined(saved.singleplayerOnly),
+    jvmPreset: defined(saved.jvmPreset),
+    notes: defined(saved.notes),
+  };
+}
+
+// Lo que recibe `save_instance`; las notas se guardan aparte (set_instance_notes)
+function toSavedInstance(instance: Instance): SavedInstance {
+  const { eventCard, statsCard, infoCard } = instance;
+  return {
+    id: instance.id,
+    name: instance.name,
+    version: instance.version,
+    lastPlayed: instance.lastPlayed,
+    icon: instance.icon,
+    path: instance.path,
+    images: instance.images,
+    description: instance.description,
+    ram: instance.ram,
+    serverIp: instance.serverIp,
+    serverName: instance.serverName,
+    modpackUrl: instance.modpackUrl,
+    mods: instance.mods,
+    launcher: instance.launcher,
+    modloader: instance.modloader,
+    eventCard: eventCard ? {
+      image: eventCard.image,
+      title: eventCard.title,
+      event_name: eventCard.eventName,
+      date: eventCard.date,
+      rewards: eventCard.rewards,
+    } : undefined,
+    statsCard: statsCard ? {
+      image: statsCard.image,
+      players_online: statsCard.playersOnline,
+      latency: statsCard.latency,
+      status: statsCard.status,
+    } : undefined,
+    infoCard: infoCard ? {
+      image: infoCard.image,
+      mods_installed: infoCard.modsInstalled,
+      last_update: infoCard.lastUpdate,
+      pack_version: infoCard.packVersion,
+    } : undefined,
+    resolution_width: instance.resolutionWidth,
+    resolution_height: instance.resolutionHeight,
+    trackLatestPatch: instance.trackLatestPatch,
+    gamepadProfile: instance.gamepadProfile,
+    linuxTweaks: instance.linuxTweaks,
+    gameLanguage: instance.gameLanguage,
+    translationPackUrl: instance.translationPackUrl,
+    singleplayerOnly: instance.singleplayerOnly,
+    jvmPreset: instance.jvmPreset,
+  };
+}
+
+// Hasta que responda get_platform_info
+const IS_LINUX = navigator.userAgent.includes("Linux");
+const LARGE_DOWNLOAD_BYTES = 20 * 1024 * 1024;
+// Instancias cuyo aviso de drivers ya se mostró en esta sesión
+const graphicsWarned = new Set<string>();
+
+const ANTIVIRUS_MARKER = "[antivirus]";
+// Launcher más antiguo que la versión mínima (ver service_status::OUTDATED_MARKER)
+const OUTDATED_MARKER = "[launcher-outdated]";
+// Códigos de error de modpack (ver minecraft::modpack::archive_error y host_pins::PIN_MARKER)
+const MODPACK_ERROR_TITLES: Record<string, string> = {
+  "[modpack:encrypted]": "Modpack protegido con contraseña",
+  "[modpack:corrupt]": "Modpack incompleto o dañado",
+  "[modpack:unsupported]": "Formato de modpack no soportado",
+  "[host-pin]": "Certificado del servidor del modpack cambiado",
+};
+
+
 interface SavedAccount {
   username: string;
   type: "microsoft" | "offline";
@@ -94,14 +184,20 @@ interface InstanceSettingsDraft {
   image?: string;
   resolutionWidth?: number;
   resolutionHeight?: number;
+  trackLatestPatch?: boolean;
+  linuxTweaks?: LinuxTweaks;
+  gameLanguage?: string;
+  translationPackUrl?: string;
+  singleplayerOnly?: boolean;
+  jvmPreset?: string;
 }
 
 function App() {
   const [instances, setInstances] = useState<Instance[]>([]);
   const [selectedInstance, setSelectedInstance] = useState<Instance | null>(null);
   const [isLaunching, setIsLaunching] = useState(false);
-  const [launchProgress, setLaunchProgress] = useState<{ percent: number; stage: string; message: string } | null>(null);
-  const [unlistenProgress, setUnlistenProgress] = useState<() => void | undefined>();
+  const [launchProgress, setLaunchProgress] = useState<LaunchProgressState | null>(null);
+  const [unlistenProgress, setUnlistenProgress] = useState<(() => void) | undefined>();
   const [launchingInstanceId, setLaunchingInstanceId] = useState<string | null>(null);
   const [isDownloading, setIsDownloading] = useState(false);
   const [lastLaunchDurationMs, setLastLaunchDurationMs] = useState<number | null>(null);
@@ -111,10 +207,15 @@ function App() {
   const [isLoggedIn, setIsLoggedIn] = useState(false);
   const [showOfflineLogin, setShowOfflineLogin] = useState(false);
   const [showCreateInstance, setShowCreateInstance] = useState(false);
+  const [createModpackUrl, setCreateModpackUrl] = useState<string | undefined>(undefined);
   const [isLoginLoading, setIsLoginLoading] = useState(false);
   const [username, setUsername] = useState("");
   const [userAvatar, setUserAvatar] = useState("");
   const [settingsInstance, setSettingsInstance] = useState<Instance | null>(null);
+  const [safeModeStep, setSafeModeStep] = useState<string | null>(null);
+  const [gameLanguages, setGameLanguages] = useState<string[]>([]);
+  const [platform, setPlatform] = useState<PlatformInfo | null>(null);
+  const [jvmPresets, setJvmPresets] = useState<JvmPreset[]>([]);
   const [settingsDraft, setSettingsDraft] = useState<InstanceSettingsDraft | null>(null);
   const [advancedInstance, setAdvancedInstance] = useState<Instance | null>(null);
   const [advancedDraft, setAdvancedDraft] = useState<InstanceSettingsDraft | null>(null);
@@ -138,11 +239,180 @@ function App() {
     isOpen: boolean;
     error: string;
     code: number;
+    instanceId?: string;
+    suggestions?: CrashSuggestion[];
+    logUrl?: string | null;
+    autoUpload?: boolean | null;
+    safeMode?: SafeModeOffer | null;
+    exitInfo?: ExitCodeInfo | null;
   }>({
     isOpen: false,
     error: "",
     code: 0,
   });
+  const [announcements, setAnnouncements] = useState<Announcement[]>([]);
+  const [maintenance, setMaintenance] = useState<Maintenance[]>([]);
+  const [serviceStatus, setServiceStatus] = useState<StatusReport | null>(null);
+  const [removals, setRemovals] = useState<RemovalStatus[]>([]);
+  const [undoable, setUndoable] = useState<UndoAvailable | null>(null);
+  const [nowSecs, setNowSecs] = useState(() => Math.floor(Date.now() / 1000));
+
+  // Mantenimiento (local, comando remoto o feed del staff); mientras dure no se puede jugar
+  useEffect(() => {
+    invokeCommand("get_maintenance").then(setMaintenance).catch(console.error);
+    const unlisten = listenEvent("maintenance", (event) => setMaintenance(event.payload));
+    const tick = setInterval(() => setNowSecs(Math.floor(Date.now() / 1000)), 15000);
+    return () => {
+      unlisten.then((fn) => fn());
+      clearInterval(tick);
+    };
+  }, []);
+
+  // Página de estado pública: avisos, problemas conocidos y versión mínima del launcher
+  useEffect(() => {
+    invokeCommand("get_service_status").then(setServiceStatus).catch(console.error);
+    const unlisten = listenEvent("status_changed", (event) => setServiceStatus(event.payload));
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
+  // Ajustes de accesibilidad del sistema: sin animaciones y alto contraste
+  useEffect(() => {
+    const apply = (prefs: AccessibilityPrefs) => {
+      document.documentElement.classList.toggle("reduce-motion", prefs.reducedMotion);
+      document.documentElement.classList.toggle("high-contrast", prefs.highContrast);
+    };
+    invokeCommand("get_accessibility_prefs").then(apply).catch(console.error);
+    const unlisten = listenEvent("accessibility_changed", (event) => apply(event.payload));
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
+  // Mundos dañados tras un cierre con error: ofrecer restaurar antes de volver a entrar
+  useEffect(() => {
+    const unlisten = listenEvent("save_corruption", async (event) => {
+      const { instanceId, worlds } = event.payload;
+      for (const world of worlds) {
+        const problems = world.problems.map((p) => `• ${p.file}: ${p.detail}`).join("\n");
+        const header = `El mundo "${world.folder}" parece dañado tras el cierre:\n${problems}\n\nNo se ha subido a la nube para no sobrescribir la copia buena.`;
+        const source = world.levelDatOld
+          ? "levelDatOld"
+          : world.backups.length > 0
+            ? { backup: { file: world.backups[0] } }
+            : null;
+        if (source === null) {
+          alert(`${header}\n\nNo hay copias de seguridad de este mundo.`);
+          continue;
+        }
+        const what = source === "levelDatOld" ? "la copia automática de level.dat" : `la copia de seguridad ${world.backups[0]}`;
+        if (!window.confirm(`${header}\n\n¿Restaurar ${what}? El mundo dañado se guarda antes en backups/worlds.`)) continue;
+        try {
+          await invokeCommand("restore_world", { instanceId, folder: world.folder, source });
+          alert(`Mundo "${world.folder}" restaurado.`);
+        } catch (error) {
+          alert(`No se pudo restaurar el mundo: ${error}`);
+        }
+      }
+    });
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
+  const activeMaintenance = maintenance.find((m) =>
+    (m.until === undefined || m.until === null || m.until > nowSecs) &&
+    (!m.instanceIds?.length || (selectedInstance !== null && m.instanceIds.includes(selectedInstance.id)))
+  );
+
+  // Borrados en segundo plano (instancias, cachés); terminados se quitan a los pocos segundos
+  useEffect(() => {
+    const unlisten = listenEvent("removal_progress", (event) => {
+      const removal = event.payload;
+      setRemovals((prev) => [...prev.filter((r) => r.jobId !== removal.jobId), removal]);
+      if (removal.state === "error") {
+        alert(`No se pudo borrar ${removal.label}: ${removal.error}`);
+      }
+      if (removal.state !== "running") {
+        setTimeout(() => setRemovals((prev) => prev.filter((r) => r.jobId !== removal.jobId)), 4000);
+      }
+    });
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
+  // Cada sincronización recalcula mods, versión del pack y fecha de la tarjeta
+  useEffect(() => {
+    const unlisten = listenEvent("instance_card_updated", () => loadInstances());
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
+  // Borrados y limpiezas se pueden deshacer durante unos minutos; el aviso se oculta antes
+  useEffect(() => {
+    const unlisten = listenEvent("undo_available", (event) => setUndoable(event.payload));
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
+  useEffect(() => {
+    if (!undoable) return;
+    const timeout = setTimeout(() => setUndoable(null), 15000);
+    return () => clearTimeout(timeout);
+  }, [undoable]);
+
+  // Si el webview se recargó a mitad de una preparación, retomar el progreso donde iba
+  useEffect(() => {
+    let unlisten: (() => void) | undefined;
+    let cancelled = false;
+    invokeCommand("get_active_progress").then(async (snapshots) => {
+      const snapshot = snapshots.sort((a, b) => b.updatedAt - a.updatedAt)[0];
+      if (!snapshot || cancelled) return;
+      const p = snapshot.event;
+      const instanceId = p.instanceId;
+      setIsLaunching(true);
+      setLaunchingInstanceId(instanceId);
+      setLaunchProgress(p.stage === "iniciado"
+        ? { percent: 100, stage: "iniciado", message: "Jugando..." }
+        : { percent: p.percent ?? 0, stage: p.stage, message: p.message ?? "" });
+      unlisten = await listenEvent("launch_progress", (event) => {
+        const e = event.payload;
+        if (!e || e.instanceId !== instanceId) return;
+        if (e.stage === "cerrado" || e.stage === "crasheado" || e.stage === "error" || e.stage === "descarga_completa") {
+          setIsLaunching(false);
+          setLaunchingInstanceId(null);
+          setLaunchProgress(null);
+          unlisten?.();
+          return;
+        }
+        setLaunchProgress(e.stage === "iniciado"
+          ? { percent: 100, stage: "iniciado", message: "Jugando..." }
+          : { percent: e.percent, stage: e.stage, message: e.message, extraction: e.extraction });
+      });
+    }).catch(console.error);
+    return () => {
+      cancelled = true;
+      unlisten?.();
+    };
+  }, []);
+
+  // Avisos del staff durante el evento (pack actualizado, reinicio del servidor)
+  useEffect(() => {
+    const unlisten = listenEvent("announcement", (event) => {
+      const announcement = event.payload;
+      setAnnouncements((prev) => [...prev.slice(-2), announcement]);
+      setTimeout(() => {
+        setAnnouncements((prev) => prev.filter((a) => a !== announcement));
+      }, 10000);
+    });
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
 
   useEffect(() => {
     // Verificar sesión guardada
@@ -160,14 +430,42 @@ function App() {
         setUsername(savedUsername);
         // Restore offline session in Rust
         if (authType === "offline") {
-          invoke("start_offline_login", { username: savedUsername }).catch(console.error);
+          invokeCommand("start_offline_login", { username: savedUsername }).catch(console.error);
         }
       }
       if (savedAvatar) setUserAvatar(savedAvatar);
       loadInstances();
+    } else {
+      // El backend restaura la última cuenta usada en segundo plano: puede estar lista ya o llegar después
+      let restored = false;
+      const applyStartup = (startup: StartupAuthState) => {
+        if (restored || !startup.loggedIn || !startup.profile) return;
+        restored = true;
+        setIsLoggedIn(true);
+        setIsOfflineMode(startup.authType === "offline");
+        setUsername(startup.profile.name);
+        localStorage.setItem("drk_launcher_logged_in", "true");
+        localStorage.setItem("drk_launcher_auth_type", startup.authType || "msa");
+        localStorage.setItem("drk_launcher_username", startup.profile.name);
+        loadInstances();
+      };
+      const unlisten = listenEvent("session_restored", (event) => applyStartup(event.payload));
+      invokeCommand("get_startup_auth_state").then(applyStartup).catch(console.error);
+      return () => {
+        unlisten.then((fn) => fn());
+      };
     }
   }, []);
 
+  useEffect(() => {
+    invokeCommand("get_platform_info").then(setPlatform).catch(console.error);
+    invokeCommand("get_jvm_presets").then(setJvmPresets).catch(console.error);
+  }, []);
+
+  const isLinux = platform ? platform.os === "linux" : IS_LINUX;
+  const maxRamMb = platform?.maxRamMb;
+  const selectedJvmPreset = jvmPresets.find((p) => p.id === (settingsDraft?.jvmPreset || "balanced"));
+
   // Bloquear menú contextual (click derecho) globalmente si no es admin
   useEffect(() => {
     const handleContextMenu = (e: MouseEvent) => {
@@ -179,6 +477,107 @@ function App() {
     return () => document.removeEventListener("contextmenu", handleContextMenu);
   }, [isAdmin]);
 
+  // Precargar metadatos (manifest, loader, modpack) mientras se ve el detalle de la instancia
+  useEffect(() => {
+    if (!selectedInstance) {
+      return;
+    }
+    invokeCommand("prefetch_instance", { instanceId: selectedInstance.id }).catch(console.error);
+  }, [selectedInstance?.id]);
+
+  // Los archivos soltados sobre la ventana (.mrpack, zip de CurseForge, mods .jar) se instalan en la instancia seleccionada
+  useEffect(() => {
+    invokeCommand("set_drop_target", { instanceId: selectedInstance?.id ?? null }).catch(console.error);
+  }, [selectedInstance?.id]);
+
+  useEffect(() => {
+    const unlistenDrop = listenEvent("drop_install_progress", (event) => {
+      const { message, done, error } = event.payload;
+      if (!done) {
+        return;
+      }
+      if (error) {
+        alert(`No se pudo instalar el archivo: ${error}`);
+      } else {
+        alert(message);
+      }
+    });
+    return () => {
+      unlistenDrop.then((unlisten) => unlisten());
+    };
+  }, []);
+
+  // Atajo global de teclado: el backend lanza la última instancia jugada, aquí se refleja el progreso
+  useEffect(() => {
+    const unlistenHotkey = listenEvent("hotkey_launch", async (event) => {
+      const { instanceId, name, error } = event.payload;
+      if (error) {
+        setIsLaunching(false);
+        setLaunchingInstanceId(null);
+        setLaunchProgress(null);
+        alert(`No se pudo lanzar ${name}: ${error}`);
+        return;
+      }
+      const instance = instances.find((inst) => inst.id === instanceId);
+      if (instance) {
+        setSelectedInstance(instance);
+      }
+      setIsLaunching(true);
+      setLaunchingInstanceId(instanceId);
+      setLaunchProgress({ percent: 0, stage: "iniciando", message: `Lanzando ${name} (atajo de teclado)` });
+      const unlistenProgress = await listenEvent("launch_progress", (progress) => {
+        const p = progress.payload;
+        if (!p || p.instanceId !== instanceId) return;
+        setLaunchProgress({ percent: p.percent, stage: p.stage, message: p.message });
+        if (p.stage === "cerrado" || p.stage === "crasheado" || p.stage === "error") {
+          setIsLaunching(false);
+          setLaunchingInstanceId(null);
+          setLaunchProgress(null);
+          unlistenProgress();
+        }
+      });
+    });
+    return () => {
+      unlistenHotkey.then((unlisten) => unlisten());
+    };
+  }, [instances]);
+
+  // Instancias que siguen el último parche: ofrecer la actualización antes de jugar
+  useEffect(() => {
+    if (!selectedInstance?.trackLatestPatch) {
+      return;
+    }
+    const instance = selectedInstance;
+    invokeCommand("check_instance_update", { instanceId: instance.id })
+      .then(async (update) => {
+        if (!update || !update.compatible) {
+          return;
+        }
+        if (!window.confirm(`Hay un nuevo parche para ${instance.name}: ${update.current} → ${update.latest}. ¿Actualizar y preparar la instancia?`)) {
+          return;
+        }
+        if (!(await prepareWorldsForVersion(instance, update.latest))) {
+          return;
+        }
+        await invokeCommand("apply_instance_update", { instanceId: instance.id, version: update.latest });
+        const updated = { ...instance, version: update.latest };
+        setInstances((prev) => prev.map((inst) => (inst.id === updated.id ? updated : inst)));
+        setSelectedInstance(updated);
+        await handleDownloadInstance(updated);
+      })
+      .catch(console.error);
+  }, [selectedInstance?.id]);
+
+  useEffect(() => {
+    setSafeModeStep(null);
+    if (!settingsInstance) {
+      return;
+    }
+    invokeCommand("get_safe_mode", { instanceId: settingsInstance.id })
+      .then((state) => setSafeModeStep(state.step ?? null))
+      .catch(console.error);
+  }, [settingsInstance?.id]);
+
   useEffect(() => {
     const handler = (event: KeyboardEvent) => {
       if (!event.ctrlKey || !event.shiftKey || event.key.toLowerCase() !== "a") {
@@ -203,6 +602,9 @@ function App() {
         modloader: targetInstance.modloader,
         launcher: targetInstance.launcher,
         image: targetInstance.image,
+        trackLatestPatch: targetInstance.trackLatestPatch,
+        translationPackUrl: targetInstance.translationPackUrl,
+        singleplayerOnly: targetInstance.singleplayerOnly,
       });
     };
     window.addEventListener("keydown", handler);
@@ -211,49 +613,44 @@ function App() {
 
   async function loadInstances() {
     try {
-      const instancesData = await invoke<any[]>("get_instances");
+      const instancesData = await invokeCommand("get_instances");
       // Mapear campos de snake_case a camelCase
-      const instancesWithImages = instancesData.map((instance: any) => ({
-        id: instance.id,
-        name: instance.name,
-        version: instance.version,
-        lastPlayed: instance.last_played || instance.lastPlayed || new Date().toISOString(),
-        icon: instance.icon || "default",
-        path: instance.path || "",
-        image: instance.image || (instance.images && instance.images.length > 0 ? instance.images[0] : undefined) || `https://api.dicebear.com/7.x/shapes/svg?seed=${instance.name}`,
-        images: instance.images,
-        description: instance.description,
-        ram: instance.ram,
-        serverIp: instance.server_ip || instance.serverIp,
-        serverName: instance.server_name || instance.serverName,
-        modpackUrl: instance.modpack_url || instance.modpackUrl,
-        mods: instance.mods,
-        launcher: instance.launcher,
-        newsLeft: instance.news_left || instance.newsLeft,
-        newsCenter: instance.news_center || instance.newsCenter,
-        newsRight: instance.news_right || instance.newsRight,
-        eventCard: instance.event_card ? {
-          image: instance.event_card.image,
-          eventName: instance.event_card.event_name || instance.event_card.eventName,
-          date: instance.event_card.date,
-          rewards: instance.event_card.rewards,
-        } : instance.eventCard,
-        statsCard: instance.stats_card ? {
-          image: instance.stats_card.image,
-          playersOnline: instance.stats_card.players_online || instance.stats_card.playersOnline,
-          latency: instance.stats_card.latency,
-          status: instance.stats_card.status,
-        } : instance.statsCard,
-        infoCard: instance.info_card ? {
-          image: instance.info_card.image,
-          modsInstalled: instance.info_card.mods_installed || instance.info_card.modsInstalled,
-          lastUpdate: instance.info_card.last_update || instance.info_card.lastUpdate,
-        } : instance.infoCard,
-        modloader: instance.modloader,
-        resolutionWidth: instance.resolution_width || instance.resolutionWidth,
-        resolutionHeight: instance.resolution_height || instance.resolutionHeight,
-      }));
+      const instancesWithImages = instancesData.map(toInstance);
       setInstances(instancesWithImages);
+
+      // Puestos del evento (--kiosk): verificar y entrar al servidor a pantalla completa, sin preguntas
+      const pendingKiosk = await invokeCommand("take_pending_kiosk").catch(() => null);
+      const kioskInstance = instancesWithImages.find((inst) => inst.id === pendingKiosk);
+      if (kioskInstance) {
+        setSelectedInstance(kioskInstance);
+        launchInstance(kioskInstance, true);
+        return;
+      }
+
+      // Accesos directos (escritorio/Steam) abren el launcher con una instancia para jugar
+      const pendingLaunch = await invokeCommand("take_pending_launch").catch(() => null);
+      const pendingInstance = instancesWithImages.find((inst) => inst.id === pendingLaunch);
+      if (pendingInstance) {
+        setSelectedInstance(pendingInstance);
+        handleExecuteInstance(pendingInstance);
+        return;
+      }
+
+      // Archivos .mrpack/.zip y enlaces drk:// abiertos con el launcher
+      const pendingOpen = await invokeCommand("take_pending_open").catch(() => null);
+      if (pendingOpen?.kind === "modpack") {
+        setCreateModpackUrl(pendingOpen.path);
+        setShowCreateInstance(true);
+      } else if (pendingOpen?.kind === "shareCode") {
+        importFromCode(pendingOpen.code);
+      } else if (pendingOpen?.kind === "launch") {
+        const linked = instancesWithImages.find((inst) => inst.id === pendingOpen.instanceId);
+        if (linked) {
+          setSelectedInstance(linked);
+          handleExecuteInstance(linked);
+          return;
+        }
+      }
       
       // NO seleccionar automáticamente - mostrar pantalla de bienvenida
     } catch (error) {
@@ -286,12 +683,12 @@ function App() {
   async function handleMicrosoftLogin() {
     setIsLoginLoading(true);
     try {
-      await invoke("start_microsoft_login");
+      await invokeCommand("start_microsoft_login");
       
       // Poll for login success
       const interval = setInterval(async () => {
         try {
-          const profile: any = await invoke("get_auth_profile");
+          const profile = await invokeCommand("get_auth_profile");
           if (profile) {
             clearInterval(interval);
             setIsLoggedIn(true);
@@ -322,7 +719,7 @@ function App() {
     setIsLoginLoading(true);
 
     try {
-      await invoke("start_offline_login", { username });
+      await invokeCommand("start_offline_login", { username });
       
       if (isLogin) {
         // Login
@@ -373,7 +770,7 @@ function App() {
     
     if (account.type === "offline") {
       try {
-        await invoke("start_offline_login", { username: account.username });
+        await invokeCommand("start_offline_login", { username: account.username });
       } catch (e) {
         console.error("Quick login failed", e);
       }
@@ -395,7 +792,7 @@ function App() {
 
   async function handleAdminLogin(password: string) {
     try {
-      const isValid = await invoke<boolean>("check_admin_password", { password });
+      const isValid = await invokeCommand("check_admin_password", { password });
       if (isValid) {
         setIsAdmin(true);
         localStorage.setItem("drk_launcher_admin", "true");
@@ -409,6 +806,18 @@ function App() {
     }
   }
 
+  async function handleAdminSetup(code: string, password: string) {
+    await invokeCommand("complete_admin_setup", { code, password });
+    setIsAdmin(true);
+    localStorage.setItem("drk_launcher_admin", "true");
+  }
+
+  async function handleAdminReset(token: string, password: string) {
+    await invokeCommand("reset_admin_password", { token, password });
+    setIsAdmin(true);
+    localStorage.setItem("drk_launcher_admin", "true");
+  }
+
   function handleLogout() {
     setIsLoggedIn(false);
     setIsOfflineMode(false);
@@ -423,22 +832,105 @@ function App() {
     localStorage.removeItem("drk_launcher_auth_type");
   }
 
-  async function launchInstance(instance: Instance) {
+  // Modo ahorro de datos: las descargas grandes se confirman con el tamaño estimado
+  async function confirmLargeDownload(instance: Instance): Promise<boolean> {
+    const dataSaver = await invokeCommand("get_data_saver").catch(() => false);
+    if (!dataSaver) {
+      return true;
+    }
+    try {
+      const estimate = await invokeCommand("estimate_instance_download", { instanceId: instance.id });
+      if (estimate.totalBytes < LARGE_DOWNLOAD_BYTES && estimate.unknown.length === 0) {
+        return true;
+      }
+      const megabytes = Math.ceil(estimate.totalBytes / (1024 * 1024));
+      const unknown = estimate.unknown.length > 0 ? ` más ${estimate.unknown.join(", ")} de tamaño desconocido` : "";
+      return window.confirm(`Ahorro de datos: ${instance.name} necesita descargar unos ${megabytes} MB${unknown}. ¿Continuar?`);
+    } catch {
+      return true;
+    }
+  }
+
+  // Driver antiguo o gráfica dudosa: avisar una vez por sesión antes del primer lanzamiento
+  async function confirmGraphics(instance: Instance): Promise<boolean> {
+    if (graphicsWarned.has(instance.id)) {
+      return true;
+    }
+    const check = await invokeCommand("check_graphics", { instanceId: instance.id }).catch(() => null);
+    // "unsupported" lo rechaza launch_instance con el mensaje completo
+    if (!check || check.status !== "warning") {
+      return true;
+    }
+    graphicsWarned.add(instance.id);
+    const links = check.links.map((l) => `Driver de ${l.vendor}: ${l.url}`).join("\n");
+    return window.confirm(`Posible problema con la tarjeta gráfica:\n${check.problems.join("\n")}\n\n${links}\n\n¿Jugar de todas formas?`);
+  }
+
+  // Servidor del evento lleno: avisar y esperar turno en vez de entrar y ser expulsado
+  async function waitForServerSlot(instance: Instance): Promise<boolean> {
+    const status = await invokeCommand("check_server_capacity", { instanceId: instance.id }).catch(() => null);
+    if (!status || status.max === 0 || status.online < status.max) {
+      return true;
+    }
+    if (!window.confirm(`El servidor está lleno (${status.online}/${status.max}). ¿Esperar turno? Entrarás en cuanto haya sitio.`)) {
+      return false;
+    }
+    setIsLaunching(true);
+    setLaunchingInstanceId(instance.id);
+    setLaunchProgress({ percent: 0, stage: "cola", message: "Esperando un hueco en el servidor..." });
+    const unlisten = await listenEvent("server_queue", (event) => {
+      const q = event.payload;
+      if (q.instanceId !== instance.id) return;
+      setLaunchProgress({
+        percent: 0,
+        stage: "cola",
+        message: q.error ? `Servidor sin respuesta, reintentando...` : `En cola: posición ${q.position} (${q.online}/${q.max} jugadores)`,
+      });
+    });
+    try {
+      return await invokeCommand("wait_for_server_slot", { instanceId: instance.id });
+    } catch (error) {
+      console.error(error);
+      return true;
+    } finally {
+      unlisten();
+    }
+  }
+
+  async function launchInstance(instance: Instance, kiosk = false) {
     if (launchingInstanceId && launchingInstanceId !== instance.id) {
       return;
     }
+    if (!kiosk && !(await confirmLargeDownload(instance))) {
+      return;
+    }
+    if (!kiosk && !(await confirmGraphics(instance))) {
+      return;
+    }
+    if (!kiosk && !(await waitForServerSlot(instance))) {
+      setIsLaunching(false);
+      setLaunchingInstanceId(null);
+      setLaunchProgress(null);
+      return;
+    }
     const startTime = Date.now();
     setLastLaunchDurationMs(null);
     setIsLaunching(true);
     setLaunchingInstanceId(instance.id);
     try {
       if (unlistenProgress) {
-        try { (unlistenProgress as any)(); } catch {}
+        try { unlistenProgress(); } catch {}
       }
-      const unlisten = await listen("launch_progress", (event: any) => {
+      // Los eventos llegan agrupados; `seq` descarta uno retrasado que ya quedó viejo
+      let lastSeq = 0;
+      const unlisten = await listenEvent("launch_progress", (event) => {
         const p = event.payload;
         if (!p || p.instanceId !== instance.id) return;
-        setLaunchProgress({ percent: p.percent, stage: p.stage, message: p.message });
+        if (typeof p.seq === "number") {
+          if (p.seq < lastSeq) return;
+          lastSeq = p.seq;
+        }
+        setLaunchProgress({ percent: p.percent, stage: p.stage, message: p.message, extraction: p.extraction });
         if (p.stage === "iniciado") {
           setLastLaunchDurationMs(Date.now() - startTime);
           setLaunchProgress({ percent: 100, stage: "iniciado", message: "Jugando..." });
@@ -449,9 +941,13 @@ function App() {
           setLaunchProgress(null);
           setPlayFlowInstanceId(null);
           if (unlistenProgress) {
-            try { (unlistenProgress as any)(); } catch {}
+            try { unlistenProgress(); } catch {}
           }
-          if (p.message && p.stage !== "cerrado") {
+          // Puntos del evento devueltos por el servidor de recompensas
+          if (p.reward?.message) {
+            alert(`Resultados del evento: ${p.reward.message}`);
+          }
+          if (p.message && p.stage !== "cerrado" && !showAntivirusHelp(p.message) && !showModpackErrorHelp(p.message)) {
             // Use a more friendly modal or just alert for now, but ensure newlines are preserved
             console.error("Game crash details:", p.message);
             // Parse code from message if possible (format: "El juego se cerró con error (Código: 1). Detalles: ...")
@@ -470,28 +966,26 @@ function App() {
             setCrashData({
               isOpen: true,
               error: details,
-              code: code
+              code: code,
+              instanceId: instance.id,
+              logUrl: p.logUrl,
             });
+            invokeCommand("get_crash_upload")
+              .then((value) => value as { enabled: boolean; available: boolean })
+              .then((upload) => setCrashData((prev) => ({ ...prev, autoUpload: upload.available ? upload.enabled : null })))
+              .catch(console.error);
+            // Memoria insuficiente o pausas largas del GC: ofrecer arreglos de un clic
+            invokeCommand("analyze_crash", { instanceId: instance.id })
+              .then((analysis) => setCrashData((prev) => (prev.instanceId === instance.id ? { ...prev, suggestions: analysis.suggestions, safeMode: analysis.safeMode ?? null, exitInfo: analysis.exitCode ?? null } : prev)))
+              .catch(console.error);
           }
         }
       });
       setUnlistenProgress(() => unlisten);
-      // Verificar si los mods están instalados
-      if (instance.mods && instance.mods.length > 0) {
-        // Aquí se verificaría si los mods están instalados
-        // Si no, se descargarían automáticamente
-        console.log("Verificando mods...", instance.mods);
-      }
-
-      // Verificar launcher disponible
-      if (instance.launcher) {
-        console.log("Usando launcher:", instance.launcher);
-      }
-
       // Lanzar instancia (todos juegan de la misma forma)
-      await invoke("launch_instance", { instanceId: instance.id });
+      await invokeCommand(kiosk ? "kiosk_start" : "launch_instance", { instanceId: instance.id });
       
-      // Actualizar última vez jugado
+      // El backend guarda lastPlayed cuando el juego arranca de verdad; aquí solo se refleja en la interfaz
       const timestamp = new Date().toISOString();
       const updatedInstance = { ...instance, lastPlayed: timestamp };
       setSelectedInstance(updatedInstance);
@@ -499,64 +993,83 @@ function App() {
         inst.id === instance.id ? updatedInstance : inst
       );
       setInstances(updatedInstances);
-      
-      // Guardar persistentemente (convertir a snake_case para Rust y evitar duplicados)
-      try {
-        const instanceForRust: any = {
-          id: updatedInstance.id,
-          name: updatedInstance.name,
-          version: updatedInstance.version,
-          last_played: timestamp,
-          icon: updatedInstance.icon,
-          path: updatedInstance.path,
-          image: updatedInstance.image,
-          images: updatedInstance.images,
-          description: updatedInstance.description,
-          ram: updatedInstance.ram,
-          server_ip: updatedInstance.serverIp,
-          server_name: updatedInstance.serverName,
-          modpack_url: updatedInstance.modpackUrl,
-          mods: updatedInstance.mods,
-          launcher: updatedInstance.launcher,
-          event_card: updatedInstance.eventCard ? {
-            image: updatedInstance.eventCard.image,
-            event_name: updatedInstance.eventCard.eventName,
-            date: updatedInstance.eventCard.date,
-            rewards: updatedInstance.eventCard.rewards,
-          } : undefined,
-          stats_card: updatedInstance.statsCard ? {
-            image: updatedInstance.statsCard.image,
-            players_online: updatedInstance.statsCard.playersOnline,
-            latency: updatedInstance.statsCard.latency,
-            status: updatedInstance.statsCard.status,
-          } : undefined,
-          info_card: updatedInstance.infoCard ? {
-            image: updatedInstance.infoCard.image,
-            mods_installed: updatedInstance.infoCard.modsInstalled,
-            last_update: updatedInstance.infoCard.lastUpdate,
-          } : undefined,
-          modloader: updatedInstance.modloader,
-          resolution_width: updatedInstance.resolutionWidth,
-          resolution_height: updatedInstance.resolutionHeight,
-        };
-        await invoke("save_instance", { instance: instanceForRust });
-        localStorage.setItem("drk_instances", JSON.stringify(updatedInstances));
-      } catch (error) {
-        console.error("Error saving instance:", error);
-      }
+      localStorage.setItem("drk_instances", JSON.stringify(updatedInstances));
     } catch (error) {
       console.error("Error launching instance:", error);
-      alert(`Error al lanzar la instancia: ${error}`);
+      if (!showUpdatePrompt(String(error))) {
+        alert(`Error al lanzar la instancia: ${error}`);
+      }
       setIsLaunching(false);
       setLaunchingInstanceId(null);
     } finally {}
   }
 
+  // Recarga las instancias guardadas en el backend y selecciona `selectId`
+  async function reloadInstances(selectId: string): Promise<Instance | undefined> {
+    const savedInstances = await invokeCommand("get_instances");
+    const instancesWithImages = savedInstances.map(toInstance);
+    
+    setInstances(instancesWithImages);
+    
+    // Encontrar la instancia recién creada
+    const createdInstance = instancesWithImages.find(inst => inst.id === selectId);
+    if (createdInstance) {
+      setSelectedInstance(createdInstance);
+    }
+    
+    // Guardar también en localStorage como backup
+    try {
+      localStorage.setItem("drk_instances", JSON.stringify(instancesWithImages));
+    } catch (e) {
+      console.warn("Could not save to localStorage (Quota Exceeded?):", e);
+    }
+    return createdInstance;
+  }
+
+  // Código o enlace que el staff publica: crea la instancia completa (modpack, tarjetas...)
+  async function handleImportCode() {
+    const code = window.prompt("Pega el código o enlace de la instancia:");
+    if (!code || !code.trim()) return;
+    await importFromCode(code);
+  }
+
+  async function importFromCode(code: string) {
+    try {
+      const imported = await invokeCommand("import_instance_from_code", { code: code.trim() });
+      await reloadInstances(imported.id);
+    } catch (error) {
+      console.error("Error importing instance:", error);
+      alert(`No se pudo importar la instancia: ${error}`);
+    }
+  }
+
+  // Genera el código de la instancia seleccionada (y un enlace corto si hay servidor de códigos)
+  async function handleShareInstance() {
+    if (!selectedInstance) {
+      alert("Selecciona primero la instancia que quieres compartir.");
+      return;
+    }
+    const password = window.prompt("Contraseña de administrador:");
+    if (!password) return;
+    try {
+      const share = await invokeCommand("export_instance_code", {
+        password,
+        instanceId: selectedInstance.id,
+        upload: true,
+      });
+      const text = share.link || share.code;
+      await navigator.clipboard.writeText(text).catch(() => {});
+      alert(`Código de "${selectedInstance.name}" copiado al portapapeles:\n\n${text}`);
+    } catch (error) {
+      console.error("Error sharing instance:", error);
+      alert(`No se pudo generar el código: ${error}`);
+    }
+  }
+
   async function handleCreateInstance(instanceData: InstanceData) {
-    const instanceId = Date.now().toString();
     const timestamp = new Date().toISOString();
       const newInstance: Instance = {
-        id: instanceId,
+        id: "", // el backend asigna el id definitivo
         name: instanceData.name,
         version: instanceData.version,
         lastPlayed: timestamp,
@@ -581,72 +1094,42 @@ function App() {
     
     try {
       // Guardar en Rust backend (creará las carpetas automáticamente y actualizará el path)
-      await invoke("save_instance", { instance: newInstance });
+      // El backend asigna el id definitivo (UUID) y valida el nombre
+      const saved = await invokeCommand("save_instance", { instance: toSavedInstance(newInstance) });
       
       // Recargar instancias desde el backend para obtener el path correcto
-      const savedInstances = await invoke<any[]>("get_instances");
-      const instancesWithImages = savedInstances.map((instance: any) => ({
-        id: instance.id,
-        name: instance.name,
-        version: instance.version,
-        lastPlayed: instance.last_played || instance.lastPlayed || new Date().toISOString(),
-        icon: instance.icon || "default",
-        path: instance.path || "",
-        image: instance.image || (instance.images && instance.images.length > 0 ? instance.images[0] : undefined) || `https://api.dicebear.com/7.x/shapes/svg?seed=${instance.name}`,
-        images: instance.images,
-        description: instance.description,
-        ram: instance.ram,
-        serverIp: instance.server_ip || instance.serverIp,
-        serverName: instance.server_name || instance.serverName,
-        modpackUrl: instance.modpack_url || instance.modpackUrl,
-        mods: instance.mods,
-        launcher: instance.launcher,
-        modloader: instance.modloader,
-        eventCard: instance.event_card ? {
-          image: instance.event_card.image,
-          eventName: instance.event_card.event_name || instance.event_card.eventName,
-          date: instance.event_card.date,
-          rewards: instance.event_card.rewards,
-        } : instance.eventCard,
-        statsCard: instance.stats_card ? {
-          image: instance.stats_card.image,
-          playersOnline: instance.stats_card.players_online || instance.stats_card.playersOnline,
-          latency: instance.stats_card.latency,
-          status: instance.stats_card.status,
-        } : instance.statsCard,
-        infoCard: instance.info_card ? {
-          image: instance.info_card.image,
-          modsInstalled: instance.info_card.mods_installed || instance.info_card.modsInstalled,
-          lastUpdate: instance.info_card.last_update || instance.info_card.lastUpdate,
-        } : instance.infoCard,
-        resolutionWidth: instance.resolution_width || instance.resolutionWidth,
-        resolutionHeight: instance.resolution_height || instance.resolutionHeight,
-      }));
-      
-      setInstances(instancesWithImages);
-      
-      // Encontrar la instancia recién creada
-      const createdInstance = instancesWithImages.find(inst => inst.id === instanceId);
-      if (createdInstance) {
-        setSelectedInstance(createdInstance);
-      }
-      
-      // Guardar también en localStorage como backup
-      try {
-        localStorage.setItem("drk_instances", JSON.stringify(instancesWithImages));
-      } catch (e) {
-        console.warn("Could not save to localStorage (Quota Exceeded?):", e);
-      }
+      await reloadInstances(saved.id);
       
       setShowCreateInstance(false);
-      
-      console.log("Instancia creada exitosamente:", createdInstance);
+      setCreateModpackUrl(undefined);
     } catch (error) {
       console.error("Error creating instance:", error);
       alert(`Error al crear la instancia: ${error}. Por favor intenta de nuevo.`);
     }
   }
 
+  // Antes de cambiar de versión: bloquear downgrades de mundos y ofrecer copia de seguridad al subir
+  async function prepareWorldsForVersion(instance: Instance, newVersion: string): Promise<boolean> {
+    try {
+      const plan = await invokeCommand("get_world_migration", {
+        instanceId: instance.id,
+        newVersion,
+      });
+      if (plan.blockedWorlds.length > 0) {
+        alert(`No se puede cambiar a ${newVersion}: los mundos ${plan.blockedWorlds.join(", ")} se guardaron con una versión más nueva y se corromperían.`);
+        return false;
+      }
+      if (plan.upgrade && plan.worlds.length > 0 && window.confirm(`Se encontraron ${plan.worlds.length} mundo(s). ¿Crear una copia de seguridad antes de actualizar a ${newVersion}?`)) {
+        await invokeCommand("backup_instance_worlds", { instanceId: instance.id });
+      }
+    } catch (error) {
+      console.error("Error checking worlds:", error);
+      alert(`No se pudo preparar la copia de los mundos: ${error}`);
+      return false;
+    }
+    return true;
+  }
+
   function handleSettings(instance: Instance) {
     setSettingsInstance(instance);
     setSettingsDraft({
@@ -662,7 +1145,14 @@ function App() {
       image: instance.image,
       resolutionWidth: instance.resolutionWidth || 854,
       resolutionHeight: instance.resolutionHeight || 480,
+      linuxTweaks: instance.linuxTweaks,
+      gameLanguage: instance.gameLanguage,
+      jvmPreset: instance.jvmPreset,
     });
+    setGameLanguages([]);
+    invokeCommand("get_game_languages", { version: instance.version })
+      .then(setGameLanguages)
+      .catch(() => setGameLanguages([]));
   }
 
   async function handleDownloadInstance(instance: Instance, isPlayFlow = false) {
@@ -670,10 +1160,13 @@ function App() {
       if (launchingInstanceId && launchingInstanceId !== instance.id) {
         return false;
       }
+      if (!(await confirmLargeDownload(instance))) {
+        return false;
+      }
       setIsDownloading(true);
       setLaunchingInstanceId(instance.id);
       if (unlistenProgress) {
-        try { (unlistenProgress as any)(); } catch {}
+        try { unlistenProgress(); } catch {}
       }
       return await new Promise<boolean>(async (resolve) => {
         let finished = false;
@@ -688,17 +1181,17 @@ function App() {
           setUnlistenProgress(undefined);
           resolve(ok);
         };
-        const unlisten = await listen("launch_progress", (event: any) => {
+        const unlisten = await listenEvent("launch_progress", (event) => {
           const p = event.payload;
           if (!p || p.instanceId !== instance.id) return;
-          setLaunchProgress({ percent: p.percent, stage: p.stage, message: p.message });
+          setLaunchProgress({ percent: p.percent, stage: p.stage, message: p.message, extraction: p.extraction });
           if (p.stage === "descarga_completa") {
-            try { (unlisten as any)(); } catch {}
+            try { unlisten(); } catch {}
             finalize(true);
           }
           if (p.stage === "error") {
-            try { (unlisten as any)(); } catch {}
-            if (p.message) {
+            try { unlisten(); } catch {}
+            if (p.message && !showAntivirusHelp(p.message) && !showModpackErrorHelp(p.message)) {
               alert(p.message);
             }
             if (isPlayFlow) {
@@ -709,11 +1202,13 @@ function App() {
         });
         setUnlistenProgress(() => unlisten);
         try {
-          await invoke("prepare_instance", { instanceId: instance.id });
+          await invokeCommand("prepare_instance", { instanceId: instance.id });
         } catch (error) {
           console.error("Error downloading instance:", error);
-          alert("Error al descargar la instancia");
-          try { (unlisten as any)(); } catch {}
+          if (!showAntivirusHelp(String(error)) && !showModpackErrorHelp(String(error))) {
+            alert("Error al descargar la instancia");
+          }
+          try { unlisten(); } catch {}
           if (isPlayFlow) {
             setPlayFlowInstanceId(null);
           }
@@ -739,7 +1234,7 @@ function App() {
       // Check if instance is ready to skip download/verify flow if possible
       let isReady = false;
       try {
-          isReady = await invoke<boolean>("check_instance_ready", { instanceId: instance.id });
+          isReady = (await invokeCommand("check_instance_ready", { instanceId: instance.id })).ready;
       } catch (e) {
           console.error("Failed to check instance ready state", e);
       }
@@ -762,16 +1257,112 @@ function App() {
     }
   }
 
+  // Errores de archivo de modpack con código: se explica el problema sin el código
+  function showModpackErrorHelp(message: string): boolean {
+    const code = Object.keys(MODPACK_ERROR_TITLES).find((c) => message.startsWith(c));
+    if (!code) return false;
+    alert(`${MODPACK_ERROR_TITLES[code]}\n\n${message.slice(code.length).trim()}`);
+    return true;
+  }
+
+  // Launcher desactualizado para esta instancia: se ofrece descargar la versión nueva
+  function showUpdatePrompt(message: string): boolean {
+    if (!message.startsWith(OUTDATED_MARKER)) return false;
+    const text = message.slice(OUTDATED_MARKER.length).trim();
+    const downloadUrl = serviceStatus?.status.downloadUrl;
+    if (!downloadUrl) {
+      alert(`${text}\n\nPide al staff la versión nueva del launcher.`);
+      return true;
+    }
+    setConfirmModal({
+      isOpen: true,
+      title: "Actualización necesaria",
+      message: text,
+      confirmText: "Descargar actualización",
+      onConfirm: () => {
+        setConfirmModal(prev => ({ ...prev, isOpen: false }));
+        window.open(downloadUrl, "_blank");
+      },
+    });
+    return true;
+  }
+
+  // Errores marcados por el backend como bloqueo del antivirus: se muestran instrucciones en lugar del error genérico
+  function showAntivirusHelp(message: string): boolean {
+    if (!message.startsWith(ANTIVIRUS_MARKER)) return false;
+    invokeCommand("get_antivirus_guidance")
+      .then((guidance) => {
+        const steps = guidance.products.map((p) => `${p.name}: ${p.steps}`).join("\n\n");
+        setConfirmModal({
+          isOpen: true,
+          title: "El antivirus está bloqueando archivos",
+          message: `${message.slice(ANTIVIRUS_MARKER.length).trim()}\n\nCarpeta a excluir: ${guidance.exclusionPath}\n\n${steps}`,
+          confirmText: "Reverificar archivos",
+          onConfirm: async () => {
+            setConfirmModal(prev => ({ ...prev, isOpen: false }));
+            try {
+              const stillBlocked = await invokeCommand("reverify_blocked_files");
+              alert(stillBlocked.length === 0
+                ? "Archivos verificados correctamente. Ya puedes volver a jugar."
+                : `Siguen bloqueados:\n${stillBlocked.join("\n")}`);
+            } catch (error) {
+              alert(`No se pudieron reverificar los archivos: ${error}`);
+            }
+          },
+        });
+      })
+      .catch(() => alert(message));
+    return true;
+  }
+
+  // Muestra qué cambiaría el modpack publicado antes de instalarlo
+  async function reviewModpackUpdate(instance: Instance) {
+    let diff: ModpackDiff;
+    try {
+      diff = await invokeCommand("preview_modpack_update", { instanceId: instance.id });
+    } catch (error) {
+      alert(`No se pudo revisar el modpack: ${error}`);
+      return;
+    }
+    if (diff.added.length + diff.changed.length + diff.removed.length === 0) {
+      alert("La instancia ya tiene el modpack publicado.");
+      return;
+    }
+    const list = (title: string, paths: string[]) =>
+      paths.length === 0 ? "" : `\n\n${title} (${paths.length}):\n${paths.slice(0, 15).join("\n")}${paths.length > 15 ? "\n…" : ""}`;
+    setConfirmModal({
+      isOpen: true,
+      title: "Actualización del modpack",
+      message: `Descarga: ${(diff.downloadSize / 1024 / 1024).toFixed(1)} MB · sin cambios: ${diff.unchanged}` +
+        list("Nuevos", diff.added.map((f) => f.path)) +
+        list("Modificados", diff.changed.map((f) => f.path)) +
+        list("Eliminados", diff.removed),
+      confirmText: "Actualizar",
+      onConfirm: async () => {
+        setConfirmModal(prev => ({ ...prev, isOpen: false }));
+        try {
+          await invokeCommand("apply_modpack_update", { instanceId: instance.id });
+          alert("Modpack actualizado.");
+        } catch (error) {
+          alert(`No se pudo actualizar el modpack: ${error}`);
+        }
+      },
+    });
+  }
+
   async function handleDeleteInstance(instanceId: string) {
+    const trash = await invokeCommand("get_trash_settings").catch(() => null);
     setConfirmModal({
       isOpen: true,
       title: "Eliminar Instancia",
-      message: "¿Estás seguro de que quieres eliminar esta instancia? Esta acción no se puede deshacer y se perderán todos los datos asociados.",
+      message: trash?.enabled
+        ? `La instancia se moverá a la papelera y podrás restaurarla durante ${trash.retentionDays} días. Después se borrará para siempre.`
+        : "¿Estás seguro de que quieres eliminar esta instancia? Podrás deshacerlo durante unos minutos; después se perderán todos los datos asociados.",
       isDanger: true,
       confirmText: "Eliminar",
       onConfirm: async () => {
         try {
-          await invoke("delete_instance", { instanceId });
+          await invokeCommand("delete_instance", { instanceId });
           setInstances(prev => prev.filter(i => i.id !== instanceId));
           if (selectedInstance?.id === instanceId) {
             setSelectedInstance(null);
@@ -779,12 +1370,22 @@ function App() {
           setConfirmModal(prev => ({ ...prev, isOpen: false }));
         } catch (error) {
           console.error("Error deleting instance:", error);
-          alert("Error al eliminar la instancia");
+          alert(`Error al eliminar la instancia: ${error}`);
         }
       }
     });
   }
 
+  async function handleUndo() {
+    setUndoable(null);
+    try {
+      await invokeCommand("undo_last_operation");
+      await loadInstances();
+    } catch (error) {
+      alert(`No se pudo deshacer: ${error}`);
+    }
+  }
+
   // Mostrar pantalla de login offline
   if (showOfflineLogin) {
     return (
@@ -824,6 +1425,8 @@ function App() {
         selectedInstance={selectedInstance}
         onSelectInstance={setSelectedInstance}
         onCreateInstance={() => setShowCreateInstance(true)}
+        onImportCode={handleImportCode}
+        onShareInstance={handleShareInstance}
         isOfflineMode={isOfflineMode}
         onLogout={handleLogout}
         userAvatar={userAvatar}
@@ -846,10 +1449,15 @@ function App() {
         onDownloadInstance={handleDownloadInstance}
         onExecuteInstance={handleExecuteInstance}
         onHome={() => setSelectedInstance(null)}
+        onCancelQueue={(instance) => invokeCommand("cancel_server_queue", { instanceId: instance.id }).catch(console.error)}
       />
       {showCreateInstance && (
         <CreateInstance
-          onClose={() => setShowCreateInstance(false)}
+          initialModpackUrl={createModpackUrl}
+          onClose={() => {
+            setShowCreateInstance(false);
+            setCreateModpackUrl(undefined);
+          }}
           onSave={handleCreateInstance}
         />
       )}
@@ -857,6 +1465,8 @@ function App() {
         <AdminLogin
           onClose={() => setShowAdminLogin(false)}
           onLogin={handleAdminLogin}
+          onSetup={handleAdminSetup}
+          onReset={handleAdminReset}
         />
       )}
       <ConfirmModal
@@ -868,11 +1478,117 @@ function App() {
         isDanger={confirmModal.isDanger}
         confirmText={confirmModal.confirmText}
       />
+      <div className="top-banners">
+        {activeMaintenance && (
+          <div className="maintenance-banner">
+            Mantenimiento: {activeMaintenance.message || "el servidor se está reiniciando"}
+            {activeMaintenance.until && ` · quedan ${Math.max(1, Math.ceil((activeMaintenance.until - nowSecs) / 60))} min`}
+          </div>
+        )}
+        {serviceStatus?.outdated && (
+          <div className="maintenance-banner">
+            Esta versión del launcher ({serviceStatus.launcherVersion}) ya no es compatible; actualiza a la {serviceStatus.status.minLauncherVersion} o posterior para jugar
+            {serviceStatus.status.downloadUrl && (
+              <a href={serviceStatus.status.downloadUrl} target="_blank" rel="noreferrer"> · Descargar</a>
+            )}
+          </div>
+        )}
+        {serviceStatus?.status.maintenance?.map((notice, i) => (
+          <div key={`maintenance-${i}`} className="status-banner">
+            {notice.title ? `${notice.title}: ` : "Mantenimiento: "}{notice.message}
+            {notice.url && <a href={notice.url} target="_blank" rel="noreferrer"> · Más info</a>}
+          </div>
+        ))}
+        {serviceStatus?.status.knownIssues?.map((notice, i) => (
+          <div key={`issue-${i}`} className="status-banner status-banner-issue">
+            {notice.title ? `${notice.title}: ` : "Problema conocido: "}{notice.message}
+            {notice.url && <a href={notice.url} target="_blank" rel="noreferrer"> · Más info</a>}
+          </div>
+        ))}
+      </div>
+      {(removals.length > 0 || undoable) && (
+        <div className="removal-list">
+          {undoable && (
+            <div className="removal-item">
+              <span>
+                {undoable.kind === "delete_instance" ? `${undoable.label} eliminada` : `${undoable.label} borrados`}
+              </span>
+              <button onClick={handleUndo}>Deshacer</button>
+            </div>
+          )}
+          {removals.map((removal) => {
+            const { doneBytes, totalBytes } = removal.progress;
+            const percent = totalBytes > 0 ? Math.round((doneBytes / totalBytes) * 100) : 100;
+            return (
+              <div key={removal.jobId} className={`removal-item removal-${removal.state}`}>
+                <span>
+                  {removal.state === "running" && `Borrando ${removal.label}... ${percent}% (${(doneBytes / 1048576).toFixed(0)} / ${(totalBytes / 1048576).toFixed(0)} MB)`}
+                  {removal.state === "done" && `${removal.label} borrado`}
+                  {removal.state === "cancelled" && `Borrado de ${removal.label} cancelado`}
+                  {removal.state === "error" && `Error al borrar ${removal.label}`}
+                </span>
+                <div className="removal-bar"><div style={{ width: `${percent}%` }} /></div>
+                {removal.state === "running" && (
+                  <button onClick={() => invokeCommand("cancel_removal", { jobId: removal.jobId })}>Cancelar</button>
+                )}
+              </div>
+            );
+          })}
+        </div>
+      )}
+      {announcements.length > 0 && (
+        <div className="announcement-toasts">
+          {announcements.map((announcement) => (
+            <div
+              key={`${announcement.receivedAt}-${announcement.message}`}
+              className={`announcement-toast announcement-${announcement.type}`}
+              onClick={() => setAnnouncements((prev) => prev.filter((a) => a !== announcement))}
+            >
+              {announcement.title && <strong>{announcement.title}</strong>}
+              <span>{announcement.message}</span>
+            </div>
+          ))}
+        </div>
+      )}
       <CrashModal
         isOpen={crashData.isOpen}
         onClose={() => setCrashData(prev => ({ ...prev, isOpen: false }))}
         error={crashData.error}
         code={crashData.code}
+        suggestions={crashData.suggestions}
+        logUrl={crashData.logUrl}
+        autoUpload={crashData.autoUpload}
+        onToggleAutoUpload={(enabled) => {
+          invokeCommand("set_crash_upload", { enabled })
+            .then(() => setCrashData((prev) => ({ ...prev, autoUpload: enabled })))
+            .catch((error) => alert(`No se pudo guardar la preferencia: ${error}`));
+        }}
+        safeMode={crashData.safeMode}
+        exitInfo={crashData.exitInfo}
+        onSafeMode={async (offer) => {
+          const instance = instances.find((inst) => inst.id === crashData.instanceId);
+          if (!instance) return;
+          try {
+            await invokeCommand("start_safe_mode", { instanceId: instance.id, step: offer.step });
+            setCrashData((prev) => ({ ...prev, isOpen: false }));
+            await launchInstance(instance);
+          } catch (error) {
+            alert(`No se pudo activar el modo seguro: ${error}`);
+          }
+        }}
+        onApplySuggestion={async (suggestion) => {
+          if (!crashData.instanceId) return;
+          try {
+            const warnings = await invokeCommand("apply_crash_suggestion", { instanceId: crashData.instanceId, suggestion });
+            if (warnings.length > 0) {
+              alert(warnings.join("\n"));
+            }
+            await loadInstances();
+            setCrashData((prev) => ({ ...prev, suggestions: prev.suggestions?.filter((s) => s !== suggestion) }));
+          } catch (error) {
+            alert(`No se pudo aplicar la sugerencia: ${error}`);
+          }
+        }}
       />
       {settingsInstance && settingsDraft && (
         <div className="instance-settings-overlay" onClick={() => setSettingsInstance(null)}>
@@ -890,6 +1606,28 @@ function App() {
                   <div style={{ whiteSpace: "pre-wrap" }}>{launchProgress.message}</div>
                 </div>
               )}
+              {safeModeStep && (
+                <div style={{ marginBottom: 10, padding: 10, background: "#2b2f3a", border: "1px solid #444", borderRadius: 6, color: "#e5e7eb" }}>
+                  <div style={{ fontWeight: 600, marginBottom: 6 }}>Modo seguro activo</div>
+                  <div style={{ marginBottom: 8 }}>La instancia arranca con gráficos bajos{safeModeStep !== "lowerSettings" ? " y algunos mods desactivados" : ""}.</div>
+                  <button
+                    className="instance-settings-cancel"
+                    onClick={async () => {
+                      try {
+                        const warnings = await invokeCommand("exit_safe_mode", { instanceId: settingsInstance.id });
+                        if (warnings.length > 0) {
+                          alert(warnings.join("\n"));
+                        }
+                        setSafeModeStep(null);
+                      } catch (error) {
+                        alert(`No se pudo salir del modo seguro: ${error}`);
+                      }
+                    }}
+                  >
+                    Salir del modo seguro
+                  </button>
+                </div>
+              )}
               <div className="instance-settings-grid">
                 <div className="instance-settings-readonly">
                   <span>Nombre</span>
@@ -903,9 +1641,18 @@ function App() {
                   <label>RAM (MB)</label>
                   <input
                     type="number"
+                    max={maxRamMb}
                     value={settingsDraft.ram || 0}
                     onChange={(e) => setSettingsDraft({ ...settingsDraft, ram: Number(e.target.value) })}
                   />
+                  {platform && (
+                    <span className="instance-settings-hint">
+                      {platform.is64Bit
+                        ? `Este equipo tiene ${platform.totalRamMb} MB`
+                        : `Sistema de 32 bits: máximo ${platform.maxRamMb} MB`}
+                      {platform.availableDiskBytes != null && ` · ${(platform.availableDiskBytes / 1024 ** 3).toFixed(1)} GB libres`}
+                    </span>
+                  )}
                 </div>
                 <div className="instance-settings-field">
                   <label>Resolución</label>
@@ -926,6 +1673,59 @@ function App() {
                     />
                   </div>
                 </div>
+                <div className="instance-settings-field">
+                  <label>Idioma del juego</label>
+                  <select
+                    value={settingsDraft.gameLanguage || ""}
+                    onChange={(e) => setSettingsDraft({ ...settingsDraft, gameLanguage: e.target.value || undefined })}
+                  >
+                    <option value="">Por defecto del juego</option>
+                    {settingsDraft.gameLanguage && !gameLanguages.includes(settingsDraft.gameLanguage) && (
+                      <option value={settingsDraft.gameLanguage}>{settingsDraft.gameLanguage}</option>
+                    )}
+                    {gameLanguages.map((code) => (
+                      <option key={code} value={code}>{code}</option>
+                    ))}
+                  </select>
+                </div>
+                <div className="instance-settings-field">
+                  <label>Argumentos de Java</label>
+                  <select
+                    value={settingsDraft.jvmPreset || "balanced"}
+                    onChange={(e) => setSettingsDraft({ ...settingsDraft, jvmPreset: e.target.value })}
+                  >
+                    {jvmPresets.map((preset) => (
+                      <option key={preset.id} value={preset.id}>{preset.name}</option>
+                    ))}
+                  </select>
+                  {selectedJvmPreset && <span className="instance-settings-hint">{selectedJvmPreset.description}</span>}
+                </div>
+                {isLinux && (
+                  <div className="instance-settings-field">
+                    <label>Linux</label>
+                    {([
+                      ["forceX11", "Forzar X11 (XWayland)"],
+                      ["nvidiaThreadedOptimizations", "Optimizaciones en hilos de NVIDIA"],
+                      ["mesaGlthread", "mesa_glthread (AMD/Intel)"],
+                      ["primeOffload", "Usar la GPU dedicada (PRIME)"],
+                    ] as const).map(([key, label]) => (
+                      <label key={key} style={{ display: 'flex', gap: '0.5rem', alignItems: 'center' }}>
+                        <input
+                          type="checkbox"
+                          checked={!!settingsDraft.linuxTweaks?.[key]}
+                          onChange={(e) => setSettingsDraft({ ...settingsDraft, linuxTweaks: { ...settingsDraft.linuxTweaks, [key]: e.target.checked } })}
+                        />
+                        {label}
+                      </label>
+                    ))}
+                    <input
+                      type="text"
+                      placeholder="Ruta de libglfw.so (opcional)"
+                      value={settingsDraft.linuxTweaks?.glfwLibname || ""}
+                      onChange={(e) => setSettingsDraft({ ...settingsDraft, linuxTweaks: { ...settingsDraft.linuxTweaks, glfwLibname: e.target.value || undefined } })}
+                    />
+                  </div>
+                )}
               </div>
             </div>
             <div className="instance-settings-actions">
@@ -933,7 +1733,7 @@ function App() {
                 className="instance-settings-cancel"
                 onClick={() => {
                   const path = settingsInstance.path;
-                  invoke("open_folder", { path }).catch(() => alert("No se pudo abrir la carpeta de la instancia"));
+                  invokeCommand("open_folder", { path }).catch(() => alert("No se pudo abrir la carpeta de la instancia"));
                 }}
               >
                 Ver Instancia
@@ -941,12 +1741,201 @@ function App() {
               <button
                 className="instance-settings-cancel"
                 onClick={() => {
-                  const path = settingsInstance.path + "\\logs";
-                  invoke("open_folder", { path }).catch(() => alert("No se pudo abrir la carpeta de logs"));
+                  invokeCommand("open_instance_folder", { instanceId: settingsInstance.id, which: "logs" }).catch(() => alert("No se pudo abrir la carpeta de logs"));
                 }}
               >
                 Ver Logs
               </button>
+              <button
+                className="instance-settings-cancel"
+                onClick={() => {
+                  invokeCommand("open_instance_folder", { instanceId: settingsInstance.id, which: "mods" }).catch(() => alert("No se pudo abrir la carpeta de mods"));
+                }}
+              >
+                Ver Mods
+              </button>
+              <button
+                className="instance-settings-cancel"
+                onClick={() => {
+                  invokeCommand("open_instance_folder", { instanceId: settingsInstance.id, which: "screenshots" }).catch(() => alert("No se pudo abrir la carpeta de capturas"));
+                }}
+              >
+                Ver Capturas
+              </button>
+              <button
+                className="instance-settings-cancel"
+                onClick={async () => {
+                  const steamShortcut = window.confirm("¿Añadir también un acceso directo en Steam (Modo Juego)?");
+                  try {
+                    const result = await invokeCommand("apply_gamepad_profile", {
+                      instanceId: settingsInstance.id,
+                      steamShortcut,
+                    });
+                    if (result.warnings.length > 0) {
+                      alert(result.warnings.join("\n"));
+                    }
+                    await loadInstances();
+                    setSettingsInstance(null);
+                  } catch (error) {
+                    alert(`No se pudo aplicar el perfil de mando: ${error}`);
+                  }
+                }}
+              >
+                Perfil Steam Deck / mando
+              </button>
+              <button
+                className="instance-settings-cancel"
+                onClick={async () => {
+                  if (!window.confirm("Se reducirá la RAM, se instalarán mods de rendimiento, se usarán gráficos rápidos y los argumentos de Java para PC de bajos recursos. ¿Continuar?")) {
+                    return;
+                  }
+                  try {
+                    const result = await invokeCommand("optimize_instance", {
+                      instanceId: settingsInstance.id,
+                      profile: "low-end",
+                    });
+                    if (result.warnings.length > 0) {
+                      alert(result.warnings.join("\n"));
+                    }
+                    await loadInstances();
+                    setSettingsInstance(null);
+                  } catch (error) {
+                    alert(`No se pudo optimizar la instancia: ${error}`);
+                  }
+                }}
+              >
+                Modo PC de bajos recursos
+              </button>
+              {settingsInstance.modpackUrl && (
+                <button
+                  className="instance-settings-cancel"
+                  onClick={() => reviewModpackUpdate(settingsInstance)}
+                >
+                  Revisar actualización del modpack
+                </button>
+              )}
+              <button
+                className="instance-settings-cancel"
+                onClick={async () => {
+                  const notes = window.prompt("Notas visibles para los jugadores:", settingsInstance.notes ?? "");
+                  if (notes === null) return;
+                  const password = window.prompt("Contraseña de administrador:");
+                  if (!password) return;
+                  try {
+                    await invokeCommand("set_instance_notes", { password, instanceId: settingsInstance.id, notes });
+                    await loadInstances();
+                    setSettingsInstance(null);
+                  } catch (error) {
+                    alert(`No se pudieron guardar las notas: ${error}`);
+                  }
+                }}
+              >
+                Notas de la instancia
+              </button>
+              <button
+                className="instance-settings-cancel"
+                onClick={async () => {
+                  const password = window.prompt("Contraseña de administrador:");
+                  if (!password) return;
+                  try {
+                    const current = await invokeCommand("get_instance_admin_notes", { password, instanceId: settingsInstance.id });
+                    const notes = window.prompt("Notas de staff (solo administradores):", current ?? "");
+                    if (notes === null) return;
+                    await invokeCommand("set_instance_admin_notes", { password, instanceId: settingsInstance.id, notes });
+                  } catch (error) {
+                    alert(`No se pudieron guardar las notas de staff: ${error}`);
+                  }
+                }}
+              >
+                Notas de staff
+              </button>
+              <button
+                className="instance-settings-cancel"
+                onClick={async () => {
+                  const password = window.prompt("Contraseña de administrador:");
+                  if (!password) return;
+                  try {
+                    const current = await invokeCommand("get_instance_config_patches", { password, instanceId: settingsInstance.id });
+                    const text = window.prompt(
+                      'Parches de configuración (JSON), p. ej. [{"file": "config/menu.toml", "key": "server.ip", "value": "evento.example"}]:',
+                      JSON.stringify(current)
+                    );
+                    if (text === null) return;
+                    const patches = text.trim() === "" ? [] : JSON.parse(text);
+                    await invokeCommand("set_instance_config_patches", { password, instanceId: settingsInstance.id, patches });
+                  } catch (error) {
+                    alert(`No se pudieron guardar los parches: ${error}`);
+                  }
+                }}
+              >
+                Parches de configuración
+              </button>
+              <button
+                className="instance-settings-cancel"
+                onClick={async () => {
+                  const password = window.prompt("Contraseña de administrador:");
+                  if (!password) return;
+                  try {
+                    const players = await invokeCommand("get_instance_players", { password, instanceId: settingsInstance.id });
+                    alert(players.length === 0
+                      ? "Nadie ha jugado esta instancia en este equipo"
+                      : players.map((p) =>
+                          `${p.name ?? "(nombre desconocido)"} · ${p.uuid}` +
+                          (p.worlds.length > 0 ? `\n  Mundos: ${p.worlds.join(", ")}` : "")
+                        ).join("\n"));
+                  } catch (error) {
+                    alert(`No se pudo leer la lista de jugadores: ${error}`);
+                  }
+                }}
+              >
+                Jugadores de la instancia
+              </button>
+              <button
+                className="instance-settings-cancel"
+                onClick={async () => {
+                  const password = window.prompt("Contraseña de administrador:");
+                  if (!password) return;
+                  try {
+                    const result = await invokeCommand("smoke_test_instance", { password, instanceId: settingsInstance.id, timeoutSecs: null });
+                    alert(result.passed
+                      ? `Prueba superada en ${result.seconds} s: el juego cargó hasta el menú.`
+                      : `Prueba fallida: ${result.reason}` +
+                        (result.problemMod ? `\nMod sospechoso: ${result.problemMod}` : "") +
+                        `\n\nRegistro: ${result.logPath}`);
+                  } catch (error) {
+                    alert(`No se pudo hacer la prueba de arranque: ${error}`);
+                  }
+                }}
+              >
+                Prueba de arranque
+              </button>
+              <button
+                className="instance-settings-cancel"
+                onClick={async () => {
+                  if (!window.confirm("Se creará una copia de prueba con sus propias librerías, assets y Java (vuelve a descargarlos). Los jugadores de este equipo no se ven afectados. ¿Continuar?")) return;
+                  const password = window.prompt("Contraseña de administrador:");
+                  if (!password) return;
+                  try {
+                    const clone = await invokeCommand("create_sandbox_clone", { password, instanceId: settingsInstance.id });
+                    await reloadInstances(clone.id);
+                    setSettingsInstance(null);
+                  } catch (error) {
+                    alert(`No se pudo crear la copia de prueba: ${error}`);
+                  }
+                }}
+              >
+                Copia de prueba aislada
+              </button>
+              <button
+                className="instance-settings-cancel"
+                onClick={() => {
+                  invokeCommand("create_instance_shortcut", { instanceId: settingsInstance.id, steam: false })
+                    .then(() => alert("Acceso directo creado"))
+                    .catch((error) => alert(`No se pudo crear el acceso directo: ${error}`));
+                }}
+              >
+                Crear acceso directo
+              </button>
               <button
                 className="instance-settings-cancel"
                 onClick={() => setSettingsInstance(null)}
@@ -961,6 +1950,9 @@ function App() {
                     ram: settingsDraft.ram,
                     resolutionWidth: settingsDraft.resolutionWidth,
                     resolutionHeight: settingsDraft.resolutionHeight,
+                    linuxTweaks: settingsDraft.linuxTweaks,
+                    gameLanguage: settingsDraft.gameLanguage,
+                    jvmPreset: settingsDraft.jvmPreset,
                   };
                   const updatedInstances = instances.map((inst) =>
                     inst.id === updatedInstance.id ? updatedInstance : inst
@@ -970,44 +1962,7 @@ function App() {
                     setSelectedInstance(updatedInstance);
                   }
                   try {
-                    const instanceForRust: any = {
-                      id: updatedInstance.id,
-                      name: updatedInstance.name,
-                      version: updatedInstance.version,
-                      last_played: updatedInstance.lastPlayed,
-                      icon: updatedInstance.icon,
-                      path: updatedInstance.path,
-                      image: updatedInstance.image,
-                      images: updatedInstance.images,
-                      description: updatedInstance.description,
-                      ram: updatedInstance.ram,
-                      server_ip: updatedInstance.serverIp,
-                      server_name: updatedInstance.serverName,
-                      modpack_url: updatedInstance.modpackUrl,
-                      mods: updatedInstance.mods,
-                      launcher: updatedInstance.launcher,
-                      resolution_width: updatedInstance.resolutionWidth,
-                      resolution_height: updatedInstance.resolutionHeight,
-                      event_card: updatedInstance.eventCard ? {
-                        image: updatedInstance.eventCard.image,
-                        event_name: updatedInstance.eventCard.eventName,
-                        date: updatedInstance.eventCard.date,
-                        rewards: updatedInstance.eventCard.rewards,
-                      } : undefined,
-                      stats_card: updatedInstance.statsCard ? {
-                        image: updatedInstance.statsCard.image,
-                        players_online: updatedInstance.statsCard.playersOnline,
-                        latency: updatedInstance.statsCard.latency,
-                        status: updatedInstance.statsCard.status,
-                      } : undefined,
-                      info_card: updatedInstance.infoCard ? {
-                        image: updatedInstance.infoCard.image,
-                        mods_installed: updatedInstance.infoCard.modsInstalled,
-                        last_update: updatedInstance.infoCard.lastUpdate,
-                      } : undefined,
-                      modloader: updatedInstance.modloader,
-                    };
-                    await invoke("save_instance", { instance: instanceForRust });
+                    await invokeCommand("save_instance", { instance: toSavedInstance(updatedInstance) });
                     localStorage.setItem("drk_instances", JSON.stringify(updatedInstances));
                   } catch (error) {
                     console.error("Error saving settings:", error);
@@ -1045,10 +2000,31 @@ function App() {
                     onChange={(e) => setAdvancedDraft({ ...advancedDraft, version: e.target.value })}
                   />
                 </div>
+                <div className="admin-settings-field">
+                  <label>
+                    <input
+                      type="checkbox"
+                      checked={!!advancedDraft.trackLatestPatch}
+                      onChange={(e) => setAdvancedDraft({ ...advancedDraft, trackLatestPatch: e.target.checked })}
+                    />
+                    Seguir último parche ({advancedDraft.version.split(".").slice(0, 2).join(".")}.x)
+                  </label>
+                </div>
+                <div className="admin-settings-field">
+                  <label>
+                    <input
+                      type="checkbox"
+                      checked={!!advancedDraft.singleplayerOnly}
+                      onChange={(e) => setAdvancedDraft({ ...advancedDraft, singleplayerOnly: e.target.checked })}
+                    />
+                    Solo un jugador (sin multijugador ni chat)
+                  </label>
+                </div>
                 <div className="admin-settings-field">
                   <label>RAM (MB)</label>
                   <input
                     type="number"
+                    max={maxRamMb}
                     value={advancedDraft.ram || 0}
                     onChange={(e) => setAdvancedDraft({ ...advancedDraft, ram: Number(e.target.value) })}
                   />
@@ -1074,6 +2050,13 @@ function App() {
                     onChange={(e) => setAdvancedDraft({ ...advancedDraft, modpackUrl: e.target.value })}
                   />
                 </div>
+                <div className="admin-settings-field">
+                  <label>Pack de traducción (URL)</label>
+                  <input
+                    value={advancedDraft.translationPackUrl || ""}
+                    onChange={(e) => setAdvancedDraft({ ...advancedDraft, translationPackUrl: e.target.value })}
+                  />
+                </div>
                 <div className="admin-settings-field">
                   <label>Modloader</label>
                   <select
@@ -1118,6 +2101,26 @@ function App() {
               <button
                 className="admin-settings-save"
                 onClick={async () => {
+                  if (advancedDraft.version !== advancedInstance.version || (advancedDraft.modloader || "vanilla") !== (advancedInstance.modloader || "vanilla")) {
+                    try {
+                      const report = await invokeCommand("check_instance_compatibility", {
+                        version: advancedDraft.version,
+                        modloader: advancedDraft.modloader,
+                      });
+                      if (report.errors.length > 0) {
+                        alert(report.errors.join("\n"));
+                        return;
+                      }
+                      if (report.warnings.length > 0 && !window.confirm(`${report.warnings.join("\n")}\n\n¿Guardar de todas formas?`)) {
+                        return;
+                      }
+                    } catch (error) {
+                      console.error("Error checking compatibility:", error);
+                    }
+                  }
+                  if (advancedDraft.version !== advancedInstance.version && !(await prepareWorldsForVersion(advancedInstance, advancedDraft.version))) {
+                    return;
+                  }
                   const updatedInstance: Instance = {
                     ...advancedInstance,
                     name: advancedDraft.name,
@@ -1130,6 +2133,9 @@ function App() {
                     modloader: advancedDraft.modloader,
                     launcher: advancedDraft.launcher,
                     image: advancedDraft.image,
+                    trackLatestPatch: advancedDraft.trackLatestPatch,
+                    translationPackUrl: advancedDraft.translationPackUrl,
+                    singleplayerOnly: advancedDraft.singleplayerOnly,
                   };
                   const updatedInstances = instances.map((inst) =>
                     inst.id === updatedInstance.id ? updatedInstance : inst
@@ -1139,42 +2145,7 @@ function App() {
                     setSelectedInstance(updatedInstance);
                   }
                   try {
-                    const instanceForRust: any = {
-                      id: updatedInstance.id,
-                      name: updatedInstance.name,
-                      version: updatedInstance.version,
-                      last_played: updatedInstance.lastPlayed,
-                      icon: updatedInstance.icon,
-                      path: updatedInstance.path,
-                      image: updatedInstance.image,
-                      images: updatedInstance.images,
-                      description: updatedInstance.description,
-                      ram: updatedInstance.ram,
-                      server_ip: updatedInstance.serverIp,
-                      server_name: updatedInstance.serverName,
-                      modpack_url: updatedInstance.modpackUrl,
-                      mods: updatedInstance.mods,
-                      launcher: updatedInstance.launcher,
-                      event_card: updatedInstance.eventCard ? {
-                        image: updatedInstance.eventCard.image,
-                        event_name: updatedInstance.eventCard.eventName,
-                        date: updatedInstance.eventCard.date,
-                        rewards: updatedInstance.eventCard.rewards,
-                      } : undefined,
-                      stats_card: updatedInstance.statsCard ? {
-                        image: updatedInstance.statsCard.image,
-                        players_online: updatedInstance.statsCard.playersOnline,
-                        latency: updatedInstance.statsCard.latency,
-                        status: updatedInstance.statsCard.status,
-                      } : undefined,
-                      info_card: updatedInstance.infoCard ? {
-                        image: updatedInstance.infoCard.image,
-                        mods_installed: updatedInstance.infoCard.modsInstalled,
-                        last_update: updatedInstance.infoCard.lastUpdate,
-                      } : undefined,
-                      modloader: updatedInstance.modloader,
-                    };
-                    await invoke("save_instance", { instance: instanceForRust });
+                    await invokeCommand("save_instance", { instance: toSavedInstance(updatedInstance) });
                     localStorage.setItem("drk_instances", JSON.stringify(updatedInstances));
                   } catch (error) {
                     console.error("Error saving settings:", error);
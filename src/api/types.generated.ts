@@ -0,0 +1,1495 @@
+// Generado por scripts/gen-api-types.mjs a partir de src-tauri/src/schema.rs. No editar a mano.
+import { invoke } from "@tauri-apps/api/core";
+import { listen, type EventCallback, type UnlistenFn } from "@tauri-apps/api/event";
+
+export const API_SCHEMA_VERSION = 1;
+
+/** Accessibility settings the player turned on in the OS. Sent as `accessibility_changed` when they change while the launcher is open. */
+export type AccessibilityPrefs = {
+  /** Windows high contrast theme, macOS "Increase contrast", GNOME high contrast */
+  highContrast: boolean;
+  /** Windows "Show animations" off, macOS "Reduce motion", GNOME animations off */
+  reducedMotion: boolean;
+};
+
+export type AdminSetupStatus = {
+  /** Where to read the setup code while no password is set */
+  codePath?: string | null;
+  configured: boolean;
+};
+
+/** A broadcast forwarded to the UI as the `announcement` event. */
+export type Announcement = {
+  /** Instance the broadcast is about, if any */
+  instanceId?: string | null;
+  message?: string;
+  receivedAt?: number;
+  title?: string | null;
+  /** `pack_update`, `server_restart`, `message` or whatever the server sends */
+  type?: string;
+};
+
+/** Staff broadcast channel (pack pushed, server restarting). Admin only, the token is a secret. */
+export type AnnouncementSettings = {
+  allowedCommands?: Array<string>;
+  /** Shared HMAC key for remote commands (see `crate::remote_commands`); none means commands are ignored */
+  commandSecret?: string | null;
+  enabled?: boolean;
+  /** `ws://` or `wss://` URL */
+  endpoint?: string | null;
+  /** Sent as `Authorization: Bearer <token>` on the handshake */
+  token?: string | null;
+};
+
+/** `announcement_status` payload: whether the socket is up, and why not. */
+export type AnnouncementStatus = {
+  connected: boolean;
+  error?: string | null;
+};
+
+export type AntivirusGuidance = {
+  blockedFiles: Array<BlockedFile>;
+  exclusionPath: string;
+  products: Array<ProductGuidance>;
+};
+
+/** Per-index view of the shared `assets/objects` store. */
+export type AssetIndexStats = {
+  id: string;
+  /** Objects the index lists that are not on disk */
+  missing: number;
+  objectCount: number;
+  sizeBytes: number;
+};
+
+export type AssetStats = {
+  indexes: Array<AssetIndexStats>;
+  objectCount: number;
+  reclaimableBytes: number;
+  /** Objects referenced by more than one index */
+  sharedObjects: number;
+  totalBytes: number;
+  unreferencedCount: number;
+};
+
+/** Signed list of the pack's file hashes made at launch, for event servers that check players run the unmodified pack. Admin only, the secret is shared with the server. */
+export type AttestationSettings = {
+  enabled?: boolean;
+  /** Verification endpoint the signed attestation is POSTed to; none keeps it local */
+  endpoint?: string | null;
+  /** HMAC key; falls back to the announcement command secret */
+  secret?: string | null;
+};
+
+export type AuditEntry = {
+  /** License written in the jar's own metadata */
+  declaredLicense?: string | null;
+  fileName: string;
+  /** What an organizer should look at, in Spanish for the UI */
+  flags: Array<string>;
+  /** License the platform lists for the project */
+  license?: string | null;
+  origin: Origin;
+  projectName?: string | null;
+  projectUrl?: string | null;
+  redistribution: Redistribution;
+  sha1: string;
+  sourceUrl?: string | null;
+};
+
+export type BlockedFile = {
+  path: string;
+  symptom: Symptom;
+  /** Where to download it again; `None` for files the launcher doesn't fetch itself (Java) */
+  url?: string | null;
+};
+
+export type BundleReport = {
+  archiveBytes: number;
+  file: string;
+  files: number;
+  /** Needed but not on this machine; launch the instance once online and export again */
+  missing: Array<string>;
+  /** client, libraries, assets, java and instance */
+  sections: {
+    [key: string]: SectionSize;
+  };
+  /** Uncompressed size of everything packed */
+  totalBytes: number;
+};
+
+/** Result of validating a version/loader pair before an instance is saved. */
+export type CompatibilityReport = {
+  /** The instance would not prepare/launch with this combination */
+  errors: Array<string>;
+  warnings: Array<string>;
+};
+
+export type ComponentState = "ready" | "missing" | "partial" | "outdated";
+
+export type ComponentStatus = {
+  percent: number;
+  state: ComponentState;
+};
+
+/** One value staff set in a mod's config after every sync, so an event-specific setting (the server IP of a menu mod, say) doesn't need a repacked modpack. */
+export type ConfigPatch = {
+  /** Relative to the game folder, e.g. `config/menu.toml`; `.json` or `.toml` */
+  file: string;
+  /** JSON pointer for JSON files (`/server/ip`), dotted key for TOML (`server.ip`) */
+  key: string;
+  /** String, number, boolean or a list of those */
+  value: unknown;
+};
+
+export type ContentAudit = {
+  entries: Array<AuditEntry>;
+  generatedAt: number;
+  instanceId: string;
+  /** Lookups that failed (no CurseForge key, platform down); affected files show as unknown */
+  warnings: Array<string>;
+};
+
+/** A project found by `search`. */
+export type ContentHit = {
+  description: string;
+  downloads: number;
+  iconUrl?: string | null;
+  /** What goes in the instance's mod list or modpack field, e.g. `modrinth:sodium` */
+  reference: string;
+  source: string;
+  title: string;
+};
+
+export type ContentKind = "mod" | "modpack" | "resourcepack";
+
+export type CrashAnalysis = {
+  causes: Array<CrashCause>;
+  /** The last crash's exit code in words, when it's one that says something */
+  exitCode?: ExitCodeInfo | null;
+  /** Full installer output when the cause is `LoaderInstallFailed` */
+  loaderLog?: string | null;
+  /** After several crashes in a row, or when a safe mode launch crashed too */
+  safeMode?: SafeModeOffer | null;
+  suggestions: Array<Suggestion>;
+};
+
+export type CrashCause = "outOfMemory" | "longGcPauses" | "loaderInstallFailed";
+
+/** API keys and the HTTP user agent, injected by `crate::http`. Admin only. */
+export type Credentials = {
+  curseforgeApiKey?: string | null;
+  githubToken?: string | null;
+  userAgent?: string | null;
+};
+
+export type DamagedWorld = {
+  /** Zips of this world in `backups/worlds`, newest first */
+  backups: Array<string>;
+  folder: string;
+  /** `level.dat` is damaged and `level.dat_old`, the game's copy of the previous save, reads fine */
+  levelDatOld: boolean;
+  problems: Array<SaveProblem>;
+};
+
+export type DataDirInfo = {
+  /** Running inside a Flatpak sandbox */
+  flatpak: boolean;
+  path: string;
+  portable: boolean;
+};
+
+/** What `delete_instance` did with the folder: moved to the trash (restorable with `restore_instance`) or being erased in the background (see `remover::spawn`). */
+export type DeletedInstance = {
+  jobId?: string | null;
+  trashId?: string | null;
+};
+
+export type Direction = "pull" | "push";
+
+export type DiskKind = "ssd" | "hdd";
+
+/** Rough size of a pending download, shown before starting it in data saver mode. */
+export type DownloadEstimate = {
+  assetsBytes: number;
+  javaBytes: number;
+  modpackBytes: number;
+  totalBytes: number;
+  /** Parts whose size is not known yet (e.g. "assets" before the index is downloaded) */
+  unknown: Array<string>;
+};
+
+export type DownloadStats = {
+  hosts?: {
+    [key: string]: HostStats;
+  };
+  /** Latest `run_mirror_speedtest` */
+  speedtest?: Speedtest | null;
+};
+
+export type DriverLink = {
+  url: string;
+  vendor: string;
+};
+
+/** `drop_install_progress` payload. `instanceId` is null when nothing was selected to drop on. */
+export type DropInstallProgress = {
+  done: boolean;
+  error?: string | null;
+  file?: string | null;
+  instanceId?: string | null;
+  message: string;
+  percent: number;
+};
+
+export type EventCard = {
+  date?: string | null;
+  event_name?: string | null;
+  image?: string | null;
+  rewards?: string | null;
+  title?: string | null;
+};
+
+export type ExitCategory = "driver" | "memory" | "mods" | "java" | "killed";
+
+/** A game exit code in words. */
+export type ExitCodeInfo = {
+  code: number;
+  explanation: string;
+  /** `0xC0000005`; what Windows tools and forum posts show */
+  hex: string;
+  likely: ExitCategory;
+  /** `STATUS_ACCESS_VIOLATION`, `SIGKILL`... */
+  name?: string | null;
+};
+
+export type ExtractionPhase = "extracting" | "cleaning";
+
+/** Counts for one folder while cleaning, for the whole pack while extracting. */
+export type ExtractionProgress = {
+  /** Folder being cleaned or the last file extracted */
+  current?: string | null;
+  doneBytes: number;
+  doneFiles: number;
+  phase: ExtractionPhase;
+  totalBytes: number;
+  totalFiles: number;
+};
+
+export type FailureKind = "network" | "disk" | "antivirus" | "crash" | "other";
+
+/** Whether the launcher registers itself as the handler for pack files and `drk://` links. */
+export type FileAssociationSettings = {
+  deepLinks?: boolean;
+  enabled?: boolean;
+  /** Executable the current registration points at; it is redone when the launcher moves */
+  registeredExe?: string | null;
+};
+
+/** Where this machine reports itself so organizers see every booth PC in one place. */
+export type FleetSettings = {
+  /** Shown to organizers next to the machine id, e.g. `Stand 4` */
+  label?: string | null;
+  lastError?: string | null;
+  /** Unix seconds of the last report the fleet server accepted */
+  lastReportAt?: number | null;
+  /** Settings the fleet server changed in its last override */
+  overridden?: Array<string>;
+  /** Minutes between reports; the fleet server may change it */
+  reportMinutes?: number | null;
+  /** Receives the `MachineReport` JSON as a POST; none turns reporting off */
+  url?: string | null;
+};
+
+export type GamepadProfileResult = {
+  modUrl?: string | null;
+  shortcuts: Array<string>;
+  /** Parts of the profile that could not be applied; the rest still is */
+  warnings: Array<string>;
+};
+
+export type GpuAdapter = {
+  /** `YYYY-MM-DD`; Windows only */
+  driverDate?: string | null;
+  driverVersion?: string | null;
+  name: string;
+  /** Highest version a real context reported (Linux, through `glxinfo`) */
+  openglVersion?: string | null;
+};
+
+export type GraphicsCheck = {
+  adapters: Array<GpuAdapter>;
+  /** Driver downloads for the adapters with problems */
+  links: Array<DriverLink>;
+  problems: Array<string>;
+  /** OpenGL version the Minecraft version needs, e.g. `3.2` */
+  required: string;
+  status: GraphicsStatus;
+};
+
+export type GraphicsStatus = "ok" | "warning" | "unsupported";
+
+/** Whether the launcher is locked to guest play and which instances guests may launch. */
+export type GuestMode = {
+  allowedInstances: Array<string>;
+  enabled: boolean;
+};
+
+/** Memory and CPU of this machine. Sizes are always MiB, whatever unit sysinfo works in. */
+export type HardwareProfile = {
+  availableRamMb: number;
+  /** Physical cores; `None` when the OS doesn't say */
+  cpuCores?: number | null;
+  /** Logical processors (hardware threads) */
+  cpuThreads: number;
+  freeSwapMb: number;
+  totalRamMb: number;
+  totalSwapMb: number;
+};
+
+/** Certificates seen for one host, trusted on first use. */
+export type HostPin = {
+  /** Last time an unknown certificate showed up */
+  changedAt?: number | null;
+  /** SHA-256 of the leaf certificates, oldest first */
+  fingerprints: Array<string>;
+  firstSeen: number;
+  lastSeen: number;
+};
+
+export type HostStats = {
+  bytes: number;
+  downloads: number;
+  failures: number;
+  /** Bytes per second of the most recent downloads, oldest first */
+  speedHistory?: Array<number>;
+  totalMillis: number;
+};
+
+export type HotkeyLaunch = {
+  error?: string | null;
+  instanceId: string;
+  name: string;
+};
+
+/** Differences between this machine and a reference index. */
+export type IndexDrift = {
+  /** Present on both with a different hash */
+  changed: Array<string>;
+  checked: number;
+  /** Only on this machine */
+  extra: Array<string>;
+  /** In the reference, not on this machine */
+  missing: Array<string>;
+};
+
+/** Numbers are refreshed from the instance folder after each sync (see `info_card::refresh`). */
+export type InfoCard = {
+  image?: string | null;
+  last_update?: string | null;
+  mods_installed?: number | null;
+  pack_version?: string | null;
+};
+
+export type Instance = {
+  /** Staff-only remarks; stripped from what `get_instances` returns */
+  adminNotes?: string | null;
+  /** Config values set after every sync (see `minecraft::config_patch`); staff-only */
+  configPatches?: Array<ConfigPatch> | null;
+  debugLaunch?: boolean | null;
+  description?: string | null;
+  eventCard?: EventCard | null;
+  gameLanguage?: string | null;
+  /** Controller/Steam Deck profile applied (see `gamepad::apply_gamepad_profile`) */
+  gamepadProfile?: boolean | null;
+  icon: string;
+  id: string;
+  images?: Array<string> | null;
+  infoCard?: InfoCard | null;
+  /** Runtime folder under the data root (`java/runtimes/<build>`) this instance launches with instead of any other Java of the same major; set on instances from offline bundles */
+  javaRuntime?: string | null;
+  /** JVM flag preset (see `minecraft::jvm_presets`) */
+  jvmPreset?: string | null;
+  /** Set when the game really starts (see `last_played::record_launch`) */
+  lastPlayed?: string;
+  launcher?: string | null;
+  linuxTweaks?: LinuxTweaks | null;
+  logLevel?: string | null;
+  modloader?: string | null;
+  modpackUrl?: string | null;
+  mods?: Array<string> | null;
+  name: string;
+  /** Shown to players on the instance page (see `instance_notes`) */
+  notes?: string | null;
+  path: string;
+  ram?: number | null;
+  resolution_height?: number | null;
+  resolution_width?: number | null;
+  /** Set on admin test clones (see `sandbox`): the root holding their own libraries, assets and Java */
+  sandboxRoot?: string | null;
+  serverIp?: string | null;
+  serverName?: string | null;
+  /** Launch with multiplayer and chat disabled, for supervised stations */
+  singleplayerOnly?: boolean | null;
+  statsCard?: StatsCard | null;
+  /** Follow new patch releases of the same minor (1.21.x) after user confirmation */
+  trackLatestPatch?: boolean | null;
+  translationPackUrl?: string | null;
+  version: string;
+};
+
+/** `instance_card_updated` payload. */
+export type InstanceCardUpdated = {
+  infoCard: InfoCard;
+  instanceId: string;
+};
+
+/** Exactly what a successful prepare of an instance resolved to, in `<instance>/instance.lock`. Written by the first prepare; later prepares compare against it and report drift, so an event pack plays the same on every station and every night until an admin clears it. */
+export type InstanceLock = {
+  /** Runtime the game is started with; `None` when its `release` file can't be read */
+  java?: JavaRelease | null;
+  /** SHA-1 of every library that applies on this system, by maven name */
+  libraries: {
+    [key: string]: string;
+  };
+  loader: string;
+  /** Loader build, `None` for vanilla */
+  loaderVersion?: string | null;
+  /** Unix seconds; not compared */
+  lockedAt: number;
+  mcVersion: string;
+  /** SHA-1 of every file in `minecraft/mods`, by path relative to it */
+  mods: {
+    [key: string]: string;
+  };
+  v: number;
+  /** SHA-1 of each version json the profile was merged from, by version id */
+  versionJsons: {
+    [key: string]: string;
+  };
+};
+
+/** An account that has played an instance, as the game remembers it. */
+export type InstancePlayer = {
+  /** When the game stops trusting the cached name, e.g. `2024-06-01 12:00:00 +0000` */
+  expiresOn?: string | null;
+  /** `None` when only a world's `playerdata` knows the account */
+  name?: string | null;
+  /** Dashed, lowercase */
+  uuid: string;
+  /** World folders holding a `playerdata/<uuid>.dat` for the account */
+  worlds: Array<string>;
+};
+
+/** What is already on disk for an instance, checked without touching the network. */
+export type InstanceReadiness = {
+  assets: ComponentStatus;
+  client: ComponentStatus;
+  java: ComponentStatus;
+  mods: ComponentStatus;
+  /** Player-added jars held back on a remote-managed instance (see `mod_scan::hold_player_mods`) */
+  pendingMods: Array<string>;
+  ready: boolean;
+};
+
+export type InstanceStorage = {
+  id: string;
+  /** Launcher logs next to `minecraft/` plus the game's own `minecraft/logs` */
+  logs: number;
+  mods: number;
+  name: string;
+  other: number;
+  saves: number;
+  total: number;
+};
+
+/** What happens to the transient files a session leaves in the game folder. Files the launcher regenerates, duplicates and heap dumps are always deleted; the policy decides for the JVM's own crash logs. */
+export type JanitorPolicy = "delete" | "archive" | "off";
+
+export type JanitorSettings = {
+  archiveDays?: number;
+  policy?: JanitorPolicy;
+};
+
+/** `java_download_progress` payload. */
+export type JavaDownloadProgress = {
+  major: number;
+  message: string;
+  percent: number;
+};
+
+/** A runtime's `release` file: the exact build a runtime folder holds. */
+export type JavaRelease = {
+  arch?: string | null;
+  os?: string | null;
+  /** `JAVA_RUNTIME_VERSION`, e.g. `17.0.9+9` */
+  runtimeVersion?: string | null;
+  /** `IMPLEMENTOR` */
+  vendor?: string | null;
+  /** `JAVA_VERSION`, e.g. `17.0.9` or `1.8.0_392` */
+  version: string;
+};
+
+export type JobAction = "verify" | "sync";
+
+/** A named set of GC/JIT flags an instance can pick instead of the launcher default. */
+export type JvmPreset = {
+  description: string;
+  id: string;
+  name: string;
+};
+
+/** Event-night stations: which account they play with and how stale the catalog may be. */
+export type KioskSettings = {
+  /** A cached catalog older than this stops the start; `None` is 24 hours */
+  maxCatalogAgeHours?: number | null;
+  /** Offline account the station signs in with; `None` keeps whoever is signed in */
+  username?: string | null;
+};
+
+export type LastPlayed = {
+  /** RFC 3339, UTC */
+  at: string;
+  /** Calendar date in this machine's time zone */
+  date: string;
+  instanceId: string;
+  /** "hace 3 días", or "Nunca" for instances never launched */
+  relative: string;
+};
+
+/** `latest` block of the manifest with release dates, for defaults and "new version" badges. */
+export type LatestVersions = {
+  release: string;
+  releaseDate?: string | null;
+  snapshot: string;
+  snapshotDate?: string | null;
+};
+
+export type LaunchAttempt = {
+  errorCode?: number | null;
+  failure?: FailureKind | null;
+  message?: string | null;
+  outcome: Outcome;
+  /** How long the game process ran */
+  playMillis?: number | null;
+  /** Download, sync and command building time; `None` when preparation failed */
+  prepareMillis?: number | null;
+  /** Safe mode step the session ran with */
+  safeMode?: SafeModeStep | null;
+  /** Preparation phases in the order they ran; a phase seen twice is added up */
+  stages?: Array<StageTiming>;
+  /** Unix seconds */
+  startedAt: number;
+};
+
+/** `launch_progress` payload. Only the final stages fill in the optional fields. */
+export type LaunchProgress = {
+  debug?: boolean | null;
+  /** Sent while a modpack is cleaned out and extracted */
+  extraction?: ExtractionProgress | null;
+  instanceId: string;
+  logUrl?: string | null;
+  message: string;
+  percent: number;
+  /** Event rewards result, sent with `cerrado` and `crasheado` */
+  reward?: RewardReport | null;
+  /** Stamped by `emit_progress`: per-instance sequence number */
+  seq?: number | null;
+  stage: string;
+  /** Sent with `iniciado` and launch errors */
+  timings?: PrepareTimings | null;
+  /** Stamped by `emit_progress`: `EVENT_VERSION` */
+  v?: number | null;
+};
+
+export type LinkStatus = "ok" | "warning" | "broken";
+
+/** Per-instance Linux workarounds. Ignored on other platforms. */
+export type LinuxTweaks = {
+  /** Run through XWayland even when a Wayland session is available (GLFW 3.4+ prefers Wayland) */
+  forceX11?: boolean;
+  /** System GLFW to load instead of LWJGL's bundled one, e.g. a Wayland-patched libglfw.so */
+  glfwLibname?: string | null;
+  mesaGlthread?: boolean;
+  nvidiaThreadedOptimizations?: boolean;
+  /** Render on the discrete GPU of hybrid laptops (PRIME render offload) */
+  primeOffload?: boolean;
+};
+
+/** Loader build an instance resolved to. It stays until the catalog pins another build or an admin clears it, so a change in Forge's promotions mid-event never upgrades anyone. */
+export type LoaderLock = {
+  loader: string;
+  lockedAt: number;
+  mcVersion: string;
+  /** `catalog` when staff pinned it, `promotions` when it was Forge's recommended build */
+  source: string;
+  version: string;
+};
+
+/** One build of a mod loader for a given Minecraft version. */
+export type LoaderVersion = {
+  /** False for builds the loader marks (or names) beta/alpha/pre-release */
+  stable: boolean;
+  version: string;
+};
+
+/** Every known build of a loader for one Minecraft version, newest first. */
+export type LoaderVersions = {
+  latest?: string | null;
+  loader: string;
+  recommended?: string | null;
+  versions: Array<LoaderVersion>;
+};
+
+/** Blocks launching while the event server restarts. Set by staff locally, by a signed remote command (see `crate::remote_commands`) or by the maintenance feed. */
+export type Maintenance = {
+  /** Instances it applies to; empty means all of them */
+  instanceIds?: Array<string>;
+  message?: string;
+  /** Unix seconds when it ends by itself; shown as a countdown. `None` lasts until lifted */
+  until?: number | null;
+};
+
+/** Prometheus-style endpoint for organizers watching many stations. Only ever listens on 127.0.0.1; a scraper on the station (or a forwarding agent) collects it. */
+export type MetricsSettings = {
+  enabled?: boolean;
+  /** `None` is 9464; a new port applies after restarting the launcher */
+  port?: number | null;
+};
+
+export type MinecraftProfile = {
+  access_token: string;
+  has_entitlement: boolean;
+  id: string;
+  name: string;
+};
+
+export type MirrorReport = {
+  /** Files checked on the mirror */
+  checked: number;
+  /** The first `MAX_REPORTED` problems */
+  missing: Array<MissingFile>;
+  missingCount: number;
+};
+
+/** Air-gapped events: every metadata and download URL (Mojang manifest and profiles, libraries, assets, Java, Fabric meta, Forge maven) is fetched from one staff server instead. The server mirrors upstream by host and path, `https://piston-meta.mojang.com/mc/game/x.json` becoming `<base>/piston-meta.mojang.com/mc/game/x.json`: the layout `wget -x` produces. Query strings are kept, static servers ignore them. URLs already on the mirror are left alone. The Forge installer downloads its own libraries and can't be redirected; install Forge once online, or copy its `libraries/` folder, before going offline. */
+export type MirrorSettings = {
+  baseUrl?: string | null;
+  enabled?: boolean;
+};
+
+export type MissingFile = {
+  instanceId: string;
+  /** Upstream URL (or what failed to resolve) */
+  item: string;
+  reason: string;
+};
+
+export type ModScanSettings = {
+  /** JSON list of SHA-1 hashes staff approve for every instance, or `{"approved": [...], "loaders": {...}}` (see `loader_pin`) */
+  catalogUrl?: string | null;
+  enabled?: boolean;
+  /** Jars Modrinth knows by hash are trusted even with unusual entries */
+  modrinthLookup?: boolean;
+};
+
+export type ModUrlCheck = {
+  contentType?: string | null;
+  /** Name the jar gets in `mods/` */
+  fileName: string;
+  httpStatus?: number | null;
+  problems: Array<string>;
+  /** Direct download the launcher will use, when it differs from `url` (CurseForge, Modrinth...) */
+  resolved?: string | null;
+  /** Bytes, when the server says */
+  size?: number | null;
+  status: LinkStatus;
+  url: string;
+};
+
+export type ModUrlReport = {
+  broken: number;
+  /** Same order as the URLs given */
+  checks: Array<ModUrlCheck>;
+  ok: number;
+  warnings: number;
+};
+
+/** What extracting a pack over an instance would do, so it can be reviewed before applying. */
+export type ModpackDiff = {
+  added: Array<PackFile>;
+  changed: Array<PackFile>;
+  /** Size of the pack itself, i.e. what applying the update downloads */
+  downloadSize: number;
+  /** Files in the folders the sync replaces wholesale that the pack no longer ships */
+  removed: Array<string>;
+  unchanged: number;
+};
+
+/** What an admin needs to validate a modpack before assigning it to an instance. */
+export type ModpackInfo = {
+  /** Loader or version were guessed from the bundled mods because the pack doesn't declare them */
+  detectedFromMods: boolean;
+  /** "curseforge", "modrinth", "multimc" or "zip" when there is no manifest; "7z" and "rar" packs are not read beyond their format */
+  format: string;
+  loader?: string | null;
+  loaderVersion?: string | null;
+  mcVersion?: string | null;
+  /** Jars bundled in the zip plus mods the manifest downloads separately */
+  modCount: number;
+  name?: string | null;
+  totalSize: number;
+};
+
+/** Native OS notifications for work that finishes in the background. */
+export type NotificationSettings = {
+  enabled?: boolean;
+  /** Skip the notification while the launcher window is visible */
+  onlyWhenMinimized?: boolean;
+};
+
+/** What the launcher was opened with, routed by the frontend once it has loaded. */
+export type OpenRequest = {
+  kind: "modpack";
+  path: string;
+} | {
+  code: string;
+  kind: "shareCode";
+} | {
+  instanceId: string;
+  kind: "launch";
+};
+
+export type Operation = {
+  /** Unix seconds */
+  createdAt: number;
+  expiresAt: number;
+  id: string;
+  /** Instance record to put back in the list on undo */
+  instance?: Instance | null;
+  items?: Array<StagedItem>;
+  /** `delete_instance`, `clear_caches`, `prune_assets` */
+  kind: string;
+  label: string;
+  /** Deleted instance sitting in the trash; undo restores it from there */
+  trashId?: string | null;
+};
+
+export type OptimizeResult = {
+  jvmPreset: string;
+  modUrls: Array<string>;
+  ramMb: number;
+  /** Parts of the profile that could not be applied; the rest still is */
+  warnings: Array<string>;
+};
+
+export type Origin = "modrinth" | "curseforge" | "unknown";
+
+export type Outcome = "closed" | "crashed" | "failed";
+
+/** A file a modpack update writes into the game folder. */
+export type PackFile = {
+  /** Relative to the game folder, `/` separated */
+  path: string;
+  size: number;
+};
+
+/** Offered to instances with `trackLatestPatch` before re-preparing on the new patch. */
+export type PatchUpdate = {
+  /** False when the instance loader has no build for the new patch yet */
+  compatible: boolean;
+  current: string;
+  latest: string;
+  /** Loader version the new patch would use (Forge/Fabric), when there is one */
+  loaderVersion?: string | null;
+};
+
+export type PerformanceInfo = {
+  /** What "automatic" resolves to on this machine */
+  auto: Tuning;
+  cpus: number;
+  detectedDisk: DiskKind;
+  /** Storage type in use (the override, if set) */
+  disk: DiskKind;
+  settings: PerformanceSettings;
+};
+
+/** Overrides for the download pools and asset verification; `None` means tuned automatically. */
+export type PerformanceSettings = {
+  assetWorkers?: number | null;
+  /** Storage type of the data dir when detection gets it wrong */
+  disk?: DiskKind | null;
+  downloadTimeoutSecs?: number | null;
+  modWorkers?: number | null;
+  /** Seconds without receiving a byte before a download is dropped and retried */
+  stallTimeoutSecs?: number | null;
+  verifyMode?: VerifyMode | null;
+};
+
+/** What happens when a modpack host presents a certificate never seen for it before. */
+export type PinMode = "off" | "warn" | "block";
+
+/** What the UI needs to hide options the machine can't run and word instructions for it. */
+export type PlatformInfo = {
+  /** `x64`, `arm64`, `x86` */
+  arch: string;
+  /** Free space on the drive holding the launcher data; `None` when no mount matches */
+  availableDiskBytes?: number | null;
+  dataDir: string;
+  /** Whether the OS is 64-bit, even when the launcher itself runs as a 32-bit process */
+  is64Bit: boolean;
+  /** Largest heap worth offering for an instance on this machine */
+  maxRamMb: number;
+  /** Mojang's names: `windows`, `osx`, `linux` */
+  os: string;
+  totalRamMb: number;
+};
+
+/** `prefetch_status` payload: one step of warming the caches for an instance. */
+export type PrefetchStatus = {
+  instanceId: string;
+  message: string;
+  /** `running`, `done` or `error` */
+  status: string;
+  step: string;
+};
+
+/** Where the preparation time of a launch went, for the `iniciado` progress payload. */
+export type PrepareTimings = {
+  prepareMillis?: number | null;
+  stages: Array<StageTiming>;
+};
+
+/** One probe download of a speedtest run. */
+export type ProbeResult = {
+  bytes?: number;
+  /** Bytes per second over the whole request, latency included */
+  bytesPerSec?: number | null;
+  error?: string | null;
+  host: string;
+  /** Time until the response headers arrived */
+  latencyMs?: number | null;
+  url: string;
+};
+
+export type ProductGuidance = {
+  name: string;
+  steps: string;
+};
+
+/** Last `launch_progress` event of an instance, kept so a reloaded webview can pick up where the previous one left off. */
+export type ProgressSnapshot = {
+  /** Preparation or game still running */
+  active: boolean;
+  /** The event payload as it was emitted */
+  event: LaunchProgress;
+  /** Unix millis */
+  updatedAt: number;
+};
+
+export type PruneResult = {
+  freedBytes: number;
+  removedCount: number;
+};
+
+export type QuarantinedMod = {
+  fileName: string;
+  findings: Array<string>;
+  id: string;
+  instanceId: string;
+  /** Unix seconds */
+  quarantinedAt: number;
+  sha1: string;
+  /** Where the jar goes back on approval */
+  targetPath: string;
+  url: string;
+};
+
+/** `server_queue` payload: either where the player stands or why the last ping failed. */
+export type QueueUpdate = {
+  error?: string | null;
+  instanceId: string;
+  max?: number | null;
+  online?: number | null;
+  position?: number | null;
+};
+
+/** Whether the pack may ship the file, as far as the platform metadata tells. */
+export type Redistribution = "allowed" | "unknown" | "restricted";
+
+export type RemovalProgress = {
+  doneBytes: number;
+  doneFiles: number;
+  totalBytes: number;
+  totalFiles: number;
+};
+
+export type RemovalState = "running" | "done" | "cancelled" | "error";
+
+/** `removal_progress` payload, reported under the job id `spawn` returned. */
+export type RemovalStatus = {
+  error?: string | null;
+  jobId: string;
+  label: string;
+  progress: RemovalProgress;
+  state: RemovalState;
+};
+
+export type RestoreSource = "levelDatOld" | {
+  backup: {
+    file: string;
+  };
+};
+
+/** What the final `launch_progress` payload carries as `reward`. */
+export type RewardReport = {
+  /** `RewardResult::summary`, or why the submission failed */
+  message: string;
+  points?: number | null;
+  submitted: boolean;
+  totalPoints?: number | null;
+};
+
+/** Safe mode launch proposed after a crash, for the crash dialog. */
+export type SafeModeOffer = {
+  /** Crashes in a row so far */
+  crashes: number;
+  description: string;
+  /** Jars this step disables */
+  mods: Array<string>;
+  step: SafeModeStep;
+};
+
+export type SafeModeState = {
+  /** Jars renamed to `<name>.disabled`, put back when safe mode ends */
+  disabled?: Array<string>;
+  /** Steps whose sessions still crashed */
+  failed?: Array<SafeModeStep>;
+  /** Step of the last safe mode session that closed cleanly */
+  fixedBy?: SafeModeStep | null;
+  /** Jars in `mods/` after the last session outside safe mode that closed cleanly */
+  knownGood?: Array<string> | null;
+  settingsLowered?: boolean;
+  /** Step the next launches use; `None` while the instance runs normally */
+  step?: SafeModeStep | null;
+};
+
+/** Safe mode goes one step further each time the game still crashes; every step keeps the changes of the ones before it, so the step that got a clean session is what fixed it. */
+export type SafeModeStep = "lowerSettings" | "disableRecentMods" | "disableOptionalMods";
+
+/** One ping; `online` and `max` are `None` when the server didn't answer. */
+export type Sample = {
+  at: number;
+  max?: number | null;
+  online?: number | null;
+};
+
+export type SaveConflict = {
+  reason: string;
+  world: string;
+};
+
+/** `save_corruption` payload, emitted when a session that ended badly left damaged worlds. */
+export type SaveCorruption = {
+  instanceId: string;
+  worlds: Array<DamagedWorld>;
+};
+
+export type SaveProblem = {
+  detail: string;
+  /** Path inside the world folder, `/` separated */
+  file: string;
+};
+
+/** `save_sync` payload. */
+export type SaveSyncResult = {
+  direction: Direction;
+  instanceId: string;
+  report: SyncReport;
+};
+
+export type SaveSyncStatus = {
+  /** Staff configured a target */
+  available: boolean;
+  worlds: Array<string>;
+};
+
+/** A one-shot job, typically "verify everything 2 hours before the event". Persisted in settings. */
+export type ScheduledJob = {
+  action?: JobAction;
+  id: string;
+  instanceIds?: Array<string>;
+  lastResult?: string | null;
+  lastRun?: number | null;
+  /** Unix seconds */
+  runAt: number;
+};
+
+/** `scheduled_job` payload: `running` when a job starts, then `ok` or `error`. */
+export type ScheduledJobStatus = {
+  jobId: string;
+  message: string;
+  status: string;
+};
+
+export type SectionSize = {
+  bytes: number;
+  files: number;
+};
+
+/** Background sampling of the instances' event servers for the stats card sparkline. */
+export type ServerHistorySettings = {
+  enabled?: boolean;
+  intervalMinutes?: number;
+};
+
+/** What `provision_server` left in the target folder. */
+export type ServerInstall = {
+  loader: string;
+  path: string;
+  /** Script that starts the server with the Java the launcher picked for this version */
+  startScript: string;
+};
+
+/** What the event server answers to a server list ping. */
+export type ServerStatus = {
+  max: number;
+  motd?: string | null;
+  online: number;
+  version?: string | null;
+};
+
+/** Public status JSON published by staff, shown as banners across the launcher. */
+export type ServiceStatus = {
+  /** Where the update is downloaded from; shown next to the version warning */
+  downloadUrl?: string | null;
+  knownIssues?: Array<StatusNotice>;
+  maintenance?: Array<StatusNotice>;
+  /** Older launchers can't play until they update, e.g. `1.4.0` */
+  minLauncherVersion?: string | null;
+};
+
+export type ShareCode = {
+  /** Compact code that carries the whole definition */
+  code: string;
+  /** Short link from the code endpoint, when the definition was uploaded */
+  link?: string | null;
+};
+
+export type SmokeTestResult = {
+  /** Everything the game printed during the test */
+  logPath: string;
+  passed: boolean;
+  /** Mod the loader or the crash report blamed, when it named one */
+  problemMod?: string | null;
+  reason: string;
+  seconds: number;
+};
+
+export type Speedtest = {
+  /** Unix seconds */
+  at: number;
+  results: Array<ProbeResult>;
+};
+
+export type StageTiming = {
+  millis: number;
+  stage: string;
+};
+
+/** One path moved out of the way: `staged` is relative to the operation's folder. */
+export type StagedItem = {
+  original: string;
+  staged: string;
+};
+
+/** Whether a remembered account is signed in; sent as `session_restored` once the startup restore ends. */
+export type StartupAuthState = {
+  authType?: string | null;
+  loggedIn: boolean;
+  profile?: MinecraftProfile | null;
+};
+
+export type StatsCard = {
+  image?: string | null;
+  latency?: number | null;
+  players_online?: number | null;
+  status?: string | null;
+};
+
+/** A line of the status page: a planned maintenance or a known issue. */
+export type StatusNotice = {
+  message?: string;
+  title?: string | null;
+  /** Page with the details, if any */
+  url?: string | null;
+};
+
+/** What the UI gets from `get_service_status` and the `status_changed` event. */
+export type StatusReport = {
+  /** Unix seconds of the last successful poll; `None` until one succeeds */
+  fetchedAt?: number | null;
+  launcherVersion: string;
+  /** The installed launcher is below `minLauncherVersion`; launches are blocked */
+  outdated: boolean;
+  status: ServiceStatus;
+};
+
+/** Bytes per area of the data dir. Mods hard-linked from `mods-store` count both there and in each instance, so the parts can add up to more than the disk actually holds. */
+export type StorageSummary = {
+  assets: number;
+  caches: number;
+  instances: Array<InstanceStorage>;
+  java: number;
+  libraries: number;
+  /** Settings, indexes and anything else in the data dir */
+  other: number;
+  totalBytes: number;
+};
+
+export type Suggestion = {
+  action: SuggestionAction;
+  description: string;
+  title: string;
+};
+
+export type SuggestionAction = {
+  raiseRam: {
+    ramMb: number;
+  };
+} | {
+  installMods: {
+    mods: Array<string>;
+  };
+};
+
+export type Symptom = "accessDenied" | "hashMismatch" | "other" | "vanished" | "javaKilled";
+
+export type SyncReport = {
+  /** Left untouched until the player picks a side with `resolve_save_conflict` */
+  conflicts: Array<SaveConflict>;
+  errors: Array<string>;
+  pulled: Array<string>;
+  pushed: Array<string>;
+};
+
+/** Staff-provided storage for player saves. S3 uses path-style URLs (`<endpoint>/<bucket>/<key>`), which MinIO and most S3-compatible services accept. */
+export type SyncTarget = {
+  kind: "webdav";
+  password?: string | null;
+  url: string;
+  username?: string | null;
+} | {
+  accessKey: string;
+  bucket: string;
+  endpoint: string;
+  kind: "s3";
+  region?: string;
+  secretKey: string;
+};
+
+export type TrashEntry = {
+  /** Unix seconds */
+  deletedAt: number;
+  id: string;
+  instance: Instance;
+};
+
+export type TrashSettings = {
+  /** Deleting an instance moves it to the trash instead of erasing it */
+  enabled?: boolean;
+  /** Days an instance stays in the trash before it's purged for good */
+  retentionDays?: number;
+};
+
+export type Tuning = {
+  assetWorkers: number;
+  downloadTimeoutSecs: number;
+  modWorkers: number;
+  stallTimeoutSecs: number;
+  verifyMode: VerifyMode;
+};
+
+/** `undo_available` payload: the operation without its staged paths. */
+export type UndoAvailable = {
+  expiresAt: number;
+  id: string;
+  kind: string;
+  label: string;
+};
+
+/** `instance_update_applied` payload; `error` is set when the update failed. */
+export type UpdateApplied = {
+  error?: string | null;
+  instanceId: string;
+  name: string;
+};
+
+/** `instance_update_available` payload. `deferred` when the data saver holds the download back. */
+export type UpdateAvailable = {
+  deferred: boolean;
+  instanceId: string;
+  name: string;
+};
+
+/** Background modpack updates for remote-managed instances (those with a modpack URL). */
+export type UpdaterSettings = {
+  enabled?: boolean;
+  intervalMinutes?: number;
+};
+
+/** How existing asset objects are checked before launch. */
+export type VerifyMode = "full" | "quick";
+
+/** Where version metadata comes from. Admin only. */
+export type VersionMetaSettings = {
+  /** Never contact Mojang, Fabric or Forge (closed-network events) */
+  selfHostedOnly?: boolean;
+  /** Base URL of the staff meta server, asked before the public sources */
+  serverUrl?: string | null;
+};
+
+/** A world under `minecraft/saves`, with the version that last wrote its `level.dat`. */
+export type WorldInfo = {
+  dataVersion?: number | null;
+  folder: string;
+  name: string;
+  /** `Data.Version.Name`; missing on worlds last opened before 1.9 */
+  versionName?: string | null;
+};
+
+export type WorldMigrationPlan = {
+  /** Worlds saved by a newer version than the target; opening them would corrupt them */
+  blockedWorlds: Array<string>;
+  fromVersion: string;
+  toVersion: string;
+  upgrade: boolean;
+  worlds: Array<WorldInfo>;
+};
+
+export interface Commands {
+  analyze_crash: { args: { instanceId: string }; output: CrashAnalysis };
+  apply_crash_suggestion: { args: { instanceId: string; suggestion: Suggestion }; output: Array<string> };
+  apply_gamepad_profile: { args: { instanceId: string; steamShortcut: boolean }; output: GamepadProfileResult };
+  apply_instance_update: { args: { instanceId: string; version: string }; output: null };
+  apply_modpack_update: { args: { instanceId: string }; output: null };
+  approve_pending_mod: { args: { password: string; instanceId: string; fileName: string }; output: null };
+  approve_quarantined_mod: { args: { password: string; id: string }; output: QuarantinedMod };
+  attach_instance_image: { args: { instanceId: string; source: string }; output: string };
+  audit_instance_content: { args: { password: string; instanceId: string }; output: ContentAudit };
+  backup_instance_worlds: { args: { instanceId: string }; output: Array<string> };
+  cancel_modpack_extraction: { args: { instanceId: string }; output: boolean };
+  cancel_removal: { args: { jobId: string }; output: boolean };
+  cancel_scheduled_job: { args: { password: string; jobId: string }; output: null };
+  cancel_server_queue: { args: { instanceId: string }; output: null };
+  check_admin_password: { args: { password: string }; output: boolean };
+  check_graphics: { args: { instanceId: string }; output: GraphicsCheck };
+  check_instance_compatibility: { args: { version: string; modloader?: string | null }; output: CompatibilityReport };
+  check_instance_ready: { args: { instanceId: string }; output: InstanceReadiness };
+  check_instance_saves: { args: { instanceId: string }; output: Array<DamagedWorld> };
+  check_instance_update: { args: { instanceId: string }; output: PatchUpdate | null };
+  check_server_capacity: { args: { instanceId: string }; output: ServerStatus | null };
+  check_version_support: { args: { version: string }; output: string | null };
+  clear_caches: { args: { password: string }; output: Operation };
+  clear_instance_lock: { args: { password: string; instanceId: string }; output: null };
+  clear_loader_lock: { args: { password: string; instanceId: string }; output: null };
+  complete_admin_setup: { args: { code: string; password: string }; output: null };
+  create_instance_shortcut: { args: { instanceId: string; steam: boolean }; output: Array<string> };
+  create_kiosk_shortcut: { args: { password: string; instanceId: string }; output: string };
+  create_sandbox_clone: { args: { password: string; instanceId: string }; output: Instance };
+  delete_instance: { args: { instanceId: string; permanent?: boolean | null }; output: DeletedInstance };
+  download_java: { args: { major: number }; output: string };
+  download_server: { args: { password: string; version: string; targetDir: string; loader?: string | null }; output: ServerInstall };
+  estimate_instance_download: { args: { instanceId: string }; output: DownloadEstimate };
+  exit_safe_mode: { args: { instanceId: string }; output: Array<string> };
+  export_content_index: { args: { password: string; file: string }; output: number };
+  export_diagnostics: { args: { instanceId: string; file: string; password?: string | null }; output: Array<string> };
+  export_instance_code: { args: { password: string; instanceId: string; upload?: boolean | null }; output: ShareCode };
+  export_offline_bundle: { args: { password: string; instanceId: string; file: string }; output: BundleReport };
+  forget_host_pin: { args: { password: string; host: string }; output: null };
+  format_last_played: { args: { instanceId: string; locale?: string | null }; output: LastPlayed };
+  get_accessibility_prefs: { args: {}; output: AccessibilityPrefs };
+  get_account_avatar: { args: { uuid: string }; output: string };
+  get_active_progress: { args: {}; output: Array<ProgressSnapshot> };
+  get_admin_reset_log: { args: { password: string }; output: Array<unknown> };
+  get_admin_setup_status: { args: {}; output: AdminSetupStatus };
+  get_announcement_settings: { args: { password: string }; output: AnnouncementSettings };
+  get_antivirus_guidance: { args: {}; output: AntivirusGuidance };
+  get_api_schema: { args: {}; output: unknown };
+  get_asset_stats: { args: {}; output: AssetStats };
+  get_attestation_settings: { args: { password: string }; output: AttestationSettings };
+  get_auth_profile: { args: {}; output: MinecraftProfile | null };
+  get_crash_upload: { args: {}; output: unknown };
+  get_credentials: { args: { password: string }; output: Credentials };
+  get_data_dir_info: { args: {}; output: DataDirInfo };
+  get_data_saver: { args: {}; output: boolean };
+  get_default_ram: { args: { modloader?: string | null }; output: number };
+  get_download_stats: { args: {}; output: DownloadStats };
+  get_file_associations: { args: {}; output: FileAssociationSettings };
+  get_fleet_registration: { args: {}; output: FleetSettings };
+  get_game_languages: { args: { version: string }; output: Array<string> };
+  get_guest_mode: { args: {}; output: GuestMode };
+  get_hardware_profile: { args: {}; output: HardwareProfile };
+  get_host_pin_mode: { args: {}; output: PinMode };
+  get_host_pins: { args: { password: string }; output: {
+    [key: string]: HostPin;
+  } };
+  get_instance_admin_notes: { args: { password: string; instanceId: string }; output: string | null };
+  get_instance_config_patches: { args: { password: string; instanceId: string }; output: Array<ConfigPatch> };
+  get_instance_lock: { args: { instanceId: string }; output: InstanceLock | null };
+  get_instance_players: { args: { password: string; instanceId: string }; output: Array<InstancePlayer> };
+  get_instances: { args: {}; output: Array<Instance> };
+  get_janitor_settings: { args: {}; output: JanitorSettings };
+  get_java_info: { args: { mcVersion: string }; output: unknown };
+  get_jvm_presets: { args: {}; output: Array<JvmPreset> };
+  get_kiosk_settings: { args: {}; output: KioskSettings };
+  get_latest_versions: { args: {}; output: LatestVersions };
+  get_launch_history: { args: { instanceId: string }; output: Array<LaunchAttempt> };
+  get_launch_hotkey: { args: {}; output: string | null };
+  get_loader_locks: { args: { password: string }; output: {
+    [key: string]: LoaderLock;
+  } };
+  get_loader_recommendation: { args: { loader: string; mcVersion: string }; output: LoaderVersions };
+  get_machine_id: { args: {}; output: string };
+  get_maintenance: { args: {}; output: Array<Maintenance> };
+  get_maintenance_url: { args: {}; output: string | null };
+  get_mc_versions: { args: { limit: number }; output: Array<string> };
+  get_metrics_settings: { args: {}; output: MetricsSettings };
+  get_mirror_settings: { args: {}; output: MirrorSettings };
+  get_mod_scan_settings: { args: {}; output: ModScanSettings };
+  get_notification_settings: { args: {}; output: NotificationSettings };
+  get_performance_settings: { args: {}; output: PerformanceInfo };
+  get_platform_info: { args: {}; output: PlatformInfo };
+  get_progress_snapshot: { args: { instanceId: string }; output: ProgressSnapshot | null };
+  get_remote_command_log: { args: { password: string }; output: Array<unknown> };
+  get_rewards_endpoint: { args: {}; output: string | null };
+  get_safe_mode: { args: { instanceId: string }; output: SafeModeState };
+  get_save_sync: { args: { instanceId: string }; output: SaveSyncStatus };
+  get_save_sync_target: { args: { password: string }; output: SyncTarget | null };
+  get_scheduled_jobs: { args: {}; output: Array<ScheduledJob> };
+  get_server_history: { args: { host: string }; output: Array<Sample> };
+  get_server_history_settings: { args: {}; output: ServerHistorySettings };
+  get_service_status: { args: {}; output: StatusReport };
+  get_share_code_endpoint: { args: {}; output: string | null };
+  get_startup_auth_state: { args: {}; output: StartupAuthState };
+  get_status_url: { args: {}; output: string | null };
+  get_storage_summary: { args: { refresh?: boolean | null }; output: StorageSummary };
+  get_trash_settings: { args: {}; output: TrashSettings };
+  get_updater_settings: { args: {}; output: UpdaterSettings };
+  get_version_meta_settings: { args: { password: string }; output: VersionMetaSettings };
+  get_world_migration: { args: { instanceId: string; newVersion: string }; output: WorldMigrationPlan };
+  import_instance_from_code: { args: { code: string }; output: Instance };
+  inspect_modpack: { args: { urlOrPath: string }; output: ModpackInfo };
+  is_admin_configured: { args: {}; output: boolean };
+  kiosk_start: { args: { instanceId: string }; output: null };
+  launch_instance: { args: { instanceId: string }; output: null };
+  launch_world: { args: { instanceId: string; worldName: string }; output: null };
+  list_pending_mods: { args: { instanceId: string }; output: Array<string> };
+  list_quarantined_mods: { args: { password: string }; output: Array<QuarantinedMod> };
+  list_trash: { args: {}; output: Array<TrashEntry> };
+  migrate_data_dir: { args: { password: string; toPortable: boolean }; output: string };
+  open_folder: { args: { path: string; password?: string | null }; output: null };
+  open_instance_folder: { args: { instanceId: string; which: string }; output: null };
+  optimize_instance: { args: { instanceId: string; profile: string }; output: OptimizeResult };
+  prefetch_images: { args: { urls: Array<string> }; output: null };
+  prefetch_instance: { args: { instanceId: string }; output: null };
+  prepare_instance: { args: { instanceId: string }; output: null };
+  preview_modpack_update: { args: { instanceId: string }; output: ModpackDiff };
+  prune_assets: { args: { password: string }; output: PruneResult };
+  purge_trash: { args: { trashId?: string | null }; output: Array<string> };
+  register_machine: { args: { password: string; fleetUrl: string; label?: string | null }; output: FleetSettings };
+  reject_pending_mod: { args: { password: string; instanceId: string; fileName: string }; output: null };
+  reject_quarantined_mod: { args: { password: string; id: string }; output: null };
+  reset_admin_password: { args: { token: string; password: string }; output: null };
+  resolve_save_conflict: { args: { instanceId: string; world: string; keep: string }; output: SyncReport };
+  restore_instance: { args: { trashId: string }; output: Instance };
+  restore_world: { args: { instanceId: string; folder: string; source: RestoreSource }; output: null };
+  reveal_file: { args: { path: string }; output: null };
+  reverify_blocked_files: { args: {}; output: Array<string> };
+  run_mirror_speedtest: { args: {}; output: Speedtest };
+  save_instance: { args: { instance: Instance }; output: Instance };
+  schedule_job: { args: { password: string; instanceIds: Array<string>; runAt: number; action: JobAction }; output: ScheduledJob };
+  search_content: { args: { instanceId?: string | null; query: string; kind: ContentKind }; output: Array<ContentHit> };
+  set_admin_password: { args: { currentPassword: string; password: string }; output: null };
+  set_announcement_settings: { args: { password: string; announcements: AnnouncementSettings }; output: null };
+  set_attestation_settings: { args: { password: string; attestation: AttestationSettings }; output: null };
+  set_crash_upload: { args: { enabled: boolean }; output: null };
+  set_crash_upload_endpoint: { args: { password: string; endpoint?: string | null }; output: null };
+  set_credentials: { args: { password: string; credentials: Credentials }; output: null };
+  set_data_saver: { args: { enabled: boolean }; output: null };
+  set_drop_target: { args: { instanceId?: string | null }; output: null };
+  set_file_associations: { args: { enabled: boolean; deepLinks: boolean }; output: null };
+  set_guest_mode: { args: { password: string; enabled: boolean; allowedInstances: Array<string> }; output: null };
+  set_host_pin_mode: { args: { password: string; mode: PinMode }; output: null };
+  set_instance_admin_notes: { args: { password: string; instanceId: string; notes?: string | null }; output: null };
+  set_instance_config_patches: { args: { password: string; instanceId: string; patches: Array<ConfigPatch> }; output: null };
+  set_instance_notes: { args: { password: string; instanceId: string; notes?: string | null }; output: null };
+  set_janitor_settings: { args: { janitor: JanitorSettings }; output: null };
+  set_kiosk_settings: { args: { password: string; kiosk: KioskSettings }; output: null };
+  set_launch_hotkey: { args: { hotkey?: string | null }; output: null };
+  set_maintenance: { args: { password: string; maintenance?: Maintenance | null }; output: null };
+  set_maintenance_url: { args: { password: string; url?: string | null }; output: null };
+  set_metrics_settings: { args: { password: string; metrics: MetricsSettings }; output: null };
+  set_mirror_settings: { args: { password: string; mirror: MirrorSettings }; output: null };
+  set_mod_scan_settings: { args: { password: string; modScan: ModScanSettings }; output: null };
+  set_notification_settings: { args: { notifications: NotificationSettings }; output: null };
+  set_performance_settings: { args: { performance: PerformanceSettings }; output: PerformanceSettings };
+  set_rewards_endpoint: { args: { password: string; endpoint?: string | null }; output: null };
+  set_save_sync_target: { args: { password: string; target?: SyncTarget | null }; output: null };
+  set_server_history_settings: { args: { serverHistory: ServerHistorySettings }; output: null };
+  set_share_code_endpoint: { args: { password: string; endpoint?: string | null }; output: null };
+  set_status_url: { args: { password: string; url?: string | null }; output: null };
+  set_synced_worlds: { args: { instanceId: string; worlds: Array<string> }; output: null };
+  set_trash_settings: { args: { trash: TrashSettings }; output: null };
+  set_updater_settings: { args: { updater: UpdaterSettings }; output: null };
+  set_version_meta_settings: { args: { password: string; versionMeta: VersionMetaSettings }; output: null };
+  smoke_test_instance: { args: { password: string; instanceId: string; timeoutSecs?: number | null }; output: SmokeTestResult };
+  start_microsoft_login: { args: {}; output: string };
+  start_offline_login: { args: { username: string }; output: string };
+  start_safe_mode: { args: { instanceId: string; step: SafeModeStep }; output: null };
+  take_pending_kiosk: { args: {}; output: string | null };
+  take_pending_launch: { args: {}; output: string | null };
+  take_pending_open: { args: {}; output: OpenRequest | null };
+  undo_last_operation: { args: {}; output: Operation };
+  validate_mirror: { args: { password: string; instanceIds: Array<string> }; output: MirrorReport };
+  validate_mod_urls: { args: { password: string; urls: Array<string> }; output: ModUrlReport };
+  verify_against_index: { args: { password: string; file: string }; output: IndexDrift };
+  wait_for_server_slot: { args: { instanceId: string }; output: boolean };
+}
+
+export interface Events {
+  accessibility_changed: AccessibilityPrefs;
+  announcement: Announcement;
+  announcement_status: AnnouncementStatus;
+  drop_install_progress: DropInstallProgress;
+  hotkey_launch: HotkeyLaunch;
+  instance_card_updated: InstanceCardUpdated;
+  instance_update_applied: UpdateApplied;
+  instance_update_available: UpdateAvailable;
+  java_download_progress: JavaDownloadProgress;
+  launch_progress: LaunchProgress;
+  maintenance: Array<Maintenance>;
+  prefetch_status: PrefetchStatus;
+  removal_progress: RemovalStatus;
+  save_corruption: SaveCorruption;
+  save_sync: SaveSyncResult;
+  scheduled_job: ScheduledJobStatus;
+  server_queue: QueueUpdate;
+  session_restored: StartupAuthState;
+  status_changed: StatusReport;
+  undo_available: UndoAvailable;
+}
+
+type ArgsOf<C extends keyof Commands> = Commands[C]["args"];
+
+// invoke con los argumentos y el resultado de cada comando comprobados por TypeScript
+export function invokeCommand<C extends keyof Commands>(
+  command: C,
+  ...args: {} extends ArgsOf<C> ? [args?: ArgsOf<C>] : [args: ArgsOf<C>]
+): Promise<Commands[C]["output"]> {
+  return invoke(command, args[0]);
+}
+
+export function listenEvent<E extends keyof Events>(event: E, handler: EventCallback<Events[E]>): Promise<UnlistenFn> {
+  return listen<Events[E]>(event, handler);
+}
@@ -1,22 +1,69 @@
-import { useState } from "react";
+import { useEffect, useState } from "react";
+import { invoke } from "@tauri-apps/api/core";
 import "./AdminLogin.css";
 import { Lock, X } from "lucide-react";
 
 interface AdminLoginProps {
   onLogin: (password: string) => Promise<boolean>;
+  // Primer arranque: fija la contraseña con el código del archivo de configuración
+  onSetup: (code: string, password: string) => Promise<void>;
+  // Contraseña olvidada: token firmado por el backend para este equipo
+  onReset: (token: string, password: string) => Promise<void>;
   onClose: () => void;
 }
 
-export default function AdminLogin({ onLogin, onClose }: AdminLoginProps) {
+interface AdminSetupStatus {
+  configured: boolean;
+  codePath: string | null;
+}
+
+export default function AdminLogin({ onLogin, onSetup, onReset, onClose }: AdminLoginProps) {
   const [password, setPassword] = useState("");
+  const [code, setCode] = useState("");
+  const [confirmPassword, setConfirmPassword] = useState("");
+  const [setup, setSetup] = useState<AdminSetupStatus | null>(null);
+  const [resetting, setResetting] = useState(false);
+  const [machineId, setMachineId] = useState("");
   const [error, setError] = useState("");
   const [loading, setLoading] = useState(false);
 
+  useEffect(() => {
+    invoke<AdminSetupStatus>("get_admin_setup_status")
+      .then(setSetup)
+      .catch(() => setSetup({ configured: true, codePath: null }));
+  }, []);
+
+  const needsSetup = setup !== null && !setup.configured;
+  const choosingPassword = needsSetup || resetting;
+
+  const startReset = () => {
+    setResetting(true);
+    setError("");
+    setPassword("");
+    invoke<string>("get_machine_id").then(setMachineId).catch(() => setMachineId(""));
+  };
+
   const handleSubmit = async (e: React.FormEvent) => {
     e.preventDefault();
     setLoading(true);
     setError("");
-    
+
+    if (choosingPassword) {
+      if (password !== confirmPassword) {
+        setError("Las contraseñas no coinciden");
+        setLoading(false);
+        return;
+      }
+      try {
+        await (needsSetup ? onSetup(code, password) : onReset(code, password));
+        onClose();
+      } catch (err) {
+        setError(String(err));
+      }
+      setLoading(false);
+      return;
+    }
+
     const success = await onLogin(password);
     if (success) {
       onClose();
@@ -32,29 +79,71 @@ export default function AdminLogin({ onLogin, onClose }: AdminLoginProps) {
         <button className="admin-login-close" onClick={onClose}>
           <X size={20} />
         </button>
-        
+
         <div className="admin-login-header">
           <div className="admin-login-icon">
             <Lock size={24} />
           </div>
-          <h2>Acceso Administrativo</h2>
-          <p>Ingresa la contraseña para continuar</p>
+          <h2>{needsSetup ? "Configurar administrador" : resetting ? "Restablecer contraseña" : "Acceso Administrativo"}</h2>
+          {resetting ? (
+            <p>
+              Pide al staff un token de restablecimiento para el equipo{" "}
+              <span className="admin-login-path">{machineId || "…"}</span>{" "}
+              y elige la nueva contraseña.
+            </p>
+          ) : needsSetup ? (
+            <p>
+              Introduce el código de un solo uso guardado en{" "}
+              <span className="admin-login-path">{setup?.codePath ?? "la carpeta de datos del launcher"}</span>{" "}
+              y elige la contraseña de administrador.
+            </p>
+          ) : (
+            <p>Ingresa la contraseña para continuar</p>
+          )}
         </div>
 
         <form onSubmit={handleSubmit} className="admin-login-form">
+          {choosingPassword && (
+            <input
+              type="text"
+              value={code}
+              onChange={(e) => setCode(e.target.value)}
+              placeholder={resetting ? "Token de restablecimiento" : "Código de configuración"}
+              className="admin-login-input"
+              autoFocus
+            />
+          )}
           <input
             type="password"
             value={password}
             onChange={(e) => setPassword(e.target.value)}
-            placeholder="Contraseña"
+            placeholder={choosingPassword ? "Nueva contraseña" : "Contraseña"}
             className="admin-login-input"
-            autoFocus
+            autoFocus={!choosingPassword}
           />
+          {choosingPassword && (
+            <input
+              type="password"
+              value={confirmPassword}
+              onChange={(e) => setConfirmPassword(e.target.value)}
+              placeholder="Repite la contraseña"
+              className="admin-login-input"
+            />
+          )}
           {error && <p className="admin-login-error">{error}</p>}
-          
-          <button type="submit" className="admin-login-submit" disabled={loading}>
-            {loading ? "Verificando..." : "Acceder"}
+
+          <button type="submit" className="admin-login-submit" disabled={loading || setup === null}>
+            {loading ? "Verificando..." : choosingPassword ? "Guardar contraseña" : "Acceder"}
           </button>
+          {!needsSetup && setup !== null && (
+            <button
+              type="button"
+              className="admin-login-link"
+              onClick={() => (resetting ? setResetting(false) : startReset())}
+            >
+              {resetting ? "Volver al acceso" : "¿Olvidaste la contraseña?"}
+            </button>
+          )}
         </form>
       </div>
     </div>
@@ -1,14 +1,38 @@
 import { X, AlertTriangle, Copy } from 'lucide-react';
 import './CrashModal.css';
+import type { ExitCodeInfo, SafeModeOffer, Suggestion } from '../../api/types.generated';
+
+export type CrashSuggestion = Suggestion;
+export type { ExitCodeInfo, SafeModeOffer };
+
+const LIKELY_CAUSE: Record<ExitCodeInfo["likely"], string> = {
+  driver: "driver de la tarjeta gráfica",
+  memory: "falta de memoria",
+  mods: "un mod",
+  java: "Java",
+  killed: "cerrado desde fuera del juego",
+};
 
 interface CrashModalProps {
   isOpen: boolean;
   onClose: () => void;
   error: string;
   code: number;
+  suggestions?: CrashSuggestion[];
+  onApplySuggestion?: (suggestion: CrashSuggestion) => void;
+  // Enlace a los registros subidos tras el cierre (si el jugador lo activó)
+  logUrl?: string | null;
+  // null cuando el staff no configuró dónde subir los registros
+  autoUpload?: boolean | null;
+  onToggleAutoUpload?: (enabled: boolean) => void;
+  // Tras varios cierres seguidos: relanzar con gráficos bajos y mods desactivados
+  safeMode?: SafeModeOffer | null;
+  onSafeMode?: (offer: SafeModeOffer) => void;
+  // Código de salida explicado (Windows y señales de Java)
+  exitInfo?: ExitCodeInfo | null;
 }
 
-export default function CrashModal({ isOpen, onClose, error, code }: CrashModalProps) {
+export default function CrashModal({ isOpen, onClose, error, code, suggestions = [], onApplySuggestion, logUrl, autoUpload = null, onToggleAutoUpload, safeMode = null, onSafeMode, exitInfo = null }: CrashModalProps) {
   if (!isOpen) return null;
 
   const handleCopy = () => {
@@ -25,7 +49,7 @@ export default function CrashModal({ isOpen, onClose, error, code }: CrashModalP
             </div>
             <div className="crash-modal-title">
               <h2>El juego se ha cerrado inesperadamente</h2>
-              <p>Código de salida: {code}</p>
+              <p>Código de salida: {code}{exitInfo && ` (${exitInfo.hex}${exitInfo.name ? `, ${exitInfo.name}` : ""})`}</p>
             </div>
           </div>
           <button className="crash-modal-close" onClick={onClose}>
@@ -39,9 +63,71 @@ export default function CrashModal({ isOpen, onClose, error, code }: CrashModalP
             A continuación se muestran los detalles del registro de errores:
           </p>
           
+          {exitInfo && (
+            <div className="crash-modal-exit">
+              <strong>Causa probable: {LIKELY_CAUSE[exitInfo.likely]}</strong>
+              <p>{exitInfo.explanation}</p>
+            </div>
+          )}
+
           <div className="crash-modal-logs">
             {error || "No hay detalles disponibles."}
           </div>
+
+          {logUrl && (
+            <div className="crash-modal-log-link">
+              <span>Registros subidos: pega este enlace en Discord para pedir ayuda.</span>
+              <code>{logUrl}</code>
+              <button className="crash-btn crash-btn-secondary" onClick={() => navigator.clipboard.writeText(logUrl)}>
+                <Copy size={16} />
+                Copiar enlace
+              </button>
+            </div>
+          )}
+
+          {autoUpload !== null && (
+            <label className="crash-modal-auto-upload">
+              <input
+                type="checkbox"
+                checked={autoUpload}
+                onChange={(e) => onToggleAutoUpload?.(e.target.checked)}
+              />
+              Subir el informe de error y latest.log automáticamente cuando el juego se cierre con error
+            </label>
+          )}
+
+          {safeMode && (
+            <div className="crash-modal-suggestions">
+              <h3>Modo seguro</h3>
+              <div className="crash-modal-suggestion">
+                <div>
+                  <strong>El juego se cerró {safeMode.crashes} {safeMode.crashes === 1 ? "vez" : "veces"} seguidas</strong>
+                  <p>{safeMode.description}</p>
+                  {safeMode.mods.length > 0 && <p>Mods que se desactivan: {safeMode.mods.join(", ")}</p>}
+                </div>
+                <button className="crash-btn crash-btn-primary" onClick={() => onSafeMode?.(safeMode)}>
+                  Jugar en modo seguro
+                </button>
+              </div>
+            </div>
+          )}
+
+          {suggestions.length > 0 && (
+            <div className="crash-modal-suggestions">
+              <h3>Sugerencias</h3>
+              {suggestions.map((suggestion) => (
+                <div key={suggestion.title} className="crash-modal-suggestion">
+                  <div>
+                    <strong>{suggestion.title}</strong>
+                    <p>{suggestion.description}</p>
+                  </div>
+                  <button className="crash-btn crash-btn-primary" onClick={() => onApplySuggestion?.(suggestion)}>
+                    Aplicar
+                  </button>
+                </div>
+              ))}
+            </div>
+          )}
         </div>
 
         <div className="crash-modal-actions">
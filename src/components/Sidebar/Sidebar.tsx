@@ -1,6 +1,7 @@
 import { useState, useRef, useEffect } from "react";
 import "./Sidebar.css";
-import { Lock, Unlock, LogOut, Settings, User, Plus } from "lucide-react";
+import { cachedImageSrc } from "../../utils/instanceImages";
+import { Lock, Unlock, LogOut, Settings, User, Plus, Ticket, Share2 } from "lucide-react";
 
 interface Instance {
   id: string;
@@ -17,6 +18,8 @@ interface SidebarProps {
   selectedInstance: Instance | null;
   onSelectInstance: (instance: Instance) => void;
   onCreateInstance: () => void;
+  onImportCode?: () => void;
+  onShareInstance?: () => void;
   isOfflineMode: boolean;
   onLogout: () => void;
   userAvatar?: string;
@@ -31,6 +34,8 @@ export default function Sidebar({
   selectedInstance,
   onSelectInstance,
   onCreateInstance,
+  onImportCode,
+  onShareInstance,
   userAvatar,
   username,
   isAdmin,
@@ -38,7 +43,7 @@ export default function Sidebar({
   onOpenAdminLogin,
   onLogout,
 }: SidebarProps) {
-  const avatarUrl = userAvatar || `https://api.mineskin.org/render/body/8667ba71-b85a-4005-af54-45751bd8e8c7`;
+  const avatarUrl = cachedImageSrc(userAvatar || `https://api.mineskin.org/render/body/8667ba71-b85a-4005-af54-45751bd8e8c7`);
   const [hoverPreview, setHoverPreview] = useState<{
     instance: Instance;
     top: number;
@@ -212,6 +217,16 @@ export default function Sidebar({
                 <Settings size={16} />
                 <span>Configuración</span>
               </button>
+              <button className="sidebar-user-menu-item" onClick={() => { setShowUserMenu(false); onImportCode?.(); }}>
+                <Ticket size={16} />
+                <span>Importar código</span>
+              </button>
+              {isAdmin && (
+                <button className="sidebar-user-menu-item" onClick={() => { setShowUserMenu(false); onShareInstance?.(); }}>
+                  <Share2 size={16} />
+                  <span>Compartir instancia</span>
+                </button>
+              )}
               <div className="sidebar-user-menu-divider"></div>
               <button className="sidebar-user-menu-item logout" onClick={onLogout}>
                 <LogOut size={16} />
@@ -1,10 +1,13 @@
 import { useState, useEffect } from "react";
 import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
 import { X, Save, Server, Cpu, Image as ImageIcon, Plus, Trash2, Link as LinkIcon, Eye, ChevronLeft, ChevronRight, Upload, ArrowLeft, ArrowRight } from "lucide-react";
 import "./CreateInstance.css";
 
 
 interface CreateInstanceProps {
+  // Modpack abierto desde el explorador (.mrpack o .zip de CurseForge)
+  initialModpackUrl?: string;
   onClose: () => void;
   onSave: (instanceData: InstanceData) => void;
 }
@@ -102,23 +105,36 @@ function ImageUploadInput({ value, onChange, placeholder = "URL de imagen..." }:
   );
 }
 
-export default function CreateInstance({ onClose, onSave }: CreateInstanceProps) {
+interface LoaderVersions {
+  loader: string;
+  recommended: string | null;
+  latest: string | null;
+  versions: { version: string; stable: boolean }[];
+}
+
+export default function CreateInstance({ initialModpackUrl, onClose, onSave }: CreateInstanceProps) {
   const [name, setName] = useState("");
   const [description, setDescription] = useState("");
   const [images, setImages] = useState<string[]>([]);
   const [newImageUrl, setNewImageUrl] = useState("");
   const [ram, setRam] = useState(4096);
+  // Mientras el usuario no toque la RAM se sigue el valor por defecto del loader
+  const [ramTouched, setRamTouched] = useState(false);
   const [systemRam, setSystemRam] = useState(8192);
   const [serverIp, setServerIp] = useState("");
   const [serverName, setServerName] = useState("");
-  const [modpackUrl, setModpackUrl] = useState("");
+  const [modpackUrl, setModpackUrl] = useState(initialModpackUrl ?? "");
   const [version, setVersion] = useState("1.20.1");
   const [versions, setVersions] = useState<string[]>([]);
   const [selectedLauncher, setSelectedLauncher] = useState<string>("");
   const [modloader, setModloader] = useState("vanilla");
   const [loaderVersion, setLoaderVersion] = useState<string>("");
+  const [loaderVersions, setLoaderVersions] = useState<LoaderVersions | null>(null);
   const [resolutionWidth, setResolutionWidth] = useState(854);
   const [resolutionHeight, setResolutionHeight] = useState(480);
+  const [javaProgress, setJavaProgress] = useState<string | null>(null);
+  const [versionWarning, setVersionWarning] = useState<string | null>(null);
+  const [modpackDetection, setModpackDetection] = useState<string | null>(null);
   const [javaInfo, setJavaInfo] = useState<{recommended: number; installed: boolean; path: string} | null>(null);
   const [isLoading, setIsLoading] = useState(false);
   const [showPreview, setShowPreview] = useState(true);
@@ -159,6 +175,39 @@ export default function CreateInstance({ onClose, onSave }: CreateInstanceProps)
     setSelectedLauncher("official"); // Default interno
   }, []);
 
+  // Leer versión y loader del modpack (manifest o, si no tiene, los mods incluidos) para no elegirlos a mano
+  useEffect(() => {
+    const url = modpackUrl.trim();
+    setModpackDetection(null);
+    if (!url) {
+      return;
+    }
+    let cancelled = false;
+    const timer = setTimeout(() => {
+      invoke<{ mcVersion: string | null; loader: string | null; detectedFromMods: boolean }>("inspect_modpack", { urlOrPath: url })
+        .then((info) => {
+          if (cancelled) return;
+          const applied: string[] = [];
+          if (info.mcVersion && versions.includes(info.mcVersion)) {
+            setVersion(info.mcVersion);
+            applied.push(`Minecraft ${info.mcVersion}`);
+          }
+          if (info.loader === "fabric" || info.loader === "forge") {
+            setModloader(info.loader);
+            applied.push(info.loader === "fabric" ? "Fabric" : "Forge");
+          }
+          if (applied.length > 0) {
+            setModpackDetection(`Detectado ${info.detectedFromMods ? "a partir de los mods" : "en el modpack"}: ${applied.join(" + ")}`);
+          }
+        })
+        .catch(() => {});
+    }, 800);
+    return () => {
+      cancelled = true;
+      clearTimeout(timer);
+    };
+  }, [modpackUrl, versions]);
+
   useEffect(() => {
     let cancelled = false;
 
@@ -177,6 +226,11 @@ export default function CreateInstance({ onClose, onSave }: CreateInstanceProps)
     }
 
     loadJavaInfo();
+    invoke<string | null>("check_version_support", { version })
+      .then((warning) => {
+        if (!cancelled) setVersionWarning(warning);
+      })
+      .catch(() => setVersionWarning(null));
     return () => {
       cancelled = true;
     };
@@ -188,20 +242,23 @@ export default function CreateInstance({ onClose, onSave }: CreateInstanceProps)
     async function loadLoaderRecommendation() {
       if (modloader === "vanilla") {
         setLoaderVersion("");
+        setLoaderVersions(null);
         return;
       }
       try {
-        const recommendation = await invoke<string>("get_loader_recommendation", {
+        const result = await invoke<LoaderVersions>("get_loader_recommendation", {
           loader: modloader,
           mcVersion: version,
         });
         if (!cancelled) {
-          setLoaderVersion(recommendation);
+          setLoaderVersions(result);
+          setLoaderVersion(result.recommended ?? result.latest ?? "");
         }
       } catch (error) {
         console.error("Error loading loader recommendation:", error);
         if (!cancelled) {
           setLoaderVersion("");
+          setLoaderVersions(null);
         }
       }
     }
@@ -219,16 +276,27 @@ export default function CreateInstance({ onClose, onSave }: CreateInstanceProps)
         setVersions(v);
         setVersion(v[0]);
       }
+      const latest = await invoke<{ release: string }>("get_latest_versions").catch(() => null);
+      if (latest && v?.includes(latest.release)) {
+        setVersion(latest.release);
+      }
     } catch (e) {
       console.error("Error loading versions", e);
     }
   }
 
+  useEffect(() => {
+    if (ramTouched) return;
+    invoke<number>("get_default_ram", { modloader })
+      .then(setRam)
+      .catch((error) => console.error("Error loading default RAM:", error));
+  }, [modloader, ramTouched]);
+
   async function loadSystemRam() {
     try {
-      const totalRam = await invoke<number>("get_system_ram");
-      setSystemRam(totalRam);
-      setRam(Math.floor(totalRam * 0.5));
+      // En sistemas de 32 bits maxRamMb ya viene limitado
+      const platform = await invoke<{ totalRamMb: number; maxRamMb: number }>("get_platform_info");
+      setSystemRam(platform.maxRamMb);
     } catch (error) {
       console.error("Error loading system RAM:", error);
     }
@@ -278,6 +346,9 @@ export default function CreateInstance({ onClose, onSave }: CreateInstanceProps)
     if (!javaInfo || javaInfo.installed) {
       return;
     }
+    const unlisten = await listen<{ major: number; percent: number; message: string }>("java_download_progress", (event) => {
+      setJavaProgress(event.payload.message);
+    });
     try {
       await invoke("download_java", { major: javaInfo.recommended });
       const info = await invoke<{ recommended: number; installed: boolean; path: string }>("get_java_info", { mcVersion: version });
@@ -285,6 +356,9 @@ export default function CreateInstance({ onClose, onSave }: CreateInstanceProps)
     } catch (error) {
       console.error("Error downloading Java:", error);
       alert("Error al descargar Java");
+    } finally {
+      unlisten();
+      setJavaProgress(null);
     }
   }
 
@@ -479,16 +553,22 @@ export default function CreateInstance({ onClose, onSave }: CreateInstanceProps)
                       max={systemRam > 0 ? systemRam : 16384}
                       step="512"
                       value={ram}
-                      onChange={(e) => setRam(parseInt(e.target.value))}
+                      onChange={(e) => {
+                        setRam(parseInt(e.target.value));
+                        setRamTouched(true);
+                      }}
                       className="create-instance-slider"
                     />
                     <span className="create-instance-ram-value">{ram / 1024} GB</span>
                   </div>
                   <div className="create-instance-ram-presets">
-                    {[2048, 4096, 6144, 8192].map((preset) => (
+                    {[2048, 4096, 6144, 8192].filter((preset) => preset <= systemRam).map((preset) => (
                       <button
                         key={preset}
-                        onClick={() => setRam(preset)}
+                        onClick={() => {
+                          setRam(preset);
+                          setRamTouched(true);
+                        }}
                         className={ram === preset ? "active" : ""}
                         type="button"
                       >
@@ -542,6 +622,7 @@ export default function CreateInstance({ onClose, onSave }: CreateInstanceProps)
                     className="create-instance-input"
                     placeholder="https://www.mediafire.com/..."
                   />
+                  {modpackDetection && <span className="create-instance-hint">{modpackDetection}</span>}
                 </div>
               </div>
 
@@ -586,6 +667,7 @@ export default function CreateInstance({ onClose, onSave }: CreateInstanceProps)
                       <option key={v} value={v}>{v}</option>
                     ))}
                   </select>
+                  {versionWarning && <span className="create-instance-warning">{versionWarning}</span>}
                 </div>
                 <div className="create-instance-field">
                   <label className="create-instance-label">Loader</label>
@@ -594,8 +676,20 @@ export default function CreateInstance({ onClose, onSave }: CreateInstanceProps)
                     <option value="fabric">Fabric</option>
                     <option value="forge">Forge</option>
                   </select>
-                  {loaderVersion && (
-                    <div className="create-instance-hint">Versión recomendada del loader: {loaderVersion}</div>
+                  {loaderVersions && loaderVersions.versions.length > 0 && (
+                    <select value={loaderVersion} onChange={(e) => setLoaderVersion(e.target.value)} className="create-instance-input">
+                      {loaderVersions.versions.map((v) => (
+                        <option key={v.version} value={v.version}>
+                          {v.version}
+                          {v.version === loaderVersions.recommended ? " (recomendada)" : ""}
+                          {v.version === loaderVersions.latest && v.version !== loaderVersions.recommended ? " (última)" : ""}
+                          {!v.stable ? " (beta)" : ""}
+                        </option>
+                      ))}
+                    </select>
+                  )}
+                  {loaderVersions?.recommended && (
+                    <div className="create-instance-hint">Versión recomendada del loader: {loaderVersions.recommended}</div>
                   )}
                 </div>
                 <div className="create-instance-field">
@@ -605,7 +699,7 @@ export default function CreateInstance({ onClose, onSave }: CreateInstanceProps)
                       <>
                         Java {javaInfo.recommended} {javaInfo.installed ? `(instalado)` : `(no instalado)`}
                         {!javaInfo.installed && (
-                          <button onClick={handleDownloadJava} className="create-instance-button create-instance-button-secondary" style={{ marginLeft: 8 }}>Descargar Java</button>
+                          <button onClick={handleDownloadJava} disabled={javaProgress !== null} className="create-instance-button create-instance-button-secondary" style={{ marginLeft: 8 }}>{javaProgress ?? "Descargar Java"}</button>
                         )}
                       </>
                     ) : (
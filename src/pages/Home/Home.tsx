@@ -1,6 +1,8 @@
 import { useState, useEffect } from "react";
 import { Play, Settings, Download, ChevronLeft, ChevronRight, CheckCircle2, Eye } from "lucide-react";
 import { invoke } from "@tauri-apps/api/core";
+import { cachedImageSrc, instanceImageSrc } from "../../utils/instanceImages";
+import type { ExtractionProgress } from "../../api/types.generated";
 import "./Home.css";
 
 interface Instance {
@@ -13,6 +15,7 @@ interface Instance {
   image?: string;
   images?: string[];
   description?: string;
+  notes?: string;
   modpackUrl?: string;
   serverIp?: string;
   serverName?: string;
@@ -52,13 +55,82 @@ interface Instance {
     image?: string;
     modsInstalled?: number;
     lastUpdate?: string;
+    packVersion?: string;
   };
 }
 
+interface ComponentStatus {
+  state: "ready" | "missing" | "partial" | "outdated";
+  percent: number;
+}
+
+interface InstanceReadiness {
+  ready: boolean;
+  java: ComponentStatus;
+  client: ComponentStatus;
+  assets: ComponentStatus;
+  mods: ComponentStatus;
+}
+
+const READINESS_LABELS: [keyof Omit<InstanceReadiness, "ready">, string][] = [
+  ["java", "Java"],
+  ["client", "Cliente"],
+  ["assets", "Assets"],
+  ["mods", "Mods"],
+];
+
+function readinessSummary(readiness: InstanceReadiness): string {
+  return READINESS_LABELS
+    .filter(([key]) => readiness[key].state !== "ready")
+    .map(([key, label]) => {
+      const status = readiness[key];
+      if (status.state === "partial") return `${label} ${status.percent}%`;
+      if (status.state === "outdated") return `${label} desactualizados`;
+      return `${label} pendiente`;
+    })
+    .join(" · ");
+}
+
+interface ServerSample {
+  at: number;
+  online: number | null;
+  max: number | null;
+}
+
+const SPARKLINE_WIDTH = 240;
+const SPARKLINE_HEIGHT = 40;
+
+// Polyline points for the players online; pings without an answer count as 0
+function sparklinePoints(samples: ServerSample[]): string {
+  const first = samples[0].at;
+  const span = Math.max(samples[samples.length - 1].at - first, 1);
+  const peak = Math.max(...samples.map((s) => s.max ?? 0), ...samples.map((s) => s.online ?? 0), 1);
+  return samples
+    .map((s) => {
+      const x = ((s.at - first) / span) * SPARKLINE_WIDTH;
+      const y = SPARKLINE_HEIGHT - ((s.online ?? 0) / peak) * SPARKLINE_HEIGHT;
+      return `${x.toFixed(1)},${y.toFixed(1)}`;
+    })
+    .join(" ");
+}
+
+// The launcher stores computed dates as YYYY-MM-DD; staff-typed text is shown as is
+function formatLastUpdate(value?: string): string | undefined {
+  if (!value || !/^\d{4}-\d{2}-\d{2}$/.test(value)) return value;
+  return new Date(`${value}T00:00:00`).toLocaleDateString();
+}
+
+export interface LaunchProgressState {
+  percent: number;
+  stage: string;
+  message: string;
+  extraction?: ExtractionProgress | null;
+}
+
 interface HomeProps {
   selectedInstance: Instance | null;
   isLaunching: boolean;
-  launchProgress?: { percent: number; stage: string; message: string } | null;
+  launchProgress?: LaunchProgressState | null;
   isDownloading: boolean;
   launchingInstanceId: string | null;
   launchDurationMs?: number | null;
@@ -69,6 +141,7 @@ interface HomeProps {
   onDownloadInstance: (instance: Instance) => Promise<boolean> | boolean | void;
   onExecuteInstance: (instance: Instance) => void;
   onHome?: () => void;
+  onCancelQueue?: (instance: Instance) => void;
 }
 
 export default function Home({
@@ -84,9 +157,11 @@ export default function Home({
   instances,
   onDownloadInstance,
   onExecuteInstance,
+  onCancelQueue,
 }: HomeProps) {
   const [currentImageIndex, setCurrentImageIndex] = useState(0);
   const [isDownloaded, setIsDownloaded] = useState(false);
+  const [readiness, setReadiness] = useState<InstanceReadiness | null>(null);
   const [isVerifying, setIsVerifying] = useState(false);
   const [showWelcome, setShowWelcome] = useState(true);
   const [downloadedInstances, setDownloadedInstances] = useState<Record<string, boolean>>({});
@@ -103,6 +178,31 @@ export default function Home({
     }
   }, []);
 
+  const [lastPlayed, setLastPlayed] = useState<{ relative: string; date: string } | null>(null);
+  const gameStarted = launchProgress?.stage === "iniciado";
+
+  // El backend lo guarda al arrancar el juego y lo redacta en el idioma del sistema
+  useEffect(() => {
+    const instanceId = selectedInstance?.id;
+    setLastPlayed(null);
+    if (!instanceId) return;
+    invoke<{ relative: string; date: string }>("format_last_played", { instanceId, locale: navigator.language })
+      .then(setLastPlayed)
+      .catch(() => setLastPlayed(null));
+  }, [selectedInstance?.id, selectedInstance?.lastPlayed, gameStarted]);
+
+  const [serverHistory, setServerHistory] = useState<ServerSample[]>([]);
+
+  // Sampled in the background every few minutes while the launcher is open
+  useEffect(() => {
+    const serverIp = selectedInstance?.serverIp;
+    setServerHistory([]);
+    if (!serverIp) return;
+    invoke<ServerSample[]>("get_server_history", { host: serverIp })
+      .then(setServerHistory)
+      .catch(() => setServerHistory([]));
+  }, [selectedInstance?.serverIp]);
+
   useEffect(() => {
     if (selectedInstance) {
       setCurrentImageIndex(0);
@@ -149,8 +249,11 @@ export default function Home({
 
   async function checkInstanceStatus(instance: Instance) {
     let downloaded = Boolean(downloadedInstances[instance.id]);
+    setReadiness(null);
     try {
-      downloaded = await invoke<boolean>("check_instance_ready", { instanceId: instance.id });
+      const result = await invoke<InstanceReadiness>("check_instance_ready", { instanceId: instance.id });
+      downloaded = result.ready;
+      setReadiness(result);
     } catch {}
     setIsDownloaded(downloaded);
   }
@@ -173,8 +276,7 @@ export default function Home({
       return;
     }
     try {
-      const logsPath = `${selectedInstance.path}\\logs`;
-      await invoke("open_folder", { path: logsPath });
+      await invoke("open_instance_folder", { instanceId: selectedInstance.id, which: "logs" });
     } catch (error) {
       console.error("Error abriendo carpeta de logs:", error);
       alert("No se pudo abrir la carpeta de logs");
@@ -204,7 +306,7 @@ export default function Home({
 
   // Vista de instancia seleccionada
   const images = selectedInstance.images || (selectedInstance.image ? [selectedInstance.image] : []);
-  const currentImage = images.length > 0 ? images[currentImageIndex] : null;
+  const currentImage = images.length > 0 && images[currentImageIndex] ? instanceImageSrc(selectedInstance.id, images[currentImageIndex]) : null;
   const isBlocked = Boolean(launchingInstanceId && selectedInstance.id !== launchingInstanceId);
   const runningInstance = launchingInstanceId
     ? instances.find((inst) => inst.id === launchingInstanceId)
@@ -340,12 +442,26 @@ export default function Home({
                     </>
                   )}
                 </button>
+                {activeProgress?.stage === "cola" && onCancelQueue && (
+                  <button className="home-queue-cancel" onClick={() => onCancelQueue(selectedInstance)}>
+                    Salir de la cola
+                  </button>
+                )}
+                {activeProgress?.extraction && (
+                  <button
+                    className="home-queue-cancel"
+                    onClick={() => invoke("cancel_modpack_extraction", { instanceId: selectedInstance.id }).catch(console.error)}
+                  >
+                    Cancelar extracción
+                  </button>
+                )}
 
                 {!isDownloaded && (
                   <button
                     onClick={() => onDownloadInstance(selectedInstance)}
                     disabled={isVerifying || isDownloadingActive || isBlocked}
                     className="home-instance-download-button"
+                    title={readiness && !readiness.ready ? readinessSummary(readiness) : undefined}
                   >
                     {isDownloadingActive ? (
                       <>
@@ -380,6 +496,9 @@ export default function Home({
                   )}
                 </button>
               </div>
+              {readiness && !readiness.ready && !isDownloadingActive && (
+                <div className="home-instance-readiness">{readinessSummary(readiness)}</div>
+              )}
                 <button
                   onClick={handleOpenLogs}
                   disabled={isDownloadingActive || isLaunchingActive || isBlocked}
@@ -421,7 +540,7 @@ export default function Home({
                 <div className="home-news-card">
                   <div className="home-news-card-image">
                     {selectedInstance.newsLeft?.image ? (
-                      <img src={selectedInstance.newsLeft.image} alt="Noticia" />
+                      <img src={cachedImageSrc(selectedInstance.newsLeft.image)} alt="Noticia" />
                     ) : (
                       <div className="home-news-card-placeholder">📰</div>
                     )}
@@ -436,7 +555,7 @@ export default function Home({
                 <div className="home-news-card">
                   <div className="home-news-card-image">
                     {selectedInstance.newsCenter?.image ? (
-                      <img src={selectedInstance.newsCenter.image} alt="Noticia" />
+                      <img src={cachedImageSrc(selectedInstance.newsCenter.image)} alt="Noticia" />
                     ) : (
                       <div className="home-news-card-placeholder">📰</div>
                     )}
@@ -451,7 +570,7 @@ export default function Home({
                 <div className="home-news-card">
                   <div className="home-news-card-image">
                     {selectedInstance.newsRight?.image ? (
-                      <img src={selectedInstance.newsRight.image} alt="Noticia" />
+                      <img src={cachedImageSrc(selectedInstance.newsRight.image)} alt="Noticia" />
                     ) : (
                       <div className="home-news-card-placeholder">📰</div>
                     )}
@@ -484,6 +603,14 @@ export default function Home({
                      <span className="home-info-value">{selectedInstance.serverIp || "N/A"}</span>
                   </div>
                 </div>
+                {serverHistory.length >= 2 && (
+                  <div className="home-server-history" title="Jugadores en las últimas 24 horas">
+                    <svg width={SPARKLINE_WIDTH} height={SPARKLINE_HEIGHT} viewBox={`0 0 ${SPARKLINE_WIDTH} ${SPARKLINE_HEIGHT}`}>
+                      <polyline points={sparklinePoints(serverHistory)} fill="none" stroke="currentColor" strokeWidth="2" />
+                    </svg>
+                    <span className="home-info-label">Últimas 24 h</span>
+                  </div>
+                )}
               </div>
 
               {/* 3. Basic Info Section */}
@@ -504,11 +631,30 @@ export default function Home({
                   </div>
                   <div className="home-info-item">
                     <span className="home-info-label">Actualizado:</span>
-                    <span className="home-info-value">{selectedInstance.infoCard?.lastUpdate || new Date(selectedInstance.lastPlayed).toLocaleDateString()}</span>
+                    <span className="home-info-value">{formatLastUpdate(selectedInstance.infoCard?.lastUpdate) || new Date(selectedInstance.lastPlayed).toLocaleDateString()}</span>
                   </div>
+                  {lastPlayed && (
+                    <div className="home-info-item">
+                      <span className="home-info-label">Última partida:</span>
+                      <span className="home-info-value" title={lastPlayed.date}>{lastPlayed.relative}</span>
+                    </div>
+                  )}
+                  {selectedInstance.infoCard?.packVersion && (
+                    <div className="home-info-item">
+                      <span className="home-info-label">Pack:</span>
+                      <span className="home-info-value">{selectedInstance.infoCard.packVersion}</span>
+                    </div>
+                  )}
                 </div>
               </div>
 
+              {selectedInstance.notes && (
+                <div className="home-info-section home-notes-section">
+                  <h3 className="home-section-title">NOTAS DEL EVENTO</h3>
+                  <p className="home-instance-notes">{selectedInstance.notes}</p>
+                </div>
+              )}
+
             </div>
           </div>
         </div>
@@ -0,0 +1,30 @@
+import { convertFileSrc } from "@tauri-apps/api/core";
+
+// Las URLs remotas pasan por la caché de imágenes del launcher (drk-asset, ruta remote/), que
+// las guarda en disco y las revalida con ETag; el resto se usa tal cual. Solo responde a las
+// imágenes de las instancias y a los renders de skins
+export function cachedImageSrc(url: string): string {
+  if (!/^https?:\/\//i.test(url)) {
+    return url;
+  }
+  return assetSrc(`remote/${url}`);
+}
+
+// Archivos del launcher (imágenes importadas, capturas) por el protocolo drk-asset, que solo
+// sirve las rutas permitidas dentro de la carpeta de datos
+export function assetSrc(path: string): string {
+  return convertFileSrc(path, "drk-asset");
+}
+
+// Las imágenes importadas viven en <instancia>/images (entradas "images/<archivo>");
+// URLs remotas pasan por la caché y las data URLs se usan tal cual
+export function instanceImageSrc(instanceId: string, entry: string, thumbnail = false): string {
+  if (!entry.startsWith("images/") || !instanceId) {
+    return cachedImageSrc(entry);
+  }
+  const name = entry.slice("images/".length);
+  if (thumbnail) {
+    return assetSrc(`instance/${instanceId}/thumbs/${name.replace(/\.[^.]+$/, "")}.jpg`);
+  }
+  return assetSrc(`instance/${instanceId}/images/${name}`);
+}